@@ -11,9 +11,21 @@ mod inner {
 
         for &(src, dst) in &[
             ("src/action.in.rs", "action.rs"),
+            ("src/assets.in.rs", "assets.rs"),
+            ("src/audio.in.rs", "audio.rs"),
             ("src/camera.in.rs", "camera.rs"),
+            ("src/command.in.rs", "command.rs"),
             ("src/config.in.rs", "config.rs"),
+            ("src/cursor.in.rs", "cursor.rs"),
+            ("src/designation.in.rs", "designation.rs"),
             ("src/localization.in.rs", "localization.rs"),
+            ("src/recording.in.rs", "recording.rs"),
+            ("src/net.in.rs", "net.rs"),
+            ("src/palette.in.rs", "palette.rs"),
+            ("src/save.in.rs", "save.rs"),
+            ("src/settings.in.rs", "settings.rs"),
+            ("src/simulation.in.rs", "simulation.rs"),
+            ("src/tile_atlas.in.rs", "tile_atlas.rs"),
         ] {
             let src = Path::new(src);
             let dst = Path::new(&out_dir).join(dst);
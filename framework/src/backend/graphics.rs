@@ -1,2 +1,3 @@
 pub use graphics::{Context, Graphics, ImageSize};
 pub use graphics::character::CharacterCache;
+pub use texture::{CreateTexture, Format, TextureSettings};
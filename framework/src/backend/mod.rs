@@ -1,15 +1,17 @@
-pub use self::graphics::{CharacterCache, Graphics};
+pub use self::graphics::{CharacterCache, CreateTexture, Graphics};
 use std;
 
 pub mod graphics;
 
 pub trait Backend {
-    type Texture: self::graphics::ImageSize + std::any::Any;
+    /// Textures can be created from images in memory, such as those drawn
+    /// while the game runs.
+    type Texture: self::graphics::ImageSize + CreateTexture<()> + std::any::Any;
     type CharacterCache: CharacterCache<Texture=Self::Texture>;
 }
 
 impl<T, C> Backend for (T, C)
-    where T: self::graphics::ImageSize + std::any::Any,
+    where T: self::graphics::ImageSize + CreateTexture<()> + std::any::Any,
           C: CharacterCache<Texture=T>,
 {
     type Texture = T;
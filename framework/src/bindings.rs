@@ -1,4 +1,5 @@
 use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map;
 use std::hash::Hash;
 
 use util::RustcSerializeWrapper;
@@ -36,6 +37,34 @@ impl<B, A> BindingsHashMap<B, A>
     pub fn new() -> Self {
         BindingsHashMap::default()
     }
+
+    /// Returns the bindings and their actions, in no particular order.
+    pub fn iter(&self) -> hash_map::Iter<B, A> {
+        self.bindings.iter()
+    }
+
+    /// Moves the action bound to `from` onto `to`, replacing the action `to`
+    /// was bound to, if any. Does nothing if `from` is not bound.
+    pub fn rebind(&mut self, from: &B, to: B) {
+        if let Some(action) = self.bindings.remove(from) {
+            self.bindings.insert(to, action);
+        }
+    }
+
+    /// Binds each action of `defaults` which is bound to nothing here to its
+    /// binding in `defaults`, unless that binding is already taken, so that
+    /// actions added since these bindings were saved can still be used.
+    pub fn merge_defaults(&mut self, defaults: &BindingsHashMap<B, A>)
+        where B: Clone,
+              A: Clone + PartialEq,
+    {
+        for (binding, action) in &defaults.bindings {
+            let is_bound = self.bindings.values().any(|bound| bound == action);
+            if !is_bound && !self.bindings.contains_key(binding) {
+                self.bindings.insert(binding.clone(), action.clone());
+            }
+        }
+    }
 }
 
 impl<B, A> BindingStore for BindingsHashMap<B, A>
@@ -0,0 +1,98 @@
+use backend::{Backend, Graphics};
+use backend::graphics::{CharacterCache, Context};
+use draw::Draw;
+
+/// A single line of text, drawn at a fixed position.
+///
+/// The text is only replaced (and its cached width only recomputed) when it
+/// actually changes, so labels can be kept around and redrawn every frame
+/// without re-formatting or re-measuring their contents.
+pub struct Label {
+    text: String,
+    color: [f32; 4],
+    font_size: u32,
+    position: [f64; 2],
+    /// Width of the text in pixels, which is cached since measuring it
+    /// requires looking up every glyph.
+    width: Option<f64>,
+}
+
+impl Label {
+    pub fn new<S>(text: S, color: [f32; 4], font_size: u32, position: [f64; 2]) -> Self
+        where S: Into<String>,
+    {
+        Label {
+            text: text.into(),
+            color: color,
+            font_size: font_size,
+            position: position,
+            width: None,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text of the label, invalidating its cached width if the
+    /// text differs from the current one.
+    pub fn set_text<S>(&mut self, text: S)
+        where S: Into<String>,
+    {
+        let text = text.into();
+        if text != self.text {
+            self.text = text;
+            self.width = None;
+        }
+    }
+
+    pub fn font_size(&self) -> u32 {
+        self.font_size
+    }
+
+    pub fn color(&self) -> [f32; 4] {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.color = color;
+    }
+
+    pub fn position(&self) -> [f64; 2] {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: [f64; 2]) {
+        self.position = position;
+    }
+
+    /// Returns the width of the label's text in pixels.
+    pub fn width<C>(&mut self, glyph_cache: &mut C) -> f64
+        where C: CharacterCache,
+    {
+        if let Some(width) = self.width {
+            return width;
+        }
+
+        let width = glyph_cache.width(self.font_size, &self.text);
+        self.width = Some(width);
+        width
+    }
+}
+
+impl<B, G> Draw<B, G> for Label
+    where B: Backend,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn draw(&self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        use graphics::Transformed;
+        use graphics::text::Text;
+
+        Text::new_color(self.color, self.font_size).draw(
+            &self.text,
+            glyph_cache,
+            &context.draw_state,
+            context.transform.trans(self.position[0], self.position[1]),
+            graphics);
+    }
+}
@@ -6,6 +6,7 @@
 extern crate graphics;
 extern crate piston;
 extern crate serde;
+extern crate texture;
 
 pub use backend::Backend;
 pub use bindings::{
@@ -17,14 +18,18 @@ pub use bindings::{
     UnwrapBindings,
 };
 pub use draw::Draw;
+pub use label::Label;
 pub use scene::{BoxedScene, Scene, SceneCommand};
 pub use scene_manager::SceneManager;
 pub use util::RustcSerializeWrapper;
+pub use widget::{Button, List, Panel, ProgressBar, Widget};
 
 pub mod backend;
 mod bindings;
 pub mod draw;
+mod label;
 mod macros;
 mod scene;
 mod scene_manager;
 mod util;
+pub mod widget;
@@ -1,5 +1,5 @@
 #[macro_export]
-macro_rules! create_type_parsing_impls {
+macro_rules! create_type_filling_impls {
     ($type_name:ident,
      $parsed_type_name:ident,
      $($i:ident, $def:expr);+ $(;)*) => (
@@ -12,13 +12,6 @@ macro_rules! create_type_parsing_impls {
             )+
                 self
             }
-
-            pub fn from_json(json: &str) -> $type_name {
-                use serde_json;
-
-                let parsed = serde_json::from_str(json).expect("Could not parse JSON");
-                $type_name::default().fill_from_parsed(parsed)
-            }
         }
 
         // Template for the default struct
@@ -33,3 +26,25 @@ macro_rules! create_type_parsing_impls {
         }
     )
 }
+
+#[macro_export]
+macro_rules! create_type_parsing_impls {
+    ($type_name:ident,
+     $parsed_type_name:ident,
+     $($i:ident, $def:expr);+ $(;)*) => (
+        create_type_filling_impls! {
+            $type_name,
+            $parsed_type_name,
+            $($i, $def);+
+        }
+
+        impl $type_name {
+            pub fn from_json(json: &str) -> $type_name {
+                use serde_json;
+
+                let parsed = serde_json::from_str(json).expect("Could not parse JSON");
+                $type_name::default().fill_from_parsed(parsed)
+            }
+        }
+    )
+}
@@ -13,6 +13,8 @@ pub enum SceneCommand<B, E, G> {
     SetScene(BoxedScene<B, E, G>),
     PushScene(BoxedScene<B, E, G>),
     PopScene,
+    /// Clears the scene stack and pushes the provided scene onto it.
+    ReplaceAll(BoxedScene<B, E, G>),
     Clear,
 }
 
@@ -24,4 +26,34 @@ pub trait Scene<B, E, G>
     fn to_box(self) -> BoxedScene<B, E, G>;
     fn render(&mut self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache);
     fn handle_event(&mut self, e: &E) -> Option<SceneCommand<B, E, G>>;
+
+    /// Advances the state of the scene by `dt` seconds.
+    fn update(&mut self, _dt: f64) -> Option<SceneCommand<B, E, G>> {
+        None
+    }
+
+    /// Returns true if the scene wants the next frame saved as an image, e.g.
+    /// for a timelapse. Called after every update.
+    fn take_capture_request(&mut self) -> bool {
+        false
+    }
+
+    /// Called when the window is resized, with its new size in pixels.
+    fn resize(&mut self, _width: f64, _height: f64) {}
+
+    /// Called when the scene is at the top of the stack again, once the
+    /// scene pushed above it has been popped.
+    fn resume(&mut self) {}
+
+    /// Returns true if the scene below this one on the stack should be
+    /// rendered before this one, e.g. for menus drawn over the game.
+    fn is_transparent(&self) -> bool {
+        false
+    }
+
+    /// Returns lines of debugging information describing the state of the
+    /// scene, to be displayed by the debug overlay.
+    fn debug_info(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
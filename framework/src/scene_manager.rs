@@ -1,6 +1,7 @@
 use piston::input::GenericEvent;
 
 use backend::{Backend, Graphics};
+use backend::graphics::Context;
 use scene::{BoxedScene, SceneCommand};
 
 pub struct SceneManager<B, E, G>
@@ -41,6 +42,48 @@ impl<B, E, G> SceneManager<B, E, G>
         self.scene_stack.clear()
     }
 
+    /// Returns the debugging information of the scene at the top of the stack.
+    pub fn debug_info(&self) -> Vec<String> {
+        match self.scene_stack.last() {
+            Some(scene) => scene.debug_info(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Renders the scene at the top of the stack, preceded by every scene
+    /// below it which is visible through the scenes above.
+    pub fn render(&mut self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        let mut first_visible = self.scene_stack.len();
+        while first_visible > 0 {
+            first_visible -= 1;
+            if !self.scene_stack[first_visible].is_transparent() {
+                break;
+            }
+        }
+
+        for scene in &mut self.scene_stack[first_visible..] {
+            scene.render(context, graphics, glyph_cache);
+        }
+    }
+
+    /// Returns true if the scene at the top of the stack wants the next frame
+    /// saved as an image.
+    pub fn take_capture_request(&mut self) -> bool {
+        match self.scene_stack.last_mut() {
+            Some(scene) => scene.take_capture_request(),
+            None => false,
+        }
+    }
+
+    /// Lets every scene on the stack know that the window was resized, so
+    /// that the scenes below the top one are laid out anew by the time they
+    /// are shown again.
+    pub fn resize(&mut self, width: f64, height: f64) {
+        for scene in &mut self.scene_stack {
+            scene.resize(width, height);
+        }
+    }
+
     pub fn handle_event(&mut self, e: &E) {
         if let Some(mut scene) = self.scene_stack.pop() {
             let result = scene.handle_event(e);
@@ -49,6 +92,15 @@ impl<B, E, G> SceneManager<B, E, G>
         }
     }
 
+    /// Advances the state of the scene at the top of the stack.
+    pub fn update(&mut self, dt: f64) {
+        if let Some(mut scene) = self.scene_stack.pop() {
+            let result = scene.update(dt);
+            self.scene_stack.push(scene);
+            self.handle_scene_command(result);
+        }
+    }
+
     pub fn handle_scene_command(&mut self, command: Option<SceneCommand<B, E, G>>) {
         if let Some(command) = command {
             match command {
@@ -60,6 +112,13 @@ impl<B, E, G> SceneManager<B, E, G>
                 },
                 SceneCommand::PopScene => {
                     self.pop_scene();
+                    if let Some(scene) = self.scene_stack.last_mut() {
+                        scene.resume();
+                    }
+                },
+                SceneCommand::ReplaceAll(scene) => {
+                    self.clear();
+                    self.push_scene(scene);
                 },
                 SceneCommand::Clear => {
                     self.clear();
@@ -0,0 +1,85 @@
+use piston::input::{GenericEvent, MouseButton, PressEvent};
+use piston::input::Button::Mouse;
+
+use backend::{Backend, Graphics};
+use backend::graphics::Context;
+use draw::Draw;
+use label::Label;
+use super::{LINE_SPACING, contains};
+
+const PADDING: f64 = 4.0;
+
+/// A line of text on a background, which lights up while hovered and
+/// produces its action when clicked.
+pub struct Button<A> {
+    label: Label,
+    action: A,
+    color: [f32; 4],
+    hover_color: [f32; 4],
+    /// The area covered by the button, as `[x, y, width, height]`.
+    rect: [f64; 4],
+    hovered: bool,
+}
+
+impl<A> Button<A> {
+    pub fn new(label: Label, action: A, color: [f32; 4], hover_color: [f32; 4]) -> Self {
+        let height = label.font_size() as f64 * LINE_SPACING + PADDING * 2.0;
+        Button {
+            label: label,
+            action: action,
+            color: color,
+            hover_color: hover_color,
+            rect: [0.0, 0.0, 0.0, height],
+            hovered: false,
+        }
+    }
+
+    pub fn label_mut(&mut self) -> &mut Label {
+        &mut self.label
+    }
+
+    pub fn height(&self) -> f64 {
+        self.rect[3]
+    }
+
+    pub fn layout(&mut self, position: [f64; 2], width: f64) {
+        self.rect = [position[0], position[1], width, self.rect[3]];
+        let baseline = position[1] + PADDING + self.label.font_size() as f64;
+        self.label.set_position([position[0] + PADDING, baseline]);
+    }
+
+    /// Tracks whether the mouse cursor at `cursor` hovers the button,
+    /// returning its action if `e` clicked it.
+    pub fn handle_event<E>(&mut self, e: &E, cursor: [f64; 2]) -> Option<A>
+        where A: Clone,
+              E: GenericEvent,
+    {
+        self.hovered = contains(self.rect, cursor);
+
+        let mut clicked = false;
+        e.press(|button| {
+            if let Mouse(MouseButton::Left) = button {
+                clicked = true;
+            }
+        });
+
+        if clicked && self.hovered {
+            Some(self.action.clone())
+        } else {
+            None
+        }
+    }
+}
+
+impl<A, B, G> Draw<B, G> for Button<A>
+    where B: Backend,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn draw(&self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        use graphics::Rectangle;
+
+        let color = if self.hovered { self.hover_color } else { self.color };
+        Rectangle::new(color).draw(self.rect, &context.draw_state, context.transform, graphics);
+        Draw::<B, G>::draw(&self.label, context, graphics, glyph_cache);
+    }
+}
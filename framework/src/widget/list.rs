@@ -0,0 +1,80 @@
+use backend::{Backend, Graphics};
+use backend::graphics::Context;
+use draw::Draw;
+use label::Label;
+use super::LINE_SPACING;
+
+/// Lines of text of the same color and size, one below the other.
+pub struct List {
+    color: [f32; 4],
+    font_size: u32,
+    /// Position of the top-left corner of the list, in pixels.
+    position: [f64; 2],
+    labels: Vec<Label>,
+}
+
+impl List {
+    pub fn new(color: [f32; 4], font_size: u32) -> Self {
+        List {
+            color: color,
+            font_size: font_size,
+            position: [0.0, 0.0],
+            labels: Vec::new(),
+        }
+    }
+
+    /// Replaces the lines of the list. The labels of the lines which remain
+    /// are kept, so that the widths of unchanged lines need not be measured
+    /// again.
+    pub fn set_lines(&mut self, lines: Vec<String>) {
+        self.labels.truncate(lines.len());
+
+        for (i, line) in lines.into_iter().enumerate() {
+            if i < self.labels.len() {
+                self.labels[i].set_text(line);
+            } else {
+                let position = self.line_position(i);
+                self.labels.push(Label::new(line, self.color, self.font_size, position));
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    pub fn height(&self) -> f64 {
+        self.labels.len() as f64 * self.line_height()
+    }
+
+    pub fn layout(&mut self, position: [f64; 2]) {
+        self.position = position;
+        for i in 0..self.labels.len() {
+            let position = self.line_position(i);
+            self.labels[i].set_position(position);
+        }
+    }
+
+    fn line_height(&self) -> f64 {
+        self.font_size as f64 * LINE_SPACING
+    }
+
+    /// Returns the position of the baseline of the `i`th line, in pixels.
+    fn line_position(&self, i: usize) -> [f64; 2] {
+        [
+            self.position[0],
+            self.position[1] + i as f64 * self.line_height() + self.font_size as f64,
+        ]
+    }
+}
+
+impl<B, G> Draw<B, G> for List
+    where B: Backend,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn draw(&self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        for label in &self.labels {
+            Draw::<B, G>::draw(label, context, graphics, glyph_cache);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Widgets which are created once and kept around between frames, laid out
+//! from top to bottom within a panel.
+
+use piston::input::GenericEvent;
+
+use backend::{Backend, Graphics};
+use backend::graphics::Context;
+use draw::Draw;
+use label::Label;
+
+pub use self::button::Button;
+pub use self::list::List;
+pub use self::panel::Panel;
+pub use self::progress_bar::ProgressBar;
+
+mod button;
+mod list;
+mod panel;
+mod progress_bar;
+
+/// The height of a line of text, relative to its font size.
+pub const LINE_SPACING: f64 = 1.5;
+
+/// A widget within a panel. Clicking a widget may produce an action of type
+/// `A`, which is handed back to whoever feeds the panel its events.
+pub enum Widget<A> {
+    Label(Label),
+    Button(Button<A>),
+    List(List),
+    ProgressBar(ProgressBar),
+}
+
+impl<A> Widget<A> {
+    /// Returns the height of the widget, in pixels.
+    pub fn height(&self) -> f64 {
+        match *self {
+            Widget::Label(ref label) => label.font_size() as f64 * LINE_SPACING,
+            Widget::Button(ref button) => button.height(),
+            Widget::List(ref list) => list.height(),
+            Widget::ProgressBar(ref bar) => bar.height(),
+        }
+    }
+
+    /// Places the top-left corner of the widget at `position`, stretching it
+    /// to `width` pixels.
+    fn layout(&mut self, position: [f64; 2], width: f64) {
+        match *self {
+            Widget::Label(ref mut label) => {
+                let baseline = position[1] + label.font_size() as f64;
+                label.set_position([position[0], baseline]);
+            },
+            Widget::Button(ref mut button) => button.layout(position, width),
+            Widget::List(ref mut list) => list.layout(position),
+            Widget::ProgressBar(ref mut bar) => bar.layout(position, width),
+        }
+    }
+
+    /// Updates the widget with the mouse cursor at `cursor`, returning the
+    /// action of the widget if `e` clicked it.
+    fn handle_event<E>(&mut self, e: &E, cursor: [f64; 2]) -> Option<A>
+        where A: Clone,
+              E: GenericEvent,
+    {
+        match *self {
+            Widget::Button(ref mut button) => button.handle_event(e, cursor),
+            _ => None,
+        }
+    }
+}
+
+impl<A, B, G> Draw<B, G> for Widget<A>
+    where B: Backend,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn draw(&self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        match *self {
+            Widget::Label(ref label) => Draw::<B, G>::draw(label, context, graphics, glyph_cache),
+            Widget::Button(ref button) => Draw::<B, G>::draw(button, context, graphics, glyph_cache),
+            Widget::List(ref list) => Draw::<B, G>::draw(list, context, graphics, glyph_cache),
+            Widget::ProgressBar(ref bar) => Draw::<B, G>::draw(bar, context, graphics, glyph_cache),
+        }
+    }
+}
+
+/// Returns true if `point` lies within `rect`, given as
+/// `[x, y, width, height]`.
+fn contains(rect: [f64; 4], point: [f64; 2]) -> bool {
+    point[0] >= rect[0] && point[0] < rect[0] + rect[2] &&
+        point[1] >= rect[1] && point[1] < rect[1] + rect[3]
+}
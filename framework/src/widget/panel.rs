@@ -0,0 +1,119 @@
+use piston::input::{GenericEvent, MouseCursorEvent};
+
+use backend::{Backend, Graphics};
+use backend::graphics::Context;
+use draw::Draw;
+use super::Widget;
+
+/// A background on which widgets are laid out from top to bottom, stretched
+/// to the width of the panel. The panel grows to fit its widgets.
+///
+/// The panel must be fed input events for its widgets to respond to the
+/// mouse.
+pub struct Panel<A> {
+    /// Position of the top-left corner of the panel, in pixels.
+    position: [f64; 2],
+    width: f64,
+    /// The space between the edges of the panel and its widgets, and between
+    /// neighbouring widgets, in pixels.
+    padding: f64,
+    spacing: f64,
+    background_color: [f32; 4],
+    widgets: Vec<Widget<A>>,
+    /// The last known position of the mouse cursor.
+    cursor: [f64; 2],
+}
+
+impl<A> Panel<A> {
+    pub fn new(position: [f64; 2], width: f64, padding: f64, spacing: f64, background_color: [f32; 4]) -> Self {
+        Panel {
+            position: position,
+            width: width,
+            padding: padding,
+            spacing: spacing,
+            background_color: background_color,
+            widgets: Vec::new(),
+            cursor: [0.0, 0.0],
+        }
+    }
+
+    /// Adds a widget below the others, returning its index within the panel.
+    pub fn push(&mut self, widget: Widget<A>) -> usize {
+        self.widgets.push(widget);
+        self.layout();
+        self.widgets.len() - 1
+    }
+
+    /// Modifies the widget at `index` with `f`, laying out the panel again
+    /// afterwards since the widget may have changed size.
+    pub fn update_widget<F, R>(&mut self, index: usize, f: F) -> Option<R>
+        where F: FnOnce(&mut Widget<A>) -> R,
+    {
+        let result = self.widgets.get_mut(index).map(f);
+        self.layout();
+        result
+    }
+
+    /// Moves the top-left corner of the panel to `position`, in pixels.
+    pub fn set_position(&mut self, position: [f64; 2]) {
+        self.position = position;
+        self.layout();
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn height(&self) -> f64 {
+        let widgets: f64 = self.widgets.iter().map(Widget::height).sum();
+        let gaps = self.widgets.len().saturating_sub(1) as f64 * self.spacing;
+        widgets + gaps + self.padding * 2.0
+    }
+
+    /// Lets the widgets respond to the mouse, returning the action of the
+    /// widget clicked by `e`, if any.
+    pub fn handle_event<E>(&mut self, e: &E) -> Option<A>
+        where A: Clone,
+              E: GenericEvent,
+    {
+        e.mouse_cursor(|x, y| self.cursor = [x, y]);
+
+        let cursor = self.cursor;
+        let mut action = None;
+        for widget in &mut self.widgets {
+            if let Some(clicked) = widget.handle_event(e, cursor) {
+                action = Some(clicked);
+            }
+        }
+        action
+    }
+
+    fn layout(&mut self) {
+        let x = self.position[0] + self.padding;
+        let width = self.width - self.padding * 2.0;
+        let mut y = self.position[1] + self.padding;
+        for widget in &mut self.widgets {
+            widget.layout([x, y], width);
+            y += widget.height() + self.spacing;
+        }
+    }
+}
+
+impl<A, B, G> Draw<B, G> for Panel<A>
+    where B: Backend,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn draw(&self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        use graphics::Rectangle;
+
+        Rectangle::new(self.background_color).draw(
+            [self.position[0], self.position[1], self.width, self.height()],
+            &context.draw_state,
+            context.transform,
+            graphics);
+
+        for widget in &self.widgets {
+            Draw::<B, G>::draw(widget, context, graphics, glyph_cache);
+        }
+    }
+}
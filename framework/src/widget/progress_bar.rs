@@ -0,0 +1,55 @@
+use backend::{Backend, Graphics};
+use backend::graphics::Context;
+use draw::Draw;
+
+/// A bar filled from left to right in proportion to some progress.
+pub struct ProgressBar {
+    color: [f32; 4],
+    background_color: [f32; 4],
+    /// The area covered by the bar, as `[x, y, width, height]`.
+    rect: [f64; 4],
+    /// The fraction of the bar which is filled, between 0 and 1.
+    progress: f64,
+}
+
+impl ProgressBar {
+    pub fn new(height: f64, color: [f32; 4], background_color: [f32; 4]) -> Self {
+        ProgressBar {
+            color: color,
+            background_color: background_color,
+            rect: [0.0, 0.0, 0.0, height],
+            progress: 0.0,
+        }
+    }
+
+    pub fn progress(&self) -> f64 {
+        self.progress
+    }
+
+    /// Sets the fraction of the bar which is filled, which is kept between 0
+    /// and 1.
+    pub fn set_progress(&mut self, progress: f64) {
+        self.progress = progress.max(0.0).min(1.0);
+    }
+
+    pub fn height(&self) -> f64 {
+        self.rect[3]
+    }
+
+    pub fn layout(&mut self, position: [f64; 2], width: f64) {
+        self.rect = [position[0], position[1], width, self.rect[3]];
+    }
+}
+
+impl<B, G> Draw<B, G> for ProgressBar
+    where B: Backend,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn draw(&self, context: &Context, graphics: &mut G, _glyph_cache: &mut B::CharacterCache) {
+        use graphics::Rectangle;
+
+        let rect = self.rect;
+        Rectangle::new(self.background_color).draw(rect, &context.draw_state, context.transform, graphics);
+        Rectangle::new(self.color).draw([rect[0], rect[1], rect[2] * self.progress, rect[3]], &context.draw_state, context.transform, graphics);
+    }
+}
@@ -0,0 +1,26 @@
+//! The simulation of Colonize, built as a library which the game loads while
+//! it runs when built with the `hot-reload` feature.
+//!
+//! The game keeps the world, and only calls into the library to run each step
+//! of its simulation, so rebuilding the library with changes to the
+//! simulation picks them up without losing the state of the game.
+
+extern crate colonize_world as world;
+
+use std::panic;
+use std::panic::AssertUnwindSafe;
+
+use world::World;
+
+/// Runs a step of the simulation of the world at `world`, returning whether
+/// it ran without panicking.
+///
+/// The world must have been created by a game built against the same
+/// version of `colonize_world`, with the same features, as the library.
+#[no_mangle]
+pub unsafe extern "C" fn colonize_game_tick(world: *mut World) -> bool {
+    let world = &mut *world;
+    // Unwinding out of an `extern "C"` function is undefined behaviour, so a
+    // panic is reported to the game instead.
+    panic::catch_unwind(AssertUnwindSafe(|| world.tick())).is_ok()
+}
@@ -1,4 +1,42 @@
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum Action {
     Camera(CameraAction),
+    Cursor(CursorAction),
+    Simulation(SimulationAction),
+    Designate(Designation),
+    /// Drafts the selected colonist, or releases it if it is drafted.
+    ToggleDraft,
+    /// Rewinds the world to its latest snapshot.
+    Rewind,
+    /// Shows the most recent lines of the log in the console.
+    ShowLog,
+    /// Switches between the top-down and isometric views of the world.
+    ToggleIsometric,
+    /// Cycles the designations drawn over the world between all of them,
+    /// those of the kind selected for painting, and none.
+    CycleOverlays,
+    /// Shows the roster of colonists, or hides it if it is shown.
+    ToggleRoster,
+    /// Shows the graphs of the metrics of the colony over the days.
+    ShowReports,
+    /// Skips ahead an hour, to preview the lighting at other times of day.
+    /// Unavailable while recording or replaying.
+    SkipHour,
+    /// Shows what the colonists are doing and why, or hides it if it is
+    /// shown.
+    ToggleAiOverlay,
+    /// Switches between drawing the tiles of each slice of the view at once
+    /// and drawing them one by one.
+    ToggleBatchTiles,
+    /// Pauses the recording being played back and runs a single step of the
+    /// simulation.
+    StepPlayback,
+    /// Cycles the recording being played back between half, a quarter of
+    /// and its full speed.
+    CyclePlaybackSlowdown,
+    /// Scrubs the recording being played back to its previous snapshot, or
+    /// to its start once there are none left.
+    ScrubBack,
+    /// Scrubs the recording being played back forward an hour.
+    ScrubForward,
 }
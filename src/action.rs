@@ -1,4 +1,7 @@
 use camera::CameraAction;
+use cursor::CursorAction;
+use designation::Designation;
+use simulation::SimulationAction;
 
 #[cfg(feature = "nightly")]
 include!("action.in.rs");
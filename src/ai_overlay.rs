@@ -0,0 +1,141 @@
+use graphics;
+use rgframework::backend::Graphics;
+use rgframework::backend::graphics::Context;
+use world::{Position, World};
+
+use renderer::TileView;
+
+/// Color of the tiles explored while finding routes, whose opacity grows with
+/// the number of times they were explored.
+const EXPLORED_COLOR: [f32; 3] = [1.0, 0.3, 0.0];
+const MAX_EXPLORED_OPACITY: f32 = 0.6;
+/// The number of times a tile must be explored to be drawn fully opaque.
+const EXPLORED_SATURATION: u32 = 8;
+const FRONTIER_COLOR: [f32; 4] = [1.0, 0.9, 0.0, 0.6];
+const PATH_COLOR: [f32; 4] = [0.0, 0.6, 1.0, 0.8];
+/// Fraction of a tile covered by each step of a route.
+const PATH_STEP_SIZE: f64 = 0.3;
+const QUEUED_JOB_COLOR: [f32; 4] = [0.9, 0.1, 0.1, 0.9];
+const ASSIGNED_JOB_COLOR: [f32; 4] = [0.1, 0.8, 0.1, 0.9];
+const BORDER_RADIUS: f64 = 1.0;
+const ASSIGNMENT_COLOR: [f32; 4] = [0.1, 0.8, 0.1, 0.6];
+const ASSIGNMENT_RADIUS: f64 = 1.0;
+
+/// An overlay over the tile view showing what the colonists and creatures of
+/// the world are up to: the tiles explored while finding routes, the routes
+/// being walked, the jobs waiting to be taken, and which colonist is carrying
+/// out which job.
+pub struct AiOverlay {
+    visible: bool,
+}
+
+impl AiOverlay {
+    pub fn new() -> Self {
+        AiOverlay {
+            visible: false,
+        }
+    }
+
+    /// Shows or hides the overlay. The tiles explored while finding routes are
+    /// only recorded while it is shown.
+    pub fn toggle(&mut self, world: &mut World) {
+        self.visible = !self.visible;
+        world.set_search_tracing(self.visible);
+    }
+
+    pub fn draw<G>(&self, world: &World, view: &TileView, context: &Context, graphics: &mut G)
+        where G: Graphics,
+    {
+        if !self.visible {
+            return;
+        }
+
+        self.draw_search_trace(world, view, context, graphics);
+        self.draw_routes(world, view, context, graphics);
+        self.draw_jobs(world, view, context, graphics);
+    }
+
+    /// Draws a heatmap of the tiles explored while finding routes, and the
+    /// tiles left to explore when the last search ended.
+    fn draw_search_trace<G>(&self, world: &World, view: &TileView, context: &Context, graphics: &mut G)
+        where G: Graphics,
+    {
+        let trace = match world.search_trace() {
+            Some(trace) => trace,
+            None => return,
+        };
+
+        for (pos, &count) in &trace.explored {
+            if let Some(rect) = view.rect(pos) {
+                let heat = count.min(EXPLORED_SATURATION) as f32 / EXPLORED_SATURATION as f32;
+                let color = [EXPLORED_COLOR[0], EXPLORED_COLOR[1], EXPLORED_COLOR[2], heat * MAX_EXPLORED_OPACITY];
+                graphics::Rectangle::new(color).draw(rect, &context.draw_state, context.transform, graphics);
+            }
+        }
+        for pos in &trace.frontier {
+            if let Some(rect) = view.rect(pos) {
+                graphics::Rectangle::new_border(FRONTIER_COLOR, BORDER_RADIUS).draw(rect, &context.draw_state, context.transform, graphics);
+            }
+        }
+    }
+
+    /// Draws the remaining steps of the route of every entity walking
+    /// somewhere, and outlines where it is headed.
+    fn draw_routes<G>(&self, world: &World, view: &TileView, context: &Context, graphics: &mut G)
+        where G: Graphics,
+    {
+        let margin = view.tile_size * (1.0 - PATH_STEP_SIZE) / 2.0;
+        for (_, pathing) in world.entities().pathing.iter() {
+            for step in &pathing.path {
+                if let Some(rect) = view.rect(&step.to_point()) {
+                    let rect = [rect[0] + margin, rect[1] + margin, rect[2] - margin * 2.0, rect[3] - margin * 2.0];
+                    graphics::Ellipse::new(PATH_COLOR).draw(rect, &context.draw_state, context.transform, graphics);
+                }
+            }
+            if let Some(rect) = pathing.target.and_then(|target| view.rect(&target.to_point())) {
+                graphics::Rectangle::new_border(PATH_COLOR, BORDER_RADIUS).draw(rect, &context.draw_state, context.transform, graphics);
+            }
+        }
+    }
+
+    /// Outlines the target of every job, depending on whether a colonist has
+    /// taken it, and links every colonist to the target of its job. Blueprints
+    /// whose material has been found are outlined as though taken.
+    fn draw_jobs<G>(&self, world: &World, view: &TileView, context: &Context, graphics: &mut G)
+        where G: Graphics,
+    {
+        let entities = world.entities();
+        for job in world.job_queue() {
+            outline(view, &job.target, QUEUED_JOB_COLOR, context, graphics);
+        }
+        for (entity, blueprint) in entities.blueprints.iter() {
+            if let (Some(position), Some(_)) = (entities.positions.get(entity), blueprint.material) {
+                outline(view, position, ASSIGNED_JOB_COLOR, context, graphics);
+            }
+        }
+        for (colonist, job) in entities.jobs.iter() {
+            outline(view, &job.target, ASSIGNED_JOB_COLOR, context, graphics);
+
+            let position = match entities.positions.get(colonist) {
+                Some(position) if position.y == view.y || job.target.y == view.y => position,
+                _ => continue,
+            };
+            let from = view.center(&position.to_point());
+            let to = view.center(&job.target.to_point());
+            graphics::Line::new(ASSIGNMENT_COLOR, ASSIGNMENT_RADIUS).draw(
+                [from[0], from[1], to[0], to[1]],
+                &context.draw_state,
+                context.transform,
+                graphics);
+        }
+    }
+}
+
+/// Outlines the tile at `position`, if it is visible.
+fn outline<G>(view: &TileView, position: &Position, color: [f32; 4], context: &Context, graphics: &mut G)
+    where G: Graphics,
+{
+    if let Some(rect) = view.rect(&position.to_point()) {
+        graphics::Rectangle::new_border(color, BORDER_RADIUS).draw(rect, &context.draw_state, context.transform, graphics);
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use world::{Entity, FurnitureKind, World};
+
+/// Names of the sprites of the tile atlas with which entities are drawn,
+/// most of which are animations.
+const COLONIST_SPRITE: &'static str = "colonist";
+const COLONIST_WALKING_SPRITE: &'static str = "colonist_walk";
+const WORKSHOP_SPRITE: &'static str = "workshop";
+const WORKSHOP_ACTIVE_SPRITE: &'static str = "workshop_active";
+
+/// The sprite an entity is drawn with, and the game time at which it started
+/// being drawn with it.
+struct SpriteState {
+    sprite: &'static str,
+    started: u64,
+}
+
+/// Picks the sprite of the tile atlas each entity is drawn with, following
+/// what the entity is doing, and keeps track of how long it has been drawn
+/// with it, so that animations play from their first frame.
+///
+/// Colonists walk while they have a route to follow, and workshops are busy
+/// while a job at them is being worked on. Other entities have no sprite.
+pub struct EntityAnimations {
+    states: HashMap<Entity, SpriteState>,
+    /// The game time, in ticks, at the last update.
+    time: u64,
+}
+
+impl EntityAnimations {
+    pub fn new() -> Self {
+        EntityAnimations {
+            states: HashMap::new(),
+            time: 0,
+        }
+    }
+
+    /// Picks the sprite of every entity of `world` anew, starting over the
+    /// animations of those whose sprite changed.
+    pub fn update(&mut self, world: &World) {
+        let entities = world.entities();
+        self.time = world.calendar().ticks();
+
+        let in_use: Vec<Entity> = world.workshops_in_use().into_iter().map(|(entity, _)| entity).collect();
+
+        let mut sprites = Vec::new();
+        for (entity, _) in entities.colonists.iter() {
+            let walking = entities.pathing.get(entity).map_or(false, |pathing| !pathing.path.is_empty());
+            sprites.push((entity, if walking { COLONIST_WALKING_SPRITE } else { COLONIST_SPRITE }));
+        }
+        for (entity, furniture) in entities.furniture.iter() {
+            if furniture.kind != FurnitureKind::Workshop {
+                continue;
+            }
+            let active = in_use.contains(&entity);
+            sprites.push((entity, if active { WORKSHOP_ACTIVE_SPRITE } else { WORKSHOP_SPRITE }));
+        }
+
+        // The states of entities which are gone, or no longer have a sprite,
+        // are dropped.
+        let mut states = HashMap::with_capacity(sprites.len());
+        for (entity, sprite) in sprites {
+            let started = match self.states.get(&entity) {
+                Some(state) if state.sprite == sprite => state.started,
+                _ => self.time,
+            };
+            states.insert(entity, SpriteState {
+                sprite: sprite,
+                started: started,
+            });
+        }
+        self.states = states;
+    }
+
+    /// Returns the name of the sprite `entity` is drawn with, and the number
+    /// of game ticks since it started being drawn with it, if it has one.
+    pub fn sprite(&self, entity: Entity) -> Option<(&'static str, u64)> {
+        self.states.get(&entity).map(|state| (state.sprite, self.time.saturating_sub(state.started)))
+    }
+}
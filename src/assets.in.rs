@@ -0,0 +1,13 @@
+/// The fonts and textures which can be loaded, by name, along with the files
+/// they are loaded from, relative to the asset directory.
+#[derive(Default, Deserialize, Serialize)]
+pub struct AssetManifest {
+    #[serde(default)]
+    pub fonts: HashMap<String, String>,
+    #[serde(default)]
+    pub textures: HashMap<String, String>,
+    /// The atlas of the textures used to render the world, relative to the
+    /// asset directory.
+    #[serde(default)]
+    pub tile_atlas: Option<String>,
+}
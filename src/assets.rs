@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use opengl_graphics::Texture;
+use opengl_graphics::glyph_cache::GlyphCache;
+use serde_json;
+
+use backend::GlBackend;
+use error::{ColonizeError, ColonizeResult};
+use mods::ModPack;
+use tile_atlas::TileAtlas;
+use tile_renderer::TileRenderer;
+
+#[cfg(feature = "nightly")]
+include!("assets.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+
+const MANIFEST_FILE: &'static str = "manifest.json";
+
+/// An asset listed in the asset manifest.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Asset {
+    Font(String),
+    Texture(String),
+    TileAtlas,
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Asset::Font(ref name) => write!(f, "font {}", name),
+            Asset::Texture(ref name) => write!(f, "texture {}", name),
+            Asset::TileAtlas => write!(f, "tile atlas"),
+        }
+    }
+}
+
+/// The asset manifest of the asset directory, or of a mod, along with the
+/// directory its files are relative to.
+struct AssetLayer {
+    /// The name of the mod the layer belongs to, or `None` for the asset
+    /// directory.
+    mod_name: Option<String>,
+    root: PathBuf,
+    manifest: AssetManifest,
+    watcher: Option<AssetWatcher>,
+}
+
+impl AssetLayer {
+    fn load(mod_name: Option<String>, root: &PathBuf) -> Self {
+        AssetLayer {
+            mod_name: mod_name,
+            root: root.clone(),
+            manifest: load_manifest(root),
+            watcher: AssetWatcher::new(root),
+        }
+    }
+
+    /// Returns every asset listed in the manifest of the layer.
+    fn assets(&self) -> Vec<Asset> {
+        let mut assets: Vec<Asset> = self.manifest.fonts.keys().cloned().map(Asset::Font).collect();
+        assets.extend(self.manifest.textures.keys().cloned().map(Asset::Texture));
+        if self.manifest.tile_atlas.is_some() {
+            assets.push(Asset::TileAtlas);
+        }
+        assets
+    }
+}
+
+/// Loads the fonts and textures of the game by their names in the asset
+/// manifest.
+///
+/// The manifests of mods are layered on top of that of the asset directory,
+/// in load order, so that an asset listed by a mod replaces any of the same
+/// name listed before it, and a mod can replace every asset of the game
+/// without any change to the game itself.
+///
+/// In debug builds, the asset directory and the directories of mods are also
+/// watched for changes, so that any asset whose file is modified can be
+/// loaded again while the game runs.
+pub struct AssetManager {
+    /// The asset directory, followed by every mod with an asset manifest.
+    layers: Vec<AssetLayer>,
+}
+
+impl AssetManager {
+    /// Reads the asset manifest of `asset_path`, and those of the mods in
+    /// `mod_packs`, falling back to an empty manifest for the asset directory
+    /// if its manifest cannot be read. Any asset replaced by a mod is
+    /// reported.
+    pub fn load(asset_path: &PathBuf, mod_packs: &[ModPack]) -> Self {
+        let mut layers = vec![AssetLayer::load(None, asset_path)];
+        for mod_pack in mod_packs {
+            if !mod_pack.path.join(MANIFEST_FILE).exists() {
+                continue;
+            }
+            let layer = AssetLayer::load(Some(mod_pack.name.clone()), &mod_pack.path);
+            for asset in layer.assets() {
+                if let Some(other) = layers.iter().rev().find(|other| other.assets().contains(&asset)) {
+                    let overridden = match other.mod_name {
+                        Some(ref name) => format!("mod {}", name),
+                        None => "the game".to_owned(),
+                    };
+                    info!("Mod {} overrides the {} of {}", mod_pack.name, asset, overridden);
+                }
+            }
+            layers.push(layer);
+        }
+        AssetManager {
+            layers: layers,
+        }
+    }
+
+    /// Returns the path of the file listed for `name` in `files` by the
+    /// topmost layer listing one.
+    fn find<F>(&self, files: F, name: &str) -> Option<PathBuf>
+        where F: Fn(&AssetManifest) -> &HashMap<String, String>,
+    {
+        self.layers.iter().rev()
+            .filter_map(|layer| files(&layer.manifest).get(name).map(|file| layer.root.join(file)))
+            .next()
+    }
+
+    /// Loads the font `name` into a new glyph cache.
+    pub fn load_font(&self, name: &str) -> ColonizeResult<GlyphCache<'static>> {
+        let path = try!(self.find(|manifest| &manifest.fonts, name)
+            .ok_or_else(|| ColonizeError::Font(format!("unknown font: {}", name))));
+        GlyphCache::new(&path)
+            .map_err(|e| ColonizeError::Font(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Loads the texture `name`.
+    pub fn load_texture(&self, name: &str) -> ColonizeResult<Texture> {
+        let path = try!(self.find(|manifest| &manifest.textures, name)
+            .ok_or_else(|| ColonizeError::Texture(format!("unknown texture: {}", name))));
+        Texture::from_path(&path)
+            .map_err(|e| ColonizeError::Texture(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Loads the tile atlas and its texture.
+    pub fn load_tile_atlas(&self) -> ColonizeResult<(Texture, TileAtlas)> {
+        let path = try!(self.layers.iter().rev()
+            .filter_map(|layer| layer.manifest.tile_atlas.as_ref().map(|file| layer.root.join(file)))
+            .next()
+            .ok_or_else(|| ColonizeError::Texture("no tile atlas in the asset manifest".to_owned())));
+        let json = try!(::read_file_to_string(&path));
+        let atlas = try!(TileAtlas::from_json(&json));
+        let texture = try!(self.load_texture(&atlas.texture));
+        Ok((texture, atlas))
+    }
+
+    /// Loads the tile atlas and its texture into a `TileRenderer`, falling
+    /// back to flat colored tiles if either of them cannot be loaded.
+    pub fn load_tile_renderer(&self) -> TileRenderer<GlBackend> {
+        match self.load_tile_atlas() {
+            Ok((texture, atlas)) => TileRenderer::new(texture, atlas),
+            Err(e) => {
+                warn!("Failed to load tile atlas, using fallback colors: {}", e);
+                TileRenderer::fallback()
+            },
+        }
+    }
+
+    /// Returns the assets whose files have been modified since this was last
+    /// called, reading an asset manifest again if it was itself modified.
+    ///
+    /// Nothing is ever returned in release builds, in which no directory is
+    /// watched.
+    pub fn poll_changes(&mut self) -> Vec<Asset> {
+        let mut changed = Vec::new();
+        for layer in &mut self.layers {
+            let paths = match layer.watcher {
+                Some(ref watcher) => watcher.changed_paths(),
+                None => continue,
+            };
+
+            for path in paths {
+                if path == Path::new(MANIFEST_FILE) {
+                    layer.manifest = load_manifest(&layer.root);
+                    for asset in layer.assets() {
+                        push_unique(&mut changed, asset);
+                    }
+                    continue;
+                }
+
+                for (name, file) in &layer.manifest.fonts {
+                    if path == Path::new(file) {
+                        push_unique(&mut changed, Asset::Font(name.clone()));
+                    }
+                }
+                for (name, file) in &layer.manifest.textures {
+                    if path == Path::new(file) {
+                        push_unique(&mut changed, Asset::Texture(name.clone()));
+                    }
+                }
+                if layer.manifest.tile_atlas.as_ref().map_or(false, |file| path == Path::new(file)) {
+                    push_unique(&mut changed, Asset::TileAtlas);
+                }
+            }
+        }
+        changed
+    }
+}
+
+fn push_unique(assets: &mut Vec<Asset>, asset: Asset) {
+    if !assets.contains(&asset) {
+        assets.push(asset);
+    }
+}
+
+fn load_manifest(root: &PathBuf) -> AssetManifest {
+    let path = root.join(MANIFEST_FILE);
+    match read_manifest(&path) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            warn!("Failed to load asset manifest {}: {}", path.display(), e);
+            AssetManifest::default()
+        },
+    }
+}
+
+fn read_manifest(path: &PathBuf) -> ColonizeResult<AssetManifest> {
+    let json = try!(::read_file_to_string(path));
+    Ok(try!(serde_json::from_str(&json)))
+}
+
+#[cfg(debug_assertions)]
+use self::watcher::AssetWatcher;
+
+#[cfg(not(debug_assertions))]
+use self::no_watcher::AssetWatcher;
+
+#[cfg(debug_assertions)]
+mod watcher {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc::{self, Receiver};
+
+    use notify::{self, RecommendedWatcher, RecursiveMode, Watcher};
+
+    /// Watches the asset directory, or the directory of a mod, for modified
+    /// files.
+    pub struct AssetWatcher {
+        /// The watched directory, relative to which the paths of modified
+        /// files are returned.
+        root: PathBuf,
+        events: Receiver<notify::Event>,
+        _watcher: RecommendedWatcher,
+    }
+
+    impl AssetWatcher {
+        /// Starts watching `asset_path`, returning `None` if it cannot be
+        /// watched.
+        pub fn new(asset_path: &Path) -> Option<Self> {
+            // Some platforms report the paths of events as absolute paths, so
+            // the directory is watched by its canonical path, relative to
+            // which the paths of all events can then be made.
+            let root = fs::canonicalize(asset_path).unwrap_or_else(|_| asset_path.to_owned());
+            let (sender, events) = mpsc::channel();
+            let watcher = RecommendedWatcher::new(sender)
+                .and_then(|mut watcher| watcher.watch(&root, RecursiveMode::Recursive).map(|_| watcher));
+            match watcher {
+                Ok(watcher) => Some(AssetWatcher {
+                    root: root,
+                    events: events,
+                    _watcher: watcher,
+                }),
+                Err(e) => {
+                    warn!("Failed to watch assets, hot reloading is disabled: {}", e);
+                    None
+                },
+            }
+        }
+
+        /// Returns the paths, relative to the watched directory, of the files
+        /// modified since this was last called.
+        pub fn changed_paths(&self) -> Vec<PathBuf> {
+            let mut paths = Vec::new();
+            while let Ok(event) = self.events.try_recv() {
+                let path = match event.path {
+                    Some(path) => path,
+                    None => continue,
+                };
+                if let Ok(relative) = path.strip_prefix(&self.root) {
+                    if !paths.iter().any(|p| p.as_path() == relative) {
+                        paths.push(relative.to_owned());
+                    }
+                }
+            }
+            paths
+        }
+    }
+}
+
+#[cfg(not(debug_assertions))]
+mod no_watcher {
+    use std::path::{Path, PathBuf};
+
+    /// Stands in for the watcher of the asset directory, which is only used
+    /// in debug builds.
+    pub struct AssetWatcher;
+
+    impl AssetWatcher {
+        pub fn new(_asset_path: &Path) -> Option<Self> {
+            None
+        }
+
+        pub fn changed_paths(&self) -> Vec<PathBuf> {
+            Vec::new()
+        }
+    }
+}
@@ -0,0 +1,9 @@
+/// The music tracks and sound effects which can be played, by name, along
+/// with the files they are loaded from, relative to the audio directory.
+#[derive(Default, Deserialize, Serialize)]
+pub struct AudioManifest {
+    #[serde(default)]
+    pub music: HashMap<String, String>,
+    #[serde(default)]
+    pub sounds: HashMap<String, String>,
+}
@@ -0,0 +1,210 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use cgmath::Point3;
+use sdl2;
+use sdl2::mixer::{self, Channel, Chunk, Music};
+use serde_json;
+
+use error::{ColonizeError, ColonizeResult};
+use settings::Settings;
+
+#[cfg(feature = "nightly")]
+include!("audio.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/audio.rs"));
+
+const MANIFEST_FILE: &'static str = "manifest.json";
+const FREQUENCY: i32 = 44_100;
+/// The number of samples mixed at a time.
+const BUFFER_SIZE: i32 = 1024;
+/// The number of sound effects which can play at once.
+const MIXING_CHANNELS: i32 = 32;
+/// The volume at which the mixer plays sounds unchanged.
+const MAX_VOLUME: f64 = 128.0;
+/// The distance from the camera, in tiles, beyond which sound effects cannot
+/// be heard.
+const HEARING_DISTANCE: f64 = 40.0;
+/// The distance, in tiles, which every z-level between a sound effect and the
+/// camera counts for, so that sounds from other z-levels are muffled.
+const Z_LEVEL_DISTANCE: f64 = 4.0;
+
+pub const MENU_MUSIC: &'static str = "menu";
+pub const GAME_MUSIC: &'static str = "game";
+pub const CLICK_SOUND: &'static str = "click";
+pub const DIG_SOUND: &'static str = "dig";
+pub const AMBIENT_DAY_SOUND: &'static str = "ambient_day";
+pub const AMBIENT_NIGHT_SOUND: &'static str = "ambient_night";
+pub const AMBIENT_RAIN_SOUND: &'static str = "ambient_rain";
+
+/// Plays the music of each scene and the sound effects of the game, at the
+/// volumes set in the settings.
+///
+/// Music and sound effects are referred to by their names in the audio
+/// manifest. Any which could not be loaded are silently skipped, and nothing
+/// is played at all if no audio device could be opened.
+pub struct AudioManager {
+    mixer: Option<Mixer>,
+    music_volume: Cell<f64>,
+    sfx_volume: Cell<f64>,
+}
+
+/// The loaded music and sound effects, along with the SDL contexts which must
+/// be kept alive for as long as they are played.
+struct Mixer {
+    _sdl: sdl2::Sdl,
+    _audio: sdl2::AudioSubsystem,
+    _mixer: mixer::Sdl2MixerContext,
+    music: HashMap<String, Music<'static>>,
+    sounds: HashMap<String, Chunk>,
+    /// The name of the music track being played, if any.
+    playing: RefCell<Option<String>>,
+}
+
+impl AudioManager {
+    /// Opens the audio device and loads the music and sound effects listed in
+    /// the manifest of `audio_path`, falling back to playing nothing if either
+    /// fails.
+    pub fn load(audio_path: &PathBuf, settings: &Settings) -> Self {
+        let mixer = match open_mixer(audio_path) {
+            Ok(mixer) => Some(mixer),
+            Err(e) => {
+                warn!("Failed to initialize audio, playing without sound: {}", e);
+                None
+            },
+        };
+
+        let audio = AudioManager {
+            mixer: mixer,
+            music_volume: Cell::new(0.0),
+            sfx_volume: Cell::new(0.0),
+        };
+        audio.set_volumes(settings);
+        audio
+    }
+
+    /// Creates an audio manager which plays nothing, for running the game
+    /// where there is nothing to play sound on.
+    pub fn silent() -> Self {
+        AudioManager {
+            mixer: None,
+            music_volume: Cell::new(0.0),
+            sfx_volume: Cell::new(0.0),
+        }
+    }
+
+    /// Changes the volumes of the music and sound effects to those of
+    /// `settings`, including the volume of the music already playing.
+    pub fn set_volumes(&self, settings: &Settings) {
+        self.music_volume.set(settings.master_volume * settings.music_volume);
+        self.sfx_volume.set(settings.master_volume * settings.sfx_volume);
+        if self.mixer.is_some() {
+            Music::set_volume((self.music_volume.get() * MAX_VOLUME) as i32);
+        }
+    }
+
+    /// Plays the music track `name` on a loop, unless it is already playing.
+    pub fn play_music(&self, name: &str) {
+        let mixer = match self.mixer {
+            Some(ref mixer) => mixer,
+            None => return,
+        };
+        if mixer.playing.borrow().as_ref().map(String::as_str) == Some(name) {
+            return;
+        }
+
+        *mixer.playing.borrow_mut() = Some(name.to_owned());
+        match mixer.music.get(name) {
+            Some(music) => {
+                Music::set_volume((self.music_volume.get() * MAX_VOLUME) as i32);
+                let _ = music.play(-1);
+            },
+            None => Music::halt(),
+        }
+    }
+
+    /// Plays the sound effect `name` at full volume.
+    pub fn play_sound(&self, name: &str) {
+        self.play_sound_panned(name, 1.0, 0.0);
+    }
+
+    /// Plays the sound effect `name` as heard from the camera at `listener`,
+    /// quieter the further away `pos` is, and panned to the side on which it
+    /// lies.
+    pub fn play_sound_at(&self, name: &str, pos: Point3<i32>, listener: Point3<f64>) {
+        let dx = pos.x as f64 + 0.5 - listener.x;
+        let dz = pos.z as f64 + 0.5 - listener.z;
+        let dy = (pos.y as f64 - listener.y.floor()).abs();
+        let distance = (dx * dx + dz * dz).sqrt() + dy * Z_LEVEL_DISTANCE;
+        if distance >= HEARING_DISTANCE {
+            return;
+        }
+
+        let gain = 1.0 - distance / HEARING_DISTANCE;
+        let pan = (dx / HEARING_DISTANCE).max(-1.0).min(1.0);
+        self.play_sound_panned(name, gain, pan);
+    }
+
+    /// Plays the sound effect `name` with its volume multiplied by `gain`, and
+    /// panned between -1 (left) and 1 (right) by `pan`.
+    fn play_sound_panned(&self, name: &str, gain: f64, pan: f64) {
+        let sound = match self.mixer.as_ref().and_then(|mixer| mixer.sounds.get(name)) {
+            Some(sound) => sound,
+            None => return,
+        };
+
+        if let Ok(channel) = Channel::all().play(sound, 0) {
+            channel.set_volume((self.sfx_volume.get() * gain * MAX_VOLUME) as i32);
+            let right = ((1.0 + pan) / 2.0 * 255.0) as u8;
+            let _ = channel.set_panning(255 - right, right);
+        }
+    }
+}
+
+impl Drop for Mixer {
+    fn drop(&mut self) {
+        Music::halt();
+        mixer::close_audio();
+    }
+}
+
+fn open_mixer(audio_path: &PathBuf) -> ColonizeResult<Mixer> {
+    let manifest = try!(read_manifest(&audio_path.join(MANIFEST_FILE)));
+
+    let sdl = try!(sdl2::init().map_err(ColonizeError::Audio));
+    let audio = try!(sdl.audio().map_err(ColonizeError::Audio));
+    try!(mixer::open_audio(FREQUENCY, mixer::DEFAULT_FORMAT, mixer::DEFAULT_CHANNELS, BUFFER_SIZE).map_err(ColonizeError::Audio));
+    let context = try!(mixer::init(mixer::INIT_OGG).map_err(ColonizeError::Audio));
+    mixer::allocate_channels(MIXING_CHANNELS);
+
+    let mut music = HashMap::new();
+    for (name, file) in manifest.music {
+        match Music::from_file(&audio_path.join(&file)) {
+            Ok(track) => { music.insert(name, track); },
+            Err(e) => warn!("Failed to load music {}: {}", file, e),
+        }
+    }
+    let mut sounds = HashMap::new();
+    for (name, file) in manifest.sounds {
+        match Chunk::from_file(&audio_path.join(&file)) {
+            Ok(sound) => { sounds.insert(name, sound); },
+            Err(e) => warn!("Failed to load sound {}: {}", file, e),
+        }
+    }
+
+    Ok(Mixer {
+        _sdl: sdl,
+        _audio: audio,
+        _mixer: context,
+        music: music,
+        sounds: sounds,
+        playing: RefCell::new(None),
+    })
+}
+
+fn read_manifest(path: &PathBuf) -> ColonizeResult<AudioManifest> {
+    let json = try!(::read_file_to_string(path));
+    Ok(try!(serde_json::from_str(&json)))
+}
@@ -1,5 +1,32 @@
+use glium_graphics::GliumWindow;
+use glutin;
 use opengl_graphics::GlGraphics;
 use opengl_graphics::glyph_cache::GlyphCache;
+use piston::window::Size;
 use rgframework::backend::Graphics;
 
 pub type GlBackend = (<GlGraphics as Graphics>::Texture, GlyphCache<'static>);
+
+/// A window which can be made to cover the whole screen while it is open.
+pub trait FullscreenWindow {
+    /// Makes the window cover the primary monitor.
+    fn enter_fullscreen(&mut self);
+
+    /// Gives the window back the specified size.
+    fn leave_fullscreen(&mut self, size: Size);
+}
+
+// The mode of the window cannot be changed once it has been created, so the
+// window is instead moved over the primary monitor and stretched to cover it.
+impl FullscreenWindow for GliumWindow {
+    fn enter_fullscreen(&mut self) {
+        let (width, height) = glutin::get_primary_monitor().get_dimensions();
+        let window = self.window.borrow();
+        window.window.set_position(0, 0);
+        window.window.set_inner_size(width, height);
+    }
+
+    fn leave_fullscreen(&mut self, size: Size) {
+        self.window.borrow().window.set_inner_size(size.width, size.height);
+    }
+}
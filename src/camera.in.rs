@@ -1,4 +1,4 @@
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum CameraAction {
     Move(Direction),
 }
@@ -1,4 +1,5 @@
-use cgmath::{ElementWise, EuclideanSpace, Point3, Vector3};
+use cgmath::{EuclideanSpace, Point3, Vector3};
+use piston::input::keyboard::Key;
 use rgframework::Command;
 use world::Direction;
 
@@ -9,37 +10,110 @@ include!("camera.in.rs");
 include!(concat!(env!("OUT_DIR"), "/camera.rs"));
 
 pub struct Camera {
-    /// The speed at which the camera moves along the three axes.
-    movement_speed: Vector3<i32>,
-    position: Point3<i32>,
+    /// The speed at which the camera moves along the horizontal axes, in
+    /// tiles per second.
+    movement_speed: f64,
+    /// The multiplier applied to `movement_speed` while fast scrolling.
+    fast_scroll_multiplier: f64,
+    /// The position of the camera, in tiles. The horizontal components may
+    /// be fractional, while the vertical component is always a whole z-level.
+    position: Point3<f64>,
+    /// The horizontal directions in which the camera is currently moving.
+    moving: Vec<Direction>,
+    /// The keys held down to fast scroll, each of which keeps the camera
+    /// fast scrolling until it is released.
+    fast_scroll_keys: Vec<Key>,
 }
 
 impl Camera {
-    pub fn new(movement_speed: Vector3<i32>, position: Point3<i32>) -> Self {
+    pub fn new(movement_speed: f64, fast_scroll_multiplier: f64, position: Point3<f64>) -> Self {
         Camera {
             movement_speed: movement_speed,
+            fast_scroll_multiplier: fast_scroll_multiplier,
             position: position,
+            moving: Vec::new(),
+            fast_scroll_keys: Vec::new(),
         }
     }
 
-    pub fn get_position(&self) -> &Point3<i32> {
+    pub fn get_position(&self) -> &Point3<f64> {
         &self.position
     }
 
+    pub fn set_position(&mut self, position: Point3<f64>) {
+        self.position = position;
+    }
+
+    /// Returns the position of the tile the camera is currently centered on.
+    pub fn get_tile_position(&self) -> Point3<i32> {
+        Point3::new(
+            self.position.x.floor() as i32,
+            self.position.y.floor() as i32,
+            self.position.z.floor() as i32,
+        )
+    }
+
+    /// Moves the camera by one whole tile in the specified direction.
     pub fn move_in_direction(&mut self, direction: &Direction) {
-        self.position += direction.to_vector().mul_element_wise(self.movement_speed);
+        self.position += direction.to_vector().cast::<f64>();
+    }
+
+    /// Starts moving the camera in the specified direction.
+    ///
+    /// Horizontal movement continues until `stop_moving` is called, while
+    /// vertical movement immediately changes the z-level by one.
+    pub fn start_moving(&mut self, direction: &Direction) {
+        if direction.is_vertical() {
+            self.move_in_direction(direction);
+        } else if !self.moving.contains(direction) {
+            self.moving.push(*direction);
+        }
+    }
+
+    pub fn stop_moving(&mut self, direction: &Direction) {
+        self.moving.retain(|d| d != direction);
+    }
+
+    /// Stops all horizontal movement of the camera.
+    pub fn stop(&mut self) {
+        self.moving.clear();
+        self.fast_scroll_keys.clear();
+    }
+
+    /// Fast scrolls the camera while `key` is held, until
+    /// `stop_fast_scroll` is called with it.
+    pub fn start_fast_scroll(&mut self, key: Key) {
+        if !self.fast_scroll_keys.contains(&key) {
+            self.fast_scroll_keys.push(key);
+        }
+    }
+
+    pub fn stop_fast_scroll(&mut self, key: Key) {
+        self.fast_scroll_keys.retain(|&k| k != key);
+    }
+
+    /// Advances the camera's movement by `dt` seconds.
+    pub fn update(&mut self, dt: f64) {
+        let mut velocity = Vector3::new(0.0, 0.0, 0.0);
+        for direction in &self.moving {
+            velocity += direction.to_vector().cast::<f64>();
+        }
+
+        let mut speed = self.movement_speed;
+        if !self.fast_scroll_keys.is_empty() {
+            speed *= self.fast_scroll_multiplier;
+        }
+
+        self.position += velocity * speed * dt;
     }
 }
 
 impl Default for Camera {
     fn default() -> Self {
-        Camera {
-            movement_speed: Vector3::new(1, 1, 1),
-            position: Point3::origin(),
-        }
+        Camera::new(1.0, 1.0, Point3::origin())
     }
 }
 
 pub fn new_move_camera_command<'a>(direction: &'a Direction, camera: &'a mut Camera) -> Command<'a> {
-    Box::new(move || { camera.move_in_direction(direction) })
+    Box::new(move || { camera.start_moving(direction) })
 }
@@ -0,0 +1,15 @@
+/// A change to the world asked for by the player.
+///
+/// Every change the player makes to the world goes through a command, so
+/// that it can be recorded and carried out again when the game is replayed.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum PlayerCommand {
+    /// Paints a designation onto the tile at a position.
+    Designate(Designation, [i32; 3]),
+    /// Drafts a colonist, or releases it if it is drafted.
+    ToggleDraft(Entity),
+    /// Orders a drafted colonist to move to a position.
+    OrderMove(Entity, [i32; 3]),
+    /// Replaces the tile at a position with one of a material.
+    SetMaterial([i32; 3], MaterialId),
+}
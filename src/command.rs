@@ -0,0 +1,57 @@
+use cgmath::Point3;
+use world::{Entity, MaterialId, Tile, World, MAX_FLUID_LEVEL};
+
+use designation::Designation;
+
+#[cfg(feature = "nightly")]
+include!("command.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/command.rs"));
+
+impl PlayerCommand {
+    /// Returns whether the command changes the world outright rather than
+    /// through the colonists, which the players who joined a hosted game may
+    /// only do if the host allows cheats.
+    pub fn is_cheat(&self) -> bool {
+        match *self {
+            PlayerCommand::SetMaterial(..) => true,
+            PlayerCommand::Designate(..) | PlayerCommand::ToggleDraft(_) | PlayerCommand::OrderMove(..) => false,
+        }
+    }
+
+    /// Carries out the command on `world`, returning whether it had any
+    /// effect.
+    pub fn apply(&self, world: &mut World) -> bool {
+        match *self {
+            PlayerCommand::Designate(designation, pos) => {
+                let pos = to_point(pos);
+                match designation {
+                    Designation::Dig => world.designate_dig(pos),
+                    Designation::DigStairs => world.designate_dig_stairs(pos),
+                    Designation::Stockpile => world.add_stockpile(pos),
+                    Designation::Build(structure) => world.place_blueprint(structure, pos),
+                    Designation::Hunt => world.designate_hunt(pos),
+                }
+            },
+            PlayerCommand::ToggleDraft(colonist) => {
+                world.toggle_draft(colonist);
+                true
+            },
+            PlayerCommand::OrderMove(colonist, pos) => world.order_move(colonist, to_point(pos)),
+            PlayerCommand::SetMaterial(pos, material) => {
+                // Liquids are placed as a full tile of fluid.
+                let tile = if world.materials().is_liquid(material) {
+                    Tile::with_fluid_level(material, MAX_FLUID_LEVEL)
+                } else {
+                    Tile::new(material)
+                };
+                world.area.set_tile(&to_point(pos), tile)
+            },
+        }
+    }
+}
+
+fn to_point(pos: [i32; 3]) -> Point3<i32> {
+    Point3::new(pos[0], pos[1], pos[2])
+}
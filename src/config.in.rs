@@ -1,50 +1,70 @@
 #[derive(Deserialize, Serialize)]
 pub struct Config {
-    /// Localization language
-    pub language: String,
     /// Directory in which game assets are located
     pub asset_path: String,
-    /// Path to the main font file, relative to the fonts directory.
-    pub font_file: String,
-    /// Height of the window
-    pub window_height: u32,
-    /// Width of the window
-    pub window_width: u32,
+    /// Directory from which mods are loaded. Each mod is a directory within
+    /// it, and they are loaded in the order listed by its `load_order.json`.
+    pub mods_path: String,
+    /// Name of the main font in the asset manifest.
+    pub font: String,
     /// Number of updates per second.
     /// This is the fixed update rate on average over time. If the event loop
     /// lags, it will try to catch up.
     pub ups: u64,
-    /// Maximum number of frames per second.
-    /// The frame rate can be lower because the next frame is always scheduled
-    /// from the previous frame. This causes the frames to "slip" over time.
-    pub max_fps: u64,
-    /// Terminate the program upon recieving the ESC key
+    /// Terminate the program upon recieving the ESC key, rather than pausing
+    /// the game
     pub exit_on_esc: bool,
-    /// Initialize the window in fullscreen mode
-    pub fullscreen: bool,
-    /// Enable vsync
-    pub vsync: bool,
     /// Radius (in chunks) of the initially generated world
     pub initial_world_size: u32,
-    /// Font size for all rendered text
+    /// Radius (in chunks) around the camera within which chunks are generated
+    /// in the background
+    pub chunk_load_radius: u32,
+    /// Memory (in mebibytes) which loaded chunks may use before the least
+    /// recently used chunks out of range of the camera are unloaded
+    pub chunk_memory_budget: u32,
+    /// Font size for all rendered text, before scaling by the UI scale setting
     pub font_size: u32,
-    /// Key bindings for the main game scene
-    pub game_scene_key_bindings: BindingsHashMap<RustcSerializeWrapper<Key>, Action>,
+    /// Speed at which the camera scrolls, in tiles per second
+    pub camera_movement_speed: f64,
+    /// Multiplier applied to the camera speed while Shift is held
+    pub camera_fast_scroll_multiplier: f64,
+    /// Number of in-game days between the frames captured for a timelapse, or
+    /// 0 to capture none
+    pub timelapse_interval: u64,
+    /// Number of snapshots of the world kept to rewind it to, or 0 to keep
+    /// none
+    pub rewind_snapshots: u32,
+    /// Number of simulation steps between the snapshots kept for rewinding
+    pub rewind_interval: u64,
+    /// Number of seconds between the saves made by the dedicated server, or
+    /// 0 to save only when it shuts down
+    pub autosave_interval: u64,
+    /// Allow the players who joined a hosted game to use the commands which
+    /// change the world outright, such as `set_material`, which are
+    /// otherwise left to the host
+    pub allow_cheats: bool,
+    /// Parameters controlling the shape of the generated terrain, which new
+    /// worlds start out with before being set up
+    pub world_gen: WorldGenConfig,
 }
 
 #[derive(Deserialize, Serialize)]
 struct ParsedConfig {
-    language: Option<String>,
     asset_path: Option<String>,
-    font_file: Option<String>,
-    window_height: Option<u32>,
-    window_width: Option<u32>,
+    mods_path: Option<String>,
+    font: Option<String>,
     ups: Option<u64>,
-    max_fps: Option<u64>,
     exit_on_esc: Option<bool>,
-    fullscreen: Option<bool>,
-    vsync: Option<bool>,
     initial_world_size: Option<u32>,
+    chunk_load_radius: Option<u32>,
+    chunk_memory_budget: Option<u32>,
     font_size: Option<u32>,
-    game_scene_key_bindings: Option<BindingsHashMap<RustcSerializeWrapper<Key>, Action>>,
+    camera_movement_speed: Option<f64>,
+    camera_fast_scroll_multiplier: Option<f64>,
+    timelapse_interval: Option<u64>,
+    rewind_snapshots: Option<u32>,
+    rewind_interval: Option<u64>,
+    autosave_interval: Option<u64>,
+    allow_cheats: Option<bool>,
+    world_gen: Option<WorldGenConfig>,
 }
@@ -1,13 +1,7 @@
-use piston::input::keyboard::Key;
-use rgframework::{
-    BindingsHashMap,
-    BindingStore,
-    RustcSerializeWrapper,
-};
+use serde_json;
+use world::WorldGenConfig;
 
-use action::Action;
-use camera::CameraAction;
-use world::Direction;
+use error::ColonizeResult;
 
 #[cfg(feature = "nightly")]
 include!("config.in.rs");
@@ -15,26 +9,33 @@ include!("config.in.rs");
 #[cfg(feature = "with-syntex")]
 include!(concat!(env!("OUT_DIR"), "/config.rs"));
 
-create_type_parsing_impls! {
+create_type_filling_impls! {
     Config,
     ParsedConfig,
-    language, "en_CA".to_owned();
     asset_path, "./assets/".to_owned();
-    font_file, "NotoSans/NotoSans-Regular.ttf".to_owned();
-    window_height, 800;
-    window_width, 800;
+    mods_path, "./mods/".to_owned();
+    font, "default".to_owned();
     ups, 180;
-    max_fps, 10_000;
-    exit_on_esc, true;
-    fullscreen, false;
-    vsync, false;
+    exit_on_esc, false;
     initial_world_size, 3;
+    chunk_load_radius, 4;
+    chunk_memory_budget, 64;
     font_size, 16;
-    game_scene_key_bindings, BindingsHashMap::new()
-            .add_binding(RustcSerializeWrapper::new(Key::Down), Action::Camera(CameraAction::Move(Direction::South)))
-            .add_binding(RustcSerializeWrapper::new(Key::Comma), Action::Camera(CameraAction::Move(Direction::Down)))
-            .add_binding(RustcSerializeWrapper::new(Key::Up), Action::Camera(CameraAction::Move(Direction::North)))
-            .add_binding(RustcSerializeWrapper::new(Key::Left), Action::Camera(CameraAction::Move(Direction::West)))
-            .add_binding(RustcSerializeWrapper::new(Key::Right), Action::Camera(CameraAction::Move(Direction::East)))
-            .add_binding(RustcSerializeWrapper::new(Key::Period), Action::Camera(CameraAction::Move(Direction::Up)));
+    camera_movement_speed, 20.0;
+    camera_fast_scroll_multiplier, 4.0;
+    timelapse_interval, 0;
+    rewind_snapshots, 60;
+    rewind_interval, 10;
+    autosave_interval, 300;
+    allow_cheats, false;
+    world_gen, WorldGenConfig::default();
+}
+
+impl Config {
+    /// Parses the configuration from JSON, falling back to the defaults for
+    /// any values which are missing.
+    pub fn from_json(json: &str) -> ColonizeResult<Config> {
+        let parsed = try!(serde_json::from_str(json));
+        Ok(Config::default().fill_from_parsed(parsed))
+    }
 }
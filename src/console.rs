@@ -0,0 +1,300 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use cgmath::Point3;
+use graphics::color;
+use rgframework::Label;
+use rgframework::backend::{Backend, Graphics};
+use rgframework::backend::graphics::Context;
+use rgframework::draw::Draw;
+
+const BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.8];
+const LINE_SPACING: f64 = 1.5;
+const MAX_OUTPUT_LINES: usize = 12;
+const PROMPT: &'static str = "> ";
+
+/// A command which can be entered into the developer console.
+pub enum ConsoleCommand {
+    /// Regenerates the world with the specified seed.
+    Seed(u32),
+    /// Regenerates the world with its current seed.
+    Regen,
+    /// Moves the camera to the specified position.
+    Teleport(Point3<i32>),
+    /// Spawns the specified number of the named item at the camera.
+    Give(String, u32),
+    /// Saves the game, optionally under the specified name.
+    Save(Option<String>),
+    /// Loads the game, optionally from the specified name.
+    Load(Option<String>),
+    /// Replaces the tile at the specified position with the named material.
+    SetMaterial(Point3<i32>, String),
+    /// Designates the tile at the specified position to be dug out.
+    Dig(Point3<i32>),
+    /// Saves the game and records the commands carried out from then on
+    /// under the specified name.
+    Record(String),
+    /// Plays back the recording made under the specified name.
+    Replay(String),
+    /// Stops recording or playing back.
+    Stop,
+    /// Copies the recording made under the specified name, along with the
+    /// save it starts from, into the specified directory.
+    Export(String, String),
+    /// Writes a listing of the recording made under the specified name to
+    /// the specified file.
+    Dump(String, String),
+    /// Reads the listing in the specified file back into the recording made
+    /// under the specified name.
+    Import(String, String),
+    /// Hosts the game over the network, optionally on the specified port.
+    Host(Option<u16>),
+    /// Joins the game hosted at the specified address.
+    Join(String),
+    /// Leaves the game hosted or joined over the network.
+    Leave,
+    /// Lists the available commands.
+    Help,
+}
+
+/// An error encountered while parsing a console command.
+pub enum ConsoleError {
+    /// No command was entered.
+    Empty,
+    /// The command name was not recognized. The arguments it was entered with
+    /// are kept, for the command to be passed on to mods.
+    UnknownCommand(String, Vec<String>),
+    /// The arguments did not match those expected by the command, whose usage
+    /// is provided.
+    InvalidArguments(&'static str),
+}
+
+/// Usage strings for every available command.
+pub const USAGE: &'static [&'static str] = &[
+    "seed <n>",
+    "regen",
+    "tp <x> <y> <z>",
+    "give <item> [count]",
+    "save [name]",
+    "load [name]",
+    "set_material <x> <y> <z> <material>",
+    "dig <x> <y> <z>",
+    "record <name>",
+    "replay <name>",
+    "stop",
+    "export <recording> <directory>",
+    "dump <recording> <file>",
+    "import <file> <recording>",
+    "host [port]",
+    "join <address>",
+    "leave",
+    "help",
+];
+
+impl ConsoleCommand {
+    pub fn parse(line: &str) -> Result<ConsoleCommand, ConsoleError> {
+        let mut words = line.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return Err(ConsoleError::Empty),
+        };
+        let args: Vec<&str> = words.collect();
+
+        let command = match name {
+            "seed" => match args.len() {
+                1 => parse_arg(args[0]).map(ConsoleCommand::Seed),
+                _ => None,
+            },
+            "regen" if args.is_empty() => Some(ConsoleCommand::Regen),
+            "tp" => match args.len() {
+                3 => parse_point(&args).map(ConsoleCommand::Teleport),
+                _ => None,
+            },
+            "give" => match args.len() {
+                1 => Some(ConsoleCommand::Give(args[0].to_owned(), 1)),
+                2 => parse_arg(args[1]).map(|count| ConsoleCommand::Give(args[0].to_owned(), count)),
+                _ => None,
+            },
+            "save" if args.len() <= 1 => Some(ConsoleCommand::Save(args.first().map(|s| (*s).to_owned()))),
+            "load" if args.len() <= 1 => Some(ConsoleCommand::Load(args.first().map(|s| (*s).to_owned()))),
+            "set_material" => match args.len() {
+                4 => parse_point(&args[..3]).map(|p| ConsoleCommand::SetMaterial(p, args[3].to_owned())),
+                _ => None,
+            },
+            "dig" => match args.len() {
+                3 => parse_point(&args).map(ConsoleCommand::Dig),
+                _ => None,
+            },
+            "record" if args.len() == 1 => Some(ConsoleCommand::Record(args[0].to_owned())),
+            "replay" if args.len() == 1 => Some(ConsoleCommand::Replay(args[0].to_owned())),
+            "stop" if args.is_empty() => Some(ConsoleCommand::Stop),
+            "export" if args.len() == 2 => Some(ConsoleCommand::Export(args[0].to_owned(), args[1].to_owned())),
+            "dump" if args.len() == 2 => Some(ConsoleCommand::Dump(args[0].to_owned(), args[1].to_owned())),
+            "import" if args.len() == 2 => Some(ConsoleCommand::Import(args[0].to_owned(), args[1].to_owned())),
+            "host" => match args.len() {
+                0 => Some(ConsoleCommand::Host(None)),
+                1 => parse_arg(args[0]).map(|port| ConsoleCommand::Host(Some(port))),
+                _ => None,
+            },
+            "join" if args.len() == 1 => Some(ConsoleCommand::Join(args[0].to_owned())),
+            "leave" if args.is_empty() => Some(ConsoleCommand::Leave),
+            "help" if args.is_empty() => Some(ConsoleCommand::Help),
+            _ => match usage(name) {
+                Some(_) => None,
+                None => {
+                    let args = args.iter().map(|arg| (*arg).to_owned()).collect();
+                    return Err(ConsoleError::UnknownCommand(name.to_owned(), args));
+                },
+            },
+        };
+
+        match command {
+            Some(command) => Ok(command),
+            None => Err(ConsoleError::InvalidArguments(usage(name).unwrap_or(""))),
+        }
+    }
+}
+
+fn usage(name: &str) -> Option<&'static str> {
+    USAGE.iter()
+        .find(|usage| usage.split_whitespace().next() == Some(name))
+        .cloned()
+}
+
+fn parse_arg<T>(arg: &str) -> Option<T>
+    where T: FromStr,
+{
+    arg.parse().ok()
+}
+
+fn parse_point(args: &[&str]) -> Option<Point3<i32>> {
+    match (parse_arg(args[0]), parse_arg(args[1]), parse_arg(args[2])) {
+        (Some(x), Some(y), Some(z)) => Some(Point3::new(x, y, z)),
+        _ => None,
+    }
+}
+
+/// A drop-down console for entering developer commands.
+pub struct Console {
+    visible: bool,
+    input: String,
+    output: VecDeque<String>,
+    font_size: u32,
+    labels: Vec<Label>,
+}
+
+impl Console {
+    pub fn new(font_size: u32) -> Self {
+        let mut console = Console {
+            visible: false,
+            input: String::new(),
+            output: VecDeque::new(),
+            font_size: font_size,
+            labels: Vec::new(),
+        };
+        console.refresh_labels();
+        console
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn show(&mut self) {
+        self.visible = true;
+    }
+
+    /// Returns the lines shown while the console is visible, the input line
+    /// last.
+    pub fn lines(&self) -> Vec<&str> {
+        self.labels.iter().map(|label| label.text()).collect()
+    }
+
+    /// Draws the text of the console at `font_size` from now on.
+    pub fn set_font_size(&mut self, font_size: u32) {
+        self.font_size = font_size;
+        self.refresh_labels();
+    }
+
+    /// Appends typed text to the current input line.
+    pub fn type_text(&mut self, text: &str) {
+        // The key used to toggle the console should not end up in the input.
+        self.input.extend(text.chars().filter(|c| !c.is_control() && *c != '`' && *c != '~'));
+        self.refresh_labels();
+    }
+
+    /// Removes the last character of the current input line.
+    pub fn backspace(&mut self) {
+        self.input.pop();
+        self.refresh_labels();
+    }
+
+    /// Clears the input line and parses it as a command, echoing it to the
+    /// output. Returns `None` if nothing was entered.
+    pub fn submit(&mut self) -> Option<Result<ConsoleCommand, ConsoleError>> {
+        let line = ::std::mem::replace(&mut self.input, String::new());
+        if line.trim().is_empty() {
+            self.refresh_labels();
+            return None;
+        }
+
+        self.print(format!("{}{}", PROMPT, line));
+        Some(ConsoleCommand::parse(&line))
+    }
+
+    /// Appends a line to the console output.
+    pub fn print<S>(&mut self, line: S)
+        where S: Into<String>,
+    {
+        self.output.push_back(line.into());
+        while self.output.len() > MAX_OUTPUT_LINES {
+            self.output.pop_front();
+        }
+        self.refresh_labels();
+    }
+
+    fn line_height(&self) -> f64 {
+        self.font_size as f64 * LINE_SPACING
+    }
+
+    fn refresh_labels(&mut self) {
+        let line_height = self.line_height();
+        let input_line = format!("{}{}_", PROMPT, self.input);
+        let lines = self.output.iter().cloned().chain(Some(input_line));
+
+        self.labels.clear();
+        for (i, line) in lines.enumerate() {
+            let y = (i + 1) as f64 * line_height;
+            self.labels.push(Label::new(line, color::WHITE, self.font_size, [10.0, y]));
+        }
+    }
+}
+
+impl<B, G> Draw<B, G> for Console
+    where B: Backend,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn draw(&self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        use graphics::Rectangle;
+
+        if !self.visible {
+            return;
+        }
+
+        let width = context.get_view_size()[0];
+        let height = (MAX_OUTPUT_LINES + 1) as f64 * self.line_height() + self.line_height() / 2.0;
+        Rectangle::new(BACKGROUND_COLOR).draw(
+            [0.0, 0.0, width, height],
+            &context.draw_state,
+            context.transform,
+            graphics);
+
+        for label in &self.labels {
+            Draw::<B, G>::draw(label, context, graphics, glyph_cache);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::panic::{self, PanicInfo};
+use std::path::PathBuf;
+use std::sync::{Mutex, TryLockError};
+use std::thread;
+
+use backtrace::Backtrace;
+use serde_json;
+use world::{self, ChunkPos, RegionStore, World};
+
+use paths;
+use save::SaveMetadata;
+
+/// Prefix of the names of the directories crash reports are written to.
+const CRASH_DIR_PREFIX: &'static str = "crash-";
+const REPORT_FILE: &'static str = "report.txt";
+/// The world recorded for the report, in the format of the metadata of saved
+/// games.
+const WORLD_FILE: &'static str = "world.json";
+/// The recording being made when the world was recorded, as far as it got.
+const RECORDING_FILE: &'static str = "recording.json";
+
+/// The game recorded by `record_world` for the crash report, as it was when
+/// last recorded.
+struct RecordedGame {
+    seed: u32,
+    /// The serialized metadata of the world.
+    world: String,
+    /// The directories of the region stores holding the chunks of the world
+    /// which were not loaded, those taking precedence last.
+    stores: Vec<PathBuf>,
+    /// The encoded chunks which were loaded and modified since the world was
+    /// last saved.
+    chunks: Vec<(ChunkPos, Vec<u8>)>,
+    /// The name of the recording being made, and the recording so far.
+    recording: Option<(String, String)>,
+}
+
+lazy_static! {
+    /// The game last recorded by any thread, which is written into the report
+    /// whichever thread panics.
+    static ref RECORDED_GAME: Mutex<Option<RecordedGame>> = Mutex::new(None);
+}
+
+/// Installs a panic hook which, after reporting the panic as usual, writes a
+/// crash report into a new `crash-<timestamp>/` directory.
+///
+/// The report holds the panic message and where it happened, a backtrace,
+/// and the game last recorded: its seed, the state and chunks of its world,
+/// which together make up a save from which the game can be loaded as it was
+/// shortly before the crash, and the recording being made, if any.
+pub fn install() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        match write_report(info) {
+            Ok(path) => error!("A crash report was written to {}", path.display()),
+            Err(e) => error!("Failed to write crash report: {}", e),
+        }
+    }));
+}
+
+/// Records `world`, described by `metadata`, along with the recording being
+/// made of it as its name and serialized contents, to be written into the
+/// crash report if the game panics, replacing any game recorded before it.
+pub fn record_world(metadata: &SaveMetadata, world: &World, recording: Option<(String, String)>) {
+    let json = match serde_json::to_string_pretty(metadata) {
+        Ok(json) => json,
+        Err(_) => return,
+    };
+    let chunks = world.area.loaded_chunks()
+        .filter(|&(_, chunk)| chunk.is_dirty())
+        .map(|(pos, chunk)| (*pos, world::encode_chunk(chunk)))
+        .collect();
+    let recorded = RecordedGame {
+        seed: metadata.seed,
+        world: json,
+        stores: world.area.store_directories().into_iter().map(|path| path.to_path_buf()).collect(),
+        chunks: chunks,
+        recording: recording,
+    };
+    if let Ok(mut game) = RECORDED_GAME.lock() {
+        *game = Some(recorded);
+    }
+}
+
+/// Forgets the game recorded, once it is no longer played.
+pub fn clear_world() {
+    if let Ok(mut game) = RECORDED_GAME.lock() {
+        *game = None;
+    }
+}
+
+fn write_report(info: &PanicInfo) -> io::Result<PathBuf> {
+    let dir = paths::data_dir().join(format!("{}{}", CRASH_DIR_PREFIX, paths::timestamp()));
+    try!(fs::create_dir_all(&dir));
+
+    let message = match info.payload().downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match info.payload().downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "unknown panic".to_owned(),
+        },
+    };
+    let location = info.location()
+        .map_or("unknown location".to_owned(), |location| format!("{}:{}", location.file(), location.line()));
+    // The lock is held only briefly, but a thread panicking while recording
+    // would wait on itself, so the game is left out if it is held.
+    let recorded = match RECORDED_GAME.try_lock() {
+        Ok(mut game) => game.take(),
+        Err(TryLockError::Poisoned(e)) => e.into_inner().take(),
+        Err(TryLockError::WouldBlock) => None,
+    };
+
+    let mut report = try!(File::create(dir.join(REPORT_FILE)));
+    try!(writeln!(report, "Colonize {}", env!("CARGO_PKG_VERSION")));
+    try!(writeln!(report, "Thread '{}' panicked at '{}', {}", thread::current().name().unwrap_or("<unnamed>"), message, location));
+    match recorded {
+        Some(ref game) => {
+            try!(writeln!(report, "Seed: {}", game.seed));
+            try!(writeln!(report, "Modified chunks: {}", game.chunks.len()));
+            match game.recording {
+                Some((ref name, _)) => try!(writeln!(report, "Recording: {}", name)),
+                None => try!(writeln!(report, "No recording was being made")),
+            }
+        },
+        None => try!(writeln!(report, "No world was recorded")),
+    }
+    try!(writeln!(report, "\n{:?}", Backtrace::new()));
+
+    if let Some(game) = recorded {
+        try!(write_game(&dir, game));
+    }
+
+    Ok(dir)
+}
+
+/// Writes the world of `game` into `dir` as a save would be, along with the
+/// recording being made of it.
+fn write_game(dir: &PathBuf, game: RecordedGame) -> io::Result<()> {
+    let mut file = try!(File::create(dir.join(WORLD_FILE)));
+    try!(file.write_all(game.world.as_bytes()));
+
+    // The chunks are layered as the game loads them: those of the save, then
+    // those unloaded since it was made, then those still loaded.
+    let store = try!(RegionStore::new(dir));
+    for (i, directory) in game.stores.iter().enumerate() {
+        let source = try!(RegionStore::new(directory));
+        if i == 0 {
+            try!(source.copy_to(&store));
+        } else {
+            try!(source.merge_into(&store));
+        }
+    }
+    let mut chunks = Vec::new();
+    for (pos, data) in game.chunks {
+        chunks.push((pos, try!(world::decode_chunk(&data))));
+    }
+    let chunks: Vec<_> = chunks.iter().map(|&(pos, ref chunk)| (pos, chunk)).collect();
+    try!(store.save_chunks(&chunks));
+
+    if let Some((_, json)) = game.recording {
+        let mut file = try!(File::create(dir.join(RECORDING_FILE)));
+        try!(file.write_all(json.as_bytes()));
+    }
+    Ok(())
+}
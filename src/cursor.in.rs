@@ -0,0 +1,15 @@
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum CursorAction {
+    /// Shows the cursor at the center of the view, or hides it.
+    Toggle,
+    /// Moves the cursor by the specified number of tiles along the X and Z
+    /// axes.
+    Move(i32, i32),
+    /// Acts on the tile under the cursor like a left click: starts or stops
+    /// painting the selected designation, or selects the colonist standing
+    /// there.
+    Select,
+    /// Acts on the tile under the cursor like a right click: orders the
+    /// selected colonist there, or cancels the designation or selection.
+    Order,
+}
@@ -0,0 +1,50 @@
+use cgmath::Point2;
+
+#[cfg(feature = "nightly")]
+include!("cursor.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/cursor.rs"));
+
+/// A tile highlighted in the view and moved with the keyboard, which stands in
+/// for the mouse cursor so that the game can be played without a mouse.
+pub struct Cursor {
+    /// The position of the cursor along the X and Z axes, if it is shown. The
+    /// cursor always lies on the z-level of the camera.
+    position: Option<Point2<i32>>,
+}
+
+impl Cursor {
+    pub fn new() -> Self {
+        Cursor {
+            position: None,
+        }
+    }
+
+    pub fn position(&self) -> Option<Point2<i32>> {
+        self.position
+    }
+
+    /// Shows the cursor at `position`, or hides it if it is shown.
+    pub fn toggle(&mut self, position: Point2<i32>) {
+        self.position = match self.position {
+            Some(_) => None,
+            None => Some(position),
+        };
+    }
+
+    /// Moves the cursor by the specified number of tiles along the X and Z
+    /// axes, if it is shown.
+    pub fn move_by(&mut self, dx: i32, dz: i32) {
+        if let Some(ref mut position) = self.position {
+            position.x += dx;
+            position.y += dz;
+        }
+    }
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Cursor::new()
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use graphics::color;
+use rgframework::{List, Panel, Widget};
+use rgframework::backend::{Backend, Graphics};
+use rgframework::backend::graphics::Context;
+use rgframework::draw::Draw;
+
+const BACKGROUND_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.75];
+const BACKGROUND_WIDTH: f64 = 420.0;
+const PADDING: f64 = 10.0;
+
+/// Time spent in each phase of a single frame.
+#[derive(Clone, Copy, Default)]
+pub struct FrameTimings {
+    pub events: Duration,
+    pub update: Duration,
+    pub render: Duration,
+}
+
+/// An on-screen overlay displaying lines of debugging information.
+pub struct DebugOverlay {
+    visible: bool,
+    panel: Panel<()>,
+    /// The index of the list of lines within `panel`.
+    lines: usize,
+}
+
+impl DebugOverlay {
+    pub fn new(font_size: u32) -> Self {
+        let mut panel = Panel::new([0.0, 0.0], BACKGROUND_WIDTH, PADDING, 0.0, BACKGROUND_COLOR);
+        let lines = panel.push(Widget::List(List::new(color::BLACK, font_size)));
+        DebugOverlay {
+            visible: false,
+            panel: panel,
+            lines: lines,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Replaces the lines of text displayed by the overlay.
+    pub fn set_lines(&mut self, lines: Vec<String>) {
+        self.panel.update_widget(self.lines, |widget| {
+            if let Widget::List(ref mut list) = *widget {
+                list.set_lines(lines);
+            }
+        });
+    }
+}
+
+impl<B, G> Draw<B, G> for DebugOverlay
+    where B: Backend,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn draw(&self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        if !self.visible {
+            return;
+        }
+
+        Draw::<B, G>::draw(&self.panel, context, graphics, glyph_cache);
+    }
+}
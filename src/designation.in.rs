@@ -0,0 +1,15 @@
+/// What painting tiles with the mouse marks them for.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Designation {
+    /// Queues the tiles to be dug out.
+    Dig,
+    /// Queues stairs to be dug out of the tiles, or out of the ground beneath
+    /// them if they are open.
+    DigStairs,
+    /// Adds the tiles to the stockpiles.
+    Stockpile,
+    /// Places blueprints for a structure on the tiles.
+    Build(Structure),
+    /// Queues the animals standing on the tiles to be hunted.
+    Hunt,
+}
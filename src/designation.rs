@@ -0,0 +1,114 @@
+use rgframework::Command;
+use world::Structure;
+
+#[cfg(feature = "nightly")]
+include!("designation.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/designation.rs"));
+
+/// Which designations are drawn over the world.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OverlayVisibility {
+    All,
+    /// Only the designations of the kind selected for painting, so that they
+    /// stand out while more are painted.
+    Active,
+    Hidden,
+}
+
+impl OverlayVisibility {
+    /// Returns the visibility following this one, in the order in which they
+    /// are cycled through.
+    fn next(self) -> OverlayVisibility {
+        match self {
+            OverlayVisibility::All => OverlayVisibility::Active,
+            OverlayVisibility::Active => OverlayVisibility::Hidden,
+            OverlayVisibility::Hidden => OverlayVisibility::All,
+        }
+    }
+}
+
+/// Tracks the designation selected for painting with the mouse, whether it
+/// is currently being painted, and which designations are drawn.
+pub struct Designator {
+    selected: Option<Designation>,
+    painting: bool,
+    overlays: OverlayVisibility,
+}
+
+impl Designator {
+    pub fn new() -> Self {
+        Designator {
+            selected: None,
+            painting: false,
+            overlays: OverlayVisibility::All,
+        }
+    }
+
+    pub fn selected(&self) -> Option<Designation> {
+        self.selected
+    }
+
+    /// Selects the specified designation, or deselects it if it is already
+    /// selected.
+    pub fn toggle(&mut self, designation: Designation) {
+        self.selected = if self.selected == Some(designation) {
+            None
+        } else {
+            Some(designation)
+        };
+        self.painting = false;
+    }
+
+    /// Deselects the current designation.
+    pub fn cancel(&mut self) {
+        self.selected = None;
+        self.painting = false;
+    }
+
+    /// Returns the designation being painted, if any.
+    pub fn painting(&self) -> Option<Designation> {
+        if self.painting {
+            self.selected
+        } else {
+            None
+        }
+    }
+
+    pub fn set_painting(&mut self, painting: bool) {
+        self.painting = painting && self.selected.is_some();
+    }
+
+    pub fn overlays(&self) -> OverlayVisibility {
+        self.overlays
+    }
+
+    /// Switches to the next of the visibilities of the designations drawn.
+    pub fn cycle_overlays(&mut self) {
+        self.overlays = self.overlays.next();
+    }
+
+    /// Returns true if the overlays of `designation` are to be drawn.
+    pub fn shows(&self, designation: Designation) -> bool {
+        match self.overlays {
+            OverlayVisibility::All => true,
+            OverlayVisibility::Active => self.selected == Some(designation),
+            OverlayVisibility::Hidden => false,
+        }
+    }
+}
+
+impl Default for Designator {
+    fn default() -> Self {
+        Designator::new()
+    }
+}
+
+pub fn new_designate_command<'a>(designation: &'a Designation, designator: &'a mut Designator) -> Command<'a> {
+    Box::new(move || { designator.toggle(*designation) })
+}
+
+pub fn new_cycle_overlays_command(designator: &mut Designator) -> Command {
+    Box::new(move || { designator.cycle_overlays() })
+}
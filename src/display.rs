@@ -0,0 +1,44 @@
+use sdl2;
+
+/// The DPI of the displays the interface was laid out for, at which it is
+/// drawn unscaled.
+const BASE_DPI: f32 = 96.0;
+/// The steps to which the detected scale is rounded, so that text is drawn
+/// at sizes which look alike from one display to the next.
+const SCALE_STEP: f64 = 0.25;
+/// The range within which the detected scale is kept. Displays of a lower
+/// DPI than `BASE_DPI` are not scaled down, since the interface is small
+/// enough as it is.
+const MIN_SCALE: f64 = 1.0;
+const MAX_SCALE: f64 = 4.0;
+
+lazy_static! {
+    static ref DETECTED_UI_SCALE: f64 = detect_ui_scale();
+}
+
+/// Returns the factor by which to scale the interface for the DPI of the
+/// primary display, or 1 if it cannot be detected.
+///
+/// The DPI is only queried the first time, which must happen before SDL is
+/// initialized for audio, since SDL can only be initialized once at a time.
+pub fn detected_ui_scale() -> f64 {
+    *DETECTED_UI_SCALE
+}
+
+fn detect_ui_scale() -> f64 {
+    let dpi = sdl2::init()
+        .and_then(|sdl| sdl.video())
+        .and_then(|video| video.display_dpi(0));
+    match dpi {
+        Ok((diagonal_dpi, _, _)) => {
+            let scale = ((diagonal_dpi / BASE_DPI) as f64 / SCALE_STEP).round() * SCALE_STEP;
+            let scale = scale.max(MIN_SCALE).min(MAX_SCALE);
+            info!("Detected a display of {:.0} DPI, scaling the interface by {}", diagonal_dpi, scale);
+            scale
+        },
+        Err(e) => {
+            warn!("Failed to detect the DPI of the display: {}", e);
+            1.0
+        },
+    }
+}
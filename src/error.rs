@@ -0,0 +1,112 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use serde_json;
+use world::MaterialError;
+
+pub type ColonizeResult<T> = Result<T, ColonizeError>;
+
+/// An error encountered while running the game.
+#[derive(Debug)]
+pub enum ColonizeError {
+    /// A file could not be read or written.
+    Io(io::Error),
+    /// A JSON file could not be parsed or written.
+    Json(serde_json::Error),
+    /// The material definitions are invalid.
+    Material(MaterialError),
+    /// The font could not be loaded.
+    Font(String),
+    /// A texture could not be loaded.
+    Texture(String),
+    /// The window could not be created.
+    Window(String),
+    /// The terminal could not be set up to draw the game in.
+    Terminal(String),
+    /// The audio device could not be opened.
+    Audio(String),
+    /// The settings file could not be parsed.
+    Settings(String),
+    /// The script of a mod could not be run.
+    Script(String),
+    /// The name of a save or recording would refer to a location outside of
+    /// the directory it is kept in.
+    InvalidSaveName(String),
+    /// A save could not be renamed, as another save already has the name.
+    SaveExists(String),
+    /// A save was written by a newer version of the game, whose version of
+    /// the save format is given.
+    SaveVersion(u64),
+    /// The metadata of a save could not be upgraded to the current version.
+    InvalidSave(String),
+}
+
+impl fmt::Display for ColonizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ColonizeError::Io(ref e) => write!(f, "{}", e),
+            ColonizeError::Json(ref e) => write!(f, "{}", e),
+            ColonizeError::Material(ref e) => write!(f, "{}", e),
+            ColonizeError::Font(ref message) |
+            ColonizeError::Texture(ref message) |
+            ColonizeError::Window(ref message) |
+            ColonizeError::Terminal(ref message) |
+            ColonizeError::Audio(ref message) |
+            ColonizeError::Settings(ref message) |
+            ColonizeError::Script(ref message) |
+            ColonizeError::InvalidSave(ref message) => write!(f, "{}", message),
+            ColonizeError::InvalidSaveName(ref name) => write!(f, "invalid save name: {}", name),
+            ColonizeError::SaveExists(ref name) => write!(f, "a save named {} already exists", name),
+            ColonizeError::SaveVersion(version) => write!(f, "save version {} is newer than this game supports", version),
+        }
+    }
+}
+
+impl Error for ColonizeError {
+    fn description(&self) -> &str {
+        match *self {
+            ColonizeError::Io(ref e) => e.description(),
+            ColonizeError::Json(ref e) => e.description(),
+            ColonizeError::Material(ref e) => e.description(),
+            ColonizeError::Font(_) => "failed to load font",
+            ColonizeError::Texture(_) => "failed to load texture",
+            ColonizeError::Window(_) => "failed to build window",
+            ColonizeError::Terminal(_) => "failed to set up terminal",
+            ColonizeError::Audio(_) => "failed to open audio device",
+            ColonizeError::Settings(_) => "failed to parse settings",
+            ColonizeError::Script(_) => "failed to run script",
+            ColonizeError::InvalidSaveName(_) => "invalid save name",
+            ColonizeError::SaveExists(_) => "save already exists",
+            ColonizeError::SaveVersion(_) => "unsupported save version",
+            ColonizeError::InvalidSave(_) => "invalid save",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            ColonizeError::Io(ref e) => Some(e),
+            ColonizeError::Json(ref e) => Some(e),
+            ColonizeError::Material(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ColonizeError {
+    fn from(e: io::Error) -> Self {
+        ColonizeError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ColonizeError {
+    fn from(e: serde_json::Error) -> Self {
+        ColonizeError::Json(e)
+    }
+}
+
+impl From<MaterialError> for ColonizeError {
+    fn from(e: MaterialError) -> Self {
+        ColonizeError::Material(e)
+    }
+}
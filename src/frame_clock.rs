@@ -0,0 +1,34 @@
+use std::time::{Duration, Instant};
+
+/// Measures the time elapsed between successive ticks using the system's
+/// monotonic clock.
+pub struct FrameClock {
+    last_tick: Instant,
+}
+
+impl FrameClock {
+    pub fn new() -> Self {
+        FrameClock {
+            last_tick: Instant::now(),
+        }
+    }
+
+    /// Returns the time elapsed since the last tick (or since the clock was
+    /// created), and starts measuring again from now.
+    pub fn tick(&mut self) -> Duration {
+        let now = Instant::now();
+        let dt = now - self.last_tick;
+        self.last_tick = now;
+        dt
+    }
+}
+
+/// Converts a duration to fractional seconds.
+pub fn duration_to_secs(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1e9
+}
+
+/// Converts a duration to fractional milliseconds.
+pub fn duration_to_millis(duration: Duration) -> f64 {
+    duration_to_secs(duration) * 1e3
+}
@@ -1,5 +1,11 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::cmp;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::iter;
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use fps_counter;
 use opengl_graphics::GlGraphics;
@@ -8,20 +14,45 @@ use piston::event_loop::{
     Events,
     WindowEvents,
 };
-use piston::input::{Event, GenericEvent};
+use piston::input::{Event, GenericEvent, PressEvent, ReleaseEvent, ResizeEvent};
+use piston::input::Button::Keyboard;
+use piston::input::keyboard::Key;
 use piston::window::{
     AdvancedWindow,
+    Size,
     Window,
 };
 use rgframework::{Scene, SceneManager};
 use rgframework::backend::{Backend, Graphics};
-use time;
+use rgframework::draw::Draw;
+use utility::profiler::{self, ScopeTotal};
+use world::MaterialRegistry;
 
-use backend::GlBackend;
+use assets::{Asset, AssetManager};
+use audio::AudioManager;
+use backend::{FullscreenWindow, GlBackend};
 use config::Config;
+use debug_overlay::{DebugOverlay, FrameTimings};
+use error::ColonizeResult;
+use frame_clock::{self, FrameClock};
 use localization::Localization;
-use scene::MenuScene;
-use textures::TextureType;
+use palette::Palette;
+use paths;
+use scene::{GameScene, MenuScene};
+use screenshot;
+use scripting::ScriptHost;
+use settings::Settings;
+use tile_renderer::TileRenderer;
+
+/// The directory traces of the timed scopes are saved to.
+const TRACES_DIR: &'static str = "traces/";
+/// Milliseconds of a frame spent in a timed scope per character of its bar
+/// in the debug overlay, and the most characters of a bar.
+const PROFILE_BAR_MILLIS: f64 = 0.25;
+const MAX_PROFILE_BAR_LENGTH: usize = 60;
+/// The most seconds an update advances the scenes by, so that a long stall,
+/// such as while the window is dragged, is not made up for all at once.
+const MAX_UPDATE_SECS: f64 = 0.25;
 
 pub struct Game<B, E, G, W>
     where B: Backend,
@@ -30,8 +61,34 @@ pub struct Game<B, E, G, W>
           W: AdvancedWindow + Window,
 {
     config: Rc<Config>,
-    localization: Rc<Localization>,
+    settings: Rc<RefCell<Settings>>,
+    /// The frame rate cap currently applied to `events`.
+    max_fps: u64,
+    /// The size of the window before it was made to cover the screen, while
+    /// it does.
+    windowed_size: Option<Size>,
+    /// Whether either Alt key is held down.
+    alt_held: bool,
+    /// The prefix of the name under which to save the next frame as an
+    /// image, if it is to be saved.
+    pending_capture: Option<&'static str>,
+    /// The language the window was last titled in.
+    language: String,
+    localization: Rc<RefCell<Localization>>,
+    assets: AssetManager,
+    /// The tile renderer shared by the scenes, whose atlas is replaced when
+    /// its files are modified.
+    tile_renderer: Rc<TileRenderer<B>>,
     fps_counter: fps_counter::FPSCounter,
+    debug_overlay: DebugOverlay,
+    frame_timings: FrameTimings,
+    /// Measures the time between rendered frames.
+    frame_clock: FrameClock,
+    /// The time between the last two rendered frames.
+    frame_time: Duration,
+    /// Measures the time between updates, by which the scenes, and the
+    /// simulation with them, are advanced.
+    update_clock: FrameClock,
     scene_manager: SceneManager<B, E, G>,
     events: WindowEvents,
     window: W,
@@ -43,33 +100,76 @@ impl<B, E, G, W> Game<B, E, G, W>
           G: Graphics<Texture=B::Texture>,
           W: AdvancedWindow + Window,
 {
-    pub fn new(config: Config, localization: Localization, window: W, textures: HashMap<TextureType, B::Texture>) -> Self {
+    /// Creates the game, starting at the menu, or viewing the replay exported
+    /// to the directory at `replay` if one is given.
+    pub fn new(config: Config, settings: Settings, localization: Localization, palette: Palette, materials: MaterialRegistry, window: W, assets: AssetManager, tile_renderer: TileRenderer<B>, audio: AudioManager, scripts: ScriptHost, replay: Option<PathBuf>) -> Self {
         let config = Rc::new(config);
-        let localization = Rc::new(localization);
-        let textures = Rc::new(textures);
+        let settings = Rc::new(RefCell::new(settings));
+        let localization = Rc::new(RefCell::new(localization));
+        let palette = Rc::new(RefCell::new(palette));
+        let materials = Rc::new(materials);
+        let tile_renderer = Rc::new(tile_renderer);
+        let audio = Rc::new(audio);
+        let scripts = Rc::new(scripts);
 
+        let replay_scene = replay.and_then(|path| {
+            match GameScene::view_replay(config.clone(), settings.clone(), localization.clone(), palette.clone(), materials.clone(), tile_renderer.clone(), audio.clone(), scripts.clone(), &path) {
+                Ok(scene) => Some(scene.to_box()),
+                Err(e) => {
+                    error!("Failed to view replay {}: {}", path.display(), e);
+                    None
+                },
+            }
+        });
         let mut scene_manager = SceneManager::new();
-        scene_manager.push_scene(MenuScene::new(config.clone(), localization.clone(), textures.clone()).to_box());
+        match replay_scene {
+            Some(scene) => scene_manager.push_scene(scene),
+            None => scene_manager.push_scene(MenuScene::new(config.clone(), settings.clone(), localization.clone(), palette, materials, tile_renderer.clone(), audio, scripts).to_box()),
+        }
 
-        let events = window.events().ups(config.ups).max_fps(config.max_fps);
+        let max_fps = settings.borrow().max_fps;
+        let events = window.events().ups(config.ups).max_fps(max_fps);
 
-        Self::new_internal(events, config, localization, scene_manager, window)
+        Self::new_internal(events, config, settings, localization, assets, tile_renderer, scene_manager, window)
     }
 
-    fn new_internal(events: WindowEvents, config: Rc<Config>, localization: Rc<Localization>, scene_manager: SceneManager<B, E, G>, window: W) -> Self {
+    fn new_internal(events: WindowEvents, config: Rc<Config>, settings: Rc<RefCell<Settings>>, localization: Rc<RefCell<Localization>>, assets: AssetManager, tile_renderer: Rc<TileRenderer<B>>, scene_manager: SceneManager<B, E, G>, window: W) -> Self {
+        let (max_fps, language, windowed_size) = {
+            let settings = settings.borrow();
+            let default = Settings::default();
+            let windowed_size = if settings.fullscreen {
+                Some(Size { width: default.window_width, height: default.window_height })
+            } else {
+                None
+            };
+            (settings.max_fps, settings.language.clone(), windowed_size)
+        };
         Game {
             events: events,
             fps_counter: fps_counter::FPSCounter::new(),
+            debug_overlay: DebugOverlay::new(config.font_size),
+            frame_timings: FrameTimings::default(),
+            frame_clock: FrameClock::new(),
+            frame_time: Duration::default(),
+            update_clock: FrameClock::new(),
             scene_manager: scene_manager,
             window: window,
             config: config,
+            settings: settings,
+            max_fps: max_fps,
+            windowed_size: windowed_size,
+            alt_held: false,
+            pending_capture: None,
+            language: language,
             localization: localization,
+            assets: assets,
+            tile_renderer: tile_renderer,
         }
     }
 }
 
 impl<W> Game<GlBackend, Event<W::Event>, GlGraphics, W>
-    where W: AdvancedWindow + Window,
+    where W: AdvancedWindow + FullscreenWindow + Window,
           W::Event: GenericEvent,
 {
     pub fn run(&mut self, gl: &mut GlGraphics, glyph_cache: &mut <GlBackend as Backend>::CharacterCache) {
@@ -78,38 +178,255 @@ impl<W> Game<GlBackend, Event<W::Event>, GlGraphics, W>
 
             match e {
                 Event::Render(args) => {
-                    let start_time = time::precise_time_ns();
-                    if let Some(mut scene) = self.scene_manager.pop_scene() {
-                        gl.draw(args.viewport(), |c, gl| scene.render(&c, gl, glyph_cache));
-                        self.scene_manager.push_scene(scene);
+                    self.frame_time = self.frame_clock.tick();
+
+                    let start_time = Instant::now();
+                    {
+                        let _scope = profiler::scope("render");
+                        let scene_manager = &mut self.scene_manager;
+                        gl.draw(args.viewport(), |c, gl| scene_manager.render(&c, gl, glyph_cache));
+                    }
+                    self.frame_timings.render = start_time.elapsed();
+
+                    // The frame is captured before the debug overlay is drawn
+                    // over it.
+                    if let Some(prefix) = self.pending_capture.take() {
+                        if let Err(e) = screenshot::capture(args.draw_width, args.draw_height, prefix) {
+                            warn!("Failed to save screenshot: {}", e);
+                        }
                     }
-                    let end_time = time::precise_time_ns();
 
                     let fps = self.fps_counter.tick();
-                    let fps_info = format!(
-                        "{}: {:.2}{unit_millisecond} @ {} {unit_fps}",
-                        self.localization.debug_render_info,
-                        (end_time - start_time) as f64 / 1e6,
-                        fps,
-                        unit_millisecond=self.localization.util_unit_millisecond,
-                        unit_fps=self.localization.util_unit_fps,
-                    );
-
-                    gl.draw(args.viewport(), |c, gl| {
-                        use graphics::{Text, Transformed};
-
-                        Text::new(self.config.font_size).draw(
-                            &fps_info,
-                            glyph_cache,
-                            &c.draw_state,
-                            c.transform.trans(10.0, 25.0),
-                            gl);
-                    });
+                    let profile = profiler::end_frame();
+                    if self.debug_overlay.is_visible() {
+                        let mut lines = self.frame_info(fps);
+                        lines.extend(self.profile_info(&profile));
+                        lines.extend(self.scene_manager.debug_info());
+                        self.debug_overlay.set_lines(lines);
+
+                        let debug_overlay = &self.debug_overlay;
+                        gl.draw(args.viewport(), |c, gl| {
+                            Draw::<GlBackend, GlGraphics>::draw(debug_overlay, &c, gl, glyph_cache);
+                        });
+                    }
+                    self.frame_timings = FrameTimings::default();
+                },
+                Event::Update(_) => {
+                    self.reload_assets(glyph_cache);
+
+                    // The scenes are advanced by the time which actually
+                    // passed since the last update, rather than the interval
+                    // the updates are scheduled at, which they fall behind
+                    // whenever a frame runs long. The simulation clock
+                    // accumulates it into steps of its fixed interval.
+                    let dt = frame_clock::duration_to_secs(self.update_clock.tick()).min(MAX_UPDATE_SECS);
+                    let start_time = Instant::now();
+                    let scope = profiler::scope("update");
+                    self.scene_manager.update(dt);
+                    drop(scope);
+                    if self.scene_manager.take_capture_request() {
+                        self.pending_capture = Some(screenshot::TIMELAPSE_PREFIX);
+                    }
+                    self.frame_timings.update += start_time.elapsed();
                 },
                 _ => {
-                    self.scene_manager.handle_event(&e);
+                    let mut toggle_debug_overlay = false;
+                    let mut toggle_trace = false;
+                    let mut toggle_fullscreen = false;
+                    let mut take_screenshot = false;
+                    {
+                        let alt_held = &mut self.alt_held;
+                        e.press(|button_type| {
+                            match button_type {
+                                Keyboard(Key::F3) => toggle_debug_overlay = true,
+                                Keyboard(Key::F6) => toggle_trace = true,
+                                Keyboard(Key::F12) => take_screenshot = true,
+                                Keyboard(Key::LAlt) | Keyboard(Key::RAlt) => *alt_held = true,
+                                Keyboard(Key::Return) if *alt_held => toggle_fullscreen = true,
+                                _ => {},
+                            }
+                        });
+                        e.release(|button_type| {
+                            match button_type {
+                                Keyboard(Key::LAlt) | Keyboard(Key::RAlt) => *alt_held = false,
+                                _ => {},
+                            }
+                        });
+                    }
+                    let mut resized = None;
+                    e.resize(|width, height| resized = Some((width, height)));
+
+                    if toggle_debug_overlay {
+                        self.debug_overlay.toggle();
+                    }
+                    if toggle_trace {
+                        self.toggle_trace();
+                    }
+                    if toggle_debug_overlay || toggle_trace {
+                        // Scopes are only timed while their times are shown
+                        // or traced.
+                        profiler::set_enabled(self.debug_overlay.is_visible() || profiler::is_tracing());
+                    }
+                    if take_screenshot {
+                        self.pending_capture = Some(screenshot::SCREENSHOT_PREFIX);
+                    }
+                    if let Some((width, height)) = resized {
+                        self.resize(width, height);
+                    }
+
+                    // Alt+Enter is kept from the scenes, which may have bound
+                    // Enter to something else.
+                    if toggle_fullscreen {
+                        self.toggle_fullscreen();
+                    } else {
+                        let start_time = Instant::now();
+                        let _scope = profiler::scope("events");
+                        self.scene_manager.handle_event(&e);
+                        self.frame_timings.events += start_time.elapsed();
+                    }
                 }
             }
+
+            // Apply the frame rate cap, in case it was changed from the
+            // options.
+            let max_fps = self.settings.borrow().max_fps;
+            if max_fps != self.max_fps {
+                self.max_fps = max_fps;
+                self.events.set_max_fps(max_fps);
+            }
+
+            // Title the window anew, in case the language was changed from
+            // the options.
+            let language_changed = self.settings.borrow().language != self.language;
+            if language_changed {
+                self.language = self.settings.borrow().language.clone();
+                let title = self.localization.borrow().colonize_window_title.clone();
+                self.window.set_title(title);
+            }
+
+            // The game ends once there are no scenes left to run.
+            if self.scene_manager.scene_count() == 0 {
+                self.window.set_should_close(true);
+            }
         }
     }
+
+    /// Loads the assets in use whose files have been modified again,
+    /// keeping the ones already loaded if that fails.
+    fn reload_assets(&mut self, glyph_cache: &mut <GlBackend as Backend>::CharacterCache) {
+        for asset in self.assets.poll_changes() {
+            // The tile atlas is the only user of textures.
+            let result = match asset {
+                Asset::Font(ref name) if *name == self.config.font => {
+                    self.assets.load_font(name).map(|font| *glyph_cache = font)
+                },
+                Asset::Font(_) => Ok(()),
+                Asset::Texture(_) | Asset::TileAtlas => {
+                    let tile_renderer = &self.tile_renderer;
+                    self.assets.load_tile_atlas().map(|(texture, atlas)| tile_renderer.set_atlas(texture, atlas))
+                },
+            };
+            if let Err(e) = result {
+                warn!("Failed to reload {:?}: {}", asset, e);
+            }
+        }
+    }
+
+    /// Starts recording a trace of the timed scopes, or saves the one being
+    /// recorded.
+    fn toggle_trace(&mut self) {
+        if !profiler::is_tracing() {
+            profiler::start_trace();
+            return;
+        }
+
+        match save_trace() {
+            Ok(path) => info!("Saved trace to {}", path.display()),
+            Err(e) => warn!("Failed to save trace: {}", e),
+        }
+    }
+
+    /// Makes the window cover the screen, or gives it back the size it had
+    /// before.
+    fn toggle_fullscreen(&mut self) {
+        match self.windowed_size.take() {
+            Some(size) => self.window.leave_fullscreen(size),
+            None => {
+                self.windowed_size = Some(self.window.size());
+                self.window.enter_fullscreen();
+            },
+        }
+    }
+
+    /// Keeps the size of the window in the settings, and lays the scenes out
+    /// anew for it.
+    fn resize(&mut self, width: u32, height: u32) {
+        {
+            let mut settings = self.settings.borrow_mut();
+            settings.window_width = width;
+            settings.window_height = height;
+        }
+        self.scene_manager.resize(width as f64, height as f64);
+    }
+
+    /// Returns lines describing the frame rate and the time spent in each
+    /// phase of the last frame.
+    fn frame_info(&self, fps: usize) -> Vec<String> {
+        let localization = self.localization.borrow();
+        let to_millis = frame_clock::duration_to_millis;
+
+        vec![
+            format!(
+                "{} {} ({:.2}{})",
+                fps,
+                localization.util_unit_fps,
+                to_millis(self.frame_time),
+                localization.util_unit_millisecond),
+            format!(
+                "{}: {:.2}{unit} | {}: {:.2}{unit} | {}: {:.2}{unit}",
+                localization.debug_events_time,
+                to_millis(self.frame_timings.events),
+                localization.debug_update_time,
+                to_millis(self.frame_timings.update),
+                localization.debug_render_time,
+                to_millis(self.frame_timings.render),
+                unit=localization.util_unit_millisecond,
+            ),
+        ]
+    }
+
+    /// Returns lines breaking the time spent in each timed scope down into
+    /// bars.
+    fn profile_info(&self, profile: &[ScopeTotal]) -> Vec<String> {
+        let localization = self.localization.borrow();
+        let mut lines = vec![localization.debug_profile.clone()];
+        if profiler::is_tracing() {
+            lines.push(localization.debug_tracing.clone());
+        }
+
+        for total in profile {
+            let millis = frame_clock::duration_to_millis(total.duration);
+            let bar_length = cmp::min((millis / PROFILE_BAR_MILLIS).ceil() as usize, MAX_PROFILE_BAR_LENGTH);
+            let bar: String = iter::repeat('|').take(bar_length).collect();
+            lines.push(format!(
+                "{} ({}): {:.2}{} {}",
+                total.name,
+                total.count,
+                millis,
+                localization.util_unit_millisecond,
+                bar));
+        }
+        lines
+    }
+}
+
+/// Stops recording the trace of the timed scopes, saving it in the traces
+/// directory under the current time. Returns the path of the trace.
+fn save_trace() -> ColonizeResult<PathBuf> {
+    let dir = paths::cache_dir().join(TRACES_DIR);
+    try!(fs::create_dir_all(&dir));
+    let path = dir.join(format!("trace-{}.json", paths::timestamp()));
+    let mut writer = BufWriter::new(try!(File::create(&path)));
+    try!(profiler::finish_trace(&mut writer));
+    Ok(path)
 }
@@ -0,0 +1,322 @@
+//! Plays the game scene without a window, so that tests can drive it with the
+//! same input as a player and check what it did to the world.
+
+use std::cell::RefCell;
+use std::env;
+use std::rc::Rc;
+use std::sync::{Once, ONCE_INIT};
+
+use cgmath::{Point2, Point3};
+use piston::input::{Button, Event, Input};
+use piston::input::keyboard::Key;
+use rgframework::Scene;
+use world::{MaterialRegistry, World, WorldGenConfig};
+
+use audio::AudioManager;
+use config::Config;
+use error::ColonizeResult;
+use headless::{HeadlessBackend, NullGraphics};
+use localization::Localization;
+use palette::Palette;
+use paths;
+use scene::GameScene;
+use scripting::ScriptHost;
+use settings::Settings;
+use tile_renderer::TileRenderer;
+
+/// Radius (in chunks) of the worlds generated for tests, which is kept small
+/// so that they are quick to generate.
+const WORLD_SIZE: u32 = 2;
+/// Duration of a frame at 60 frames per second, in seconds.
+pub const FRAME_TIME: f64 = 1.0 / 60.0;
+
+static INIT: Once = ONCE_INIT;
+
+/// The input of a single frame, passed to the scene as the events of the
+/// window would be, after which the scene is updated by `dt` seconds.
+#[derive(Clone)]
+pub struct GameInput {
+    pub events: Vec<Input>,
+    pub dt: f64,
+}
+
+impl GameInput {
+    /// A frame without any input, lasting `dt` seconds.
+    pub fn wait(dt: f64) -> Self {
+        GameInput {
+            events: Vec::new(),
+            dt: dt,
+        }
+    }
+
+    /// A frame in which `key` is pressed, and held down until released.
+    pub fn press(key: Key) -> Self {
+        GameInput {
+            events: vec![Input::Press(Button::Keyboard(key))],
+            dt: FRAME_TIME,
+        }
+    }
+
+    pub fn release(key: Key) -> Self {
+        GameInput {
+            events: vec![Input::Release(Button::Keyboard(key))],
+            dt: FRAME_TIME,
+        }
+    }
+
+    /// A frame in which `key` is pressed and released again.
+    pub fn tap(key: Key) -> Self {
+        GameInput {
+            events: vec![Input::Press(Button::Keyboard(key)), Input::Release(Button::Keyboard(key))],
+            dt: FRAME_TIME,
+        }
+    }
+
+    /// A frame in which `text` is typed.
+    pub fn text(text: &str) -> Self {
+        GameInput {
+            events: vec![Input::Text(text.to_owned())],
+            dt: FRAME_TIME,
+        }
+    }
+
+    /// The frames in which the console is opened, `line` is entered into it,
+    /// and the console is closed again.
+    pub fn console(line: &str) -> Vec<Self> {
+        vec![
+            GameInput::tap(Key::Backquote),
+            GameInput::text(line),
+            GameInput::tap(Key::Return),
+            GameInput::tap(Key::Backquote),
+        ]
+    }
+}
+
+/// A game scene played without a window, sound or mods, in the default
+/// configuration and settings.
+///
+/// Files written by the game, such as saves, are kept in a directory of their
+/// own in the temporary directory, rather than in those of the player.
+pub struct Harness {
+    scene: GameScene<HeadlessBackend>,
+}
+
+impl Harness {
+    /// Starts a game in a new world generated from `seed`, whose colony
+    /// embarks at the origin.
+    pub fn new(seed: u32) -> Self {
+        let (config, settings, localization, palette, materials, tile_renderer, audio, scripts) = parts();
+        let world_gen = WorldGenConfig::default();
+        Harness {
+            scene: GameScene::new(config, settings, localization, palette, materials, tile_renderer, audio, scripts, seed, world_gen, Point2::new(0, 0)),
+        }
+    }
+
+    /// Starts a game from the world saved under `name`.
+    pub fn load(name: &str) -> ColonizeResult<Self> {
+        let (config, settings, localization, palette, materials, tile_renderer, audio, scripts) = parts();
+        let scene = try!(GameScene::load(config, settings, localization, palette, materials, tile_renderer, audio, scripts, name));
+        Ok(Harness {
+            scene: scene,
+        })
+    }
+
+    /// Plays `inputs` one frame at a time, through the same path as the
+    /// events of the window.
+    ///
+    /// Panics if the input leaves the game scene.
+    pub fn play(&mut self, inputs: &[GameInput]) {
+        for input in inputs {
+            for event in &input.events {
+                let event = Event::Input(event.clone());
+                let left = Scene::<HeadlessBackend, Event, NullGraphics>::handle_event(&mut self.scene, &event).is_some();
+                assert!(!left, "the input left the game scene");
+            }
+            let left = Scene::<HeadlessBackend, Event, NullGraphics>::update(&mut self.scene, input.dt).is_some();
+            assert!(!left, "the game scene was left while updating");
+        }
+    }
+
+    /// Plays frames of `dt` seconds without any input until `done` returns
+    /// true for the world, for at most `max_frames` frames. Returns whether
+    /// `done` returned true.
+    pub fn wait_until<F>(&mut self, dt: f64, max_frames: usize, mut done: F) -> bool
+        where F: FnMut(&World) -> bool,
+    {
+        for _ in 0..max_frames {
+            if done(self.scene.world()) {
+                return true;
+            }
+            self.play(&[GameInput::wait(dt)]);
+        }
+        done(self.scene.world())
+    }
+
+    pub fn world(&self) -> &World {
+        self.scene.world()
+    }
+
+    pub fn camera_position(&self) -> &Point3<f64> {
+        self.scene.camera_position()
+    }
+}
+
+/// Returns what the game scene is made from, in the default configuration and
+/// settings other than those which would slow down or disturb tests.
+fn parts() -> (Rc<Config>, Rc<RefCell<Settings>>, Rc<RefCell<Localization>>, Rc<RefCell<Palette>>, Rc<MaterialRegistry>, Rc<TileRenderer<HeadlessBackend>>, Rc<AudioManager>, Rc<ScriptHost>) {
+    INIT.call_once(|| {
+        env::set_var(paths::HOME_VAR, env::temp_dir().join("colonize-tests"));
+    });
+
+    let mut config = Config::default();
+    config.initial_world_size = WORLD_SIZE;
+    config.chunk_load_radius = WORLD_SIZE;
+    // The scale of the interface is otherwise detected from the display.
+    let mut settings = Settings::default();
+    settings.ui_scale = 1.0;
+
+    (
+        Rc::new(config),
+        Rc::new(RefCell::new(settings)),
+        Rc::new(RefCell::new(Localization::default())),
+        Rc::new(RefCell::new(Palette::default())),
+        Rc::new(MaterialRegistry::default()),
+        Rc::new(TileRenderer::fallback()),
+        Rc::new(AudioManager::silent()),
+        Rc::new(ScriptHost::load(&[])),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Point3;
+    use piston::input::keyboard::Key;
+    use world::{JobKind, Position, World, CHUNK_SIZE};
+
+    use save;
+    use super::*;
+
+    const SEED: u32 = 1;
+    /// How many frames of a second each colonists get to walk over to a
+    /// designated tile and dig it out.
+    const MAX_DIG_FRAMES: usize = 300;
+
+    fn is_solid(world: &World, pos: &Point3<i32>) -> bool {
+        world.materials().is_solid(world.area.get_tile(pos).material)
+    }
+
+    /// Returns the ground of a tile next to the first colonist, with open
+    /// space above it, falling back to the ground the colonist stands on.
+    fn ground_near_colonist(world: &World) -> Point3<i32> {
+        let entities = world.entities();
+        let (colonist, _) = entities.colonists.iter().next().expect("colonists are spawned");
+        let position = entities.positions.get(colonist).expect("colonists have a position").to_point();
+        [(1, 0), (-1, 0), (0, 1), (0, -1)].iter()
+            .map(|&(dx, dz)| Point3::new(position.x + dx, position.y - 1, position.z + dz))
+            .find(|pos| is_solid(world, pos) && !is_solid(world, &Point3::new(pos.x, pos.y + 1, pos.z)))
+            .unwrap_or(Point3::new(position.x, position.y - 1, position.z))
+    }
+
+    fn is_dig_pending(world: &World, target: Position) -> bool {
+        world.job_queue().iter()
+            .chain(world.entities().jobs.iter().map(|(_, job)| job))
+            .any(|job| job.kind == JobKind::Dig && job.target == target)
+    }
+
+    /// Returns the frames in which the camera is moved to the z-level of
+    /// `target`, and the keyboard cursor shown and moved onto it.
+    fn move_cursor_to(harness: &Harness, target: Point3<i32>) -> Vec<GameInput> {
+        let camera = harness.camera_position();
+        let start = Point3::new(camera.x.floor() as i32, camera.y.floor() as i32, camera.z.floor() as i32);
+        let mut inputs = Vec::new();
+        let level_key = if target.y < start.y { Key::Comma } else { Key::Period };
+        for _ in 0..(target.y - start.y).abs() {
+            inputs.push(GameInput::tap(level_key));
+        }
+        inputs.push(GameInput::tap(Key::K));
+        let x_key = if target.x < start.x { Key::NumPad4 } else { Key::NumPad6 };
+        for _ in 0..(target.x - start.x).abs() {
+            inputs.push(GameInput::tap(x_key));
+        }
+        let z_key = if target.z < start.z { Key::NumPad8 } else { Key::NumPad2 };
+        for _ in 0..(target.z - start.z).abs() {
+            inputs.push(GameInput::tap(z_key));
+        }
+        inputs
+    }
+
+    #[test]
+    fn held_keys_move_the_camera() {
+        let mut harness = Harness::new(SEED);
+        let start = *harness.camera_position();
+
+        // Holding Right scrolls east for as long as it is held.
+        let mut inputs = vec![GameInput::press(Key::Right)];
+        for _ in 0..59 {
+            inputs.push(GameInput::wait(FRAME_TIME));
+        }
+        inputs.push(GameInput::release(Key::Right));
+        harness.play(&inputs);
+        let scrolled = *harness.camera_position();
+        assert!(scrolled.x > start.x);
+        assert_eq!(scrolled.y, start.y);
+        assert_eq!(scrolled.z, start.z);
+
+        // The camera stops once the key is released.
+        harness.play(&[GameInput::wait(1.0)]);
+        assert_eq!(*harness.camera_position(), scrolled);
+
+        // Period and Comma change the z-level by one per press.
+        harness.play(&[GameInput::tap(Key::Period), GameInput::tap(Key::Period), GameInput::tap(Key::Comma)]);
+        assert_eq!(harness.camera_position().y, start.y + 1.0);
+    }
+
+    #[test]
+    fn colonists_dig_out_a_tile_designated_with_the_keyboard() {
+        let mut harness = Harness::new(SEED);
+        let target = ground_near_colonist(harness.world());
+        assert!(is_solid(harness.world(), &target));
+
+        // Select digging, then paint the tile under the cursor by selecting
+        // it once to start painting and again to stop.
+        let mut inputs = move_cursor_to(&harness, target);
+        inputs.push(GameInput::tap(Key::D));
+        inputs.push(GameInput::tap(Key::Return));
+        inputs.push(GameInput::tap(Key::Return));
+        harness.play(&inputs);
+        assert!(is_dig_pending(harness.world(), Position::from(target)));
+
+        // Play at the fastest speed until the tile has been dug out.
+        harness.play(&[GameInput::tap(Key::D3)]);
+        let dug = harness.wait_until(1.0, MAX_DIG_FRAMES, |world| !is_solid(world, &target));
+        assert!(dug, "the tile was not dug out within {} seconds", MAX_DIG_FRAMES);
+        assert!(!is_dig_pending(harness.world(), Position::from(target)));
+    }
+
+    #[test]
+    fn saves_entered_in_the_console_load_as_they_were_left() {
+        let name = "harness-save-and-load";
+        let mut harness = Harness::new(SEED);
+
+        // Scroll away from where the colony embarked, and change a tile
+        // within the chunks generated along with the world.
+        harness.play(&[GameInput::press(Key::Down), GameInput::wait(0.5), GameInput::release(Key::Down)]);
+        let camera = *harness.camera_position();
+        let changed = Point3::new(CHUNK_SIZE as i32 / 2, camera.y as i32, CHUNK_SIZE as i32 / 2);
+        let line = format!("set_material {} {} {} gold", changed.x, changed.y, changed.z);
+        harness.play(&GameInput::console(&line));
+        let gold = harness.world().materials().find("gold").unwrap();
+        assert_eq!(harness.world().area.get_tile(&changed).material, gold);
+
+        harness.play(&GameInput::console(&format!("save {}", name)));
+        assert!(save::save_names().iter().any(|saved| saved == name));
+
+        let loaded = Harness::load(name).unwrap();
+        let position = loaded.camera_position();
+        assert!((position.x - camera.x).abs() < 1e-9);
+        assert!((position.y - camera.y).abs() < 1e-9);
+        assert!((position.z - camera.z).abs() < 1e-9);
+        assert_eq!(loaded.world().seed(), harness.world().seed());
+        assert_eq!(loaded.world().area.get_tile(&changed).material, gold);
+    }
+}
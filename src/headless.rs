@@ -0,0 +1,75 @@
+use graphics::DrawState;
+use graphics::character::Character;
+use graphics::types::FontSize;
+use rgframework::backend::graphics::{CharacterCache, CreateTexture, Format, Graphics, ImageSize, TextureSettings};
+
+/// Width of every glyph of the `NullGlyphCache`, relative to its font size.
+const GLYPH_WIDTH: f64 = 0.5;
+
+/// The backend of the game scene run without a window, whose textures and
+/// glyphs only have a size, and whose graphics draw nothing.
+pub type HeadlessBackend = (NullTexture, NullGlyphCache);
+
+/// A texture which keeps nothing but its size.
+pub struct NullTexture {
+    size: (u32, u32),
+}
+
+impl ImageSize for NullTexture {
+    fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+}
+
+impl CreateTexture<()> for NullTexture {
+    type Error = ();
+
+    fn create<S>(_factory: &mut (), _format: Format, _memory: &[u8], size: S, _settings: &TextureSettings) -> Result<Self, ()>
+        where S: Into<[u32; 2]>,
+    {
+        let size = size.into();
+        Ok(NullTexture {
+            size: (size[0], size[1]),
+        })
+    }
+}
+
+/// A glyph cache without a font, whose glyphs are all as wide as half their
+/// font size, so that text can still be measured and laid out.
+pub struct NullGlyphCache {
+    texture: NullTexture,
+}
+
+impl CharacterCache for NullGlyphCache {
+    type Texture = NullTexture;
+
+    fn character<'a>(&'a mut self, font_size: FontSize, _ch: char) -> Character<'a, NullTexture> {
+        let size = font_size as f64;
+        Character {
+            offset: [0.0, 0.0],
+            size: [size * GLYPH_WIDTH, size],
+            texture: &self.texture,
+        }
+    }
+}
+
+/// Graphics which draw nothing.
+pub struct NullGraphics;
+
+impl Graphics for NullGraphics {
+    type Texture = NullTexture;
+
+    fn clear_color(&mut self, _color: [f32; 4]) {}
+
+    fn clear_stencil(&mut self, _value: u8) {}
+
+    fn tri_list<F>(&mut self, _draw_state: &DrawState, _color: &[f32; 4], _f: F)
+        where F: FnMut(&mut FnMut(&[[f32; 2]])),
+    {
+    }
+
+    fn tri_list_uv<F>(&mut self, _draw_state: &DrawState, _color: &[f32; 4], _texture: &NullTexture, _f: F)
+        where F: FnMut(&mut FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+    }
+}
@@ -0,0 +1,127 @@
+use rgframework::Label;
+use rgframework::backend::{Backend, Graphics};
+use rgframework::backend::graphics::Context;
+use rgframework::draw::Draw;
+
+use palette::Palette;
+
+/// Width of the panel, in pixels, at a UI scale of 1.
+const PANEL_WIDTH: f64 = 200.0;
+const LINE_SPACING: f64 = 1.5;
+const PADDING: f64 = 10.0;
+
+/// An on-screen panel describing whatever is being inspected, which is hidden
+/// while it has no lines to display.
+pub struct InspectionPanel {
+    /// Position of the top-left corner of the panel, in pixels.
+    origin: [f64; 2],
+    font_size: u32,
+    /// The factor by which the width and padding of the panel are scaled.
+    scale: f64,
+    text_color: [f32; 4],
+    background_color: [f32; 4],
+    labels: Vec<Label>,
+}
+
+impl InspectionPanel {
+    pub fn new(font_size: u32, scale: f64, origin: [f64; 2], palette: &Palette) -> Self {
+        InspectionPanel {
+            origin: origin,
+            font_size: font_size,
+            scale: scale,
+            text_color: palette.text,
+            background_color: palette.panel_background,
+            labels: Vec::new(),
+        }
+    }
+
+    /// Lays out the panel with text of `font_size`, and its width and padding
+    /// scaled by `scale`, from now on.
+    pub fn set_scale(&mut self, font_size: u32, scale: f64) {
+        self.font_size = font_size;
+        self.scale = scale;
+        // The labels are created anew at the new size with the next lines.
+        self.labels.clear();
+    }
+
+    /// Draws the panel in the colors of `palette` from now on.
+    pub fn set_palette(&mut self, palette: &Palette) {
+        self.text_color = palette.text;
+        self.background_color = palette.panel_background;
+        for label in &mut self.labels {
+            label.set_color(palette.text);
+        }
+    }
+
+    /// Replaces the lines of text displayed by the panel.
+    pub fn set_lines(&mut self, lines: Vec<String>) {
+        self.labels.truncate(lines.len());
+
+        for (i, line) in lines.into_iter().enumerate() {
+            if i < self.labels.len() {
+                self.labels[i].set_text(line);
+            } else {
+                let position = self.line_position(i);
+                self.labels.push(Label::new(line, self.text_color, self.font_size, position));
+            }
+        }
+    }
+
+    /// Moves the top-left corner of the panel to `origin`, in pixels.
+    pub fn set_origin(&mut self, origin: [f64; 2]) {
+        self.origin = origin;
+        for i in 0..self.labels.len() {
+            let position = self.line_position(i);
+            self.labels[i].set_position(position);
+        }
+    }
+
+    /// Returns the width of the panel, in pixels.
+    pub fn width(&self) -> f64 {
+        PANEL_WIDTH * self.scale
+    }
+
+    /// Returns the height of the panel with its current lines, in pixels.
+    pub fn height(&self) -> f64 {
+        (self.labels.len() as f64 + 0.5) * self.line_height() + self.padding() * 2.0
+    }
+
+    fn padding(&self) -> f64 {
+        PADDING * self.scale
+    }
+
+    fn line_height(&self) -> f64 {
+        self.font_size as f64 * LINE_SPACING
+    }
+
+    /// Returns the position of the baseline of the `i`th line, in pixels.
+    fn line_position(&self, i: usize) -> [f64; 2] {
+        [
+            self.origin[0] + self.padding(),
+            self.origin[1] + self.padding() + (i + 1) as f64 * self.line_height(),
+        ]
+    }
+}
+
+impl<B, G> Draw<B, G> for InspectionPanel
+    where B: Backend,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn draw(&self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        use graphics::Rectangle;
+
+        if self.labels.is_empty() {
+            return;
+        }
+
+        Rectangle::new(self.background_color).draw(
+            [self.origin[0], self.origin[1], self.width(), self.height()],
+            &context.draw_state,
+            context.transform,
+            graphics);
+
+        for label in &self.labels {
+            Draw::<B, G>::draw(label, context, graphics, glyph_cache);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use cgmath::Point3;
+use graphics::{Ellipse, Polygon};
+use rgframework::backend::Graphics;
+use rgframework::backend::graphics::Context;
+use world::{Area, MaterialRegistry, MAX_FLUID_LEVEL};
+
+use palette::Palette;
+
+/// Width and height, in pixels, of the diamond forming the top of a tile.
+const TILE_WIDTH: f64 = 32.0;
+const TILE_HEIGHT: f64 = 16.0;
+/// Height, in pixels, of the sides of a tile, by which each z-level is
+/// raised above the one beneath it.
+const LEVEL_HEIGHT: f64 = 12.0;
+/// The number of z-levels drawn, down from the z-level of the camera.
+const VISIBLE_LEVELS: i32 = 4;
+/// The factors by which the colors of the sides of tiles facing left and
+/// right are darkened, so that they stand out from the tops.
+const LEFT_SIDE_SHADE: f32 = 0.75;
+const RIGHT_SIDE_SHADE: f32 = 0.55;
+/// The opacity of a full tile of liquid.
+const LIQUID_OPACITY: f32 = 0.7;
+/// The size of entities, relative to the width of a tile.
+const ENTITY_SIZE: f64 = 0.4;
+
+/// Draws the top few z-levels around a point of the world in isometric
+/// projection, as seen from above the tiles with the greatest X and Z
+/// coordinates.
+pub struct IsometricView<'a> {
+    pub area: &'a Area,
+    pub materials: &'a MaterialRegistry,
+    /// The colors in which the tiles are drawn.
+    pub palette: &'a Palette,
+    /// The tile drawn at the center of the view, on the topmost z-level.
+    pub center: Point3<i32>,
+    /// The size of the view, in pixels.
+    pub size: [f64; 2],
+    /// The colors of the entities to draw, by the position of the tile each
+    /// stands in.
+    pub entities: HashMap<Point3<i32>, [f32; 4]>,
+}
+
+impl<'a> IsometricView<'a> {
+    /// Draws the view, with every tile lit by `brightness`, given the light
+    /// level of the tile.
+    ///
+    /// Tiles are drawn from back to front, and from the bottom up, so that
+    /// nearer and higher tiles are drawn over those they hide.
+    pub fn draw<G, F>(&self, brightness: F, context: &Context, graphics: &mut G)
+        where G: Graphics,
+              F: Fn(u8) -> f32,
+    {
+        let radius = self.radius();
+        let bottom = self.center.y - VISIBLE_LEVELS + 1;
+
+        for diagonal in -2 * radius..2 * radius + 1 {
+            for y in bottom..self.center.y + 1 {
+                for dx in -radius..radius + 1 {
+                    let dz = diagonal - dx;
+                    if dz < -radius || dz > radius {
+                        continue;
+                    }
+
+                    let (x, z) = (self.center.x + dx, self.center.z + dz);
+                    let screen = self.screen_position(dx, y, dz);
+                    if !self.is_on_screen(screen) {
+                        continue;
+                    }
+                    self.draw_tile(Point3::new(x, y, z), screen, &brightness, context, graphics);
+                }
+            }
+        }
+    }
+
+    /// Returns how far from the center, in tiles along the X and Z axes, the
+    /// tiles which may be on screen lie.
+    fn radius(&self) -> i32 {
+        let height = self.size[1] + 2.0 * VISIBLE_LEVELS as f64 * LEVEL_HEIGHT;
+        ((self.size[0] / TILE_WIDTH + height / TILE_HEIGHT) / 2.0).ceil() as i32 + 1
+    }
+
+    /// Returns the position on screen of the center of the top of the tile
+    /// at the specified offset from the center of the view, on z-level `y`.
+    fn screen_position(&self, dx: i32, y: i32, dz: i32) -> [f64; 2] {
+        [
+            self.size[0] / 2.0 + (dx - dz) as f64 * TILE_WIDTH / 2.0,
+            self.size[1] / 2.0 + (dx + dz) as f64 * TILE_HEIGHT / 2.0 - (y - self.center.y) as f64 * LEVEL_HEIGHT,
+        ]
+    }
+
+    fn is_on_screen(&self, screen: [f64; 2]) -> bool {
+        screen[0] > -TILE_WIDTH && screen[0] < self.size[0] + TILE_WIDTH &&
+            screen[1] > -TILE_HEIGHT && screen[1] < self.size[1] + TILE_HEIGHT + LEVEL_HEIGHT
+    }
+
+    fn draw_tile<G, F>(&self, pos: Point3<i32>, screen: [f64; 2], brightness: &F, context: &Context, graphics: &mut G)
+        where G: Graphics,
+              F: Fn(u8) -> f32,
+    {
+        let tile = self.area.get_tile(&pos);
+        let material = match self.materials.get(tile.material) {
+            Some(material) => material,
+            None => return,
+        };
+        let above = Point3::new(pos.x, pos.y + 1, pos.z);
+
+        if self.materials.is_solid(tile.material) {
+            // Only the tiles whose tops can be seen are drawn, except on the
+            // topmost z-level, whose tiles are cut through.
+            if pos.y != self.center.y && self.materials.is_solid(self.area.get_tile(&above).material) {
+                return;
+            }
+            if !self.area.is_revealed(&pos) {
+                draw_top(screen, self.palette.unrevealed, context, graphics);
+                return;
+            }
+
+            let color = shade(self.palette.material_color(material), brightness(self.area.light_level(&above)), 1.0);
+            if !self.materials.is_solid(self.area.get_tile(&Point3::new(pos.x, pos.y, pos.z + 1)).material) {
+                draw_left_side(screen, shade(color, LEFT_SIDE_SHADE, 1.0), context, graphics);
+            }
+            if !self.materials.is_solid(self.area.get_tile(&Point3::new(pos.x + 1, pos.y, pos.z)).material) {
+                draw_right_side(screen, shade(color, RIGHT_SIDE_SHADE, 1.0), context, graphics);
+            }
+            draw_top(screen, color, context, graphics);
+        } else if material.liquid && self.area.is_revealed(&pos) {
+            // Liquids are drawn as a translucent surface at the height to
+            // which they fill their tile.
+            let fill = tile.fluid_level as f64 / MAX_FLUID_LEVEL as f64;
+            let surface = [screen[0], screen[1] + (1.0 - fill) * LEVEL_HEIGHT];
+            let color = shade(self.palette.material_color(material), brightness(self.area.light_level(&pos)), LIQUID_OPACITY);
+            draw_top(surface, color, context, graphics);
+        }
+
+        // Entities stand on the tile beneath them.
+        if let Some(&color) = self.entities.get(&pos) {
+            let size = TILE_WIDTH * ENTITY_SIZE;
+            let center = [screen[0], screen[1] + LEVEL_HEIGHT];
+            let color = shade(color, brightness(self.area.light_level(&pos)), color[3]);
+            Ellipse::new(color).draw(
+                [center[0] - size / 2.0, center[1] - size, size, size],
+                &context.draw_state,
+                context.transform,
+                graphics);
+        }
+    }
+}
+
+/// Multiplies the channels of `color` by `factor`, giving it `opacity`.
+fn shade(color: [f32; 4], factor: f32, opacity: f32) -> [f32; 4] {
+    [color[0] * factor, color[1] * factor, color[2] * factor, opacity]
+}
+
+fn draw_top<G>(center: [f64; 2], color: [f32; 4], context: &Context, graphics: &mut G)
+    where G: Graphics,
+{
+    let (x, y) = (center[0], center[1]);
+    let (half_width, half_height) = (TILE_WIDTH / 2.0, TILE_HEIGHT / 2.0);
+    Polygon::new(color).draw(
+        &[[x, y - half_height], [x + half_width, y], [x, y + half_height], [x - half_width, y]],
+        &context.draw_state,
+        context.transform,
+        graphics);
+}
+
+fn draw_left_side<G>(center: [f64; 2], color: [f32; 4], context: &Context, graphics: &mut G)
+    where G: Graphics,
+{
+    let (x, y) = (center[0], center[1]);
+    let (half_width, half_height) = (TILE_WIDTH / 2.0, TILE_HEIGHT / 2.0);
+    Polygon::new(color).draw(
+        &[[x - half_width, y], [x, y + half_height], [x, y + half_height + LEVEL_HEIGHT], [x - half_width, y + LEVEL_HEIGHT]],
+        &context.draw_state,
+        context.transform,
+        graphics);
+}
+
+fn draw_right_side<G>(center: [f64; 2], color: [f32; 4], context: &Context, graphics: &mut G)
+    where G: Graphics,
+{
+    let (x, y) = (center[0], center[1]);
+    let (half_width, half_height) = (TILE_WIDTH / 2.0, TILE_HEIGHT / 2.0);
+    Polygon::new(color).draw(
+        &[[x, y + half_height], [x + half_width, y], [x + half_width, y + LEVEL_HEIGHT], [x, y + half_height + LEVEL_HEIGHT]],
+        &context.draw_state,
+        context.transform,
+        graphics);
+}
@@ -0,0 +1,71 @@
+//! The parts of Colonize shared by the game and the dedicated server: the
+//! configuration, saves, mods, networking and console commands, none of which
+//! need a window or SDL2.
+
+#![cfg_attr(feature = "nightly", feature(custom_derive, plugin))]
+#![cfg_attr(feature = "nightly", plugin(serde_macros))]
+#![cfg_attr(feature = "clippy", plugin(clippy))]
+#![cfg_attr(feature = "clippy", allow(used_underscore_binding))]
+
+extern crate app_dirs;
+extern crate backtrace;
+extern crate cgmath;
+extern crate graphics;
+extern crate hlua;
+extern crate image;
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate rgframework;
+extern crate serde;
+extern crate serde_json;
+extern crate colonize_world as world;
+
+pub mod command;
+pub mod config;
+pub mod console;
+pub mod crash;
+pub mod designation;
+pub mod error;
+pub mod localization;
+pub mod logging;
+pub mod materials;
+pub mod migration;
+pub mod minimap;
+pub mod mods;
+pub mod net;
+pub mod palette;
+pub mod paths;
+pub mod save;
+pub mod scripting;
+pub mod simulation;
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::PathBuf;
+
+use error::ColonizeResult;
+
+pub fn read_file_to_string(path: &PathBuf) -> ColonizeResult<String> {
+    let mut file = try!(File::open(&path));
+    let mut file_str = String::new();
+    try!(file.read_to_string(&mut file_str));
+    Ok(file_str)
+}
+
+/// Returns the names, without their extension, of the files in `dir` with
+/// the extension `extension`, in order.
+pub fn file_stems(dir: &PathBuf, extension: &str) -> Vec<String> {
+    let mut stems: Vec<String> = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map_or(false, |other| other == extension))
+            .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).map(|stem| stem.to_owned()))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    stems.sort();
+    stems
+}
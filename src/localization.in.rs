@@ -2,45 +2,564 @@
 pub struct Localization {
     /// Colonize - Window title
     pub colonize_window_title: String,
-    /// Debug - Render Info
-    pub debug_render_info: String,
+    /// Console - Failed to load a chunk
+    pub console_chunk_load_failed: String,
+    /// Console - Dig job queued
+    pub console_dig_queued: String,
+    /// Console - Tile cannot be dug out
+    pub console_dig_rejected: String,
+    /// Console - Connection to the host lost
+    pub console_disconnected: String,
+    /// Console - Failed to write a listing of a recording
+    pub console_dump_failed: String,
+    /// Console - Failed to export recording
+    pub console_export_failed: String,
+    /// Console - Game loaded
+    pub console_game_loaded: String,
+    /// Console - Game saved
+    pub console_game_saved: String,
+    /// Console - Failed to host game
+    pub console_host_failed: String,
+    /// Console - Hosting game
+    pub console_hosting: String,
+    /// Console - Failed to read a listing back into a recording
+    pub console_import_failed: String,
+    /// Console - Items given
+    pub console_items_given: String,
+    /// Console - Failed to join game
+    pub console_join_failed: String,
+    /// Console - Joined game
+    pub console_joined: String,
+    /// Console - Left multiplayer game
+    pub console_left: String,
+    /// Console - Failed to load game
+    pub console_load_failed: String,
+    /// Console - Material set
+    pub console_material_set: String,
+    /// Console - Not in a multiplayer game
+    pub console_not_in_game: String,
+    /// Console - Command not available on the dedicated server
+    pub console_not_on_server: String,
+    /// Console - Neither recording nor playing back
+    pub console_not_recording: String,
+    /// Console - Player joined hosted game
+    pub console_player_joined: String,
+    /// Console - Player left hosted game
+    pub console_player_left: String,
+    /// Console - Position not loaded
+    pub console_position_not_loaded: String,
+    /// Console - Command unavailable while recording or playing back
+    pub console_recording_busy: String,
+    /// Console - Listing of a recording written
+    pub console_recording_dumped: String,
+    /// Console - Failed to start or save recording
+    pub console_recording_failed: String,
+    /// Console - Listing read back into a recording
+    pub console_recording_imported: String,
+    /// Console - Recording started
+    pub console_recording_started: String,
+    /// Console - Recording stopped and saved
+    pub console_recording_stopped: String,
+    /// Console - Playback no longer matches recording
+    pub console_replay_diverged: String,
+    /// Console - Recording exported as a replay
+    pub console_replay_exported: String,
+    /// Console - Failed to open recording
+    pub console_replay_failed: String,
+    /// Console - End of recording reached
+    pub console_replay_finished: String,
+    /// Console - Viewed replay cannot be stopped
+    pub console_replay_read_only: String,
+    /// Console - Playback started
+    pub console_replay_started: String,
+    /// Console - Playback stopped
+    pub console_replay_stopped: String,
+    /// Console - Failed to save game
+    pub console_save_failed: String,
+    /// Console - Error raised by a mod
+    pub console_script_error: String,
+    /// Console - Camera teleported
+    pub console_teleported: String,
+    /// Console - Unknown command
+    pub console_unknown_command: String,
+    /// Console - Unknown item
+    pub console_unknown_item: String,
+    /// Console - Unknown material
+    pub console_unknown_material: String,
+    /// Console - Unsupported command
+    pub console_unsupported_command: String,
+    /// Console - Command usage
+    pub console_usage: String,
+    /// Console - World generated
+    pub console_world_generated: String,
+    /// Debug - Time spent handling events
+    pub debug_events_time: String,
+    /// Debug - Time spent updating
+    pub debug_update_time: String,
+    /// Debug - Time spent rendering
+    pub debug_render_time: String,
+    /// Debug - Heading of the time spent in each timed scope
+    pub debug_profile: String,
+    /// Debug - Shown while a trace is recorded
+    pub debug_tracing: String,
     /// GameScene - Welcome text
     pub gamescene_welcome_text: String,
+    /// GameScene - Date - Year
+    pub gamescene_date_year: String,
+    /// GameScene - Date - Spring
+    pub gamescene_date_spring: String,
+    /// GameScene - Date - Summer
+    pub gamescene_date_summer: String,
+    /// GameScene - Date - Autumn
+    pub gamescene_date_autumn: String,
+    /// GameScene - Date - Winter
+    pub gamescene_date_winter: String,
+    /// GameScene - Designation selected for painting
+    pub gamescene_designating: String,
+    /// GameScene - Designation - Dig
+    pub gamescene_designation_dig: String,
+    /// GameScene - Designation - Dig stairs
+    pub gamescene_designation_dig_stairs: String,
+    /// GameScene - Designation - Stockpile
+    pub gamescene_designation_stockpile: String,
+    /// GameScene - Designation - Hunt
+    pub gamescene_designation_hunt: String,
+    /// GameScene - Designation - Build
+    pub gamescene_designation_build: String,
+    /// GameScene - Overlays - Only the designations of the kind selected are drawn
+    pub gamescene_overlays_active: String,
+    /// GameScene - Overlays - No designations are drawn
+    pub gamescene_overlays_hidden: String,
+    /// GameScene - Structure - Wall
+    pub gamescene_structure_wall: String,
+    /// GameScene - Structure - Floor
+    pub gamescene_structure_floor: String,
+    /// GameScene - Structure - Stairs
+    pub gamescene_structure_stairs: String,
+    /// GameScene - Structure - Ramp
+    pub gamescene_structure_ramp: String,
+    /// GameScene - Structure - Workshop
+    pub gamescene_structure_workshop: String,
+    /// GameScene - Colonist - Job
+    pub gamescene_colonist_job: String,
+    /// GameScene - Colonist - Idle
+    pub gamescene_colonist_idle: String,
+    /// GameScene - Colonist - Drafted
+    pub gamescene_colonist_drafted: String,
+    /// GameScene - Colonist - Fighting
+    pub gamescene_colonist_fighting: String,
+    /// GameScene - Colonist - Health
+    pub gamescene_colonist_health: String,
+    /// GameScene - Colonist - Food
+    pub gamescene_colonist_food: String,
+    /// GameScene - Colonist - Rest
+    pub gamescene_colonist_rest: String,
+    /// GameScene - Colonist - Mood
+    pub gamescene_colonist_mood: String,
+    /// GameScene - Colonist - Carrying
+    pub gamescene_colonist_carrying: String,
+    /// GameScene - Colonist - Recent jobs
+    pub gamescene_colonist_recent_jobs: String,
+    /// GameScene - Roster - Heading
+    pub gamescene_roster: String,
+    /// GameScene - Job - Dig
+    pub gamescene_job_dig: String,
+    /// GameScene - Job - Haul
+    pub gamescene_job_haul: String,
+    /// GameScene - Job - Eat
+    pub gamescene_job_eat: String,
+    /// GameScene - Job - Sleep
+    pub gamescene_job_sleep: String,
+    /// GameScene - Job - Build
+    pub gamescene_job_build: String,
+    /// GameScene - Job - Hunt
+    pub gamescene_job_hunt: String,
+    /// GameScene - Job - Defined by a mod
+    pub gamescene_job_custom: String,
+    /// GameScene - Tooltip - Unexplored tile
+    pub gamescene_tooltip_unexplored: String,
+    /// GameScene - Tooltip - Designation
+    pub gamescene_tooltip_designation: String,
+    /// GameScene - Tooltip - Items
+    pub gamescene_tooltip_items: String,
+    /// GameScene - Tooltip - Invader
+    pub gamescene_tooltip_invader: String,
+    /// GameScene - Simulation paused
+    pub gamescene_paused: String,
+    /// GameScene - Simulation speed
+    pub gamescene_speed: String,
+    /// GameScene - Weather - Clear
+    pub gamescene_weather_clear: String,
+    /// GameScene - Weather - Rain
+    pub gamescene_weather_rain: String,
+    /// GameScene - Weather - Snow
+    pub gamescene_weather_snow: String,
     /// GameScene - Debug - Cursor
     pub gamescene_debug_cursor: String,
     /// GameScene - Debug - Camera
     pub gamescene_debug_camera: String,
     /// GameScene - Debug - Chunk
     pub gamescene_debug_chunk: String,
+    /// GameScene - Debug - Z-level
+    pub gamescene_debug_z_level: String,
+    /// GameScene - Debug - Number of loaded chunks
+    pub gamescene_debug_chunk_count: String,
+    /// GameScene - Debug - Memory used by loaded chunks
+    pub gamescene_debug_chunk_memory: String,
+    /// GameScene - Debug - Biome under the camera
+    pub gamescene_debug_biome: String,
+    /// GameScene - Debug info - Entity count
+    pub gamescene_debug_entities: String,
+    /// GameScene - Debug - Number of chunks waiting to be generated
+    pub gamescene_debug_pending_chunks: String,
+    /// GameScene - Debug - Time spent drawing the tiles
+    pub gamescene_debug_tile_drawing: String,
+    /// GameScene - Debug - Number of draw calls
+    pub gamescene_debug_draw_calls: String,
+    /// GameScene - Debug - Tiles are drawn in batches
+    pub gamescene_debug_batched: String,
+    /// GameScene - Debug - Tiles are drawn one by one
+    pub gamescene_debug_unbatched: String,
+    /// GameScene - Debug - Step of the recording played back, out of its last
+    pub gamescene_debug_replay: String,
+    /// GameScene - Debug - Step at which the replay diverged from the recording
+    pub gamescene_debug_replay_diverged: String,
     /// Internal - Failed to build window
     pub internal_failed_to_build_window: String,
     /// Internal - Failed to load font message
     pub internal_failed_to_load_font: String,
-    /// MenuScene - Menu option - Singleplayer
-    pub menuscene_singleplayer: String,
+    /// LoadScene - Title
+    pub loadscene_title: String,
+    /// LoadScene - No saved games were found
+    pub loadscene_no_saves: String,
+    /// LoadScene - Label - In-game date
+    pub loadscene_date: String,
+    /// LoadScene - Label - Time at which the game was saved
+    pub loadscene_saved_at: String,
+    /// LoadScene - Label - Seed
+    pub loadscene_seed: String,
+    /// LoadScene - Menu option - Load
+    pub loadscene_load: String,
+    /// LoadScene - Menu option - Rename
+    pub loadscene_rename: String,
+    /// LoadScene - Hint while typing a new name
+    pub loadscene_type_name: String,
+    /// LoadScene - Menu option - Delete
+    pub loadscene_delete: String,
+    /// LoadScene - Menu option - Delete, once chosen
+    pub loadscene_confirm_delete: String,
+    /// LoadScene - Menu option - Back
+    pub loadscene_back: String,
+    /// LoadScene - Failed to rename game
+    pub loadscene_rename_failed: String,
+    /// LoadScene - Failed to delete game
+    pub loadscene_delete_failed: String,
+    /// MenuScene - Menu option - New game
+    pub menuscene_new_game: String,
+    /// MenuScene - Menu option - Load game
+    pub menuscene_load_game: String,
     /// MenuScene - Menu option - Options
     pub menuscene_options: String,
     /// MenuScene - Menu option - Credits
     pub menuscene_credits: String,
+    /// MenuScene - Menu option - Quit
+    pub menuscene_quit: String,
+    /// MenuScene - Failed to load game
+    pub menuscene_load_failed: String,
+    /// OptionsScene - Title
+    pub optionsscene_title: String,
+    /// OptionsScene - Menu option - Resolution
+    pub optionsscene_resolution: String,
+    /// OptionsScene - Menu option - Fullscreen
+    pub optionsscene_fullscreen: String,
+    /// OptionsScene - Menu option - Vsync
+    pub optionsscene_vsync: String,
+    /// OptionsScene - Menu option - FPS cap
+    pub optionsscene_max_fps: String,
+    /// OptionsScene - Menu option - Master volume
+    pub optionsscene_master_volume: String,
+    /// OptionsScene - Menu option - Music volume
+    pub optionsscene_music_volume: String,
+    /// OptionsScene - Menu option - Sound effects volume
+    pub optionsscene_sfx_volume: String,
+    /// OptionsScene - Menu option - UI scale
+    pub optionsscene_ui_scale: String,
+    /// OptionsScene - Menu option - Language
+    pub optionsscene_language: String,
+    /// OptionsScene - Menu option - Palette
+    pub optionsscene_palette: String,
+    /// OptionsScene - Menu option - Key bindings
+    pub optionsscene_key_bindings: String,
+    /// OptionsScene - Menu option - Previous page of key bindings
+    pub optionsscene_previous_page: String,
+    /// OptionsScene - Menu option - Next page of key bindings
+    pub optionsscene_next_page: String,
+    /// OptionsScene - Menu option - Back
+    pub optionsscene_back: String,
+    /// OptionsScene - Value - On
+    pub optionsscene_on: String,
+    /// OptionsScene - Value - Off
+    pub optionsscene_off: String,
+    /// OptionsScene - Value - Unlimited
+    pub optionsscene_unlimited: String,
+    /// OptionsScene - Value - Detected automatically
+    pub optionsscene_auto: String,
+    /// OptionsScene - Waiting for the key to bind
+    pub optionsscene_press_key: String,
+    /// OptionsScene - Window settings apply after a restart
+    pub optionsscene_restart_required: String,
+    /// OptionsScene - Failed to save settings
+    pub optionsscene_save_failed: String,
+    /// PauseScene - Title
+    pub pausescene_title: String,
+    /// PauseScene - Menu option - Resume
+    pub pausescene_resume: String,
+    /// PauseScene - Menu option - Main menu
+    pub pausescene_main_menu: String,
+    /// PauseScene - Menu option - Options
+    pub pausescene_options: String,
+    /// PauseScene - Menu option - Quit
+    pub pausescene_quit: String,
+    /// ReportsScene - Title
+    pub reportsscene_title: String,
+    /// ReportsScene - Label - Days graphed
+    pub reportsscene_days: String,
+    /// ReportsScene - Label - No days to graph
+    pub reportsscene_no_days: String,
+    /// ReportsScene - Label - Highest value graphed
+    pub reportsscene_peak: String,
+    /// ReportsScene - Graph - Population
+    pub reportsscene_population: String,
+    /// ReportsScene - Graph - Items mined
+    pub reportsscene_items_mined: String,
+    /// ReportsScene - Graph - Food stock
+    pub reportsscene_food_stock: String,
+    /// ReportsScene - Graph - Deaths
+    pub reportsscene_deaths: String,
+    /// ReportsScene - Menu option - Back
+    pub reportsscene_back: String,
+    /// SetupScene - Title
+    pub setupscene_title: String,
+    /// SetupScene - Menu option - Seed
+    pub setupscene_seed: String,
+    /// SetupScene - Waiting for the seed to be typed
+    pub setupscene_type_seed: String,
+    /// SetupScene - Menu option - Randomize seed
+    pub setupscene_randomize: String,
+    /// SetupScene - Menu option - Octaves of the height noise
+    pub setupscene_octaves: String,
+    /// SetupScene - Menu option - Size of the terrain features, in tiles
+    pub setupscene_scale: String,
+    /// SetupScene - Menu option - Height variation
+    pub setupscene_amplitude: String,
+    /// SetupScene - Menu option - Sea level
+    pub setupscene_sea_level: String,
+    /// SetupScene - Menu option - Caves
+    pub setupscene_caves: String,
+    /// SetupScene - Menu option - Cave density
+    pub setupscene_cave_density: String,
+    /// SetupScene - Label - Embark site
+    pub setupscene_site: String,
+    /// SetupScene - Menu option - Embark
+    pub setupscene_embark: String,
+    /// SetupScene - Menu option - Back
+    pub setupscene_back: String,
     /// Util - Unit - Millisecond
     pub util_unit_millisecond: String,
     /// Util - Unit - FPS
     pub util_unit_fps: String,
+    /// Util - Unit - Kibibyte
+    pub util_unit_kibibyte: String,
+    /// Util - Unit - Degree Celsius
+    pub util_unit_celsius: String,
 }
 
 #[derive(Deserialize, Serialize)]
 struct ParsedLocalization {
     colonize_window_title: Option<String>,
-    debug_render_info: Option<String>,
+    console_chunk_load_failed: Option<String>,
+    console_dig_queued: Option<String>,
+    console_dig_rejected: Option<String>,
+    console_disconnected: Option<String>,
+    console_dump_failed: Option<String>,
+    console_export_failed: Option<String>,
+    console_game_loaded: Option<String>,
+    console_game_saved: Option<String>,
+    console_host_failed: Option<String>,
+    console_hosting: Option<String>,
+    console_import_failed: Option<String>,
+    console_items_given: Option<String>,
+    console_join_failed: Option<String>,
+    console_joined: Option<String>,
+    console_left: Option<String>,
+    console_load_failed: Option<String>,
+    console_material_set: Option<String>,
+    console_not_in_game: Option<String>,
+    console_not_on_server: Option<String>,
+    console_not_recording: Option<String>,
+    console_player_joined: Option<String>,
+    console_player_left: Option<String>,
+    console_position_not_loaded: Option<String>,
+    console_recording_busy: Option<String>,
+    console_recording_dumped: Option<String>,
+    console_recording_failed: Option<String>,
+    console_recording_imported: Option<String>,
+    console_recording_started: Option<String>,
+    console_recording_stopped: Option<String>,
+    console_replay_diverged: Option<String>,
+    console_replay_exported: Option<String>,
+    console_replay_failed: Option<String>,
+    console_replay_finished: Option<String>,
+    console_replay_read_only: Option<String>,
+    console_replay_started: Option<String>,
+    console_replay_stopped: Option<String>,
+    console_save_failed: Option<String>,
+    console_script_error: Option<String>,
+    console_teleported: Option<String>,
+    console_unknown_command: Option<String>,
+    console_unknown_item: Option<String>,
+    console_unknown_material: Option<String>,
+    console_unsupported_command: Option<String>,
+    console_usage: Option<String>,
+    console_world_generated: Option<String>,
+    debug_events_time: Option<String>,
+    debug_update_time: Option<String>,
+    debug_render_time: Option<String>,
+    debug_profile: Option<String>,
+    debug_tracing: Option<String>,
     gamescene_welcome_text: Option<String>,
+    gamescene_date_year: Option<String>,
+    gamescene_date_spring: Option<String>,
+    gamescene_date_summer: Option<String>,
+    gamescene_date_autumn: Option<String>,
+    gamescene_date_winter: Option<String>,
+    gamescene_designating: Option<String>,
+    gamescene_designation_dig: Option<String>,
+    gamescene_designation_dig_stairs: Option<String>,
+    gamescene_designation_stockpile: Option<String>,
+    gamescene_designation_hunt: Option<String>,
+    gamescene_designation_build: Option<String>,
+    gamescene_overlays_active: Option<String>,
+    gamescene_overlays_hidden: Option<String>,
+    gamescene_structure_wall: Option<String>,
+    gamescene_structure_floor: Option<String>,
+    gamescene_structure_stairs: Option<String>,
+    gamescene_structure_ramp: Option<String>,
+    gamescene_structure_workshop: Option<String>,
+    gamescene_colonist_job: Option<String>,
+    gamescene_colonist_idle: Option<String>,
+    gamescene_colonist_drafted: Option<String>,
+    gamescene_colonist_fighting: Option<String>,
+    gamescene_colonist_health: Option<String>,
+    gamescene_colonist_food: Option<String>,
+    gamescene_colonist_rest: Option<String>,
+    gamescene_colonist_mood: Option<String>,
+    gamescene_colonist_carrying: Option<String>,
+    gamescene_colonist_recent_jobs: Option<String>,
+    gamescene_roster: Option<String>,
+    gamescene_job_dig: Option<String>,
+    gamescene_job_haul: Option<String>,
+    gamescene_job_eat: Option<String>,
+    gamescene_job_sleep: Option<String>,
+    gamescene_job_build: Option<String>,
+    gamescene_job_hunt: Option<String>,
+    gamescene_job_custom: Option<String>,
+    gamescene_tooltip_unexplored: Option<String>,
+    gamescene_tooltip_designation: Option<String>,
+    gamescene_tooltip_items: Option<String>,
+    gamescene_tooltip_invader: Option<String>,
+    gamescene_paused: Option<String>,
+    gamescene_speed: Option<String>,
+    gamescene_weather_clear: Option<String>,
+    gamescene_weather_rain: Option<String>,
+    gamescene_weather_snow: Option<String>,
     gamescene_debug_cursor: Option<String>,
     gamescene_debug_camera: Option<String>,
     gamescene_debug_chunk: Option<String>,
+    gamescene_debug_z_level: Option<String>,
+    gamescene_debug_chunk_count: Option<String>,
+    gamescene_debug_chunk_memory: Option<String>,
+    gamescene_debug_biome: Option<String>,
+    gamescene_debug_entities: Option<String>,
+    gamescene_debug_pending_chunks: Option<String>,
+    gamescene_debug_tile_drawing: Option<String>,
+    gamescene_debug_draw_calls: Option<String>,
+    gamescene_debug_batched: Option<String>,
+    gamescene_debug_unbatched: Option<String>,
+    gamescene_debug_replay: Option<String>,
+    gamescene_debug_replay_diverged: Option<String>,
     internal_failed_to_build_window: Option<String>,
     internal_failed_to_load_font: Option<String>,
-    menuscene_singleplayer: Option<String>,
+    loadscene_title: Option<String>,
+    loadscene_no_saves: Option<String>,
+    loadscene_date: Option<String>,
+    loadscene_saved_at: Option<String>,
+    loadscene_seed: Option<String>,
+    loadscene_load: Option<String>,
+    loadscene_rename: Option<String>,
+    loadscene_type_name: Option<String>,
+    loadscene_delete: Option<String>,
+    loadscene_confirm_delete: Option<String>,
+    loadscene_back: Option<String>,
+    loadscene_rename_failed: Option<String>,
+    loadscene_delete_failed: Option<String>,
+    menuscene_new_game: Option<String>,
+    menuscene_load_game: Option<String>,
     menuscene_options: Option<String>,
     menuscene_credits: Option<String>,
+    menuscene_quit: Option<String>,
+    menuscene_load_failed: Option<String>,
+    optionsscene_title: Option<String>,
+    optionsscene_resolution: Option<String>,
+    optionsscene_fullscreen: Option<String>,
+    optionsscene_vsync: Option<String>,
+    optionsscene_max_fps: Option<String>,
+    optionsscene_master_volume: Option<String>,
+    optionsscene_music_volume: Option<String>,
+    optionsscene_sfx_volume: Option<String>,
+    optionsscene_ui_scale: Option<String>,
+    optionsscene_language: Option<String>,
+    optionsscene_palette: Option<String>,
+    optionsscene_key_bindings: Option<String>,
+    optionsscene_previous_page: Option<String>,
+    optionsscene_next_page: Option<String>,
+    optionsscene_back: Option<String>,
+    optionsscene_on: Option<String>,
+    optionsscene_off: Option<String>,
+    optionsscene_unlimited: Option<String>,
+    optionsscene_auto: Option<String>,
+    optionsscene_press_key: Option<String>,
+    optionsscene_restart_required: Option<String>,
+    optionsscene_save_failed: Option<String>,
+    pausescene_title: Option<String>,
+    pausescene_resume: Option<String>,
+    pausescene_main_menu: Option<String>,
+    pausescene_options: Option<String>,
+    pausescene_quit: Option<String>,
+    reportsscene_title: Option<String>,
+    reportsscene_days: Option<String>,
+    reportsscene_no_days: Option<String>,
+    reportsscene_peak: Option<String>,
+    reportsscene_population: Option<String>,
+    reportsscene_items_mined: Option<String>,
+    reportsscene_food_stock: Option<String>,
+    reportsscene_deaths: Option<String>,
+    reportsscene_back: Option<String>,
+    setupscene_title: Option<String>,
+    setupscene_seed: Option<String>,
+    setupscene_type_seed: Option<String>,
+    setupscene_randomize: Option<String>,
+    setupscene_octaves: Option<String>,
+    setupscene_scale: Option<String>,
+    setupscene_amplitude: Option<String>,
+    setupscene_sea_level: Option<String>,
+    setupscene_caves: Option<String>,
+    setupscene_cave_density: Option<String>,
+    setupscene_site: Option<String>,
+    setupscene_embark: Option<String>,
+    setupscene_back: Option<String>,
     util_unit_millisecond: Option<String>,
     util_unit_fps: Option<String>,
+    util_unit_kibibyte: Option<String>,
+    util_unit_celsius: Option<String>,
 }
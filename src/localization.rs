@@ -1,3 +1,10 @@
+use std::path::PathBuf;
+
+use serde_json;
+use world::{Calendar, Season};
+
+use error::ColonizeResult;
+
 #[cfg(feature = "nightly")]
 include!("localization.in.rs");
 
@@ -8,16 +15,257 @@ create_type_parsing_impls! {
     Localization,
     ParsedLocalization,
     colonize_window_title, "Colonize".to_owned();
-    debug_render_info, "Render Info".to_owned();
+    console_chunk_load_failed, "Failed to load chunk".to_owned();
+    console_dig_queued, "Dig job queued at".to_owned();
+    console_dig_rejected, "Nothing to dig at".to_owned();
+    console_disconnected, "Disconnected from the host".to_owned();
+    console_dump_failed, "Failed to write recording listing".to_owned();
+    console_export_failed, "Failed to export recording".to_owned();
+    console_game_loaded, "Loaded game".to_owned();
+    console_game_saved, "Saved game".to_owned();
+    console_host_failed, "Failed to host game".to_owned();
+    console_hosting, "Hosting game on port".to_owned();
+    console_import_failed, "Failed to import recording listing".to_owned();
+    console_items_given, "Items given at".to_owned();
+    console_join_failed, "Failed to join game".to_owned();
+    console_joined, "Joined game at".to_owned();
+    console_left, "Left the multiplayer game".to_owned();
+    console_load_failed, "Failed to load game".to_owned();
+    console_material_set, "Material set at".to_owned();
+    console_not_in_game, "Not in a multiplayer game".to_owned();
+    console_not_on_server, "Not available on the dedicated server".to_owned();
+    console_not_recording, "Not recording or playing back".to_owned();
+    console_player_joined, "Player joined".to_owned();
+    console_player_left, "Player left".to_owned();
+    console_position_not_loaded, "Position is not loaded".to_owned();
+    console_recording_busy, "Not while recording or playing back".to_owned();
+    console_recording_dumped, "Recording listing written to".to_owned();
+    console_recording_failed, "Failed to record".to_owned();
+    console_recording_imported, "Recording listing imported into".to_owned();
+    console_recording_started, "Recording".to_owned();
+    console_recording_stopped, "Recording saved".to_owned();
+    console_replay_diverged, "Playback diverged from the recording at step".to_owned();
+    console_replay_exported, "Replay exported to".to_owned();
+    console_replay_failed, "Failed to play back recording".to_owned();
+    console_replay_finished, "Playback finished".to_owned();
+    console_replay_read_only, "The replay being viewed cannot be played on from".to_owned();
+    console_replay_started, "Playing back".to_owned();
+    console_replay_stopped, "Playback stopped".to_owned();
+    console_save_failed, "Failed to save game".to_owned();
+    console_script_error, "Error in mod".to_owned();
+    console_teleported, "Teleported to".to_owned();
+    console_unknown_command, "Unknown command".to_owned();
+    console_unknown_item, "Unknown item".to_owned();
+    console_unknown_material, "Unknown material".to_owned();
+    console_unsupported_command, "This command is not supported yet".to_owned();
+    console_usage, "Usage".to_owned();
+    console_world_generated, "Generated world with seed".to_owned();
+    debug_events_time, "Events".to_owned();
+    debug_update_time, "Update".to_owned();
+    debug_render_time, "Render".to_owned();
+    debug_profile, "Profile".to_owned();
+    debug_tracing, "Recording trace (F6 to save)".to_owned();
     gamescene_welcome_text, "Welcome to Colonize!".to_owned();
+    gamescene_date_year, "Year".to_owned();
+    gamescene_date_spring, "Spring".to_owned();
+    gamescene_date_summer, "Summer".to_owned();
+    gamescene_date_autumn, "Autumn".to_owned();
+    gamescene_date_winter, "Winter".to_owned();
+    gamescene_designating, "Designating".to_owned();
+    gamescene_designation_dig, "Dig".to_owned();
+    gamescene_designation_dig_stairs, "Dig stairs".to_owned();
+    gamescene_designation_stockpile, "Stockpile".to_owned();
+    gamescene_designation_hunt, "Hunt".to_owned();
+    gamescene_designation_build, "Build".to_owned();
+    gamescene_overlays_active, "Showing the selected designation only".to_owned();
+    gamescene_overlays_hidden, "Designations hidden".to_owned();
+    gamescene_structure_wall, "wall".to_owned();
+    gamescene_structure_floor, "floor".to_owned();
+    gamescene_structure_stairs, "stairs".to_owned();
+    gamescene_structure_ramp, "ramp".to_owned();
+    gamescene_structure_workshop, "workshop".to_owned();
+    gamescene_colonist_job, "Job".to_owned();
+    gamescene_colonist_idle, "Idle".to_owned();
+    gamescene_colonist_drafted, "Drafted".to_owned();
+    gamescene_colonist_fighting, "Fighting".to_owned();
+    gamescene_colonist_health, "Health".to_owned();
+    gamescene_colonist_food, "Food".to_owned();
+    gamescene_colonist_rest, "Rest".to_owned();
+    gamescene_colonist_mood, "Mood".to_owned();
+    gamescene_colonist_carrying, "Carrying".to_owned();
+    gamescene_colonist_recent_jobs, "Recent jobs".to_owned();
+    gamescene_roster, "Colonists".to_owned();
+    gamescene_job_dig, "Digging".to_owned();
+    gamescene_job_haul, "Hauling".to_owned();
+    gamescene_job_eat, "Eating".to_owned();
+    gamescene_job_sleep, "Sleeping".to_owned();
+    gamescene_job_build, "Building".to_owned();
+    gamescene_job_hunt, "Hunting".to_owned();
+    gamescene_job_custom, "Working".to_owned();
+    gamescene_tooltip_unexplored, "Unexplored".to_owned();
+    gamescene_tooltip_designation, "Designation".to_owned();
+    gamescene_tooltip_items, "Items".to_owned();
+    gamescene_tooltip_invader, "Invader".to_owned();
+    gamescene_paused, "Paused".to_owned();
+    gamescene_speed, "Speed".to_owned();
+    gamescene_weather_clear, "Clear".to_owned();
+    gamescene_weather_rain, "Rain".to_owned();
+    gamescene_weather_snow, "Snow".to_owned();
     gamescene_debug_cursor, "Mouse Cursor".to_owned();
     gamescene_debug_camera, "Camera".to_owned();
     gamescene_debug_chunk, "Chunk".to_owned();
+    gamescene_debug_z_level, "Z-Level".to_owned();
+    gamescene_debug_chunk_count, "Loaded Chunks".to_owned();
+    gamescene_debug_chunk_memory, "Chunk Memory".to_owned();
+    gamescene_debug_biome, "Biome".to_owned();
+    gamescene_debug_entities, "Entities".to_owned();
+    gamescene_debug_pending_chunks, "Pending Chunks".to_owned();
+    gamescene_debug_tile_drawing, "Tile Drawing".to_owned();
+    gamescene_debug_draw_calls, "draw calls".to_owned();
+    gamescene_debug_batched, "batched, F7 to switch".to_owned();
+    gamescene_debug_unbatched, "one by one, F7 to switch".to_owned();
+    gamescene_debug_replay, "Replay step".to_owned();
+    gamescene_debug_replay_diverged, "diverged at".to_owned();
     internal_failed_to_build_window, "Failed to build window".to_owned();
     internal_failed_to_load_font, "Failed to load font".to_owned();
-    menuscene_singleplayer, "S)ingleplayer".to_owned();
+    loadscene_title, "Load Game".to_owned();
+    loadscene_no_saves, "No saved games".to_owned();
+    loadscene_date, "Date".to_owned();
+    loadscene_saved_at, "Saved".to_owned();
+    loadscene_seed, "Seed".to_owned();
+    loadscene_load, "L)oad".to_owned();
+    loadscene_rename, "Rename (F2)".to_owned();
+    loadscene_type_name, "type a name, Enter to confirm".to_owned();
+    loadscene_delete, "D)elete".to_owned();
+    loadscene_confirm_delete, "D)elete, again to confirm".to_owned();
+    loadscene_back, "B)ack".to_owned();
+    loadscene_rename_failed, "Failed to rename the saved game".to_owned();
+    loadscene_delete_failed, "Failed to delete the saved game".to_owned();
+    menuscene_new_game, "N)ew Game".to_owned();
+    menuscene_load_game, "L)oad Game".to_owned();
     menuscene_options, "O)ptions".to_owned();
     menuscene_credits, "C)redits".to_owned();
+    menuscene_quit, "Q)uit".to_owned();
+    menuscene_load_failed, "Failed to load the saved game".to_owned();
+    optionsscene_title, "Options".to_owned();
+    optionsscene_resolution, "Resolution".to_owned();
+    optionsscene_fullscreen, "Fullscreen".to_owned();
+    optionsscene_vsync, "Vsync".to_owned();
+    optionsscene_max_fps, "FPS cap".to_owned();
+    optionsscene_master_volume, "Master volume".to_owned();
+    optionsscene_music_volume, "Music volume".to_owned();
+    optionsscene_sfx_volume, "Effects volume".to_owned();
+    optionsscene_ui_scale, "UI scale".to_owned();
+    optionsscene_language, "Language".to_owned();
+    optionsscene_palette, "Colors".to_owned();
+    optionsscene_key_bindings, "Key bindings".to_owned();
+    optionsscene_previous_page, "Previous page".to_owned();
+    optionsscene_next_page, "Next page".to_owned();
+    optionsscene_back, "B)ack".to_owned();
+    optionsscene_on, "on".to_owned();
+    optionsscene_off, "off".to_owned();
+    optionsscene_unlimited, "unlimited".to_owned();
+    optionsscene_auto, "auto".to_owned();
+    optionsscene_press_key, "press a key".to_owned();
+    optionsscene_restart_required, "Changes to the window take effect after a restart".to_owned();
+    optionsscene_save_failed, "Failed to save the settings".to_owned();
+    pausescene_title, "Paused".to_owned();
+    pausescene_resume, "R)esume".to_owned();
+    pausescene_main_menu, "M)ain Menu".to_owned();
+    pausescene_options, "O)ptions".to_owned();
+    pausescene_quit, "Q)uit".to_owned();
+    reportsscene_title, "Colony reports".to_owned();
+    reportsscene_days, "Days".to_owned();
+    reportsscene_no_days, "No day has ended yet".to_owned();
+    reportsscene_peak, "peak".to_owned();
+    reportsscene_population, "Population".to_owned();
+    reportsscene_items_mined, "Items mined".to_owned();
+    reportsscene_food_stock, "Food stock".to_owned();
+    reportsscene_deaths, "Deaths".to_owned();
+    reportsscene_back, "B)ack".to_owned();
+    setupscene_title, "New World".to_owned();
+    setupscene_seed, "S)eed".to_owned();
+    setupscene_type_seed, "Type a seed, then press Enter".to_owned();
+    setupscene_randomize, "R)andomize Seed".to_owned();
+    setupscene_octaves, "Octaves".to_owned();
+    setupscene_scale, "Terrain Scale".to_owned();
+    setupscene_amplitude, "Height Variation".to_owned();
+    setupscene_sea_level, "Sea Level".to_owned();
+    setupscene_caves, "Caves".to_owned();
+    setupscene_cave_density, "Cave Density".to_owned();
+    setupscene_site, "Embark site".to_owned();
+    setupscene_embark, "E)mbark".to_owned();
+    setupscene_back, "B)ack".to_owned();
     util_unit_millisecond, "ms".to_owned();
     util_unit_fps, "FPS".to_owned();
+    util_unit_kibibyte, "KiB".to_owned();
+    util_unit_celsius, "°C".to_owned();
+}
+
+/// The language from which any string missing from another language is
+/// taken, before falling back to the built-in strings.
+pub const FALLBACK_LANGUAGE: &'static str = "en_CA";
+
+const LOCALIZATION_DIR: &'static str = "localization/";
+const LOCALIZATION_FILE_EXTENSION: &'static str = "json";
+
+impl Localization {
+    /// Loads the strings of `language` from the localization directory of
+    /// `asset_path`.
+    ///
+    /// Any string missing from the file of the language is taken from that of
+    /// its base language (such as `fr` for `fr_CA`), then from that of the
+    /// fallback language, and finally from the built-in English strings, so
+    /// that a partial translation can still be used.
+    pub fn load(asset_path: &PathBuf, language: &str) -> Localization {
+        // Each language is only loaded at the last of its places in the
+        // chain, from which the strings of later languages take precedence.
+        let base = language.split('_').next().unwrap_or(language);
+        let mut chain = Vec::new();
+        for name in vec![FALLBACK_LANGUAGE, base, language] {
+            chain.retain(|other| *other != name);
+            chain.push(name);
+        }
+
+        let mut localization = Localization::default();
+        for name in chain {
+            let path = localization_file(asset_path, name);
+            if name != language && !path.exists() {
+                continue;
+            }
+            match read_parsed_localization(&path) {
+                Ok(parsed) => localization = localization.fill_from_parsed(parsed),
+                Err(e) => warn!("Failed to load localization {}: {}", name, e),
+            }
+        }
+        localization
+    }
+
+    /// Returns the in-game date of `calendar`, such as "Spring 3, Year 1".
+    pub fn date(&self, calendar: &Calendar) -> String {
+        let season = match calendar.season() {
+            Season::Spring => &self.gamescene_date_spring,
+            Season::Summer => &self.gamescene_date_summer,
+            Season::Autumn => &self.gamescene_date_autumn,
+            Season::Winter => &self.gamescene_date_winter,
+        };
+        format!("{} {}, {} {}", season, calendar.day(), self.gamescene_date_year, calendar.year())
+    }
+
+    /// Returns the languages which have a file in the localization directory
+    /// of `asset_path`, in the order of their names.
+    pub fn languages(asset_path: &PathBuf) -> Vec<String> {
+        ::file_stems(&asset_path.join(LOCALIZATION_DIR), LOCALIZATION_FILE_EXTENSION)
+    }
+}
+
+fn localization_file(asset_path: &PathBuf, language: &str) -> PathBuf {
+    let mut path = asset_path.join(LOCALIZATION_DIR).join(language);
+    path.set_extension(LOCALIZATION_FILE_EXTENSION);
+    path
+}
+
+fn read_parsed_localization(path: &PathBuf) -> ColonizeResult<ParsedLocalization> {
+    let json = try!(::read_file_to_string(path));
+    Ok(try!(serde_json::from_str(&json)))
 }
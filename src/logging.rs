@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use log::{self, LogLevelFilter, LogMetadata, LogRecord};
+
+use paths;
+
+const LOGS_DIR: &'static str = "logs/";
+const LOG_FILE_NAME: &'static str = "colonize";
+const LOG_FILE_EXTENSION: &'static str = "log";
+/// Size, in bytes, beyond which the log file is rotated.
+const MAX_LOG_FILE_SIZE: u64 = 1024 * 1024;
+/// The number of rotated log files kept besides the current one.
+const ROTATED_LOG_FILES: u32 = 3;
+/// The number of the most recent log lines kept for the game to show.
+const MAX_RECENT_LINES: usize = 64;
+
+lazy_static! {
+    static ref RECENT_LINES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+}
+
+/// Starts logging to `logs/colonize.log` in the cache directory, with the
+/// previous log files rotated to `colonize.1.log`, `colonize.2.log` and so
+/// on, and to stderr unless `to_stderr` is false, as it is while the game is
+/// drawn in the terminal.
+///
+/// Messages are logged at `level`, or at the level of the longest module
+/// path in `filters` which the module logging them starts with.
+pub fn init(level: &str, filters: &BTreeMap<String, String>, to_stderr: bool) {
+    let level = match level.parse() {
+        Ok(level) => level,
+        Err(_) => {
+            let _ = writeln!(io::stderr(), "Invalid log level {}, logging at info", level);
+            LogLevelFilter::Info
+        },
+    };
+    let mut module_levels = Vec::new();
+    for (module, module_level) in filters {
+        match module_level.parse() {
+            Ok(module_level) => module_levels.push((module.clone(), module_level)),
+            Err(_) => { let _ = writeln!(io::stderr(), "Invalid log level {} of module {}, which is ignored", module_level, module); },
+        }
+    }
+    // The longest paths come first, so that the most specific filter of a
+    // module is the first one matching it.
+    module_levels.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+
+    let file = match LogFile::open() {
+        Ok(file) => Some(file),
+        Err(e) => {
+            let _ = writeln!(io::stderr(), "Failed to open the log file, logging to stderr only: {}", e);
+            None
+        },
+    };
+
+    let max_level = module_levels.iter().map(|&(_, level)| level).fold(level, ::std::cmp::max);
+    let logger = Logger {
+        level: level,
+        module_levels: module_levels,
+        to_stderr: to_stderr,
+        file: Mutex::new(file),
+    };
+    let result = log::set_logger(|max_log_level| {
+        max_log_level.set(max_level);
+        Box::new(logger)
+    });
+    if let Err(e) = result {
+        let _ = writeln!(io::stderr(), "Failed to start logging: {}", e);
+    }
+}
+
+/// Returns up to `count` of the most recently logged lines, oldest first.
+pub fn recent_lines(count: usize) -> Vec<String> {
+    let lines = match RECENT_LINES.lock() {
+        Ok(lines) => lines,
+        Err(_) => return Vec::new(),
+    };
+    let skipped = lines.len().saturating_sub(count);
+    lines.iter().skip(skipped).cloned().collect()
+}
+
+struct Logger {
+    level: LogLevelFilter,
+    module_levels: Vec<(String, LogLevelFilter)>,
+    to_stderr: bool,
+    file: Mutex<Option<LogFile>>,
+}
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &LogMetadata) -> bool {
+        let level = self.module_levels.iter()
+            .find(|&&(ref module, _)| metadata.target().starts_with(module.as_str()))
+            .map_or(self.level, |&(_, level)| level);
+        metadata.level() <= level
+    }
+
+    fn log(&self, record: &LogRecord) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!("{} {}: {}", record.level(), record.target(), record.args());
+        if self.to_stderr {
+            let _ = writeln!(io::stderr(), "{}", line);
+        }
+
+        if let Ok(mut file) = self.file.lock() {
+            let failed = match *file {
+                Some(ref mut file) => file.write_line(&format!("{} {}", paths::timestamp(), line)).is_err(),
+                None => false,
+            };
+            // Logging carries on to stderr alone, rather than failing on
+            // every message.
+            if failed {
+                *file = None;
+            }
+        }
+
+        if let Ok(mut lines) = RECENT_LINES.lock() {
+            lines.push_back(line);
+            while lines.len() > MAX_RECENT_LINES {
+                lines.pop_front();
+            }
+        }
+    }
+}
+
+/// The log file, which is rotated once it grows too large.
+struct LogFile {
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    /// Opens a new log file, rotating the one left by the previous run.
+    fn open() -> io::Result<LogFile> {
+        try!(fs::create_dir_all(paths::cache_dir().join(LOGS_DIR)));
+        try!(rotate());
+        Ok(LogFile {
+            file: try!(File::create(log_file_path(0))),
+            size: 0,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.size + line.len() as u64 > MAX_LOG_FILE_SIZE {
+            *self = try!(LogFile::open());
+        }
+        try!(writeln!(self.file, "{}", line));
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+/// Shifts every log file to the next index, dropping the oldest.
+fn rotate() -> io::Result<()> {
+    for index in (0..ROTATED_LOG_FILES).rev() {
+        let path = log_file_path(index);
+        if path.exists() {
+            try!(fs::rename(&path, log_file_path(index + 1)));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the path of the current log file if `index` is zero, or else of
+/// the log file rotated `index` times.
+fn log_file_path(index: u32) -> PathBuf {
+    let name = match index {
+        0 => format!("{}.{}", LOG_FILE_NAME, LOG_FILE_EXTENSION),
+        index => format!("{}.{}.{}", LOG_FILE_NAME, index, LOG_FILE_EXTENSION),
+    };
+    paths::cache_dir().join(LOGS_DIR).join(name)
+}
@@ -0,0 +1,184 @@
+use world::World;
+
+#[cfg(feature = "hot-reload")]
+use self::reload::GameLibrary;
+
+// Seconds between checks for a newer build of the game library.
+const RELOAD_INTERVAL: f64 = 1.0;
+
+/// Runs the steps of the simulation of a world.
+///
+/// With the `hot-reload` feature, the steps are run by the `colonize_game`
+/// library once it has been built, which is loaded again whenever it is
+/// rebuilt, so that changes to the simulation are picked up while the world
+/// keeps its state. Until the library is built, and without the feature, the
+/// steps are run by the code the game itself was built with.
+pub struct GameLogic {
+    /// The build of the library loaded last, if any.
+    library: Option<GameLibrary>,
+    /// Seconds until the library is next checked for a newer build.
+    reload_timer: f64,
+}
+
+impl GameLogic {
+    pub fn new() -> Self {
+        GameLogic {
+            library: GameLibrary::load_newer(None),
+            reload_timer: RELOAD_INTERVAL,
+        }
+    }
+
+    /// Advances the timer by `dt` seconds, loading the library again if it
+    /// has been rebuilt since it was last checked.
+    pub fn update(&mut self, dt: f64) {
+        self.reload_timer -= dt;
+        if self.reload_timer > 0.0 {
+            return;
+        }
+        self.reload_timer = RELOAD_INTERVAL;
+
+        if let Some(library) = GameLibrary::load_newer(self.library.as_ref()) {
+            // The last build is only unloaded once nothing can call into it.
+            self.library = Some(library);
+        }
+    }
+
+    /// Runs a step of the simulation of `world`.
+    pub fn tick(&self, world: &mut World) {
+        match self.library {
+            Some(ref library) => library.tick(world),
+            None => world.tick(),
+        }
+    }
+}
+
+impl Default for GameLogic {
+    fn default() -> Self {
+        GameLogic::new()
+    }
+}
+
+/// Stands in for the game library without the `hot-reload` feature, under
+/// which it is never loaded.
+#[cfg(not(feature = "hot-reload"))]
+enum GameLibrary {}
+
+#[cfg(not(feature = "hot-reload"))]
+impl GameLibrary {
+    fn load_newer(_loaded: Option<&GameLibrary>) -> Option<GameLibrary> {
+        None
+    }
+
+    fn tick(&self, _world: &mut World) {
+        match *self {}
+    }
+}
+
+#[cfg(feature = "hot-reload")]
+mod reload {
+    use std::env;
+    use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
+    use std::time::SystemTime;
+
+    use libloading::{Library, Symbol};
+    use world::World;
+
+    use error::ColonizeResult;
+
+    const LIBRARY_NAME: &'static str = "colonize_game";
+    const TICK_SYMBOL: &'static [u8] = b"colonize_game_tick\0";
+
+    /// Counts the builds of the library loaded so far, to give each copy of
+    /// it a name of its own.
+    static LOADED_COUNT: AtomicUsize = ATOMIC_USIZE_INIT;
+
+    type TickFn = unsafe extern "C" fn(*mut World) -> bool;
+
+    /// A build of the `colonize_game` library, loaded from a copy of it so
+    /// that the library can be rebuilt while it is loaded.
+    pub struct GameLibrary {
+        /// The function by which the library runs a step of the simulation,
+        /// which may only be called while `library` is loaded.
+        tick: TickFn,
+        library: Option<Library>,
+        /// When the library which was copied was last modified.
+        modified: SystemTime,
+        copy_path: PathBuf,
+    }
+
+    impl GameLibrary {
+        /// Loads the library if it has been built since `loaded` was, or
+        /// at all if no build of it has been loaded yet.
+        pub fn load_newer(loaded: Option<&GameLibrary>) -> Option<GameLibrary> {
+            let path = match library_path() {
+                Ok(path) => path,
+                Err(_) => return None,
+            };
+            let modified = match fs::metadata(&path).and_then(|metadata| metadata.modified()) {
+                Ok(modified) => modified,
+                // The library has not been built.
+                Err(_) => return None,
+            };
+            if loaded.map_or(false, |loaded| loaded.modified >= modified) {
+                return None;
+            }
+
+            match GameLibrary::load(&path, modified) {
+                Ok(library) => {
+                    info!("Loaded the game library from {}", path.display());
+                    Some(library)
+                },
+                Err(e) => {
+                    warn!("Failed to load the game library: {}", e);
+                    None
+                },
+            }
+        }
+
+        fn load(path: &PathBuf, modified: SystemTime) -> ColonizeResult<GameLibrary> {
+            // The library is copied before it is loaded, as it could not be
+            // replaced by the next build on every platform while loaded, and
+            // the loader could hand back the build it already has.
+            let count = LOADED_COUNT.fetch_add(1, Ordering::SeqCst);
+            let copy_path = path.with_file_name(format!("{}{}-{}{}", DLL_PREFIX, LIBRARY_NAME, count, DLL_SUFFIX));
+            try!(fs::copy(path, &copy_path));
+
+            let library = try!(Library::new(&copy_path));
+            let tick = {
+                let tick: Symbol<TickFn> = try!(unsafe { library.get(TICK_SYMBOL) });
+                *tick
+            };
+            Ok(GameLibrary {
+                tick: tick,
+                library: Some(library),
+                modified: modified,
+                copy_path: copy_path,
+            })
+        }
+
+        pub fn tick(&self, world: &mut World) {
+            if !unsafe { (self.tick)(world) } {
+                panic!("The game library panicked while running a step of the simulation");
+            }
+        }
+    }
+
+    impl Drop for GameLibrary {
+        fn drop(&mut self) {
+            // The copy can only be removed once it is unloaded.
+            self.library.take();
+            let _ = fs::remove_file(&self.copy_path);
+        }
+    }
+
+    /// Returns the path at which the library is built, beside the executable
+    /// of the game.
+    fn library_path() -> io::Result<PathBuf> {
+        let exe = try!(env::current_exe());
+        Ok(exe.with_file_name(format!("{}{}{}", DLL_PREFIX, LIBRARY_NAME, DLL_SUFFIX)))
+    }
+}
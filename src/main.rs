@@ -1,40 +1,84 @@
 #![cfg_attr(feature = "nightly", feature(custom_derive, plugin))]
 #![cfg_attr(feature = "nightly", plugin(serde_macros))]
+#![cfg_attr(feature = "hot-reload", feature(alloc_system))]
 #![cfg_attr(feature = "clippy", plugin(clippy))]
 #![cfg_attr(feature = "clippy", allow(used_underscore_binding))]
 
+// With the `hot-reload` feature, the memory of the world is shared with the
+// game library, which is built with the system allocator.
+#[cfg(feature = "hot-reload")]
+extern crate alloc_system;
 extern crate cgmath;
 extern crate fps_counter;
+extern crate gl;
 extern crate glium_graphics;
+extern crate glutin;
 extern crate graphics;
+extern crate image;
+#[macro_use]
+extern crate lazy_static;
+#[cfg(feature = "hot-reload")]
+extern crate libloading;
+#[cfg(unix)]
+extern crate libc;
+#[macro_use]
+extern crate log;
+extern crate notify;
 extern crate opengl_graphics;
 extern crate piston;
+extern crate rand;
 #[macro_use]
 extern crate rgframework;
+extern crate sdl2;
 extern crate serde;
 extern crate serde_json;
 extern crate shader_version;
-extern crate time;
+extern crate toml;
+extern crate colonize;
 extern crate colonize_utility as utility;
 extern crate colonize_world as world;
 
 mod action;
+mod ai_overlay;
+mod animation;
+mod assets;
+mod audio;
 mod backend;
 mod camera;
-mod config;
+mod cursor;
+mod debug_overlay;
+mod display;
+mod frame_clock;
 mod game;
-mod localization;
+#[cfg(test)]
+mod harness;
+#[cfg(any(unix, test))]
+mod headless;
+mod inspection_panel;
+mod isometric;
+mod logic;
+mod particles;
+mod recording;
+mod renderer;
+mod roster;
 mod scene;
-mod textures;
+mod screenshot;
+mod settings;
+mod slice_cache;
+mod slice_textures;
+#[cfg(unix)]
+mod terminal;
+mod tile_atlas;
+mod tile_batch;
+mod tile_renderer;
 
-use std::error;
-use std::fs::File;
-use std::io::Read;
+use std::env;
+use std::io::{self, Write};
 use std::path::PathBuf;
+use std::process;
 
 use glium_graphics::GliumWindow as Window;
 use opengl_graphics::GlGraphics;
-use opengl_graphics::glyph_cache::GlyphCache;
 use piston::window::{
     BuildFromWindowSettings,
     Size,
@@ -42,80 +86,193 @@ use piston::window::{
 };
 use shader_version::OpenGL;
 
+// The modules shared with the dedicated server are kept in the library, and
+// used by the modules of the game as if they were its own.
+use colonize::{
+    command,
+    config,
+    console,
+    crash,
+    designation,
+    error,
+    localization,
+    logging,
+    materials,
+    migration,
+    minimap,
+    mods,
+    net,
+    palette,
+    paths,
+    save,
+    scripting,
+    simulation,
+};
+use colonize::read_file_to_string;
+
+use assets::AssetManager;
+use audio::AudioManager;
 use config::Config;
+use error::{ColonizeError, ColonizeResult};
 use localization::Localization;
+use palette::Palette;
 use game::Game;
-
-type ColonizeError = Box<error::Error>;
-type ColonizeResult<T> = std::result::Result<T, ColonizeError>;
+use scripting::ScriptHost;
+use settings::Settings;
+use world::MaterialRegistry;
 
 const CONFIG_PATH: &'static str = "colonize.json";
-const FONT_DIR: &'static str = "fonts/";
-const LOCALIZATION_DIR: &'static str = "localization/";
-const TEXTURES_DIR: &'static str = "textures/";
-const LOCALIZATION_FILE_EXTENSION: &'static str = "json";
+const AUDIO_DIR: &'static str = "audio/";
+/// The argument with which the game is drawn in the terminal rather than in a
+/// window.
+const TERMINAL_ARG: &'static str = "--terminal";
 
 const OPENGL_VERSION: OpenGL = OpenGL::V3_2;
 
+/// Upgrades every save to the current version of the save format, instead
+/// of running the game.
+const MIGRATE_SAVES_FLAG: &'static str = "--migrate-saves";
+/// Views the replay exported to the directory given after it, instead of
+/// starting at the menu.
+const VIEW_REPLAY_FLAG: &'static str = "--view-replay";
+
 fn main() {
+    crash::install();
+
+    if env::args().skip(1).any(|arg| arg == MIGRATE_SAVES_FLAG) {
+        process::exit(if migrate_saves() { 0 } else { 1 });
+    }
+
+    let replay = env::args().skip_while(|arg| arg != VIEW_REPLAY_FLAG).nth(1).map(PathBuf::from);
+    if let Err(e) = run(replay) {
+        error!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run(replay: Option<PathBuf>) -> ColonizeResult<()> {
     // Load the configuration from its JSON file, falling back to the default
-    // configuration in the event of an error.
+    // configuration if it cannot be read. A file which cannot be parsed is
+    // reported rather than ignored.
     let config = match read_file_to_string(&CONFIG_PATH.into()) {
-        Ok(json) => Config::from_json(&json),
+        Ok(json) => try!(Config::from_json(&json)),
         Err(_) => Config::default(),
     };
 
+    // Load the player's settings from their TOML file, falling back to the
+    // default settings in the event of an error.
+    let settings = match Settings::load(&settings::settings_path()) {
+        Ok(settings) => settings,
+        Err(_) => Settings::default(),
+    };
+
+    // With `--terminal`, the game is drawn in the terminal, starting from the
+    // save named after it if there is one.
+    let args: Vec<String> = env::args().skip(1).collect();
+    let in_terminal = args.first().map_or(false, |arg| arg == TERMINAL_ARG);
+
+    // Start logging, at the levels chosen in the settings. Nothing is logged
+    // to stderr while the game is drawn in the terminal.
+    logging::init(&settings.log_level, &settings.log_filters, !in_terminal);
+
+    // Detect the scale of the interface from the DPI of the display before
+    // SDL is initialized for audio.
+    display::detected_ui_scale();
+
     // Define the asset path.
     let asset_path: PathBuf = (&config.asset_path).into();
 
-    // Load the localization file for the specified language, falling back to
-    // the default localization definition in the event of an error.
-    let mut localization_file = asset_path.join(LOCALIZATION_DIR)
-        .join(&config.language);
-    localization_file.set_extension(LOCALIZATION_FILE_EXTENSION);
-    let localization = match read_file_to_string(&localization_file) {
-        Ok(json) => Localization::from_json(&json),
-        Err(_) => Localization::default(),
-    };
+    // Load the localization of the language chosen by the player, falling
+    // back to English for any string it is missing.
+    let localization = Localization::load(&asset_path, &settings.language);
+
+    // Load the colors chosen by the player.
+    let palette = Palette::load(&asset_path, &settings.palette);
+
+    // Find the mods, in the order they are layered on top of the assets of
+    // the game.
+    let mod_packs = mods::find_mod_packs(&(&config.mods_path).into());
+
+    // Load the scripts of the mods, which may define materials of their own.
+    let mut scripts = ScriptHost::load(&mod_packs);
+
+    // Load the materials from which the world is made.
+    let materials = materials::load_material_registry(&asset_path, &mod_packs, scripts.take_materials());
+
+    if in_terminal {
+        return run_in_terminal(config, settings, localization, palette, materials, scripts, args.get(1).map(|name| name.as_str()));
+    }
 
     // Initialize the window and graphics backend.
-    let window: Window = make_window(&config, &localization);
+    let window: Window = try!(make_window(&config, &settings, &localization));
     let mut gl = GlGraphics::new(OPENGL_VERSION);
 
+    // Read the asset manifests, by which fonts and textures are loaded.
+    let assets = AssetManager::load(&asset_path, &mod_packs);
+
     // Initialize the glyph cache.
-    let mut glyph_cache = GlyphCache::new(&asset_path.join(FONT_DIR).join(&config.font_file))
-        .expect(&localization.internal_failed_to_load_font);
+    let mut glyph_cache = try!(assets.load_font(&config.font)
+        .map_err(|e| ColonizeError::Font(format!("{}: {}", localization.internal_failed_to_load_font, e))));
+
+    // Load the tile atlas used to render the world.
+    let tile_renderer = assets.load_tile_renderer();
 
-    // Load all required textures.
-    let textures_path = asset_path.join(TEXTURES_DIR);
-    let textures = textures::load_textures_opengl(&textures_path);
+    // Open the audio device and load the music and sound effects.
+    let audio = AudioManager::load(&asset_path.join(AUDIO_DIR), &settings);
 
     // Construct the `Game` object and run the game.
-    let mut game = Game::new(config, localization, window, textures);
+    let mut game = Game::new(config, settings, localization, palette, materials, window, assets, tile_renderer, audio, scripts, replay);
     game.run(&mut gl, &mut glyph_cache);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn run_in_terminal(config: Config, settings: Settings, localization: Localization, palette: Palette, materials: MaterialRegistry, scripts: ScriptHost, save_name: Option<&str>) -> ColonizeResult<()> {
+    terminal::run(config, settings, localization, palette, materials, scripts, save_name)
+}
+
+#[cfg(not(unix))]
+fn run_in_terminal(_config: Config, _settings: Settings, _localization: Localization, _palette: Palette, _materials: MaterialRegistry, _scripts: ScriptHost, _save_name: Option<&str>) -> ColonizeResult<()> {
+    Err(ColonizeError::Terminal("the game can only be drawn in the terminal on Unix".to_owned()))
 }
 
-fn read_file_to_string(path: &PathBuf) -> ColonizeResult<String> {
-    let mut file = try!(File::open(&path));
-    let mut file_str = String::new();
-    try!(file.read_to_string(&mut file_str));
-    Ok(file_str)
+/// Upgrades every save in place, reporting the outcome for each. Returns
+/// whether all of them could be upgraded.
+fn migrate_saves() -> bool {
+    let mut succeeded = true;
+    for name in save::save_names() {
+        match save::migrate_save(&name) {
+            Ok((version, regions)) if version == migration::SAVE_VERSION && regions == 0 => {
+                println!("{}: already up to date", name);
+            },
+            Ok((version, regions)) => {
+                println!("{}: upgraded from version {} to {}, rewriting {} region files", name, version, migration::SAVE_VERSION, regions);
+            },
+            Err(e) => {
+                let _ = writeln!(io::stderr(), "{}: failed to upgrade: {}", name, e);
+                succeeded = false;
+            },
+        }
+    }
+    succeeded
 }
 
-fn make_window<W>(config: &Config, localization: &Localization) -> W
+fn make_window<W>(config: &Config, settings: &Settings, localization: &Localization) -> ColonizeResult<W>
     where W: BuildFromWindowSettings,
 {
     WindowSettings::new(
             localization.colonize_window_title.clone(),
             Size {
-                width: config.window_width,
-                height: config.window_height,
+                width: settings.window_width,
+                height: settings.window_height,
             }
         )
         .exit_on_esc(config.exit_on_esc)
-        .fullscreen(config.fullscreen)
-        .vsync(config.vsync)
+        .fullscreen(settings.fullscreen)
+        .resizable(true)
+        .vsync(settings.vsync)
         .opengl(OPENGL_VERSION)
         .build()
-        .expect(&localization.internal_failed_to_build_window)
+        .map_err(|e| ColonizeError::Window(format!("{}: {}", localization.internal_failed_to_build_window, e)))
 }
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde_json;
+use world::{Material, MaterialId, MaterialRegistry};
+
+use error::ColonizeResult;
+use mods::ModPack;
+
+const MATERIALS_FILE: &'static str = "materials.json";
+
+/// Loads the material definitions from the asset directory, falling back to
+/// the built-in materials if they cannot be loaded, and layers those defined
+/// by each mod in `mod_packs` on top of them, in load order.
+///
+/// The materials of a mod are read from its own materials file, followed by
+/// those its script defined, taken from `script_materials` by the name of the
+/// mod. A material replaces any of the same name defined before it, which is
+/// reported as an override. A material whose id is already used by one of
+/// another name is left out, and reported as a conflict, so that one mod
+/// cannot keep the others from being used.
+pub fn load_material_registry(asset_path: &PathBuf, mod_packs: &[ModPack], mut script_materials: HashMap<String, Vec<Material>>) -> MaterialRegistry {
+    let registry = match read_material_registry(&asset_path.join(MATERIALS_FILE)) {
+        Ok(registry) => registry,
+        Err(e) => {
+            warn!("Failed to load materials, using built-in materials: {}", e);
+            MaterialRegistry::default()
+        },
+    };
+
+    let mut materials: Vec<Material> = registry.iter().cloned().collect();
+    let mut changed = false;
+    for mod_pack in mod_packs {
+        let mut added = Vec::new();
+        let path = mod_pack.path.join(MATERIALS_FILE);
+        if path.exists() {
+            match read_materials(&path) {
+                Ok(file_materials) => added.extend(file_materials),
+                Err(e) => warn!("Failed to load the materials of mod {}: {}", mod_pack.name, e),
+            }
+        }
+        if let Some(defined) = script_materials.remove(&mod_pack.name) {
+            added.extend(defined);
+        }
+
+        for material in added {
+            if let Some(other) = materials.iter().find(|other| other.id == material.id && other.name != material.name) {
+                warn!("Mod {} defines material {} with the id {} of material {}, which is left out",
+                    mod_pack.name, material.name, material.id, other.name);
+                continue;
+            }
+            match materials.iter().position(|other| other.name == material.name) {
+                Some(index) => {
+                    info!("Mod {} overrides material {}", mod_pack.name, material.name);
+                    materials[index] = material;
+                },
+                None => materials.push(material),
+            }
+            changed = true;
+        }
+    }
+    if !changed {
+        return registry;
+    }
+
+    match MaterialRegistry::new(materials) {
+        Ok(registry) => registry,
+        Err(e) => {
+            warn!("Failed to add the materials of mods: {}", e);
+            registry
+        },
+    }
+}
+
+/// Returns the material of `materials` referred to by `name`, which may be
+/// either the name or the numeric identifier of a material.
+pub fn find_material(materials: &MaterialRegistry, name: &str) -> Option<MaterialId> {
+    if let Some(id) = materials.find(name) {
+        return Some(id);
+    }
+
+    match name.parse::<u16>() {
+        Ok(id) if materials.get(MaterialId(id)).is_some() => Some(MaterialId(id)),
+        _ => None,
+    }
+}
+
+fn read_material_registry(path: &PathBuf) -> ColonizeResult<MaterialRegistry> {
+    Ok(try!(MaterialRegistry::new(try!(read_materials(path)))))
+}
+
+fn read_materials(path: &PathBuf) -> ColonizeResult<Vec<Material>> {
+    let json = try!(::read_file_to_string(path));
+    Ok(try!(serde_json::from_str(&json)))
+}
@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+use serde_json;
+use serde_json::Value;
+use world::{Calendar, Stats};
+
+use error::{ColonizeError, ColonizeResult};
+
+/// The version of the metadata of the saves written by this version of the
+/// game, increased whenever the format of the metadata changes.
+pub const SAVE_VERSION: u64 = 3;
+/// The version of saves written before their metadata recorded a version.
+const UNVERSIONED_SAVE_VERSION: u64 = 1;
+
+/// Upgrades the metadata of a save from one version to the next.
+type Migration = fn(&mut Value) -> ColonizeResult<()>;
+
+/// The migration at index `i` upgrades the metadata of a save from version
+/// `i + 1` to version `i + 2`. A migration is added here whenever
+/// `SAVE_VERSION` is increased, so that saves of every older version can
+/// still be loaded.
+const MIGRATIONS: &'static [Migration] = &[
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+];
+
+/// Upgrades `metadata`, the metadata of a save, to `SAVE_VERSION` by applying
+/// each migration from its version on in turn. Returns the version the save
+/// was upgraded from, or an error if it was written by a newer version of the
+/// game or records a version no game ever wrote.
+pub fn migrate(metadata: &mut Value) -> ColonizeResult<u64> {
+    let version = try!(object(metadata)).get("version")
+        .and_then(Value::as_u64)
+        .unwrap_or(UNVERSIONED_SAVE_VERSION);
+    if version > SAVE_VERSION {
+        return Err(ColonizeError::SaveVersion(version));
+    }
+    if version < UNVERSIONED_SAVE_VERSION {
+        return Err(invalid_metadata(&format!("invalid save version {}", version)));
+    }
+
+    // With the version in range, the migrations from it on are always found,
+    // as there is one for each version before `SAVE_VERSION`.
+    for migration in &MIGRATIONS[(version - UNVERSIONED_SAVE_VERSION) as usize..] {
+        try!(migration(metadata));
+    }
+    try!(object(metadata)).insert("version".to_owned(), Value::U64(SAVE_VERSION));
+
+    Ok(version)
+}
+
+/// Version 2 records when the game was saved, and the chunk column around
+/// which the world was first generated. Saves of version 1 are recorded as
+/// saved at an unknown time, and as embarked at the origin, around which
+/// every world used to be generated.
+fn migrate_v1_to_v2(metadata: &mut Value) -> ColonizeResult<()> {
+    let metadata = try!(object(metadata));
+    metadata.entry("saved_at".to_owned()).or_insert(Value::U64(0));
+
+    let state = match metadata.get_mut("state") {
+        Some(state) => try!(object(state)),
+        None => return Err(invalid_metadata("missing state")),
+    };
+    state.entry("embark".to_owned()).or_insert_with(|| Value::Array(vec![Value::I64(0), Value::I64(0)]));
+    Ok(())
+}
+
+/// Version 3 keeps the metrics of the colony for every day. Saves of version
+/// 2 start keeping them from the day on which they were saved.
+fn migrate_v2_to_v3(metadata: &mut Value) -> ColonizeResult<()> {
+    let metadata = try!(object(metadata));
+    let state = match metadata.get_mut("state") {
+        Some(state) => try!(object(state)),
+        None => return Err(invalid_metadata("missing state")),
+    };
+    let calendar: Calendar = match state.get("calendar") {
+        Some(calendar) => try!(serde_json::from_value(calendar.clone())),
+        None => Calendar::default(),
+    };
+    state.insert("stats".to_owned(), serde_json::to_value(&Stats::new(calendar.days())));
+    Ok(())
+}
+
+fn object(value: &mut Value) -> ColonizeResult<&mut BTreeMap<String, Value>> {
+    value.as_object_mut().ok_or_else(|| invalid_metadata("expected an object"))
+}
+
+fn invalid_metadata(message: &str) -> ColonizeError {
+    ColonizeError::InvalidSave(message.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use error::ColonizeError;
+    use super::{migrate, SAVE_VERSION};
+
+    fn metadata_of_version(version: u64) -> serde_json::Value {
+        serde_json::from_str(&format!("{{\"version\": {}, \"state\": {{}}}}", version)).unwrap()
+    }
+
+    #[test]
+    fn rejects_version_zero() {
+        match migrate(&mut metadata_of_version(0)) {
+            Err(ColonizeError::InvalidSave(_)) => {},
+            _ => panic!("expected an invalid save"),
+        }
+    }
+
+    #[test]
+    fn rejects_versions_newer_than_the_game() {
+        match migrate(&mut metadata_of_version(SAVE_VERSION + 1)) {
+            Err(ColonizeError::SaveVersion(version)) => assert_eq!(version, SAVE_VERSION + 1),
+            _ => panic!("expected a newer save version"),
+        }
+    }
+
+    #[test]
+    fn leaves_current_version_as_it_is() {
+        assert_eq!(migrate(&mut metadata_of_version(SAVE_VERSION)).unwrap(), SAVE_VERSION);
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+
+use cgmath::{Point2, Point3};
+use graphics;
+use rgframework::backend::{Backend, Graphics};
+use rgframework::backend::graphics::{Context, CreateTexture, Format, TextureSettings};
+use world::{Area, MaterialRegistry, CHUNK_SIZE, LOG2_OF_CHUNK_SIZE};
+
+use palette::Palette;
+
+/// The number of chunk columns shown along each side of the minimap.
+const MINIMAP_CHUNKS: i32 = 8;
+/// The number of tiles shown along each side of the minimap, one per pixel of
+/// its texture.
+const MINIMAP_TILES: i32 = MINIMAP_CHUNKS * CHUNK_SIZE as i32;
+/// The size of the minimap on screen, and its distance from the edges of the
+/// window, in pixels at a UI scale of 1.
+const MINIMAP_SIZE: f64 = 192.0;
+const MINIMAP_MARGIN: f64 = 10.0;
+const BORDER_RADIUS: f64 = 1.0;
+/// The number of tiles above the generated surface at which the search for
+/// the surface of a column starts, so that anything built on it is shown.
+const SCAN_HEIGHT: i32 = 8;
+/// The number of tiles searched for the surface of a column.
+const SCAN_DEPTH: i32 = 32;
+/// The brightness of the surface at elevation zero, its change per tile of
+/// elevation, and the range within which it is kept.
+const BASE_BRIGHTNESS: f32 = 0.7;
+const HEIGHT_SHADING: f32 = 0.02;
+const MIN_BRIGHTNESS: f32 = 0.4;
+const MAX_BRIGHTNESS: f32 = 1.0;
+
+/// A map of the explored surface around the camera, drawn in a corner of the
+/// screen with the area covered by the tile view outlined on it.
+///
+/// The map is drawn into a texture one chunk column at a time, and a column
+/// is only drawn again once one of the chunks its surface was found in has
+/// been modified (or loaded). The texture itself is only replaced when a
+/// column has been drawn again, or when the camera moves into another chunk
+/// column, around which the map is then centered.
+pub struct Minimap<B>
+    where B: Backend,
+{
+    /// Where the minimap is drawn on screen, in pixels.
+    rect: [f64; 4],
+    /// The chunk column at the center of the minimap.
+    center: Point2<i32>,
+    /// The color of every tile of the minimap, as RGBA bytes indexed by
+    /// `[z][x]`.
+    pixels: Vec<u8>,
+    /// The chunks in which the surface of every chunk column drawn so far was
+    /// found, and their revisions at the time.
+    columns: HashMap<Point2<i32>, Vec<(Point3<i32>, Option<u64>)>>,
+    texture: Option<B::Texture>,
+}
+
+impl<B> Minimap<B>
+    where B: Backend,
+{
+    /// Creates a minimap in the bottom-right corner of a window of the
+    /// specified size, with its size scaled by `scale`.
+    pub fn new(window_width: f64, window_height: f64, scale: f64) -> Self {
+        Minimap {
+            rect: minimap_rect(window_width, window_height, scale),
+            center: Point2::new(0, 0),
+            pixels: vec![0; (MINIMAP_TILES * MINIMAP_TILES * 4) as usize],
+            columns: HashMap::new(),
+            texture: None,
+        }
+    }
+
+    /// Moves the minimap to the bottom-right corner of a window of the
+    /// specified size, with its size scaled by `scale`.
+    pub fn resize(&mut self, window_width: f64, window_height: f64, scale: f64) {
+        self.rect = minimap_rect(window_width, window_height, scale);
+    }
+
+    /// Draws every chunk column again on the next update, as when the palette
+    /// was changed.
+    pub fn redraw(&mut self) {
+        self.columns.clear();
+    }
+
+    /// Centers the minimap on the chunk column containing `camera`, and draws
+    /// any chunk column whose chunks have changed since it was last drawn.
+    pub fn update(&mut self, area: &Area, materials: &MaterialRegistry, palette: &Palette, camera: &Point3<i32>) {
+        let center = Point2::new(camera.x >> LOG2_OF_CHUNK_SIZE, camera.z >> LOG2_OF_CHUNK_SIZE);
+        if center != self.center {
+            self.center = center;
+            self.columns.clear();
+        }
+
+        let mut changed = self.texture.is_none();
+        let half = MINIMAP_CHUNKS / 2;
+        for chunk_x in center.x - half..center.x + half {
+            for chunk_z in center.y - half..center.y + half {
+                let column = Point2::new(chunk_x, chunk_z);
+                let is_stale = match self.columns.get(&column) {
+                    Some(chunks) => chunks.iter().any(|&(pos, revision)| {
+                        area.get_chunk(pos).map(|chunk| chunk.revision()) != revision
+                    }),
+                    None => true,
+                };
+                if is_stale {
+                    let chunks = self.draw_column(area, materials, palette, column);
+                    self.columns.insert(column, chunks);
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            let size = [MINIMAP_TILES as u32, MINIMAP_TILES as u32];
+            self.texture = <B::Texture as CreateTexture<()>>::create(&mut (), Format::Rgba8, &self.pixels, size, &TextureSettings::new()).ok();
+        }
+    }
+
+    /// Draws the tiles of the chunk column at `column` into the pixels of the
+    /// minimap, returning the chunks in which their surface was searched for
+    /// and the revisions of those chunks.
+    fn draw_column(&mut self, area: &Area, materials: &MaterialRegistry, palette: &Palette, column: Point2<i32>) -> Vec<(Point3<i32>, Option<u64>)> {
+        let origin = self.origin();
+        let mut chunks = Vec::new();
+        for rel_z in 0..CHUNK_SIZE as i32 {
+            for rel_x in 0..CHUNK_SIZE as i32 {
+                let x = column.x * CHUNK_SIZE as i32 + rel_x;
+                let z = column.y * CHUNK_SIZE as i32 + rel_z;
+                let color = surface_pixel(area, materials, palette, x, z, |y| {
+                    let chunk_pos = Point3::new(column.x, y >> LOG2_OF_CHUNK_SIZE, column.y);
+                    if !chunks.iter().any(|&(chunk, _)| chunk == chunk_pos) {
+                        chunks.push((chunk_pos, area.get_chunk(chunk_pos).map(|chunk| chunk.revision())));
+                    }
+                });
+
+                let i = (((z - origin.y) * MINIMAP_TILES + x - origin.x) * 4) as usize;
+                self.pixels[i..i + 4].copy_from_slice(&color);
+            }
+        }
+        chunks
+    }
+
+    /// Returns the position, in tiles, of the top-left corner of the minimap.
+    fn origin(&self) -> Point2<i32> {
+        let half = MINIMAP_CHUNKS / 2;
+        Point2::new(
+            (self.center.x - half) * CHUNK_SIZE as i32,
+            (self.center.y - half) * CHUNK_SIZE as i32,
+        )
+    }
+
+    /// Returns the position, in tiles, shown on the minimap at the screen
+    /// position `pos`, or `None` if it lies outside of the minimap.
+    pub fn tile_at(&self, pos: Point2<f64>) -> Option<Point2<f64>> {
+        let rect = self.rect;
+        let is_inside = pos.x >= rect[0] && pos.x < rect[0] + rect[2] && pos.y >= rect[1] && pos.y < rect[1] + rect[3];
+        if !is_inside {
+            return None;
+        }
+
+        let origin = self.origin();
+        let scale = MINIMAP_TILES as f64 / rect[2];
+        Some(Point2::new(
+            origin.x as f64 + (pos.x - rect[0]) * scale,
+            origin.y as f64 + (pos.y - rect[1]) * scale,
+        ))
+    }
+
+    /// Draws the minimap in the colors of `palette`, outlining `view`, the
+    /// area covered by the tile view given in tiles as `[x, z, width, height]`.
+    pub fn draw<G>(&self, view: [f64; 4], palette: &Palette, context: &Context, graphics: &mut G)
+        where G: Graphics<Texture=B::Texture>,
+    {
+        let texture = match self.texture {
+            Some(ref texture) => texture,
+            None => return,
+        };
+
+        graphics::Image::new().rect(self.rect).draw(texture, &context.draw_state, context.transform, graphics);
+        graphics::Rectangle::new_border(palette.border, BORDER_RADIUS).draw(self.rect, &context.draw_state, context.transform, graphics);
+
+        let origin = self.origin();
+        let scale = self.rect[2] / MINIMAP_TILES as f64;
+        let viewport = [
+            self.rect[0] + (view[0] - origin.x as f64) * scale,
+            self.rect[1] + (view[1] - origin.y as f64) * scale,
+            view[2] * scale,
+            view[3] * scale,
+        ];
+        graphics::Rectangle::new_border(palette.minimap_viewport, BORDER_RADIUS).draw(viewport, &context.draw_state, context.transform, graphics);
+    }
+}
+
+/// Returns where the minimap is drawn in a window of the specified size, with
+/// its size scaled by `scale`.
+fn minimap_rect(window_width: f64, window_height: f64, scale: f64) -> [f64; 4] {
+    let (size, margin) = (MINIMAP_SIZE * scale, MINIMAP_MARGIN * scale);
+    [
+        window_width - size - margin,
+        window_height - size - margin,
+        size,
+        size,
+    ]
+}
+
+/// Returns the color from `palette` with which the explored surface at the
+/// specified position along the X and Z axes is drawn, calling `scanned` with
+/// each z-level searched for it.
+pub fn surface_pixel<F>(area: &Area, materials: &MaterialRegistry, palette: &Palette, x: i32, z: i32, mut scanned: F) -> [u8; 4]
+    where F: FnMut(i32),
+{
+    // The surface is the first solid or liquid tile beneath the top of the
+    // search.
+    let top = area.surface_height(x, z) + SCAN_HEIGHT;
+    for y in (top - SCAN_DEPTH..top + 1).rev() {
+        scanned(y);
+
+        let pos = Point3::new(x, y, z);
+        let material = area.get_tile(&pos).material;
+        if !materials.is_solid(material) && !materials.is_liquid(material) {
+            continue;
+        }
+        return match (area.is_revealed(&pos), materials.get(material)) {
+            (true, Some(material)) => surface_color(palette.material_color(material), y),
+            _ => unexplored_color(palette),
+        };
+    }
+    unexplored_color(palette)
+}
+
+fn unexplored_color(palette: &Palette) -> [u8; 4] {
+    let color = palette.unrevealed;
+    [(color[0] * 255.0) as u8, (color[1] * 255.0) as u8, (color[2] * 255.0) as u8, 255]
+}
+
+/// Returns the color of a surface tile of the specified color at elevation
+/// `y`, so that higher ground is drawn brighter.
+pub fn surface_color(color: [f32; 4], y: i32) -> [u8; 4] {
+    let brightness = (BASE_BRIGHTNESS + y as f32 * HEIGHT_SHADING)
+        .max(MIN_BRIGHTNESS)
+        .min(MAX_BRIGHTNESS);
+    [
+        (color[0] * brightness * 255.0) as u8,
+        (color[1] * brightness * 255.0) as u8,
+        (color[2] * brightness * 255.0) as u8,
+        255,
+    ]
+}
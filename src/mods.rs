@@ -0,0 +1,76 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde_json;
+
+use error::ColonizeResult;
+
+/// The file in the mods directory listing the names of the mods in the order
+/// they are loaded.
+const LOAD_ORDER_FILE: &'static str = "load_order.json";
+
+/// A mod found in the mods directory.
+///
+/// A mod is a directory which may contain any of a `main.lua` script, a
+/// `materials.json` file of materials, and a `manifest.json` asset manifest
+/// whose files are relative to the directory of the mod. Whatever a mod
+/// defines is layered on top of the built-in assets and the mods loaded
+/// before it, replacing anything of the same name.
+pub struct ModPack {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Returns the mods found in `mods_path`, in the order they are loaded.
+///
+/// The mods listed in the load order file of the mods directory come first,
+/// in the order they are listed, followed by any other mod in the order of
+/// their names. Having no mods directory, or no load order file, is no error.
+pub fn find_mod_packs(mods_path: &PathBuf) -> Vec<ModPack> {
+    let mut names: Vec<String> = match fs::read_dir(mods_path) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    names.sort();
+
+    let load_order_path = mods_path.join(LOAD_ORDER_FILE);
+    let load_order = if load_order_path.exists() {
+        match read_load_order(&load_order_path) {
+            Ok(load_order) => load_order,
+            Err(e) => {
+                warn!("Failed to load the mod load order, loading mods by name: {}", e);
+                Vec::new()
+            },
+        }
+    } else {
+        Vec::new()
+    };
+
+    let mut ordered = Vec::new();
+    for name in load_order {
+        match names.iter().position(|other| *other == name) {
+            Some(index) => ordered.push(names.remove(index)),
+            None => {
+                if !ordered.contains(&name) {
+                    warn!("Mod {} is in the load order, but was not found", name);
+                }
+            },
+        }
+    }
+    ordered.extend(names);
+
+    ordered.into_iter()
+        .map(|name| ModPack {
+            path: mods_path.join(&name),
+            name: name,
+        })
+        .collect()
+}
+
+fn read_load_order(path: &PathBuf) -> ColonizeResult<Vec<String>> {
+    let json = try!(::read_file_to_string(path));
+    Ok(try!(serde_json::from_str(&json)))
+}
@@ -0,0 +1,47 @@
+/// A message sent by the host of a game to the players who joined it.
+#[derive(Deserialize, Serialize)]
+pub enum HostMessage {
+    /// Sent once a player joins, or when the host starts over in another
+    /// world, with what the world is generated from.
+    Welcome {
+        seed: u32,
+        world_gen: WorldGenConfig,
+        embark: [i32; 2],
+    },
+    /// The changes to the state of the world, along with the chunks changed,
+    /// since the last update. The first update after a welcome sets the
+    /// whole state.
+    Update {
+        changes: Vec<StateChange>,
+        chunks: Vec<ChunkUpdate>,
+    },
+}
+
+/// A change to the state of the world, as serialized to JSON, at the path
+/// of the value changed. Changes are applied in order.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub enum StateChange {
+    /// Replaces the value at the path, or adds it as a field of its object
+    /// or as the element following the last of its array.
+    Set(Vec<PathSegment>, Value),
+    /// Removes the field at the path from its object.
+    Remove(Vec<PathSegment>),
+    /// Shortens the array at the path to a length.
+    Truncate(Vec<PathSegment>, usize),
+}
+
+/// A step along the path to a value within the state of the world.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum PathSegment {
+    /// A field of an object.
+    Field(String),
+    /// An element of an array.
+    Index(usize),
+}
+
+/// A chunk, encoded as it is saved.
+#[derive(Deserialize, Serialize)]
+pub struct ChunkUpdate {
+    pos: [i32; 3],
+    data: Vec<u8>,
+}
@@ -0,0 +1,533 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use cgmath::Point3;
+use serde::{Deserialize, Serialize};
+use serde_json;
+use serde_json::Value;
+use world;
+use world::{ChunkPos, World, WorldGenConfig};
+
+use command::PlayerCommand;
+use error::{ColonizeError, ColonizeResult};
+
+#[cfg(feature = "nightly")]
+include!("net.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/net.rs"));
+
+/// The port on which games are hosted and joined unless another is given.
+pub const DEFAULT_PORT: u16 = 7428;
+/// Seconds between the updates sent by the host to the players.
+const UPDATE_INTERVAL: f64 = 0.1;
+/// The most chunks sent to a player in one update, so that the chunks loaded
+/// when a player joins are sent over several updates rather than at once.
+const MAX_CHUNKS_PER_UPDATE: usize = 64;
+/// Seconds after which a player who stopped receiving messages is dropped,
+/// rather than holding up the game of the host.
+const WRITE_TIMEOUT_SECS: u64 = 5;
+
+/// A game played over the network, either hosted or joined.
+pub enum Session {
+    Host(Host),
+    Client(Client),
+}
+
+/// Something which happened to the players of a hosted game.
+pub enum SessionEvent {
+    /// A player joined from an address.
+    Joined(SocketAddr),
+    /// The player at an address left, or was dropped as its connection
+    /// failed.
+    Left(SocketAddr),
+}
+
+/// Hosts a game, in which the simulation is run and the commands of the
+/// players who joined are carried out, while they are sent the state of the
+/// world to keep theirs in step.
+///
+/// The players generate the world from the same seed as the host, so only
+/// the chunks loaded by the host are sent to them, as they change. Likewise,
+/// only the parts of the state of the world which changed since the last
+/// update are sent.
+///
+/// The commands which change the world outright are only carried out for
+/// the players if the host allows cheats.
+pub struct Host {
+    listener: TcpListener,
+    players: Vec<Player>,
+    /// Seconds until the next update is sent to the players.
+    update_timer: f64,
+    allow_cheats: bool,
+}
+
+struct Player {
+    address: SocketAddr,
+    connection: Connection,
+    /// The revision of every chunk as it was last sent to the player.
+    sent_revisions: HashMap<ChunkPos, u64>,
+    /// The state of the world as it was last sent to the player, serialized
+    /// to JSON, or null if it has not been sent yet.
+    sent_state: Value,
+}
+
+impl Host {
+    /// Starts hosting a game on `port` of every network interface, carrying
+    /// out the cheats of the players if `allow_cheats` is set.
+    pub fn listen(port: u16, allow_cheats: bool) -> io::Result<Self> {
+        let listener = try!(TcpListener::bind(("0.0.0.0", port)));
+        try!(listener.set_nonblocking(true));
+
+        Ok(Host {
+            listener: listener,
+            players: Vec::new(),
+            update_timer: 0.0,
+            allow_cheats: allow_cheats,
+        })
+    }
+
+    /// Admits the players who connected since the last update, and sends
+    /// them the state of `world` every `UPDATE_INTERVAL` seconds. Returns
+    /// what happened to the players, along with the commands received from
+    /// them, which are for the host to carry out.
+    pub fn update(&mut self, dt: f64, world: &World) -> (Vec<SessionEvent>, Vec<PlayerCommand>) {
+        let mut events = Vec::new();
+        loop {
+            match self.listener.accept() {
+                Ok((stream, address)) => {
+                    match self.admit(stream, address, world) {
+                        Ok(()) => events.push(SessionEvent::Joined(address)),
+                        Err(e) => warn!("Failed to admit a player from {}: {}", address, e),
+                    }
+                },
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    warn!("Failed to accept a player: {}", e);
+                    break;
+                },
+            }
+        }
+
+        let mut commands: Vec<PlayerCommand> = Vec::new();
+        let mut i = 0;
+        while i < self.players.len() {
+            match self.players[i].connection.receive::<PlayerCommand>() {
+                Ok(received) => {
+                    for command in received {
+                        if command.is_cheat() && !self.allow_cheats {
+                            warn!("Ignored {:?} from player {}, as cheats are not allowed", command, self.players[i].address);
+                            continue;
+                        }
+                        commands.push(command);
+                    }
+                    i += 1;
+                },
+                Err(e) => events.push(self.remove_player(i, &e)),
+            }
+        }
+        self.update_timer -= dt;
+        if self.update_timer <= 0.0 && !self.players.is_empty() {
+            self.update_timer = UPDATE_INTERVAL;
+            let state = serde_json::to_value(world.state());
+            let mut i = 0;
+            while i < self.players.len() {
+                match self.players[i].send_update(world, &state) {
+                    Ok(()) => i += 1,
+                    Err(e) => events.push(self.remove_player(i, &e)),
+                }
+            }
+        }
+
+        (events, commands)
+    }
+
+    /// Welcomes every player into `world` anew, as when the host starts over
+    /// in another world.
+    pub fn restart(&mut self, world: &World) -> Vec<SessionEvent> {
+        let mut events = Vec::new();
+        let mut i = 0;
+        while i < self.players.len() {
+            self.players[i].sent_revisions.clear();
+            self.players[i].sent_state = Value::Null;
+            match self.players[i].connection.send(&welcome(world)) {
+                Ok(()) => i += 1,
+                Err(e) => events.push(self.remove_player(i, &e)),
+            }
+        }
+        self.update_timer = 0.0;
+        events
+    }
+
+    fn admit(&mut self, stream: TcpStream, address: SocketAddr, world: &World) -> ColonizeResult<()> {
+        // Connections accepted by a nonblocking listener are nonblocking as
+        // well on some platforms.
+        try!(stream.set_nonblocking(false));
+        let mut connection = try!(Connection::new(stream));
+        try!(connection.send(&welcome(world)));
+
+        self.players.push(Player {
+            address: address,
+            connection: connection,
+            sent_revisions: HashMap::new(),
+            sent_state: Value::Null,
+        });
+        Ok(())
+    }
+
+    fn remove_player(&mut self, index: usize, reason: &fmt::Display) -> SessionEvent {
+        let player = self.players.remove(index);
+        info!("Player {} left: {}", player.address, reason);
+        SessionEvent::Left(player.address)
+    }
+}
+
+impl Player {
+    /// Sends the changes to `state`, the serialized state of `world`, since
+    /// it was last sent to the player, along with the chunks which changed
+    /// since they were last sent. Nothing is sent if nothing changed.
+    fn send_update(&mut self, world: &World, state: &Value) -> ColonizeResult<()> {
+        let area = &world.area;
+        // Chunks unloaded since are sent again once they are loaded.
+        self.sent_revisions.retain(|&pos, _| area.get_chunk(pos).is_some());
+
+        let mut chunks = Vec::new();
+        for (&pos, chunk) in area.loaded_chunks() {
+            if chunks.len() == MAX_CHUNKS_PER_UPDATE {
+                break;
+            }
+            if self.sent_revisions.get(&pos) == Some(&chunk.revision()) {
+                continue;
+            }
+            self.sent_revisions.insert(pos, chunk.revision());
+            chunks.push(ChunkUpdate {
+                pos: [pos.x, pos.y, pos.z],
+                data: world::encode_chunk(chunk),
+            });
+        }
+
+        let mut changes = Vec::new();
+        diff_state(&self.sent_state, state, &mut Vec::new(), &mut changes);
+        if changes.is_empty() && chunks.is_empty() {
+            return Ok(());
+        }
+        self.sent_state = state.clone();
+
+        self.connection.send(&HostMessage::Update {
+            changes: changes,
+            chunks: chunks,
+        })
+    }
+}
+
+/// Joins a game hosted over the network, sending the commands of the player
+/// to the host rather than carrying them out.
+pub struct Client {
+    connection: Connection,
+    /// The state of the world of the host, serialized to JSON, as of the last
+    /// update received, or null until the first.
+    state: Value,
+}
+
+impl Client {
+    /// Joins the game hosted at `address`, on `DEFAULT_PORT` unless the
+    /// address includes a port.
+    pub fn connect(address: &str) -> io::Result<Self> {
+        let stream = if address.contains(':') {
+            try!(TcpStream::connect(address))
+        } else {
+            try!(TcpStream::connect((address, DEFAULT_PORT)))
+        };
+
+        Ok(Client {
+            connection: try!(Connection::new(stream)),
+            state: Value::Null,
+        })
+    }
+
+    pub fn send(&mut self, command: &PlayerCommand) -> ColonizeResult<()> {
+        self.connection.send(command)
+    }
+
+    /// Returns the messages received from the host since the last call, or
+    /// an error once the connection to the host is lost.
+    pub fn receive(&mut self) -> ColonizeResult<Vec<HostMessage>> {
+        self.connection.receive()
+    }
+
+    /// Forgets the state of the world of the host, as it is sent whole again
+    /// once the player has been welcomed into a world.
+    pub fn welcome(&mut self) {
+        self.state = Value::Null;
+    }
+
+    /// Brings `world` in step with the world of the host, given the changes
+    /// and chunks of an update received from it.
+    pub fn apply_update(&mut self, world: &mut World, changes: Vec<StateChange>, chunks: Vec<ChunkUpdate>) -> ColonizeResult<()> {
+        if !changes.is_empty() {
+            for change in changes {
+                try!(apply_state_change(&mut self.state, change));
+            }
+            world.set_state(try!(serde_json::from_value(self.state.clone())));
+        }
+        for chunk in chunks {
+            let decoded = try!(world::decode_chunk(&chunk.data));
+            world.area.restore_chunk(to_point(chunk.pos), decoded);
+        }
+        Ok(())
+    }
+}
+
+/// Appends to `changes` those which turn `old` into `new`, two states of the
+/// world serialized to JSON, where `path` leads to them within the whole
+/// state.
+///
+/// Objects and arrays are compared field by field and element by element,
+/// so that only the values which changed within them are sent.
+fn diff_state(old: &Value, new: &Value, path: &mut Vec<PathSegment>, changes: &mut Vec<StateChange>) {
+    match (old, new) {
+        (&Value::Object(ref old), &Value::Object(ref new)) => {
+            for key in old.keys() {
+                if !new.contains_key(key) {
+                    let mut removed = path.clone();
+                    removed.push(PathSegment::Field(key.clone()));
+                    changes.push(StateChange::Remove(removed));
+                }
+            }
+            for (key, value) in new {
+                path.push(PathSegment::Field(key.clone()));
+                match old.get(key) {
+                    Some(old_value) => diff_state(old_value, value, path, changes),
+                    None => changes.push(StateChange::Set(path.clone(), value.clone())),
+                }
+                path.pop();
+            }
+        },
+        (&Value::Array(ref old), &Value::Array(ref new)) => {
+            if new.len() < old.len() {
+                changes.push(StateChange::Truncate(path.clone(), new.len()));
+            }
+            for (i, value) in new.iter().enumerate() {
+                path.push(PathSegment::Index(i));
+                match old.get(i) {
+                    Some(old_value) => diff_state(old_value, value, path, changes),
+                    None => changes.push(StateChange::Set(path.clone(), value.clone())),
+                }
+                path.pop();
+            }
+        },
+        _ => {
+            if old != new {
+                changes.push(StateChange::Set(path.clone(), new.clone()));
+            }
+        },
+    }
+}
+
+/// Applies `change` to `state`, a state of the world serialized to JSON.
+fn apply_state_change(state: &mut Value, change: StateChange) -> ColonizeResult<()> {
+    match change {
+        StateChange::Set(path, value) => {
+            let (last, parent) = match path.split_last() {
+                Some((last, parent)) => (last, parent),
+                None => {
+                    *state = value;
+                    return Ok(());
+                },
+            };
+            match (last, value_at(state, parent)) {
+                (&PathSegment::Field(ref key), Some(&mut Value::Object(ref mut fields))) => {
+                    fields.insert(key.clone(), value);
+                },
+                (&PathSegment::Index(i), Some(&mut Value::Array(ref mut items))) => {
+                    if i < items.len() {
+                        items[i] = value;
+                    } else if i == items.len() {
+                        items.push(value);
+                    } else {
+                        return Err(invalid_change());
+                    }
+                },
+                _ => return Err(invalid_change()),
+            }
+        },
+        StateChange::Remove(path) => {
+            let (last, parent) = try!(path.split_last().ok_or_else(invalid_change));
+            match (last, value_at(state, parent)) {
+                (&PathSegment::Field(ref key), Some(&mut Value::Object(ref mut fields))) => {
+                    fields.remove(key);
+                },
+                _ => return Err(invalid_change()),
+            }
+        },
+        StateChange::Truncate(path, len) => {
+            match value_at(state, &path) {
+                Some(&mut Value::Array(ref mut items)) => items.truncate(len),
+                _ => return Err(invalid_change()),
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Returns the value at `path` within `value`, if there is one.
+fn value_at<'a>(value: &'a mut Value, path: &[PathSegment]) -> Option<&'a mut Value> {
+    let (first, rest) = match path.split_first() {
+        Some((first, rest)) => (first, rest),
+        None => return Some(value),
+    };
+    let child = match (first, value) {
+        (&PathSegment::Field(ref key), &mut Value::Object(ref mut fields)) => fields.get_mut(key),
+        (&PathSegment::Index(i), &mut Value::Array(ref mut items)) => items.get_mut(i),
+        _ => None,
+    };
+    child.and_then(|child| value_at(child, rest))
+}
+
+fn invalid_change() -> ColonizeError {
+    io::Error::new(io::ErrorKind::InvalidData, "update does not match the state of the world").into()
+}
+
+fn welcome(world: &World) -> HostMessage {
+    let embark = world.embark();
+    HostMessage::Welcome {
+        seed: world.seed(),
+        world_gen: *world.config(),
+        embark: [embark.x, embark.y],
+    }
+}
+
+fn to_point(pos: [i32; 3]) -> Point3<i32> {
+    Point3::new(pos[0], pos[1], pos[2])
+}
+
+/// A connection to another player, over which messages are exchanged as
+/// lines of JSON.
+///
+/// Lines are read on a thread of their own, so that receiving never blocks,
+/// while messages are sent on the calling thread.
+struct Connection {
+    stream: TcpStream,
+    lines: Receiver<String>,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        try!(stream.set_nodelay(true));
+        try!(stream.set_write_timeout(Some(Duration::from_secs(WRITE_TIMEOUT_SECS))));
+        let reader = BufReader::new(try!(stream.try_clone()));
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    },
+                    Err(_) => break,
+                }
+            }
+        });
+
+        Ok(Connection {
+            stream: stream,
+            lines: lines,
+        })
+    }
+
+    fn send<T>(&mut self, message: &T) -> ColonizeResult<()>
+        where T: Serialize,
+    {
+        try!(serde_json::to_writer(&mut self.stream, message));
+        try!(self.stream.write_all(b"\n"));
+        Ok(())
+    }
+
+    /// Returns the messages received since the last call, or an error once
+    /// the connection is closed.
+    fn receive<T>(&mut self) -> ColonizeResult<Vec<T>>
+        where T: Deserialize,
+    {
+        let mut messages = Vec::new();
+        loop {
+            match self.lines.try_recv() {
+                Ok(line) => messages.push(try!(serde_json::from_str(&line))),
+                Err(TryRecvError::Empty) => return Ok(messages),
+                Err(TryRecvError::Disconnected) => {
+                    return Err(io::Error::new(io::ErrorKind::ConnectionAborted, "connection closed").into());
+                },
+            }
+        }
+    }
+}
+
+impl Drop for Connection {
+    /// Closes the connection, which also stops the thread reading from it.
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use serde_json::Value;
+
+    use super::{apply_state_change, diff_state, PathSegment, StateChange};
+
+    fn json(text: &str) -> Value {
+        serde_json::from_str(text).unwrap()
+    }
+
+    /// Returns `old` with the changes which turn it into `new` applied, along
+    /// with the changes.
+    fn round_trip(old: &Value, new: &Value) -> (Value, Vec<StateChange>) {
+        let mut changes = Vec::new();
+        diff_state(old, new, &mut Vec::new(), &mut changes);
+        let mut state = old.clone();
+        for change in serde_json::from_str::<Vec<StateChange>>(&serde_json::to_string(&changes).unwrap()).unwrap() {
+            apply_state_change(&mut state, change).unwrap();
+        }
+        (state, changes)
+    }
+
+    #[test]
+    fn sends_the_whole_state_first() {
+        let new = json(r#"{"calendar": {"ticks": 3}, "job_queue": [1, 2]}"#);
+        let (state, changes) = round_trip(&Value::Null, &new);
+        assert_eq!(state, new);
+        assert_eq!(changes, vec![StateChange::Set(Vec::new(), new.clone())]);
+    }
+
+    #[test]
+    fn sends_only_the_values_which_changed() {
+        let old = json(r#"{"calendar": {"ticks": 3}, "job_queue": [1, 2], "next_raid": 0}"#);
+        let new = json(r#"{"calendar": {"ticks": 4}, "job_queue": [1, 2], "next_raid": 0}"#);
+        let (state, changes) = round_trip(&old, &new);
+        assert_eq!(state, new);
+        let path = vec![PathSegment::Field("calendar".to_owned()), PathSegment::Field("ticks".to_owned())];
+        assert_eq!(changes, vec![StateChange::Set(path, Value::U64(4))]);
+    }
+
+    #[test]
+    fn grows_and_shrinks_arrays_and_objects() {
+        let old = json(r#"{"a": [1, 2, 3], "b": {"x": 1, "y": 2}}"#);
+        let new = json(r#"{"a": [1, 5], "b": {"y": 2, "z": [3]}}"#);
+        assert_eq!(round_trip(&old, &new).0, new);
+        assert_eq!(round_trip(&new, &old).0, old);
+    }
+
+    #[test]
+    fn sends_nothing_if_nothing_changed() {
+        let state = json(r#"{"a": [1, {"b": null}]}"#);
+        assert!(round_trip(&state, &state).1.is_empty());
+    }
+}
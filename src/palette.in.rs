@@ -0,0 +1,64 @@
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Palette {
+    /// Colors in which materials are drawn instead of their own, by the name
+    /// of the material, as RGB. Materials keep their own opacity, and are
+    /// drawn in these colors even where they have a texture.
+    pub materials: HashMap<String, [f32; 3]>,
+    /// Color of the text of menus and panels
+    pub text: [f32; 4],
+    /// Color with which the screen is cleared in menus
+    pub background: [f32; 4],
+    /// Color of buttons
+    pub button: [f32; 4],
+    /// Color of buttons under the mouse
+    pub button_hover: [f32; 4],
+    /// Color of the highlighted choice of a menu, such as the selected save
+    pub selected: [f32; 4],
+    /// Color of borders around previews and the minimap
+    pub border: [f32; 4],
+    /// Color behind the text of the inspection panel and tooltips
+    pub panel_background: [f32; 4],
+    /// Color of the outline of the cursor and of the selection
+    pub cursor: [f32; 4],
+    /// Color filling the selection
+    pub cursor_fill: [f32; 4],
+    /// Color filling the tiles of stockpiles
+    pub stockpile: [f32; 4],
+    /// Color filling the tiles of blueprints
+    pub blueprint: [f32; 4],
+    /// Color of the hatching over the tiles designated to be dug out
+    pub dig_mark: [f32; 4],
+    /// Color filling the bars of the roster which show how well the needs of
+    /// colonists are met
+    pub need_bar: [f32; 4],
+    /// Color of the lines of the graphs of the reports
+    pub graph: [f32; 4],
+    /// Color of tiles which have yet to be revealed
+    pub unrevealed: [f32; 4],
+    /// Color of the outline of the embark site in the world preview
+    pub embark_area: [f32; 4],
+    /// Color of the outline of the view on the minimap
+    pub minimap_viewport: [f32; 4],
+}
+
+#[derive(Deserialize, Serialize)]
+struct ParsedPalette {
+    materials: Option<HashMap<String, [f32; 3]>>,
+    text: Option<[f32; 4]>,
+    background: Option<[f32; 4]>,
+    button: Option<[f32; 4]>,
+    button_hover: Option<[f32; 4]>,
+    selected: Option<[f32; 4]>,
+    border: Option<[f32; 4]>,
+    panel_background: Option<[f32; 4]>,
+    cursor: Option<[f32; 4]>,
+    cursor_fill: Option<[f32; 4]>,
+    stockpile: Option<[f32; 4]>,
+    blueprint: Option<[f32; 4]>,
+    dig_mark: Option<[f32; 4]>,
+    need_bar: Option<[f32; 4]>,
+    graph: Option<[f32; 4]>,
+    unrevealed: Option<[f32; 4]>,
+    embark_area: Option<[f32; 4]>,
+    minimap_viewport: Option<[f32; 4]>,
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde_json;
+use world::Material;
+
+use error::ColonizeResult;
+
+#[cfg(feature = "nightly")]
+include!("palette.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/palette.rs"));
+
+create_type_filling_impls! {
+    Palette,
+    ParsedPalette,
+    materials, HashMap::new();
+    text, [0.0, 0.0, 0.0, 1.0];
+    background, [1.0, 1.0, 1.0, 1.0];
+    button, [0.9, 0.9, 0.9, 1.0];
+    button_hover, [0.75, 0.75, 0.75, 1.0];
+    selected, [0.7, 0.8, 1.0, 1.0];
+    border, [0.0, 0.0, 0.0, 1.0];
+    panel_background, [1.0, 1.0, 1.0, 0.85];
+    cursor, [1.0, 0.0, 0.0, 1.0];
+    cursor_fill, [1.0, 0.0, 0.0, 0.25];
+    stockpile, [0.9, 0.8, 0.2, 0.35];
+    blueprint, [0.2, 0.4, 0.9, 0.45];
+    dig_mark, [0.55, 0.3, 0.1, 0.8];
+    need_bar, [0.3, 0.7, 0.3, 1.0];
+    graph, [0.2, 0.4, 0.9, 1.0];
+    unrevealed, [0.0, 0.0, 0.0, 1.0];
+    embark_area, [1.0, 0.0, 0.0, 1.0];
+    minimap_viewport, [1.0, 1.0, 1.0, 1.0];
+}
+
+/// The palette used unless another is chosen, in which the game looks as it
+/// always has.
+pub const DEFAULT_PALETTE: &'static str = "default";
+
+const PALETTES_DIR: &'static str = "palettes/";
+const PALETTE_FILE_EXTENSION: &'static str = "json";
+
+impl Palette {
+    /// Loads the palette `name` from the palettes directory of `asset_path`.
+    /// Any color missing from its file is taken from the built-in palette,
+    /// which is used as a whole if the file cannot be loaded.
+    pub fn load(asset_path: &PathBuf, name: &str) -> Palette {
+        let mut path = asset_path.join(PALETTES_DIR).join(name);
+        path.set_extension(PALETTE_FILE_EXTENSION);
+        match read_parsed_palette(&path) {
+            Ok(parsed) => Palette::default().fill_from_parsed(parsed),
+            Err(e) => {
+                warn!("Failed to load palette {}: {}", name, e);
+                Palette::default()
+            },
+        }
+    }
+
+    /// Returns the palettes which have a file in the palettes directory of
+    /// `asset_path`, in the order of their names.
+    pub fn names(asset_path: &PathBuf) -> Vec<String> {
+        ::file_stems(&asset_path.join(PALETTES_DIR), PALETTE_FILE_EXTENSION)
+    }
+
+    /// Returns whether the palette replaces the color of `material`.
+    pub fn recolors(&self, material: &Material) -> bool {
+        self.materials.contains_key(&material.name)
+    }
+
+    /// Returns the color in which `material` is drawn.
+    pub fn material_color(&self, material: &Material) -> [f32; 4] {
+        match self.materials.get(&material.name) {
+            Some(color) => [color[0], color[1], color[2], material.color[3]],
+            None => material.color,
+        }
+    }
+}
+
+fn read_parsed_palette(path: &PathBuf) -> ColonizeResult<ParsedPalette> {
+    let json = try!(::read_file_to_string(path));
+    Ok(try!(serde_json::from_str(&json)))
+}
@@ -0,0 +1,196 @@
+use cgmath::Point3;
+use graphics;
+use rand::{self, Rng, XorShiftRng};
+use rgframework::backend::Graphics;
+use rgframework::backend::graphics::Context;
+
+use renderer::TileView;
+
+/// The most particles alive at once. Particles spawned beyond it are dropped.
+const MAX_PARTICLES: usize = 2000;
+
+/// Describes the particles spawned by an emitter, and how they move and fade.
+/// Distances are in tiles, and times in seconds.
+pub struct ParticleEffect {
+    /// The number of particles spawned each second by an emitter at a single
+    /// point, or on each tile by an emitter covering an area.
+    pub rate: f64,
+    pub lifetime: f64,
+    /// The velocity of the particles along the X and Z axes.
+    pub velocity: [f64; 2],
+    /// The largest random deviation of the velocity of each particle along
+    /// either axis.
+    pub spread: f64,
+    /// The width and height of the particles, relative to a tile.
+    pub size: [f64; 2],
+    /// The color of the particles when spawned, which fades into `end_color`
+    /// over their lifetime.
+    pub start_color: [f32; 4],
+    pub end_color: [f32; 4],
+}
+
+/// Dust thrown up when a tile is dug out.
+pub static DUST: ParticleEffect = ParticleEffect {
+    rate: 0.0,
+    lifetime: 0.6,
+    velocity: [0.0, 0.0],
+    spread: 1.2,
+    size: [0.12, 0.12],
+    start_color: [0.55, 0.45, 0.3, 0.9],
+    end_color: [0.55, 0.45, 0.3, 0.0],
+};
+pub static RAIN: ParticleEffect = ParticleEffect {
+    rate: 0.5,
+    lifetime: 0.4,
+    velocity: [1.0, 8.0],
+    spread: 0.2,
+    size: [0.04, 0.5],
+    start_color: [0.6, 0.7, 0.9, 0.7],
+    end_color: [0.6, 0.7, 0.9, 0.3],
+};
+pub static SNOW: ParticleEffect = ParticleEffect {
+    rate: 0.15,
+    lifetime: 3.0,
+    velocity: [0.3, 1.0],
+    spread: 0.3,
+    size: [0.1, 0.1],
+    start_color: [1.0, 1.0, 1.0, 0.9],
+    end_color: [1.0, 1.0, 1.0, 0.0],
+};
+/// Smoke rising from a workshop being worked at.
+pub static SMOKE: ParticleEffect = ParticleEffect {
+    rate: 4.0,
+    lifetime: 2.0,
+    velocity: [0.3, -0.8],
+    spread: 0.2,
+    size: [0.25, 0.25],
+    start_color: [0.35, 0.35, 0.35, 0.6],
+    end_color: [0.6, 0.6, 0.6, 0.0],
+};
+
+struct Particle {
+    effect: &'static ParticleEffect,
+    /// The z-level of the particle, and its position in tiles along the X and
+    /// Z axes.
+    y: i32,
+    position: [f64; 2],
+    velocity: [f64; 2],
+    age: f64,
+}
+
+/// The particles drawn over the tile view, such as dust, rain and smoke.
+///
+/// Particles are only for show: they are kept out of the world, so they are
+/// never saved nor rewound, and they are spawned with a generator of their
+/// own, so as to leave the random numbers of the simulation untouched.
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    rng: XorShiftRng,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        ParticleSystem {
+            particles: Vec::new(),
+            rng: rand::weak_rng(),
+        }
+    }
+
+    /// Spawns `count` particles of `effect` at once, at `position` in tiles.
+    pub fn burst(&mut self, effect: &'static ParticleEffect, position: Point3<f64>, count: u32) {
+        for _ in 0..count {
+            self.spawn(effect, position.y.floor() as i32, [position.x, position.z]);
+        }
+    }
+
+    /// Spawns the particles of `effect` due over `dt` seconds from an emitter
+    /// at `position`, in tiles.
+    pub fn emit(&mut self, effect: &'static ParticleEffect, position: Point3<f64>, dt: f64) {
+        let count = self.spawn_count(effect.rate * dt);
+        self.burst(effect, position, count);
+    }
+
+    /// Spawns the particles of `effect` due over `dt` seconds from an emitter
+    /// covering `area`, given in tiles as `[x, z, width, depth]`, on z-level
+    /// `y`. Particles are only spawned on the tiles for which `allowed`
+    /// returns `true`.
+    pub fn emit_over<F>(&mut self, effect: &'static ParticleEffect, y: i32, area: [f64; 4], dt: f64, allowed: F)
+        where F: Fn(Point3<i32>) -> bool,
+    {
+        let count = self.spawn_count(effect.rate * area[2] * area[3] * dt);
+        for _ in 0..count {
+            let x = area[0] + self.rng.gen::<f64>() * area[2];
+            let z = area[1] + self.rng.gen::<f64>() * area[3];
+            if allowed(Point3::new(x.floor() as i32, y, z.floor() as i32)) {
+                self.spawn(effect, y, [x, z]);
+            }
+        }
+    }
+
+    /// Moves every particle along by `dt` seconds, removing those which have
+    /// outlived their effect.
+    pub fn update(&mut self, dt: f64) {
+        for particle in &mut self.particles {
+            particle.age += dt;
+            particle.position[0] += particle.velocity[0] * dt;
+            particle.position[1] += particle.velocity[1] * dt;
+        }
+        self.particles.retain(|particle| particle.age < particle.effect.lifetime);
+    }
+
+    /// Removes every particle, such as when the world is replaced.
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    /// Draws the particles on the z-level of `view`.
+    pub fn draw<G>(&self, view: &TileView, context: &Context, graphics: &mut G)
+        where G: Graphics,
+    {
+        for particle in &self.particles {
+            if particle.y != view.y {
+                continue;
+            }
+
+            let effect = particle.effect;
+            let width = effect.size[0] * view.tile_size;
+            let height = effect.size[1] * view.tile_size;
+            let rect = [
+                (particle.position[0] - view.min.x as f64) * view.tile_size + view.offset.x - width / 2.0,
+                (particle.position[1] - view.min.y as f64) * view.tile_size + view.offset.y - height / 2.0,
+                width,
+                height,
+            ];
+            let fade = (particle.age / effect.lifetime) as f32;
+            let mut color = [0.0; 4];
+            for (i, channel) in color.iter_mut().enumerate() {
+                *channel = effect.start_color[i] + (effect.end_color[i] - effect.start_color[i]) * fade;
+            }
+            graphics::Rectangle::new(color).draw(rect, &context.draw_state, context.transform, graphics);
+        }
+    }
+
+    /// Returns the number of particles to spawn to keep up an average of
+    /// `expected`, which may be fractional.
+    fn spawn_count(&mut self, expected: f64) -> u32 {
+        (expected + self.rng.gen::<f64>()).floor() as u32
+    }
+
+    fn spawn(&mut self, effect: &'static ParticleEffect, y: i32, position: [f64; 2]) {
+        if self.particles.len() >= MAX_PARTICLES {
+            return;
+        }
+
+        let velocity = [
+            effect.velocity[0] + self.rng.gen_range(-1.0, 1.0) * effect.spread,
+            effect.velocity[1] + self.rng.gen_range(-1.0, 1.0) * effect.spread,
+        ];
+        self.particles.push(Particle {
+            effect: effect,
+            y: y,
+            position: position,
+            velocity: velocity,
+            age: 0.0,
+        });
+    }
+}
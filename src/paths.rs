@@ -0,0 +1,52 @@
+use std::env;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use app_dirs::{self, AppDataType, AppInfo};
+
+const APP_INFO: AppInfo = AppInfo { name: "colonize", author: "colonize" };
+
+/// Environment variable naming a directory in which to keep every file the
+/// game writes instead of the directories of the platform, such as `.` to
+/// keep them in the working directory while developing.
+pub const HOME_VAR: &'static str = "COLONIZE_HOME";
+
+/// Returns the directory in which the settings are kept, such as
+/// `~/.config/colonize/` on Linux.
+pub fn config_dir() -> PathBuf {
+    dir(AppDataType::UserConfig)
+}
+
+/// Returns the directory in which the saves, screenshots and crash reports
+/// are kept, such as `~/.local/share/colonize/` on Linux.
+pub fn data_dir() -> PathBuf {
+    dir(AppDataType::UserData)
+}
+
+/// Returns the directory in which the logs and traces are kept, such as
+/// `~/.cache/colonize/` on Linux.
+pub fn cache_dir() -> PathBuf {
+    dir(AppDataType::UserCache)
+}
+
+/// Returns the directory of the platform for files of type `data_type`,
+/// unless overridden by `HOME_VAR`.
+///
+/// The working directory is used if the directory of the platform cannot be
+/// determined, such as when no home directory is set.
+fn dir(data_type: AppDataType) -> PathBuf {
+    if let Some(home) = env::var_os(HOME_VAR) {
+        return PathBuf::from(home);
+    }
+    app_dirs::get_app_root(data_type, &APP_INFO).unwrap_or_else(|_| PathBuf::from("."))
+}
+
+/// Returns the time elapsed since the Unix epoch, in milliseconds, so that
+/// the names of the files named after it sort in the order they were written.
+pub fn timestamp() -> u64 {
+    let elapsed = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => elapsed,
+        Err(_) => Duration::default(),
+    };
+    elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1_000_000
+}
@@ -0,0 +1,40 @@
+/// A command carried out on the world while it was recorded.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RecordedCommand {
+    /// The number of steps the simulation had run when the command was
+    /// carried out, before the next step.
+    pub tick: u64,
+    pub command: PlayerCommand,
+}
+
+/// A hash of the state of the world once the simulation had run a number of
+/// steps.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Checksum {
+    pub tick: u64,
+    pub hash: u64,
+}
+
+/// The commands carried out on a world over a stretch of a game, which
+/// replay it from the save it started from, along with checksums of the
+/// state of the world to tell whether the replay keeps in step.
+#[derive(Deserialize, Serialize)]
+pub struct Recording {
+    /// Version of the format of the recording, which is only played back by
+    /// the version which wrote it
+    pub version: u64,
+    /// Version of the game which made the recording
+    #[serde(default)]
+    pub game_version: String,
+    /// Seed from which the world was generated
+    pub seed: u32,
+    /// The number of steps the simulation had run when the recording started
+    pub start_tick: u64,
+    /// The number of steps the simulation had run when the recording stopped
+    pub end_tick: u64,
+    #[serde(default)]
+    pub commands: Vec<RecordedCommand>,
+    /// Left out of listings edited by hand, whose replays are not checked
+    #[serde(default)]
+    pub checksums: Vec<Checksum>,
+}
@@ -0,0 +1,430 @@
+use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use cgmath::Point3;
+use serde_json;
+use world;
+use world::{MaterialRegistry, World};
+
+use command::PlayerCommand;
+use error::ColonizeResult;
+use paths;
+use save;
+
+#[cfg(feature = "nightly")]
+include!("recording.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/recording.rs"));
+
+const RECORDINGS_DIR: &'static str = "recordings/";
+const RECORDING_FILE: &'static str = "recording.json";
+/// Version of the format of recordings, which is raised whenever the
+/// simulation changes in a way which replays the same commands differently.
+pub const RECORDING_VERSION: u64 = 1;
+/// Steps of the simulation between checksums of the state of the world.
+const CHECKSUM_INTERVAL: u64 = 100;
+/// Version of the game, which is kept in every recording.
+const GAME_VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+/// The positions of the chunks changed since a recording started, in the
+/// order in which they are hashed.
+///
+/// Only these chunks are hashed, as which of the others happen to be loaded
+/// depends on where the camera was while the game was played, rather than
+/// on the simulation. A chunk is noted once it has been changed and not yet
+/// saved, which is checked after every command and step, between which the
+/// game is never saved.
+#[derive(Default)]
+pub struct ChangedChunks {
+    /// The step at which each chunk was first noted.
+    positions: BTreeMap<(i32, i32, i32), u64>,
+    /// The revisions of the chunks restored by rewinding the world which
+    /// were not changed yet by then, and are only noted once changed again.
+    restored: HashMap<(i32, i32, i32), u64>,
+}
+
+impl ChangedChunks {
+    /// Notes the loaded chunks of `world` which have been changed since they
+    /// were last saved.
+    pub fn note(&mut self, world: &World) {
+        let tick = world.calendar().ticks();
+        for (pos, chunk) in world.area.loaded_chunks() {
+            let key = (pos.x, pos.y, pos.z);
+            if !chunk.is_dirty() || self.restored.get(&key) == Some(&chunk.revision()) {
+                continue;
+            }
+            self.restored.remove(&key);
+            self.positions.entry(key).or_insert(tick);
+        }
+    }
+
+    /// Forgets the chunks first changed after the step `world` was just
+    /// rewound to, which are changed again as the simulation catches up.
+    fn seek(&mut self, world: &World) {
+        let tick = world.calendar().ticks();
+        let positions = ::std::mem::replace(&mut self.positions, BTreeMap::new());
+        self.positions = positions.into_iter().filter(|&(_, noted)| noted <= tick).collect();
+        // The chunks restored by rewinding are marked as changed, although
+        // those first changed after the step hold what they did before then.
+        self.restored.clear();
+        for (pos, chunk) in world.area.loaded_chunks() {
+            let key = (pos.x, pos.y, pos.z);
+            if chunk.is_dirty() && !self.positions.contains_key(&key) {
+                self.restored.insert(key, chunk.revision());
+            }
+        }
+    }
+}
+
+/// Returns a hash of the state of `world`, along with the contents of the
+/// chunks in `changed` which are loaded.
+///
+/// The state is hashed as it is saved, so that anything which makes the
+/// simulation play out differently sooner or later changes the hash, such as
+/// the random number generator, the entities and the queued jobs. The chunks
+/// are hashed as they are encoded in saves, so that the tiles the colonists
+/// dug out, built or flooded are checked too.
+pub fn checksum(world: &World, changed: &ChangedChunks) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    match serde_json::to_string(world.state()) {
+        Ok(json) => json.hash(&mut hasher),
+        Err(e) => warn!("Failed to serialize the world for a checksum: {}", e),
+    }
+    for &(x, y, z) in changed.positions.keys() {
+        if let Some(chunk) = world.area.get_chunk(Point3::new(x, y, z)) {
+            (x, y, z).hash(&mut hasher);
+            world::encode_chunk(chunk).hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// Records the commands carried out on a world, so that the game can be
+/// replayed from the save made when the recording started.
+///
+/// Only the commands are recorded, rather than the input of the player,
+/// since the simulation runs in whole steps whatever the frame rate, and
+/// every change the player makes to the world goes through a command. A
+/// recording is kept in a directory of its own, along with the save it
+/// starts from, so that saving the game under another name while recording
+/// never changes where the replay starts.
+pub struct Recorder {
+    name: String,
+    recording: Recording,
+    changed: ChangedChunks,
+    /// Whether the recording was written out as it was stopped.
+    finished: bool,
+}
+
+impl Recorder {
+    /// Starts recording `world` under `name`, saving it as the recording
+    /// starts from it. Any recording made under the same name before is
+    /// replaced.
+    pub fn start(name: &str, world: &mut World, camera_position: &Point3<f64>) -> ColonizeResult<Self> {
+        let path = try!(recording_path(name));
+        // The chunks of the last recording must not be mixed into the save.
+        if path.exists() {
+            try!(fs::remove_dir_all(&path));
+        }
+        try!(save::save_world_to(&path, world, camera_position));
+
+        let changed = ChangedChunks::default();
+        let tick = world.calendar().ticks();
+        let hash = checksum(world, &changed);
+        Ok(Recorder {
+            name: name.to_owned(),
+            recording: Recording {
+                version: RECORDING_VERSION,
+                game_version: GAME_VERSION.to_owned(),
+                seed: world.seed(),
+                start_tick: tick,
+                end_tick: tick,
+                commands: Vec::new(),
+                checksums: vec![Checksum { tick: tick, hash: hash }],
+            },
+            changed: changed,
+            finished: false,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the recording so far as it is written out.
+    pub fn to_json(&self) -> ColonizeResult<String> {
+        Ok(try!(serde_json::to_string(&self.recording)))
+    }
+
+    /// Records `command`, which was just carried out on `world`.
+    pub fn record(&mut self, world: &World, command: PlayerCommand) {
+        self.changed.note(world);
+        self.recording.commands.push(RecordedCommand {
+            tick: world.calendar().ticks(),
+            command: command,
+        });
+    }
+
+    /// Records a checksum of `world` every `CHECKSUM_INTERVAL` steps of the
+    /// simulation, given that it just ran one.
+    pub fn after_tick(&mut self, world: &World) {
+        self.changed.note(world);
+        let tick = world.calendar().ticks();
+        self.recording.end_tick = tick;
+        if (tick - self.recording.start_tick) % CHECKSUM_INTERVAL == 0 {
+            let hash = checksum(world, &self.changed);
+            self.recording.checksums.push(Checksum { tick: tick, hash: hash });
+        }
+    }
+
+    /// Stops recording, writing the recording out alongside the save it
+    /// starts from.
+    pub fn finish(mut self) -> ColonizeResult<()> {
+        self.finished = true;
+        self.write()
+    }
+
+    fn write(&self) -> ColonizeResult<()> {
+        let json = try!(self.to_json());
+        let mut file = try!(File::create(try!(recording_path(&self.name)).join(RECORDING_FILE)));
+        try!(file.write_all(json.as_bytes()));
+        Ok(())
+    }
+}
+
+impl Drop for Recorder {
+    /// Writes out the recording unless it was stopped, so that it is kept
+    /// when the game is left while recording.
+    fn drop(&mut self) {
+        if !self.finished {
+            if let Err(e) = self.write() {
+                warn!("Failed to write recording {}: {}", self.name, e);
+            }
+        }
+    }
+}
+
+/// Copies the recording made under `name`, along with the save it starts
+/// from, into a new directory at `destination`, from which it can be viewed
+/// with `--view-replay`. The directory must not exist yet.
+pub fn export(name: &str, destination: &Path) -> ColonizeResult<()> {
+    let path = try!(recording_path(name));
+    if !path.join(RECORDING_FILE).exists() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("no recording named {}", name)).into());
+    }
+    if destination.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", destination.display())).into());
+    }
+
+    try!(copy_dir(&path, destination));
+    Ok(())
+}
+
+/// Writes the recording made under `name` to the file at `destination` as a
+/// listing of its commands and checksums, one field per line, which can be
+/// read, edited by hand and compared with other listings.
+pub fn dump(name: &str, destination: &Path) -> ColonizeResult<()> {
+    let path = try!(recording_path(name));
+    let json = try!(::read_file_to_string(&path.join(RECORDING_FILE)));
+    let recording: Recording = try!(serde_json::from_str(&json));
+
+    let listing = try!(serde_json::to_string_pretty(&recording));
+    let mut file = try!(File::create(destination));
+    try!(file.write_all(listing.as_bytes()));
+    try!(file.write_all(b"\n"));
+    Ok(())
+}
+
+/// Reads the listing in the file at `source` back into the recording made
+/// under `name`, replacing its commands and checksums while keeping the
+/// save it starts from.
+///
+/// The listing must be of the same version of the format, and list its
+/// commands and checksums in order, within the steps it was recorded over.
+pub fn import(source: &Path, name: &str) -> ColonizeResult<()> {
+    let path = try!(recording_path(name));
+    if !path.join(RECORDING_FILE).exists() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("no recording named {}", name)).into());
+    }
+    let listing = try!(::read_file_to_string(&source.to_path_buf()));
+    let recording: Recording = try!(serde_json::from_str(&listing));
+    try!(validate(&recording));
+
+    let json = try!(serde_json::to_string(&recording));
+    let mut file = try!(File::create(path.join(RECORDING_FILE)));
+    try!(file.write_all(json.as_bytes()));
+    Ok(())
+}
+
+/// Checks that `recording` can be played back, as one edited by hand may
+/// not.
+fn validate(recording: &Recording) -> io::Result<()> {
+    let invalid = |message: String| Err(io::Error::new(io::ErrorKind::InvalidData, message));
+
+    if recording.version != RECORDING_VERSION {
+        return invalid(format!("recording version {} is not {}", recording.version, RECORDING_VERSION));
+    }
+    if recording.end_tick < recording.start_tick {
+        return invalid(format!("recording ends at step {}, before it starts", recording.end_tick));
+    }
+    let command_ticks: Vec<u64> = recording.commands.iter().map(|recorded| recorded.tick).collect();
+    let checksum_ticks: Vec<u64> = recording.checksums.iter().map(|checksum| checksum.tick).collect();
+    for &(kind, ref ticks) in &[("command", command_ticks), ("checksum", checksum_ticks)] {
+        if let Some(&tick) = ticks.iter().find(|&&tick| tick < recording.start_tick || tick > recording.end_tick) {
+            return invalid(format!("{} at step {} is outside the recording", kind, tick));
+        }
+        if let Some(pair) = ticks.windows(2).find(|pair| pair[1] < pair[0]) {
+            return invalid(format!("{} at step {} is listed after step {}", kind, pair[1], pair[0]));
+        }
+    }
+    Ok(())
+}
+
+/// Replays a recording, carrying out its commands on the world loaded from
+/// the save it starts from as the simulation reaches the steps at which they
+/// were recorded, and checking the state of the world against the checksums
+/// along the way.
+pub struct Playback {
+    /// The directory holding the recording and the save it starts from.
+    path: PathBuf,
+    recording: Recording,
+    changed: ChangedChunks,
+    /// The index of the next command to carry out.
+    next_command: usize,
+    /// The index of the next checksum to check.
+    next_checksum: usize,
+    /// The step after which the state of the world first differed from the
+    /// recording, if it has.
+    divergence: Option<u64>,
+}
+
+impl Playback {
+    /// Opens the recording made under `name`, returning it along with the
+    /// world it starts from and the position of the camera at the time.
+    pub fn open(name: &str, initial_size: u32, materials: Rc<MaterialRegistry>) -> ColonizeResult<(Self, World, Point3<f64>)> {
+        Playback::open_from(&try!(recording_path(name)), initial_size, materials)
+    }
+
+    /// Opens the recording in the directory at `path`, such as one exported
+    /// elsewhere, as `open` does those made under a name.
+    pub fn open_from(path: &Path, initial_size: u32, materials: Rc<MaterialRegistry>) -> ColonizeResult<(Self, World, Point3<f64>)> {
+        let json = try!(::read_file_to_string(&path.join(RECORDING_FILE)));
+        let recording: Recording = try!(serde_json::from_str(&json));
+        let (world, camera_position) = try!(save::load_world_from(path, initial_size, materials));
+
+        if recording.version != RECORDING_VERSION || recording.game_version != GAME_VERSION {
+            warn!("Recording {} was made by version {} of the game, and may not replay faithfully", path.display(), recording.game_version);
+        }
+        let mut playback = Playback {
+            path: path.to_path_buf(),
+            recording: recording,
+            changed: ChangedChunks::default(),
+            next_command: 0,
+            next_checksum: 0,
+            divergence: None,
+        };
+        playback.check(&world);
+        Ok((playback, world, camera_position))
+    }
+
+    /// Returns the directory holding the recording and the save it starts
+    /// from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Catches up with `world` after it was rewound to an earlier step,
+    /// carrying out the commands of the recording from that step on again.
+    pub fn seek(&mut self, world: &World) {
+        let tick = world.calendar().ticks();
+        self.next_command = self.recording.commands.iter()
+            .position(|recorded| recorded.tick >= tick)
+            .unwrap_or(self.recording.commands.len());
+        self.next_checksum = self.recording.checksums.iter()
+            .position(|checksum| checksum.tick > tick)
+            .unwrap_or(self.recording.checksums.len());
+        self.changed.seek(world);
+    }
+
+    /// Returns the commands to carry out on `world` before its next step, in
+    /// the order in which they were recorded.
+    pub fn take_due_commands(&mut self, world: &World) -> Vec<PlayerCommand> {
+        let tick = world.calendar().ticks();
+        let mut due = Vec::new();
+        while let Some(recorded) = self.recording.commands.get(self.next_command) {
+            if recorded.tick > tick {
+                break;
+            }
+            due.push(recorded.command.clone());
+            self.next_command += 1;
+        }
+        due
+    }
+
+    /// Checks `world`, given that it just ran a step, against the checksum
+    /// recorded once the simulation had run as many steps, if any. Returns
+    /// the step at which it differs, the first time it does.
+    pub fn check(&mut self, world: &World) -> Option<u64> {
+        self.changed.note(world);
+        let tick = world.calendar().ticks();
+        while let Some(&expected) = self.recording.checksums.get(self.next_checksum) {
+            if expected.tick > tick {
+                break;
+            }
+            self.next_checksum += 1;
+            if expected.tick == tick && self.divergence.is_none() && expected.hash != checksum(world, &self.changed) {
+                warn!("Replay diverged from the recording at step {}", tick);
+                self.divergence = Some(tick);
+                return self.divergence;
+            }
+        }
+        None
+    }
+
+    /// Returns the number of steps the simulation had run when the recording
+    /// stopped.
+    pub fn end_tick(&self) -> u64 {
+        self.recording.end_tick
+    }
+
+    /// Returns whether the simulation of `world` has run every step of the
+    /// recording.
+    pub fn is_finished(&self, world: &World) -> bool {
+        world.calendar().ticks() >= self.recording.end_tick
+    }
+
+    /// Returns the step after which the state of the world first differed
+    /// from the recording, if it has.
+    pub fn divergence(&self) -> Option<u64> {
+        self.divergence
+    }
+}
+
+/// Copies the directory at `from`, along with everything in it, to `to`.
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    try!(fs::create_dir_all(to));
+    for entry in try!(fs::read_dir(from)) {
+        let entry = try!(entry);
+        let target = to.join(entry.file_name());
+        if try!(entry.file_type()).is_dir() {
+            try!(copy_dir(&entry.path(), &target));
+        } else {
+            try!(fs::copy(entry.path(), target));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the directory in which the recording made under `name` is kept
+/// along with the save it starts from.
+fn recording_path(name: &str) -> ColonizeResult<PathBuf> {
+    save::named_path(&paths::data_dir().join(RECORDINGS_DIR), name)
+}
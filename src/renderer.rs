@@ -0,0 +1,467 @@
+use std::{u8, u16};
+
+use cgmath::{Point2, Point3};
+use graphics;
+use rgframework::backend::{Backend, Graphics};
+use rgframework::backend::graphics::Context;
+use world::{Area, ChunkArray2d, MaterialId, MaterialRegistry, CHUNK_SIZE, LOG2_OF_CHUNK_SIZE, MAX_LIGHT_LEVEL};
+
+use palette::Palette;
+use slice_cache;
+use slice_cache::{SliceCache, SliceCell, SliceRevisions};
+use slice_textures::SliceTextures;
+use tile_batch::{BatchKey, TileBatch};
+use tile_renderer::TileRenderer;
+
+// The batch of the tiles which have not been revealed, which no material and
+// light level share.
+const UNREVEALED_BATCH: BatchKey = (u16::MAX, u8::MAX);
+// Brightness of unlit tiles, relative to fully lit ones.
+const MIN_LIGHT_BRIGHTNESS: f32 = 0.15;
+
+/// A tile of the map, as it is to be drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapTile {
+    /// The opaque material visible at the tile, which may not be in the
+    /// registry, in which case only the overlay is drawn.
+    pub material: MaterialId,
+    /// The see-through material drawn over `material`, and its opacity.
+    pub overlay: Option<(MaterialId, f32)>,
+    /// The light level at which the tile is seen.
+    pub light: u8,
+    /// The factor by which the colors of the tile are multiplied.
+    pub brightness: f32,
+    /// Whether the tile has been revealed to the player. Tiles which have not
+    /// are drawn alike, whatever they are made of.
+    pub revealed: bool,
+}
+
+/// Draws the game, so that it can be shown by other means than the window.
+///
+/// The map is drawn one tile at a time, at positions given in tiles from the
+/// first tile of the view, while text is placed in pixels, which a backend
+/// drawing in another unit lays out as it can.
+pub trait Renderer {
+    /// Starts a frame by filling the view with `color`.
+    fn clear(&mut self, color: [f32; 4]);
+
+    /// Draws a tile of the map at `pos`.
+    fn draw_tile(&mut self, pos: Point2<i32>, tile: &MapTile);
+
+    /// Draws `slice`, the slice of the chunk column at `chunk_pos` resolved
+    /// from chunks with the revisions `revisions`, with its first tile at
+    /// `pos` and lit by `daylight`. This is called before the tiles of the
+    /// slice are drawn one by one, and renderers which draw some of them here
+    /// leave those out of `draw_tile`. Others draw nothing.
+    #[allow(unused_variables)]
+    fn draw_slice(&mut self, pos: Point2<i32>, chunk_pos: Point2<i32>, revisions: SliceRevisions, slice: &ChunkArray2d<SliceCell>, daylight: f32) {}
+
+    /// Draws a line of text with its top-left corner at `pos`.
+    fn draw_text(&mut self, pos: [f64; 2], text: &str, color: [f32; 4], font_size: u32);
+
+    /// Finishes the frame, drawing anything held back until then.
+    fn present(&mut self);
+}
+
+/// The tiles visible at a single z-level of the tile view, and where they are
+/// drawn on screen.
+#[derive(Clone, Copy)]
+pub struct TileView {
+    /// The z-level being viewed.
+    pub y: i32,
+    /// The first and last visible tiles along the X and Z axes.
+    pub min: Point2<i32>,
+    pub max: Point2<i32>,
+    /// Pixel offset of the first visible tile.
+    pub offset: Point2<f64>,
+    pub tile_size: f64,
+}
+
+impl TileView {
+    /// Returns the rectangle covered by the tile at `pos` on screen, or `None`
+    /// if it is not visible.
+    pub fn rect(&self, pos: &Point3<i32>) -> Option<[f64; 4]> {
+        let in_view = pos.y == self.y &&
+            pos.x >= self.min.x && pos.x <= self.max.x &&
+            pos.z >= self.min.y && pos.z <= self.max.y;
+        if !in_view {
+            return None;
+        }
+
+        Some(self.cell_rect(Point2::new(pos.x - self.min.x, pos.z - self.min.y)))
+    }
+
+    /// Returns the center of the tile at `pos` on screen, whether it is
+    /// visible or not.
+    pub fn center(&self, pos: &Point3<i32>) -> [f64; 2] {
+        [
+            (pos.x - self.min.x) as f64 * self.tile_size + self.offset.x + self.tile_size / 2.0,
+            (pos.z - self.min.y) as f64 * self.tile_size + self.offset.y + self.tile_size / 2.0,
+        ]
+    }
+
+    /// Returns the rectangle covered on screen by the tile at `pos`, in tiles
+    /// from the first tile of the view.
+    fn cell_rect(&self, pos: Point2<i32>) -> [f64; 4] {
+        graphics::rectangle::square(
+            pos.x as f64 * self.tile_size + self.offset.x,
+            pos.y as f64 * self.tile_size + self.offset.y,
+            self.tile_size)
+    }
+}
+
+/// Draws the tiles of `view` with `renderer`, lit by `daylight`. Tiles
+/// outside of the generated chunks have no material, and are left undrawn.
+/// The slices of the chunks out of view are then dropped from `slice_cache`.
+pub fn draw_map<R>(renderer: &mut R, view: &TileView, slice_cache: &mut SliceCache, area: &Area, materials: &MaterialRegistry, daylight: f32)
+    where R: Renderer,
+{
+    // Tiles are drawn one chunk at a time, so that the resolved slice of each
+    // chunk only needs to be fetched from the cache once per frame.
+    for chunk_x in (view.min.x >> LOG2_OF_CHUNK_SIZE)..(view.max.x >> LOG2_OF_CHUNK_SIZE) + 1 {
+        for chunk_z in (view.min.y >> LOG2_OF_CHUNK_SIZE)..(view.max.y >> LOG2_OF_CHUNK_SIZE) + 1 {
+            let chunk_pos = Point2::new(chunk_x, chunk_z);
+            let slice = slice_cache.get_slice(area, materials, chunk_pos, view.y);
+            let slice_origin = Point2::new(chunk_x * CHUNK_SIZE as i32 - view.min.x, chunk_z * CHUNK_SIZE as i32 - view.min.y);
+            renderer.draw_slice(slice_origin, chunk_pos, slice_cache::slice_revisions(area, chunk_pos, view.y), slice, daylight);
+
+            for (rel_z, row) in slice.iter().enumerate() {
+                let z = chunk_z * CHUNK_SIZE as i32 + rel_z as i32;
+                if z < view.min.y || z > view.max.y {
+                    continue;
+                }
+
+                for (rel_x, cell) in row.iter().enumerate() {
+                    let x = chunk_x * CHUNK_SIZE as i32 + rel_x as i32;
+                    if x < view.min.x || x > view.max.x {
+                        continue;
+                    }
+
+                    // Liquids are more opaque the fuller their tile is.
+                    let overlay = cell.overlay.and_then(|(id, fill)| {
+                        materials.get(id).map(|overlay| (id, overlay.color[3] * fill))
+                    });
+                    if materials.get(cell.material).is_none() && overlay.is_none() {
+                        continue;
+                    }
+
+                    renderer.draw_tile(Point2::new(x - view.min.x, z - view.min.y), &MapTile {
+                        material: cell.material,
+                        overlay: overlay,
+                        light: cell.light,
+                        brightness: light_brightness(daylight, cell.light),
+                        revealed: cell.revealed,
+                    });
+                }
+            }
+        }
+    }
+    slice_cache.evict_unused();
+}
+
+/// Returns the brightness at which to draw a tile of the specified light
+/// level, given the brightness of fully lit tiles.
+pub fn light_brightness(daylight: f32, light: u8) -> f32 {
+    let light = light as f32 / MAX_LIGHT_LEVEL as f32;
+    (MIN_LIGHT_BRIGHTNESS + (1.0 - MIN_LIGHT_BRIGHTNESS) * light) * daylight
+}
+
+/// Draws into the window with the graphics of the piston backend.
+///
+/// Flat-colored tiles are drawn a slice at a time from the textures of
+/// `SliceTextures` if they are given. Otherwise, they are collected into a
+/// batch if one is given, which is drawn as soon as anything else is, so that
+/// whatever is drawn over the map still covers it. Tiles which have not been
+/// revealed are batched either way.
+pub struct PistonRenderer<'a, B, G>
+    where B: Backend + 'a,
+          G: Graphics<Texture=B::Texture> + 'a,
+{
+    context: &'a Context,
+    graphics: &'a mut G,
+    glyph_cache: &'a mut B::CharacterCache,
+    tile_renderer: &'a TileRenderer<B>,
+    materials: &'a MaterialRegistry,
+    palette: &'a Palette,
+    view: TileView,
+    tile_batch: Option<&'a mut TileBatch>,
+    slice_textures: Option<&'a mut SliceTextures<B>>,
+    /// Whether the flat-colored tiles of the slice being drawn were drawn
+    /// from its texture.
+    slice_drawn: bool,
+    /// The number of draw calls the tiles have taken so far.
+    tile_draw_calls: usize,
+}
+
+impl<'a, B, G> PistonRenderer<'a, B, G>
+    where B: Backend + 'a,
+          G: Graphics<Texture=B::Texture> + 'a,
+{
+    pub fn new(context: &'a Context, graphics: &'a mut G, glyph_cache: &'a mut B::CharacterCache, tile_renderer: &'a TileRenderer<B>, materials: &'a MaterialRegistry, palette: &'a Palette, view: TileView, tile_batch: Option<&'a mut TileBatch>, slice_textures: Option<&'a mut SliceTextures<B>>) -> Self {
+        PistonRenderer {
+            context: context,
+            graphics: graphics,
+            glyph_cache: glyph_cache,
+            tile_renderer: tile_renderer,
+            materials: materials,
+            palette: palette,
+            view: view,
+            tile_batch: tile_batch,
+            slice_textures: slice_textures,
+            slice_drawn: false,
+            tile_draw_calls: 0,
+        }
+    }
+
+    /// Draws the batched tiles, returning the number of draw calls the tiles
+    /// drawn so far have taken.
+    pub fn flush_tiles(&mut self) -> usize {
+        if let Some(ref mut tile_batch) = self.tile_batch {
+            self.tile_draw_calls += tile_batch.draw_call_count();
+            tile_batch.draw(self.context, self.graphics);
+        }
+        self.tile_draw_calls
+    }
+
+    /// Returns the graphics and glyph cache, to draw what the renderer does
+    /// not with, over everything it drew so far.
+    pub fn parts(&mut self) -> (&mut G, &mut B::CharacterCache) {
+        self.flush_tiles();
+        (&mut *self.graphics, &mut *self.glyph_cache)
+    }
+}
+
+impl<'a, B, G> Renderer for PistonRenderer<'a, B, G>
+    where B: Backend + 'a,
+          G: Graphics<Texture=B::Texture> + 'a,
+{
+    fn clear(&mut self, color: [f32; 4]) {
+        graphics::clear(color, self.graphics);
+    }
+
+    fn draw_tile(&mut self, pos: Point2<i32>, tile: &MapTile) {
+        let rect = self.view.cell_rect(pos);
+        let material = self.materials.get(tile.material);
+        if self.slice_drawn && tile.revealed && tile.overlay.is_none() {
+            let is_flat = material.map_or(false, |material| self.tile_renderer.flat_color(material, self.palette, tile.brightness).is_some());
+            if is_flat {
+                return;
+            }
+        }
+        if let Some(ref mut tile_batch) = self.tile_batch {
+            if !tile.revealed {
+                tile_batch.push(UNREVEALED_BATCH, self.palette.unrevealed, rect);
+                return;
+            }
+            // Tiles with a texture or an overlay are drawn one by one.
+            let color = match (material, tile.overlay) {
+                (Some(material), None) => self.tile_renderer.flat_color(material, self.palette, tile.brightness),
+                _ => None,
+            };
+            if let Some(color) = color {
+                let MaterialId(id) = tile.material;
+                tile_batch.push((id, tile.light), color, rect);
+                return;
+            }
+        } else if !tile.revealed {
+            graphics::Rectangle::new(self.palette.unrevealed).draw(rect, &self.context.draw_state, self.context.transform, self.graphics);
+            self.tile_draw_calls += 1;
+            return;
+        }
+
+        if let Some(material) = material {
+            self.tile_renderer.draw_tile(material, self.palette, tile.brightness, 1.0, rect, self.context, self.graphics);
+        }
+        if let Some((overlay, opacity)) = tile.overlay {
+            if let Some(overlay) = self.materials.get(overlay) {
+                self.tile_renderer.draw_tile(overlay, self.palette, tile.brightness, opacity, rect, self.context, self.graphics);
+            }
+        }
+        self.tile_draw_calls += 1;
+    }
+
+    fn draw_slice(&mut self, pos: Point2<i32>, chunk_pos: Point2<i32>, revisions: SliceRevisions, slice: &ChunkArray2d<SliceCell>, daylight: f32) {
+        use graphics::Image;
+
+        self.slice_drawn = false;
+        let texture = match self.slice_textures {
+            Some(ref mut slice_textures) => slice_textures.get(chunk_pos, self.view.y, revisions, slice, self.tile_renderer, self.materials, self.palette),
+            None => return,
+        };
+        if let Some(texture) = texture {
+            let origin = self.view.cell_rect(pos);
+            let size = CHUNK_SIZE as f64 * self.view.tile_size;
+            Image::new_color([daylight, daylight, daylight, 1.0])
+                .rect([origin[0], origin[1], size, size])
+                .draw(texture, &self.context.draw_state, self.context.transform, self.graphics);
+            self.slice_drawn = true;
+            self.tile_draw_calls += 1;
+        }
+    }
+
+    fn draw_text(&mut self, pos: [f64; 2], text: &str, color: [f32; 4], font_size: u32) {
+        use graphics::Transformed;
+        use graphics::text::Text;
+
+        self.flush_tiles();
+        Text::new_color(color, font_size).draw(
+            text,
+            self.glyph_cache,
+            &self.context.draw_state,
+            self.context.transform.trans(pos[0], pos[1]),
+            self.graphics);
+    }
+
+    fn present(&mut self) {
+        self.flush_tiles();
+    }
+}
+
+/// Something drawn by a `HeadlessRenderer`.
+#[cfg(test)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCommand {
+    Clear([f32; 4]),
+    Tile(Point2<i32>, MapTile),
+    /// The position of the first tile of a slice, and its chunk column.
+    Slice(Point2<i32>, Point2<i32>),
+    Text([f64; 2], String),
+    Present,
+}
+
+/// Records what it is asked to draw, rather than drawing it, so that tests
+/// can check what a frame is made of without a window.
+#[cfg(test)]
+#[derive(Default)]
+pub struct HeadlessRenderer {
+    pub commands: Vec<DrawCommand>,
+}
+
+#[cfg(test)]
+impl HeadlessRenderer {
+    /// Returns the tiles drawn, in the order in which they were.
+    pub fn tiles(&self) -> Vec<(Point2<i32>, MapTile)> {
+        self.commands.iter().filter_map(|command| match *command {
+            DrawCommand::Tile(pos, tile) => Some((pos, tile)),
+            _ => None,
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+impl Renderer for HeadlessRenderer {
+    fn clear(&mut self, color: [f32; 4]) {
+        self.commands.push(DrawCommand::Clear(color));
+    }
+
+    fn draw_tile(&mut self, pos: Point2<i32>, tile: &MapTile) {
+        self.commands.push(DrawCommand::Tile(pos, *tile));
+    }
+
+    fn draw_slice(&mut self, pos: Point2<i32>, chunk_pos: Point2<i32>, _revisions: SliceRevisions, _slice: &ChunkArray2d<SliceCell>, _daylight: f32) {
+        self.commands.push(DrawCommand::Slice(pos, chunk_pos));
+    }
+
+    fn draw_text(&mut self, pos: [f64; 2], text: &str, _color: [f32; 4], _font_size: u32) {
+        self.commands.push(DrawCommand::Text(pos, text.to_owned()));
+    }
+
+    fn present(&mut self) {
+        self.commands.push(DrawCommand::Present);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Point2, Point3};
+    use world::{Area, MaterialRegistry, Tile, WorldGenConfig, CHUNK_SIZE};
+
+    use slice_cache::SliceCache;
+    use super::*;
+
+    const SEED: u32 = 42;
+
+    /// Returns an area generated around the origin, along with a z-level
+    /// within its generated chunks.
+    fn area(materials: &MaterialRegistry) -> (Area, i32) {
+        let area = Area::new(SEED, 1, Point2::new(0, 0), WorldGenConfig::default(), materials, None);
+        let y = area.surface_chunk(Point2::new(0, 0)).y * CHUNK_SIZE as i32;
+        (area, y)
+    }
+
+    fn view(y: i32, min: Point2<i32>, max: Point2<i32>) -> TileView {
+        TileView {
+            y: y,
+            min: min,
+            max: max,
+            offset: Point2::new(0.0, 0.0),
+            tile_size: 16.0,
+        }
+    }
+
+    #[test]
+    fn draws_each_tile_of_the_view_once() {
+        let materials = MaterialRegistry::default();
+        let (area, y) = area(&materials);
+        let mut renderer = HeadlessRenderer::default();
+        // The view straddles the chunks on either side of the origin.
+        let half = CHUNK_SIZE as i32 / 2;
+        draw_map(&mut renderer, &view(y, Point2::new(-half, -half), Point2::new(half - 1, half - 1)), &mut SliceCache::new(), &area, &materials, 1.0);
+
+        let mut positions: Vec<_> = renderer.tiles().into_iter().map(|(pos, _)| (pos.x, pos.y)).collect();
+        positions.sort();
+        let mut expected = Vec::new();
+        for x in 0..CHUNK_SIZE as i32 {
+            for z in 0..CHUNK_SIZE as i32 {
+                expected.push((x, z));
+            }
+        }
+        assert_eq!(positions, expected);
+    }
+
+    #[test]
+    fn draws_each_slice_of_the_view_from_its_first_tile() {
+        let materials = MaterialRegistry::default();
+        let (area, y) = area(&materials);
+        let mut renderer = HeadlessRenderer::default();
+        let half = CHUNK_SIZE as i32 / 2;
+        draw_map(&mut renderer, &view(y, Point2::new(-half, -half), Point2::new(half - 1, half - 1)), &mut SliceCache::new(), &area, &materials, 1.0);
+
+        let slices: Vec<_> = renderer.commands.iter().filter_map(|command| match *command {
+            DrawCommand::Slice(pos, chunk_pos) => Some(((pos.x, pos.y), (chunk_pos.x, chunk_pos.y))),
+            _ => None,
+        }).collect();
+        assert_eq!(slices, vec![
+            ((-half, -half), (-1, -1)),
+            ((-half, half), (-1, 0)),
+            ((half, -half), (0, -1)),
+            ((half, half), (0, 0)),
+        ]);
+    }
+
+    #[test]
+    fn draws_the_material_set_at_a_tile() {
+        let materials = MaterialRegistry::default();
+        let (mut area, y) = area(&materials);
+        let gold = materials.find("gold").unwrap();
+        assert!(area.set_tile(&Point3::new(3, y, 5), Tile::new(gold)));
+
+        let mut renderer = HeadlessRenderer::default();
+        draw_map(&mut renderer, &view(y, Point2::new(2, 2), Point2::new(9, 9)), &mut SliceCache::new(), &area, &materials, 1.0);
+
+        let tiles = renderer.tiles();
+        let drawn = tiles.iter().find(|&&(pos, _)| pos == Point2::new(1, 3)).map(|&(_, tile)| tile.material);
+        assert_eq!(drawn, Some(gold));
+    }
+
+    #[test]
+    fn leaves_tiles_outside_of_the_generated_chunks_undrawn() {
+        let materials = MaterialRegistry::default();
+        let (area, y) = area(&materials);
+        let mut renderer = HeadlessRenderer::default();
+        let far = 10 * CHUNK_SIZE as i32;
+        draw_map(&mut renderer, &view(y, Point2::new(far, far), Point2::new(far + 7, far + 7)), &mut SliceCache::new(), &area, &materials, 1.0);
+
+        assert!(renderer.tiles().is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+use cgmath::Point2;
+use rgframework::Label;
+use rgframework::backend::{Backend, Graphics};
+use rgframework::backend::graphics::Context;
+use rgframework::draw::Draw;
+use world::Entity;
+
+use palette::Palette;
+
+/// Width of the roster, in pixels, at a UI scale of 1.
+const ROSTER_WIDTH: f64 = 220.0;
+const LINE_SPACING: f64 = 1.5;
+const PADDING: f64 = 10.0;
+/// Height of the bars showing how well the needs of a colonist are met, and
+/// the space between them, in pixels at a UI scale of 1.
+const BAR_HEIGHT: f64 = 6.0;
+const BAR_GAP: f64 = 4.0;
+/// Space left beneath every colonist, in pixels at a UI scale of 1.
+const ENTRY_GAP: f64 = 8.0;
+
+/// What the roster shows of a colonist.
+pub struct RosterEntry {
+    pub entity: Entity,
+    /// The name of the colonist, and where it is.
+    pub title: String,
+    /// What the colonist is doing.
+    pub activity: String,
+    /// How well each need of the colonist is met, from 0 to 1, in the order
+    /// in which the heading of the roster names them.
+    pub needs: [f32; 3],
+}
+
+/// An on-screen panel listing every colonist, with where it is, what it is
+/// doing and how well its needs are met. The roster is hidden until it is
+/// toggled.
+pub struct Roster {
+    /// Position of the top-left corner of the roster, in pixels.
+    origin: [f64; 2],
+    font_size: u32,
+    /// The factor by which the width, padding and bars of the roster are
+    /// scaled.
+    scale: f64,
+    text_color: [f32; 4],
+    background_color: [f32; 4],
+    bar_color: [f32; 4],
+    bar_background_color: [f32; 4],
+    visible: bool,
+    heading: Vec<Label>,
+    entries: Vec<RosterEntry>,
+    /// The title and activity of every entry, in turn.
+    labels: Vec<Label>,
+}
+
+impl Roster {
+    pub fn new(font_size: u32, scale: f64, origin: [f64; 2], palette: &Palette) -> Self {
+        Roster {
+            origin: origin,
+            font_size: font_size,
+            scale: scale,
+            text_color: palette.text,
+            background_color: palette.panel_background,
+            bar_color: palette.need_bar,
+            bar_background_color: palette.button,
+            visible: false,
+            heading: Vec::new(),
+            entries: Vec::new(),
+            labels: Vec::new(),
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Lays out the roster with its top-left corner at `origin`, text of
+    /// `font_size`, and its width, padding and bars scaled by `scale`, from
+    /// now on.
+    pub fn set_layout(&mut self, origin: [f64; 2], font_size: u32, scale: f64) {
+        self.origin = origin;
+        self.font_size = font_size;
+        self.scale = scale;
+        // The labels are created anew at the new size with the next entries.
+        self.heading.clear();
+        self.labels.clear();
+    }
+
+    /// Draws the roster in the colors of `palette` from now on.
+    pub fn set_palette(&mut self, palette: &Palette) {
+        self.text_color = palette.text;
+        self.background_color = palette.panel_background;
+        self.bar_color = palette.need_bar;
+        self.bar_background_color = palette.button;
+        for label in self.heading.iter_mut().chain(self.labels.iter_mut()) {
+            label.set_color(palette.text);
+        }
+    }
+
+    /// Replaces the lines of the heading and the colonists listed.
+    pub fn set_entries(&mut self, heading: Vec<String>, entries: Vec<RosterEntry>) {
+        let mut positions = Vec::new();
+        for i in 0..heading.len() {
+            positions.push(self.heading_position(i));
+        }
+        set_labels(&mut self.heading, heading, positions, self.text_color, self.font_size);
+
+        let heading_height = self.heading.len() as f64 * self.line_height();
+        let mut texts = Vec::new();
+        let mut positions = Vec::new();
+        for (i, entry) in entries.iter().enumerate() {
+            let top = self.entry_top(heading_height, i);
+            texts.push(entry.title.clone());
+            positions.push([self.origin[0] + self.padding(), top + self.line_height()]);
+            texts.push(entry.activity.clone());
+            positions.push([self.origin[0] + self.padding(), top + 2.0 * self.line_height()]);
+        }
+        set_labels(&mut self.labels, texts, positions, self.text_color, self.font_size);
+        self.entries = entries;
+    }
+
+    /// Returns the colonist listed at `point`, in pixels, if the roster is
+    /// shown.
+    pub fn entity_at(&self, point: Point2<f64>) -> Option<Entity> {
+        if !self.visible || point.x < self.origin[0] || point.x > self.origin[0] + self.width() {
+            return None;
+        }
+
+        let heading_height = self.heading.len() as f64 * self.line_height();
+        let top = self.entry_top(heading_height, 0);
+        if point.y < top {
+            return None;
+        }
+        let i = ((point.y - top) / self.entry_height()) as usize;
+        self.entries.get(i).map(|entry| entry.entity)
+    }
+
+    /// Returns true if `point`, in pixels, lies over the roster while it is
+    /// shown.
+    pub fn contains(&self, point: Point2<f64>) -> bool {
+        self.visible &&
+            point.x >= self.origin[0] && point.x <= self.origin[0] + self.width() &&
+            point.y >= self.origin[1] && point.y <= self.origin[1] + self.height()
+    }
+
+    /// Returns the width of the roster, in pixels.
+    pub fn width(&self) -> f64 {
+        ROSTER_WIDTH * self.scale
+    }
+
+    /// Returns the height of the roster with its current entries, in pixels.
+    pub fn height(&self) -> f64 {
+        let heading_height = self.heading.len() as f64 * self.line_height();
+        self.entry_top(heading_height, self.entries.len()) - self.origin[1] + self.padding()
+    }
+
+    fn padding(&self) -> f64 {
+        PADDING * self.scale
+    }
+
+    fn line_height(&self) -> f64 {
+        self.font_size as f64 * LINE_SPACING
+    }
+
+    /// Returns the height taken by every entry: its title and activity, the
+    /// bars beneath them, and the space left before the next entry.
+    fn entry_height(&self) -> f64 {
+        2.5 * self.line_height() + (BAR_HEIGHT + ENTRY_GAP) * self.scale
+    }
+
+    /// Returns the position of the baseline of the `i`th line of the heading,
+    /// in pixels.
+    fn heading_position(&self, i: usize) -> [f64; 2] {
+        [
+            self.origin[0] + self.padding(),
+            self.origin[1] + self.padding() + (i + 1) as f64 * self.line_height(),
+        ]
+    }
+
+    /// Returns the top of the `i`th entry, in pixels, beneath a heading of
+    /// `heading_height`.
+    fn entry_top(&self, heading_height: f64, i: usize) -> f64 {
+        self.origin[1] + self.padding() + heading_height + i as f64 * self.entry_height()
+    }
+}
+
+/// Replaces the text and positions of `labels` with `texts` and `positions`,
+/// creating or removing labels as needed.
+fn set_labels(labels: &mut Vec<Label>, texts: Vec<String>, positions: Vec<[f64; 2]>, color: [f32; 4], font_size: u32) {
+    labels.truncate(texts.len());
+    for (i, (text, position)) in texts.into_iter().zip(positions).enumerate() {
+        if i < labels.len() {
+            labels[i].set_text(text);
+            labels[i].set_position(position);
+        } else {
+            labels.push(Label::new(text, color, font_size, position));
+        }
+    }
+}
+
+impl<B, G> Draw<B, G> for Roster
+    where B: Backend,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn draw(&self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        use graphics::Rectangle;
+
+        if !self.visible {
+            return;
+        }
+
+        Rectangle::new(self.background_color).draw(
+            [self.origin[0], self.origin[1], self.width(), self.height()],
+            &context.draw_state,
+            context.transform,
+            graphics);
+
+        for label in self.heading.iter().chain(self.labels.iter()) {
+            Draw::<B, G>::draw(label, context, graphics, glyph_cache);
+        }
+
+        // The needs of every colonist are drawn as bars side by side beneath
+        // its activity, each filled as far as the need is met.
+        let heading_height = self.heading.len() as f64 * self.line_height();
+        let gap = BAR_GAP * self.scale;
+        let bar_width = (self.width() - 2.0 * self.padding() - 2.0 * gap) / 3.0;
+        let bar_height = BAR_HEIGHT * self.scale;
+        for (i, entry) in self.entries.iter().enumerate() {
+            let y = self.entry_top(heading_height, i) + 2.5 * self.line_height();
+            for (j, &need) in entry.needs.iter().enumerate() {
+                let x = self.origin[0] + self.padding() + j as f64 * (bar_width + gap);
+                Rectangle::new(self.bar_background_color).draw(
+                    [x, y, bar_width, bar_height],
+                    &context.draw_state,
+                    context.transform,
+                    graphics);
+                let filled = bar_width * need.max(0.0).min(1.0) as f64;
+                Rectangle::new(self.bar_color).draw(
+                    [x, y, filled, bar_height],
+                    &context.draw_state,
+                    context.transform,
+                    graphics);
+            }
+        }
+    }
+}
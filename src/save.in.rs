@@ -0,0 +1,36 @@
+/// Everything needed to recreate a saved world, other than its modified
+/// chunks, which are kept in region files alongside it.
+#[derive(Deserialize, Serialize)]
+pub struct SaveMetadata {
+    /// Version of the format of the metadata, from which saves of older
+    /// versions are upgraded when loaded
+    #[serde(default)]
+    pub version: u64,
+    /// Seed from which the world was generated
+    pub seed: u32,
+    /// State of the world which is not stored in its chunks
+    pub state: WorldState,
+    /// Parameters with which the world was generated
+    pub world_gen: WorldGenConfig,
+    /// Position of the camera, in tiles
+    pub camera_position: [f64; 3],
+    /// Time at which the game was saved, in seconds since the Unix epoch
+    #[serde(default)]
+    pub saved_at: u64,
+}
+
+/// The parts of the metadata of a save which are listed when picking a game
+/// to load, read without parsing the rest of the state of the world.
+#[derive(Deserialize)]
+struct SaveSummary {
+    seed: u32,
+    state: StateSummary,
+    #[serde(default)]
+    saved_at: u64,
+}
+
+#[derive(Deserialize)]
+struct StateSummary {
+    #[serde(default)]
+    calendar: Calendar,
+}
@@ -0,0 +1,274 @@
+use std::cmp;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cgmath::Point3;
+use image;
+use serde_json;
+use serde_json::Value;
+use world::{Calendar, MaterialRegistry, RegionStore, World, WorldGenConfig, WorldState, CHUNK_SIZE};
+
+use error::{ColonizeError, ColonizeResult};
+use migration;
+use minimap;
+use palette::Palette;
+use paths;
+
+#[cfg(feature = "nightly")]
+include!("save.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/save.rs"));
+
+const SAVES_DIR: &'static str = "saves/";
+const METADATA_FILE: &'static str = "world.json";
+const THUMBNAIL_FILE: &'static str = "thumbnail.png";
+/// The number of pixels of the thumbnail along each side, and the number of
+/// tiles between the columns sampled for neighbouring pixels.
+pub const THUMBNAIL_PIXELS: u32 = 64;
+const THUMBNAIL_SCALE: i32 = 2;
+
+/// A game found in the saves directory, as listed when picking one to load.
+pub struct SavedGame {
+    /// The name of the colony, under which the game was saved.
+    pub name: String,
+    pub seed: u32,
+    /// The in-game date and time at which the game was saved.
+    pub calendar: Calendar,
+    /// The time at which the game was saved, in seconds since the Unix epoch,
+    /// or 0 for games saved before it was recorded.
+    pub saved_at: u64,
+}
+
+/// Returns the name under which a new colony settled in `world` is saved
+/// unless another is given, so that each colony gets a save of its own.
+pub fn default_save_name(world: &World) -> String {
+    format!("colony-{}", world.seed())
+}
+
+/// Saves the world under `name`, writing only the chunks modified since it
+/// was last saved, along with a thumbnail of the surface around the camera.
+pub fn save_world(name: &str, world: &mut World, camera_position: &Point3<f64>) -> ColonizeResult<()> {
+    save_world_to(&try!(save_path(name)), world, camera_position)
+}
+
+/// Saves the world into the directory at `path`, as `save_world` does into
+/// the saves directory.
+pub fn save_world_to(path: &Path, world: &mut World, camera_position: &Point3<f64>) -> ColonizeResult<()> {
+    try!(world.save(try!(RegionStore::new(path))));
+
+    try!(write_metadata(path, &world_metadata(world, camera_position)));
+
+    let thumbnail = draw_thumbnail(world, camera_position);
+    try!(image::save_buffer(&path.join(THUMBNAIL_FILE), &thumbnail, THUMBNAIL_PIXELS, THUMBNAIL_PIXELS, image::ColorType::RGBA(8)));
+
+    Ok(())
+}
+
+/// Loads the world saved under `name`, returning it along with the position
+/// of the camera when it was saved.
+pub fn load_world(name: &str, initial_size: u32, materials: Rc<MaterialRegistry>) -> ColonizeResult<(World, Point3<f64>)> {
+    load_world_from(&try!(save_path(name)), initial_size, materials)
+}
+
+/// Loads the world saved into the directory at `path`, as `load_world` does
+/// from the saves directory.
+pub fn load_world_from(path: &Path, initial_size: u32, materials: Rc<MaterialRegistry>) -> ColonizeResult<(World, Point3<f64>)> {
+    let (metadata, _) = try!(read_metadata(path));
+
+    let store = try!(RegionStore::new(path));
+    let world = World::load(metadata.seed, metadata.state, initial_size, metadata.world_gen, materials, store);
+    let camera_position = metadata.camera_position;
+
+    Ok((world, Point3::new(camera_position[0], camera_position[1], camera_position[2])))
+}
+
+/// Upgrades the game saved under `name` to the current version of the save
+/// format in place, rewriting its metadata and any region files of an older
+/// format. Returns the version of the metadata before the upgrade, and the
+/// number of region files rewritten.
+pub fn migrate_save(name: &str) -> ColonizeResult<(u64, usize)> {
+    let path = try!(save_path(name));
+    let (metadata, version) = try!(read_metadata(&path));
+    if version != migration::SAVE_VERSION {
+        try!(write_metadata(&path, &metadata));
+    }
+
+    let regions = try!(try!(RegionStore::new(&path)).upgrade());
+    Ok((version, regions))
+}
+
+/// Returns the names of the directories in the saves directory, whether or
+/// not they hold a readable save.
+pub fn save_names() -> Vec<String> {
+    let entries = match fs::read_dir(saves_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut names: Vec<String> = entries.filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Returns the games in the saves directory, most recently saved first.
+/// Directories without readable metadata are skipped.
+pub fn list_saves() -> Vec<SavedGame> {
+    let mut saves: Vec<SavedGame> = save_names().into_iter()
+        .filter_map(|name| {
+            let path = saves_dir().join(&name).join(METADATA_FILE);
+            let json = match ::read_file_to_string(&path) {
+                Ok(json) => json,
+                Err(_) => return None,
+            };
+            match serde_json::from_str::<SaveSummary>(&json) {
+                Ok(summary) => Some(SavedGame {
+                    name: name,
+                    seed: summary.seed,
+                    calendar: summary.state.calendar,
+                    saved_at: summary.saved_at,
+                }),
+                Err(e) => {
+                    warn!("Failed to read save {}: {}", name, e);
+                    None
+                },
+            }
+        })
+        .collect();
+    saves.sort_by(|a, b| (b.saved_at, &a.name).cmp(&(a.saved_at, &b.name)));
+    saves
+}
+
+/// Returns the path of the thumbnail of the game saved under `name`, which
+/// games saved before thumbnails were recorded lack.
+pub fn thumbnail_path(name: &str) -> ColonizeResult<PathBuf> {
+    Ok(try!(save_path(name)).join(THUMBNAIL_FILE))
+}
+
+/// Renames the game saved under `name` to `new_name`, unless a game is
+/// already saved under it.
+pub fn rename_save(name: &str, new_name: &str) -> ColonizeResult<()> {
+    let path = try!(save_path(name));
+    let new_path = try!(save_path(new_name));
+    if new_path.exists() {
+        return Err(ColonizeError::SaveExists(new_name.to_owned()));
+    }
+
+    try!(fs::rename(path, new_path));
+    Ok(())
+}
+
+/// Deletes the game saved under `name`, along with all of its chunks.
+pub fn delete_save(name: &str) -> ColonizeResult<()> {
+    try!(fs::remove_dir_all(try!(save_path(name))));
+    Ok(())
+}
+
+/// Returns the position at which the camera starts out in `world`: over the
+/// middle of its embark chunk column, on the z-level just above the surface
+/// or the sea, whichever is higher.
+pub fn embark_camera_position(world: &World) -> Point3<f64> {
+    let embark = world.embark();
+    let x = embark.x * CHUNK_SIZE as i32 + CHUNK_SIZE as i32 / 2;
+    let z = embark.y * CHUNK_SIZE as i32 + CHUNK_SIZE as i32 / 2;
+    let y = cmp::max(world.area.surface_height(x, z), world.config().sea_level) + 1;
+    Point3::new(x as f64, y as f64, z as f64)
+}
+
+/// Returns everything needed to recreate `world` other than its modified
+/// chunks.
+pub fn world_metadata(world: &World, camera_position: &Point3<f64>) -> SaveMetadata {
+    let saved_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+    SaveMetadata {
+        version: migration::SAVE_VERSION,
+        seed: world.seed(),
+        state: world.state().clone(),
+        world_gen: *world.config(),
+        camera_position: [camera_position.x, camera_position.y, camera_position.z],
+        saved_at: saved_at,
+    }
+}
+
+/// Formats `timestamp`, in seconds since the Unix epoch, as a date and time
+/// in UTC.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = timestamp / 86400;
+    let seconds = timestamp % 86400;
+
+    // Counts the days from the 1st of March of year 0, so that leap days
+    // fall at the end of the years, which are grouped into eras of 400 years
+    // repeating the same calendar.
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 };
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, seconds / 3600, seconds / 60 % 60)
+}
+
+/// Reads the metadata of the save in the directory at `path`, upgrading it
+/// to the current version of the save format. Returns the metadata along
+/// with the version it was upgraded from.
+fn read_metadata(path: &Path) -> ColonizeResult<(SaveMetadata, u64)> {
+    let json = try!(::read_file_to_string(&path.join(METADATA_FILE)));
+    let mut metadata: Value = try!(serde_json::from_str(&json));
+    let version = try!(migration::migrate(&mut metadata));
+    Ok((try!(serde_json::from_value(metadata)), version))
+}
+
+fn write_metadata(path: &Path, metadata: &SaveMetadata) -> ColonizeResult<()> {
+    let json = try!(serde_json::to_string_pretty(metadata));
+    let mut file = try!(File::create(path.join(METADATA_FILE)));
+    try!(file.write_all(json.as_bytes()));
+    Ok(())
+}
+
+/// Draws the explored surface around `camera_position` as on the minimap,
+/// returning the pixels of the thumbnail as RGBA bytes. Thumbnails are drawn
+/// in the built-in palette, whichever palette the game was saved with.
+fn draw_thumbnail(world: &World, camera_position: &Point3<f64>) -> Vec<u8> {
+    let palette = Palette::default();
+    let half = THUMBNAIL_PIXELS as i32 / 2;
+    let (center_x, center_z) = (camera_position.x.floor() as i32, camera_position.z.floor() as i32);
+    let mut pixels = Vec::with_capacity((THUMBNAIL_PIXELS * THUMBNAIL_PIXELS * 4) as usize);
+    for pixel_z in 0..THUMBNAIL_PIXELS as i32 {
+        for pixel_x in 0..THUMBNAIL_PIXELS as i32 {
+            let x = center_x + (pixel_x - half) * THUMBNAIL_SCALE;
+            let z = center_z + (pixel_z - half) * THUMBNAIL_SCALE;
+            pixels.extend_from_slice(&minimap::surface_pixel(&world.area, world.materials(), &palette, x, z, |_| {}));
+        }
+    }
+    pixels
+}
+
+fn save_path(name: &str) -> ColonizeResult<PathBuf> {
+    named_path(&saves_dir(), name)
+}
+
+/// Returns the path of the entry named `name` in the directory at `dir`, in
+/// which saves and recordings are kept under the names given by the player.
+pub fn named_path(dir: &Path, name: &str) -> ColonizeResult<PathBuf> {
+    // Names must not be able to escape the directory.
+    let is_valid = !name.is_empty() && name != "." && name != ".." &&
+        !name.contains(|c: char| c == '/' || c == '\\');
+    if !is_valid {
+        return Err(ColonizeError::InvalidSaveName(name.to_owned()));
+    }
+
+    Ok(dir.join(name))
+}
+
+fn saves_dir() -> PathBuf {
+    paths::data_dir().join(SAVES_DIR)
+}
@@ -1,17 +1,30 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::mem;
+use std::path::Path;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
-use cgmath::{EuclideanSpace, Point2, Point3, Vector3};
+use cgmath::{EuclideanSpace, Point2, Point3};
 use graphics;
+use graphics::color;
 use piston::input::keyboard::Key;
-use piston::input::{GenericEvent, MouseCursorEvent, PressEvent};
-use piston::input::Button::Keyboard;
+use piston::input::{
+    GenericEvent,
+    MouseCursorEvent,
+    PressEvent,
+    ReleaseEvent,
+    TextEvent,
+};
+use piston::input::Button::{Keyboard, Mouse};
+use piston::input::MouseButton;
 use rgframework::{
     BindingsHashMap,
     BindingMap,
     BindingStore,
     BoxedScene,
     Command,
+    Label,
     Scene,
     SceneCommand,
     UnwrapBindings,
@@ -21,153 +34,2000 @@ use rgframework::backend::graphics::{Context};
 use rgframework::draw::Draw;
 use utility::Bounds;
 use world;
-use world::{Direction, World};
+use world::{Entity, EventKind, ItemKind, JobKind, MaterialRegistry, Position, Precipitation, Structure, Subscription, World, WorldEvent, WorldGenConfig, LOG2_OF_CHUNK_SIZE, MAX_LIGHT_LEVEL, TICKS_PER_HOUR};
 
 use action::Action;
+use ai_overlay::AiOverlay;
+use animation::EntityAnimations;
 use camera;
 use camera::{Camera, CameraAction};
+use command::PlayerCommand;
+use audio;
+use audio::AudioManager;
 use config::Config;
+use console;
+use crash;
+use console::{Console, ConsoleCommand, ConsoleError};
+use cursor::{Cursor, CursorAction};
+use designation;
+use designation::{Designation, Designator, OverlayVisibility};
+use frame_clock;
+use inspection_panel::InspectionPanel;
+use isometric::IsometricView;
 use localization::Localization;
-use scene::MenuScene;
-use textures::TextureType;
+use palette::Palette;
+use logging;
+use logic::GameLogic;
+use materials;
+use roster::{Roster, RosterEntry};
+use minimap::Minimap;
+use net;
+use net::{Client, Host, HostMessage, Session, SessionEvent};
+use particles;
+use particles::ParticleSystem;
+use recording;
+use recording::{Playback, Recorder};
+use renderer;
+use renderer::{PistonRenderer, Renderer, TileView, light_brightness};
+use save;
+use scene::{MenuScene, PauseScene, ReportsScene};
+use scripting::{self, ScriptHost, ScriptMessage};
+use settings::Settings;
+use simulation;
+use simulation::SimulationClock;
+use slice_cache::SliceCache;
+use slice_textures::SliceTextures;
+use tile_batch::TileBatch;
+use tile_renderer::TileRenderer;
+use error::ColonizeResult;
 
-const CAMERA_INITIAL_POSITION: Point3<i32> = Point3 { x: 0, y: 15, z: 1};
-const CAMERA_MOVEMENT_SPEED: Vector3<i32> = Vector3 { x: 1, y: 1, z: 1 };
-const CURSOR_COLOR: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
-const CURSOR_SIZE: f64 = 16.0;
+const CURSOR_BORDER_RADIUS: f64 = 1.5;
+// The number of tiles kept between the keyboard cursor and the edges of the
+// view, which scrolls to follow it.
+const CURSOR_MARGIN: i32 = 3;
 const TILE_SIZE: f64 = 16.0;
+// Distance between the inspection panel and the edges of the window, at a
+// UI scale of 1.
+const PANEL_MARGIN: f64 = 10.0;
+// Position of the first of the labels along the left edge of the window, and
+// the distance between neighbouring labels, at a UI scale of 1.
+const LABELS_POSITION: [f64; 2] = [10.0, 100.0];
+const LABEL_SPACING: f64 = 30.0;
+// Position of the top-left corner of the roster, beneath the labels, in
+// pixels at a UI scale of 1
+const ROSTER_POSITION: [f64; 2] = [10.0, 210.0];
+// Fraction of a tile covered by an entity.
+const ENTITY_SIZE: f64 = 0.75;
+// Fraction of a tile covered by an item.
+const ITEM_SIZE: f64 = 0.5;
+// The number of lines hatching a tile designated to be dug out, along each
+// diagonal, and their thickness as a fraction of a tile.
+const HATCH_LINES: u32 = 3;
+const HATCH_THICKNESS: f64 = 0.08;
+// Offset of the tooltip from the corner of the tile it describes, in pixels
+// at a UI scale of 1.
+const TOOLTIP_OFFSET: f64 = 4.0;
+// Brightness of the surface at midnight, relative to noon.
+const NIGHT_BRIGHTNESS: f32 = 0.3;
+// Maximum number of chunks whose light is recomputed per update.
+const LIGHT_UPDATE_BUDGET: usize = 16;
+// Seconds between ambient sounds.
+const AMBIENT_INTERVAL: f64 = 30.0;
+// Daylight above which the ambient sounds of the day are played.
+const AMBIENT_DAY_THRESHOLD: f64 = 0.5;
+// The number of the most recent log lines shown in the console.
+const LOG_LINES_SHOWN: usize = 12;
+// Steps of the simulation between recordings of the world for crash reports.
+const CRASH_RECORD_INTERVAL: u64 = 100;
+/// The number of particles of dust thrown up when a tile is dug out.
+const DUST_PARTICLES: u32 = 12;
+/// The number of lines beneath the view drawn by `GameScene::render_text`.
+pub const TEXT_STATUS_LINES: usize = 4;
+// Characters drawing the keyboard cursor and the entities as text.
+const CURSOR_GLYPH: &'static str = "X";
+const COLONIST_GLYPH: &'static str = "@";
+const INVADER_GLYPH: &'static str = "G";
+const ANIMAL_GLYPH: &'static str = "a";
+const FURNITURE_GLYPH: &'static str = "&";
+const ITEM_GLYPH: &'static str = "*";
+// The most jobs remembered of every colonist for the inspection panel
+const JOB_HISTORY_LENGTH: usize = 5;
 
 pub struct GameScene<B>
     where B: Backend,
 {
     config: Rc<Config>,
-    localization: Rc<Localization>,
+    settings: Rc<RefCell<Settings>>,
+    localization: Rc<RefCell<Localization>>,
+    palette: Rc<RefCell<Palette>>,
     key_bindings: BindingsHashMap<Key, Action>,
     mouse_pos: Point2<f64>,
     world: World,
+    /// Name under which the world was last saved or loaded, if any.
+    save_name: Option<String>,
+    materials: Rc<MaterialRegistry>,
     bounds: Bounds<i32>,
     camera: Camera,
+    /// Stands in for the mouse cursor while the game is played with the
+    /// keyboard alone.
     cursor: Cursor,
-    textures: Rc<HashMap<TextureType, B::Texture>>,
+    slice_cache: SliceCache,
+    simulation_clock: SimulationClock,
+    /// Runs the steps of the simulation, from the game library when it is
+    /// hot-reloaded.
+    logic: GameLogic,
+    tile_renderer: Rc<TileRenderer<B>>,
+    audio: Rc<AudioManager>,
+    /// Seconds until the next ambient sound is played.
+    ambient_timer: f64,
+    scripts: Rc<ScriptHost>,
+    /// The day on which the last frame of the timelapse was captured, or on
+    /// which the scene was created.
+    timelapse_day: u64,
+    /// Whether the next frame should be captured for the timelapse.
+    capture_requested: bool,
+    /// The steps of the simulation run since the last snapshot of the world
+    /// was taken for rewinding.
+    ticks_since_snapshot: u64,
+    /// The steps of the simulation run since the world was last recorded for
+    /// crash reports, or `None` if it has not been recorded yet.
+    ticks_since_crash_record: Option<u64>,
+    welcome_label: Label,
+    /// Shows the in-game date and the speed of the simulation.
+    date_label: Label,
+    /// Shows the weather and temperature under the camera.
+    weather_label: Label,
+    designator: Designator,
+    /// Shows the designation selected for painting, if any.
+    designation_label: Label,
+    /// The colonist being inspected, if any.
+    selected: Option<Entity>,
+    inspection_panel: InspectionPanel,
+    /// The kinds of the jobs every colonist finished last, the latest first.
+    job_history: HashMap<Entity, VecDeque<JobKind>>,
+    /// Lists every colonist, while it is shown.
+    roster: Roster,
+    /// Describes the tile being hovered.
+    tooltip: InspectionPanel,
+    /// Whether the tooltip describes the tile under the mouse cursor, rather
+    /// than the tile at the center of the view, which the keyboard moves.
+    tooltip_follows_mouse: bool,
+    /// Shows what the colonists and creatures are up to, for debugging.
+    ai_overlay: AiOverlay,
+    minimap: Minimap<B>,
+    console: Console,
+    /// Whether the flat-colored tiles are drawn a slice at a time from
+    /// textures, and the tiles which have not been revealed in batches,
+    /// rather than one by one, which can be switched to compare the two.
+    batch_tiles: bool,
+    tile_batch: TileBatch,
+    slice_textures: SliceTextures<B>,
+    /// The time spent drawing the tiles of the last frame, and the number of
+    /// draw calls it took.
+    tile_draw_stats: (Duration, usize),
+    /// Records the commands carried out on the world, while it is recorded.
+    recorder: Option<Recorder>,
+    /// Carries out the commands of a recording instead of those of the
+    /// player, while it is played back.
+    playback: Option<Playback>,
+    /// Whether the recording played back is only viewed, and cannot be
+    /// stopped to play on from where it is.
+    read_only: bool,
+    /// Whether the top few z-levels are drawn in isometric projection,
+    /// rather than the z-level of the camera from above.
+    isometric: bool,
+    /// The sprites the entities are drawn with in the top-down view.
+    animations: EntityAnimations,
+    particles: ParticleSystem,
+    /// The game played over the network, if it was hosted or joined.
+    session: Option<Session>,
+    /// Through which the parts of the scene take the events of the world.
+    subscriptions: Subscriptions,
 }
 
 impl<B> GameScene<B>
     where B: Backend,
 {
-    pub fn new(config: Rc<Config>, localization: Rc<Localization>, textures: Rc<HashMap<TextureType, B::Texture>>) -> Self {
+    /// Creates a game scene in a new world generated from `seed` with the
+    /// parameters `world_gen`, whose colony starts in the chunk column
+    /// `embark`.
+    pub fn new(config: Rc<Config>, settings: Rc<RefCell<Settings>>, localization: Rc<RefCell<Localization>>, palette: Rc<RefCell<Palette>>, materials: Rc<MaterialRegistry>, tile_renderer: Rc<TileRenderer<B>>, audio: Rc<AudioManager>, scripts: Rc<ScriptHost>, seed: u32, world_gen: WorldGenConfig, embark: Point2<i32>) -> Self {
+        let world = World::new(Some(seed), config.initial_world_size, embark, world_gen, materials.clone());
+        let camera_position = save::embark_camera_position(&world);
+
         Self::new_internal(
             config.clone(),
+            settings,
             localization.clone(),
-            config.game_scene_key_bindings.unwrap_bindings(),
-            textures,
+            palette,
+            world,
+            camera_position,
+            materials,
+            tile_renderer,
+            audio,
+            scripts,
         )
     }
 
-    fn new_internal(config: Rc<Config>, localization: Rc<Localization>, key_bindings: BindingsHashMap<Key, Action>, textures: Rc<HashMap<TextureType, B::Texture>>) -> Self {
-        // TODO: refactor these magic numbers.
-        let bounds = Bounds::new(0, 0, 54, 49);
-        let cursor = Cursor::new(
-            bounds.width() as f64 / 2.0,
-            bounds.height() as f64 / 2.0,
+    /// Creates a game scene from the world saved under `name`.
+    pub fn load(config: Rc<Config>, settings: Rc<RefCell<Settings>>, localization: Rc<RefCell<Localization>>, palette: Rc<RefCell<Palette>>, materials: Rc<MaterialRegistry>, tile_renderer: Rc<TileRenderer<B>>, audio: Rc<AudioManager>, scripts: Rc<ScriptHost>, name: &str) -> ColonizeResult<Self> {
+        let (world, camera_position) = try!(save::load_world(name, config.initial_world_size, materials.clone()));
+
+        let mut scene = Self::new_internal(
+            config.clone(),
+            settings,
+            localization.clone(),
+            palette,
+            world,
+            camera_position,
+            materials,
+            tile_renderer,
+            audio,
+            scripts,
         );
+        scene.save_name = Some(name.to_owned());
+        Ok(scene)
+    }
 
-        GameScene {
+    /// Creates a game scene viewing the replay exported to the directory at
+    /// `path`, which is played back without letting the world be changed.
+    pub fn view_replay(config: Rc<Config>, settings: Rc<RefCell<Settings>>, localization: Rc<RefCell<Localization>>, palette: Rc<RefCell<Palette>>, materials: Rc<MaterialRegistry>, tile_renderer: Rc<TileRenderer<B>>, audio: Rc<AudioManager>, scripts: Rc<ScriptHost>, path: &Path) -> ColonizeResult<Self> {
+        let (playback, world, camera_position) = try!(Playback::open_from(path, config.initial_world_size, materials.clone()));
+
+        let mut scene = Self::new_internal(
+            config.clone(),
+            settings,
+            localization.clone(),
+            palette,
+            world,
+            camera_position,
+            materials,
+            tile_renderer,
+            audio,
+            scripts,
+        );
+        scene.playback = Some(playback);
+        scene.read_only = true;
+        Ok(scene)
+    }
+
+    fn new_internal(config: Rc<Config>, settings: Rc<RefCell<Settings>>, localization: Rc<RefCell<Localization>>, palette: Rc<RefCell<Palette>>, mut world: World, camera_position: Point3<f64>, materials: Rc<MaterialRegistry>, tile_renderer: Rc<TileRenderer<B>>, audio: Rc<AudioManager>, scripts: Rc<ScriptHost>) -> Self {
+        let camera = Camera::new(
+            config.camera_movement_speed,
+            config.camera_fast_scroll_multiplier,
+            camera_position);
+        let (key_bindings, font_size, ui_scale, window_width, window_height) = {
+            let settings = settings.borrow();
+            (
+                settings.game_scene_key_bindings.unwrap_bindings(),
+                settings.scale_font_size(config.font_size),
+                settings.ui_scale(),
+                settings.window_width as f64,
+                settings.window_height as f64,
+            )
+        };
+        let timelapse_day = world.calendar().days();
+        start_history(&mut world, &config);
+        let subscriptions = Subscriptions::new(&mut world);
+        let welcome_text = localization.borrow().gamescene_welcome_text.clone();
+        let text_color = palette.borrow().text;
+        audio.play_music(audio::GAME_MUSIC);
+        let mut scene = GameScene {
+            bounds: view_bounds(window_width, window_height),
             key_bindings: key_bindings,
             mouse_pos: Point2::origin(),
+            welcome_label: Label::new(welcome_text, text_color, font_size, [0.0, 0.0]),
+            date_label: Label::new("", text_color, font_size, [0.0, 0.0]),
+            weather_label: Label::new("", text_color, font_size, [0.0, 0.0]),
+            designator: Designator::new(),
+            designation_label: Label::new("", text_color, font_size, [0.0, 0.0]),
+            selected: None,
+            inspection_panel: InspectionPanel::new(font_size, ui_scale, [0.0, 0.0], &palette.borrow()),
+            job_history: HashMap::new(),
+            roster: Roster::new(font_size, ui_scale, [0.0, 0.0], &palette.borrow()),
+            tooltip: InspectionPanel::new(font_size, ui_scale, [0.0, 0.0], &palette.borrow()),
+            tooltip_follows_mouse: true,
+            ai_overlay: AiOverlay::new(),
+            minimap: Minimap::new(window_width, window_height, ui_scale),
+            console: Console::new(font_size),
+            batch_tiles: true,
+            tile_batch: TileBatch::new(),
+            slice_textures: SliceTextures::new(),
+            tile_draw_stats: (Duration::default(), 0),
+            recorder: None,
+            playback: None,
+            read_only: false,
+            isometric: false,
+            animations: EntityAnimations::new(),
+            particles: ParticleSystem::new(),
+            session: None,
             localization: localization,
-            world: World::new(None, config.initial_world_size),
+            palette: palette,
+            world: world,
+            save_name: None,
+            subscriptions: subscriptions,
+            materials: materials,
             config: config,
-            bounds: bounds,
-            camera: Camera::new(CAMERA_MOVEMENT_SPEED, CAMERA_INITIAL_POSITION),
-            cursor: cursor,
-            textures: textures,
+            settings: settings,
+            camera: camera,
+            cursor: Cursor::new(),
+            slice_cache: SliceCache::new(),
+            simulation_clock: SimulationClock::new(),
+            logic: GameLogic::new(),
+            tile_renderer: tile_renderer,
+            audio: audio,
+            scripts: scripts,
+            ambient_timer: AMBIENT_INTERVAL,
+            timelapse_day: timelapse_day,
+            capture_requested: false,
+            ticks_since_snapshot: 0,
+            ticks_since_crash_record: None,
+        };
+        scene.layout_overlays();
+        scene
+    }
+
+    /// Lays out the labels and panels drawn over the tile view for the size
+    /// of the window, at the scale of the interface and in the palette set,
+    /// either of which may have been changed in the options.
+    fn layout_overlays(&mut self) {
+        let settings = self.settings.borrow();
+        let palette = self.palette.borrow();
+        let font_size = settings.scale_font_size(self.config.font_size);
+        let ui_scale = settings.ui_scale();
+        let (window_width, window_height) = (settings.window_width as f64, settings.window_height as f64);
+
+        let mut labels = [&mut self.welcome_label, &mut self.date_label, &mut self.weather_label, &mut self.designation_label];
+        for (i, label) in labels.iter_mut().enumerate() {
+            let position = settings.scale_position([LABELS_POSITION[0], LABELS_POSITION[1] + i as f64 * LABEL_SPACING]);
+            **label = Label::new(label.text().to_owned(), palette.text, font_size, position);
         }
+
+        let margin = settings.scale_length(PANEL_MARGIN);
+        self.inspection_panel.set_scale(font_size, ui_scale);
+        self.inspection_panel.set_palette(&palette);
+        let panel_origin = [window_width - self.inspection_panel.width() - margin, margin];
+        self.inspection_panel.set_origin(panel_origin);
+        self.roster.set_layout(settings.scale_position(ROSTER_POSITION), font_size, ui_scale);
+        self.roster.set_palette(&palette);
+        self.tooltip.set_scale(font_size, ui_scale);
+        self.tooltip.set_palette(&palette);
+        self.minimap.resize(window_width, window_height, ui_scale);
+        self.console.set_font_size(font_size);
     }
-}
 
-impl<B, E, G> Scene<B, E, G> for GameScene<B>
-    where B: Backend + 'static,
-          E: GenericEvent,
-          G: Graphics<Texture=B::Texture>,
-{
-    fn to_box(self) -> BoxedScene<B, E, G> {
-        Box::new(self)
+    fn handle_console_event<E>(&mut self, e: &E)
+        where E: GenericEvent,
+    {
+        let mut submitted = None;
+
+        e.text(|text| {
+            self.console.type_text(text);
+        });
+
+        e.press(|button_type| {
+            if let Keyboard(key) = button_type {
+                match key {
+                    Key::Backquote | Key::Escape => self.console.toggle(),
+                    Key::Backspace => self.console.backspace(),
+                    Key::Return => submitted = self.console.submit(),
+                    _ => {},
+                }
+            }
+        });
+
+        let output = match submitted {
+            Some(Ok(command)) => self.execute_console_command(command),
+            // The changes mods make to the world are not recorded.
+            Some(Err(ConsoleError::UnknownCommand(_, _))) if self.is_recorded() => {
+                vec![self.localization.borrow().console_recording_busy.clone()]
+            },
+            Some(Err(ConsoleError::UnknownCommand(name, args))) => {
+                match self.scripts.run_command(&name, args, &mut self.world) {
+                    Some(messages) => self.script_output(messages),
+                    None => vec![format!("{}: {}", self.localization.borrow().console_unknown_command, name)],
+                }
+            },
+            Some(Err(ConsoleError::InvalidArguments(usage))) => vec![format!("{}: {}", self.localization.borrow().console_usage, usage)],
+            Some(Err(ConsoleError::Empty)) | None => Vec::new(),
+        };
+        for line in output {
+            self.console.print(line);
+        }
     }
 
-    fn render(&mut self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
-        use graphics::{clear, color, Transformed};
-        use graphics::text::Text;
+    /// Executes a console command, returning the lines to print in response.
+    fn execute_console_command(&mut self, command: ConsoleCommand) -> Vec<String> {
+        let localization = self.localization.clone();
+        let localization = localization.borrow();
 
-        clear(color::WHITE, graphics);
+        match command {
+            ConsoleCommand::Seed(seed) => self.regenerate_world(seed),
+            ConsoleCommand::Regen => {
+                let seed = self.world.seed();
+                self.regenerate_world(seed)
+            },
+            ConsoleCommand::Host(port) => {
+                let port = port.unwrap_or(net::DEFAULT_PORT);
+                match Host::listen(port, self.config.allow_cheats) {
+                    Ok(host) => {
+                        self.session = Some(Session::Host(host));
+                        vec![format!("{}: {}", localization.console_hosting, port)]
+                    },
+                    Err(e) => vec![format!("{}: {}", localization.console_host_failed, e)],
+                }
+            },
+            // Joining replaces the world, which the recording could no longer
+            // keep in step with.
+            ConsoleCommand::Join(_) if self.is_recorded() => vec![localization.console_recording_busy.clone()],
+            ConsoleCommand::Join(address) => {
+                match Client::connect(&address) {
+                    Ok(client) => {
+                        self.session = Some(Session::Client(client));
+                        vec![format!("{}: {}", localization.console_joined, address)]
+                    },
+                    Err(e) => vec![format!("{}: {}", localization.console_join_failed, e)],
+                }
+            },
+            ConsoleCommand::Leave => {
+                match self.session.take() {
+                    Some(_) => vec![localization.console_left.clone()],
+                    None => vec![localization.console_not_in_game.clone()],
+                }
+            },
+            ConsoleCommand::Teleport(pos) => {
+                self.camera.set_position(Point3::new(pos.x as f64, pos.y as f64, pos.z as f64));
+                vec![format!("{}: {:?}", localization.console_teleported, pos)]
+            },
+            ConsoleCommand::SetMaterial(pos, name) => {
+                match materials::find_material(&self.materials, &name) {
+                    Some(material) => {
+                        if self.issue(PlayerCommand::SetMaterial([pos.x, pos.y, pos.z], material)) {
+                            vec![format!("{}: {:?}", localization.console_material_set, pos)]
+                        } else {
+                            vec![format!("{}: {:?}", localization.console_position_not_loaded, pos)]
+                        }
+                    },
+                    None => vec![format!("{}: {}", localization.console_unknown_material, name)],
+                }
+            },
+            ConsoleCommand::Save(name) => {
+                let name = self.resolve_save_name(name);
+                match save::save_world(&name, &mut self.world, self.camera.get_position()) {
+                    Ok(()) => {
+                        self.save_name = Some(name.clone());
+                        vec![format!("{}: {}", localization.console_game_saved, name)]
+                    },
+                    Err(e) => vec![format!("{}: {}", localization.console_save_failed, e)],
+                }
+            },
+            ConsoleCommand::Load(name) => {
+                let name = self.resolve_save_name(name);
+                match save::load_world(&name, self.config.initial_world_size, self.materials.clone()) {
+                    Ok((world, camera_position)) => {
+                        let mut output = self.replace_world(world, camera_position);
+                        self.save_name = Some(name.clone());
+                        output.insert(0, format!("{}: {}", localization.console_game_loaded, name));
+                        output
+                    },
+                    Err(e) => vec![format!("{}: {}", localization.console_load_failed, e)],
+                }
+            },
+            ConsoleCommand::Dig(pos) => {
+                if self.issue(PlayerCommand::Designate(Designation::Dig, [pos.x, pos.y, pos.z])) {
+                    vec![format!("{}: {:?}", localization.console_dig_queued, pos)]
+                } else {
+                    vec![format!("{}: {:?}", localization.console_dig_rejected, pos)]
+                }
+            },
+            ConsoleCommand::Give(..) if self.is_recorded() => vec![localization.console_recording_busy.clone()],
+            ConsoleCommand::Give(name, count) => {
+                match ItemKind::from_name(&name) {
+                    Some(kind) => {
+                        let pos = self.camera.get_tile_position();
+                        for _ in 0..count {
+                            self.world.spawn_item(kind, pos);
+                        }
+                        vec![format!("{}: {:?}", localization.console_items_given, pos)]
+                    },
+                    None => vec![format!("{}: {}", localization.console_unknown_item, name)],
+                }
+            },
+            ConsoleCommand::Record(name) => {
+                if self.is_recorded() {
+                    return vec![localization.console_recording_busy.clone()];
+                }
+                match Recorder::start(&name, &mut self.world, self.camera.get_position()) {
+                    Ok(recorder) => {
+                        self.recorder = Some(recorder);
+                        vec![format!("{}: {}", localization.console_recording_started, name)]
+                    },
+                    Err(e) => vec![format!("{}: {}", localization.console_recording_failed, e)],
+                }
+            },
+            ConsoleCommand::Replay(name) => {
+                if self.recorder.is_some() {
+                    return vec![localization.console_recording_busy.clone()];
+                }
+                match Playback::open(&name, self.config.initial_world_size, self.materials.clone()) {
+                    Ok((playback, world, camera_position)) => {
+                        let mut output = self.replace_world(world, camera_position);
+                        output.insert(0, format!("{}: {}", localization.console_replay_started, name));
+                        // The save may already differ from the world which
+                        // was recorded.
+                        if let Some(tick) = playback.divergence() {
+                            output.push(format!("{}: {}", localization.console_replay_diverged, tick));
+                        }
+                        self.playback = Some(playback);
+                        output
+                    },
+                    Err(e) => vec![format!("{}: {}", localization.console_replay_failed, e)],
+                }
+            },
+            ConsoleCommand::Stop => {
+                if self.read_only {
+                    vec![localization.console_replay_read_only.clone()]
+                } else if self.is_recorded() {
+                    self.end_recording()
+                } else {
+                    vec![localization.console_not_recording.clone()]
+                }
+            },
+            ConsoleCommand::Export(name, destination) => {
+                match recording::export(&name, Path::new(&destination)) {
+                    Ok(()) => vec![format!("{}: {}", localization.console_replay_exported, destination)],
+                    Err(e) => vec![format!("{}: {}", localization.console_export_failed, e)],
+                }
+            },
+            ConsoleCommand::Dump(name, destination) => {
+                match recording::dump(&name, Path::new(&destination)) {
+                    Ok(()) => vec![format!("{}: {}", localization.console_recording_dumped, destination)],
+                    Err(e) => vec![format!("{}: {}", localization.console_dump_failed, e)],
+                }
+            },
+            ConsoleCommand::Import(..) if self.is_recorded() => vec![localization.console_recording_busy.clone()],
+            ConsoleCommand::Import(source, name) => {
+                match recording::import(Path::new(&source), &name) {
+                    Ok(()) => vec![format!("{}: {}", localization.console_recording_imported, name)],
+                    Err(e) => vec![format!("{}: {}", localization.console_import_failed, e)],
+                }
+            },
+            ConsoleCommand::Help => {
+                let mut usages: Vec<String> = console::USAGE.iter().map(|usage| (*usage).to_owned()).collect();
+                usages.extend(self.scripts.command_usages());
+                usages
+            },
+        }
+    }
 
+    /// Returns the lines to print to the console for the messages of mods.
+    fn script_output(&self, messages: Vec<ScriptMessage>) -> Vec<String> {
+        messages.into_iter().map(|message| match message {
+            ScriptMessage::Print(text) => text,
+            ScriptMessage::Error(name, e) => format!("{} {}: {}", self.localization.borrow().console_script_error, name, e),
+        }).collect()
+    }
+
+    /// Lets the mods decorate the chunks generated since the last update, and
+    /// know of the jobs they defined which have been finished and of the
+    /// seasons which have begun.
+    fn run_script_hooks(&mut self, events: &[WorldEvent]) {
+        let messages = self.scripts.run_hooks(events, &mut self.world);
+        for line in self.script_output(messages) {
+            self.console.print(line);
+        }
+    }
+
+    /// Returns the save name to use when none is given: the name under which
+    /// the world was last saved or loaded, or the default name of the colony.
+    fn resolve_save_name(&self, name: Option<String>) -> String {
+        name.or_else(|| self.save_name.clone())
+            .unwrap_or_else(|| save::default_save_name(&self.world))
+    }
+
+    /// Returns the in-game date and time, followed by the speed of the
+    /// simulation.
+    fn date_text(&self) -> String {
+        let localization = self.localization.borrow();
+        let calendar = self.world.calendar();
+        let speed = if self.simulation_clock.is_paused() {
+            localization.gamescene_paused.clone()
+        } else {
+            format!("{}: {}x", localization.gamescene_speed, self.simulation_clock.rate())
+        };
+
+        format!(
+            "{} - {:02}:{:02} ({})",
+            localization.date(calendar),
+            calendar.hour(),
+            calendar.minute(),
+            speed)
+    }
+
+    /// Returns the position, in tiles, of the top-left corner of the view.
+    fn view_origin(&self) -> Point2<f64> {
         let camera_pos = self.camera.get_position();
+        Point2::new(
+            camera_pos.x - self.bounds.width() as f64 / 2.0,
+            camera_pos.z - self.bounds.height() as f64 / 2.0,
+        )
+    }
+
+    /// Returns the position of the tile under the mouse cursor, on the
+    /// z-level of the camera.
+    fn tile_under_mouse(&self) -> Point3<i32> {
+        let origin = self.view_origin();
+        Point3::new(
+            (origin.x + self.mouse_pos.x / TILE_SIZE).floor() as i32,
+            self.camera.get_tile_position().y,
+            (origin.y + self.mouse_pos.y / TILE_SIZE).floor() as i32,
+        )
+    }
+
+    /// Returns the position of the tile under the keyboard cursor, on the
+    /// z-level of the camera, if the cursor is shown.
+    fn tile_under_cursor(&self) -> Option<Point3<i32>> {
+        self.cursor.position().map(|pos| Point3::new(pos.x, self.camera.get_tile_position().y, pos.y))
+    }
+
+    /// Returns the position of the tile described by the tooltip.
+    fn hovered_tile(&self) -> Point3<i32> {
+        if let Some(pos) = self.tile_under_cursor() {
+            pos
+        } else if self.tooltip_follows_mouse {
+            self.tile_under_mouse()
+        } else {
+            self.camera.get_tile_position()
+        }
+    }
+
+    /// Carries out an action of the keyboard cursor.
+    fn handle_cursor_action(&mut self, action: CursorAction) {
+        if let CursorAction::Toggle = action {
+            let center = self.camera.get_tile_position();
+            self.cursor.toggle(Point2::new(center.x, center.z));
+            self.designator.set_painting(false);
+            return;
+        }
+
+        let pos = match self.tile_under_cursor() {
+            Some(pos) => pos,
+            None => return,
+        };
+        match action {
+            CursorAction::Move(dx, dz) => self.move_cursor(dx, dz),
+            // Painting is started and stopped by selecting, so that moving
+            // the cursor in between paints every tile it passes.
+            CursorAction::Select => {
+                if self.designator.selected().is_some() {
+                    let painting = self.designator.painting().is_none();
+                    self.designator.set_painting(painting);
+                    self.paint_designation(pos);
+                } else {
+                    self.select_colonist(pos);
+                }
+            },
+            CursorAction::Order => self.order_or_cancel(pos),
+            CursorAction::Toggle => {},
+        }
+    }
+
+    /// Moves the keyboard cursor by the specified number of tiles, scrolling
+    /// the view along with it once it comes near an edge, and paints the tile
+    /// it moves onto.
+    fn move_cursor(&mut self, dx: i32, dz: i32) {
+        self.cursor.move_by(dx, dz);
+        let pos = match self.tile_under_cursor() {
+            Some(pos) => pos,
+            None => return,
+        };
+
+        let center = self.camera.get_tile_position();
+        let mut camera_pos = *self.camera.get_position();
+        if (pos.x - center.x).abs() > self.bounds.width() / 2 - CURSOR_MARGIN {
+            camera_pos.x += dx as f64;
+        }
+        if (pos.z - center.z).abs() > self.bounds.height() / 2 - CURSOR_MARGIN {
+            camera_pos.z += dz as f64;
+        }
+        self.camera.set_position(camera_pos);
+
+        self.paint_designation(pos);
+    }
+
+    /// Applies the designation being painted to the tile at `pos`.
+    fn paint_designation(&mut self, pos: Point3<i32>) {
+        if let Some(designation) = self.designator.painting() {
+            self.issue(PlayerCommand::Designate(designation, [pos.x, pos.y, pos.z]));
+        }
+    }
+
+    /// Carries out `command`, returning whether it had any effect. In a game
+    /// joined over the network, the command is sent to the host to be
+    /// carried out there instead, and is assumed to have an effect.
+    ///
+    /// Nothing is carried out while a recording is played back, as the
+    /// world would no longer keep in step with it.
+    fn issue(&mut self, command: PlayerCommand) -> bool {
+        if self.playback.is_some() {
+            return false;
+        }
+        if let Some(Session::Client(ref mut client)) = self.session {
+            return match client.send(&command) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("Failed to send a command to the host: {}", e);
+                    false
+                },
+            };
+        }
+
+        let issued = command.apply(&mut self.world);
+        if issued {
+            if let Some(ref mut recorder) = self.recorder {
+                recorder.record(&self.world, command);
+            }
+        }
+        issued
+    }
+
+    /// Returns whether the world is being recorded or played back, during
+    /// which it is only changed by the steps of the simulation and the
+    /// commands recorded.
+    fn is_recorded(&self) -> bool {
+        self.recorder.is_some() || self.playback.is_some()
+    }
+
+    /// Stops recording or playing back the world. Returns the lines to print
+    /// in response.
+    fn end_recording(&mut self) -> Vec<String> {
+        let localization = self.localization.borrow();
+        if self.playback.take().is_some() {
+            self.simulation_clock.set_slowdown(1);
+            return vec![localization.console_replay_stopped.clone()];
+        }
+
+        match self.recorder.take() {
+            Some(recorder) => {
+                let name = recorder.name().to_owned();
+                match recorder.finish() {
+                    Ok(()) => vec![format!("{}: {}", localization.console_recording_stopped, name)],
+                    Err(e) => vec![format!("{}: {}", localization.console_recording_failed, e)],
+                }
+            },
+            None => Vec::new(),
+        }
+    }
 
-        let start_x = camera_pos.x - self.bounds.width() / 2;
-        let start_z = camera_pos.z - self.bounds.height() / 2;
+    /// Returns the name of the designation selected for painting, if any,
+    /// followed by which designations are drawn unless all of them are.
+    fn designation_text(&self) -> String {
+        let localization = self.localization.borrow();
+        let overlays = match self.designator.overlays() {
+            OverlayVisibility::All => None,
+            OverlayVisibility::Active => Some(&localization.gamescene_overlays_active),
+            OverlayVisibility::Hidden => Some(&localization.gamescene_overlays_hidden),
+        };
+        let designation = match self.designator.selected() {
+            Some(Designation::Dig) => localization.gamescene_designation_dig.clone(),
+            Some(Designation::DigStairs) => localization.gamescene_designation_dig_stairs.clone(),
+            Some(Designation::Stockpile) => localization.gamescene_designation_stockpile.clone(),
+            Some(Designation::Build(structure)) => {
+                format!("{} {}", localization.gamescene_designation_build, self.structure_name(structure))
+            },
+            Some(Designation::Hunt) => localization.gamescene_designation_hunt.clone(),
+            None => return overlays.map_or(String::new(), |overlays| overlays.clone()),
+        };
+
+        match overlays {
+            Some(overlays) => format!("{}: {} ({})", localization.gamescene_designating, designation, overlays),
+            None => format!("{}: {}", localization.gamescene_designating, designation),
+        }
+    }
+
+    fn structure_name(&self, structure: Structure) -> String {
+        let localization = self.localization.borrow();
+        match structure {
+            Structure::Wall => localization.gamescene_structure_wall.clone(),
+            Structure::Floor => localization.gamescene_structure_floor.clone(),
+            Structure::Stairs => localization.gamescene_structure_stairs.clone(),
+            Structure::Ramp => localization.gamescene_structure_ramp.clone(),
+            Structure::Workshop => localization.gamescene_structure_workshop.clone(),
+        }
+    }
+
+    /// Returns the lines describing the tile at `pos`: what it looks like to
+    /// be made of, where it is, what has been designated on it, and the items
+    /// and creatures on it. Tiles which have not been revealed yet are only
+    /// described by their position.
+    fn tooltip_lines(&mut self, pos: Point3<i32>) -> Vec<String> {
+        let localization = self.localization.borrow();
+        let materials = &self.materials;
+        let cell = {
+            let chunk_pos = Point2::new(pos.x >> LOG2_OF_CHUNK_SIZE, pos.z >> LOG2_OF_CHUNK_SIZE);
+            let rel_pos = world::abs_pos_to_rel_chunk_pos(&pos);
+            self.slice_cache.get_slice(&self.world.area, materials, chunk_pos, pos.y)[rel_pos.z][rel_pos.x]
+        };
+        let position = format!("({}, {}, {})", pos.x, pos.y, pos.z);
+        if !cell.revealed {
+            return vec![localization.gamescene_tooltip_unexplored.clone(), position];
+        }
+
+        // The tile drawn at an open position is the one beneath it, and
+        // anything see-through over it is named first.
+        let mut names: Vec<&str> = Vec::new();
+        if let Some(overlay) = cell.overlay.and_then(|(id, _)| materials.get(id)) {
+            names.push(&overlay.name);
+        }
+        if let Some(material) = materials.get(cell.material) {
+            names.push(&material.name);
+        }
+        let mut lines = vec![names.join(", "), position];
 
-        for x in 0..self.bounds.width() {
-            for z in 0..self.bounds.height() {
-                let screen_pos = Point2::new(x, z);
-                let pos = Point3::new(x + start_x, camera_pos.y, z + start_z);
-                let cell_drawable = CellDrawable::new(pos, screen_pos, &self.world, self.textures.clone());
-                Draw::<B, G>::draw(&cell_drawable, context, graphics, glyph_cache);
+        let entities = self.world.entities();
+        let target = Position::from(pos);
+        let blueprints: Vec<String> = entities.blueprints.iter()
+            .filter(|&(blueprint, _)| entities.positions.get(blueprint) == Some(&target))
+            .map(|(_, blueprint)| format!("{} {}", localization.gamescene_designation_build, self.structure_name(blueprint.structure)))
+            .collect();
+        let mut designations: Vec<&str> = Vec::new();
+        let jobs = self.world.job_queue().iter().chain(entities.jobs.iter().map(|(_, job)| job));
+        for job in jobs.filter(|job| job.target == target) {
+            match job.kind {
+                JobKind::Dig => designations.push(&localization.gamescene_designation_dig),
+                JobKind::DigStairs => designations.push(&localization.gamescene_designation_dig_stairs),
+                _ => {},
             }
         }
+        if self.world.stockpiles().contains(&target) {
+            designations.push(&localization.gamescene_designation_stockpile);
+        }
+        designations.extend(blueprints.iter().map(|blueprint| blueprint.as_str()));
+        if !designations.is_empty() {
+            lines.push(format!("{}: {}", localization.gamescene_tooltip_designation, designations.join(", ")));
+        }
 
-        Draw::<B, G>::draw(&self.cursor, context, graphics, glyph_cache);
+        let mut items = Vec::new();
+        for (entity, position) in entities.positions.iter() {
+            if *position != target {
+                continue;
+            }
+            if let Some(item) = entities.items.get(entity) {
+                items.push(item.kind.name());
+            } else if let Some(colonist) = entities.colonists.get(entity) {
+                lines.push(colonist.name.clone());
+            } else if let Some(animal) = entities.animals.get(entity) {
+                lines.push(animal.kind.name().to_owned());
+            } else if entities.invaders.contains(entity) {
+                lines.push(localization.gamescene_tooltip_invader.clone());
+            } else if entities.furniture.contains(entity) {
+                lines.push(localization.gamescene_structure_workshop.clone());
+            }
+        }
+        if !items.is_empty() {
+            lines.push(format!("{}: {}", localization.gamescene_tooltip_items, items.join(", ")));
+        }
 
-        Text::new(self.config.font_size).draw(
-            &self.localization.gamescene_welcome_text,
-            glyph_cache,
-            &context.draw_state,
-            context.transform.trans(10.0, 100.0),
-            graphics);
+        lines
+    }
 
-        Text::new(self.config.font_size).draw(
-            format!("{}: {:?}", self.localization.gamescene_debug_cursor, self.mouse_pos).as_ref(),
-            glyph_cache,
-            &context.draw_state,
-            context.transform.trans(10.0, 150.0),
-            graphics);
+    /// Places the tooltip beside the bottom-right corner of the tile at
+    /// `pos`, or on the other side of the tile if it would not fit on screen.
+    fn tooltip_origin(&self, pos: Point3<i32>) -> [f64; 2] {
+        let origin = self.view_origin();
+        let left = (pos.x as f64 - origin.x) * TILE_SIZE;
+        let top = (pos.z as f64 - origin.y) * TILE_SIZE;
+        let settings = self.settings.borrow();
+        let offset = settings.scale_length(TOOLTIP_OFFSET);
+        let mut x = left + TILE_SIZE + offset;
+        let mut y = top + TILE_SIZE + offset;
+        if x + self.tooltip.width() > settings.window_width as f64 {
+            x = left - self.tooltip.width() - offset;
+        }
+        if y + self.tooltip.height() > settings.window_height as f64 {
+            y = top - self.tooltip.height() - offset;
+        }
+        [x, y]
+    }
 
-        Text::new(self.config.font_size).draw(
-            format!("{}: {:?}", self.localization.gamescene_debug_camera, self.camera.get_position()).as_ref(),
-            glyph_cache,
-            &context.draw_state,
-            context.transform.trans(10.0, 200.0),
-            graphics);
+    /// Selects the colonist standing on the tile at `pos` for inspection, or
+    /// clears the selection if there is none.
+    fn select_colonist(&mut self, pos: Point3<i32>) {
+        let pos = Position::from(pos);
+        let entities = self.world.entities();
+        self.selected = entities.colonists.iter()
+            .map(|(entity, _)| entity)
+            .find(|&entity| entities.positions.get(entity) == Some(&pos));
+    }
 
-        Text::new(self.config.font_size).draw(
-            format!("{}: {:?}", self.localization.gamescene_debug_chunk, world::abs_pos_to_chunk_pos(self.camera.get_position())).as_ref(),
-            glyph_cache,
-            &context.draw_state,
-            context.transform.trans(10.0, 250.0),
-            graphics);
+    /// Orders the selected colonist to move to the tile at `pos` if it is
+    /// drafted, or otherwise cancels the designation and selection.
+    fn order_or_cancel(&mut self, pos: Point3<i32>) {
+        let ordered = match self.selected {
+            Some(colonist) if self.designator.selected().is_none() => self.issue(PlayerCommand::OrderMove(colonist, [pos.x, pos.y, pos.z])),
+            _ => false,
+        };
+        if !ordered {
+            self.designator.cancel();
+            self.selected = None;
+        }
+    }
+
+    /// Returns the lines describing the selected colonist, which are empty if
+    /// there is none.
+    fn inspection_lines(&self) -> Vec<String> {
+        let localization = self.localization.borrow();
+        let entities = self.world.entities();
+        let entity = match self.selected {
+            Some(entity) if entities.is_alive(entity) => entity,
+            _ => return Vec::new(),
+        };
+        let colonist = match entities.colonists.get(entity) {
+            Some(colonist) => colonist,
+            None => return Vec::new(),
+        };
+
+        let mut lines = vec![
+            colonist.name.clone(),
+            format!("{}: {}", localization.gamescene_colonist_job, self.activity(&localization, entity)),
+        ];
+        if let Some(health) = entities.health.get(entity) {
+            lines.push(format!("{}: {:.0}/{:.0}", localization.gamescene_colonist_health, health.current, health.max));
+        }
+        if let Some(needs) = entities.needs.get(entity) {
+            lines.push(format!("{}: {:.0}%", localization.gamescene_colonist_food, needs.food * 100.0));
+            lines.push(format!("{}: {:.0}%", localization.gamescene_colonist_rest, needs.rest * 100.0));
+            lines.push(format!("{}: {:.0}%", localization.gamescene_colonist_mood, needs.mood * 100.0));
+        }
+        if let Some(item) = colonist.carrying.and_then(|item| entities.items.get(item)) {
+            lines.push(format!("{}: {}", localization.gamescene_colonist_carrying, item.kind.name()));
+        }
+        if let Some(history) = self.job_history.get(&entity) {
+            let jobs: Vec<&str> = history.iter().map(|&kind| self.job_name(&localization, kind)).collect();
+            lines.push(format!("{}: {}", localization.gamescene_colonist_recent_jobs, jobs.join(", ")));
+        }
+        lines
+    }
+
+    /// Returns what the colonist `entity` is doing: the name of its job, or
+    /// whether it is fighting, drafted or idle.
+    fn activity<'a>(&'a self, localization: &'a Localization, entity: Entity) -> &'a str {
+        let entities = self.world.entities();
+        let is_fighting = entities.fighters.get(entity).map_or(false, |fighter| fighter.target.is_some());
+        let is_drafted = entities.colonists.get(entity).map_or(false, |colonist| colonist.drafted);
+        match entities.jobs.get(entity).map(|job| job.kind) {
+            _ if is_fighting => &localization.gamescene_colonist_fighting,
+            Some(kind) => self.job_name(localization, kind),
+            None if is_drafted => &localization.gamescene_colonist_drafted,
+            None => &localization.gamescene_colonist_idle,
+        }
+    }
+
+    /// Returns the name of jobs of the specified kind.
+    fn job_name<'a>(&'a self, localization: &'a Localization, kind: JobKind) -> &'a str {
+        match kind {
+            JobKind::Dig | JobKind::DigStairs => &localization.gamescene_job_dig,
+            JobKind::Haul(_) => &localization.gamescene_job_haul,
+            JobKind::Eat(_) => &localization.gamescene_job_eat,
+            JobKind::Sleep => &localization.gamescene_job_sleep,
+            JobKind::Build(_) => &localization.gamescene_job_build,
+            JobKind::Hunt(_) => &localization.gamescene_job_hunt,
+            JobKind::Custom(index) => self.world.custom_job_name(index).unwrap_or(&localization.gamescene_job_custom),
+        }
+    }
+
+    /// Returns the heading of the roster and its entries, one for every
+    /// colonist.
+    fn roster_contents(&self) -> (Vec<String>, Vec<RosterEntry>) {
+        let localization = self.localization.borrow();
+        let entities = self.world.entities();
+        let heading = vec![
+            localization.gamescene_roster.clone(),
+            format!("{} / {} / {}", localization.gamescene_colonist_food, localization.gamescene_colonist_rest, localization.gamescene_colonist_mood),
+        ];
+
+        let mut entries = Vec::new();
+        for (entity, colonist) in entities.colonists.iter() {
+            let pos = match entities.positions.get(entity) {
+                Some(pos) => pos,
+                None => continue,
+            };
+            let needs = entities.needs.get(entity).map_or([0.0; 3], |needs| [needs.food, needs.rest, needs.mood]);
+            entries.push(RosterEntry {
+                entity: entity,
+                title: format!("{} ({}, {}, {})", colonist.name, pos.x, pos.y, pos.z),
+                activity: self.activity(&localization, entity).to_owned(),
+                needs: needs,
+            });
+        }
+        (heading, entries)
+    }
+
+    /// Remembers the kinds of the jobs finished by every colonist, forgetting
+    /// the colonists which are no longer alive.
+    fn record_job_history(&mut self, events: &[WorldEvent]) {
+        for event in events {
+            if let WorldEvent::JobFinished(entity, kind, _) = *event {
+                let history = self.job_history.entry(entity).or_insert_with(VecDeque::new);
+                history.push_front(kind);
+                if history.len() > JOB_HISTORY_LENGTH {
+                    history.pop_back();
+                }
+            }
+        }
+        let entities = self.world.entities();
+        self.job_history.retain(|&entity, _| entities.is_alive(entity));
+    }
+
+    /// Selects `colonist` for inspection and moves the camera over it.
+    fn focus_colonist(&mut self, colonist: Entity) {
+        self.selected = Some(colonist);
+        if let Some(pos) = self.world.entities().positions.get(colonist) {
+            self.camera.set_position(Point3::new(pos.x as f64, pos.y as f64, pos.z as f64));
+        }
+    }
+
+    /// Returns the weather and temperature at the position of the camera.
+    fn weather_text(&self) -> String {
+        let localization = self.localization.borrow();
+        let tile_pos = self.camera.get_tile_position();
+        let temperature = self.world.temperature_at(tile_pos.x, tile_pos.z);
+        let weather = match self.world.weather().precipitation(temperature) {
+            Some(Precipitation::Rain) => &localization.gamescene_weather_rain,
+            Some(Precipitation::Snow) => &localization.gamescene_weather_snow,
+            None => &localization.gamescene_weather_clear,
+        };
+
+        format!("{}, {:.0}{}", weather, temperature, localization.util_unit_celsius)
+    }
+
+    /// Plays the sounds of the events of the world since the last update, and
+    /// an ambient sound fitting the weather and time of day every so often.
+    fn play_sounds(&mut self, events: &[WorldEvent], dt: f64) {
+        let listener = *self.camera.get_position();
+        for event in events {
+            if let WorldEvent::Dug(pos) = *event {
+                self.audio.play_sound_at(audio::DIG_SOUND, pos.to_point(), listener);
+            }
+        }
+
+        self.ambient_timer -= dt;
+        if self.ambient_timer > 0.0 {
+            return;
+        }
+        self.ambient_timer = AMBIENT_INTERVAL;
+
+        let temperature = self.world.temperature_at(listener.x.floor() as i32, listener.z.floor() as i32);
+        let sound = if self.world.weather().precipitation(temperature) == Some(Precipitation::Rain) {
+            audio::AMBIENT_RAIN_SOUND
+        } else if self.world.calendar().daylight() > AMBIENT_DAY_THRESHOLD {
+            audio::AMBIENT_DAY_SOUND
+        } else {
+            audio::AMBIENT_NIGHT_SOUND
+        };
+        self.audio.play_sound(sound);
+    }
+
+    /// Spawns the dust of the solid tiles opened up, however they were, the
+    /// smoke of the workshops in use, and the rain or snow falling over the
+    /// view, if any.
+    fn spawn_particles(&mut self, events: &[WorldEvent], dt: f64) {
+        for event in events {
+            if let WorldEvent::VoxelChanged(pos, before, after) = *event {
+                if !self.materials.is_solid(before) || self.materials.is_solid(after) {
+                    continue;
+                }
+                let center = Point3::new(pos.x as f64 + 0.5, pos.y as f64, pos.z as f64 + 0.5);
+                self.particles.burst(&particles::DUST, center, DUST_PARTICLES);
+            }
+        }
+
+        for (_, pos) in self.world.workshops_in_use() {
+            let top = Point3::new(pos.x as f64 + 0.5, pos.y as f64, pos.z as f64);
+            self.particles.emit(&particles::SMOKE, top, dt);
+        }
+
+        // Precipitation only falls on the tiles lit by the sun, which are
+        // open to the sky.
+        let camera_pos = self.camera.get_tile_position();
+        let temperature = self.world.temperature_at(camera_pos.x, camera_pos.z);
+        let effect = match self.world.weather().precipitation(temperature) {
+            Some(Precipitation::Rain) => &particles::RAIN,
+            Some(Precipitation::Snow) => &particles::SNOW,
+            None => return,
+        };
+        let view_origin = self.view_origin();
+        let view = [view_origin.x, view_origin.y, self.bounds.width() as f64, self.bounds.height() as f64];
+        let area = &self.world.area;
+        self.particles.emit_over(effect, camera_pos.y, view, dt, |pos| area.light_level(&pos) == MAX_LIGHT_LEVEL);
+    }
+
+    /// Requests a frame to be captured for the timelapse once the interval
+    /// set in the configuration has passed since the last one.
+    fn update_timelapse(&mut self) {
+        let interval = self.config.timelapse_interval;
+        let day = self.world.calendar().days();
+        if interval > 0 && day >= self.timelapse_day + interval {
+            self.timelapse_day = day;
+            self.capture_requested = true;
+        }
+    }
+
+    /// Returns the world being played, for tests to check what their input
+    /// did to it.
+    #[cfg(test)]
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    #[cfg(test)]
+    pub fn camera_position(&self) -> &Point3<f64> {
+        self.camera.get_position()
+    }
+
+    /// Makes the tile view `width` by `height` tiles, rather than fitting it
+    /// to the window, for front-ends drawing it with `render_text`.
+    pub fn set_view_size(&mut self, width: i32, height: i32) {
+        self.bounds = Bounds::new(0, 0, width, height);
+    }
+
+    /// Draws the z-level of the camera with `renderer` a unit per tile, for
+    /// front-ends drawing each tile as a character. The entities standing on
+    /// it are drawn as letters over their tiles, the date, weather,
+    /// designation and hovered tile on the `TEXT_STATUS_LINES` beneath the
+    /// view, and the console over the top of the view while it is open.
+    pub fn render_text<R>(&mut self, renderer: &mut R)
+        where R: Renderer,
+    {
+        let view_origin = self.view_origin();
+        let min = Point2::new(view_origin.x.floor() as i32, view_origin.y.floor() as i32);
+        let view = TileView {
+            y: self.camera.get_tile_position().y,
+            min: min,
+            max: Point2::new(min.x + self.bounds.width() - 1, min.y + self.bounds.height() - 1),
+            offset: Point2::new(0.0, 0.0),
+            tile_size: 1.0,
+        };
+        let daylight = self.daylight_brightness();
+        let font_size = self.config.font_size;
+
+        renderer.clear(color::BLACK);
+        renderer::draw_map(renderer, &view, &mut self.slice_cache, &self.world.area, &self.materials, daylight);
+
+        {
+            let entities = self.world.entities();
+            for (entity, position) in entities.positions.iter() {
+                let renderable = match entities.renderables.get(entity) {
+                    Some(renderable) => renderable,
+                    None => continue,
+                };
+                let rect = match view.rect(&position.to_point()) {
+                    Some(rect) if self.world.area.is_revealed(&position.to_point()) => rect,
+                    _ => continue,
+                };
+
+                let glyph = if entities.colonists.contains(entity) {
+                    COLONIST_GLYPH
+                } else if entities.invaders.contains(entity) {
+                    INVADER_GLYPH
+                } else if entities.animals.contains(entity) {
+                    ANIMAL_GLYPH
+                } else if entities.furniture.contains(entity) {
+                    FURNITURE_GLYPH
+                } else {
+                    ITEM_GLYPH
+                };
+                renderer.draw_text([rect[0], rect[1]], glyph, renderable.color, font_size);
+            }
+        }
+
+        if let Some(rect) = self.tile_under_cursor().and_then(|pos| view.rect(&pos)) {
+            renderer.draw_text([rect[0], rect[1]], CURSOR_GLYPH, self.palette.borrow().cursor, font_size);
+        }
+
+        let hovered = self.hovered_tile();
+        let status_lines: [String; TEXT_STATUS_LINES] = [
+            self.date_label.text().to_owned(),
+            self.weather_label.text().to_owned(),
+            self.designation_label.text().to_owned(),
+            self.tooltip_lines(hovered).join(" | "),
+        ];
+        let top = self.bounds.height() as f64;
+        for (row, line) in status_lines.iter().enumerate() {
+            renderer.draw_text([0.0, top + row as f64], line, color::WHITE, font_size);
+        }
+
+        // The lines of the console are padded to cover the map beneath them.
+        if self.console.is_visible() {
+            let width = self.bounds.width() as usize;
+            for (row, line) in self.console.lines().iter().enumerate() {
+                renderer.draw_text([0.0, row as f64], &format!("{:1$}", line, width), color::WHITE, font_size);
+            }
+        }
+
+        renderer.present();
+    }
+
+    /// Returns the brightness of fully lit tiles, which follows the time of
+    /// day.
+    fn daylight_brightness(&self) -> f32 {
+        let daylight = self.world.calendar().daylight() as f32;
+        NIGHT_BRIGHTNESS + (1.0 - NIGHT_BRIGHTNESS) * daylight
+    }
+
+    /// Runs a step of the simulation, carrying out beforehand the commands
+    /// of the recording being played back which are due, and recording or
+    /// checking a checksum of the world afterwards.
+    fn run_tick(&mut self) {
+        if let Some(ref mut playback) = self.playback {
+            for command in playback.take_due_commands(&self.world) {
+                command.apply(&mut self.world);
+            }
+        }
+
+        self.logic.tick(&mut self.world);
+        self.ticks_since_snapshot += 1;
+        if self.ticks_since_snapshot >= self.config.rewind_interval {
+            self.ticks_since_snapshot = 0;
+            self.world.take_snapshot();
+        }
+
+        if let Some(ref mut recorder) = self.recorder {
+            recorder.after_tick(&self.world);
+        }
+        let divergence = match self.playback {
+            Some(ref mut playback) => playback.check(&self.world),
+            None => None,
+        };
+        if let Some(tick) = divergence {
+            let line = format!("{}: {}", self.localization.borrow().console_replay_diverged, tick);
+            self.console.print(line);
+            self.console.show();
+        }
+    }
+
+    /// Pauses the game once the simulation has run every step of the
+    /// recording being played back, so that its end can be looked over. The
+    /// playback is kept, so that it can still be scrubbed back, until it is
+    /// stopped to play on from there.
+    fn finish_playback(&mut self) {
+        if !self.playback.as_ref().map_or(false, |playback| playback.is_finished(&self.world)) {
+            return;
+        }
+
+        // The commands carried out after the last step are kept too.
+        if let Some(ref mut playback) = self.playback {
+            for command in playback.take_due_commands(&self.world) {
+                command.apply(&mut self.world);
+            }
+        }
+        self.simulation_clock.set_slowdown(1);
+        if !self.simulation_clock.is_paused() {
+            self.simulation_clock.toggle_pause();
+        }
+        let line = self.localization.borrow().console_replay_finished.clone();
+        self.console.print(line);
+    }
+
+    /// Scrubs the recording being played back to its previous snapshot, or
+    /// to its start once there are none left.
+    fn scrub_back(&mut self) {
+        let path = match self.playback {
+            Some(ref playback) => playback.path().to_path_buf(),
+            None => return,
+        };
+        if self.world.rewind() {
+            self.ticks_since_snapshot = 0;
+            if let Some(ref mut playback) = self.playback {
+                playback.seek(&self.world);
+            }
+            return;
+        }
+
+        let read_only = self.read_only;
+        match Playback::open_from(&path, self.config.initial_world_size, self.materials.clone()) {
+            Ok((playback, world, _)) => {
+                // The camera stays where the player moved it.
+                let camera_position = *self.camera.get_position();
+                self.playback = None;
+                for line in self.replace_world(world, camera_position) {
+                    self.console.print(line);
+                }
+                self.playback = Some(playback);
+                self.read_only = read_only;
+            },
+            Err(e) => {
+                let line = format!("{}: {}", self.localization.borrow().console_replay_failed, e);
+                self.console.print(line);
+            },
+        }
+    }
+
+    /// Scrubs the recording being played back forward by `ticks` steps of
+    /// the simulation, or to its end.
+    fn scrub_forward(&mut self, ticks: u64) {
+        let mut ticks_run = 0;
+        for _ in 0..ticks {
+            if self.playback.as_ref().map_or(true, |playback| playback.is_finished(&self.world)) {
+                break;
+            }
+            self.run_tick();
+            ticks_run += 1;
+        }
+        if ticks_run > 0 {
+            self.finish_playback();
+        }
+    }
+
+    /// Records the world, and the recording being made of it, for crash
+    /// reports once `CRASH_RECORD_INTERVAL` steps have run since it last was,
+    /// so that a crash report holds the game as it was shortly before the
+    /// crash.
+    fn record_for_crash_report(&mut self, ticks: u32) {
+        let ticks_since_crash_record = match self.ticks_since_crash_record {
+            Some(previous) => previous + ticks as u64,
+            None => CRASH_RECORD_INTERVAL,
+        };
+        if ticks_since_crash_record >= CRASH_RECORD_INTERVAL {
+            let recording = self.recorder.as_ref().and_then(|recorder| {
+                recorder.to_json().ok().map(|json| (recorder.name().to_owned(), json))
+            });
+            crash::record_world(&save::world_metadata(&self.world, self.camera.get_position()), &self.world, recording);
+            self.ticks_since_crash_record = Some(0);
+        } else {
+            self.ticks_since_crash_record = Some(ticks_since_crash_record);
+        }
+    }
+
+    fn regenerate_world(&mut self, seed: u32) -> Vec<String> {
+        let mut output = self.end_recording();
+        let world_gen = *self.world.config();
+        let embark = self.world.embark();
+        self.generate_world(seed, world_gen, embark);
+        output.insert(0, format!("{}: {}", self.localization.borrow().console_world_generated, seed));
+        output.extend(self.restart_session());
+        output
+    }
+
+    /// Replaces the world with one generated from `seed` with the parameters
+    /// `world_gen`, whose colony starts in the chunk column `embark`.
+    fn generate_world(&mut self, seed: u32, world_gen: WorldGenConfig, embark: Point2<i32>) {
+        self.world = World::new(Some(seed), self.config.initial_world_size, embark, world_gen, self.materials.clone());
+        self.camera.set_position(save::embark_camera_position(&self.world));
+        start_history(&mut self.world, &self.config);
+        self.subscriptions = Subscriptions::new(&mut self.world);
+        self.read_only = false;
+        self.ticks_since_snapshot = 0;
+        self.ticks_since_crash_record = None;
+        self.save_name = None;
+        self.slice_cache = SliceCache::new();
+        self.slice_textures = SliceTextures::new();
+        self.particles.clear();
+        self.job_history.clear();
+    }
+
+    /// Exchanges commands and updates with the other players of the game
+    /// played over the network, if any.
+    fn update_session(&mut self, dt: f64) {
+        let (events, received) = match self.session {
+            Some(Session::Host(ref mut host)) => (host.update(dt, &self.world), Ok(Vec::new())),
+            Some(Session::Client(ref mut client)) => ((Vec::new(), Vec::new()), client.receive()),
+            None => return,
+        };
+        let (events, commands) = events;
+        for line in self.session_event_lines(events) {
+            self.console.print(line);
+        }
+        // The commands of the other players are carried out as those of the
+        // host are, so that they are recorded along with them.
+        for command in commands {
+            self.issue(command);
+        }
+
+        match received {
+            Ok(messages) => {
+                for message in messages {
+                    self.receive_host_message(message);
+                }
+            },
+            Err(e) => {
+                self.session = None;
+                let line = format!("{}: {}", self.localization.borrow().console_disconnected, e);
+                self.console.print(line);
+            },
+        }
+    }
+
+    fn receive_host_message(&mut self, message: HostMessage) {
+        match message {
+            HostMessage::Welcome { seed, world_gen, embark } => {
+                if let Some(Session::Client(ref mut client)) = self.session {
+                    client.welcome();
+                }
+                self.generate_world(seed, world_gen, Point2::new(embark[0], embark[1]));
+            },
+            HostMessage::Update { changes, chunks } => {
+                if let Some(Session::Client(ref mut client)) = self.session {
+                    if let Err(e) = client.apply_update(&mut self.world, changes, chunks) {
+                        warn!("Failed to apply an update from the host: {}", e);
+                    }
+                }
+            },
+        }
+    }
+
+    /// Welcomes the players of a hosted game into the world which just
+    /// replaced the last one, or leaves a joined game, whose world is no
+    /// longer the one shared with its host. Returns the lines to print in
+    /// response.
+    fn restart_session(&mut self) -> Vec<String> {
+        let is_client = match self.session {
+            Some(Session::Client(_)) => true,
+            _ => false,
+        };
+        if is_client {
+            self.session = None;
+            return vec![self.localization.borrow().console_left.clone()];
+        }
+
+        let events = match self.session {
+            Some(Session::Host(ref mut host)) => host.restart(&self.world),
+            _ => Vec::new(),
+        };
+        self.session_event_lines(events)
+    }
+
+    /// Returns the lines to print to the console for the players who joined
+    /// or left a hosted game.
+    fn session_event_lines(&self, events: Vec<SessionEvent>) -> Vec<String> {
+        let localization = self.localization.borrow();
+        events.into_iter().map(|event| match event {
+            SessionEvent::Joined(address) => format!("{}: {}", localization.console_player_joined, address),
+            SessionEvent::Left(address) => format!("{}: {}", localization.console_player_left, address),
+        }).collect()
+    }
+
+    /// Replaces the world with `world`, loaded from a save, and moves the
+    /// camera to `camera_position`. Returns the lines to print in response.
+    fn replace_world(&mut self, mut world: World, camera_position: Point3<f64>) -> Vec<String> {
+        let mut output = self.end_recording();
+        start_history(&mut world, &self.config);
+        self.subscriptions = Subscriptions::new(&mut world);
+        self.world = world;
+        self.read_only = false;
+        self.ticks_since_snapshot = 0;
+        self.ticks_since_crash_record = None;
+        self.save_name = None;
+        self.camera.set_position(camera_position);
+        self.slice_cache = SliceCache::new();
+        self.slice_textures = SliceTextures::new();
+        self.particles.clear();
+        self.job_history.clear();
+        self.simulation_clock.reset();
+        output.extend(self.restart_session());
+        output
+    }
+
+    /// Draws the z-level of the camera from above, along with what lies on
+    /// it, and the AI overlay and cursor over it.
+    fn render_top_down<G>(&mut self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache)
+        where G: Graphics<Texture=B::Texture>,
+    {
+        let camera_y = self.camera.get_tile_position().y;
+
+        // Position (in tiles) of the top-left corner of the view.
+        let view_origin = self.view_origin();
+        let view_x = view_origin.x;
+        let view_z = view_origin.y;
+        let start_x = view_x.floor();
+        let start_z = view_z.floor();
+
+        // Pixel offset of the first visible tile, which lets the view scroll
+        // smoothly between whole tiles.
+        let offset_x = (start_x - view_x) * TILE_SIZE;
+        let offset_z = (start_z - view_z) * TILE_SIZE;
+
+        // Range (in tiles) of the visible area. One extra row and column are
+        // drawn to cover the partially visible tiles along the edges of the
+        // view.
+        let min_x = start_x as i32;
+        let min_z = start_z as i32;
+        let max_x = min_x + self.bounds.width();
+        let max_z = min_z + self.bounds.height();
+
+        let daylight = self.daylight_brightness();
+
+        let tile_view = TileView {
+            y: camera_y,
+            min: Point2::new(min_x, min_z),
+            max: Point2::new(max_x, max_z),
+            offset: Point2::new(offset_x, offset_z),
+            tile_size: TILE_SIZE,
+        };
+        let cursor = self.tile_under_cursor();
+
+        // The textures of the slices which went out of view during the last
+        // frame are dropped before the textures of this one are fetched.
+        self.slice_textures.evict_unused();
+
+        let palette = self.palette.borrow();
+        let (tile_batch, slice_textures) = if self.batch_tiles {
+            (Some(&mut self.tile_batch), Some(&mut self.slice_textures))
+        } else {
+            (None, None)
+        };
+        let mut renderer = PistonRenderer::new(context, graphics, glyph_cache, &*self.tile_renderer, &*self.materials, &palette, tile_view, tile_batch, slice_textures);
+        renderer.clear(palette.background);
+
+        let tiles_start = Instant::now();
+        renderer::draw_map(&mut renderer, &tile_view, &mut self.slice_cache, &self.world.area, &self.materials, daylight);
+        self.tile_draw_stats = (tiles_start.elapsed(), renderer.flush_tiles());
+
+        {
+            let (graphics, _) = renderer.parts();
+
+            // Designations are drawn over the tiles they cover, beneath the
+            // items lying on them, unless they have been hidden. Stockpiles
+            // and blueprints fill their tiles, while the tiles to be dug out
+            // are hatched, across both diagonals for stairs.
+            let tile_rect = |position: &Position| -> Option<[f64; 4]> {
+                let in_view = position.y == camera_y &&
+                    position.x >= min_x && position.x <= max_x &&
+                    position.z >= min_z && position.z <= max_z;
+                if !in_view {
+                    return None;
+                }
+                Some(graphics::rectangle::square(
+                    (position.x - min_x) as f64 * TILE_SIZE + offset_x,
+                    (position.z - min_z) as f64 * TILE_SIZE + offset_z,
+                    TILE_SIZE))
+            };
+            if self.designator.shows(Designation::Stockpile) {
+                for stockpile in self.world.stockpiles() {
+                    if let Some(rect) = tile_rect(stockpile) {
+                        graphics::Rectangle::new(palette.stockpile).draw(rect, &context.draw_state, context.transform, graphics);
+                    }
+                }
+            }
+
+            let entities = self.world.entities();
+            for (entity, blueprint) in entities.blueprints.iter() {
+                if !self.designator.shows(Designation::Build(blueprint.structure)) {
+                    continue;
+                }
+                if let Some(rect) = entities.positions.get(entity).and_then(|position| tile_rect(position)) {
+                    graphics::Rectangle::new(palette.blueprint).draw(rect, &context.draw_state, context.transform, graphics);
+                }
+            }
+
+            let hatching = graphics::Line::new(palette.dig_mark, TILE_SIZE * HATCH_THICKNESS / 2.0);
+            for job in self.world.job_queue().iter().chain(entities.jobs.iter().map(|(_, job)| job)) {
+                let (designation, crossed) = match job.kind {
+                    JobKind::Dig => (Designation::Dig, false),
+                    JobKind::DigStairs => (Designation::DigStairs, true),
+                    _ => continue,
+                };
+                if !self.designator.shows(designation) {
+                    continue;
+                }
+                if let Some(rect) = tile_rect(&job.target) {
+                    for line in hatch_lines(rect, crossed) {
+                        hatching.draw(line, &context.draw_state, context.transform, graphics);
+                    }
+                }
+            }
+
+            // Entities are drawn over the tiles of the z-level they stand
+            // on, unless those tiles have not been revealed yet. Entities
+            // with a sprite in the atlas are drawn with it, tinted with their
+            // color. Otherwise, items and furniture are drawn as squares, and
+            // anything else as circles.
+            for (entity, position) in entities.positions.iter() {
+                let renderable = match entities.renderables.get(entity) {
+                    Some(renderable) => renderable,
+                    None => continue,
+                };
+                let in_view = position.y == camera_y &&
+                    position.x >= min_x && position.x <= max_x &&
+                    position.z >= min_z && position.z <= max_z;
+                if !in_view || !self.world.area.is_revealed(&position.to_point()) {
+                    continue;
+                }
+
+                let is_item = entities.items.contains(entity);
+                let is_square = is_item || entities.furniture.contains(entity);
+                let size = TILE_SIZE * if is_item { ITEM_SIZE } else { ENTITY_SIZE };
+                let margin = (TILE_SIZE - size) / 2.0;
+                let brightness = light_brightness(daylight, self.world.area.light_level(&position.to_point()));
+                let color = renderable.color;
+                let color = [color[0] * brightness, color[1] * brightness, color[2] * brightness, color[3]];
+                if let Some((sprite, elapsed)) = self.animations.sprite(entity) {
+                    let rect = graphics::rectangle::square(
+                        (position.x - min_x) as f64 * TILE_SIZE + offset_x,
+                        (position.z - min_z) as f64 * TILE_SIZE + offset_z,
+                        TILE_SIZE);
+                    if self.tile_renderer.draw_sprite(sprite, elapsed, color, rect, context, graphics) {
+                        continue;
+                    }
+                }
+                let rect = [
+                    (position.x - min_x) as f64 * TILE_SIZE + offset_x + margin,
+                    (position.z - min_z) as f64 * TILE_SIZE + offset_z + margin,
+                    size,
+                    size,
+                ];
+                if is_square {
+                    graphics::Rectangle::new(color).draw(rect, &context.draw_state, context.transform, graphics);
+                } else {
+                    graphics::Ellipse::new(color).draw(rect, &context.draw_state, context.transform, graphics);
+                }
+            }
+
+            self.particles.draw(&tile_view, context, graphics);
+            self.ai_overlay.draw(&self.world, &tile_view, context, graphics);
+
+            if let Some(pos) = cursor {
+                let rect = graphics::rectangle::square(
+                    (pos.x - min_x) as f64 * TILE_SIZE + offset_x,
+                    (pos.z - min_z) as f64 * TILE_SIZE + offset_z,
+                    TILE_SIZE);
+                graphics::Rectangle::new(palette.cursor_fill).draw(rect, &context.draw_state, context.transform, graphics);
+                graphics::Rectangle::new_border(palette.cursor, CURSOR_BORDER_RADIUS).draw(rect, &context.draw_state, context.transform, graphics);
+            }
+        }
+    }
+
+    /// Draws the top few z-levels around the camera in isometric projection,
+    /// along with the entities on them.
+    fn render_isometric<G>(&self, context: &Context, graphics: &mut G)
+        where G: Graphics,
+    {
+        let entities = self.world.entities();
+        let mut entity_colors = HashMap::new();
+        for (entity, position) in entities.positions.iter() {
+            let position = position.to_point();
+            if !self.world.area.is_revealed(&position) {
+                continue;
+            }
+            if let Some(renderable) = entities.renderables.get(entity) {
+                entity_colors.insert(position, renderable.color);
+            }
+        }
+
+        let palette = self.palette.borrow();
+        let view = IsometricView {
+            area: &self.world.area,
+            materials: &self.materials,
+            palette: &palette,
+            center: self.camera.get_tile_position(),
+            size: [self.bounds.width() as f64 * TILE_SIZE, self.bounds.height() as f64 * TILE_SIZE],
+            entities: entity_colors,
+        };
+        let daylight = self.daylight_brightness();
+        view.draw(|light| light_brightness(daylight, light), context, graphics);
+    }
+}
+
+impl<B, E, G> Scene<B, E, G> for GameScene<B>
+    where B: Backend + 'static,
+          E: GenericEvent,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn to_box(self) -> BoxedScene<B, E, G> {
+        Box::new(self)
+    }
+
+    fn render(&mut self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        self.tile_renderer.set_time(self.world.calendar().ticks());
+        if self.isometric {
+            graphics::clear(self.palette.borrow().background, graphics);
+            self.render_isometric(context, graphics);
+        } else {
+            self.render_top_down(context, graphics, glyph_cache);
+        }
+
+        Draw::<B, G>::draw(&self.welcome_label, context, graphics, glyph_cache);
+        Draw::<B, G>::draw(&self.date_label, context, graphics, glyph_cache);
+        Draw::<B, G>::draw(&self.weather_label, context, graphics, glyph_cache);
+        Draw::<B, G>::draw(&self.designation_label, context, graphics, glyph_cache);
+        Draw::<B, G>::draw(&self.inspection_panel, context, graphics, glyph_cache);
+        Draw::<B, G>::draw(&self.roster, context, graphics, glyph_cache);
+        Draw::<B, G>::draw(&self.tooltip, context, graphics, glyph_cache);
+        let view_origin = self.view_origin();
+        self.minimap.draw([view_origin.x, view_origin.y, self.bounds.width() as f64, self.bounds.height() as f64], &self.palette.borrow(), context, graphics);
+
+        Draw::<B, G>::draw(&self.console, context, graphics, glyph_cache);
     }
 
     fn handle_event(&mut self, e: &E) -> Option<SceneCommand<B, E, G>> {
         let mut maybe_scene = None;
 
+        let mut mouse_moved = false;
         e.mouse_cursor(|x, y| {
             self.mouse_pos = Point2::new(x, y);
+            mouse_moved = true;
         });
 
+        // While the console is open, it receives all keyboard input.
+        if self.console.is_visible() {
+            self.handle_console_event(e);
+            return None;
+        }
+
+        if mouse_moved {
+            self.tooltip_follows_mouse = true;
+            let pos = self.tile_under_mouse();
+            self.paint_designation(pos);
+        }
+
         e.press(|button_type| {
-            if let Keyboard(key) = button_type {
-                match key {
-                    Key::Backspace => maybe_scene = Some(SceneCommand::SetScene(MenuScene::new(self.config.clone(), self.localization.clone(), self.textures.clone()).to_box())),
+            match button_type {
+                // Clicking a colonist on the roster moves the camera over it
+                // and inspects it, and clicking the minimap moves the camera
+                // to the position clicked. Otherwise, without a designation
+                // to paint, clicking inspects colonists instead.
+                Mouse(MouseButton::Left) => {
+                    let pos = self.tile_under_mouse();
+                    if self.roster.contains(self.mouse_pos) {
+                        if let Some(colonist) = self.roster.entity_at(self.mouse_pos) {
+                            self.focus_colonist(colonist);
+                        }
+                    } else if let Some(minimap_pos) = self.minimap.tile_at(self.mouse_pos) {
+                        let y = self.camera.get_position().y;
+                        self.camera.set_position(Point3::new(minimap_pos.x, y, minimap_pos.y));
+                    } else if self.designator.selected().is_some() {
+                        self.designator.set_painting(true);
+                        self.paint_designation(pos);
+                    } else {
+                        self.select_colonist(pos);
+                    }
+                },
+                // Drafted colonists are ordered about with the right button,
+                // which otherwise cancels the designation or selection.
+                Mouse(MouseButton::Right) => {
+                    let pos = self.tile_under_mouse();
+                    self.order_or_cancel(pos);
+                },
+                Keyboard(key) => match key {
+                    Key::LShift | Key::RShift => self.camera.start_fast_scroll(key),
+                    Key::Backquote => {
+                        self.camera.stop();
+                        self.designator.set_painting(false);
+                        self.console.toggle();
+                    },
+                    Key::Escape => {
+                        // Keys and buttons released while paused are never
+                        // seen by this scene, so stop the camera and painting
+                        // before pausing.
+                        self.camera.stop();
+                        self.designator.set_painting(false);
+                        maybe_scene = Some(SceneCommand::PushScene(PauseScene::new(self.config.clone(), self.settings.clone(), self.localization.clone(), self.palette.clone(), self.materials.clone(), self.tile_renderer.clone(), self.audio.clone(), self.scripts.clone()).to_box()));
+                    },
+                    Key::Backspace => maybe_scene = Some(SceneCommand::SetScene(MenuScene::new(self.config.clone(), self.settings.clone(), self.localization.clone(), self.palette.clone(), self.materials.clone(), self.tile_renderer.clone(), self.audio.clone(), self.scripts.clone()).to_box())),
                     _ => {
-                        let command = self.get_command_from_binding(&key);
-                        if let Some(mut command) = command {
-                            command();
+                        // While the keyboard cursor is shown, the keys which
+                        // move the camera along the horizontal axes move the
+                        // cursor instead.
+                        let action = self.key_bindings.get_action_from_binding(&key).cloned();
+                        match action {
+                            Some(Action::Cursor(action)) => self.handle_cursor_action(action),
+                            Some(Action::ToggleDraft) => {
+                                if let Some(colonist) = self.selected {
+                                    self.issue(PlayerCommand::ToggleDraft(colonist));
+                                }
+                            },
+                            Some(Action::ShowReports) => {
+                                self.camera.stop();
+                                self.designator.set_painting(false);
+                                let stats = self.world.stats().clone();
+                                maybe_scene = Some(SceneCommand::PushScene(ReportsScene::new(self.config.clone(), self.settings.clone(), self.localization.clone(), self.palette.clone(), self.audio.clone(), stats).to_box()));
+                            },
+                            // A recording only keeps in step with a world
+                            // whose time is never skipped.
+                            Some(Action::SkipHour) if !self.is_recorded() => self.world.skip_time(TICKS_PER_HOUR),
+                            Some(Action::ToggleAiOverlay) => self.ai_overlay.toggle(&mut self.world),
+                            // A recording being played back can be stepped
+                            // through one step at a time, played at half and
+                            // a quarter of its speed in turn, scrubbed back
+                            // to the snapshots taken as it played, and
+                            // scrubbed forward an hour at a time.
+                            Some(Action::StepPlayback) if self.playback.is_some() => self.simulation_clock.step(),
+                            Some(Action::CyclePlaybackSlowdown) if self.playback.is_some() => {
+                                let slowdown = match self.simulation_clock.slowdown() {
+                                    1 => 2,
+                                    2 => 4,
+                                    _ => 1,
+                                };
+                                self.simulation_clock.set_slowdown(slowdown);
+                            },
+                            Some(Action::ScrubBack) if self.playback.is_some() => self.scrub_back(),
+                            Some(Action::ScrubForward) if self.playback.is_some() => self.scrub_forward(TICKS_PER_HOUR),
+                            Some(Action::Camera(CameraAction::Move(direction))) if self.cursor.position().is_some() && !direction.is_vertical() => {
+                                let offset = direction.to_vector();
+                                self.move_cursor(offset.x, offset.z);
+                            },
+                            action => {
+                                if let Some(Action::Camera(CameraAction::Move(_))) = action {
+                                    self.tooltip_follows_mouse = false;
+                                }
+                                let command = self.get_command_from_binding(&key);
+                                if let Some(mut command) = command {
+                                    command();
+                                }
+                            },
                         }
                     }
-                }
+                },
+                _ => {},
+            }
+        });
+
+        e.release(|button_type| {
+            match button_type {
+                Mouse(MouseButton::Left) => self.designator.set_painting(false),
+                Keyboard(key) => match key {
+                    Key::LShift | Key::RShift => self.camera.stop_fast_scroll(key),
+                    _ => {
+                        if let Some(&Action::Camera(CameraAction::Move(direction))) = self.key_bindings.get_action_from_binding(&key) {
+                            self.camera.stop_moving(&direction);
+                        }
+                    }
+                },
+                _ => {},
             }
         });
 
         maybe_scene
     }
+
+    fn update(&mut self, dt: f64) -> Option<SceneCommand<B, E, G>> {
+        self.camera.update(dt);
+
+        let camera_chunk = world::abs_pos_to_chunk_pos(&self.camera.get_tile_position());
+        let mut errors = Vec::new();
+        if let Err(e) = self.world.area.request_chunks_around(camera_chunk, self.config.chunk_load_radius as i32) {
+            errors.push(e);
+        }
+        errors.extend(self.world.area.receive_chunks());
+        for e in errors {
+            error!("{}", e);
+            let line = format!("{}: {}", self.localization.borrow().console_chunk_load_failed, e);
+            self.console.print(line);
+        }
+
+        let budget = self.config.chunk_memory_budget as usize * 1024 * 1024;
+        if let Err(e) = self.world.area.unload_chunks(budget) {
+            warn!("Failed to unload chunks: {}", e);
+        }
+
+        self.update_session(dt);
+
+        // The simulation keeps its fixed interval at every speed, so faster
+        // speeds only change how many steps are run. The world of a game
+        // joined over the network is simulated by its host instead.
+        self.logic.update(dt);
+        let ticks = match self.session {
+            Some(Session::Client(_)) => 0,
+            _ => self.simulation_clock.update(dt),
+        };
+        let mut ticks_run = 0;
+        for _ in 0..ticks {
+            if self.playback.as_ref().map_or(false, |playback| playback.is_finished(&self.world)) {
+                break;
+            }
+            self.run_tick();
+            ticks_run += 1;
+        }
+        if ticks_run > 0 {
+            self.finish_playback();
+        }
+        self.record_for_crash_report(ticks_run);
+        self.animations.update(&self.world);
+        self.world.update_light(LIGHT_UPDATE_BUDGET);
+        let events = self.world.take_events(self.subscriptions.sounds);
+        self.play_sounds(&events, dt);
+        let events = self.world.take_events(self.subscriptions.particles);
+        self.spawn_particles(&events, dt);
+        self.particles.update(dt);
+        let events = self.world.take_events(self.subscriptions.scripts);
+        self.run_script_hooks(&events);
+        let events = self.world.take_events(self.subscriptions.job_history);
+        self.record_job_history(&events);
+        self.update_timelapse();
+        self.minimap.update(&self.world.area, &self.materials, &self.palette.borrow(), &self.camera.get_tile_position());
+        let date_text = self.date_text();
+        self.date_label.set_text(date_text);
+        let weather_text = self.weather_text();
+        self.weather_label.set_text(weather_text);
+        let designation_text = self.designation_text();
+        self.designation_label.set_text(designation_text);
+        let inspection_lines = self.inspection_lines();
+        self.inspection_panel.set_lines(inspection_lines);
+        if self.roster.is_visible() {
+            let (heading, entries) = self.roster_contents();
+            self.roster.set_entries(heading, entries);
+        }
+
+        // The tooltip is hidden while the mouse cursor is over the minimap or
+        // the roster.
+        let over_overlay = self.tooltip_follows_mouse &&
+            (self.minimap.tile_at(self.mouse_pos).is_some() || self.roster.contains(self.mouse_pos));
+        let tooltip_lines = if over_overlay {
+            Vec::new()
+        } else {
+            let hovered = self.hovered_tile();
+            self.tooltip_lines(hovered)
+        };
+        self.tooltip.set_lines(tooltip_lines);
+        let hovered = self.hovered_tile();
+        let tooltip_origin = self.tooltip_origin(hovered);
+        self.tooltip.set_origin(tooltip_origin);
+
+        None
+    }
+
+    fn take_capture_request(&mut self) -> bool {
+        mem::replace(&mut self.capture_requested, false)
+    }
+
+    /// Fits the tile view to the window, and moves the panels drawn along its
+    /// edges.
+    fn resize(&mut self, width: f64, height: f64) {
+        self.bounds = view_bounds(width, height);
+        self.layout_overlays();
+    }
+
+    /// Picks up the key bindings, the scale of the interface and the palette,
+    /// in case they were changed from the options.
+    fn resume(&mut self) {
+        self.key_bindings = self.settings.borrow().game_scene_key_bindings.unwrap_bindings();
+        self.layout_overlays();
+        self.minimap.redraw();
+        self.slice_textures.redraw();
+    }
+
+    fn debug_info(&self) -> Vec<String> {
+        let localization = self.localization.borrow();
+        let camera_pos = self.camera.get_position();
+        let tile_pos = self.camera.get_tile_position();
+
+        let mut lines = vec![
+            format!("{}: {:?}", localization.gamescene_debug_cursor, self.mouse_pos),
+            format!("{}: ({:.2}, {:.2})", localization.gamescene_debug_camera, camera_pos.x, camera_pos.z),
+            format!("{}: {}", localization.gamescene_debug_z_level, tile_pos.y),
+            format!("{}: {:?}", localization.gamescene_debug_chunk, world::abs_pos_to_chunk_pos(&tile_pos)),
+            format!("{}: {}", localization.gamescene_debug_biome, self.world.area.biome_at(tile_pos.x, tile_pos.z).name()),
+            format!("{}: {}", localization.gamescene_debug_entities, self.world.entities().len()),
+            format!("{}: {}", localization.gamescene_debug_chunk_count, self.world.area.chunk_count()),
+            format!("{}: {}", localization.gamescene_debug_pending_chunks, self.world.area.pending_chunk_count()),
+            format!(
+                "{}: {} {}",
+                localization.gamescene_debug_chunk_memory,
+                self.world.area.memory_usage() / 1024,
+                localization.util_unit_kibibyte),
+            format!(
+                "{}: {:.2}{}, {} {} ({})",
+                localization.gamescene_debug_tile_drawing,
+                frame_clock::duration_to_millis(self.tile_draw_stats.0),
+                localization.util_unit_millisecond,
+                self.tile_draw_stats.1,
+                localization.gamescene_debug_draw_calls,
+                if self.batch_tiles { &localization.gamescene_debug_batched } else { &localization.gamescene_debug_unbatched }),
+        ];
+        if let Some(ref playback) = self.playback {
+            let divergence = match playback.divergence() {
+                Some(tick) => format!(" ({} {})", localization.gamescene_debug_replay_diverged, tick),
+                None => String::new(),
+            };
+            lines.push(format!(
+                "{}: {} / {}{}",
+                localization.gamescene_debug_replay,
+                self.world.calendar().ticks(),
+                playback.end_tick(),
+                divergence));
+        }
+        lines
+    }
 }
 
 impl<B> BindingMap<Key> for GameScene<B>
@@ -182,6 +2042,55 @@ impl<B> BindingMap<Key> for GameScene<B>
                             CameraAction::Move(ref direction) => Some(camera::new_move_camera_command(direction, &mut self.camera)),
                         }
                     },
+                    Action::Simulation(ref action) => Some(simulation::new_simulation_command(action, &mut self.simulation_clock)),
+                    Action::Designate(ref designation) => Some(designation::new_designate_command(designation, &mut self.designator)),
+                    // Cursor actions, the actions of the AI overlay and of
+                    // playback, and skipping time need most of the scene, the
+                    // reports are a scene of their own, and drafting is
+                    // recorded along with the other commands of the player,
+                    // so they are carried out directly when their keys are
+                    // pressed.
+                    Action::Cursor(_) | Action::ShowReports | Action::ToggleDraft | Action::SkipHour | Action::ToggleAiOverlay |
+                    Action::StepPlayback | Action::CyclePlaybackSlowdown | Action::ScrubBack | Action::ScrubForward => None,
+                    // A recording only keeps in step with a world which is
+                    // never rewound.
+                    Action::Rewind if self.is_recorded() => None,
+                    Action::Rewind => {
+                        let world = &mut self.world;
+                        let ticks_since_snapshot = &mut self.ticks_since_snapshot;
+                        let command: Command = Box::new(move || {
+                            if world.rewind() {
+                                *ticks_since_snapshot = 0;
+                            }
+                        });
+                        Some(command)
+                    },
+                    Action::ShowLog => {
+                        let console = &mut self.console;
+                        let command: Command = Box::new(move || {
+                            for line in logging::recent_lines(LOG_LINES_SHOWN) {
+                                console.print(line);
+                            }
+                            console.show();
+                        });
+                        Some(command)
+                    },
+                    Action::ToggleIsometric => {
+                        let isometric = &mut self.isometric;
+                        let command: Command = Box::new(move || *isometric = !*isometric);
+                        Some(command)
+                    },
+                    Action::CycleOverlays => Some(designation::new_cycle_overlays_command(&mut self.designator)),
+                    Action::ToggleBatchTiles => {
+                        let batch_tiles = &mut self.batch_tiles;
+                        let command: Command = Box::new(move || *batch_tiles = !*batch_tiles);
+                        Some(command)
+                    },
+                    Action::ToggleRoster => {
+                        let roster = &mut self.roster;
+                        let command: Command = Box::new(move || roster.toggle());
+                        Some(command)
+                    },
                 }
             },
             _ => None,
@@ -189,98 +2098,61 @@ impl<B> BindingMap<Key> for GameScene<B>
     }
 }
 
-struct Cursor {
-    x: f64,
-    y: f64,
+/// The subscriptions to the events of the world of each part of the scene
+/// which responds to them.
+struct Subscriptions {
+    sounds: Subscription,
+    particles: Subscription,
+    scripts: Subscription,
+    job_history: Subscription,
 }
 
-impl Cursor {
-    pub fn new(x: f64, y: f64) -> Self {
-        Cursor {
-            x: x,
-            y: y,
+impl Subscriptions {
+    /// Subscribes every part of the scene to the events of `world`, which
+    /// must be done again whenever the world is replaced.
+    fn new(world: &mut World) -> Self {
+        Subscriptions {
+            sounds: world.subscribe(&[EventKind::Dug]),
+            particles: world.subscribe(&[EventKind::VoxelChanged]),
+            scripts: world.subscribe(scripting::HOOK_EVENTS),
+            job_history: world.subscribe(&[EventKind::JobFinished]),
         }
     }
 }
 
-impl<B, G> Draw<B, G> for Cursor
-    where B: Backend,
-          G: Graphics<Texture=B::Texture>,
-{
-    fn draw(&self, context: &Context, graphics: &mut G, _glyph_cache: &mut B::CharacterCache) {
-        use graphics::Rectangle;
-
-        Rectangle::new(CURSOR_COLOR).draw(
-            [self.x, self.y, CURSOR_SIZE, CURSOR_SIZE],
-            &context.draw_state,
-            context.transform,
-            graphics);
-    }
+/// Starts keeping the snapshots of `world` to which it can be rewound, taking
+/// the first one right away.
+fn start_history(world: &mut World, config: &Config) {
+    world.set_history_capacity(config.rewind_snapshots as usize);
+    world.take_snapshot();
 }
 
-/// Drawable representation of a single cell.
-pub struct CellDrawable<'a, B>
-    where B: Backend,
-{
-    pub pos: Point3<i32>,
-    pub screen_pos: Point2<i32>,
-    pub world: &'a World,
-    textures: Rc<HashMap<TextureType, B::Texture>>,
+/// Returns the size, in tiles, of the tile view filling a window of the
+/// specified size.
+fn view_bounds(window_width: f64, window_height: f64) -> Bounds<i32> {
+    Bounds::new(0, 0, (window_width / TILE_SIZE).ceil() as i32, (window_height / TILE_SIZE).ceil() as i32)
 }
 
-impl<'a, B, G> Draw<B, G> for CellDrawable<'a, B>
-    where B: Backend,
-          G: Graphics<Texture=B::Texture>,
-{
-    fn draw(&self, context: &Context, graphics: &mut G, _glyph_cache: &mut B::CharacterCache) {
-        self.draw_cell::<G>(context, graphics);
-    }
-}
-
-impl<'a, B> CellDrawable<'a, B>
-    where B: Backend,
-{
-    pub fn new(pos: Point3<i32>, screen_pos: Point2<i32>, world: &'a World, textures: Rc<HashMap<TextureType, B::Texture>>) -> Self {
-        CellDrawable {
-            pos: pos,
-            screen_pos: screen_pos,
-            world: world,
-            textures: textures,
-        }
-    }
-
-    fn draw_cell<G>(&self, context: &Context, graphics: &mut G)
-        where G: Graphics<Texture=B::Texture>,
-    {
-        self.draw_terrain::<G>(context, graphics);
-    }
-
-    fn draw_terrain<G>(&self, context: &Context, graphics: &mut G)
-        where G: Graphics<Texture=B::Texture>,
-    {
-        use graphics::Image;
-
-        let tile = self.world.area.get_tile(&self.pos);
-
-        // If the tile is see-through, we want to render the tile_type
-        // underneath it, instead.
-        let texture = if tile.tile_type.is_solid() {
-            self.textures.get(&TextureType::TileTexture(tile.tile_type))
+/// Returns `HATCH_LINES` evenly spaced lines hatching the square `rect`
+/// diagonally, running from its lower left to its upper right, along with as
+/// many running from its lower right to its upper left if `crossed` is set.
+fn hatch_lines(rect: [f64; 4], crossed: bool) -> Vec<[f64; 4]> {
+    let (x, y, size) = (rect[0], rect[1], rect[2]);
+    let spacing = 2.0 * size / (HATCH_LINES + 1) as f64;
+    let mut lines = Vec::new();
+    for i in 1..HATCH_LINES + 1 {
+        // The line along which the offsets from the corner of the square add
+        // up to `t`, clipped to the square.
+        let t = i as f64 * spacing;
+        let (x1, y1, x2, y2) = if t <= size {
+            (t, 0.0, 0.0, t)
         } else {
-            let tile = self.world.area.get_tile(&(self.pos + Direction::Down.to_vector()));
-            self.textures.get(&TextureType::TileTexture(tile.tile_type))
+            (size, t - size, t - size, size)
         };
-
-        // Don't draw invisible tiles.
-        let texture = match texture {
-            Some(texture) => texture,
-            None => return,
-        };
-
-        let texture_x = self.screen_pos.x as f64 * TILE_SIZE;
-        let texture_y = self.screen_pos.y as f64 * TILE_SIZE;
-        Image::new()
-            .rect(graphics::rectangle::square(texture_x, texture_y, TILE_SIZE))
-            .draw(texture, &context.draw_state, context.transform, graphics);
+        lines.push([x + x1, y + y1, x + x2, y + y2]);
+        if crossed {
+            lines.push([x + size - x1, y + y1, x + size - x2, y + y2]);
+        }
     }
+    lines
 }
@@ -0,0 +1,348 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use piston::input::{GenericEvent, PressEvent, TextEvent};
+use piston::input::keyboard::Key;
+use piston::input::Button::Keyboard;
+use graphics;
+use image;
+use rgframework::{BoxedScene, Button, Label, Panel, Scene, SceneCommand, Widget};
+use rgframework::backend::{Backend, Graphics};
+use rgframework::backend::graphics::{Context, CreateTexture, Format, TextureSettings};
+use rgframework::draw::Draw;
+use world::MaterialRegistry;
+
+use audio;
+use audio::AudioManager;
+use config::Config;
+use localization::Localization;
+use palette::Palette;
+use save;
+use save::SavedGame;
+use scene::{GameScene, MenuScene};
+use scripting::ScriptHost;
+use settings::Settings;
+use tile_renderer::TileRenderer;
+
+const LIST_POSITION: [f64; 2] = [10.0, 10.0];
+const DETAILS_POSITION: [f64; 2] = [330.0, 212.0];
+const MENU_WIDTH: f64 = 300.0;
+const MENU_PADDING: f64 = 0.0;
+const MENU_SPACING: f64 = 6.0;
+const MENU_BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.0];
+
+/// Where the thumbnail of the selected save is drawn, and its size, in
+/// pixels.
+const THUMBNAIL_RECT: [f64; 4] = [330.0, 10.0, 192.0, 192.0];
+const BORDER_RADIUS: f64 = 1.0;
+/// The most saves listed at once. The list scrolls to keep the selected save
+/// in view.
+const VISIBLE_SAVES: usize = 12;
+
+/// What choosing an entry of the menus does.
+#[derive(Clone, Copy)]
+enum LoadAction {
+    /// Selects the save at the index within the list of saves.
+    Select(usize),
+    Load,
+    /// Waits for a new name of the selected save to be typed.
+    Rename,
+    Delete,
+    Back,
+}
+
+/// Lists the saved games, most recently saved first, with the thumbnail, the
+/// in-game date, the time of saving and the seed of the selected one, which
+/// can be loaded, renamed or deleted.
+pub struct LoadScene<B>
+    where B: Backend,
+{
+    config: Rc<Config>,
+    settings: Rc<RefCell<Settings>>,
+    localization: Rc<RefCell<Localization>>,
+    palette: Rc<RefCell<Palette>>,
+    materials: Rc<MaterialRegistry>,
+    tile_renderer: Rc<TileRenderer<B>>,
+    audio: Rc<AudioManager>,
+    scripts: Rc<ScriptHost>,
+    saves: Vec<SavedGame>,
+    selected: usize,
+    /// The name being typed for the selected save, while waiting for one to
+    /// be.
+    typed_name: Option<String>,
+    /// Whether deleting the selected save was chosen once, and awaits being
+    /// chosen again.
+    confirm_delete: bool,
+    /// The outcome of the last failed action, shown below the details.
+    status: Option<String>,
+    list: Panel<LoadAction>,
+    details: Panel<LoadAction>,
+    thumbnail: Option<B::Texture>,
+}
+
+impl<B> LoadScene<B>
+    where B: Backend,
+{
+    pub fn new(config: Rc<Config>, settings: Rc<RefCell<Settings>>, localization: Rc<RefCell<Localization>>, palette: Rc<RefCell<Palette>>, materials: Rc<MaterialRegistry>, tile_renderer: Rc<TileRenderer<B>>, audio: Rc<AudioManager>, scripts: Rc<ScriptHost>) -> Self {
+        let mut scene = LoadScene {
+            config: config,
+            settings: settings,
+            localization: localization,
+            palette: palette,
+            materials: materials,
+            tile_renderer: tile_renderer,
+            audio: audio,
+            scripts: scripts,
+            saves: save::list_saves(),
+            selected: 0,
+            typed_name: None,
+            confirm_delete: false,
+            status: None,
+            list: Panel::new(LIST_POSITION, MENU_WIDTH, MENU_PADDING, MENU_SPACING, MENU_BACKGROUND_COLOR),
+            details: Panel::new(DETAILS_POSITION, MENU_WIDTH, MENU_PADDING, MENU_SPACING, MENU_BACKGROUND_COLOR),
+            thumbnail: None,
+        };
+        scene.select(0);
+        scene
+    }
+
+    /// Carries out the entry of the menus chosen by `action`, returning the
+    /// scene to switch to, if any.
+    fn perform<E, G>(&mut self, action: LoadAction) -> Option<SceneCommand<B, E, G>>
+        where B: 'static,
+              E: GenericEvent,
+              G: Graphics<Texture=B::Texture>,
+    {
+        self.audio.play_sound(audio::CLICK_SOUND);
+        if let LoadAction::Back = action {
+            let scene = MenuScene::new(self.config.clone(), self.settings.clone(), self.localization.clone(), self.palette.clone(), self.materials.clone(), self.tile_renderer.clone(), self.audio.clone(), self.scripts.clone());
+            return Some(SceneCommand::SetScene(scene.to_box()));
+        }
+        if let LoadAction::Select(index) = action {
+            self.select(index);
+            return None;
+        }
+
+        let name = match self.saves.get(self.selected) {
+            Some(saved_game) => saved_game.name.clone(),
+            None => return None,
+        };
+        match action {
+            LoadAction::Load => {
+                match GameScene::load(self.config.clone(), self.settings.clone(), self.localization.clone(), self.palette.clone(), self.materials.clone(), self.tile_renderer.clone(), self.audio.clone(), self.scripts.clone(), &name) {
+                    Ok(scene) => return Some(SceneCommand::SetScene(scene.to_box())),
+                    Err(e) => self.status = Some(format!("{}: {}", self.localization.borrow().menuscene_load_failed, e)),
+                }
+            },
+            LoadAction::Rename => self.typed_name = Some(name),
+            LoadAction::Delete if self.confirm_delete => {
+                match save::delete_save(&name) {
+                    Ok(()) => {
+                        self.saves.remove(self.selected);
+                        let selected = self.selected;
+                        self.select(selected);
+                        return None;
+                    },
+                    Err(e) => self.status = Some(format!("{}: {}", self.localization.borrow().loadscene_delete_failed, e)),
+                }
+            },
+            LoadAction::Delete => {
+                self.confirm_delete = true;
+                self.build_menus();
+                return None;
+            },
+            LoadAction::Select(_) | LoadAction::Back => unreachable!(),
+        }
+
+        self.confirm_delete = false;
+        self.build_menus();
+        None
+    }
+
+    /// Selects the save at `index`, or the last one if there are fewer, and
+    /// loads its thumbnail.
+    fn select(&mut self, index: usize) {
+        self.selected = if index < self.saves.len() { index } else { self.saves.len().saturating_sub(1) };
+        self.confirm_delete = false;
+        self.thumbnail = self.saves.get(self.selected)
+            .and_then(|saved_game| load_thumbnail::<B>(&saved_game.name));
+        self.build_menus();
+    }
+
+    /// Handles a key pressed or text typed while waiting for a new name of
+    /// the selected save to be typed. Enter renames the save, and Escape
+    /// leaves it as it was.
+    fn handle_rename_event<E>(&mut self, e: &E)
+        where E: GenericEvent,
+    {
+        let mut typed_name = match self.typed_name.take() {
+            Some(typed_name) => typed_name,
+            None => return,
+        };
+        let mut done = false;
+        let mut confirmed = false;
+
+        e.text(|text| typed_name.push_str(text));
+        e.press(|button_type| {
+            if let Keyboard(key) = button_type {
+                match key {
+                    Key::Backspace => { typed_name.pop(); },
+                    Key::Return => {
+                        done = true;
+                        confirmed = true;
+                    },
+                    Key::Escape => done = true,
+                    _ => {},
+                }
+            }
+        });
+
+        if !done {
+            self.typed_name = Some(typed_name);
+            self.build_menus();
+            return;
+        }
+
+        if confirmed {
+            let name = self.saves[self.selected].name.clone();
+            if typed_name != name {
+                match save::rename_save(&name, &typed_name) {
+                    Ok(()) => {
+                        self.saves[self.selected].name = typed_name;
+                        self.status = None;
+                    },
+                    Err(e) => self.status = Some(format!("{}: {}", self.localization.borrow().loadscene_rename_failed, e)),
+                }
+            }
+        }
+        self.build_menus();
+    }
+
+    /// Builds the list of saves, scrolled to the selected one, and the
+    /// details of the selected save.
+    fn build_menus(&mut self) {
+        let localization = self.localization.borrow();
+        let palette = self.palette.borrow();
+        let settings = self.settings.borrow();
+        let font_size = settings.scale_font_size(self.config.font_size);
+        let button = |text: String, action: LoadAction, color: [f32; 4]| {
+            let label = Label::new(text, palette.text, font_size, [0.0, 0.0]);
+            Widget::Button(Button::new(label, action, color, palette.button_hover))
+        };
+
+        let mut list = Panel::new(settings.scale_position(LIST_POSITION), settings.scale_length(MENU_WIDTH), MENU_PADDING, settings.scale_length(MENU_SPACING), MENU_BACKGROUND_COLOR);
+        list.push(Widget::Label(Label::new(localization.loadscene_title.clone(), palette.text, font_size, [0.0, 0.0])));
+        if self.saves.is_empty() {
+            list.push(Widget::Label(Label::new(localization.loadscene_no_saves.clone(), palette.text, font_size, [0.0, 0.0])));
+        }
+        let first = (self.selected + 1).saturating_sub(VISIBLE_SAVES);
+        for (index, saved_game) in self.saves.iter().enumerate().skip(first).take(VISIBLE_SAVES) {
+            let color = if index == self.selected { palette.selected } else { palette.button };
+            let name = match self.typed_name {
+                Some(ref typed_name) if index == self.selected => format!("{}_ ({})", typed_name, localization.loadscene_type_name),
+                _ => saved_game.name.clone(),
+            };
+            list.push(button(name, LoadAction::Select(index), color));
+        }
+        self.list = list;
+
+        let mut details = Panel::new(settings.scale_position(DETAILS_POSITION), settings.scale_length(MENU_WIDTH), MENU_PADDING, settings.scale_length(MENU_SPACING), MENU_BACKGROUND_COLOR);
+        if let Some(saved_game) = self.saves.get(self.selected) {
+            let saved_at = if saved_game.saved_at == 0 { "-".to_owned() } else { save::format_timestamp(saved_game.saved_at) };
+            for text in vec![
+                format!("{}: {}", localization.loadscene_date, localization.date(&saved_game.calendar)),
+                format!("{}: {}", localization.loadscene_saved_at, saved_at),
+                format!("{}: {}", localization.loadscene_seed, saved_game.seed),
+            ] {
+                details.push(Widget::Label(Label::new(text, palette.text, font_size, [0.0, 0.0])));
+            }
+            let delete = if self.confirm_delete { &localization.loadscene_confirm_delete } else { &localization.loadscene_delete };
+            details.push(button(localization.loadscene_load.clone(), LoadAction::Load, palette.button));
+            details.push(button(localization.loadscene_rename.clone(), LoadAction::Rename, palette.button));
+            details.push(button(delete.clone(), LoadAction::Delete, palette.button));
+        }
+        details.push(button(localization.loadscene_back.clone(), LoadAction::Back, palette.button));
+        if let Some(ref status) = self.status {
+            details.push(Widget::Label(Label::new(status.as_str(), palette.text, font_size, [0.0, 0.0])));
+        }
+        self.details = details;
+    }
+}
+
+impl<B, E, G> Scene<B, E, G> for LoadScene<B>
+    where B: Backend + 'static,
+          E: GenericEvent,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn to_box(self) -> BoxedScene<B, E, G> {
+        Box::new(self)
+    }
+
+    fn render(&mut self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        use graphics::clear;
+
+        let palette = self.palette.borrow();
+        clear(palette.background, graphics);
+
+        Draw::<B, G>::draw(&self.list, context, graphics, glyph_cache);
+        Draw::<B, G>::draw(&self.details, context, graphics, glyph_cache);
+
+        if self.saves.is_empty() {
+            return;
+        }
+        let thumbnail_rect = self.settings.borrow().scale_rect(THUMBNAIL_RECT);
+        if let Some(ref thumbnail) = self.thumbnail {
+            graphics::Image::new().rect(thumbnail_rect).draw(thumbnail, &context.draw_state, context.transform, graphics);
+        }
+        graphics::Rectangle::new_border(palette.border, BORDER_RADIUS).draw(thumbnail_rect, &context.draw_state, context.transform, graphics);
+    }
+
+    fn handle_event(&mut self, e: &E) -> Option<SceneCommand<B, E, G>> {
+        if self.typed_name.is_some() {
+            self.handle_rename_event(e);
+            return None;
+        }
+
+        let mut action = self.list.handle_event(e).or_else(|| self.details.handle_event(e));
+
+        e.press(|button_type| {
+            if let Keyboard(key) = button_type {
+                match key {
+                    Key::Up if self.selected > 0 => action = Some(LoadAction::Select(self.selected - 1)),
+                    Key::Down => action = Some(LoadAction::Select(self.selected + 1)),
+                    Key::L | Key::Return => action = Some(LoadAction::Load),
+                    // Unlike a letter, F2 is not typed into the name once
+                    // renaming starts.
+                    Key::F2 => action = Some(LoadAction::Rename),
+                    Key::D | Key::Delete => action = Some(LoadAction::Delete),
+                    Key::B | Key::Escape => action = Some(LoadAction::Back),
+                    _ => {},
+                }
+            }
+        });
+
+        action.and_then(|action| self.perform(action))
+    }
+
+    /// Rebuilds the menus, in case the UI scale was changed.
+    fn resume(&mut self) {
+        self.build_menus();
+    }
+}
+
+/// Loads the thumbnail of the game saved under `name` into a texture, if it
+/// has one.
+fn load_thumbnail<B>(name: &str) -> Option<B::Texture>
+    where B: Backend,
+{
+    let path = match save::thumbnail_path(name) {
+        Ok(path) => path,
+        Err(_) => return None,
+    };
+    let image = match image::open(&path) {
+        Ok(image) => image.to_rgba(),
+        Err(_) => return None,
+    };
+    let size = [image.width(), image.height()];
+    <B::Texture as CreateTexture<()>>::create(&mut (), Format::Rgba8, &image.into_raw(), size, &TextureSettings::new()).ok()
+}
@@ -1,38 +1,112 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
 use std::rc::Rc;
 
 use piston::input::{GenericEvent, PressEvent};
 use piston::input::keyboard::Key;
 use piston::input::Button::Keyboard;
-use rgframework::{BoxedScene, Scene, SceneCommand};
+use rgframework::{BoxedScene, Button, Label, Panel, Scene, SceneCommand, Widget};
 use rgframework::backend::{Backend, Graphics};
 use rgframework::backend::graphics::Context;
+use rgframework::draw::Draw;
+use world::MaterialRegistry;
 
+use audio;
+use audio::AudioManager;
 use config::Config;
+use crash;
 use localization::Localization;
-use scene::GameScene;
-use textures::TextureType;
+use palette::Palette;
+use scene::{LoadScene, OptionsScene, SetupScene};
+use scripting::ScriptHost;
+use settings::Settings;
+use tile_renderer::TileRenderer;
+
+const MENU_POSITION: [f64; 2] = [10.0, 80.0];
+const MENU_WIDTH: f64 = 300.0;
+const MENU_PADDING: f64 = 0.0;
+const MENU_SPACING: f64 = 20.0;
+const MENU_BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.0];
+
+/// What choosing an entry of the menu does.
+#[derive(Clone, Copy)]
+enum MenuAction {
+    NewGame,
+    LoadGame,
+    Options,
+    Credits,
+    Quit,
+}
 
 pub struct MenuScene<B>
     where B:Backend,
 {
     config: Rc<Config>,
-    localization: Rc<Localization>,
-    textures: Rc<HashMap<TextureType, B::Texture>>,
+    settings: Rc<RefCell<Settings>>,
+    localization: Rc<RefCell<Localization>>,
+    palette: Rc<RefCell<Palette>>,
+    materials: Rc<MaterialRegistry>,
+    tile_renderer: Rc<TileRenderer<B>>,
+    audio: Rc<AudioManager>,
+    scripts: Rc<ScriptHost>,
+    menu: Panel<MenuAction>,
 }
 
 impl<B> MenuScene<B>
     where B: Backend,
 {
-    pub fn new(config: Rc<Config>, localization: Rc<Localization>, textures: Rc<HashMap<TextureType, B::Texture>>) -> Self {
+    pub fn new(config: Rc<Config>, settings: Rc<RefCell<Settings>>, localization: Rc<RefCell<Localization>>, palette: Rc<RefCell<Palette>>, materials: Rc<MaterialRegistry>, tile_renderer: Rc<TileRenderer<B>>, audio: Rc<AudioManager>, scripts: Rc<ScriptHost>) -> Self {
+        audio.play_music(audio::MENU_MUSIC);
+        crash::clear_world();
+        let menu = build_menu(&config, &settings.borrow(), &localization.borrow(), &palette.borrow());
         MenuScene {
+            menu: menu,
             config: config,
+            settings: settings,
             localization: localization,
-            textures: textures,
+            palette: palette,
+            materials: materials,
+            tile_renderer: tile_renderer,
+            audio: audio,
+            scripts: scripts,
+        }
+    }
+
+    /// Carries out the entry of the menu chosen by `action`, returning the
+    /// scene to switch to, if any.
+    fn perform<E, G>(&mut self, action: MenuAction) -> Option<SceneCommand<B, E, G>>
+        where B: 'static,
+              E: GenericEvent,
+              G: Graphics<Texture=B::Texture>,
+    {
+        self.audio.play_sound(audio::CLICK_SOUND);
+        match action {
+            MenuAction::NewGame => Some(SceneCommand::SetScene(SetupScene::new(self.config.clone(), self.settings.clone(), self.localization.clone(), self.palette.clone(), self.materials.clone(), self.tile_renderer.clone(), self.audio.clone(), self.scripts.clone()).to_box())),
+            MenuAction::LoadGame => Some(SceneCommand::SetScene(LoadScene::new(self.config.clone(), self.settings.clone(), self.localization.clone(), self.palette.clone(), self.materials.clone(), self.tile_renderer.clone(), self.audio.clone(), self.scripts.clone()).to_box())),
+            MenuAction::Options => Some(SceneCommand::PushScene(OptionsScene::new(self.config.clone(), self.settings.clone(), self.localization.clone(), self.palette.clone(), self.audio.clone()).to_box())),
+            MenuAction::Credits => None,
+            MenuAction::Quit => Some(SceneCommand::Clear),
         }
     }
 }
 
+/// Builds the entries of the menu, scaled as set in `settings`, in the
+/// colors of `palette`.
+fn build_menu(config: &Config, settings: &Settings, localization: &Localization, palette: &Palette) -> Panel<MenuAction> {
+    let font_size = settings.scale_font_size(config.font_size);
+    let mut menu = Panel::new(settings.scale_position(MENU_POSITION), settings.scale_length(MENU_WIDTH), MENU_PADDING, settings.scale_length(MENU_SPACING), MENU_BACKGROUND_COLOR);
+    for &(text, action) in &[
+        (&localization.menuscene_new_game, MenuAction::NewGame),
+        (&localization.menuscene_load_game, MenuAction::LoadGame),
+        (&localization.menuscene_options, MenuAction::Options),
+        (&localization.menuscene_credits, MenuAction::Credits),
+        (&localization.menuscene_quit, MenuAction::Quit),
+    ] {
+        let label = Label::new(text.as_str(), palette.text, font_size, [0.0, 0.0]);
+        menu.push(Widget::Button(Button::new(label, action, palette.button, palette.button_hover)));
+    }
+    menu
+}
+
 impl<B, E, G> Scene<B, E, G> for MenuScene<B>
     where B: Backend + 'static,
           E: GenericEvent,
@@ -43,42 +117,33 @@ impl<B, E, G> Scene<B, E, G> for MenuScene<B>
     }
 
     fn render(&mut self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
-        use graphics::{clear, color, Transformed};
-        use graphics::text::Text;
-
-        clear(color::WHITE, graphics);
-
-        Text::new(self.config.font_size).draw(
-            &self.localization.menuscene_singleplayer,
-            glyph_cache,
-            &context.draw_state,
-            context.transform.trans(10.0, 100.0),
-            graphics);
-
-        Text::new(self.config.font_size).draw(
-            &self.localization.menuscene_options,
-            glyph_cache,
-            &context.draw_state,
-            context.transform.trans(10.0, 150.0),
-            graphics);
-
-        Text::new(self.config.font_size).draw(
-            &self.localization.menuscene_credits,
-            glyph_cache,
-            &context.draw_state,
-            context.transform.trans(10.0, 200.0),
-            graphics);
+        use graphics::clear;
+
+        clear(self.palette.borrow().background, graphics);
+
+        Draw::<B, G>::draw(&self.menu, context, graphics, glyph_cache);
     }
 
     fn handle_event(&mut self, e: &E) -> Option<SceneCommand<B, E, G>> {
-        let mut maybe_scene = None;
+        let mut action = self.menu.handle_event(e);
 
         e.press(|button_type| {
-            if let Keyboard(Key::S) = button_type {
-                maybe_scene = Some(SceneCommand::SetScene(GameScene::new(self.config.clone(), self.localization.clone(), self.textures.clone()).to_box()));
+            if let Keyboard(key) = button_type {
+                match key {
+                    Key::N => action = Some(MenuAction::NewGame),
+                    Key::L => action = Some(MenuAction::LoadGame),
+                    Key::O => action = Some(MenuAction::Options),
+                    Key::Q => action = Some(MenuAction::Quit),
+                    _ => {},
+                }
             }
         });
 
-        maybe_scene
+        action.and_then(|action| self.perform(action))
+    }
+
+    /// Rebuilds the menu, in case the UI scale was changed from the options.
+    fn resume(&mut self) {
+        self.menu = build_menu(&self.config, &self.settings.borrow(), &self.localization.borrow(), &self.palette.borrow());
     }
 }
@@ -1,5 +1,15 @@
-pub use self::game_scene::GameScene;
+pub use self::game_scene::{GameScene, TEXT_STATUS_LINES};
+pub use self::load_scene::LoadScene;
 pub use self::menu_scene::MenuScene;
+pub use self::options_scene::OptionsScene;
+pub use self::pause_scene::PauseScene;
+pub use self::reports_scene::ReportsScene;
+pub use self::setup_scene::SetupScene;
 
 mod game_scene;
+mod load_scene;
 mod menu_scene;
+mod options_scene;
+mod pause_scene;
+mod reports_scene;
+mod setup_scene;
@@ -0,0 +1,366 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use piston::input::{GenericEvent, PressEvent};
+use piston::input::keyboard::Key;
+use piston::input::Button::Keyboard;
+use rgframework::{BindingStore, BoxedScene, Button, Label, Panel, RustcSerializeWrapper, Scene, SceneCommand, Widget};
+use rgframework::backend::{Backend, Graphics};
+use rgframework::backend::graphics::Context;
+use rgframework::draw::Draw;
+
+use audio;
+use audio::AudioManager;
+use config::Config;
+use localization::Localization;
+use palette::Palette;
+use settings;
+use settings::Settings;
+
+const GENERAL_POSITION: [f64; 2] = [10.0, 10.0];
+const GENERAL_WIDTH: f64 = 300.0;
+const BINDINGS_POSITION: [f64; 2] = [320.0, 10.0];
+const BINDINGS_WIDTH: f64 = 470.0;
+const MENU_PADDING: f64 = 0.0;
+const MENU_SPACING: f64 = 6.0;
+const MENU_BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.0];
+
+/// The window sizes offered, as `(width, height)`.
+const RESOLUTIONS: [(u32, u32); 5] = [(800, 600), (800, 800), (1024, 768), (1280, 720), (1920, 1080)];
+/// The frame rate caps offered, the last of which is high enough to leave
+/// the frame rate unlimited.
+const FPS_CAPS: [u64; 5] = [30, 60, 120, 144, UNLIMITED_FPS];
+const UNLIMITED_FPS: u64 = 10_000;
+/// The number of steps between silence and full volume.
+const VOLUME_STEPS: u32 = 10;
+/// The UI scales offered, the first of which detects the scale from the DPI
+/// of the display.
+const UI_SCALES: [f64; 6] = [settings::AUTO_UI_SCALE, 0.75, 1.0, 1.25, 1.5, 2.0];
+const BINDINGS_PER_PAGE: usize = 10;
+
+/// What choosing an entry of the menu does.
+#[derive(Clone, Copy)]
+enum OptionsAction {
+    Resolution,
+    Fullscreen,
+    Vsync,
+    MaxFps,
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    UiScale,
+    Language,
+    Palette,
+    /// Waits for a key to replace the key binding at the specified index of
+    /// the sorted bindings.
+    Rebind(usize),
+    PreviousPage,
+    NextPage,
+    Back,
+}
+
+/// Menu editing the settings of the player.
+///
+/// Every change is saved right away, and applied right away where the game
+/// allows: the size and mode of the window only change after a restart.
+pub struct OptionsScene {
+    config: Rc<Config>,
+    settings: Rc<RefCell<Settings>>,
+    localization: Rc<RefCell<Localization>>,
+    palette: Rc<RefCell<Palette>>,
+    audio: Rc<AudioManager>,
+    /// The settings as edited, including those which have yet to take
+    /// effect.
+    draft: Settings,
+    /// The languages which can be chosen.
+    languages: Vec<String>,
+    /// The palettes which can be chosen.
+    palettes: Vec<String>,
+    /// The keys bound to actions, sorted by the actions they are bound to.
+    bindings: Vec<Key>,
+    /// The page of `bindings` being shown.
+    page: usize,
+    /// The index within `bindings` of the binding waiting for a key to be
+    /// pressed, if any.
+    rebinding: Option<usize>,
+    /// The error encountered while last saving the settings, if any.
+    status: Option<String>,
+    general: Panel<OptionsAction>,
+    key_bindings: Panel<OptionsAction>,
+}
+
+impl OptionsScene {
+    pub fn new(config: Rc<Config>, settings: Rc<RefCell<Settings>>, localization: Rc<RefCell<Localization>>, palette: Rc<RefCell<Palette>>, audio: Rc<AudioManager>) -> Self {
+        // The saved settings are edited, since the settings of the window in
+        // use may differ from those saved.
+        let draft = match Settings::load(&settings::settings_path()) {
+            Ok(draft) => draft,
+            Err(_) => settings.borrow().clone(),
+        };
+        let bindings = sorted_bindings(&draft);
+        let languages = Localization::languages(&(&config.asset_path).into());
+        let palettes = Palette::names(&(&config.asset_path).into());
+        let mut scene = OptionsScene {
+            config: config,
+            settings: settings,
+            localization: localization,
+            palette: palette,
+            audio: audio,
+            draft: draft,
+            languages: languages,
+            palettes: palettes,
+            bindings: bindings,
+            page: 0,
+            rebinding: None,
+            status: None,
+            general: Panel::new(GENERAL_POSITION, GENERAL_WIDTH, MENU_PADDING, MENU_SPACING, MENU_BACKGROUND_COLOR),
+            key_bindings: Panel::new(BINDINGS_POSITION, BINDINGS_WIDTH, MENU_PADDING, MENU_SPACING, MENU_BACKGROUND_COLOR),
+        };
+        scene.build_menus();
+        scene
+    }
+
+    /// Carries out the entry of the menu chosen by `action`, returning true
+    /// if the menu should be closed.
+    fn perform(&mut self, action: OptionsAction) -> bool {
+        self.audio.play_sound(audio::CLICK_SOUND);
+        match action {
+            OptionsAction::Resolution => {
+                let (width, height) = next_value(&RESOLUTIONS, (self.draft.window_width, self.draft.window_height));
+                self.draft.window_width = width;
+                self.draft.window_height = height;
+            },
+            OptionsAction::Fullscreen => self.draft.fullscreen = !self.draft.fullscreen,
+            OptionsAction::Vsync => self.draft.vsync = !self.draft.vsync,
+            OptionsAction::MaxFps => self.draft.max_fps = next_value(&FPS_CAPS, self.draft.max_fps),
+            OptionsAction::MasterVolume => self.draft.master_volume = next_volume(self.draft.master_volume),
+            OptionsAction::MusicVolume => self.draft.music_volume = next_volume(self.draft.music_volume),
+            OptionsAction::SfxVolume => self.draft.sfx_volume = next_volume(self.draft.sfx_volume),
+            OptionsAction::UiScale => self.draft.ui_scale = next_value(&UI_SCALES, self.draft.ui_scale),
+            OptionsAction::Language => if !self.languages.is_empty() {
+                let next = self.languages.iter().position(|language| *language == self.draft.language).map_or(0, |index| index + 1);
+                self.draft.language = self.languages[next % self.languages.len()].clone();
+            },
+            OptionsAction::Palette => if !self.palettes.is_empty() {
+                let next = self.palettes.iter().position(|palette| *palette == self.draft.palette).map_or(0, |index| index + 1);
+                self.draft.palette = self.palettes[next % self.palettes.len()].clone();
+            },
+            OptionsAction::Rebind(index) => {
+                self.rebinding = Some(index);
+                self.build_menus();
+                return false;
+            },
+            OptionsAction::PreviousPage => {
+                self.page = self.page.saturating_sub(1);
+                self.build_menus();
+                return false;
+            },
+            OptionsAction::NextPage => {
+                if (self.page + 1) * BINDINGS_PER_PAGE < self.bindings.len() {
+                    self.page += 1;
+                }
+                self.build_menus();
+                return false;
+            },
+            OptionsAction::Back => return true,
+        }
+
+        self.apply();
+        false
+    }
+
+    /// Binds `key` to the action of the binding waiting for a key, replacing
+    /// whatever `key` was bound to.
+    fn rebind(&mut self, key: Key) {
+        let index = match self.rebinding.take() {
+            Some(index) => index,
+            None => return,
+        };
+
+        if let Some(&old) = self.bindings.get(index) {
+            self.draft.game_scene_key_bindings.rebind(&RustcSerializeWrapper::new(old), RustcSerializeWrapper::new(key));
+        }
+        self.bindings = sorted_bindings(&self.draft);
+        self.apply();
+    }
+
+    /// Applies the edited settings which can be changed while the game is
+    /// running, and saves all of them.
+    fn apply(&mut self) {
+        let language_changed = self.settings.borrow().language != self.draft.language;
+        let palette_changed = self.settings.borrow().palette != self.draft.palette;
+        self.settings.borrow_mut().apply_live(&self.draft);
+        if language_changed {
+            *self.localization.borrow_mut() = Localization::load(&(&self.config.asset_path).into(), &self.draft.language);
+        }
+        if palette_changed {
+            *self.palette.borrow_mut() = Palette::load(&(&self.config.asset_path).into(), &self.draft.palette);
+        }
+        self.audio.set_volumes(&self.settings.borrow());
+
+        self.status = match self.draft.save(&settings::settings_path()) {
+            Ok(()) => None,
+            Err(e) => Some(format!("{}: {}", self.localization.borrow().optionsscene_save_failed, e)),
+        };
+        self.build_menus();
+    }
+
+    /// Builds the entries of both menus from the edited settings.
+    fn build_menus(&mut self) {
+        let localization = self.localization.borrow();
+        let palette = self.palette.borrow();
+        let draft = &self.draft;
+        let settings = self.settings.borrow();
+        let font_size = settings.scale_font_size(self.config.font_size);
+        let max_fps = if draft.max_fps >= UNLIMITED_FPS {
+            localization.optionsscene_unlimited.clone()
+        } else {
+            draft.max_fps.to_string()
+        };
+        let ui_scale = if draft.ui_scale == settings::AUTO_UI_SCALE {
+            format!("{} ({:.0}%)", localization.optionsscene_auto, draft.ui_scale() * 100.0)
+        } else {
+            format!("{:.0}%", draft.ui_scale * 100.0)
+        };
+
+        let mut general = Panel::new(settings.scale_position(GENERAL_POSITION), settings.scale_length(GENERAL_WIDTH), MENU_PADDING, settings.scale_length(MENU_SPACING), MENU_BACKGROUND_COLOR);
+        general.push(Widget::Label(Label::new(localization.optionsscene_title.clone(), palette.text, font_size, [0.0, 0.0])));
+        for (text, action) in vec![
+            (format!("{}: {}x{}", localization.optionsscene_resolution, draft.window_width, draft.window_height), OptionsAction::Resolution),
+            (format!("{}: {}", localization.optionsscene_fullscreen, on_off(&localization, draft.fullscreen)), OptionsAction::Fullscreen),
+            (format!("{}: {}", localization.optionsscene_vsync, on_off(&localization, draft.vsync)), OptionsAction::Vsync),
+            (format!("{}: {}", localization.optionsscene_max_fps, max_fps), OptionsAction::MaxFps),
+            (format!("{}: {:.0}%", localization.optionsscene_master_volume, draft.master_volume * 100.0), OptionsAction::MasterVolume),
+            (format!("{}: {:.0}%", localization.optionsscene_music_volume, draft.music_volume * 100.0), OptionsAction::MusicVolume),
+            (format!("{}: {:.0}%", localization.optionsscene_sfx_volume, draft.sfx_volume * 100.0), OptionsAction::SfxVolume),
+            (format!("{}: {}", localization.optionsscene_ui_scale, ui_scale), OptionsAction::UiScale),
+            (format!("{}: {}", localization.optionsscene_language, draft.language), OptionsAction::Language),
+            (format!("{}: {}", localization.optionsscene_palette, draft.palette), OptionsAction::Palette),
+            (localization.optionsscene_back.clone(), OptionsAction::Back),
+        ] {
+            let label = Label::new(text, palette.text, font_size, [0.0, 0.0]);
+            general.push(Widget::Button(Button::new(label, action, palette.button, palette.button_hover)));
+        }
+        general.push(Widget::Label(Label::new(localization.optionsscene_restart_required.clone(), palette.text, font_size, [0.0, 0.0])));
+        if let Some(ref status) = self.status {
+            general.push(Widget::Label(Label::new(status.clone(), palette.text, font_size, [0.0, 0.0])));
+        }
+
+        let mut key_bindings = Panel::new(settings.scale_position(BINDINGS_POSITION), settings.scale_length(BINDINGS_WIDTH), MENU_PADDING, settings.scale_length(MENU_SPACING), MENU_BACKGROUND_COLOR);
+        key_bindings.push(Widget::Label(Label::new(localization.optionsscene_key_bindings.clone(), palette.text, font_size, [0.0, 0.0])));
+        let first = self.page * BINDINGS_PER_PAGE;
+        for (index, key) in self.bindings.iter().enumerate().skip(first).take(BINDINGS_PER_PAGE) {
+            let action = draft.game_scene_key_bindings.get_action_from_binding(&RustcSerializeWrapper::new(*key))
+                .map_or(String::new(), |action| format!("{:?}", action));
+            let key = if self.rebinding == Some(index) {
+                localization.optionsscene_press_key.clone()
+            } else {
+                format!("{:?}", key)
+            };
+            let text = format!("{}: {}", action, key);
+            let label = Label::new(text, palette.text, font_size, [0.0, 0.0]);
+            key_bindings.push(Widget::Button(Button::new(label, OptionsAction::Rebind(index), palette.button, palette.button_hover)));
+        }
+        for &(text, action) in &[
+            (&localization.optionsscene_previous_page, OptionsAction::PreviousPage),
+            (&localization.optionsscene_next_page, OptionsAction::NextPage),
+        ] {
+            let label = Label::new(text.as_str(), palette.text, font_size, [0.0, 0.0]);
+            key_bindings.push(Widget::Button(Button::new(label, action, palette.button, palette.button_hover)));
+        }
+
+        self.general = general;
+        self.key_bindings = key_bindings;
+    }
+}
+
+impl<B, E, G> Scene<B, E, G> for OptionsScene
+    where B: Backend,
+          E: GenericEvent,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn to_box(self) -> BoxedScene<B, E, G> {
+        Box::new(self)
+    }
+
+    fn render(&mut self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        use graphics::clear;
+
+        clear(self.palette.borrow().background, graphics);
+
+        Draw::<B, G>::draw(&self.general, context, graphics, glyph_cache);
+        Draw::<B, G>::draw(&self.key_bindings, context, graphics, glyph_cache);
+    }
+
+    fn handle_event(&mut self, e: &E) -> Option<SceneCommand<B, E, G>> {
+        // While waiting for a key to bind, the next key pressed is bound, or
+        // the rebinding is cancelled by Escape.
+        if self.rebinding.is_some() {
+            let mut pressed = None;
+            e.press(|button_type| {
+                if let Keyboard(key) = button_type {
+                    pressed = Some(key);
+                }
+            });
+            match pressed {
+                Some(Key::Escape) => {
+                    self.rebinding = None;
+                    self.build_menus();
+                },
+                Some(key) => self.rebind(key),
+                None => {},
+            }
+            return None;
+        }
+
+        let general_action = self.general.handle_event(e);
+        let mut action = self.key_bindings.handle_event(e).or(general_action);
+
+        e.press(|button_type| {
+            if let Keyboard(key) = button_type {
+                match key {
+                    Key::Escape | Key::B => action = Some(OptionsAction::Back),
+                    _ => {},
+                }
+            }
+        });
+
+        if let Some(action) = action {
+            if self.perform(action) {
+                return Some(SceneCommand::PopScene);
+            }
+        }
+        None
+    }
+}
+
+/// Returns the keys bound to actions, sorted by the actions they are bound
+/// to, then by the keys themselves.
+fn sorted_bindings(settings: &Settings) -> Vec<Key> {
+    let mut bindings: Vec<(String, Key)> = settings.game_scene_key_bindings.iter()
+        .map(|(key, action)| (format!("{:?} {:?}", action, **key), **key))
+        .collect();
+    bindings.sort_by(|a, b| a.0.cmp(&b.0));
+    bindings.into_iter().map(|(_, key)| key).collect()
+}
+
+/// Returns the localized word for whether a setting is `enabled`.
+fn on_off(localization: &Localization, enabled: bool) -> &str {
+    if enabled { &localization.optionsscene_on } else { &localization.optionsscene_off }
+}
+
+/// Returns the value following `current` among `values`, wrapping around to
+/// the first value, which is also returned if `current` is not among them.
+pub fn next_value<T>(values: &[T], current: T) -> T
+    where T: Copy + PartialEq,
+{
+    let next = values.iter().position(|&value| value == current).map_or(0, |index| index + 1);
+    values[next % values.len()]
+}
+
+/// Returns the volume a step louder than `volume`, wrapping around to silence
+/// after full volume.
+fn next_volume(volume: f64) -> f64 {
+    let step = (volume * VOLUME_STEPS as f64).round() as u32;
+    ((step + 1) % (VOLUME_STEPS + 1)) as f64 / VOLUME_STEPS as f64
+}
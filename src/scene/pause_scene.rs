@@ -0,0 +1,150 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use piston::input::{GenericEvent, PressEvent};
+use piston::input::keyboard::Key;
+use piston::input::Button::Keyboard;
+use graphics::color;
+use rgframework::{BoxedScene, Button, Label, Panel, Scene, SceneCommand, Widget};
+use rgframework::backend::{Backend, Graphics};
+use rgframework::backend::graphics::Context;
+use rgframework::draw::Draw;
+use world::MaterialRegistry;
+
+use audio;
+use audio::AudioManager;
+use config::Config;
+use localization::Localization;
+use palette::Palette;
+use scene::{MenuScene, OptionsScene};
+use scripting::ScriptHost;
+use settings::Settings;
+use tile_renderer::TileRenderer;
+
+const BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+const MENU_POSITION: [f64; 2] = [10.0, 80.0];
+const MENU_WIDTH: f64 = 300.0;
+const MENU_PADDING: f64 = 0.0;
+const MENU_SPACING: f64 = 20.0;
+const MENU_BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.0];
+const BUTTON_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.4];
+const BUTTON_HOVER_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.7];
+
+/// What choosing an entry of the menu does.
+#[derive(Clone, Copy)]
+enum PauseAction {
+    Resume,
+    Options,
+    MainMenu,
+    Quit,
+}
+
+/// Menu drawn over the game while it is paused.
+pub struct PauseScene<B>
+    where B: Backend,
+{
+    config: Rc<Config>,
+    settings: Rc<RefCell<Settings>>,
+    localization: Rc<RefCell<Localization>>,
+    palette: Rc<RefCell<Palette>>,
+    materials: Rc<MaterialRegistry>,
+    tile_renderer: Rc<TileRenderer<B>>,
+    audio: Rc<AudioManager>,
+    scripts: Rc<ScriptHost>,
+    menu: Panel<PauseAction>,
+}
+
+impl<B> PauseScene<B>
+    where B: Backend,
+{
+    pub fn new(config: Rc<Config>, settings: Rc<RefCell<Settings>>, localization: Rc<RefCell<Localization>>, palette: Rc<RefCell<Palette>>, materials: Rc<MaterialRegistry>, tile_renderer: Rc<TileRenderer<B>>, audio: Rc<AudioManager>, scripts: Rc<ScriptHost>) -> Self {
+        let menu = build_menu(&config, &settings.borrow(), &localization.borrow());
+        PauseScene {
+            menu: menu,
+            config: config,
+            settings: settings,
+            localization: localization,
+            palette: palette,
+            materials: materials,
+            tile_renderer: tile_renderer,
+            audio: audio,
+            scripts: scripts,
+        }
+    }
+}
+
+/// Builds the title and entries of the menu, scaled as set in `settings`.
+fn build_menu(config: &Config, settings: &Settings, localization: &Localization) -> Panel<PauseAction> {
+    let font_size = settings.scale_font_size(config.font_size);
+    let mut menu = Panel::new(settings.scale_position(MENU_POSITION), settings.scale_length(MENU_WIDTH), MENU_PADDING, settings.scale_length(MENU_SPACING), MENU_BACKGROUND_COLOR);
+    menu.push(Widget::Label(Label::new(localization.pausescene_title.clone(), color::WHITE, font_size, [0.0, 0.0])));
+    for &(text, action) in &[
+        (&localization.pausescene_resume, PauseAction::Resume),
+        (&localization.pausescene_options, PauseAction::Options),
+        (&localization.pausescene_main_menu, PauseAction::MainMenu),
+        (&localization.pausescene_quit, PauseAction::Quit),
+    ] {
+        let label = Label::new(text.as_str(), color::WHITE, font_size, [0.0, 0.0]);
+        menu.push(Widget::Button(Button::new(label, action, BUTTON_COLOR, BUTTON_HOVER_COLOR)));
+    }
+    menu
+}
+
+impl<B, E, G> Scene<B, E, G> for PauseScene<B>
+    where B: Backend + 'static,
+          E: GenericEvent,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn to_box(self) -> BoxedScene<B, E, G> {
+        Box::new(self)
+    }
+
+    fn render(&mut self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        use graphics::Rectangle;
+
+        let size = context.get_view_size();
+        Rectangle::new(BACKGROUND_COLOR).draw(
+            [0.0, 0.0, size[0], size[1]],
+            &context.draw_state,
+            context.transform,
+            graphics);
+
+        Draw::<B, G>::draw(&self.menu, context, graphics, glyph_cache);
+    }
+
+    fn handle_event(&mut self, e: &E) -> Option<SceneCommand<B, E, G>> {
+        let mut action = self.menu.handle_event(e);
+
+        e.press(|button_type| {
+            if let Keyboard(key) = button_type {
+                match key {
+                    Key::Escape | Key::R => action = Some(PauseAction::Resume),
+                    Key::O => action = Some(PauseAction::Options),
+                    Key::M => action = Some(PauseAction::MainMenu),
+                    Key::Q => action = Some(PauseAction::Quit),
+                    _ => {},
+                }
+            }
+        });
+
+        if action.is_some() {
+            self.audio.play_sound(audio::CLICK_SOUND);
+        }
+        match action {
+            Some(PauseAction::Resume) => Some(SceneCommand::PopScene),
+            Some(PauseAction::Options) => Some(SceneCommand::PushScene(OptionsScene::new(self.config.clone(), self.settings.clone(), self.localization.clone(), self.palette.clone(), self.audio.clone()).to_box())),
+            Some(PauseAction::MainMenu) => Some(SceneCommand::ReplaceAll(MenuScene::new(self.config.clone(), self.settings.clone(), self.localization.clone(), self.palette.clone(), self.materials.clone(), self.tile_renderer.clone(), self.audio.clone(), self.scripts.clone()).to_box())),
+            Some(PauseAction::Quit) => Some(SceneCommand::Clear),
+            None => None,
+        }
+    }
+
+    /// Rebuilds the menu, in case the UI scale was changed from the options.
+    fn resume(&mut self) {
+        self.menu = build_menu(&self.config, &self.settings.borrow(), &self.localization.borrow());
+    }
+
+    fn is_transparent(&self) -> bool {
+        true
+    }
+}
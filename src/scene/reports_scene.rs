@@ -0,0 +1,211 @@
+use std::cell::RefCell;
+use std::cmp;
+use std::rc::Rc;
+
+use piston::input::{GenericEvent, PressEvent};
+use piston::input::keyboard::Key;
+use piston::input::Button::Keyboard;
+use graphics;
+use rgframework::{BoxedScene, Button, Label, Panel, Scene, SceneCommand, Widget};
+use rgframework::backend::{Backend, Graphics};
+use rgframework::backend::graphics::Context;
+use rgframework::draw::Draw;
+use world::Stats;
+
+use audio;
+use audio::AudioManager;
+use config::Config;
+use localization::Localization;
+use palette::Palette;
+use settings::Settings;
+
+const MENU_POSITION: [f64; 2] = [10.0, 10.0];
+const MENU_WIDTH: f64 = 300.0;
+const MENU_PADDING: f64 = 0.0;
+const MENU_SPACING: f64 = 6.0;
+const MENU_BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.0];
+
+/// Where the first graph is drawn, and the size of every graph, in pixels.
+/// The graphs are laid out in a grid of `GRAPH_COLUMNS` columns, spaced by
+/// `GRAPH_SPACING`, which leaves room for the caption above each graph.
+const GRAPH_RECT: [f64; 4] = [10.0, 160.0, 360.0, 150.0];
+const GRAPH_SPACING: [f64; 2] = [30.0, 50.0];
+const GRAPH_COLUMNS: usize = 2;
+/// Distance between the baseline of the caption of a graph and its top, in
+/// pixels.
+const CAPTION_OFFSET: f64 = 8.0;
+const BORDER_RADIUS: f64 = 1.0;
+const LINE_RADIUS: f64 = 1.0;
+/// The most days graphed, the latest ones, which cover two in-game years.
+const MAX_DAYS_GRAPHED: usize = 96;
+
+/// What choosing an entry of the menu does.
+#[derive(Clone, Copy)]
+enum ReportsAction {
+    Back,
+}
+
+/// A line graph of one metric of the colony over the days graphed.
+struct Graph {
+    caption: Label,
+    /// Where the graph is drawn, in pixels.
+    rect: [f64; 4],
+    /// The points joined by the line of the graph, in pixels.
+    points: Vec<[f64; 2]>,
+}
+
+/// Graphs of the metrics of the colony over the days, so that the player can
+/// see how the colony is faring. The game waits beneath until the graphs are
+/// closed.
+pub struct ReportsScene {
+    config: Rc<Config>,
+    settings: Rc<RefCell<Settings>>,
+    localization: Rc<RefCell<Localization>>,
+    palette: Rc<RefCell<Palette>>,
+    audio: Rc<AudioManager>,
+    stats: Stats,
+    menu: Panel<ReportsAction>,
+    graphs: Vec<Graph>,
+}
+
+impl ReportsScene {
+    /// Creates a scene graphing `stats`, which are those of the game at the
+    /// time it was opened.
+    pub fn new(config: Rc<Config>, settings: Rc<RefCell<Settings>>, localization: Rc<RefCell<Localization>>, palette: Rc<RefCell<Palette>>, audio: Rc<AudioManager>, stats: Stats) -> Self {
+        let mut scene = ReportsScene {
+            config: config,
+            settings: settings,
+            localization: localization,
+            palette: palette,
+            audio: audio,
+            stats: stats,
+            menu: Panel::new(MENU_POSITION, MENU_WIDTH, MENU_PADDING, MENU_SPACING, MENU_BACKGROUND_COLOR),
+            graphs: Vec::new(),
+        };
+        scene.build_layout();
+        scene
+    }
+
+    /// Builds the menu and the graphs, scaled as set in the settings.
+    fn build_layout(&mut self) {
+        let localization = self.localization.borrow();
+        let palette = self.palette.borrow();
+        let settings = self.settings.borrow();
+        let font_size = settings.scale_font_size(self.config.font_size);
+        let days = self.stats.days();
+        let days = &days[days.len().saturating_sub(MAX_DAYS_GRAPHED)..];
+
+        let mut menu = Panel::new(settings.scale_position(MENU_POSITION), settings.scale_length(MENU_WIDTH), MENU_PADDING, settings.scale_length(MENU_SPACING), MENU_BACKGROUND_COLOR);
+        let range = match (days.first(), days.last()) {
+            (Some(first), Some(last)) => format!("{}: {} - {}", localization.reportsscene_days, first.day + 1, last.day + 1),
+            _ => localization.reportsscene_no_days.clone(),
+        };
+        for text in vec![localization.reportsscene_title.clone(), range] {
+            menu.push(Widget::Label(Label::new(text, palette.text, font_size, [0.0, 0.0])));
+        }
+        let label = Label::new(localization.reportsscene_back.as_str(), palette.text, font_size, [0.0, 0.0]);
+        menu.push(Widget::Button(Button::new(label, ReportsAction::Back, palette.button, palette.button_hover)));
+        self.menu = menu;
+
+        let metrics: Vec<(&str, Vec<u32>)> = vec![
+            (localization.reportsscene_population.as_str(), days.iter().map(|day| day.population).collect()),
+            (localization.reportsscene_items_mined.as_str(), days.iter().map(|day| day.items_mined).collect()),
+            (localization.reportsscene_food_stock.as_str(), days.iter().map(|day| day.food_stock).collect()),
+            (localization.reportsscene_deaths.as_str(), days.iter().map(|day| day.deaths).collect()),
+        ];
+        let graph_rect = settings.scale_rect(GRAPH_RECT);
+        let spacing = [settings.scale_length(GRAPH_SPACING[0]), settings.scale_length(GRAPH_SPACING[1])];
+        self.graphs = metrics.into_iter().enumerate().map(|(i, (name, values))| {
+            let (column, row) = ((i % GRAPH_COLUMNS) as f64, (i / GRAPH_COLUMNS) as f64);
+            let rect = [
+                graph_rect[0] + column * (graph_rect[2] + spacing[0]),
+                graph_rect[1] + row * (graph_rect[3] + spacing[1]),
+                graph_rect[2],
+                graph_rect[3],
+            ];
+            let peak = values.iter().cloned().max().unwrap_or(0);
+            let caption = match values.last() {
+                Some(latest) => format!("{}: {} ({} {})", name, latest, localization.reportsscene_peak, peak),
+                None => name.to_owned(),
+            };
+            let caption_position = [rect[0], rect[1] - settings.scale_length(CAPTION_OFFSET)];
+            Graph {
+                caption: Label::new(caption, palette.text, font_size, caption_position),
+                rect: rect,
+                points: graph_points(&values, cmp::max(peak, 1), rect),
+            }
+        }).collect();
+    }
+}
+
+/// Returns the points of a line graph of `values` spread across `rect`, in
+/// which `peak` reaches the top of the rectangle. A single value is graphed
+/// as a level line.
+fn graph_points(values: &[u32], peak: u32, rect: [f64; 4]) -> Vec<[f64; 2]> {
+    let y = |value: u32| rect[1] + rect[3] * (1.0 - value as f64 / peak as f64);
+    match values.len() {
+        0 => Vec::new(),
+        1 => vec![[rect[0], y(values[0])], [rect[0] + rect[2], y(values[0])]],
+        count => {
+            let step = rect[2] / (count - 1) as f64;
+            values.iter().enumerate()
+                .map(|(i, &value)| [rect[0] + i as f64 * step, y(value)])
+                .collect()
+        },
+    }
+}
+
+impl<B, E, G> Scene<B, E, G> for ReportsScene
+    where B: Backend + 'static,
+          E: GenericEvent,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn to_box(self) -> BoxedScene<B, E, G> {
+        Box::new(self)
+    }
+
+    fn render(&mut self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        use graphics::clear;
+
+        let palette = self.palette.borrow();
+        clear(palette.background, graphics);
+
+        Draw::<B, G>::draw(&self.menu, context, graphics, glyph_cache);
+
+        let border = graphics::Rectangle::new_border(palette.border, BORDER_RADIUS);
+        let line = graphics::Line::new(palette.graph, LINE_RADIUS);
+        for graph in &self.graphs {
+            Draw::<B, G>::draw(&graph.caption, context, graphics, glyph_cache);
+            border.draw(graph.rect, &context.draw_state, context.transform, graphics);
+            for segment in graph.points.windows(2) {
+                line.draw([segment[0][0], segment[0][1], segment[1][0], segment[1][1]], &context.draw_state, context.transform, graphics);
+            }
+        }
+    }
+
+    fn handle_event(&mut self, e: &E) -> Option<SceneCommand<B, E, G>> {
+        let mut action = self.menu.handle_event(e);
+
+        e.press(|button_type| {
+            if let Keyboard(key) = button_type {
+                match key {
+                    Key::Escape | Key::B => action = Some(ReportsAction::Back),
+                    _ => {},
+                }
+            }
+        });
+
+        match action {
+            Some(ReportsAction::Back) => {
+                self.audio.play_sound(audio::CLICK_SOUND);
+                Some(SceneCommand::PopScene)
+            },
+            None => None,
+        }
+    }
+
+    /// Rebuilds the menu and the graphs, in case the window was resized.
+    fn resize(&mut self, _width: f64, _height: f64) {
+        self.build_layout();
+    }
+}
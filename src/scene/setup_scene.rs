@@ -0,0 +1,425 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use cgmath::Point2;
+use piston::input::{GenericEvent, MouseCursorEvent, PressEvent, TextEvent};
+use piston::input::keyboard::Key;
+use piston::input::Button::{Keyboard, Mouse};
+use piston::input::MouseButton;
+use graphics;
+use rand;
+use rgframework::{BoxedScene, Button, Label, Panel, Scene, SceneCommand, Widget};
+use rgframework::backend::{Backend, Graphics};
+use rgframework::backend::graphics::{Context, CreateTexture, Format, TextureSettings};
+use rgframework::draw::Draw;
+use world::{MaterialRegistry, Overworld, TerrainMaterials, WorldGenConfig, WorldGenerator, CHUNK_SIZE, OVERWORLD_SIZE};
+
+use audio;
+use audio::AudioManager;
+use config::Config;
+use localization::Localization;
+use palette::Palette;
+use minimap;
+use scene::{GameScene, MenuScene};
+use scene::options_scene::next_value;
+use scripting::ScriptHost;
+use settings::Settings;
+use tile_renderer::TileRenderer;
+
+const MENU_POSITION: [f64; 2] = [10.0, 10.0];
+const MENU_WIDTH: f64 = 300.0;
+const MENU_PADDING: f64 = 0.0;
+const MENU_SPACING: f64 = 6.0;
+const MENU_BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.0];
+
+/// Where the map of the overworld is drawn, and its size, in pixels, with a
+/// pixel for each cell.
+const OVERWORLD_RECT: [f64; 4] = [320.0, 10.0, 256.0, 256.0];
+/// Where the preview of the terrain around the embark site is drawn, and its
+/// size, in pixels.
+const PREVIEW_RECT: [f64; 4] = [320.0, 276.0, 256.0, 256.0];
+/// The number of pixels of the preview texture along each side, and the
+/// number of tiles between the columns sampled for neighbouring pixels.
+const PREVIEW_PIXELS: i32 = 128;
+const PREVIEW_SCALE: i32 = 2;
+const BORDER_RADIUS: f64 = 1.0;
+/// The most digits a seed can be typed with.
+const MAX_SEED_DIGITS: usize = 10;
+
+/// The values offered for each parameter of the world generator.
+const OCTAVES: [u32; 6] = [1, 2, 3, 4, 6, 8];
+/// Terrain scales, in tiles per cycle of the first octave, which is the
+/// inverse of its frequency.
+const SCALES: [f64; 5] = [16.0, 32.0, 64.0, 128.0, 256.0];
+const AMPLITUDES: [f64; 5] = [0.5, 0.75, 1.0, 1.5, 2.0];
+const SEA_LEVELS: [i32; 5] = [0, 7, 14, 21, 28];
+const CAVE_DENSITIES: [f64; 4] = [0.05, 0.1, 0.15, 0.2];
+
+/// What choosing an entry of the menu does.
+#[derive(Clone, Copy)]
+enum SetupAction {
+    /// Waits for a seed to be typed.
+    Seed,
+    RandomizeSeed,
+    Octaves,
+    Scale,
+    Amplitude,
+    SeaLevel,
+    Caves,
+    CaveDensity,
+    Embark,
+    Back,
+}
+
+/// Sets up a new world before embarking on it: its seed, the parameters of
+/// the world generator, and the site of the colony, with a map of the
+/// overworld and a preview of the terrain around the site.
+///
+/// The site is a cell of the overworld, picked by clicking the map or with
+/// the arrow keys. The colony starts in the chunk column at its center.
+///
+/// The map and the preview are drawn from the height map and biomes of the
+/// world generator alone, so that they are quick enough to redraw whenever a
+/// parameter changes.
+pub struct SetupScene<B>
+    where B: Backend,
+{
+    config: Rc<Config>,
+    settings: Rc<RefCell<Settings>>,
+    localization: Rc<RefCell<Localization>>,
+    palette: Rc<RefCell<Palette>>,
+    materials: Rc<MaterialRegistry>,
+    tile_renderer: Rc<TileRenderer<B>>,
+    audio: Rc<AudioManager>,
+    scripts: Rc<ScriptHost>,
+    seed: u32,
+    world_gen: WorldGenConfig,
+    /// The seed being typed, while waiting for one to be.
+    typed_seed: Option<String>,
+    /// The cell of the overworld picked as the site of the colony.
+    embark_cell: Point2<i32>,
+    mouse_pos: [f64; 2],
+    menu: Panel<SetupAction>,
+    overworld_map: Option<B::Texture>,
+    preview: Option<B::Texture>,
+}
+
+impl<B> SetupScene<B>
+    where B: Backend,
+{
+    pub fn new(config: Rc<Config>, settings: Rc<RefCell<Settings>>, localization: Rc<RefCell<Localization>>, palette: Rc<RefCell<Palette>>, materials: Rc<MaterialRegistry>, tile_renderer: Rc<TileRenderer<B>>, audio: Rc<AudioManager>, scripts: Rc<ScriptHost>) -> Self {
+        let world_gen = config.world_gen;
+        let mut scene = SetupScene {
+            config: config,
+            settings: settings,
+            localization: localization,
+            palette: palette,
+            materials: materials,
+            tile_renderer: tile_renderer,
+            audio: audio,
+            scripts: scripts,
+            seed: rand::random(),
+            world_gen: world_gen,
+            typed_seed: None,
+            embark_cell: Point2::new(OVERWORLD_SIZE / 2, OVERWORLD_SIZE / 2),
+            mouse_pos: [0.0, 0.0],
+            menu: Panel::new(MENU_POSITION, MENU_WIDTH, MENU_PADDING, MENU_SPACING, MENU_BACKGROUND_COLOR),
+            overworld_map: None,
+            preview: None,
+        };
+        scene.build_menu();
+        scene.draw_overworld_map();
+        scene.draw_preview();
+        scene
+    }
+
+    /// Carries out the entry of the menu chosen by `action`, returning the
+    /// scene to switch to, if any.
+    fn perform<E, G>(&mut self, action: SetupAction) -> Option<SceneCommand<B, E, G>>
+        where B: 'static,
+              E: GenericEvent,
+              G: Graphics<Texture=B::Texture>,
+    {
+        self.audio.play_sound(audio::CLICK_SOUND);
+        match action {
+            SetupAction::Seed => {
+                self.typed_seed = Some(String::new());
+                self.build_menu();
+                return None;
+            },
+            SetupAction::RandomizeSeed => self.seed = rand::random(),
+            SetupAction::Octaves => self.world_gen.octaves = next_value(&OCTAVES, self.world_gen.octaves),
+            SetupAction::Scale => self.world_gen.frequency = 1.0 / next_value(&SCALES, 1.0 / self.world_gen.frequency),
+            SetupAction::Amplitude => self.world_gen.amplitude = next_value(&AMPLITUDES, self.world_gen.amplitude),
+            SetupAction::SeaLevel => self.world_gen.sea_level = next_value(&SEA_LEVELS, self.world_gen.sea_level),
+            SetupAction::Caves => self.world_gen.caves = !self.world_gen.caves,
+            SetupAction::CaveDensity => self.world_gen.cave_density = next_value(&CAVE_DENSITIES, self.world_gen.cave_density),
+            SetupAction::Embark => {
+                let scene = GameScene::new(self.config.clone(), self.settings.clone(), self.localization.clone(), self.palette.clone(), self.materials.clone(), self.tile_renderer.clone(), self.audio.clone(), self.scripts.clone(), self.seed, self.world_gen, Overworld::cell_center(self.embark_cell));
+                return Some(SceneCommand::SetScene(scene.to_box()));
+            },
+            SetupAction::Back => {
+                let scene = MenuScene::new(self.config.clone(), self.settings.clone(), self.localization.clone(), self.palette.clone(), self.materials.clone(), self.tile_renderer.clone(), self.audio.clone(), self.scripts.clone());
+                return Some(SceneCommand::SetScene(scene.to_box()));
+            },
+        }
+
+        self.build_menu();
+        self.draw_overworld_map();
+        self.draw_preview();
+        None
+    }
+
+    /// Picks `cell` as the embark site, unless it lies outside of the
+    /// overworld.
+    fn select_cell(&mut self, cell: Point2<i32>) {
+        if !Overworld::contains(cell) || cell == self.embark_cell {
+            return;
+        }
+        self.embark_cell = cell;
+        self.build_menu();
+        self.draw_preview();
+    }
+
+    /// Returns the cell of the overworld drawn at `pos` on the map, if any.
+    fn cell_at(&self, pos: [f64; 2]) -> Option<Point2<i32>> {
+        let rect = self.settings.borrow().scale_rect(OVERWORLD_RECT);
+        let x = ((pos[0] - rect[0]) / rect[2] * OVERWORLD_SIZE as f64).floor();
+        let z = ((pos[1] - rect[1]) / rect[3] * OVERWORLD_SIZE as f64).floor();
+        let cell = Point2::new(x as i32, z as i32);
+        if x >= 0.0 && z >= 0.0 && Overworld::contains(cell) { Some(cell) } else { None }
+    }
+
+    /// Handles a key pressed or text typed while waiting for a seed to be
+    /// typed. Enter sets the seed typed, if any, and Escape keeps the seed as
+    /// it was.
+    fn handle_seed_event<E>(&mut self, e: &E)
+        where E: GenericEvent,
+    {
+        let mut typed_seed = match self.typed_seed.take() {
+            Some(typed_seed) => typed_seed,
+            None => return,
+        };
+        let mut done = false;
+
+        e.text(|text| {
+            for c in text.chars().filter(|c| c.is_digit(10)) {
+                if typed_seed.len() < MAX_SEED_DIGITS {
+                    typed_seed.push(c);
+                }
+            }
+        });
+        e.press(|button_type| {
+            if let Keyboard(key) = button_type {
+                match key {
+                    Key::Backspace => { typed_seed.pop(); },
+                    Key::Return => {
+                        // Seeds too large for a `u32` are ignored.
+                        if let Ok(seed) = typed_seed.parse() {
+                            self.seed = seed;
+                        }
+                        done = true;
+                    },
+                    Key::Escape => done = true,
+                    _ => {},
+                }
+            }
+        });
+
+        if !done {
+            self.typed_seed = Some(typed_seed);
+        }
+        self.build_menu();
+        if done {
+            self.draw_overworld_map();
+            self.draw_preview();
+        }
+    }
+
+    /// Builds the entries of the menu from the seed and parameters chosen.
+    fn build_menu(&mut self) {
+        let localization = self.localization.borrow();
+        let palette = self.palette.borrow();
+        let world_gen = &self.world_gen;
+        let settings = self.settings.borrow();
+        let font_size = settings.scale_font_size(self.config.font_size);
+        let seed = match self.typed_seed {
+            Some(ref typed_seed) => format!("{}_ ({})", typed_seed, localization.setupscene_type_seed),
+            None => self.seed.to_string(),
+        };
+        let caves = if world_gen.caves { &localization.optionsscene_on } else { &localization.optionsscene_off };
+
+        let mut menu = Panel::new(settings.scale_position(MENU_POSITION), settings.scale_length(MENU_WIDTH), MENU_PADDING, settings.scale_length(MENU_SPACING), MENU_BACKGROUND_COLOR);
+        menu.push(Widget::Label(Label::new(localization.setupscene_title.clone(), palette.text, font_size, [0.0, 0.0])));
+        for (text, action) in vec![
+            (format!("{}: {}", localization.setupscene_seed, seed), SetupAction::Seed),
+            (localization.setupscene_randomize.clone(), SetupAction::RandomizeSeed),
+            (format!("{}: {}", localization.setupscene_octaves, world_gen.octaves), SetupAction::Octaves),
+            (format!("{}: {:.0}", localization.setupscene_scale, 1.0 / world_gen.frequency), SetupAction::Scale),
+            (format!("{}: {:.0}%", localization.setupscene_amplitude, world_gen.amplitude * 100.0), SetupAction::Amplitude),
+            (format!("{}: {}", localization.setupscene_sea_level, world_gen.sea_level), SetupAction::SeaLevel),
+            (format!("{}: {}", localization.setupscene_caves, caves), SetupAction::Caves),
+            (format!("{}: {:.2}", localization.setupscene_cave_density, world_gen.cave_density), SetupAction::CaveDensity),
+        ] {
+            let label = Label::new(text, palette.text, font_size, [0.0, 0.0]);
+            menu.push(Widget::Button(Button::new(label, action, palette.button, palette.button_hover)));
+        }
+        let site = format!("{}: {}, {}", localization.setupscene_site, self.embark_cell.x, self.embark_cell.y);
+        menu.push(Widget::Label(Label::new(site, palette.text, font_size, [0.0, 0.0])));
+        for (text, action) in vec![
+            (localization.setupscene_embark.clone(), SetupAction::Embark),
+            (localization.setupscene_back.clone(), SetupAction::Back),
+        ] {
+            let label = Label::new(text, palette.text, font_size, [0.0, 0.0]);
+            menu.push(Widget::Button(Button::new(label, action, palette.button, palette.button_hover)));
+        }
+        self.menu = menu;
+    }
+
+    /// Generates the overworld and draws it into the texture of the map, each
+    /// cell colored as on the preview.
+    fn draw_overworld_map(&mut self) {
+        let generator = WorldGenerator::new(self.seed, self.world_gen, &self.materials);
+        let terrain = TerrainMaterials::new(&self.materials);
+        let overworld = Overworld::generate(&generator);
+        let palette = self.palette.borrow();
+
+        let mut pixels = Vec::with_capacity((OVERWORLD_SIZE * OVERWORLD_SIZE * 4) as usize);
+        for z in 0..OVERWORLD_SIZE {
+            for x in 0..OVERWORLD_SIZE {
+                let cell = overworld.get(Point2::new(x, z)).expect("the cell lies within the overworld");
+                let (material, y) = if cell.elevation < self.world_gen.sea_level {
+                    (terrain.water, self.world_gen.sea_level)
+                } else {
+                    (terrain.surface(cell.biome), cell.elevation)
+                };
+                let color = self.materials.get(material).map_or(palette.unrevealed, |material| palette.material_color(material));
+                pixels.extend_from_slice(&minimap::surface_color(color, y));
+            }
+        }
+
+        let size = [OVERWORLD_SIZE as u32, OVERWORLD_SIZE as u32];
+        self.overworld_map = <B::Texture as CreateTexture<()>>::create(&mut (), Format::Rgba8, &pixels, size, &TextureSettings::new()).ok();
+    }
+
+    /// Draws the surface around the embark site into the preview texture,
+    /// colored by the material covering it and shaded by its height, as on
+    /// the minimap.
+    fn draw_preview(&mut self) {
+        let generator = WorldGenerator::new(self.seed, self.world_gen, &self.materials);
+        let terrain = TerrainMaterials::new(&self.materials);
+        let palette = self.palette.borrow();
+        let half = PREVIEW_PIXELS / 2;
+        let column = Overworld::cell_center(self.embark_cell);
+        let (origin_x, origin_z) = (column.x * CHUNK_SIZE as i32, column.y * CHUNK_SIZE as i32);
+
+        let mut pixels = Vec::with_capacity((PREVIEW_PIXELS * PREVIEW_PIXELS * 4) as usize);
+        for pixel_z in 0..PREVIEW_PIXELS {
+            for pixel_x in 0..PREVIEW_PIXELS {
+                let x = origin_x + (pixel_x - half) * PREVIEW_SCALE;
+                let z = origin_z + (pixel_z - half) * PREVIEW_SCALE;
+                let height = generator.height_at(x, z);
+
+                // Whatever lies below the sea level is under water, which is
+                // drawn at the sea level.
+                let (material, y) = if height < self.world_gen.sea_level {
+                    (terrain.water, self.world_gen.sea_level)
+                } else {
+                    (terrain.surface(generator.biome_at(x, z)), height)
+                };
+                let color = self.materials.get(material).map_or(palette.unrevealed, |material| palette.material_color(material));
+                pixels.extend_from_slice(&minimap::surface_color(color, y));
+            }
+        }
+
+        let size = [PREVIEW_PIXELS as u32, PREVIEW_PIXELS as u32];
+        self.preview = <B::Texture as CreateTexture<()>>::create(&mut (), Format::Rgba8, &pixels, size, &TextureSettings::new()).ok();
+    }
+}
+
+impl<B, E, G> Scene<B, E, G> for SetupScene<B>
+    where B: Backend + 'static,
+          E: GenericEvent,
+          G: Graphics<Texture=B::Texture>,
+{
+    fn to_box(self) -> BoxedScene<B, E, G> {
+        Box::new(self)
+    }
+
+    fn render(&mut self, context: &Context, graphics: &mut G, glyph_cache: &mut B::CharacterCache) {
+        use graphics::clear;
+
+        let palette = self.palette.borrow();
+        clear(palette.background, graphics);
+
+        Draw::<B, G>::draw(&self.menu, context, graphics, glyph_cache);
+
+        let (overworld_rect, preview_rect) = {
+            let settings = self.settings.borrow();
+            (settings.scale_rect(OVERWORLD_RECT), settings.scale_rect(PREVIEW_RECT))
+        };
+        if let Some(ref overworld_map) = self.overworld_map {
+            graphics::Image::new().rect(overworld_rect).draw(overworld_map, &context.draw_state, context.transform, graphics);
+        }
+        graphics::Rectangle::new_border(palette.border, BORDER_RADIUS).draw(overworld_rect, &context.draw_state, context.transform, graphics);
+        let cell_size = [overworld_rect[2] / OVERWORLD_SIZE as f64, overworld_rect[3] / OVERWORLD_SIZE as f64];
+        let site = [
+            overworld_rect[0] + self.embark_cell.x as f64 * cell_size[0] - BORDER_RADIUS,
+            overworld_rect[1] + self.embark_cell.y as f64 * cell_size[1] - BORDER_RADIUS,
+            cell_size[0] + 2.0 * BORDER_RADIUS,
+            cell_size[1] + 2.0 * BORDER_RADIUS,
+        ];
+        graphics::Rectangle::new_border(palette.embark_area, BORDER_RADIUS).draw(site, &context.draw_state, context.transform, graphics);
+
+        if let Some(ref preview) = self.preview {
+            graphics::Image::new().rect(preview_rect).draw(preview, &context.draw_state, context.transform, graphics);
+        }
+        graphics::Rectangle::new_border(palette.border, BORDER_RADIUS).draw(preview_rect, &context.draw_state, context.transform, graphics);
+
+        // The world starts out generated this many tiles around the embark
+        // site, at the center of the preview.
+        let extent = self.config.initial_world_size as f64 * CHUNK_SIZE as f64;
+        let scale = preview_rect[2] / (PREVIEW_PIXELS * PREVIEW_SCALE) as f64;
+        let center = [preview_rect[0] + preview_rect[2] / 2.0, preview_rect[1] + preview_rect[3] / 2.0];
+        let embark_area = [center[0] - extent * scale, center[1] - extent * scale, 2.0 * extent * scale, 2.0 * extent * scale];
+        graphics::Rectangle::new_border(palette.embark_area, BORDER_RADIUS).draw(embark_area, &context.draw_state, context.transform, graphics);
+    }
+
+    fn handle_event(&mut self, e: &E) -> Option<SceneCommand<B, E, G>> {
+        if self.typed_seed.is_some() {
+            self.handle_seed_event(e);
+            return None;
+        }
+
+        e.mouse_cursor(|x, y| self.mouse_pos = [x, y]);
+
+        let mut action = self.menu.handle_event(e);
+
+        let mut cell = None;
+        e.press(|button_type| {
+            let site = self.embark_cell;
+            match button_type {
+                Keyboard(Key::S) => action = Some(SetupAction::Seed),
+                Keyboard(Key::R) => action = Some(SetupAction::RandomizeSeed),
+                Keyboard(Key::E) | Keyboard(Key::Return) => action = Some(SetupAction::Embark),
+                Keyboard(Key::B) | Keyboard(Key::Escape) => action = Some(SetupAction::Back),
+                Keyboard(Key::Left) => cell = Some(Point2::new(site.x - 1, site.y)),
+                Keyboard(Key::Right) => cell = Some(Point2::new(site.x + 1, site.y)),
+                Keyboard(Key::Up) => cell = Some(Point2::new(site.x, site.y - 1)),
+                Keyboard(Key::Down) => cell = Some(Point2::new(site.x, site.y + 1)),
+                Mouse(MouseButton::Left) => cell = self.cell_at(self.mouse_pos),
+                _ => {},
+            }
+        });
+        if let Some(cell) = cell {
+            self.select_cell(cell);
+        }
+
+        action.and_then(|action| self.perform(action))
+    }
+
+    /// Rebuilds the menu, in case the UI scale was changed.
+    fn resume(&mut self) {
+        self.build_menu();
+    }
+}
@@ -0,0 +1,51 @@
+use std::fs;
+use std::path::PathBuf;
+
+use gl;
+use gl::types::{GLint, GLvoid};
+use image;
+
+use error::ColonizeResult;
+use paths;
+
+/// Prefixes of the names of the images saved when F12 is pressed, and of
+/// those captured for a timelapse.
+pub const SCREENSHOT_PREFIX: &'static str = "screenshot";
+pub const TIMELAPSE_PREFIX: &'static str = "timelapse";
+
+const SCREENSHOTS_DIR: &'static str = "screenshots/";
+const BYTES_PER_PIXEL: usize = 4;
+
+/// Saves the contents of the back buffer, which is `width` by `height`
+/// pixels, as a PNG image named after `prefix` and the current time in the
+/// screenshots directory. Returns the path of the image.
+///
+/// Must be called after the frame is drawn, but before the buffers are
+/// swapped.
+pub fn capture(width: u32, height: u32, prefix: &str) -> ColonizeResult<PathBuf> {
+    let mut pixels = vec![0u8; width as usize * height as usize * BYTES_PER_PIXEL];
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            width as GLint,
+            height as GLint,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr() as *mut GLvoid);
+    }
+
+    // OpenGL reads the rows from the bottom of the window up.
+    let row_length = width as usize * BYTES_PER_PIXEL;
+    let mut image = Vec::with_capacity(pixels.len());
+    for row in pixels.chunks(row_length).rev() {
+        image.extend_from_slice(row);
+    }
+
+    let dir = paths::data_dir().join(SCREENSHOTS_DIR);
+    try!(fs::create_dir_all(&dir));
+    let path = dir.join(format!("{}-{}.png", prefix, paths::timestamp()));
+    try!(image::save_buffer(&path, &image, width, height, image::ColorType::RGBA(8)));
+    Ok(path)
+}
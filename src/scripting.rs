@@ -0,0 +1,477 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::ptr;
+use std::rc::Rc;
+
+use cgmath::Point3;
+use hlua::{self, Lua, LuaFunction};
+use serde_json;
+use world::{EventKind, ItemKind, JobKind, Material, Season, Tile, World, WorldEvent, CHUNK_SIZE, MAX_FLUID_LEVEL};
+
+use error::{ColonizeError, ColonizeResult};
+use mods::ModPack;
+
+/// The script run when a mod is loaded, within the directory of the mod.
+const MAIN_SCRIPT: &'static str = "main.lua";
+/// The prefixes of the names of the global functions handling the console
+/// commands and finishing the jobs defined by mods.
+const COMMAND_PREFIX: &'static str = "command_";
+const JOB_PREFIX: &'static str = "job_";
+/// The name of the global function decorating newly generated chunks.
+const DECORATE_HOOK: &'static str = "decorate";
+/// The name of the global function called as a season begins.
+const SEASON_HOOK: &'static str = "season";
+
+/// The kinds of the events of the world handed to the hooks of mods by
+/// `ScriptHost::run_hooks`.
+pub const HOOK_EVENTS: &'static [EventKind] = &[EventKind::JobFinished, EventKind::SeasonChanged];
+
+/// Something a mod has asked of the game through the scripting API.
+///
+/// Requests are only carried out once the script which made them returns, so
+/// that scripts never hold on to the world while they run.
+enum ScriptRequest {
+    AddMaterial(String),
+    RegisterCommand(String, String),
+    RegisterJob(String),
+    SetMaterial(Point3<i32>, String),
+    SpawnItem(String, Point3<i32>),
+    DesignateDig(Point3<i32>),
+    QueueJob(String, Point3<i32>),
+    Print(String),
+}
+
+/// A line for the console, printed by a mod or reporting an error in one.
+pub enum ScriptMessage {
+    Print(String),
+    /// An error in the named mod.
+    Error(String, String),
+}
+
+/// The state shared between the game and the API functions of every mod.
+struct ScriptContext {
+    /// The world which the script being run may query, while it is run by
+    /// `Mod::call`.
+    world: Cell<*const World>,
+    /// The requests made by the script being run.
+    requests: RefCell<Vec<ScriptRequest>>,
+}
+
+impl ScriptContext {
+    /// Returns `f` applied to the world, or `default` if no world can be
+    /// queried right now (such as while a mod is being loaded).
+    fn query<T, F>(&self, default: T, f: F) -> T
+        where F: FnOnce(&World) -> T,
+    {
+        let world = self.world.get();
+        if world.is_null() {
+            return default;
+        }
+        // The pointer is only set by `Mod::call`, which holds a borrow of the
+        // world until it has cleared it again.
+        f(unsafe { &*world })
+    }
+
+    fn request(&self, request: ScriptRequest) {
+        self.requests.borrow_mut().push(request);
+    }
+}
+
+/// Clears the world of a `ScriptContext` when dropped, even if the script
+/// panicked.
+struct ClearWorld<'a>(&'a ScriptContext);
+
+impl<'a> Drop for ClearWorld<'a> {
+    fn drop(&mut self) {
+        self.0.world.set(ptr::null());
+    }
+}
+
+/// A mod, loaded from its own directory into its own Lua state, so that an
+/// error in one mod never affects another.
+struct Mod {
+    name: String,
+    lua: RefCell<Lua<'static>>,
+}
+
+impl Mod {
+    /// Creates the Lua state of the mod, with the scripting API available to
+    /// it, and runs its main script.
+    fn load(name: String, path: &PathBuf, context: &Rc<ScriptContext>) -> ColonizeResult<Self> {
+        let code = try!(::read_file_to_string(&path.join(MAIN_SCRIPT)));
+
+        let mut lua = Lua::new();
+        // Only the libraries which cannot reach outside of the game are
+        // opened.
+        lua.open_base();
+        lua.open_math();
+        lua.open_string();
+        lua.open_table();
+        register_api(&mut lua, context);
+
+        let result: Result<(), _> = lua.execute(&code);
+        try!(result.map_err(|e| ColonizeError::Script(format!("{:?}", e))));
+
+        Ok(Mod {
+            name: name,
+            lua: RefCell::new(lua),
+        })
+    }
+
+    /// Calls the global function `name` of the mod with `args`, if it is
+    /// defined, letting it query `world`. Returns the requests it made, or a
+    /// description of the error it raised.
+    fn call<A>(&self, context: &ScriptContext, world: &World, name: &str, args: A) -> Result<Vec<ScriptRequest>, String>
+        where A: for<'a, 'b> hlua::Push<&'b mut LuaFunction<hlua::PushGuard<&'a mut Lua<'static>>>>,
+    {
+        let mut lua = self.lua.borrow_mut();
+        let mut function: LuaFunction<_> = match lua.get(name) {
+            Some(function) => function,
+            None => return Ok(Vec::new()),
+        };
+
+        context.world.set(world as *const World);
+        let _clear = ClearWorld(context);
+        let result: Result<(), _> = function.call_with_args(args);
+        let requests = ::std::mem::replace(&mut *context.requests.borrow_mut(), Vec::new());
+        match result {
+            Ok(()) => Ok(requests),
+            Err(e) => Err(format!("{:?}", e)),
+        }
+    }
+}
+
+/// Adds the `colonize` table, through which mods query and change the game,
+/// to the globals of `lua`.
+fn register_api(lua: &mut Lua<'static>, context: &Rc<ScriptContext>) {
+    let mut api = lua.empty_array("colonize");
+    api.set("CHUNK_SIZE", CHUNK_SIZE as i32);
+
+    // Queries, answered from the world while a hook is run.
+    let c = context.clone();
+    api.set("material_at", hlua::function3(move |x: i32, y: i32, z: i32| -> Option<String> {
+        c.query(None, |world| {
+            let material = world.area.get_tile(&Point3::new(x, y, z)).material;
+            world.materials().get(material).map(|material| material.name.clone())
+        })
+    }));
+    let c = context.clone();
+    api.set("surface_height", hlua::function2(move |x: i32, z: i32| -> i32 {
+        c.query(0, |world| world.area.surface_height(x, z))
+    }));
+    let c = context.clone();
+    api.set("day", hlua::function0(move || -> u32 {
+        c.query(0, |world| world.calendar().days() as u32)
+    }));
+    let c = context.clone();
+    api.set("hour", hlua::function0(move || -> u32 {
+        c.query(0, |world| world.calendar().hour() as u32)
+    }));
+
+    // Definitions, accepted while the main script of the mod is run.
+    let c = context.clone();
+    api.set("add_material", hlua::function1(move |json: String| {
+        c.request(ScriptRequest::AddMaterial(json));
+    }));
+    let c = context.clone();
+    api.set("register_command", hlua::function2(move |name: String, usage: String| {
+        c.request(ScriptRequest::RegisterCommand(name, usage));
+    }));
+    let c = context.clone();
+    api.set("register_job", hlua::function1(move |name: String| {
+        c.request(ScriptRequest::RegisterJob(name));
+    }));
+
+    // Changes to the world, carried out once the hook returns.
+    let c = context.clone();
+    api.set("set_material", hlua::function4(move |x: i32, y: i32, z: i32, name: String| {
+        c.request(ScriptRequest::SetMaterial(Point3::new(x, y, z), name));
+    }));
+    let c = context.clone();
+    api.set("spawn_item", hlua::function4(move |kind: String, x: i32, y: i32, z: i32| {
+        c.request(ScriptRequest::SpawnItem(kind, Point3::new(x, y, z)));
+    }));
+    let c = context.clone();
+    api.set("designate_dig", hlua::function3(move |x: i32, y: i32, z: i32| {
+        c.request(ScriptRequest::DesignateDig(Point3::new(x, y, z)));
+    }));
+    let c = context.clone();
+    api.set("queue_job", hlua::function4(move |name: String, x: i32, y: i32, z: i32| {
+        c.request(ScriptRequest::QueueJob(name, Point3::new(x, y, z)));
+    }));
+    let c = context.clone();
+    api.set("print", hlua::function1(move |text: String| {
+        c.request(ScriptRequest::Print(text));
+    }));
+}
+
+/// Loads the scripts of mods, and runs their hooks.
+///
+/// The script of a mod is the `main.lua` file in its directory, which is run
+/// when the mod is loaded. It can define materials, console commands and jobs
+/// through the `colonize` table, and the following global functions, which
+/// are called by the game:
+///
+/// * `decorate(x, y, z)`, whenever the chunk whose lowest corner is at the
+///   specified tile has been generated;
+/// * `command_<name>(args)`, when the console command `name` registered by
+///   the mod is entered, with its arguments as an array of strings;
+/// * `job_<name>(x, y, z)`, when a colonist has finished the job `name`
+///   registered by the mod at the specified tile.
+///
+/// Mods are loaded in their load order, and a console command or job defined
+/// by a mod replaces any of the same name defined by a mod loaded before it.
+/// A mod whose script cannot be loaded is skipped, and errors raised by the
+/// hooks of a mod are reported without stopping the mod or affecting any
+/// other.
+pub struct ScriptHost {
+    mods: Vec<Mod>,
+    context: Rc<ScriptContext>,
+    /// The console commands defined by mods, with their usage, and the index
+    /// of the mod defining each.
+    commands: HashMap<String, (String, usize)>,
+    /// The jobs defined by mods, and the index of the mod defining each.
+    jobs: HashMap<String, usize>,
+    /// The materials defined by each mod, by the name of the mod.
+    materials: HashMap<String, Vec<Material>>,
+}
+
+impl ScriptHost {
+    /// Loads the script of every mod in `mod_packs` which has one, in the
+    /// order they are given.
+    pub fn load(mod_packs: &[ModPack]) -> Self {
+        let mut host = ScriptHost {
+            mods: Vec::new(),
+            context: Rc::new(ScriptContext {
+                world: Cell::new(ptr::null()),
+                requests: RefCell::new(Vec::new()),
+            }),
+            commands: HashMap::new(),
+            jobs: HashMap::new(),
+            materials: HashMap::new(),
+        };
+
+        for mod_pack in mod_packs {
+            if !mod_pack.path.join(MAIN_SCRIPT).exists() {
+                continue;
+            }
+            let name = mod_pack.name.clone();
+            let loaded = Mod::load(name.clone(), &mod_pack.path, &host.context);
+            let requests = ::std::mem::replace(&mut *host.context.requests.borrow_mut(), Vec::new());
+            match loaded {
+                Ok(loaded) => {
+                    let index = host.mods.len();
+                    host.mods.push(loaded);
+                    host.define(index, requests);
+                },
+                Err(e) => {
+                    warn!("Failed to load mod {}: {}", name, e);
+                },
+            }
+        }
+
+        host
+    }
+
+    /// Records the materials, console commands and jobs requested by the mod
+    /// at `index` while it was loaded.
+    fn define(&mut self, index: usize, requests: Vec<ScriptRequest>) {
+        let name = self.mods[index].name.clone();
+        let mut materials = Vec::new();
+        for request in requests {
+            match request {
+                ScriptRequest::AddMaterial(json) => match serde_json::from_str::<Material>(&json) {
+                    Ok(material) => materials.push(material),
+                    Err(e) => warn!("Invalid material in mod {}: {}", name, e),
+                },
+                ScriptRequest::RegisterCommand(command, usage) => {
+                    if let Some((_, other)) = self.commands.insert(command.clone(), (usage, index)) {
+                        self.report_override(other, &name, "console command", &command);
+                    }
+                },
+                ScriptRequest::RegisterJob(job) => {
+                    if let Some(other) = self.jobs.insert(job.clone(), index) {
+                        self.report_override(other, &name, "job", &job);
+                    }
+                },
+                _ => warn!("Mod {} changed the world while loading, which is ignored", name),
+            }
+        }
+        if !materials.is_empty() {
+            self.materials.insert(name, materials);
+        }
+    }
+
+    /// Reports that the mod `name` replaced the `kind` named `defined`, which
+    /// was defined by the mod at `other`.
+    fn report_override(&self, other: usize, name: &str, kind: &str, defined: &str) {
+        let other = &self.mods[other].name;
+        if other != name {
+            info!("Mod {} overrides the {} {} of mod {}", name, kind, defined, other);
+        }
+    }
+
+    /// Takes the materials defined by each mod, by the name of the mod, so
+    /// that they can be added to the material registry.
+    pub fn take_materials(&mut self) -> HashMap<String, Vec<Material>> {
+        ::std::mem::replace(&mut self.materials, HashMap::new())
+    }
+
+    /// Returns the usage of every console command defined by a mod.
+    pub fn command_usages(&self) -> Vec<String> {
+        let mut usages: Vec<String> = self.commands.values().map(|&(ref usage, _)| usage.clone()).collect();
+        usages.sort();
+        usages
+    }
+
+    /// Runs the console command `name` with `args`, returning `None` if no
+    /// mod defines it.
+    pub fn run_command(&self, name: &str, args: Vec<String>, world: &mut World) -> Option<Vec<ScriptMessage>> {
+        let index = match self.commands.get(name) {
+            Some(&(_, index)) => index,
+            None => return None,
+        };
+
+        let mut messages = Vec::new();
+        let function = format!("{}{}", COMMAND_PREFIX, name);
+        let result = self.mods[index].call(&self.context, world, &function, args);
+        self.apply(index, result, world, &mut messages);
+        Some(messages)
+    }
+
+    /// Lets the mods decorate the chunks of `world` generated since the last
+    /// call, and know of the jobs they defined which have been finished and
+    /// of the seasons which have begun, among `events`.
+    pub fn run_hooks(&self, events: &[WorldEvent], world: &mut World) -> Vec<ScriptMessage> {
+        let mut messages = Vec::new();
+        let generated = world.area.take_generated_chunks();
+        if !generated.is_empty() {
+            messages.extend(self.decorate(&generated, world));
+        }
+        for event in events {
+            match *event {
+                WorldEvent::JobFinished(_, JobKind::Custom(index), pos) => {
+                    let name = match world.custom_job_name(index) {
+                        Some(name) => name.clone(),
+                        None => continue,
+                    };
+                    messages.extend(self.finish_job(&name, pos.to_point(), world));
+                },
+                WorldEvent::SeasonChanged(season) => {
+                    messages.extend(self.begin_season(season, world));
+                },
+                _ => {},
+            }
+        }
+        messages
+    }
+
+    /// Lets every mod decorate the chunks at `chunks`, which have just been
+    /// generated.
+    pub fn decorate(&self, chunks: &[Point3<i32>], world: &mut World) -> Vec<ScriptMessage> {
+        let mut messages = Vec::new();
+        for pos in chunks {
+            let size = CHUNK_SIZE as i32;
+            let args = (pos.x * size, pos.y * size, pos.z * size);
+            for index in 0..self.mods.len() {
+                let result = self.mods[index].call(&self.context, world, DECORATE_HOOK, args);
+                self.apply(index, result, world, &mut messages);
+            }
+        }
+        messages
+    }
+
+    /// Lets the mod which defines the job `name` know that it has been
+    /// finished at `pos`.
+    pub fn finish_job(&self, name: &str, pos: Point3<i32>, world: &mut World) -> Vec<ScriptMessage> {
+        let mut messages = Vec::new();
+        if let Some(&index) = self.jobs.get(name) {
+            let function = format!("{}{}", JOB_PREFIX, name);
+            let result = self.mods[index].call(&self.context, world, &function, (pos.x, pos.y, pos.z));
+            self.apply(index, result, world, &mut messages);
+        }
+        messages
+    }
+
+    /// Lets every mod know that `season` has begun.
+    pub fn begin_season(&self, season: Season, world: &mut World) -> Vec<ScriptMessage> {
+        let mut messages = Vec::new();
+        let name = match season {
+            Season::Spring => "spring",
+            Season::Summer => "summer",
+            Season::Autumn => "autumn",
+            Season::Winter => "winter",
+        };
+        for index in 0..self.mods.len() {
+            let result = self.mods[index].call(&self.context, world, SEASON_HOOK, name);
+            self.apply(index, result, world, &mut messages);
+        }
+        messages
+    }
+
+    /// Carries out the requests made by a hook of the mod at `index`, or
+    /// reports the error it raised, adding any lines to print to `messages`.
+    ///
+    /// Requests are checked before being carried out, so that a mod cannot
+    /// leave the world in a state which the game could not.
+    fn apply(&self, index: usize, result: Result<Vec<ScriptRequest>, String>, world: &mut World, messages: &mut Vec<ScriptMessage>) {
+        let name = &self.mods[index].name;
+        let requests = match result {
+            Ok(requests) => requests,
+            Err(e) => {
+                messages.push(ScriptMessage::Error(name.clone(), e));
+                return;
+            },
+        };
+
+        for request in requests {
+            let error = match request {
+                ScriptRequest::SetMaterial(pos, material) => {
+                    match world.materials().find(&material) {
+                        Some(id) => {
+                            // Liquids are placed as a full tile of fluid.
+                            let tile = if world.materials().is_liquid(id) {
+                                Tile::with_fluid_level(id, MAX_FLUID_LEVEL)
+                            } else {
+                                Tile::new(id)
+                            };
+                            world.area.set_tile(&pos, tile);
+                            None
+                        },
+                        None => Some(format!("unknown material: {}", material)),
+                    }
+                },
+                ScriptRequest::SpawnItem(kind, pos) => match ItemKind::from_name(&kind) {
+                    Some(kind) => {
+                        world.spawn_item(kind, pos);
+                        None
+                    },
+                    None => Some(format!("unknown item: {}", kind)),
+                },
+                ScriptRequest::DesignateDig(pos) => {
+                    world.designate_dig(pos);
+                    None
+                },
+                ScriptRequest::QueueJob(job, pos) => {
+                    if self.jobs.get(&job) == Some(&index) {
+                        world.queue_custom_job(&job, pos);
+                        None
+                    } else {
+                        Some(format!("unregistered job: {}", job))
+                    }
+                },
+                ScriptRequest::Print(text) => {
+                    messages.push(ScriptMessage::Print(text));
+                    None
+                },
+                ScriptRequest::AddMaterial(_) |
+                ScriptRequest::RegisterCommand(..) |
+                ScriptRequest::RegisterJob(_) => Some("definitions are only accepted while the mod is loaded".to_owned()),
+            };
+            if let Some(e) = error {
+                messages.push(ScriptMessage::Error(name.clone(), e));
+            }
+        }
+    }
+}
@@ -0,0 +1,404 @@
+#![cfg_attr(feature = "clippy", plugin(clippy))]
+#![cfg_attr(feature = "clippy", allow(used_underscore_binding))]
+
+extern crate cgmath;
+extern crate colonize;
+#[cfg(unix)]
+extern crate libc;
+#[macro_use]
+extern crate log;
+extern crate rand;
+extern crate colonize_world as world;
+
+mod signals;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::io::{self, BufRead};
+use std::path::PathBuf;
+use std::process;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cgmath::{Point2, Point3};
+use world::{MaterialRegistry, Overworld, Subscription, World, WorldGenConfig, OVERWORLD_SIZE};
+
+use colonize::{console, crash, localization, logging, materials, mods, net, save, scripting};
+use colonize::command::PlayerCommand;
+use colonize::config::Config;
+use colonize::console::{ConsoleCommand, ConsoleError};
+use colonize::designation::Designation;
+use colonize::error::ColonizeResult;
+use colonize::localization::Localization;
+use colonize::net::{Host, SessionEvent};
+use colonize::read_file_to_string;
+use colonize::scripting::{ScriptHost, ScriptMessage};
+use colonize::simulation::SimulationClock;
+
+const CONFIG_PATH: &'static str = "colonize.json";
+
+/// Hosts the game on the port given after it, rather than on
+/// `net::DEFAULT_PORT`.
+const PORT_FLAG: &'static str = "--port";
+/// Loads and saves the world under the name given after it, rather than
+/// under `DEFAULT_SAVE_NAME`.
+const SAVE_FLAG: &'static str = "--save";
+/// Generates the world from the seed given after it, if no world was saved
+/// under the name of the save yet, rather than from a random seed.
+const SEED_FLAG: &'static str = "--seed";
+const DEFAULT_SAVE_NAME: &'static str = "server";
+
+/// The console command which saves the world and shuts the server down.
+const QUIT_COMMAND: &'static str = "quit";
+/// The console commands of the game which the server runs, as the others
+/// need a player of its own, such as to move the camera.
+const SERVER_COMMANDS: &'static [&'static str] = &["seed", "regen", "save", "load", "set_material", "dig", "help"];
+/// Time the server sleeps between updates, which is well within the interval
+/// between steps of the simulation.
+const UPDATE_SLEEP_MILLIS: u64 = 10;
+/// The most tiles whose light is updated per update, as in the game.
+const LIGHT_UPDATE_BUDGET: usize = 16;
+
+fn main() {
+    crash::install();
+    signals::install();
+
+    if let Err(e) = run() {
+        error!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> ColonizeResult<()> {
+    // Load the configuration from its JSON file, falling back to the default
+    // configuration if it cannot be read. A file which cannot be parsed is
+    // reported rather than ignored.
+    let config = match read_file_to_string(&CONFIG_PATH.into()) {
+        Ok(json) => try!(Config::from_json(&json)),
+        Err(_) => Config::default(),
+    };
+
+    // The server has no settings of its own, so it logs at the info level.
+    logging::init("info", &BTreeMap::new(), true);
+
+    let port = match flag_value(PORT_FLAG) {
+        Some(port) => try!(port.parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid port {}", port)))),
+        None => net::DEFAULT_PORT,
+    };
+    let save_name = flag_value(SAVE_FLAG).unwrap_or_else(|| DEFAULT_SAVE_NAME.to_owned());
+    let seed = match flag_value(SEED_FLAG) {
+        Some(seed) => try!(seed.parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid seed {}", seed)))),
+        None => rand::random(),
+    };
+
+    // The mods are loaded as they are by the game, so that the players who
+    // join share its materials, and the world is decorated the same way.
+    let asset_path: PathBuf = (&config.asset_path).into();
+    let localization = Localization::load(&asset_path, localization::FALLBACK_LANGUAGE);
+    let mod_packs = mods::find_mod_packs(&(&config.mods_path).into());
+    let mut scripts = ScriptHost::load(&mod_packs);
+    let materials = Rc::new(materials::load_material_registry(&asset_path, &mod_packs, scripts.take_materials()));
+
+    let mut server = try!(Server::start(config, localization, materials, scripts, save_name, seed, port));
+    let console = read_console();
+    let mut last_update = Instant::now();
+    while !signals::received() {
+        let mut quit = false;
+        loop {
+            match console.try_recv() {
+                Ok(line) => {
+                    if line.trim() == QUIT_COMMAND {
+                        quit = true;
+                        break;
+                    }
+                    for output in server.run_command(&line) {
+                        println!("{}", output);
+                    }
+                },
+                // A server run without a terminal has no console to read,
+                // and is shut down by signal instead.
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        if quit {
+            break;
+        }
+
+        let elapsed = last_update.elapsed();
+        last_update = Instant::now();
+        server.update(elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9);
+        thread::sleep(Duration::from_millis(UPDATE_SLEEP_MILLIS));
+    }
+
+    info!("Shutting down");
+    server.save()
+}
+
+/// Returns the argument given after `flag`, if any.
+fn flag_value(flag: &str) -> Option<String> {
+    env::args().skip_while(|arg| arg != flag).nth(1)
+}
+
+/// Returns the lines read from stdin, which are read on a thread of their
+/// own so that the server keeps running while none are entered.
+fn read_console() -> Receiver<String> {
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            match line {
+                Ok(line) => {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                },
+                Err(_) => break,
+            }
+        }
+    });
+    lines
+}
+
+/// Runs the simulation of a game hosted over the network, without a window,
+/// for the players who join it.
+///
+/// As the server has no camera, the chunks around the position the camera
+/// was saved at, or started out at in a new world, are kept loaded, as if a
+/// player stayed there.
+struct Server {
+    config: Config,
+    localization: Localization,
+    materials: Rc<MaterialRegistry>,
+    scripts: ScriptHost,
+    world: World,
+    /// Through which the hooks of the mods take the events of the world.
+    script_events: Subscription,
+    camera_position: Point3<f64>,
+    host: Host,
+    simulation_clock: SimulationClock,
+    /// The name the world is saved under.
+    save_name: String,
+    /// Seconds until the world is next saved.
+    autosave_timer: f64,
+}
+
+impl Server {
+    /// Loads the world saved under `save_name`, or generates a new one from
+    /// `seed` if none was saved under it yet, and starts hosting it on
+    /// `port`.
+    fn start(config: Config, localization: Localization, materials: Rc<MaterialRegistry>, scripts: ScriptHost, save_name: String, seed: u32, port: u16) -> ColonizeResult<Self> {
+        let (mut world, camera_position) = if save::save_names().contains(&save_name) {
+            info!("Loading the world saved as {}", save_name);
+            try!(save::load_world(&save_name, config.initial_world_size, materials.clone()))
+        } else {
+            info!("Generating a new world from seed {}", seed);
+            // The colony starts in the middle of the overworld, as it does
+            // by default in the game.
+            let embark = Overworld::cell_center(Point2::new(OVERWORLD_SIZE / 2, OVERWORLD_SIZE / 2));
+            new_world(&config, &materials, seed, config.world_gen, embark)
+        };
+        let script_events = world.subscribe(scripting::HOOK_EVENTS);
+        let host = try!(Host::listen(port, config.allow_cheats));
+        info!("Hosting game on port {}", port);
+
+        let autosave_timer = config.autosave_interval as f64;
+        Ok(Server {
+            config: config,
+            localization: localization,
+            materials: materials,
+            scripts: scripts,
+            world: world,
+            script_events: script_events,
+            camera_position: camera_position,
+            host: host,
+            simulation_clock: SimulationClock::new(),
+            save_name: save_name,
+            autosave_timer: autosave_timer,
+        })
+    }
+
+    /// Exchanges commands and updates with the players, runs the steps of
+    /// the simulation due after `dt` seconds, and saves the world every
+    /// `autosave_interval` seconds of the configuration.
+    fn update(&mut self, dt: f64) {
+        let center = Point3::new(self.camera_position.x as i32, self.camera_position.y as i32, self.camera_position.z as i32);
+        if let Err(e) = self.world.area.request_chunks_around(world::abs_pos_to_chunk_pos(&center), self.config.chunk_load_radius as i32) {
+            error!("{}", e);
+        }
+        for e in self.world.area.receive_chunks() {
+            error!("{}", e);
+        }
+
+        let budget = self.config.chunk_memory_budget as usize * 1024 * 1024;
+        if let Err(e) = self.world.area.unload_chunks(budget) {
+            warn!("Failed to unload chunks: {}", e);
+        }
+
+        let (events, commands) = self.host.update(dt, &self.world);
+        self.log_session_events(events);
+        for command in commands {
+            command.apply(&mut self.world);
+        }
+
+        for _ in 0..self.simulation_clock.update(dt) {
+            self.world.tick();
+        }
+        self.world.update_light(LIGHT_UPDATE_BUDGET);
+        let events = self.world.take_events(self.script_events);
+        let messages = self.scripts.run_hooks(&events, &mut self.world);
+        for line in self.script_output(messages) {
+            info!("{}", line);
+        }
+
+        if self.config.autosave_interval > 0 {
+            self.autosave_timer -= dt;
+            if self.autosave_timer <= 0.0 {
+                self.autosave_timer = self.config.autosave_interval as f64;
+                if let Err(e) = self.save() {
+                    warn!("Failed to autosave the world: {}", e);
+                }
+            }
+        }
+    }
+
+    fn save(&mut self) -> ColonizeResult<()> {
+        try!(save::save_world(&self.save_name, &mut self.world, &self.camera_position));
+        info!("Saved the world as {}", self.save_name);
+        Ok(())
+    }
+
+    /// Runs the console command `line`, returning the lines to print in
+    /// response.
+    ///
+    /// Only the commands in `SERVER_COMMANDS` are run by the server, along
+    /// with those defined by mods.
+    fn run_command(&mut self, line: &str) -> Vec<String> {
+        let command = match ConsoleCommand::parse(line) {
+            Ok(command) => command,
+            Err(ConsoleError::UnknownCommand(name, args)) => {
+                return match self.scripts.run_command(&name, args, &mut self.world) {
+                    Some(messages) => self.script_output(messages),
+                    None => vec![format!("{}: {}", self.localization.console_unknown_command, name)],
+                };
+            },
+            Err(ConsoleError::InvalidArguments(usage)) => return vec![format!("{}: {}", self.localization.console_usage, usage)],
+            Err(ConsoleError::Empty) => return Vec::new(),
+        };
+
+        match command {
+            ConsoleCommand::Seed(seed) => self.regenerate_world(seed),
+            ConsoleCommand::Regen => {
+                let seed = self.world.seed();
+                self.regenerate_world(seed)
+            },
+            ConsoleCommand::Save(name) => {
+                if let Some(name) = name {
+                    self.save_name = name;
+                }
+                match self.save() {
+                    Ok(()) => vec![format!("{}: {}", self.localization.console_game_saved, self.save_name)],
+                    Err(e) => vec![format!("{}: {}", self.localization.console_save_failed, e)],
+                }
+            },
+            ConsoleCommand::Load(name) => {
+                let name = name.unwrap_or_else(|| self.save_name.clone());
+                match save::load_world(&name, self.config.initial_world_size, self.materials.clone()) {
+                    Ok(loaded) => {
+                        let mut output = self.replace_world(loaded);
+                        output.insert(0, format!("{}: {}", self.localization.console_game_loaded, name));
+                        self.save_name = name;
+                        output
+                    },
+                    Err(e) => vec![format!("{}: {}", self.localization.console_load_failed, e)],
+                }
+            },
+            ConsoleCommand::SetMaterial(pos, name) => {
+                match materials::find_material(&self.materials, &name) {
+                    Some(material) => {
+                        if PlayerCommand::SetMaterial([pos.x, pos.y, pos.z], material).apply(&mut self.world) {
+                            vec![format!("{}: {:?}", self.localization.console_material_set, pos)]
+                        } else {
+                            vec![format!("{}: {:?}", self.localization.console_position_not_loaded, pos)]
+                        }
+                    },
+                    None => vec![format!("{}: {}", self.localization.console_unknown_material, name)],
+                }
+            },
+            ConsoleCommand::Dig(pos) => {
+                if PlayerCommand::Designate(Designation::Dig, [pos.x, pos.y, pos.z]).apply(&mut self.world) {
+                    vec![format!("{}: {:?}", self.localization.console_dig_queued, pos)]
+                } else {
+                    vec![format!("{}: {:?}", self.localization.console_dig_rejected, pos)]
+                }
+            },
+            ConsoleCommand::Help => {
+                let mut usages: Vec<String> = console::USAGE.iter()
+                    .filter(|usage| usage.split_whitespace().next().map_or(false, |name| SERVER_COMMANDS.contains(&name)))
+                    .map(|usage| (*usage).to_owned())
+                    .collect();
+                usages.push(QUIT_COMMAND.to_owned());
+                usages.extend(self.scripts.command_usages());
+                usages
+            },
+            _ => vec![format!("{}: {}", self.localization.console_not_on_server, line.trim())],
+        }
+    }
+
+    /// Replaces the world with one generated from `seed`, with the terrain
+    /// parameters and embark site of the current one. Returns the lines to
+    /// print in response.
+    fn regenerate_world(&mut self, seed: u32) -> Vec<String> {
+        let world_gen = *self.world.config();
+        let embark = self.world.embark();
+        let generated = new_world(&self.config, &self.materials, seed, world_gen, embark);
+        let mut output = self.replace_world(generated);
+        output.insert(0, format!("{}: {}", self.localization.console_world_generated, seed));
+        output
+    }
+
+    /// Replaces the world with `world`, whose camera position is
+    /// `camera_position`, and welcomes every player into it anew. Returns the
+    /// lines to print in response.
+    fn replace_world(&mut self, (mut world, camera_position): (World, Point3<f64>)) -> Vec<String> {
+        self.script_events = world.subscribe(scripting::HOOK_EVENTS);
+        self.world = world;
+        self.camera_position = camera_position;
+        self.simulation_clock.reset();
+        let events = self.host.restart(&self.world);
+        self.session_event_lines(events)
+    }
+
+    fn log_session_events(&self, events: Vec<SessionEvent>) {
+        for line in self.session_event_lines(events) {
+            info!("{}", line);
+        }
+    }
+
+    /// Returns the lines to print for the players who joined or left.
+    fn session_event_lines(&self, events: Vec<SessionEvent>) -> Vec<String> {
+        events.into_iter().map(|event| match event {
+            SessionEvent::Joined(address) => format!("{}: {}", self.localization.console_player_joined, address),
+            SessionEvent::Left(address) => format!("{}: {}", self.localization.console_player_left, address),
+        }).collect()
+    }
+
+    /// Returns the lines to print for the messages of mods.
+    fn script_output(&self, messages: Vec<ScriptMessage>) -> Vec<String> {
+        messages.into_iter().map(|message| match message {
+            ScriptMessage::Print(text) => text,
+            ScriptMessage::Error(name, e) => format!("{} {}: {}", self.localization.console_script_error, name, e),
+        }).collect()
+    }
+}
+
+/// Generates a world from `seed` with the terrain parameters `world_gen`,
+/// whose colony starts in the chunk column `embark`, returning it along with
+/// the position the camera starts out at.
+fn new_world(config: &Config, materials: &Rc<MaterialRegistry>, seed: u32, world_gen: WorldGenConfig, embark: Point2<i32>) -> (World, Point3<f64>) {
+    let world = World::new(Some(seed), config.initial_world_size, embark, world_gen, materials.clone());
+    let camera_position = save::embark_camera_position(&world);
+    (world, camera_position)
+}
@@ -0,0 +1,55 @@
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Settings {
+    /// Height of the window
+    pub window_height: u32,
+    /// Width of the window
+    pub window_width: u32,
+    /// Initialize the window in fullscreen mode
+    pub fullscreen: bool,
+    /// Enable vsync
+    pub vsync: bool,
+    /// Maximum number of frames per second.
+    /// The frame rate can be lower because the next frame is always scheduled
+    /// from the previous frame. This causes the frames to "slip" over time.
+    pub max_fps: u64,
+    /// Volume of all audio, between 0 and 1
+    pub master_volume: f64,
+    /// Volume of the music, relative to the master volume
+    pub music_volume: f64,
+    /// Volume of the sound effects, relative to the master volume
+    pub sfx_volume: f64,
+    /// Factor by which the text and layout of the interface are scaled, or 0
+    /// to detect it from the DPI of the display
+    pub ui_scale: f64,
+    /// Language of the user interface, by the name of its localization file
+    pub language: String,
+    /// Colors of the game, by the name of their file in the palettes
+    /// directory
+    pub palette: String,
+    /// Level of the messages written to the log, one of "off", "error",
+    /// "warn", "info", "debug" or "trace"
+    pub log_level: String,
+    /// Levels of the messages written to the log by specific modules, such as
+    /// "colonize_world::chunk_loader", overriding `log_level`
+    pub log_filters: BTreeMap<String, String>,
+    /// Key bindings for the main game scene
+    pub game_scene_key_bindings: BindingsHashMap<RustcSerializeWrapper<Key>, Action>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ParsedSettings {
+    window_height: Option<u32>,
+    window_width: Option<u32>,
+    fullscreen: Option<bool>,
+    vsync: Option<bool>,
+    max_fps: Option<u64>,
+    master_volume: Option<f64>,
+    music_volume: Option<f64>,
+    sfx_volume: Option<f64>,
+    ui_scale: Option<f64>,
+    language: Option<String>,
+    palette: Option<String>,
+    log_level: Option<String>,
+    log_filters: Option<BTreeMap<String, String>>,
+    game_scene_key_bindings: Option<BindingsHashMap<RustcSerializeWrapper<Key>, Action>>,
+}
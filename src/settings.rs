@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+
+use piston::input::keyboard::Key;
+use rgframework::{
+    BindingsHashMap,
+    BindingStore,
+    RustcSerializeWrapper,
+};
+use toml;
+
+use action::Action;
+use camera::CameraAction;
+use cursor::CursorAction;
+use designation::Designation;
+use display;
+use error::{ColonizeError, ColonizeResult};
+use localization;
+use palette;
+use paths;
+use simulation::{SimulationAction, SimulationSpeed};
+use world::{Direction, Structure};
+
+#[cfg(feature = "nightly")]
+include!("settings.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/settings.rs"));
+
+/// The file, in the configuration directory, which the settings are loaded
+/// from and saved to.
+const SETTINGS_FILE: &'static str = "settings.toml";
+
+/// The value of `ui_scale` for which the scale of the interface is detected
+/// from the DPI of the display.
+pub const AUTO_UI_SCALE: f64 = 0.0;
+
+create_type_filling_impls! {
+    Settings,
+    ParsedSettings,
+    window_height, 800;
+    window_width, 800;
+    fullscreen, false;
+    vsync, false;
+    max_fps, 10_000;
+    master_volume, 1.0;
+    music_volume, 0.6;
+    sfx_volume, 0.8;
+    ui_scale, AUTO_UI_SCALE;
+    language, localization::FALLBACK_LANGUAGE.to_owned();
+    palette, palette::DEFAULT_PALETTE.to_owned();
+    log_level, "info".to_owned();
+    log_filters, BTreeMap::new();
+    game_scene_key_bindings, BindingsHashMap::new()
+            .add_binding(RustcSerializeWrapper::new(Key::Down), Action::Camera(CameraAction::Move(Direction::South)))
+            .add_binding(RustcSerializeWrapper::new(Key::Comma), Action::Camera(CameraAction::Move(Direction::Down)))
+            .add_binding(RustcSerializeWrapper::new(Key::Up), Action::Camera(CameraAction::Move(Direction::North)))
+            .add_binding(RustcSerializeWrapper::new(Key::Left), Action::Camera(CameraAction::Move(Direction::West)))
+            .add_binding(RustcSerializeWrapper::new(Key::Right), Action::Camera(CameraAction::Move(Direction::East)))
+            .add_binding(RustcSerializeWrapper::new(Key::Period), Action::Camera(CameraAction::Move(Direction::Up)))
+            .add_binding(RustcSerializeWrapper::new(Key::Space), Action::Simulation(SimulationAction::TogglePause))
+            .add_binding(RustcSerializeWrapper::new(Key::D1), Action::Simulation(SimulationAction::SetSpeed(SimulationSpeed::Normal)))
+            .add_binding(RustcSerializeWrapper::new(Key::D2), Action::Simulation(SimulationAction::SetSpeed(SimulationSpeed::Fast)))
+            .add_binding(RustcSerializeWrapper::new(Key::D3), Action::Simulation(SimulationAction::SetSpeed(SimulationSpeed::Fastest)))
+            .add_binding(RustcSerializeWrapper::new(Key::D), Action::Designate(Designation::Dig))
+            .add_binding(RustcSerializeWrapper::new(Key::J), Action::Designate(Designation::DigStairs))
+            .add_binding(RustcSerializeWrapper::new(Key::S), Action::Designate(Designation::Stockpile))
+            .add_binding(RustcSerializeWrapper::new(Key::W), Action::Designate(Designation::Build(Structure::Wall)))
+            .add_binding(RustcSerializeWrapper::new(Key::F), Action::Designate(Designation::Build(Structure::Floor)))
+            .add_binding(RustcSerializeWrapper::new(Key::X), Action::Designate(Designation::Build(Structure::Stairs)))
+            .add_binding(RustcSerializeWrapper::new(Key::P), Action::Designate(Designation::Build(Structure::Ramp)))
+            .add_binding(RustcSerializeWrapper::new(Key::B), Action::Designate(Designation::Build(Structure::Workshop)))
+            .add_binding(RustcSerializeWrapper::new(Key::H), Action::Designate(Designation::Hunt))
+            .add_binding(RustcSerializeWrapper::new(Key::R), Action::ToggleDraft)
+            .add_binding(RustcSerializeWrapper::new(Key::Z), Action::Rewind)
+            .add_binding(RustcSerializeWrapper::new(Key::L), Action::ShowLog)
+            .add_binding(RustcSerializeWrapper::new(Key::I), Action::ToggleIsometric)
+            .add_binding(RustcSerializeWrapper::new(Key::V), Action::CycleOverlays)
+            .add_binding(RustcSerializeWrapper::new(Key::U), Action::ToggleRoster)
+            .add_binding(RustcSerializeWrapper::new(Key::G), Action::ShowReports)
+            .add_binding(RustcSerializeWrapper::new(Key::K), Action::Cursor(CursorAction::Toggle))
+            .add_binding(RustcSerializeWrapper::new(Key::NumPad1), Action::Cursor(CursorAction::Move(-1, 1)))
+            .add_binding(RustcSerializeWrapper::new(Key::NumPad2), Action::Cursor(CursorAction::Move(0, 1)))
+            .add_binding(RustcSerializeWrapper::new(Key::NumPad3), Action::Cursor(CursorAction::Move(1, 1)))
+            .add_binding(RustcSerializeWrapper::new(Key::NumPad4), Action::Cursor(CursorAction::Move(-1, 0)))
+            .add_binding(RustcSerializeWrapper::new(Key::NumPad6), Action::Cursor(CursorAction::Move(1, 0)))
+            .add_binding(RustcSerializeWrapper::new(Key::NumPad7), Action::Cursor(CursorAction::Move(-1, -1)))
+            .add_binding(RustcSerializeWrapper::new(Key::NumPad8), Action::Cursor(CursorAction::Move(0, -1)))
+            .add_binding(RustcSerializeWrapper::new(Key::NumPad9), Action::Cursor(CursorAction::Move(1, -1)))
+            .add_binding(RustcSerializeWrapper::new(Key::NumPad5), Action::Cursor(CursorAction::Select))
+            .add_binding(RustcSerializeWrapper::new(Key::Return), Action::Cursor(CursorAction::Select))
+            .add_binding(RustcSerializeWrapper::new(Key::O), Action::Cursor(CursorAction::Order))
+            .add_binding(RustcSerializeWrapper::new(Key::F4), Action::SkipHour)
+            .add_binding(RustcSerializeWrapper::new(Key::F5), Action::ToggleAiOverlay)
+            .add_binding(RustcSerializeWrapper::new(Key::F7), Action::ToggleBatchTiles)
+            .add_binding(RustcSerializeWrapper::new(Key::F8), Action::StepPlayback)
+            .add_binding(RustcSerializeWrapper::new(Key::F9), Action::CyclePlaybackSlowdown)
+            .add_binding(RustcSerializeWrapper::new(Key::F10), Action::ScrubBack)
+            .add_binding(RustcSerializeWrapper::new(Key::F11), Action::ScrubForward);
+}
+
+/// Returns the path of the file the settings are loaded from and saved to.
+pub fn settings_path() -> PathBuf {
+    paths::config_dir().join(SETTINGS_FILE)
+}
+
+impl Settings {
+    /// Reads the settings from the TOML file at `path`, falling back to the
+    /// defaults for any which are missing.
+    pub fn load(path: &PathBuf) -> ColonizeResult<Settings> {
+        let toml = try!(::read_file_to_string(path));
+        Settings::from_toml(&toml)
+    }
+
+    /// Parses the settings from TOML, falling back to the defaults for any
+    /// which are missing.
+    ///
+    /// The saved key bindings replace the default ones, except that actions
+    /// bound to no key, such as those added since the settings were saved,
+    /// keep their default keys where those are free.
+    pub fn from_toml(toml: &str) -> ColonizeResult<Settings> {
+        let parsed = try!(toml::decode_str(toml).ok_or(ColonizeError::Settings("invalid settings file".to_owned())));
+        let mut settings = Settings::default().fill_from_parsed(parsed);
+        settings.game_scene_key_bindings.merge_defaults(&Settings::default().game_scene_key_bindings);
+        Ok(settings)
+    }
+
+    /// Writes the settings to the file at `path` as TOML, creating its
+    /// directory if need be.
+    pub fn save(&self, path: &PathBuf) -> ColonizeResult<()> {
+        if let Some(dir) = path.parent() {
+            try!(fs::create_dir_all(dir));
+        }
+        let mut file = try!(File::create(path));
+        try!(file.write_all(toml::encode_str(self).as_bytes()));
+        Ok(())
+    }
+
+    /// Returns the factor by which the interface is scaled, as set or as
+    /// detected from the DPI of the display.
+    pub fn ui_scale(&self) -> f64 {
+        if self.ui_scale == AUTO_UI_SCALE {
+            display::detected_ui_scale()
+        } else {
+            self.ui_scale
+        }
+    }
+
+    /// Returns the size at which to render text of size `font_size`.
+    pub fn scale_font_size(&self, font_size: u32) -> u32 {
+        (font_size as f64 * self.ui_scale()).round() as u32
+    }
+
+    /// Returns the length, in pixels, at which to lay out a part of the
+    /// interface `length` pixels long at its designed size.
+    pub fn scale_length(&self, length: f64) -> f64 {
+        length * self.ui_scale()
+    }
+
+    /// Returns the position, in pixels, at which to lay out a part of the
+    /// interface placed at `position` at its designed size.
+    pub fn scale_position(&self, position: [f64; 2]) -> [f64; 2] {
+        [self.scale_length(position[0]), self.scale_length(position[1])]
+    }
+
+    /// Returns the area, as `[x, y, width, height]` in pixels, in which to lay
+    /// out a part of the interface covering `rect` at its designed size.
+    pub fn scale_rect(&self, rect: [f64; 4]) -> [f64; 4] {
+        [self.scale_length(rect[0]), self.scale_length(rect[1]), self.scale_length(rect[2]), self.scale_length(rect[3])]
+    }
+
+    /// Copies the settings of `other` which can be changed while the game is
+    /// running. The size and mode of the window are left unchanged, since they
+    /// are only read when the window is created.
+    pub fn apply_live(&mut self, other: &Settings) {
+        self.max_fps = other.max_fps;
+        self.master_volume = other.master_volume;
+        self.music_volume = other.music_volume;
+        self.sfx_volume = other.sfx_volume;
+        self.ui_scale = other.ui_scale;
+        self.language = other.language.clone();
+        self.palette = other.palette.clone();
+        self.game_scene_key_bindings = other.game_scene_key_bindings.clone();
+    }
+}
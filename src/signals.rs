@@ -0,0 +1,46 @@
+#[cfg(unix)]
+mod imp {
+    use std::sync::atomic::{AtomicBool, ATOMIC_BOOL_INIT, Ordering};
+
+    use libc;
+
+    static RECEIVED: AtomicBool = ATOMIC_BOOL_INIT;
+
+    extern "C" fn handle(_signal: libc::c_int) {
+        // Nothing but an atomic store is safe to do in a signal handler.
+        RECEIVED.store(true, Ordering::SeqCst);
+    }
+
+    pub fn install() {
+        unsafe {
+            libc::signal(libc::SIGINT, handle as libc::sighandler_t);
+            libc::signal(libc::SIGTERM, handle as libc::sighandler_t);
+        }
+    }
+
+    pub fn received() -> bool {
+        RECEIVED.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    pub fn install() {}
+
+    pub fn received() -> bool {
+        false
+    }
+}
+
+/// Catches SIGINT and SIGTERM from now on, rather than letting them end the
+/// process. Does nothing on platforms other than Unix, where the server is
+/// shut down from its console instead.
+pub fn install() {
+    imp::install()
+}
+
+/// Returns whether SIGINT or SIGTERM was received since `install` was
+/// called.
+pub fn received() -> bool {
+    imp::received()
+}
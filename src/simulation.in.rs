@@ -0,0 +1,15 @@
+/// A rate at which the simulation can run, relative to real time.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum SimulationSpeed {
+    Normal,
+    Fast,
+    Fastest,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum SimulationAction {
+    /// Pauses the simulation, or resumes it at its previous speed.
+    TogglePause,
+    /// Runs the simulation at the specified speed, resuming it if paused.
+    SetSpeed(SimulationSpeed),
+}
@@ -0,0 +1,129 @@
+use std::cmp;
+use std::mem;
+
+use rgframework::Command;
+
+#[cfg(feature = "nightly")]
+include!("simulation.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/simulation.rs"));
+
+// Interval between steps of the simulation at normal speed, in seconds.
+const TICK_INTERVAL: f64 = 0.1;
+
+impl SimulationSpeed {
+    /// Returns the number of steps of the simulation run per interval.
+    pub fn multiplier(&self) -> u32 {
+        match *self {
+            SimulationSpeed::Normal => 1,
+            SimulationSpeed::Fast => 2,
+            SimulationSpeed::Fastest => 4,
+        }
+    }
+}
+
+/// Decides how many steps of the simulation to run as real time passes.
+///
+/// The simulation always advances in whole steps at a fixed interval, so
+/// that its outcome does not depend on the frame rate. Faster speeds run
+/// several steps per interval rather than shortening the interval, while
+/// slow motion lengthens the interval instead.
+pub struct SimulationClock {
+    speed: SimulationSpeed,
+    paused: bool,
+    /// The factor by which the interval is lengthened, which is 1 unless
+    /// the simulation runs in slow motion.
+    slowdown: u32,
+    /// Whether a single step was asked to be run while paused.
+    step_requested: bool,
+    /// Time elapsed since the last interval, in seconds.
+    timer: f64,
+}
+
+impl SimulationClock {
+    pub fn new() -> Self {
+        SimulationClock {
+            speed: SimulationSpeed::Normal,
+            paused: false,
+            slowdown: 1,
+            step_requested: false,
+            timer: 0.0,
+        }
+    }
+
+    pub fn speed(&self) -> SimulationSpeed {
+        self.speed
+    }
+
+    /// Returns the number of steps of the simulation run per interval at
+    /// normal speed, which is below 1 in slow motion.
+    pub fn rate(&self) -> f64 {
+        self.speed.multiplier() as f64 / self.slowdown as f64
+    }
+
+    pub fn slowdown(&self) -> u32 {
+        self.slowdown
+    }
+
+    /// Lengthens the interval between steps by `slowdown`, or restores it if
+    /// `slowdown` is 1.
+    pub fn set_slowdown(&mut self, slowdown: u32) {
+        self.slowdown = cmp::max(slowdown, 1);
+    }
+
+    /// Pauses the simulation, and has the next update run a single step.
+    pub fn step(&mut self) {
+        self.paused = true;
+        self.step_requested = true;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Changes the speed of the simulation, resuming it if paused.
+    pub fn set_speed(&mut self, speed: SimulationSpeed) {
+        self.speed = speed;
+        self.paused = false;
+    }
+
+    /// Discards any time accumulated towards the next interval.
+    pub fn reset(&mut self) {
+        self.timer = 0.0;
+    }
+
+    /// Advances the clock by `dt` seconds, returning the number of steps of
+    /// the simulation to run.
+    pub fn update(&mut self, dt: f64) -> u32 {
+        if self.paused {
+            return if mem::replace(&mut self.step_requested, false) { 1 } else { 0 };
+        }
+
+        let interval = TICK_INTERVAL * self.slowdown as f64;
+        let mut ticks = 0;
+        self.timer += dt;
+        while self.timer >= interval {
+            ticks += self.speed.multiplier();
+            self.timer -= interval;
+        }
+        ticks
+    }
+}
+
+impl Default for SimulationClock {
+    fn default() -> Self {
+        SimulationClock::new()
+    }
+}
+
+pub fn new_simulation_command<'a>(action: &'a SimulationAction, clock: &'a mut SimulationClock) -> Command<'a> {
+    match *action {
+        SimulationAction::TogglePause => Box::new(move || { clock.toggle_pause() }),
+        SimulationAction::SetSpeed(speed) => Box::new(move || { clock.set_speed(speed) }),
+    }
+}
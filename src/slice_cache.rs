@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use cgmath::{Point2, Point3};
+use world::{Area, ChunkArray2d, MaterialId, MaterialRegistry, CHUNK_SIZE, LOG2_OF_CHUNK_SIZE, MAX_FLUID_LEVEL, MAX_LIGHT_LEVEL, OUT_OF_BOUNDS};
+
+/// Revisions of the chunk containing a slice and of the chunks above and
+/// below it, or `None` for those which are not loaded. A slice only needs to
+/// be resolved, or drawn, again once they change.
+pub type SliceRevisions = (Option<u64>, Option<u64>, Option<u64>);
+
+/// What is visible at a single position of a slice.
+#[derive(Clone, Copy)]
+pub struct SliceCell {
+    /// The opaque material drawn at this position.
+    pub material: MaterialId,
+    /// The see-through material (such as a liquid or a layer of snow) drawn
+    /// over `material`, and the fraction of its tile which it fills, if any.
+    pub overlay: Option<(MaterialId, f32)>,
+    /// The light level at which the cell is seen.
+    pub light: u8,
+    /// Whether the tile at this position has been revealed to the player.
+    pub revealed: bool,
+}
+
+/// Caches the materials visible at a single z-level, one chunk at a time.
+///
+/// Resolving which tile to draw at a position requires up to three chunk
+/// lookups, since see-through tiles show the tile below them, and the tops of
+/// opaque tiles are lit by the tile above them. Rather than repeating this
+/// for every visible tile each frame, the resolved slice of each chunk is
+/// cached until the chunk (or the chunk above or below it) is modified, or
+/// until the z-level changes. Slices which scroll out of view are dropped by
+/// `evict_unused`, so that the cache does not grow as the view is panned.
+///
+/// The window draws the flat-colored tiles of each slice from a texture,
+/// which `SliceTextures` keeps along the same lines.
+pub struct SliceCache {
+    y: i32,
+    /// The number of frames for which `evict_unused` has been called.
+    frame: u64,
+    slices: HashMap<Point2<i32>, CachedSlice>,
+}
+
+struct CachedSlice {
+    /// Revisions of the chunks the slice was resolved from, at the time.
+    revisions: SliceRevisions,
+    /// Cells to draw, indexed by `[z][x]`.
+    tiles: ChunkArray2d<SliceCell>,
+    /// The frame during which the slice was last fetched.
+    last_used: u64,
+}
+
+impl SliceCache {
+    pub fn new() -> Self {
+        SliceCache {
+            y: 0,
+            frame: 0,
+            slices: HashMap::new(),
+        }
+    }
+
+    /// Returns the visible cells of the chunk column at `chunk_pos` (in
+    /// chunk coordinates along the X and Z axes) at the absolute z-level `y`,
+    /// indexed by `[z][x]`.
+    pub fn get_slice(&mut self, area: &Area, materials: &MaterialRegistry, chunk_pos: Point2<i32>, y: i32) -> &ChunkArray2d<SliceCell> {
+        if y != self.y {
+            self.slices.clear();
+            self.y = y;
+        }
+
+        let revisions = slice_revisions(area, chunk_pos, y);
+        let is_stale = match self.slices.get(&chunk_pos) {
+            Some(slice) => slice.revisions != revisions,
+            None => true,
+        };
+
+        if is_stale {
+            self.slices.insert(chunk_pos, CachedSlice {
+                revisions: revisions,
+                tiles: resolve_slice(area, materials, chunk_pos, y),
+                last_used: self.frame,
+            });
+        }
+
+        let slice = self.slices.get_mut(&chunk_pos).unwrap();
+        slice.last_used = self.frame;
+        &slice.tiles
+    }
+
+    /// Drops the slices which have not been fetched since the last call, and
+    /// starts a new frame. Called once the view has been drawn, this keeps
+    /// only the slices of the chunks in view.
+    pub fn evict_unused(&mut self) {
+        let frame = self.frame;
+        self.slices.retain(|_, slice| slice.last_used == frame);
+        self.frame += 1;
+    }
+}
+
+/// Returns the revisions of the chunks from which the slice of the chunk
+/// column at `chunk_pos` at the absolute z-level `y` is resolved.
+pub fn slice_revisions(area: &Area, chunk_pos: Point2<i32>, y: i32) -> SliceRevisions {
+    let above = y + 1;
+    let below = y - 1;
+    let revision = |y: i32| {
+        area.get_chunk(Point3::new(chunk_pos.x, y >> LOG2_OF_CHUNK_SIZE, chunk_pos.y))
+            .map(|chunk| chunk.revision())
+    };
+
+    (revision(y), revision(above), revision(below))
+}
+
+fn resolve_slice(area: &Area, materials: &MaterialRegistry, chunk_pos: Point2<i32>, y: i32) -> ChunkArray2d<SliceCell> {
+    let empty = SliceCell {
+        material: OUT_OF_BOUNDS,
+        overlay: None,
+        light: MAX_LIGHT_LEVEL,
+        revealed: false,
+    };
+    let mut tiles = [[empty; CHUNK_SIZE]; CHUNK_SIZE];
+    let chunk_at = |y: i32| area.get_chunk(Point3::new(chunk_pos.x, y >> LOG2_OF_CHUNK_SIZE, chunk_pos.y));
+    let rel_y = |y: i32| (y & (CHUNK_SIZE as i32 - 1)) as usize;
+
+    let chunk = match chunk_at(y) {
+        Some(chunk) => chunk,
+        None => return tiles,
+    };
+    let above = y + 1;
+    let above_chunk = chunk_at(above);
+    let below = y - 1;
+    let below_chunk = chunk_at(below);
+
+    for (z, row) in chunk.iter_slice(rel_y(y)).enumerate() {
+        for (x, tile) in row.enumerate() {
+            let revealed = chunk.is_revealed(&Point3::new(x, rel_y(y), z));
+            // If the tile is see-through, we want to render the material
+            // underneath it, instead. Liquids and other visible see-through
+            // materials are drawn over the material underneath them.
+            tiles[z][x] = if materials.is_solid(tile.material) {
+                // Only the top of an opaque tile is visible, which is lit
+                // like the tile above it.
+                let light = match above_chunk {
+                    Some(above_chunk) => above_chunk.light_level(&Point3::new(x, rel_y(above), z)),
+                    None => MAX_LIGHT_LEVEL,
+                };
+
+                SliceCell {
+                    material: tile.material,
+                    overlay: None,
+                    light: light,
+                    revealed: revealed,
+                }
+            } else {
+                let overlay = match materials.get(tile.material) {
+                    Some(material) if material.liquid => {
+                        Some((tile.material, tile.fluid_level as f32 / MAX_FLUID_LEVEL as f32))
+                    },
+                    Some(material) if material.color[3] > 0.0 || material.texture.is_some() => Some((tile.material, 1.0)),
+                    _ => None,
+                };
+
+                let material_below = match below_chunk {
+                    Some(below_chunk) => below_chunk.get_tile(&Point3::new(x, rel_y(below), z)).material,
+                    None => OUT_OF_BOUNDS,
+                };
+
+                SliceCell {
+                    material: material_below,
+                    overlay: overlay,
+                    light: chunk.light_level(&Point3::new(x, rel_y(y), z)),
+                    revealed: revealed,
+                }
+            };
+        }
+    }
+
+    tiles
+}
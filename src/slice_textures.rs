@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use cgmath::Point2;
+use rgframework::backend::Backend;
+use rgframework::backend::graphics::{CreateTexture, Format, TextureSettings};
+use world::{ChunkArray2d, MaterialRegistry, CHUNK_SIZE};
+
+use palette::Palette;
+use renderer::light_brightness;
+use slice_cache::{SliceCell, SliceRevisions};
+use tile_renderer::TileRenderer;
+
+/// The number of pixels along each side of a tile in the texture of a slice.
+/// The textures are stretched over the view, which blends neighbouring
+/// pixels, so each tile takes a few of them to keep its edges sharp.
+const PIXELS_PER_TILE: usize = 4;
+/// The number of pixels along each side of the texture of a slice.
+const TEXTURE_SIZE: usize = CHUNK_SIZE * PIXELS_PER_TILE;
+
+/// Textures into which the flat-colored tiles of the slices of the view are
+/// drawn, one per chunk column, so that the window draws each slice at once
+/// rather than tile by tile.
+///
+/// A texture is drawn again whenever the slice it was drawn from changes,
+/// which is whenever one of the chunks the slice is resolved from is
+/// modified, and once the atlas or the palette has been replaced. Tiles are
+/// drawn lit as they would be in full daylight, and the texture is darkened
+/// as a whole as the day turns to night. Tiles with a texture or an overlay,
+/// and the tiles which have not been revealed, are left out, to be drawn one
+/// by one.
+pub struct SliceTextures<B>
+    where B: Backend,
+{
+    y: i32,
+    atlas_version: u64,
+    /// The number of frames for which `evict_unused` has been called.
+    frame: u64,
+    textures: HashMap<Point2<i32>, SliceTexture<B>>,
+}
+
+struct SliceTexture<B>
+    where B: Backend,
+{
+    /// Revisions of the chunks the slice was resolved from, at the time the
+    /// texture was drawn.
+    revisions: SliceRevisions,
+    texture: Option<B::Texture>,
+    /// The frame during which the texture was last fetched.
+    last_used: u64,
+}
+
+impl<B> SliceTextures<B>
+    where B: Backend,
+{
+    pub fn new() -> Self {
+        SliceTextures {
+            y: 0,
+            atlas_version: 0,
+            frame: 0,
+            textures: HashMap::new(),
+        }
+    }
+
+    /// Draws every slice again when it is next fetched, as when the palette
+    /// was changed.
+    pub fn redraw(&mut self) {
+        self.textures.clear();
+    }
+
+    /// Returns the texture of `slice`, the slice of the chunk column at
+    /// `chunk_pos` at the absolute z-level `y`, as resolved from chunks with
+    /// the revisions `revisions`, drawing it first if it has changed since it
+    /// was last drawn. Returns `None` if the texture could not be created.
+    pub fn get(&mut self, chunk_pos: Point2<i32>, y: i32, revisions: SliceRevisions, slice: &ChunkArray2d<SliceCell>, tile_renderer: &TileRenderer<B>, materials: &MaterialRegistry, palette: &Palette) -> Option<&B::Texture> {
+        if y != self.y || tile_renderer.atlas_version() != self.atlas_version {
+            self.textures.clear();
+            self.y = y;
+            self.atlas_version = tile_renderer.atlas_version();
+        }
+
+        let is_stale = match self.textures.get(&chunk_pos) {
+            Some(texture) => texture.revisions != revisions,
+            None => true,
+        };
+
+        if is_stale {
+            let pixels = draw_slice(slice, tile_renderer, materials, palette);
+            let size = [TEXTURE_SIZE as u32, TEXTURE_SIZE as u32];
+            self.textures.insert(chunk_pos, SliceTexture {
+                revisions: revisions,
+                texture: <B::Texture as CreateTexture<()>>::create(&mut (), Format::Rgba8, &pixels, size, &TextureSettings::new()).ok(),
+                last_used: self.frame,
+            });
+        }
+
+        let texture = self.textures.get_mut(&chunk_pos).unwrap();
+        texture.last_used = self.frame;
+        texture.texture.as_ref()
+    }
+
+    /// Drops the textures which have not been fetched since the last call,
+    /// and starts a new frame, as `SliceCache::evict_unused` does.
+    pub fn evict_unused(&mut self) {
+        let frame = self.frame;
+        self.textures.retain(|_, texture| texture.last_used == frame);
+        self.frame += 1;
+    }
+}
+
+/// Returns the color in which `cell` is drawn into the texture of its slice,
+/// in full daylight, or `None` if it is left out of it. Overlays missing from
+/// the registry are not drawn, so they do not leave their tile out.
+fn flat_color<B>(cell: &SliceCell, tile_renderer: &TileRenderer<B>, materials: &MaterialRegistry, palette: &Palette) -> Option<[f32; 4]>
+    where B: Backend,
+{
+    let has_overlay = cell.overlay.and_then(|(id, _)| materials.get(id)).is_some();
+    if !cell.revealed || has_overlay {
+        return None;
+    }
+    materials.get(cell.material).and_then(|material| {
+        tile_renderer.flat_color(material, palette, light_brightness(1.0, cell.light))
+    })
+}
+
+/// Draws the flat-colored tiles of `slice` into the pixels of a texture, as
+/// RGBA bytes indexed by `[z][x]`, leaving the others transparent.
+fn draw_slice<B>(slice: &ChunkArray2d<SliceCell>, tile_renderer: &TileRenderer<B>, materials: &MaterialRegistry, palette: &Palette) -> Vec<u8>
+    where B: Backend,
+{
+    let mut pixels = vec![0; TEXTURE_SIZE * TEXTURE_SIZE * 4];
+    for (z, row) in slice.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let color = match flat_color(cell, tile_renderer, materials, palette) {
+                Some(color) => color,
+                None => continue,
+            };
+            let rgba = [
+                (color[0] * 255.0) as u8,
+                (color[1] * 255.0) as u8,
+                (color[2] * 255.0) as u8,
+                (color[3] * 255.0) as u8,
+            ];
+            for pixel_z in z * PIXELS_PER_TILE..(z + 1) * PIXELS_PER_TILE {
+                for pixel_x in x * PIXELS_PER_TILE..(x + 1) * PIXELS_PER_TILE {
+                    let i = (pixel_z * TEXTURE_SIZE + pixel_x) * 4;
+                    pixels[i..i + 4].copy_from_slice(&rgba);
+                }
+            }
+        }
+    }
+    pixels
+}
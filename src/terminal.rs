@@ -0,0 +1,545 @@
+use std::cell::RefCell;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::rc::Rc;
+use std::str;
+use std::thread;
+use std::time::Duration;
+
+use cgmath::Point2;
+use libc;
+use rand;
+use piston::input::{Button, Event, Input};
+use piston::input::keyboard::Key;
+use rgframework::Scene;
+use world::{Material, MaterialRegistry, Overworld, OVERWORLD_SIZE};
+
+use audio::AudioManager;
+use config::Config;
+use error::{ColonizeError, ColonizeResult};
+use frame_clock::{self, FrameClock};
+use headless::{HeadlessBackend, NullGraphics};
+use localization::Localization;
+use palette::Palette;
+use renderer::{MapTile, Renderer};
+use scene::{GameScene, TEXT_STATUS_LINES};
+use scripting::ScriptHost;
+use settings::Settings;
+use tile_renderer::TileRenderer;
+
+/// Milliseconds between the frames drawn in the terminal.
+const FRAME_INTERVAL: u64 = 50;
+/// Seconds a key is held down after the terminal last sent it. Terminals only
+/// send keys as they are pressed and repeated, so keys are released once they
+/// are no longer repeated.
+const KEY_HOLD_TIME: f64 = 0.15;
+/// Switches to the alternate screen, hides the cursor and has the numeric
+/// keypad send keys of its own.
+const ENTER_ESCAPES: &'static str = "\x1b[?1049h\x1b[?25l\x1b=";
+/// Undoes `ENTER_ESCAPES`, along with any color left set.
+const LEAVE_ESCAPES: &'static str = "\x1b[0m\x1b>\x1b[?25h\x1b[?1049l";
+const ESCAPE: u8 = 0x1b;
+const CTRL_C: u8 = 0x03;
+/// The characters typed with Shift on a US keyboard, and the keys they are
+/// typed with.
+const SHIFTED_CHARS: &'static [(char, char)] = &[
+    ('~', '`'), ('!', '1'), ('@', '2'), ('#', '3'), ('$', '4'), ('%', '5'),
+    ('^', '6'), ('&', '7'), ('*', '8'), ('(', '9'), (')', '0'), ('_', '-'),
+    ('+', '='), ('{', '['), ('}', ']'), ('|', '\\'), (':', ';'), ('"', '\''),
+    ('<', ','), ('>', '.'), ('?', '/'),
+];
+const BLACK: [u8; 3] = [0, 0, 0];
+const BLANK: Cell = Cell {
+    glyph: ' ',
+    color: BLACK,
+    background: BLACK,
+};
+
+/// Runs the game drawn in the terminal with a character per tile, rather than
+/// in a window, starting from the save named `save_name` if there is one.
+///
+/// The keys sent by the terminal are passed to the game scene as those of the
+/// window are, so the same key bindings apply. The menus are not drawn in the
+/// terminal, so leaving the game scene, as Escape does, ends the game, and
+/// the game is saved and loaded from the console.
+pub fn run(config: Config, settings: Settings, localization: Localization, palette: Palette, materials: MaterialRegistry, scripts: ScriptHost, save_name: Option<&str>) -> ColonizeResult<()> {
+    let config = Rc::new(config);
+    let settings = Rc::new(RefCell::new(settings));
+    let localization = Rc::new(RefCell::new(localization));
+    let palette = Rc::new(RefCell::new(palette));
+    let materials = Rc::new(materials);
+    let tile_renderer = Rc::new(TileRenderer::fallback());
+    let audio = Rc::new(AudioManager::silent());
+    let scripts = Rc::new(scripts);
+
+    let mut scene: GameScene<HeadlessBackend> = match save_name {
+        Some(name) => try!(GameScene::load(config, settings, localization, palette.clone(), materials.clone(), tile_renderer, audio, scripts, name)),
+        None => {
+            // New colonies embark at the middle of the overworld, where the
+            // setup scene first puts the site.
+            let world_gen = config.world_gen;
+            let embark = Overworld::cell_center(Point2::new(OVERWORLD_SIZE / 2, OVERWORLD_SIZE / 2));
+            GameScene::new(config, settings, localization, palette.clone(), materials.clone(), tile_renderer, audio, scripts, rand::random(), world_gen, embark)
+        },
+    };
+
+    let terminal = try!(RawTerminal::enter());
+    let mut renderer = TerminalRenderer::new(materials, palette);
+    let mut keys = KeyReader::new();
+    let mut clock = FrameClock::new();
+    let mut stdout = io::stdout();
+    loop {
+        let (columns, rows) = try!(terminal.size());
+        if renderer.resize(columns, rows) {
+            scene.set_view_size(columns as i32, rows.saturating_sub(TEXT_STATUS_LINES) as i32);
+        }
+
+        let dt = frame_clock::duration_to_secs(clock.tick());
+        let events = match try!(keys.read(dt)) {
+            Some(events) => events,
+            None => break,
+        };
+        let mut left = false;
+        for event in &events {
+            left |= Scene::<HeadlessBackend, Event, NullGraphics>::handle_event(&mut scene, event).is_some();
+        }
+        if left || Scene::<HeadlessBackend, Event, NullGraphics>::update(&mut scene, dt).is_some() {
+            break;
+        }
+
+        scene.render_text(&mut renderer);
+        try!(stdout.write_all(renderer.take_output().as_bytes()));
+        try!(stdout.flush());
+
+        thread::sleep(Duration::from_millis(FRAME_INTERVAL));
+    }
+
+    Ok(())
+}
+
+/// Keeps the terminal in raw mode, in which keys are read as they are sent
+/// rather than a line at a time, and shows the game on its alternate screen,
+/// until dropped.
+struct RawTerminal {
+    /// The mode of the terminal before it was put in raw mode.
+    original: libc::termios,
+}
+
+impl RawTerminal {
+    fn enter() -> ColonizeResult<RawTerminal> {
+        if unsafe { libc::isatty(libc::STDIN_FILENO) == 0 || libc::isatty(libc::STDOUT_FILENO) == 0 } {
+            return Err(ColonizeError::Terminal("standard input and output must be a terminal".to_owned()));
+        }
+
+        let mut original: libc::termios = unsafe { mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        let mut raw = original;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        // Reads return right away, with whatever was sent since the last one.
+        raw.c_cc[libc::VMIN] = 0;
+        raw.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        let terminal = RawTerminal {
+            original: original,
+        };
+        let mut stdout = io::stdout();
+        try!(stdout.write_all(ENTER_ESCAPES.as_bytes()));
+        try!(stdout.flush());
+        Ok(terminal)
+    }
+
+    /// Returns the number of columns and rows of the terminal.
+    fn size(&self) -> ColonizeResult<(usize, usize)> {
+        let mut size: libc::winsize = unsafe { mem::zeroed() };
+        if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok((size.ws_col as usize, size.ws_row as usize))
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(LEAVE_ESCAPES.as_bytes());
+        let _ = stdout.flush();
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+}
+
+/// A character of the terminal.
+#[derive(Clone, Copy, PartialEq)]
+struct Cell {
+    glyph: char,
+    color: [u8; 3],
+    background: [u8; 3],
+}
+
+/// Draws into a grid of characters, a tile or a character of text to each,
+/// writing out as a frame is presented the escapes which draw the characters
+/// changed since the last frame.
+struct TerminalRenderer {
+    materials: Rc<MaterialRegistry>,
+    palette: Rc<RefCell<Palette>>,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Cell>,
+    /// The characters as they were written out, if they have been since the
+    /// terminal was last cleared.
+    shown: Vec<Option<Cell>>,
+    output: String,
+}
+
+impl TerminalRenderer {
+    fn new(materials: Rc<MaterialRegistry>, palette: Rc<RefCell<Palette>>) -> Self {
+        TerminalRenderer {
+            materials: materials,
+            palette: palette,
+            columns: 0,
+            rows: 0,
+            cells: Vec::new(),
+            shown: Vec::new(),
+            output: String::new(),
+        }
+    }
+
+    /// Fits the grid to a terminal of `columns` by `rows` characters, clearing
+    /// the terminal. Returns whether the size changed.
+    fn resize(&mut self, columns: usize, rows: usize) -> bool {
+        if columns == self.columns && rows == self.rows {
+            return false;
+        }
+
+        self.columns = columns;
+        self.rows = rows;
+        self.cells = vec![BLANK; columns * rows];
+        self.shown = vec![None; columns * rows];
+        self.output.push_str("\x1b[0m\x1b[2J");
+        true
+    }
+
+    /// Returns the escapes written out since this was last called.
+    fn take_output(&mut self) -> String {
+        mem::replace(&mut self.output, String::new())
+    }
+
+    fn cell_mut(&mut self, column: i64, row: i64) -> Option<&mut Cell> {
+        if column < 0 || row < 0 || column >= self.columns as i64 || row >= self.rows as i64 {
+            return None;
+        }
+        self.cells.get_mut(row as usize * self.columns + column as usize)
+    }
+}
+
+impl Renderer for TerminalRenderer {
+    fn clear(&mut self, color: [f32; 4]) {
+        let color = to_rgb(color, 1.0);
+        for cell in &mut self.cells {
+            *cell = Cell {
+                glyph: ' ',
+                color: color,
+                background: color,
+            };
+        }
+    }
+
+    fn draw_tile(&mut self, pos: Point2<i32>, tile: &MapTile) {
+        let cell = if tile.revealed {
+            // Liquids and other see-through materials are drawn over the
+            // tile beneath them, in a blend of their colors.
+            let palette = self.palette.borrow();
+            let overlay = tile.overlay.and_then(|(id, opacity)| self.materials.get(id).map(|overlay| (overlay, opacity)));
+            let (material, color) = match (overlay, self.materials.get(tile.material)) {
+                (Some((overlay, opacity)), Some(below)) => (overlay, blend(palette.material_color(below), palette.material_color(overlay), opacity)),
+                (Some((overlay, _)), None) => (overlay, palette.material_color(overlay)),
+                (None, Some(material)) => (material, palette.material_color(material)),
+                (None, None) => return,
+            };
+            Cell {
+                glyph: glyph(material),
+                color: to_rgb(color, tile.brightness),
+                background: BLACK,
+            }
+        } else {
+            BLANK
+        };
+
+        if let Some(target) = self.cell_mut(pos.x as i64, pos.y as i64) {
+            *target = cell;
+        }
+    }
+
+    /// Draws `text` from the character at `pos`, in columns and rows, over the
+    /// background of the characters it covers.
+    fn draw_text(&mut self, pos: [f64; 2], text: &str, color: [f32; 4], _font_size: u32) {
+        let color = to_rgb(color, 1.0);
+        let row = pos[1].floor() as i64;
+        let start = pos[0].floor() as i64;
+        for (i, glyph) in text.chars().enumerate() {
+            if let Some(cell) = self.cell_mut(start + i as i64, row) {
+                cell.glyph = glyph;
+                cell.color = color;
+            }
+        }
+    }
+
+    fn present(&mut self) {
+        let mut next = None;
+        let mut colors = None;
+        for (i, &cell) in self.cells.iter().enumerate() {
+            if self.shown[i] == Some(cell) {
+                continue;
+            }
+            self.shown[i] = Some(cell);
+
+            // The cursor only needs to be moved to characters which do not
+            // follow the last one written.
+            if next != Some(i) {
+                self.output.push_str(&format!("\x1b[{};{}H", i / self.columns + 1, i % self.columns + 1));
+            }
+            if colors != Some((cell.color, cell.background)) {
+                let (color, background) = (cell.color, cell.background);
+                self.output.push_str(&format!(
+                    "\x1b[38;2;{};{};{};48;2;{};{};{}m",
+                    color[0], color[1], color[2], background[0], background[1], background[2]));
+                colors = Some((color, background));
+            }
+            self.output.push(cell.glyph);
+            next = Some(i + 1);
+        }
+    }
+}
+
+/// Returns the character drawing `material`.
+fn glyph(material: &Material) -> char {
+    material.glyph.unwrap_or(if material.liquid {
+        '~'
+    } else if material.solid {
+        '#'
+    } else {
+        '.'
+    })
+}
+
+/// Returns the color of a see-through color drawn over another with the
+/// specified opacity.
+fn blend(below: [f32; 4], over: [f32; 4], opacity: f32) -> [f32; 4] {
+    let mix = |i: usize| below[i] * (1.0 - opacity) + over[i] * opacity;
+    [mix(0), mix(1), mix(2), 1.0]
+}
+
+/// Returns the 24-bit color of `color` multiplied by `brightness`.
+fn to_rgb(color: [f32; 4], brightness: f32) -> [u8; 3] {
+    let channel = |i: usize| ((color[i] * brightness).max(0.0).min(1.0) * 255.0).round() as u8;
+    [channel(0), channel(1), channel(2)]
+}
+
+/// Turns the keys sent by the terminal into the events of the window, of keys
+/// being pressed and released and text being typed.
+struct KeyReader {
+    /// The keys held down, with the seconds left until they are released.
+    held: Vec<(Key, f64)>,
+}
+
+impl KeyReader {
+    fn new() -> Self {
+        KeyReader {
+            held: Vec::new(),
+        }
+    }
+
+    /// Reads the keys sent since the last call, given that `dt` seconds have
+    /// passed since. Returns the events they make up, or `None` if the game
+    /// is to be quit, as it is with Ctrl+C.
+    fn read(&mut self, dt: f64) -> ColonizeResult<Option<Vec<Event>>> {
+        let mut events = Vec::new();
+        let held = mem::replace(&mut self.held, Vec::new());
+        for (key, left) in held {
+            if left > dt {
+                self.held.push((key, left - dt));
+            } else {
+                events.push(Event::Input(Input::Release(Button::Keyboard(key))));
+            }
+        }
+
+        let mut input = Vec::new();
+        let mut buffer = [0; 64];
+        loop {
+            let count = try!(io::stdin().read(&mut buffer));
+            if count == 0 {
+                break;
+            }
+            input.extend_from_slice(&buffer[..count]);
+        }
+
+        let mut rest = &input[..];
+        while !rest.is_empty() {
+            if rest[0] == CTRL_C {
+                return Ok(None);
+            }
+
+            let (key, text, len) = parse_key(rest);
+            rest = &rest[len..];
+            if let Some((key, shift)) = key {
+                if shift {
+                    self.press(&mut events, Key::LShift);
+                }
+                self.press(&mut events, key);
+            }
+            if let Some(text) = text {
+                events.push(Event::Input(Input::Text(text)));
+            }
+        }
+        Ok(Some(events))
+    }
+
+    fn press(&mut self, events: &mut Vec<Event>, key: Key) {
+        events.push(Event::Input(Input::Press(Button::Keyboard(key))));
+        match self.held.iter().position(|&(held, _)| held == key) {
+            Some(i) => self.held[i].1 = KEY_HOLD_TIME,
+            None => self.held.push((key, KEY_HOLD_TIME)),
+        }
+    }
+}
+
+/// Returns the key sent at the start of `bytes` along with whether Shift was
+/// held, the text it types, and the number of bytes it was sent as.
+fn parse_key(bytes: &[u8]) -> (Option<(Key, bool)>, Option<String>, usize) {
+    match bytes[0] {
+        ESCAPE => (parse_escape(bytes), None, escape_len(bytes)),
+        b'\r' | b'\n' => (Some((Key::Return, false)), None, 1),
+        b'\t' => (Some((Key::Tab, false)), None, 1),
+        0x08 | 0x7f => (Some((Key::Backspace, false)), None, 1),
+        byte @ 0x20...0x7e => {
+            let ch = byte as char;
+            (char_key(ch), Some(ch.to_string()), 1)
+        },
+        // Characters outside of ASCII only type text.
+        byte if byte >= 0xc0 => {
+            let len = if byte >= 0xf0 { 4 } else if byte >= 0xe0 { 3 } else { 2 };
+            let len = ::std::cmp::min(len, bytes.len());
+            (None, str::from_utf8(&bytes[..len]).ok().map(|text| text.to_owned()), len)
+        },
+        _ => (None, None, 1),
+    }
+}
+
+/// Returns the key typing `ch`, and whether Shift is held to type it.
+fn char_key(ch: char) -> Option<(Key, bool)> {
+    let (unshifted, shift) = match SHIFTED_CHARS.iter().find(|&&(shifted, _)| shifted == ch) {
+        Some(&(_, unshifted)) => (unshifted, true),
+        None if ch >= 'A' && ch <= 'Z' => ((ch as u8 - b'A' + b'a') as char, true),
+        None => (ch, false),
+    };
+    // The keys of the window are numbered by the ASCII characters they type.
+    match Key::from(unshifted as u32) {
+        Key::Unknown => None,
+        key => Some((key, shift)),
+    }
+}
+
+/// Returns the number of bytes of the escape sequence at the start of
+/// `bytes`. An Escape not followed by `[` or `O` is the Escape key alone, as
+/// is one sent last.
+fn escape_len(bytes: &[u8]) -> usize {
+    match bytes.get(1) {
+        Some(&b'O') if bytes.len() > 2 => 3,
+        Some(&b'[') => {
+            match bytes[2..].iter().position(|&byte| byte >= 0x40 && byte <= 0x7e) {
+                Some(end) => end + 3,
+                None => bytes.len(),
+            }
+        },
+        _ => 1,
+    }
+}
+
+/// Returns the key sent as the escape sequence at the start of `bytes`, and
+/// whether Shift was held.
+fn parse_escape(bytes: &[u8]) -> Option<(Key, bool)> {
+    let len = escape_len(bytes);
+    if len == 1 {
+        return Some((Key::Escape, false));
+    }
+
+    // Sequences cut short have no final character.
+    let sequence = match str::from_utf8(&bytes[2..len]) {
+        Ok(sequence) if !sequence.is_empty() => sequence,
+        _ => return None,
+    };
+    if bytes[1] == b'O' {
+        return ss3_key(sequence).map(|key| (key, false));
+    }
+
+    // The parameters of the sequence are its number, and the modifiers held
+    // plus one, of which 2 is Shift alone.
+    let (parameters, last) = sequence.split_at(sequence.len() - 1);
+    let mut parameters = parameters.split(';');
+    let number = parameters.next().unwrap_or("");
+    let shift = parameters.next() == Some("2");
+    let key = match (number, last) {
+        (_, "A") => Key::Up,
+        (_, "B") => Key::Down,
+        (_, "C") => Key::Right,
+        (_, "D") => Key::Left,
+        (_, "H") | ("1", "~") | ("7", "~") => Key::Home,
+        (_, "F") | ("4", "~") | ("8", "~") => Key::End,
+        (_, "P") => Key::F1,
+        (_, "Q") => Key::F2,
+        (_, "R") => Key::F3,
+        (_, "S") => Key::F4,
+        ("2", "~") => Key::Insert,
+        ("3", "~") => Key::Delete,
+        ("5", "~") => Key::PageUp,
+        ("6", "~") => Key::PageDown,
+        ("11", "~") => Key::F1,
+        ("12", "~") => Key::F2,
+        ("13", "~") => Key::F3,
+        ("14", "~") => Key::F4,
+        ("15", "~") => Key::F5,
+        ("17", "~") => Key::F6,
+        ("18", "~") => Key::F7,
+        ("19", "~") => Key::F8,
+        ("20", "~") => Key::F9,
+        ("21", "~") => Key::F10,
+        ("23", "~") => Key::F11,
+        ("24", "~") => Key::F12,
+        _ => return None,
+    };
+    Some((key, shift))
+}
+
+/// Returns the key sent as `ESC O` followed by `sequence`, as the arrow keys,
+/// F1 to F4 and the numeric keypad are in the modes the terminal is put in.
+fn ss3_key(sequence: &str) -> Option<Key> {
+    let key = match sequence {
+        "A" => Key::Up,
+        "B" => Key::Down,
+        "C" => Key::Right,
+        "D" => Key::Left,
+        "H" => Key::Home,
+        "F" => Key::End,
+        "P" => Key::F1,
+        "Q" => Key::F2,
+        "R" => Key::F3,
+        "S" => Key::F4,
+        "M" => Key::NumPadEnter,
+        "p" => Key::NumPad0,
+        "q" => Key::NumPad1,
+        "r" => Key::NumPad2,
+        "s" => Key::NumPad3,
+        "t" => Key::NumPad4,
+        "u" => Key::NumPad5,
+        "v" => Key::NumPad6,
+        "w" => Key::NumPad7,
+        "x" => Key::NumPad8,
+        "y" => Key::NumPad9,
+        _ => return None,
+    };
+    Some(key)
+}
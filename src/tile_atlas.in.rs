@@ -0,0 +1,25 @@
+#[derive(Deserialize, Serialize)]
+pub struct TileAtlas {
+    /// Name of the atlas texture in the asset manifest.
+    pub texture: String,
+    /// Regions of the atlas texture, as `[x, y, width, height]` in pixels,
+    /// keyed by the name of the tile type they depict.
+    pub regions: HashMap<String, [i32; 4]>,
+    /// Animations made of regions of the atlas texture, keyed by name. An
+    /// animation is drawn in place of the region of the same name, if any.
+    #[serde(default)]
+    pub animations: HashMap<String, Animation>,
+}
+
+/// A sequence of regions of the atlas texture, shown one after the other as
+/// the game runs.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Animation {
+    /// Names of the regions shown, in order.
+    pub frames: Vec<String>,
+    /// The number of game ticks for which each frame is shown.
+    pub frame_duration: u32,
+    /// Whether the animation stops on its last frame, rather than looping.
+    #[serde(default)]
+    pub once: bool,
+}
@@ -0,0 +1,49 @@
+use std::cmp;
+use std::collections::HashMap;
+
+use serde_json;
+
+use error::ColonizeResult;
+
+#[cfg(feature = "nightly")]
+include!("tile_atlas.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/tile_atlas.rs"));
+
+impl TileAtlas {
+    pub fn from_json(json: &str) -> ColonizeResult<TileAtlas> {
+        Ok(try!(serde_json::from_str(json)))
+    }
+
+    /// Returns the region of the atlas texture depicting the named tile type.
+    pub fn get_region(&self, name: &str) -> Option<[i32; 4]> {
+        self.regions.get(name).cloned()
+    }
+
+    /// Returns the region of the atlas texture to draw for `name`, which is
+    /// the current frame of the animation of that name if there is one, or
+    /// else the region of that name. `elapsed` is the number of game ticks
+    /// since the animation started.
+    pub fn get_frame(&self, name: &str, elapsed: u64) -> Option<[i32; 4]> {
+        match self.animations.get(name) {
+            Some(animation) => animation.frame_at(elapsed).and_then(|frame| self.get_region(frame)),
+            None => self.get_region(name),
+        }
+    }
+}
+
+impl Animation {
+    /// Returns the name of the frame shown `elapsed` game ticks after the
+    /// animation started, or `None` if it has no frames.
+    pub fn frame_at(&self, elapsed: u64) -> Option<&str> {
+        if self.frames.is_empty() {
+            return None;
+        }
+
+        let count = self.frames.len() as u64;
+        let index = elapsed / cmp::max(self.frame_duration, 1) as u64;
+        let index = if self.once { cmp::min(index, count - 1) } else { index % count };
+        Some(&self.frames[index as usize])
+    }
+}
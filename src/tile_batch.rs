@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use graphics::triangulation;
+use rgframework::backend::Graphics;
+use rgframework::backend::graphics::Context;
+
+/// The distance, in pixels, below which the edges of tiles are considered to
+/// meet, despite rounding errors.
+const EDGE_TOLERANCE: f64 = 1e-6;
+
+/// Identifies the tiles drawn in the same color, such as those of one
+/// material at one light level.
+pub type BatchKey = (u16, u8);
+
+/// Collects the flat-colored tiles of a frame, to draw those of each color
+/// with a single draw call.
+///
+/// Tiles of the same color which are pushed one after the other, each to the
+/// right of the last, are merged into a single wide rectangle.
+pub struct TileBatch {
+    /// The color of each batch, and its rectangles, in pixels as `[x, y,
+    /// width, height]`.
+    batches: Vec<([f32; 4], Vec<[f64; 4]>)>,
+    indices: HashMap<BatchKey, usize>,
+    /// The rectangle being extended by the tiles pushed to the right of it,
+    /// and the batch it belongs to.
+    run: Option<(usize, [f64; 4])>,
+}
+
+impl TileBatch {
+    pub fn new() -> Self {
+        TileBatch {
+            batches: Vec::new(),
+            indices: HashMap::new(),
+            run: None,
+        }
+    }
+
+    /// Adds a tile of the specified color to the batch identified by `key`.
+    /// Every tile of a batch must be of the color of the first.
+    pub fn push(&mut self, key: BatchKey, color: [f32; 4], rect: [f64; 4]) {
+        let index = {
+            let batches = &mut self.batches;
+            *self.indices.entry(key).or_insert_with(|| {
+                batches.push((color, Vec::new()));
+                batches.len() - 1
+            })
+        };
+
+        if let Some((run_index, ref mut run)) = self.run {
+            let adjacent = nearly_equal(run[1], rect[1]) && nearly_equal(run[3], rect[3]) && nearly_equal(run[0] + run[2], rect[0]);
+            if run_index == index && adjacent {
+                run[2] += rect[2];
+                return;
+            }
+        }
+        self.end_run();
+        self.run = Some((index, rect));
+    }
+
+    /// Returns the number of draw calls with which the batch is drawn.
+    pub fn draw_call_count(&self) -> usize {
+        self.batches.len()
+    }
+
+    /// Draws every batch, and empties it.
+    pub fn draw<G>(&mut self, context: &Context, graphics: &mut G)
+        where G: Graphics,
+    {
+        self.end_run();
+        for (color, rects) in self.batches.drain(..) {
+            graphics.tri_list(&context.draw_state, &color, |f| {
+                for rect in &rects {
+                    f(&triangulation::rect_tri_list_xy(context.transform, *rect));
+                }
+            });
+        }
+        self.indices.clear();
+    }
+
+    fn end_run(&mut self) {
+        if let Some((index, rect)) = self.run.take() {
+            self.batches[index].1.push(rect);
+        }
+    }
+}
+
+fn nearly_equal(a: f64, b: f64) -> bool {
+    (a - b).abs() < EDGE_TOLERANCE
+}
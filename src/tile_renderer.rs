@@ -0,0 +1,135 @@
+use std::cell::{Cell, RefCell};
+
+use rgframework::backend::{Backend, Graphics};
+use rgframework::backend::graphics::Context;
+use world::Material;
+
+use palette::Palette;
+use tile_atlas::TileAtlas;
+
+/// Draws tiles from a texture atlas, falling back to flat colored rectangles
+/// for any material which the atlas does not provide, or which the palette
+/// recolors.
+///
+/// The atlas can be replaced while the renderer is shared between scenes, so
+/// that it can be loaded again once its files have been modified.
+///
+/// Materials whose texture is animated in the atlas are drawn at the frame
+/// reached at the game time last set.
+pub struct TileRenderer<B>
+    where B: Backend,
+{
+    atlas: RefCell<Option<(B::Texture, TileAtlas)>>,
+    /// The game time, in ticks, at which animated tiles are drawn.
+    time: Cell<u64>,
+    /// The number of times the atlas has been replaced.
+    atlas_version: Cell<u64>,
+}
+
+impl<B> TileRenderer<B>
+    where B: Backend,
+{
+    pub fn new(texture: B::Texture, atlas: TileAtlas) -> Self {
+        TileRenderer {
+            atlas: RefCell::new(Some((texture, atlas))),
+            time: Cell::new(0),
+            atlas_version: Cell::new(0),
+        }
+    }
+
+    /// Creates a `TileRenderer` which draws every tile as a colored rectangle.
+    pub fn fallback() -> Self {
+        TileRenderer {
+            atlas: RefCell::new(None),
+            time: Cell::new(0),
+            atlas_version: Cell::new(0),
+        }
+    }
+
+    /// Replaces the atlas and its texture, or gives a `TileRenderer` created
+    /// as a fallback its first.
+    pub fn set_atlas(&self, texture: B::Texture, atlas: TileAtlas) {
+        *self.atlas.borrow_mut() = Some((texture, atlas));
+        self.atlas_version.set(self.atlas_version.get() + 1);
+    }
+
+    /// Returns a number which changes whenever the atlas is replaced, so that
+    /// whatever was drawn with the previous one can be drawn again.
+    pub fn atlas_version(&self) -> u64 {
+        self.atlas_version.get()
+    }
+
+    /// Sets the game time, in ticks, at which animated tiles are drawn.
+    pub fn set_time(&self, ticks: u64) {
+        self.time.set(ticks);
+    }
+
+    /// Draws a tile of the specified material into `rect`, given in pixels as
+    /// `[x, y, width, height]`, with the specified brightness (by which its
+    /// color is multiplied) and opacity. Materials without a texture, or
+    /// recolored by `palette`, are drawn in their color from `palette`, unless
+    /// it is fully transparent.
+    pub fn draw_tile<G>(&self, material: &Material, palette: &Palette, brightness: f32, opacity: f32, rect: [f64; 4], context: &Context, graphics: &mut G)
+        where G: Graphics<Texture=B::Texture>,
+    {
+        use graphics::{Image, Rectangle};
+
+        let atlas = self.atlas.borrow();
+        let region = if palette.recolors(material) { None } else { get_region(&atlas, material, self.time.get()) };
+        let color = palette.material_color(material);
+        if let Some((texture, region)) = region {
+            Image::new_color([brightness, brightness, brightness, opacity])
+                .rect(rect)
+                .src_rect(region)
+                .draw(texture, &context.draw_state, context.transform, graphics);
+        } else if color[3] > 0.0 {
+            Rectangle::new([color[0] * brightness, color[1] * brightness, color[2] * brightness, opacity]).draw(rect, &context.draw_state, context.transform, graphics);
+        }
+    }
+
+    /// Returns the color in which a tile of `material` is drawn with the
+    /// specified brightness and `palette`, or `None` if it is drawn with a
+    /// texture, or not drawn at all.
+    pub fn flat_color(&self, material: &Material, palette: &Palette, brightness: f32) -> Option<[f32; 4]> {
+        let atlas = self.atlas.borrow();
+        let color = palette.material_color(material);
+        let textured = !palette.recolors(material) && get_region(&atlas, material, self.time.get()).is_some();
+        if textured || color[3] <= 0.0 {
+            return None;
+        }
+        Some([color[0] * brightness, color[1] * brightness, color[2] * brightness, 1.0])
+    }
+
+    /// Draws the region or the animation of the atlas named `sprite` into
+    /// `rect`, tinted with `color`. Animations are drawn at the frame reached
+    /// `elapsed` game ticks after they started. Returns `false`, having drawn
+    /// nothing, if the atlas has no such sprite.
+    pub fn draw_sprite<G>(&self, sprite: &str, elapsed: u64, color: [f32; 4], rect: [f64; 4], context: &Context, graphics: &mut G) -> bool
+        where G: Graphics<Texture=B::Texture>,
+    {
+        use graphics::Image;
+
+        let atlas = self.atlas.borrow();
+        let region = match *atlas {
+            Some((ref texture, ref atlas)) => atlas.get_frame(sprite, elapsed).map(|region| (texture, region)),
+            None => None,
+        };
+        match region {
+            Some((texture, region)) => {
+                Image::new_color(color)
+                    .rect(rect)
+                    .src_rect(region)
+                    .draw(texture, &context.draw_state, context.transform, graphics);
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+fn get_region<'a, T>(atlas: &'a Option<(T, TileAtlas)>, material: &Material, time: u64) -> Option<(&'a T, [i32; 4])> {
+    match (atlas, &material.texture) {
+        (&Some((ref texture, ref atlas)), &Some(ref name)) => atlas.get_frame(name, time).map(|region| (texture, region)),
+        _ => None,
+    }
+}
@@ -2,6 +2,10 @@
 #![cfg_attr(feature = "clippy", plugin(clippy))]
 
 extern crate cgmath;
+#[macro_use]
+extern crate lazy_static;
+
+pub mod profiler;
 
 use std::ops::Sub;
 
@@ -0,0 +1,190 @@
+//! Lightweight scoped timers, aggregated per frame and optionally recorded as
+//! a trace which can be opened in the `chrome://tracing` viewer of Chrome.
+//!
+//! Timing is off until enabled with `set_enabled`. While it is, a scope only
+//! costs a check of a flag.
+
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::sync::atomic::{ATOMIC_BOOL_INIT, ATOMIC_USIZE_INIT, AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = ATOMIC_BOOL_INIT;
+static NEXT_THREAD_INDEX: AtomicUsize = ATOMIC_USIZE_INIT;
+
+lazy_static! {
+    static ref PROFILE: Mutex<Profile> = Mutex::new(Profile::new());
+}
+
+thread_local! {
+    /// The index by which the scopes timed on this thread are told apart in
+    /// traces, assigned when the first one is.
+    static THREAD_INDEX: Cell<Option<usize>> = Cell::new(None);
+}
+
+/// The total time spent in the scopes of one name.
+#[derive(Clone, Copy)]
+pub struct ScopeTotal {
+    pub name: &'static str,
+    pub duration: Duration,
+    /// The number of times a scope of this name was timed.
+    pub count: u32,
+}
+
+/// A scope timed for a trace.
+struct TraceEvent {
+    name: &'static str,
+    thread: usize,
+    /// The time from the start of the trace to the start of the scope.
+    start: Duration,
+    duration: Duration,
+}
+
+struct Profile {
+    frame: Vec<ScopeTotal>,
+    /// The start of the trace being recorded and its events, if one is.
+    trace: Option<(Instant, Vec<TraceEvent>)>,
+}
+
+impl Profile {
+    fn new() -> Self {
+        Profile {
+            frame: Vec::new(),
+            trace: None,
+        }
+    }
+
+    fn record(&mut self, name: &'static str, start: Instant, duration: Duration) {
+        match self.frame.iter().position(|total| total.name == name) {
+            Some(index) => {
+                let total = &mut self.frame[index];
+                total.duration += duration;
+                total.count += 1;
+            },
+            None => self.frame.push(ScopeTotal {
+                name: name,
+                duration: duration,
+                count: 1,
+            }),
+        }
+
+        if let Some((trace_start, ref mut events)) = self.trace {
+            // Scopes which started before the trace are left out.
+            if start >= trace_start {
+                events.push(TraceEvent {
+                    name: name,
+                    thread: thread_index(),
+                    start: start - trace_start,
+                    duration: duration,
+                });
+            }
+        }
+    }
+}
+
+/// A timer which records the time from its creation until it is dropped under
+/// its name.
+pub struct Scope {
+    name: &'static str,
+    start: Option<Instant>,
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            let duration = start.elapsed();
+            if let Ok(mut profile) = PROFILE.lock() {
+                profile.record(self.name, start, duration);
+            }
+        }
+    }
+}
+
+/// Starts timing a scope under `name`, until the returned timer is dropped.
+pub fn scope(name: &'static str) -> Scope {
+    Scope {
+        name: name,
+        start: if is_enabled() { Some(Instant::now()) } else { None },
+    }
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Turns timing on or off. The scopes started while it is off are never
+/// recorded.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns the total time spent in each scope since the last call, in the
+/// order in which they were first recorded.
+pub fn end_frame() -> Vec<ScopeTotal> {
+    match PROFILE.lock() {
+        Ok(mut profile) => ::std::mem::replace(&mut profile.frame, Vec::new()),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn is_tracing() -> bool {
+    PROFILE.lock().map(|profile| profile.trace.is_some()).unwrap_or(false)
+}
+
+/// Starts recording every timed scope for a trace, discarding any trace
+/// already being recorded.
+pub fn start_trace() {
+    if let Ok(mut profile) = PROFILE.lock() {
+        profile.trace = Some((Instant::now(), Vec::new()));
+    }
+}
+
+/// Stops recording the trace, writing it to `writer` in the JSON format of
+/// the Chrome trace viewer. Writes nothing if no trace was being recorded.
+pub fn finish_trace<W>(writer: &mut W) -> io::Result<()>
+    where W: Write,
+{
+    let trace = match PROFILE.lock() {
+        Ok(mut profile) => profile.trace.take(),
+        Err(_) => None,
+    };
+    let events = match trace {
+        Some((_, events)) => events,
+        None => return Ok(()),
+    };
+
+    try!(writeln!(writer, "{{\"traceEvents\":["));
+    for (i, event) in events.iter().enumerate() {
+        let separator = if i + 1 < events.len() { "," } else { "" };
+        // Scope names are identifiers in the source, so they need no
+        // escaping.
+        try!(writeln!(
+            writer,
+            "{{\"name\":\"{}\",\"ph\":\"X\",\"pid\":0,\"tid\":{},\"ts\":{},\"dur\":{}}}{}",
+            event.name,
+            event.thread,
+            duration_to_micros(event.start),
+            duration_to_micros(event.duration),
+            separator));
+    }
+    try!(writeln!(writer, "]}}"));
+    Ok(())
+}
+
+fn thread_index() -> usize {
+    THREAD_INDEX.with(|index| {
+        match index.get() {
+            Some(index) => index,
+            None => {
+                let next = NEXT_THREAD_INDEX.fetch_add(1, Ordering::Relaxed);
+                index.set(Some(next));
+                next
+            },
+        }
+    })
+}
+
+fn duration_to_micros(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000 + duration.subsec_nanos() as u64 / 1000
+}
@@ -0,0 +1,89 @@
+#![feature(test)]
+
+extern crate cgmath;
+extern crate colonize_world;
+extern crate test;
+
+use cgmath::Point3;
+use colonize_world::{Chunk, ChunkPos, MaterialRegistry, Tiles, WorldGenConfig, WorldGenerator, CHUNK_SIZE, decode_chunk, encode_chunk};
+use test::Bencher;
+
+const SEED: u32 = 0;
+/// The range of heights, in chunks, searched for the chunk the surface
+/// passes through.
+const SURFACE_SEARCH_RANGE: i32 = 4;
+
+fn setup() -> WorldGenerator {
+    let materials = MaterialRegistry::default();
+    WorldGenerator::new(SEED, WorldGenConfig::default(), &materials)
+}
+
+/// Returns the position of the chunk at the origin of the X and Z axes with
+/// the most kinds of tiles, which is the one the surface passes through.
+///
+/// Chunks of a single kind of tile, such as those of air or deep stone, are
+/// stored without a tile index at all, so they would not measure much.
+fn surface_chunk_pos(generator: &WorldGenerator) -> ChunkPos {
+    (-SURFACE_SEARCH_RANGE..SURFACE_SEARCH_RANGE)
+        .map(|y| Point3::new(0, y, 0))
+        .max_by_key(|&pos| generator.generate_chunk(pos).palette().len())
+        .expect("the search range is not empty")
+}
+
+/// Copies the tiles of `chunk` into an array indexed by `[y][z][x]`.
+fn unpack(chunk: &Chunk) -> Tiles {
+    let mut tiles = [[[chunk.get_tile(&Point3::new(0, 0, 0)); CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+    for (y, slice) in tiles.iter_mut().enumerate() {
+        for (z, row) in slice.iter_mut().enumerate() {
+            for (x, tile) in row.iter_mut().enumerate() {
+                *tile = chunk.get_tile(&Point3::new(x, y, z));
+            }
+        }
+    }
+    tiles
+}
+
+#[bench]
+fn bench_generate_chunk(b: &mut Bencher) {
+    let generator = setup();
+    let pos = surface_chunk_pos(&generator);
+    b.iter(|| generator.generate_chunk(pos));
+}
+
+#[bench]
+fn bench_from_tiles(b: &mut Bencher) {
+    let generator = setup();
+    let tiles = unpack(&generator.generate_chunk(surface_chunk_pos(&generator)));
+    b.iter(|| Chunk::from_tiles(&tiles));
+}
+
+/// Reads every row of the chunk, as the tiles of the view are read when the
+/// world is rendered.
+#[bench]
+fn bench_iter_row(b: &mut Bencher) {
+    let generator = setup();
+    let chunk = generator.generate_chunk(surface_chunk_pos(&generator));
+    b.iter(|| {
+        let mut fluid = 0u32;
+        for y in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                fluid += chunk.iter_row(y, z).map(|tile| tile.fluid_level as u32).sum::<u32>();
+            }
+        }
+        fluid
+    });
+}
+
+#[bench]
+fn bench_encode_chunk(b: &mut Bencher) {
+    let generator = setup();
+    let chunk = generator.generate_chunk(surface_chunk_pos(&generator));
+    b.iter(|| encode_chunk(&chunk));
+}
+
+#[bench]
+fn bench_decode_chunk(b: &mut Bencher) {
+    let generator = setup();
+    let data = encode_chunk(&generator.generate_chunk(surface_chunk_pos(&generator)));
+    b.iter(|| decode_chunk(&data).expect("the encoded chunk is valid"));
+}
@@ -0,0 +1,63 @@
+#![feature(test)]
+
+extern crate cgmath;
+extern crate colonize_world;
+extern crate test;
+
+use cgmath::{Point2, Point3};
+use colonize_world::{Area, MaterialRegistry, CHUNK_SIZE, NavGraph, WorldGenConfig, find_path, is_standable};
+use test::Bencher;
+
+const SEED: u32 = 0;
+/// The number of chunks generated on each side of the origin, along each
+/// axis.
+const INITIAL_SIZE: u32 = 3;
+/// The distance from the origin of the ends of the route, along each
+/// horizontal axis.
+const ROUTE_EXTENT: i32 = 40;
+
+/// Generates an area and picks the ends of a route across it, on the surface
+/// at opposite corners.
+fn setup() -> (Area, MaterialRegistry, Point3<i32>, Point3<i32>) {
+    let materials = MaterialRegistry::default();
+    let area = Area::new(SEED, INITIAL_SIZE, Point2::new(0, 0), WorldGenConfig::default(), &materials, None);
+    let start = find_ground(&area, &materials, -ROUTE_EXTENT, -ROUTE_EXTENT);
+    let target = find_ground(&area, &materials, ROUTE_EXTENT, ROUTE_EXTENT);
+    (area, materials, start, target)
+}
+
+/// Returns the highest tile which can be stood in at the specified position
+/// along the X and Z axes.
+fn find_ground(area: &Area, materials: &MaterialRegistry, x: i32, z: i32) -> Point3<i32> {
+    let top = INITIAL_SIZE as i32 * CHUNK_SIZE as i32 - 1;
+    (-top..top).rev()
+        .map(|y| Point3::new(x, y, z))
+        .find(|pos| is_standable(area, materials, pos))
+        .expect("the generated area has ground to stand on")
+}
+
+#[bench]
+fn bench_find_path_by_tile(b: &mut Bencher) {
+    let (area, materials, start, target) = setup();
+    b.iter(|| find_path(&area, &materials, start, target, false));
+}
+
+#[bench]
+fn bench_find_path_by_zone(b: &mut Bencher) {
+    let (area, materials, start, target) = setup();
+    let mut navigation = NavGraph::new();
+    b.iter(|| navigation.find_path(&area, &materials, start, target, false));
+}
+
+/// Finds the route after modifying a chunk along it every time, so that the
+/// zones of that chunk must be found again.
+#[bench]
+fn bench_find_path_by_zone_after_change(b: &mut Bencher) {
+    let (mut area, materials, start, target) = setup();
+    let mut navigation = NavGraph::new();
+    let tile = area.get_tile(&target);
+    b.iter(|| {
+        area.set_tile(&target, tile);
+        navigation.find_path(&area, &materials, start, target, false)
+    });
+}
@@ -10,7 +10,16 @@ mod inner {
         let out_dir = env::var_os("OUT_DIR").unwrap();
 
         for &(src, dst) in &[
+            ("src/calendar.in.rs", "calendar.rs"),
+            ("src/components.in.rs", "components.rs"),
             ("src/direction.in.rs", "direction.rs"),
+            ("src/entity.in.rs", "entity.rs"),
+            ("src/material.in.rs", "material.rs"),
+            ("src/rng.in.rs", "rng.rs"),
+            ("src/stats.in.rs", "stats.rs"),
+            ("src/weather.in.rs", "weather.rs"),
+            ("src/world.in.rs", "world.rs"),
+            ("src/world_gen_config.in.rs", "world_gen_config.rs"),
         ] {
             let src = Path::new(src);
             let dst = Path::new(&out_dir).join(dst);
@@ -1,84 +1,579 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
 
-use noise::{ Seed, open_simplex2 };
-use cgmath::Point3;
+use cgmath::{Point2, Point3};
+use rayon::prelude::*;
 
 use { CHUNK_SIZE, LOG2_OF_CHUNK_SIZE };
-use chunk::Chunk;
-use terrain::{ Tile, TileType };
-use mapgen;
-
-// TODO: refactor these values to be configurable.
-const NOISE_SCALING_FACTOR: f64 = 1.0 / 64.0;
-const NOISE_GENERATOR: fn(&Seed, &[f64; 2]) -> f64 = scaled_open_simplex2;
+use biome::Biome;
+use chunk::{Chunk, ChunkPos, TileMetadata};
+use chunk_loader::{self, ChunkLoader, ChunkLoadError};
+use direction::Direction;
+use light::MAX_LIGHT_LEVEL;
+use mapgen::WorldGenerator;
+use material::{MaterialId, MaterialRegistry};
+use region;
+use region::RegionStore;
+use terrain::Tile;
+use world_gen_config::WorldGenConfig;
 
 pub struct Area {
-    chunks: HashMap<Point3<i32>, Chunk>,
-    seed: Seed,
+    chunks: HashMap<ChunkPos, Chunk>,
+    /// The frame during which each loaded chunk was last within the range
+    /// requested by `request_chunks_around`.
+    last_used: HashMap<ChunkPos, u64>,
+    /// Incremented on every call to `request_chunks_around`.
+    frame: u64,
+    /// The latest revision given to any chunk of the area, incremented for
+    /// every revision given.
+    revision: u64,
+    generator: Arc<WorldGenerator>,
+    loader: ChunkLoader,
+    /// Positions of the chunks which failed to load, which are never
+    /// requested again, so that a chunk the store could not read is not
+    /// generated over.
+    failed: HashSet<ChunkPos>,
+    /// The errors encountered while loading chunks, not yet returned by
+    /// `receive_chunks`.
+    failures: Vec<ChunkLoadError>,
+    /// Where the chunks of the area were last saved, if anywhere.
+    store: Option<Arc<RegionStore>>,
+    /// A temporary store to which modified chunks are unloaded until the area
+    /// is next saved, so that they never reach a save the player has not
+    /// chosen to save.
+    scratch: Option<Arc<RegionStore>>,
+    /// Positions of the tiles whose fluid may need to move during the next
+    /// step of the fluid simulation.
+    active_fluids: HashSet<Point3<i32>>,
+    /// Positions of the loaded chunks whose light must be recomputed.
+    unlit: HashSet<ChunkPos>,
+    /// Positions of the chunks generated (rather than loaded from the store)
+    /// since they were last taken.
+    generated: Vec<ChunkPos>,
+    /// The tiles whose material has been set since they were last taken,
+    /// with the materials they were made of before and after.
+    changed_tiles: Vec<(Point3<i32>, MaterialId, MaterialId)>,
+    /// The chunks whose tiles have been set since the journal was last
+    /// taken, encoded as they were before the first of their tiles was set,
+    /// if the area is journaling.
+    journal: Option<HashMap<ChunkPos, Vec<u8>>>,
 }
 
 impl Area {
-    pub fn new(rng_seed: u32, initial_size: u32) -> Self {
+    /// Creates an area, generating the chunks within `initial_size` chunks of
+    /// the surface chunk of the chunk column `center` (or loading those which
+    /// were saved to `store`).
+    pub fn new(rng_seed: u32, initial_size: u32, center: Point2<i32>, config: WorldGenConfig, materials: &MaterialRegistry, store: Option<RegionStore>) -> Self {
+        let generator = Arc::new(WorldGenerator::new(rng_seed, config, materials));
+        let store = store.map(Arc::new);
         let mut area = Area {
             chunks: HashMap::new(),
-            seed: Seed::new(rng_seed),
+            last_used: HashMap::new(),
+            frame: 0,
+            revision: 0,
+            loader: ChunkLoader::new(generator.clone(), store.iter().cloned().collect()),
+            failed: HashSet::new(),
+            failures: Vec::new(),
+            store: store,
+            scratch: None,
+            generator: generator,
+            active_fluids: HashSet::new(),
+            unlit: HashSet::new(),
+            generated: Vec::new(),
+            changed_tiles: Vec::new(),
+            journal: None,
         };
 
         // We take a u32 and convert to an i32 internally because we generate
-        // around (0, 0). but we also want to only accept valid input.
+        // around the center, but we also want to only accept valid input.
         // TODO: find a better way to do this.
         let initial_size = initial_size as i32;
+        let center = area.surface_chunk(center);
 
-        for z in -initial_size..initial_size {
-            for x in -initial_size..initial_size {
-                // Since the height map is 2D, along the X and Y axes, we only
-                // generate it once per column.
-                // This reduces the number of calls to `generate_height_map`
-                // from `initial_size^3` to `initial_size^2`.
-                let mut pos = Point3::new(x, 0, z);
-                let height_map = mapgen::generate_height_map(
-                    &area.seed,
-                    &pos,
-                    NOISE_GENERATOR);
-                for y in -initial_size..initial_size {
-                    pos.y = y;
-                    mapgen::generate_chunk(
-                        pos,
-                        height_map,
-                        |p, c| { area.add_chunk(p, c); });
+        // The initial area is generated in parallel, but waited for, so that
+        // the game never starts in an empty world. The chunks are added in
+        // the order of their positions, whichever finishes first. Those which
+        // fail to load are left out, and reported by `receive_chunks`.
+        let mut positions = Vec::new();
+        for z in center.z - initial_size..center.z + initial_size {
+            for x in center.x - initial_size..center.x + initial_size {
+                for y in center.y - initial_size..center.y + initial_size {
+                    positions.push(Point3::new(x, y, z));
                 }
             }
         }
+        let mut chunks = Vec::new();
+        {
+            let generator = &*area.generator;
+            let stores = area.stores();
+            positions.par_iter()
+                .map(|&pos| chunk_loader::load_or_generate(generator, &stores, pos))
+                .collect_into(&mut chunks);
+        }
+        for (pos, result) in positions.into_iter().zip(chunks) {
+            area.receive_chunk(pos, result);
+        }
 
         area
     }
 
-    pub fn add_chunk(&mut self, p: Point3<i32>, c: Chunk) {
+    /// Requests the generation of every chunk within `radius` chunks of
+    /// `center` along the horizontal axes, and within one chunk of it along
+    /// the vertical axis, which has not been generated yet.
+    ///
+    /// The chunks within this range which are already loaded are marked as
+    /// used, and are never unloaded until they fall out of range. Chunks
+    /// which failed to load are never requested again.
+    ///
+    /// Fails if the workers loading the chunks have stopped, in which case
+    /// they are started again, and the chunks requested again on the next
+    /// call.
+    pub fn request_chunks_around(&mut self, center: ChunkPos, radius: i32) -> Result<(), ChunkLoadError> {
+        self.frame += 1;
+
+        for y in center.y - 1..center.y + 2 {
+            for z in center.z - radius..center.z + radius + 1 {
+                for x in center.x - radius..center.x + radius + 1 {
+                    let pos = Point3::new(x, y, z);
+                    if let Some(last_used) = self.last_used.get_mut(&pos) {
+                        *last_used = self.frame;
+                        continue;
+                    }
+                    if self.failed.contains(&pos) {
+                        continue;
+                    }
+                    if let Err(e) = self.loader.request(pos) {
+                        self.restart_loader();
+                        return Err(e);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Unloads the least recently used chunks until the loaded chunks fit
+    /// within `budget` bytes, returning the number of chunks unloaded.
+    ///
+    /// Chunks modified since they were last saved are written to a temporary
+    /// store first, so that they are loaded from it rather than regenerated
+    /// when requested again. They are only written to the store of the save
+    /// once the area is saved, so that a game quit without saving leaves its
+    /// save as it was.
+    pub fn unload_chunks(&mut self, budget: usize) -> io::Result<usize> {
+        let usage = self.memory_usage();
+        if usage <= budget {
+            return Ok(0);
+        }
+
+        // Chunks in use during the current frame are never unloaded, even if
+        // that means exceeding the budget.
+        let frame = self.frame;
+        let mut candidates: Vec<(u64, ChunkPos)> = self.last_used.iter()
+            .filter(|&(_, &last_used)| last_used < frame)
+            .map(|(pos, last_used)| (*last_used, *pos))
+            .collect();
+        candidates.sort_by_key(|&(last_used, _)| last_used);
+
+        let mut excess = usage - budget;
+        let mut evicted = Vec::new();
+        for (_, pos) in candidates {
+            if excess == 0 {
+                break;
+            }
+            excess = excess.saturating_sub(self.chunks[&pos].memory_usage());
+            evicted.push(pos);
+        }
+
+        let has_dirty = evicted.iter().any(|pos| self.chunks[pos].is_dirty());
+        if has_dirty {
+            let scratch = try!(self.scratch());
+            let dirty: Vec<_> = evicted.iter()
+                .map(|pos| (*pos, &self.chunks[pos]))
+                .filter(|&(_, chunk)| chunk.is_dirty())
+                .collect();
+            try!(scratch.save_chunks(&dirty));
+        }
+
+        for pos in &evicted {
+            self.chunks.remove(pos);
+            self.last_used.remove(pos);
+            self.unlit.remove(pos);
+        }
+        // Sunlight now enters the chunks below the evicted ones from the sky.
+        for pos in &evicted {
+            self.queue_relight(*pos + Direction::Down.to_vector());
+        }
+
+        Ok(evicted.len())
+    }
+
+    /// Returns the temporary store to which modified chunks are unloaded,
+    /// creating it if needed.
+    fn scratch(&mut self) -> io::Result<Arc<RegionStore>> {
+        if let Some(ref scratch) = self.scratch {
+            return Ok(scratch.clone());
+        }
+
+        let scratch = Arc::new(try!(RegionStore::temporary()));
+        self.scratch = Some(scratch.clone());
+        self.restart_loader();
+        Ok(scratch)
+    }
+
+    /// Returns the directories of the region stores holding the chunks of
+    /// the area which are not loaded: the store the area was last saved to,
+    /// if any, followed by the temporary store of the chunks modified and
+    /// unloaded since, which take precedence over it.
+    pub fn store_directories(&self) -> Vec<&Path> {
+        self.store.iter().chain(self.scratch.iter()).map(|store| store.directory()).collect()
+    }
+
+    /// Returns the stores from which chunks are loaded, the chunks unloaded
+    /// since the last save first.
+    fn stores(&self) -> Vec<Arc<RegionStore>> {
+        self.scratch.iter().chain(self.store.iter()).cloned().collect()
+    }
+
+    /// Replaces the chunk loader with one loading chunks from the current
+    /// stores.
+    fn restart_loader(&mut self) {
+        // Any chunks still being generated are requested again once the new
+        // loader is running. The old loader's workers finish their current
+        // chunks in the background, without holding up the frame.
+        self.loader = ChunkLoader::new(self.generator.clone(), self.stores());
+    }
+
+    /// Adds every chunk which has finished generating in the background
+    /// since the last call, returning the errors of those which failed to
+    /// load.
+    pub fn receive_chunks(&mut self) -> Vec<ChunkLoadError> {
+        for (pos, result) in self.loader.receive() {
+            self.receive_chunk(pos, result);
+        }
+        ::std::mem::replace(&mut self.failures, Vec::new())
+    }
+
+    fn receive_chunk(&mut self, pos: ChunkPos, result: Result<(Chunk, bool), ChunkLoadError>) {
+        match result {
+            Ok((chunk, generated)) => {
+                if generated {
+                    self.generated.push(pos);
+                }
+                self.add_chunk(pos, chunk);
+            },
+            Err(e) => {
+                self.failed.insert(pos);
+                self.failures.push(e);
+            },
+        }
+    }
+
+    /// Returns the positions of the chunks generated since this was last
+    /// called, so that every chunk is only seen once. Chunks loaded from the
+    /// region store are left out, as they were seen when first generated.
+    pub fn take_generated_chunks(&mut self) -> Vec<ChunkPos> {
+        ::std::mem::replace(&mut self.generated, Vec::new())
+    }
+
+    /// Returns the positions of the tiles whose material has been changed by
+    /// `set_tile` since this was last called, with the materials they were
+    /// made of before and after. Changes to the level of fluid in a tile
+    /// alone are left out.
+    pub fn take_changed_tiles(&mut self) -> Vec<(Point3<i32>, MaterialId, MaterialId)> {
+        ::std::mem::replace(&mut self.changed_tiles, Vec::new())
+    }
+
+    /// Saves every chunk modified since the last save to `store`.
+    ///
+    /// If the area was last saved elsewhere, the previously saved chunks are
+    /// copied over first, and chunks are saved to and loaded from `store`
+    /// from then on. The chunks unloaded since the last save are then merged
+    /// into `store`, and their temporary store is discarded.
+    pub fn save(&mut self, store: RegionStore) -> io::Result<()> {
+        let is_new_store = match self.store {
+            Some(ref current) => current.directory() != store.directory(),
+            None => true,
+        };
+        if is_new_store {
+            if let Some(ref current) = self.store {
+                try!(current.copy_to(&store));
+            }
+        }
+        if let Some(ref scratch) = self.scratch {
+            try!(scratch.merge_into(&store));
+        }
+
+        {
+            let dirty: Vec<_> = self.chunks.iter()
+                .filter(|&(_, chunk)| chunk.is_dirty())
+                .map(|(pos, chunk)| (*pos, chunk))
+                .collect();
+            try!(store.save_chunks(&dirty));
+        }
+        for chunk in self.chunks.values_mut() {
+            chunk.mark_saved();
+        }
+
+        let had_scratch = self.scratch.take().is_some();
+        if is_new_store {
+            self.store = Some(Arc::new(store));
+        }
+        if is_new_store || had_scratch {
+            self.restart_loader();
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of chunks waiting to be generated.
+    pub fn pending_chunk_count(&self) -> usize {
+        self.loader.pending_count()
+    }
+
+    pub fn add_chunk(&mut self, p: ChunkPos, mut c: Chunk) {
+        self.revision += 1;
+        c.set_revision(self.revision);
         self.chunks.insert(p, c);
+        self.last_used.insert(p, self.frame);
+
+        // The new chunk may block the sunlight reaching its neighbours, or let
+        // light into them.
+        self.queue_relight(p);
+        for direction in &[Direction::North, Direction::East, Direction::South, Direction::West, Direction::Up, Direction::Down] {
+            self.queue_relight(p + direction.to_vector());
+        }
+    }
+
+    /// Returns the number of chunks currently loaded.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns every loaded chunk along with its position, in no particular
+    /// order.
+    pub fn loaded_chunks(&self) -> hash_map::Iter<ChunkPos, Chunk> {
+        self.chunks.iter()
+    }
+
+    /// Returns the approximate number of bytes used by the loaded chunks.
+    pub fn memory_usage(&self) -> usize {
+        self.chunks.values().map(Chunk::memory_usage).sum()
+    }
+
+    /// Returns the biome at the specified absolute position along the X and Z
+    /// axes.
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        self.generator.biome_at(x, z)
+    }
+
+    /// Returns the elevation at which the surface was generated at the
+    /// specified absolute position along the X and Z axes. Tiles dug out or
+    /// placed since are not taken into account.
+    pub fn surface_height(&self, x: i32, z: i32) -> i32 {
+        self.generator.height_at(x, z)
+    }
+
+    /// Returns the chunk of the chunk column `column` through which the
+    /// surface was generated at the corner of the column with the lowest
+    /// coordinates.
+    pub fn surface_chunk(&self, column: Point2<i32>) -> ChunkPos {
+        let x = column.x * CHUNK_SIZE as i32;
+        let z = column.y * CHUNK_SIZE as i32;
+        Point3::new(column.x, self.surface_height(x, z) >> LOG2_OF_CHUNK_SIZE, column.y)
     }
 
-    pub fn get_chunk(&self, p: Point3<i32>) -> Option<&Chunk> {
+    pub fn get_chunk(&self, p: ChunkPos) -> Option<&Chunk> {
         self.chunks.get(&p)
     }
 
+    /// Returns the light level of the tile at the specified absolute
+    /// position. Tiles outside of the loaded chunks are fully lit.
+    pub fn light_level(&self, p: &Point3<i32>) -> u8 {
+        match self.get_chunk(abs_pos_to_chunk_pos(p)) {
+            Some(chunk) => chunk.light_level(&abs_pos_to_rel_chunk_pos(p)),
+            None => MAX_LIGHT_LEVEL,
+        }
+    }
+
+    /// Replaces the light levels of the chunk at `p`, returning true if any
+    /// of them changed.
+    pub fn set_light(&mut self, p: ChunkPos, light: Box<[u8]>) -> bool {
+        match self.chunks.get_mut(&p) {
+            Some(chunk) => {
+                let changed = chunk.set_light(light);
+                if changed {
+                    self.revision += 1;
+                    chunk.set_revision(self.revision);
+                }
+                changed
+            },
+            None => false,
+        }
+    }
+
+    /// Returns true if the tile at the specified absolute position has been
+    /// revealed to the player. Tiles outside of the loaded chunks are hidden.
+    pub fn is_revealed(&self, p: &Point3<i32>) -> bool {
+        match self.get_chunk(abs_pos_to_chunk_pos(p)) {
+            Some(chunk) => chunk.is_revealed(&abs_pos_to_rel_chunk_pos(p)),
+            None => false,
+        }
+    }
+
+    /// Reveals the tile at the specified absolute position to the player,
+    /// returning true if it was hidden.
+    pub fn reveal(&mut self, p: &Point3<i32>) -> bool {
+        match self.chunks.get_mut(&abs_pos_to_chunk_pos(p)) {
+            Some(chunk) => {
+                let revealed = chunk.reveal(&abs_pos_to_rel_chunk_pos(p));
+                if revealed {
+                    self.revision += 1;
+                    chunk.set_revision(self.revision);
+                }
+                revealed
+            },
+            None => false,
+        }
+    }
+
+    /// Queues the chunk at `p` to have its light recomputed, if it is loaded.
+    pub fn queue_relight(&mut self, p: ChunkPos) {
+        if self.chunks.contains_key(&p) {
+            self.unlit.insert(p);
+        }
+    }
+
+    /// Returns the positions of the chunks queued to have their light
+    /// recomputed, clearing the queue.
+    pub fn take_unlit_chunks(&mut self) -> HashSet<ChunkPos> {
+        ::std::mem::replace(&mut self.unlit, HashSet::new())
+    }
+
     pub fn get_tile(&self, p: &Point3<i32>) -> Tile {
         let chunk_pos = abs_pos_to_chunk_pos(p);
         let tile_pos = abs_pos_to_rel_chunk_pos(p);
 
         match self.get_chunk(chunk_pos) {
-            Some(chunk) => chunk.tiles[tile_pos[1]][tile_pos[0]][tile_pos[2]],
-            None => Tile::new(TileType::OutOfBounds),
+            Some(chunk) => chunk.get_tile(&tile_pos),
+            None => Tile::out_of_bounds(),
         }
     }
-}
 
-fn scaled_open_simplex2(seed: &Seed, point: &[f64; 2]) -> f64 {
-    open_simplex2(seed, &[point[0] * NOISE_SCALING_FACTOR, point[1] * NOISE_SCALING_FACTOR])
+    /// Replaces the tile at the specified absolute position, returning false
+    /// if the position lies within a chunk which has not been generated.
+    ///
+    /// The tile and its neighbours are queued for the next step of the fluid
+    /// simulation, since the change may allow fluid to flow, and the chunk is
+    /// queued to have its light recomputed.
+    pub fn set_tile(&mut self, p: &Point3<i32>, tile: Tile) -> bool {
+        let chunk_pos = abs_pos_to_chunk_pos(p);
+        let tile_pos = abs_pos_to_rel_chunk_pos(p);
+
+        match self.chunks.get_mut(&chunk_pos) {
+            Some(chunk) => {
+                if let Some(ref mut journal) = self.journal {
+                    journal.entry(chunk_pos).or_insert_with(|| region::encode_chunk(chunk));
+                }
+                let previous = chunk.get_tile(&tile_pos).material;
+                if previous != tile.material {
+                    self.changed_tiles.push((*p, previous, tile.material));
+                }
+                chunk.set_tile(&tile_pos, tile);
+                self.revision += 1;
+                chunk.set_revision(self.revision);
+            },
+            None => return false,
+        }
+        self.unlit.insert(chunk_pos);
+
+        self.active_fluids.insert(*p);
+        for direction in &[Direction::North, Direction::East, Direction::South, Direction::West, Direction::Up, Direction::Down] {
+            self.active_fluids.insert(*p + direction.to_vector());
+        }
+        true
+    }
+
+    /// Returns the metadata of the tile at the specified absolute position.
+    /// Tiles outside of the loaded chunks have none.
+    pub fn get_metadata(&self, p: &Point3<i32>) -> TileMetadata {
+        match self.get_chunk(abs_pos_to_chunk_pos(p)) {
+            Some(chunk) => chunk.metadata(&abs_pos_to_rel_chunk_pos(p)),
+            None => TileMetadata::default(),
+        }
+    }
+
+    /// Replaces the metadata of the tile at the specified absolute position,
+    /// returning false if the position lies within a chunk which has not been
+    /// generated. The chunk is given a new revision, as when a tile is set,
+    /// so that it is sent to the players of a hosted game again.
+    pub fn set_metadata(&mut self, p: &Point3<i32>, metadata: TileMetadata) -> bool {
+        let chunk_pos = abs_pos_to_chunk_pos(p);
+        match self.chunks.get_mut(&chunk_pos) {
+            Some(chunk) => {
+                if let Some(ref mut journal) = self.journal {
+                    journal.entry(chunk_pos).or_insert_with(|| region::encode_chunk(chunk));
+                }
+                chunk.set_metadata(&abs_pos_to_rel_chunk_pos(p), metadata);
+                self.revision += 1;
+                chunk.set_revision(self.revision);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Returns the positions queued for the next step of the fluid
+    /// simulation, clearing the queue. They are sorted from the bottom up,
+    /// then along the Z and X axes, so that fluids flow in the same order
+    /// whatever order the positions were queued in.
+    pub fn take_active_fluids(&mut self) -> Vec<Point3<i32>> {
+        let mut positions: Vec<_> = ::std::mem::replace(&mut self.active_fluids, HashSet::new()).into_iter().collect();
+        positions.sort_by_key(|pos| (pos.y, pos.z, pos.x));
+        positions
+    }
+
+    /// Starts or stops journaling the chunks whose tiles are set. Stopping
+    /// discards the journal.
+    pub fn set_journaling(&mut self, enabled: bool) {
+        if !enabled {
+            self.journal = None;
+        } else if self.journal.is_none() {
+            self.journal = Some(HashMap::new());
+        }
+    }
+
+    /// Returns the chunks journaled since this was last called, clearing the
+    /// journal.
+    pub fn take_journal(&mut self) -> HashMap<ChunkPos, Vec<u8>> {
+        match self.journal {
+            Some(ref mut journal) => ::std::mem::replace(journal, HashMap::new()),
+            None => HashMap::new(),
+        }
+    }
+
+    /// Adds `chunks` to the journal, replacing any already journaled at the
+    /// same positions, if the area is journaling.
+    pub fn extend_journal(&mut self, chunks: HashMap<ChunkPos, Vec<u8>>) {
+        if let Some(ref mut journal) = self.journal {
+            journal.extend(chunks);
+        }
+    }
+
+    /// Replaces the chunk at `p` with `chunk`, loading it if it had been
+    /// unloaded. The chunk is marked as modified, so that it is saved.
+    pub fn restore_chunk(&mut self, p: ChunkPos, mut chunk: Chunk) {
+        chunk.mark_restored();
+        self.add_chunk(p, chunk);
+    }
 }
 
 /// Takes an absolute coordinate and returns the origin coordinate of the chunk
 /// in which this coordinate is located.
-pub fn abs_pos_to_chunk_pos(p: &Point3<i32>) -> Point3<i32> {
+pub fn abs_pos_to_chunk_pos(p: &Point3<i32>) -> ChunkPos {
     Point3::new(
         p[0] >> LOG2_OF_CHUNK_SIZE,
         p[1] >> LOG2_OF_CHUNK_SIZE,
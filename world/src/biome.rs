@@ -0,0 +1,95 @@
+use self::Biome::*;
+use components::AnimalKind;
+
+// Climate thresholds, in the range of the noise functions (-1.0 to 1.0).
+// Unlike the parameters in `WorldGenConfig`, these are not saved with a
+// world, so changing them changes the biomes of the chunks which existing
+// saves have yet to generate.
+const TUNDRA_MAX_TEMPERATURE: f64 = -0.35;
+const DESERT_MIN_TEMPERATURE: f64 = 0.25;
+const DESERT_MAX_MOISTURE: f64 = 0.0;
+const MOUNTAINS_MAX_MOISTURE: f64 = -0.25;
+
+const DESERT_ANIMALS: &'static [AnimalKind] = &[AnimalKind::Lizard, AnimalKind::Rabbit];
+const MOUNTAINS_ANIMALS: &'static [AnimalKind] = &[AnimalKind::Goat];
+const PLAINS_ANIMALS: &'static [AnimalKind] = &[AnimalKind::Deer, AnimalKind::Rabbit];
+const TUNDRA_ANIMALS: &'static [AnimalKind] = &[AnimalKind::Deer];
+
+/// The climate region a column of the world belongs to, which determines the
+/// shape of its terrain and the materials on its surface.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Biome {
+    Desert,
+    Mountains,
+    Plains,
+    Tundra,
+}
+
+impl Biome {
+    /// Selects the biome for a column from its temperature and moisture, both
+    /// in the range -1.0 to 1.0.
+    pub fn from_climate(temperature: f64, moisture: f64) -> Self {
+        match () {
+            _ if temperature < TUNDRA_MAX_TEMPERATURE => Tundra,
+            _ if temperature > DESERT_MIN_TEMPERATURE && moisture < DESERT_MAX_MOISTURE => Desert,
+            _ if moisture < MOUNTAINS_MAX_MOISTURE => Mountains,
+            _ => Plains,
+        }
+    }
+
+    /// Returns the name by which this biome is referred to in asset files.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Desert => "desert",
+            Mountains => "mountains",
+            Plains => "plains",
+            Tundra => "tundra",
+        }
+    }
+
+    /// Returns the kinds of animals which live in this biome.
+    pub fn animals(&self) -> &'static [AnimalKind] {
+        match *self {
+            Desert => DESERT_ANIMALS,
+            Mountains => MOUNTAINS_ANIMALS,
+            Plains => PLAINS_ANIMALS,
+            Tundra => TUNDRA_ANIMALS,
+        }
+    }
+
+    /// Returns the fraction of the columns of this biome on which trees grow.
+    pub fn tree_density(&self) -> f64 {
+        match *self {
+            Desert => 0.0,
+            Mountains => 0.002,
+            Plains => 0.01,
+            Tundra => 0.005,
+        }
+    }
+
+    /// Returns the fraction of the columns of this biome on which shrubs grow.
+    pub fn shrub_density(&self) -> f64 {
+        match *self {
+            Desert => 0.005,
+            Mountains => 0.01,
+            Plains => 0.05,
+            Tundra => 0.01,
+        }
+    }
+
+    /// Maps a height map value in the range 0.0 to 1.0 to the elevation of
+    /// the surface of a column in this biome, relative to sea level. The
+    /// variation in height is scaled by `amplitude`.
+    pub fn height(&self, value: f64, amplitude: f64) -> f64 {
+        let (base, variation) = match *self {
+            Desert => (0.0, value * 6.0),
+            // Squaring the value keeps most of the range low, with
+            // occasional steep peaks.
+            Mountains => (2.0, value * value * 40.0),
+            Plains => (0.0, value * 8.0),
+            Tundra => (1.0, value * 12.0),
+        };
+
+        base + variation * amplitude
+    }
+}
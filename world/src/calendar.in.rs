@@ -0,0 +1,15 @@
+/// The in-game date and time, counted in steps of the simulation since the
+/// world was created.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct Calendar {
+    ticks: u64,
+}
+
+/// A quarter of the in-game year.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
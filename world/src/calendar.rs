@@ -0,0 +1,85 @@
+use std::f64;
+
+#[cfg(feature = "nightly")]
+include!("calendar.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/calendar.rs"));
+
+// The rates of the other systems, such as the needs of the colonists, the
+// interval between raids and the length of the weather, are given in these
+// units, so changing them would need those to be rebalanced as well.
+pub const TICKS_PER_HOUR: u64 = 60;
+pub const HOURS_PER_DAY: u64 = 24;
+pub const DAYS_PER_SEASON: u64 = 12;
+const SEASONS: [Season; 4] = [Season::Spring, Season::Summer, Season::Autumn, Season::Winter];
+
+const TICKS_PER_DAY: u64 = TICKS_PER_HOUR * HOURS_PER_DAY;
+const TICKS_PER_SEASON: u64 = TICKS_PER_DAY * DAYS_PER_SEASON;
+const TICKS_PER_YEAR: u64 = TICKS_PER_SEASON * 4;
+
+impl Calendar {
+    /// Creates a calendar at the first hour of the first day of spring of the
+    /// first year.
+    pub fn new() -> Self {
+        Calendar::default()
+    }
+
+    /// Advances the calendar by one step of the simulation.
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    /// Advances the calendar by the specified number of steps of the
+    /// simulation.
+    pub fn advance(&mut self, ticks: u64) {
+        self.ticks += ticks;
+    }
+
+    /// Returns the number of steps of the simulation since the world was
+    /// created.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Returns the minute of the current hour, from 0 to 59.
+    pub fn minute(&self) -> u64 {
+        self.ticks % TICKS_PER_HOUR * 60 / TICKS_PER_HOUR
+    }
+
+    /// Returns the hour of the current day, from 0 to `HOURS_PER_DAY - 1`.
+    pub fn hour(&self) -> u64 {
+        self.ticks % TICKS_PER_DAY / TICKS_PER_HOUR
+    }
+
+    /// Returns the fraction of the current day which has passed, from 0 (at
+    /// midnight) up to but excluding 1.
+    pub fn time_of_day(&self) -> f64 {
+        (self.ticks % TICKS_PER_DAY) as f64 / TICKS_PER_DAY as f64
+    }
+
+    /// Returns the amount of sunlight at the current time of day, from 0 at
+    /// midnight to 1 at noon.
+    pub fn daylight(&self) -> f64 {
+        0.5 - 0.5 * (self.time_of_day() * 2.0 * f64::consts::PI).cos()
+    }
+
+    /// Returns the number of whole days since the world was created.
+    pub fn days(&self) -> u64 {
+        self.ticks / TICKS_PER_DAY
+    }
+
+    /// Returns the day of the current season, starting from 1.
+    pub fn day(&self) -> u64 {
+        self.ticks % TICKS_PER_SEASON / TICKS_PER_DAY + 1
+    }
+
+    pub fn season(&self) -> Season {
+        SEASONS[(self.ticks % TICKS_PER_YEAR / TICKS_PER_SEASON) as usize]
+    }
+
+    /// Returns the current year, starting from 1.
+    pub fn year(&self) -> u64 {
+        self.ticks / TICKS_PER_YEAR + 1
+    }
+}
@@ -1,40 +1,724 @@
-use array::Array;
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+use std::mem;
+use std::u16;
+
 use cgmath::Point3;
+use utility::profiler;
 
-use {CHUNK_SIZE, HEIGHT_MAP_MULTIPLIER};
-use terrain::{ Tile, TileType };
+use CHUNK_SIZE;
+use light::MAX_LIGHT_LEVEL;
+use terrain::Tile;
 
 pub type ChunkArray<T> = [T; CHUNK_SIZE];
 pub type ChunkArray2d<T> = ChunkArray<ChunkArray<T>>;
 pub type ChunkArray3d<T> = ChunkArray<ChunkArray2d<T>>;
 pub type Tiles = ChunkArray3d<Tile>;
+/// The position of a chunk, in chunk coordinates (that is, in units of
+/// `CHUNK_SIZE` tiles).
+pub type ChunkPos = Point3<i32>;
 
-fn array_16x16x16<T, F>(mut f: F) -> ChunkArray3d<T>
-    where F: FnMut(usize, usize, usize) -> T
-{
-    Array::from_fn(|y| -> ChunkArray2d<T> {
-        Array::from_fn(|z| -> ChunkArray<T> {
-            Array::from_fn(|x| f(x, y, z))
-        })
-    })
+/// The number of tiles in a chunk.
+pub const CHUNK_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+/// The number of bytes needed to store one bit for every tile of a chunk.
+pub const CHUNK_MASK_LENGTH: usize = CHUNK_VOLUME / 8;
+
+/// Which tiles of a chunk have been revealed to the player.
+pub enum Visibility {
+    /// No tile has been revealed.
+    Hidden,
+    /// Every tile has been revealed.
+    Revealed,
+    /// One bit per tile, ordered like the tiles of the chunk, set for every
+    /// tile which has been revealed.
+    Partial(Box<[u8]>),
+}
+
+impl Visibility {
+    /// Creates the visibility of a chunk in which the tiles at the positions
+    /// relative to its origin for which `is_revealed` returns true have been
+    /// revealed.
+    pub fn from_fn<F>(is_revealed: F) -> Visibility
+        where F: Fn(usize, usize, usize) -> bool,
+    {
+        let mut mask = vec![0; CHUNK_MASK_LENGTH].into_boxed_slice();
+        for y in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    if is_revealed(x, y, z) {
+                        let i = tile_index(x, y, z);
+                        mask[i / 8] |= 1 << (i % 8);
+                    }
+                }
+            }
+        }
+        Visibility::from_mask(mask)
+    }
+
+    /// Creates the visibility of a chunk from one bit per tile, collapsing it
+    /// if every tile is hidden or every tile is revealed.
+    pub fn from_mask(mask: Box<[u8]>) -> Visibility {
+        debug_assert_eq!(mask.len(), CHUNK_MASK_LENGTH);
+        if mask.iter().all(|&bits| bits == 0) {
+            Visibility::Hidden
+        } else if mask.iter().all(|&bits| bits == 0xff) {
+            Visibility::Revealed
+        } else {
+            Visibility::Partial(mask)
+        }
+    }
+
+    fn is_revealed(&self, i: usize) -> bool {
+        match *self {
+            Visibility::Hidden => false,
+            Visibility::Revealed => true,
+            Visibility::Partial(ref mask) => mask[i / 8] & (1 << (i % 8)) != 0,
+        }
+    }
+
+    /// Reveals the tile at index `i`, returning true if it was hidden.
+    fn reveal(&mut self, i: usize) -> bool {
+        if self.is_revealed(i) {
+            return false;
+        }
+
+        if let Visibility::Hidden = *self {
+            *self = Visibility::Partial(vec![0; CHUNK_MASK_LENGTH].into_boxed_slice());
+        }
+        if let Visibility::Partial(ref mut mask) = *self {
+            mask[i / 8] |= 1 << (i % 8);
+        }
+        true
+    }
+
+    /// Returns the number of bytes allocated on the heap.
+    fn heap_size(&self) -> usize {
+        match *self {
+            Visibility::Partial(ref mask) => mask.len(),
+            _ => 0,
+        }
+    }
+}
+
+/// The number of units of `TileMetadata::dig_progress` per tick of work.
+const DIG_PROGRESS_PER_TICK: f32 = 100.0;
+
+/// State of a tile which its material cannot express, kept only for the few
+/// tiles of a chunk which have any.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TileMetadata {
+    /// How much of the work of digging out the tile has been done, in
+    /// hundredths of a tick.
+    dig_progress: u16,
 }
 
+impl TileMetadata {
+    /// Creates metadata from the value it is stored as.
+    pub fn from_raw(dig_progress: u16) -> TileMetadata {
+        TileMetadata {
+            dig_progress: dig_progress,
+        }
+    }
+
+    /// Returns the value the metadata is stored as.
+    pub fn to_raw(&self) -> u16 {
+        self.dig_progress
+    }
+
+    /// Returns true if the metadata holds nothing beyond the defaults, in
+    /// which case it need not be kept.
+    pub fn is_empty(&self) -> bool {
+        *self == TileMetadata::default()
+    }
+
+    /// Returns how much of the work of digging out the tile has been done, in
+    /// ticks, so that a dig which was interrupted resumes where it was left.
+    pub fn dig_progress(&self) -> f32 {
+        self.dig_progress as f32 / DIG_PROGRESS_PER_TICK
+    }
+
+    pub fn set_dig_progress(&mut self, ticks: f32) {
+        self.dig_progress = (ticks * DIG_PROGRESS_PER_TICK).round().max(0.0).min(u16::MAX as f32) as u16;
+    }
+}
+
+/// The palette index of every tile of a chunk, stored in the narrowest type
+/// which can index every entry of the palette.
+enum PaletteIndices {
+    /// Every tile is the first entry of the palette.
+    Uniform,
+    Byte(Box<[u8]>),
+    Short(Box<[u16]>),
+}
+
+impl PaletteIndices {
+    /// Returns the narrowest indices able to index a palette of `len`
+    /// entries, with every tile set to the first entry.
+    fn with_palette_len(len: usize) -> PaletteIndices {
+        match len {
+            0 | 1 => PaletteIndices::Uniform,
+            2...256 => PaletteIndices::Byte(vec![0; CHUNK_VOLUME].into_boxed_slice()),
+            _ => PaletteIndices::Short(vec![0; CHUNK_VOLUME].into_boxed_slice()),
+        }
+    }
+
+    /// Returns the number of palette entries which can be indexed.
+    fn capacity(&self) -> usize {
+        match *self {
+            PaletteIndices::Uniform => 1,
+            PaletteIndices::Byte(_) => 1 << 8,
+            PaletteIndices::Short(_) => 1 << 16,
+        }
+    }
+
+    fn get(&self, i: usize) -> usize {
+        match *self {
+            PaletteIndices::Uniform => 0,
+            PaletteIndices::Byte(ref indices) => indices[i] as usize,
+            PaletteIndices::Short(ref indices) => indices[i] as usize,
+        }
+    }
+
+    fn set(&mut self, i: usize, index: usize) {
+        debug_assert!(index < self.capacity());
+        match *self {
+            PaletteIndices::Uniform => {},
+            PaletteIndices::Byte(ref mut indices) => indices[i] = index as u8,
+            PaletteIndices::Short(ref mut indices) => indices[i] = index as u16,
+        }
+    }
+
+    /// Returns a copy of the indices stored in the next wider type.
+    fn widened(&self) -> PaletteIndices {
+        match *self {
+            PaletteIndices::Uniform => PaletteIndices::Byte(vec![0; CHUNK_VOLUME].into_boxed_slice()),
+            PaletteIndices::Byte(ref indices) => {
+                PaletteIndices::Short(indices.iter().map(|&index| index as u16).collect::<Vec<_>>().into_boxed_slice())
+            },
+            // A chunk has fewer tiles than a short can index, so its palette
+            // never needs to grow this large once its unused entries are
+            // removed.
+            PaletteIndices::Short(_) => unreachable!("chunk palette exceeded the number of tiles in a chunk"),
+        }
+    }
+
+    /// Returns the number of bytes allocated on the heap.
+    fn heap_size(&self) -> usize {
+        match *self {
+            PaletteIndices::Uniform => 0,
+            PaletteIndices::Byte(ref indices) => indices.len(),
+            PaletteIndices::Short(ref indices) => indices.len() * 2,
+        }
+    }
+}
+
+/// A cube of `CHUNK_SIZE` tiles along each axis.
+///
+/// Rather than storing every tile, a chunk stores a palette of its distinct
+/// tiles and the index into the palette of each tile. Most chunks consist of
+/// a handful of tiles, so this takes a fraction of the memory, and chunks of
+/// a single tile (such as those high in the sky) need no per-tile storage at
+/// all.
 pub struct Chunk {
-    pub tiles: Tiles,
+    /// The distinct tiles of the chunk. Entries may become unused as tiles
+    /// are replaced, and are only removed once the palette is full.
+    palette: Vec<Tile>,
+    /// The palette index of every tile, ordered by Y, then Z, then X.
+    indices: PaletteIndices,
+    /// Replaced by the area holding the chunk every time the chunk is added
+    /// to it, one of its tiles or light levels is modified or one of its
+    /// tiles is revealed, so that anything derived from the chunk knows when
+    /// it is out of date. Each area draws revisions from a counter of its
+    /// own, so that a chunk loaded again never has a revision seen before.
+    revision: u64,
+    /// Whether the chunk has been modified since it was last saved.
+    dirty: bool,
+    /// The light level of every tile, ordered like `indices`, once it has
+    /// been computed. Light is never saved, since it can be computed again
+    /// from the tiles.
+    light: Option<Box<[u8]>>,
+    visibility: Visibility,
+    /// The metadata of the tiles which have any, by index. Metadata is not
+    /// drawn, so changing it marks the chunk as modified without changing
+    /// its revision.
+    metadata: BTreeMap<usize, TileMetadata>,
 }
 
 impl Chunk {
-    pub fn generate(pos: Point3<i32>, height_map: ChunkArray2d<f64>) -> Chunk {
-        let chunk_y = pos.y * CHUNK_SIZE as i32;
+    /// Creates a chunk from tiles indexed by `[y][z][x]`.
+    pub fn from_tiles(tiles: &Tiles) -> Chunk {
+        let _scope = profiler::scope("chunk packing");
+        let mut chunk = Chunk {
+            palette: vec![tiles[0][0][0]],
+            indices: PaletteIndices::Uniform,
+            revision: 0,
+            dirty: false,
+            light: None,
+            visibility: Visibility::Revealed,
+            metadata: BTreeMap::new(),
+        };
+
+        for (y, plane) in tiles.iter().enumerate() {
+            for (z, row) in plane.iter().enumerate() {
+                for (x, tile) in row.iter().enumerate() {
+                    chunk.store(tile_index(x, y, z), *tile);
+                }
+            }
+        }
+
+        chunk
+    }
+
+    /// Creates a chunk from a palette and the index into it of every tile,
+    /// ordered by Y, then Z, then X. Returns `None` if there is not exactly
+    /// one index per tile, or if any index lies outside of the palette.
+    pub fn from_palette(palette: Vec<Tile>, indices: &[u16]) -> Option<Chunk> {
+        if palette.is_empty() || palette.len() > CHUNK_VOLUME || indices.len() != CHUNK_VOLUME {
+            return None;
+        }
+        if indices.iter().any(|&index| index as usize >= palette.len()) {
+            return None;
+        }
+
+        let mut packed = PaletteIndices::with_palette_len(palette.len());
+        for (i, &index) in indices.iter().enumerate() {
+            packed.set(i, index as usize);
+        }
+
+        Some(Chunk {
+            palette: palette,
+            indices: packed,
+            revision: 0,
+            dirty: false,
+            light: None,
+            visibility: Visibility::Revealed,
+            metadata: BTreeMap::new(),
+        })
+    }
+
+    /// Returns the chunk with only the tiles of `visibility` revealed. Chunks
+    /// are created with every tile revealed.
+    pub fn with_visibility(mut self, visibility: Visibility) -> Chunk {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Returns the chunk with the metadata of the tiles at the indices of
+    /// `metadata`, ordered by Y, then Z, then X. Returns `None` if any index
+    /// lies outside of the chunk.
+    pub fn with_metadata(mut self, metadata: BTreeMap<usize, TileMetadata>) -> Option<Chunk> {
+        if metadata.keys().any(|&i| i >= CHUNK_VOLUME) {
+            return None;
+        }
+        self.metadata = metadata.into_iter().filter(|&(_, metadata)| !metadata.is_empty()).collect();
+        Some(self)
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn set_revision(&mut self, revision: u64) {
+        self.revision = revision;
+    }
+
+    /// Returns true if the chunk has been modified since it was last saved.
+    /// Chunks which were never modified need not be saved, since they can be
+    /// generated again from the seed of the world.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Marks the chunk as modified, so that it is saved once it replaces the
+    /// chunk at its position, as when the world is rewound.
+    pub fn mark_restored(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Returns the distinct tiles of the chunk, which may include tiles no
+    /// longer present in it.
+    pub fn palette(&self) -> &[Tile] {
+        &self.palette
+    }
+
+    /// Returns the index into `palette` of the tile at the specified position
+    /// relative to the origin of the chunk.
+    pub fn palette_index(&self, p: &Point3<usize>) -> usize {
+        self.indices.get(tile_index(p.x, p.y, p.z))
+    }
+
+    /// Returns the approximate number of bytes used by the chunk.
+    pub fn memory_usage(&self) -> usize {
+        mem::size_of::<Chunk>() +
+            self.palette.capacity() * mem::size_of::<Tile>() +
+            self.indices.heap_size() +
+            self.light.as_ref().map_or(0, |light| light.len()) +
+            self.visibility.heap_size() +
+            self.metadata.len() * (mem::size_of::<usize>() + mem::size_of::<TileMetadata>())
+    }
+
+    pub fn visibility(&self) -> &Visibility {
+        &self.visibility
+    }
+
+    /// Returns true if the tile at the specified position relative to the
+    /// origin of the chunk has been revealed to the player.
+    pub fn is_revealed(&self, p: &Point3<usize>) -> bool {
+        self.visibility.is_revealed(tile_index(p.x, p.y, p.z))
+    }
+
+    /// Reveals the tile at the specified position relative to the origin of
+    /// the chunk to the player, returning true if it was hidden.
+    pub fn reveal(&mut self, p: &Point3<usize>) -> bool {
+        let revealed = self.visibility.reveal(tile_index(p.x, p.y, p.z));
+        if revealed {
+            self.dirty = true;
+        }
+        revealed
+    }
+
+    /// Returns the metadata of the tile at the specified position relative to
+    /// the origin of the chunk.
+    pub fn metadata(&self, p: &Point3<usize>) -> TileMetadata {
+        self.metadata.get(&tile_index(p.x, p.y, p.z)).cloned().unwrap_or_else(TileMetadata::default)
+    }
+
+    /// Replaces the metadata of the tile at the specified position relative
+    /// to the origin of the chunk.
+    pub fn set_metadata(&mut self, p: &Point3<usize>, metadata: TileMetadata) {
+        let i = tile_index(p.x, p.y, p.z);
+        if metadata.is_empty() {
+            self.metadata.remove(&i);
+        } else {
+            self.metadata.insert(i, metadata);
+        }
+        self.dirty = true;
+    }
+
+    /// Returns an iterator over the tiles which have metadata, by index, in
+    /// order of their indices.
+    pub fn iter_metadata(&self) -> btree_map::Iter<usize, TileMetadata> {
+        self.metadata.iter()
+    }
+
+    /// Returns true if the light of the chunk has been computed.
+    pub fn is_lit(&self) -> bool {
+        self.light.is_some()
+    }
+
+    /// Returns the light level of the tile at the specified position relative
+    /// to the origin of the chunk. Until the light of the chunk is computed,
+    /// every tile is fully lit.
+    pub fn light_level(&self, p: &Point3<usize>) -> u8 {
+        match self.light {
+            Some(ref light) => light[tile_index(p.x, p.y, p.z)],
+            None => MAX_LIGHT_LEVEL,
+        }
+    }
+
+    /// Replaces the light level of every tile, ordered by Y, then Z, then X.
+    /// Returns true if any light level changed.
+    pub fn set_light(&mut self, light: Box<[u8]>) -> bool {
+        debug_assert_eq!(light.len(), CHUNK_VOLUME);
+        if let Some(ref current) = self.light {
+            if *current == light {
+                return false;
+            }
+        }
+
+        self.light = Some(light);
+        true
+    }
+
+    /// Returns an iterator over the tiles of the row at the specified Y and Z
+    /// positions relative to the origin of the chunk, in order of X.
+    pub fn iter_row(&self, y: usize, z: usize) -> RowIter {
+        debug_assert!(y < CHUNK_SIZE && z < CHUNK_SIZE, "row ({}, {}) lies outside of the chunk", y, z);
+        RowIter {
+            chunk: self,
+            start: tile_index(0, y, z),
+            x: 0,
+        }
+    }
+
+    /// Returns an iterator over the rows of the horizontal slice at the
+    /// specified Y position relative to the origin of the chunk, in order of
+    /// Z.
+    pub fn iter_slice(&self, y: usize) -> SliceIter {
+        debug_assert!(y < CHUNK_SIZE, "slice {} lies outside of the chunk", y);
+        SliceIter {
+            chunk: self,
+            y: y,
+            z: 0,
+        }
+    }
+
+    /// Returns the tile at the specified position relative to the origin of
+    /// the chunk.
+    pub fn get_tile(&self, p: &Point3<usize>) -> Tile {
+        self.palette[self.palette_index(p)]
+    }
+
+    /// Replaces the tile at the specified position relative to the origin of
+    /// the chunk, along with its metadata.
+    pub fn set_tile(&mut self, p: &Point3<usize>, tile: Tile) {
+        let i = tile_index(p.x, p.y, p.z);
+        self.store(i, tile);
+        self.metadata.remove(&i);
+        self.dirty = true;
+    }
 
-        Chunk {
-            tiles: array_16x16x16(|x, y, z| {
-                let map_height = height_map[x][z] * HEIGHT_MAP_MULTIPLIER;
-                let tile_y = chunk_y + y as i32;
-                Tile {
-                    tile_type: TileType::get_from_elevation(tile_y, map_height as i32),
+    /// Stores `tile` at index `i`, adding it to the palette if needed.
+    fn store(&mut self, i: usize, tile: Tile) {
+        let index = match self.palette.iter().position(|t| *t == tile) {
+            Some(index) => index,
+            None => {
+                if self.palette.len() == self.indices.capacity() {
+                    self.remove_unused_tiles();
+                }
+                if self.palette.len() == self.indices.capacity() {
+                    self.indices = self.indices.widened();
                 }
-            }),
+                self.palette.push(tile);
+                self.palette.len() - 1
+            },
+        };
+        self.indices.set(i, index);
+    }
+
+    /// Removes the entries of the palette which no tile refers to.
+    fn remove_unused_tiles(&mut self) {
+        if let PaletteIndices::Uniform = self.indices {
+            return;
         }
+
+        let mut used = vec![false; self.palette.len()];
+        for i in 0..CHUNK_VOLUME {
+            used[self.indices.get(i)] = true;
+        }
+
+        let mut remapped = vec![0; self.palette.len()];
+        let mut palette = Vec::with_capacity(self.palette.len());
+        for (index, tile) in self.palette.iter().enumerate() {
+            if used[index] {
+                remapped[index] = palette.len();
+                palette.push(*tile);
+            }
+        }
+
+        for i in 0..CHUNK_VOLUME {
+            let index = self.indices.get(i);
+            self.indices.set(i, remapped[index]);
+        }
+        self.palette = palette;
+    }
+}
+
+/// An iterator over the tiles of a row of a chunk, created by
+/// `Chunk::iter_row`.
+pub struct RowIter<'a> {
+    chunk: &'a Chunk,
+    /// The index of the first tile of the row.
+    start: usize,
+    x: usize,
+}
+
+impl<'a> Iterator for RowIter<'a> {
+    type Item = Tile;
+
+    fn next(&mut self) -> Option<Tile> {
+        if self.x == CHUNK_SIZE {
+            return None;
+        }
+
+        let tile = self.chunk.palette[self.chunk.indices.get(self.start + self.x)];
+        self.x += 1;
+        Some(tile)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = CHUNK_SIZE - self.x;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for RowIter<'a> {}
+
+/// An iterator over the rows of a horizontal slice of a chunk, created by
+/// `Chunk::iter_slice`.
+pub struct SliceIter<'a> {
+    chunk: &'a Chunk,
+    y: usize,
+    z: usize,
+}
+
+impl<'a> Iterator for SliceIter<'a> {
+    type Item = RowIter<'a>;
+
+    fn next(&mut self) -> Option<RowIter<'a>> {
+        if self.z == CHUNK_SIZE {
+            return None;
+        }
+
+        let row = self.chunk.iter_row(self.y, self.z);
+        self.z += 1;
+        Some(row)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = CHUNK_SIZE - self.z;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for SliceIter<'a> {}
+
+/// Returns the index of the tile at the specified position relative to the
+/// origin of a chunk, ordered by Y, then Z, then X.
+pub fn tile_index(x: usize, y: usize, z: usize) -> usize {
+    debug_assert!(x < CHUNK_SIZE && y < CHUNK_SIZE && z < CHUNK_SIZE);
+    (y * CHUNK_SIZE + z) * CHUNK_SIZE + x
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Point3;
+
+    use CHUNK_SIZE;
+    use material::MaterialId;
+    use terrain::Tile;
+    use super::{tile_index, Chunk, PaletteIndices, Tiles};
+
+    fn tile(material: usize) -> Tile {
+        Tile::new(MaterialId(material as u16))
+    }
+
+    /// Returns the tiles of a chunk in which the tile at every position is
+    /// `f(x, y, z)`.
+    fn tiles_from_fn<F>(f: F) -> Tiles
+        where F: Fn(usize, usize, usize) -> Tile,
+    {
+        let mut tiles = [[[tile(0); CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+        for (y, plane) in tiles.iter_mut().enumerate() {
+            for (z, row) in plane.iter_mut().enumerate() {
+                for (x, element) in row.iter_mut().enumerate() {
+                    *element = f(x, y, z);
+                }
+            }
+        }
+        tiles
+    }
+
+    fn assert_tiles_eq<F>(chunk: &Chunk, f: F)
+        where F: Fn(usize, usize, usize) -> Tile,
+    {
+        for y in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    assert_eq!(chunk.get_tile(&Point3::new(x, y, z)), f(x, y, z));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn from_tiles_keeps_a_single_tile_uniform() {
+        let chunk = Chunk::from_tiles(&tiles_from_fn(|_, _, _| tile(3)));
+        assert!(match chunk.indices { PaletteIndices::Uniform => true, _ => false });
+        assert_eq!(chunk.palette(), &[tile(3)][..]);
+    }
+
+    #[test]
+    fn from_tiles_indexes_few_tiles_by_byte() {
+        let f = |x: usize, y: usize, z: usize| tile((x + y + z) % 7);
+        let chunk = Chunk::from_tiles(&tiles_from_fn(&f));
+        assert!(match chunk.indices { PaletteIndices::Byte(_) => true, _ => false });
+        assert_eq!(chunk.palette().len(), 7);
+        assert_tiles_eq(&chunk, f);
+    }
+
+    #[test]
+    fn from_tiles_widens_byte_indices_to_short() {
+        // More distinct tiles than a byte can index.
+        let f = |x: usize, y: usize, z: usize| tile(tile_index(x, y, z) % 300);
+        let chunk = Chunk::from_tiles(&tiles_from_fn(&f));
+        assert!(match chunk.indices { PaletteIndices::Short(_) => true, _ => false });
+        assert_eq!(chunk.palette().len(), 300);
+        assert_tiles_eq(&chunk, f);
+    }
+
+    #[test]
+    fn full_palette_is_shrunk_before_widening() {
+        let mut chunk = Chunk::from_tiles(&tiles_from_fn(|x, y, z| tile(tile_index(x, y, z) % 256)));
+        assert_eq!(chunk.palette().len(), 256);
+
+        // Once every tile is the same, a new tile takes the place of the
+        // unused entries rather than widening the indices.
+        for y in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    chunk.set_tile(&Point3::new(x, y, z), tile(0));
+                }
+            }
+        }
+        chunk.set_tile(&Point3::new(1, 2, 3), tile(1000));
+
+        assert!(match chunk.indices { PaletteIndices::Byte(_) => true, _ => false });
+        assert_eq!(chunk.palette(), &[tile(0), tile(1000)][..]);
+        assert_tiles_eq(&chunk, |x, y, z| if (x, y, z) == (1, 2, 3) { tile(1000) } else { tile(0) });
+    }
+
+    /// Checks that every row and slice iterated over holds the tiles found
+    /// by `get_tile`.
+    fn assert_iterators_match_tiles(chunk: &Chunk) {
+        for y in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let row = chunk.iter_row(y, z);
+                assert_eq!(row.len(), CHUNK_SIZE);
+                let expected: Vec<Tile> = (0..CHUNK_SIZE).map(|x| chunk.get_tile(&Point3::new(x, y, z))).collect();
+                assert_eq!(row.collect::<Vec<_>>(), expected);
+            }
+
+            let slice = chunk.iter_slice(y);
+            assert_eq!(slice.len(), CHUNK_SIZE);
+            for (z, row) in slice.enumerate() {
+                for (x, tile) in row.enumerate() {
+                    assert_eq!(tile, chunk.get_tile(&Point3::new(x, y, z)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn iterators_read_whole_rows_of_a_uniform_chunk() {
+        let chunk = Chunk::from_tiles(&tiles_from_fn(|_, _, _| tile(3)));
+        assert_iterators_match_tiles(&chunk);
+    }
+
+    #[test]
+    fn iterators_read_whole_rows_of_an_allocated_chunk() {
+        let chunk = Chunk::from_tiles(&tiles_from_fn(|x, y, z| tile((x + 2 * y + 3 * z) % 5)));
+        assert_iterators_match_tiles(&chunk);
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn row_outside_of_the_chunk_is_rejected() {
+        let chunk = Chunk::from_tiles(&tiles_from_fn(|_, _, _| tile(3)));
+        chunk.iter_row(CHUNK_SIZE, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn slice_outside_of_the_chunk_is_rejected() {
+        let chunk = Chunk::from_tiles(&tiles_from_fn(|_, _, _| tile(3)));
+        chunk.iter_slice(CHUNK_SIZE);
     }
 }
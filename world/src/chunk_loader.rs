@@ -0,0 +1,215 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use num_cpus;
+
+use chunk::{Chunk, ChunkPos};
+use mapgen::WorldGenerator;
+use region::RegionStore;
+
+/// Generates chunks on a pool of worker threads, so that the game does not
+/// stall while new territory is generated. Chunks which were previously saved
+/// to one of the region stores are loaded from it instead.
+///
+/// Requested chunks are delivered, in no particular order, by `receive`, along
+/// with whether each was generated rather than loaded, or the error which
+/// kept them from being loaded.
+///
+/// Dropping the loader never waits for its workers: each stops on its own
+/// once it has finished its current chunk.
+pub struct ChunkLoader {
+    requests: Sender<ChunkPos>,
+    results: Receiver<(ChunkPos, Result<(Chunk, bool), ChunkLoadError>)>,
+    /// Positions of the chunks which have been requested but not yet
+    /// received.
+    pending: HashSet<ChunkPos>,
+    /// Set when the loader is dropped, so that the workers stop without
+    /// loading the chunks still queued.
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ChunkLoader {
+    /// Starts one worker thread per CPU, each loading chunks from the first
+    /// of `stores` holding them or generating them with `generator`.
+    pub fn new(generator: Arc<WorldGenerator>, stores: Vec<Arc<RegionStore>>) -> Self {
+        let (request_sender, request_receiver) = mpsc::channel::<ChunkPos>();
+        let (result_sender, result_receiver) = mpsc::channel();
+        let request_receiver = Arc::new(Mutex::new(request_receiver));
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        for _ in 0..num_cpus::get() {
+            let generator = generator.clone();
+            let stores = stores.clone();
+            let requests = request_receiver.clone();
+            let results = result_sender.clone();
+            let cancelled = cancelled.clone();
+
+            thread::spawn(move || {
+                loop {
+                    // The lock is released as soon as a request is received,
+                    // so that the other workers can receive requests while
+                    // this one generates its chunk.
+                    let request = match requests.lock() {
+                        Ok(requests) => requests.recv(),
+                        // Another worker panicked while receiving a request,
+                        // so the queue can no longer be trusted.
+                        Err(_) => return,
+                    };
+                    let pos = match request {
+                        Ok(pos) => pos,
+                        // The loader has been dropped.
+                        Err(_) => return,
+                    };
+                    if cancelled.load(Ordering::SeqCst) {
+                        return;
+                    }
+
+                    // A chunk which fails to generate is reported rather than
+                    // taking the worker down with it, so that the others are
+                    // still served.
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| load_or_generate(&generator, &stores, pos)))
+                        .unwrap_or(Err(ChunkLoadError::Panicked(pos)));
+                    if results.send((pos, result)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+
+        ChunkLoader {
+            requests: request_sender,
+            results: result_receiver,
+            pending: HashSet::new(),
+            cancelled: cancelled,
+        }
+    }
+
+    /// Queues the chunk at `pos` for generation, unless it has already been
+    /// requested. Fails if every worker has stopped.
+    pub fn request(&mut self, pos: ChunkPos) -> Result<(), ChunkLoadError> {
+        if self.pending.insert(pos) && self.requests.send(pos).is_err() {
+            self.pending.remove(&pos);
+            return Err(ChunkLoadError::Stopped);
+        }
+        Ok(())
+    }
+
+    /// Returns the number of chunks which have been requested but not yet
+    /// received.
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns every chunk which has finished loading or failed to load
+    /// since the last call, without blocking.
+    pub fn receive(&mut self) -> Vec<(ChunkPos, Result<(Chunk, bool), ChunkLoadError>)> {
+        let mut chunks = Vec::new();
+        while let Ok((pos, result)) = self.results.try_recv() {
+            self.pending.remove(&pos);
+            chunks.push((pos, result));
+        }
+        chunks
+    }
+}
+
+/// Loads the chunk at `pos` from the first of `stores` holding it, or
+/// generates it with `generator` if it was never saved, returning it along
+/// with whether it was generated.
+///
+/// A chunk which one of the stores fails to read is never generated, as it
+/// would replace the saved chunk once the area is next saved.
+pub fn load_or_generate(generator: &WorldGenerator, stores: &[Arc<RegionStore>], pos: ChunkPos) -> Result<(Chunk, bool), ChunkLoadError> {
+    for store in stores {
+        match store.load_chunk(pos) {
+            Ok(Some(chunk)) => return Ok((chunk, false)),
+            Ok(None) => {},
+            Err(e) => return Err(ChunkLoadError::Read(pos, store.directory().to_path_buf(), e)),
+        }
+    }
+    Ok((generator.generate_chunk(pos), true))
+}
+
+/// An error encountered while loading or generating a chunk.
+#[derive(Debug)]
+pub enum ChunkLoadError {
+    /// The chunk at the given position could not be read from the region
+    /// store in the given directory.
+    Read(ChunkPos, PathBuf, io::Error),
+    /// Loading or generating the chunk at the given position panicked.
+    Panicked(ChunkPos),
+    /// Every worker has stopped, so no more chunks can be loaded.
+    Stopped,
+}
+
+impl fmt::Display for ChunkLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ChunkLoadError::Read(pos, ref directory, ref e) => write!(f, "failed to load chunk {:?} from {}: {}", pos, directory.display(), e),
+            ChunkLoadError::Panicked(pos) => write!(f, "failed to generate chunk {:?}", pos),
+            ChunkLoadError::Stopped => write!(f, "chunk workers stopped unexpectedly"),
+        }
+    }
+}
+
+impl Error for ChunkLoadError {
+    fn description(&self) -> &str {
+        match *self {
+            ChunkLoadError::Read(_, _, ref e) => e.description(),
+            ChunkLoadError::Panicked(_) => "failed to generate chunk",
+            ChunkLoadError::Stopped => "chunk workers stopped unexpectedly",
+        }
+    }
+}
+
+impl Drop for ChunkLoader {
+    fn drop(&mut self) {
+        // Cancelling the loader, and closing the request queue as its
+        // sender is dropped, makes each worker return once it has finished
+        // its current chunk, discarding the chunks still queued. The workers
+        // are not joined, so that replacing the loader never stalls the
+        // frame while they finish.
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::sync::Arc;
+
+    use cgmath::Point3;
+
+    use mapgen::WorldGenerator;
+    use material::MaterialRegistry;
+    use region::RegionStore;
+    use world_gen_config::WorldGenConfig;
+    use super::{load_or_generate, ChunkLoadError};
+
+    #[test]
+    fn does_not_generate_chunks_the_store_fails_to_read() {
+        let materials = MaterialRegistry::default();
+        let generator = WorldGenerator::new(7, WorldGenConfig::default(), &materials);
+        let pos = Point3::new(0, 0, 0);
+
+        let store = RegionStore::temporary().unwrap();
+        store.save_chunks(&[(pos, &generator.generate_chunk(pos))]).unwrap();
+        for entry in fs::read_dir(store.directory()).unwrap() {
+            File::create(entry.unwrap().path()).unwrap().write_all(b"not a region").unwrap();
+        }
+
+        match load_or_generate(&generator, &[Arc::new(store)], pos) {
+            Err(ChunkLoadError::Read(read_pos, _, _)) => assert_eq!(read_pos, pos),
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("generated a chunk over the saved one"),
+        }
+    }
+}
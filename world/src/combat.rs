@@ -0,0 +1,205 @@
+use std::i32;
+
+use rand::Rng;
+
+use area::Area;
+use calendar::{HOURS_PER_DAY, TICKS_PER_HOUR};
+use components::{Fighter, Health, Invader, Pathing, Position, Renderable};
+use entity::{Entities, Entity};
+use event::WorldEvent;
+use material::MaterialRegistry;
+use systems;
+use wildlife;
+use world::WorldState;
+
+// Raids grow with the wealth of the colony, at a rate set by
+// `WEALTH_PER_INVADER` along with `ITEM_WEALTH` and `FURNITURE_WEALTH`,
+// which only make sense when tuned together.
+/// The number of ticks between raids.
+const RAID_INTERVAL: u64 = TICKS_PER_HOUR * HOURS_PER_DAY * 4;
+/// The wealth of the colony which draws each invader beyond the first.
+const WEALTH_PER_INVADER: u32 = 20;
+const MAX_INVADERS_PER_RAID: u32 = 8;
+/// The wealth added to the colony by every item, and by every piece of
+/// furniture.
+const ITEM_WEALTH: u32 = 1;
+const FURNITURE_WEALTH: u32 = 10;
+const INVADER_HEALTH: f32 = 60.0;
+const INVADER_DAMAGE: f32 = 8.0;
+const INVADER_COLOR: [f32; 4] = [0.45, 0.05, 0.05, 1.0];
+/// The chance that a blow lands.
+const HIT_CHANCE: f64 = 0.75;
+/// The number of ticks between blows.
+const ATTACK_COOLDOWN: u32 = 10;
+/// The distance within which drafted colonists go after invaders, unless
+/// they have been ordered to move elsewhere.
+const ENGAGE_DISTANCE: i32 = 8;
+/// The chance every tick that an invader which could not find its way to its
+/// target tries again.
+const RETRY_CHANCE: f64 = 0.05;
+
+/// A blow which has landed on an entity.
+struct DamageEvent {
+    target: Entity,
+    amount: f32,
+}
+
+/// Sends invaders at the colony every few days, and lets every fighter pick a
+/// target, close in on it and strike at it. Fighters whose health runs out
+/// die. The invaders sent and the colonists who died are reported to
+/// `events`.
+///
+/// Invaders go after the nearest colonist. Colonists fight back against any
+/// invader beside them, and drafted colonists go after invaders nearby.
+pub fn tick(state: &mut WorldState, area: &Area, materials: &MaterialRegistry, events: &mut Vec<WorldEvent>) {
+    let now = state.calendar.ticks();
+    if state.next_raid == 0 {
+        state.next_raid = now + RAID_INTERVAL;
+    } else if now >= state.next_raid {
+        state.next_raid = now + RAID_INTERVAL;
+        spawn_raid(state, area, materials, events);
+    }
+
+    let colonists = positions_of(&state.entities, |entities, entity| entities.colonists.contains(entity));
+    let invaders = positions_of(&state.entities, |entities, entity| entities.invaders.contains(entity));
+
+    let mut damage = Vec::new();
+    {
+        let rng = &mut state.rng;
+        let entities = &mut state.entities;
+        for (entity, fighter) in entities.fighters.iter_mut() {
+            let (position, pathing) = match (entities.positions.get(entity), entities.pathing.get_mut(entity)) {
+                (Some(position), Some(pathing)) => (*position, pathing),
+                _ => continue,
+            };
+            fighter.cooldown = fighter.cooldown.saturating_sub(1);
+
+            let (enemies, reach) = if entities.invaders.contains(entity) {
+                (&colonists, i32::MAX)
+            } else {
+                let is_drafted = entities.colonists.get(entity).map_or(false, |colonist| colonist.drafted);
+                let has_orders = pathing.target.is_some() && fighter.target.is_none();
+                let reach = if is_drafted && !has_orders { ENGAGE_DISTANCE } else { 1 };
+                (&invaders, reach)
+            };
+
+            let (enemy, enemy_position) = match nearest(enemies, &position, reach) {
+                Some(enemy) => enemy,
+                None => {
+                    if fighter.target.take().is_some() {
+                        pathing.stop();
+                    }
+                    continue;
+                },
+            };
+            fighter.target = Some(enemy);
+
+            if distance(&position, &enemy_position) > 1 {
+                if pathing.unreachable && rng.gen::<f64>() >= RETRY_CHANCE {
+                    continue;
+                }
+                if pathing.target != Some(enemy_position) {
+                    pathing.walk_to(enemy_position, true);
+                }
+                continue;
+            }
+
+            if fighter.cooldown == 0 {
+                fighter.cooldown = ATTACK_COOLDOWN;
+                if rng.gen::<f64>() < HIT_CHANCE {
+                    damage.push(DamageEvent {
+                        target: enemy,
+                        amount: fighter.damage,
+                    });
+                }
+            }
+        }
+    }
+
+    let entities = &mut state.entities;
+    let mut dead = Vec::new();
+    for event in damage {
+        if let Some(health) = entities.health.get_mut(event.target) {
+            health.current -= event.amount;
+            if health.is_dead() && !dead.contains(&event.target) {
+                dead.push(event.target);
+            }
+        }
+    }
+    for entity in dead {
+        if entities.colonists.contains(entity) {
+            events.push(WorldEvent::ColonistDied(entity));
+        }
+        systems::interrupt_job(entities, &mut state.job_queue, entity);
+        entities.despawn(entity);
+    }
+}
+
+/// Returns how wealthy the colony is, which determines how many invaders a
+/// raid brings.
+fn colony_wealth(entities: &Entities) -> u32 {
+    entities.items.iter().count() as u32 * ITEM_WEALTH +
+        entities.furniture.iter().count() as u32 * FURNITURE_WEALTH
+}
+
+/// Spawns a band of invaders, sized by the wealth of the colony, at a single
+/// place some way off from one of the colonists.
+fn spawn_raid(state: &mut WorldState, area: &Area, materials: &MaterialRegistry, events: &mut Vec<WorldEvent>) {
+    let colonists: Vec<Position> = state.entities.colonists.iter()
+        .filter_map(|(entity, _)| state.entities.positions.get(entity))
+        .cloned()
+        .collect();
+    let center = match state.rng.choose(&colonists) {
+        Some(center) => *center,
+        None => return,
+    };
+    let position = match wildlife::find_spawn_position(&mut state.rng, area, materials, &center) {
+        Some(position) => position,
+        None => return,
+    };
+
+    let count = (1 + colony_wealth(&state.entities) / WEALTH_PER_INVADER).min(MAX_INVADERS_PER_RAID);
+    for _ in 0..count {
+        events.push(WorldEvent::Spawned(spawn_invader(&mut state.entities, position)));
+    }
+}
+
+/// Spawns an invader standing at `position`.
+fn spawn_invader(entities: &mut Entities, position: Position) -> Entity {
+    let entity = entities.spawn();
+
+    entities.positions.insert(entity, position);
+    entities.renderables.insert(entity, Renderable { color: INVADER_COLOR });
+    entities.pathing.insert(entity, Pathing::default());
+    entities.health.insert(entity, Health::new(INVADER_HEALTH));
+    entities.fighters.insert(entity, Fighter::new(INVADER_DAMAGE));
+    entities.invaders.insert(entity, Invader);
+    entity
+}
+
+/// Returns every entity which can be hurt and for which `predicate` returns
+/// true, along with its position.
+fn positions_of<F>(entities: &Entities, predicate: F) -> Vec<(Entity, Position)>
+    where F: Fn(&Entities, Entity) -> bool,
+{
+    entities.health.iter()
+        .filter(|&(entity, _)| predicate(entities, entity))
+        .filter_map(|(entity, _)| entities.positions.get(entity).map(|position| (entity, *position)))
+        .collect()
+}
+
+/// Returns the enemy nearest to `position` on the same z-level, if it lies
+/// within `reach`, along with its position.
+fn nearest(enemies: &[(Entity, Position)], position: &Position, reach: i32) -> Option<(Entity, Position)> {
+    enemies.iter()
+        .filter(|&&(_, enemy_position)| enemy_position.y == position.y)
+        .map(|&(enemy, enemy_position)| (distance(position, &enemy_position), enemy, enemy_position))
+        .filter(|&(distance, _, _)| distance <= reach)
+        .min_by_key(|&(distance, _, _)| distance)
+        .map(|(_, enemy, enemy_position)| (enemy, enemy_position))
+}
+
+/// Returns the distance between two positions along the horizontal axes.
+fn distance(a: &Position, b: &Position) -> i32 {
+    (a.x - b.x).abs() + (a.z - b.z).abs()
+}
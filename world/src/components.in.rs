@@ -0,0 +1,179 @@
+/// The tile an entity occupies, in absolute coordinates.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// How an entity is drawn.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Renderable {
+    pub color: [f32; 4],
+}
+
+/// Where an entity is walking to, and the route it is following.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Pathing {
+    /// The tile the entity is walking towards, if any.
+    pub target: Option<Position>,
+    /// Whether the entity stops beside `target`, rather than on it.
+    pub adjacent: bool,
+    /// The remaining tiles of the route, with the next tile last.
+    pub path: Vec<Position>,
+    /// Set when no route to `target` could be found.
+    pub unreachable: bool,
+}
+
+/// A task assigned to a colonist.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Job {
+    pub kind: JobKind,
+    /// The tile the job is performed on.
+    pub target: Position,
+    /// How much of the work has been done, in ticks. Digs keep their progress
+    /// in the metadata of the tile being dug instead, so that it outlives the
+    /// job.
+    pub progress: f32,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum JobKind {
+    /// Removes the tile at the target of the job.
+    Dig,
+    /// Removes the tile at the target of the job, leaving stairs in its place.
+    /// The tile can be dug from the tile above it, so that colonists can dig
+    /// their way down.
+    DigStairs,
+    /// Carries an item to the stockpile tile at the target of the job.
+    Haul(Entity),
+    /// Walks to a food item lying at the target of the job and eats it.
+    Eat(Entity),
+    /// Sleeps where the colonist stands until rested.
+    Sleep,
+    /// Fetches the material for the blueprint at the target of the job, and
+    /// builds it.
+    Build(Entity),
+    /// Chases down an animal and kills it for food. The target of the job is
+    /// where the animal was when the job was queued.
+    Hunt(Entity),
+    /// Works beside the target of the job for a while, for a job defined by a
+    /// mod. Refers to the name of the job by its index in the custom jobs of
+    /// the world.
+    Custom(u32),
+}
+
+/// A member of the colony, which carries out jobs.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Colonist {
+    pub name: String,
+    /// The item the colonist is carrying, if any.
+    #[serde(default)]
+    pub carrying: Option<Entity>,
+    /// Whether the colonist has been drafted, in which case it takes no jobs
+    /// and only moves where it is ordered to.
+    #[serde(default)]
+    pub drafted: bool,
+}
+
+/// How well the needs of a colonist are met, each from 0 (not at all) to 1
+/// (fully).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Needs {
+    pub food: f32,
+    pub rest: f32,
+    /// Follows how well the other needs have been met lately, and affects how
+    /// quickly the colonist works.
+    pub mood: f32,
+}
+
+/// How much more damage an entity can take before it dies.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+/// An entity which fights in melee.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Fighter {
+    /// The damage dealt by each blow which lands.
+    pub damage: f32,
+    /// The number of ticks until the entity can strike again.
+    #[serde(default)]
+    pub cooldown: u32,
+    /// The entity being fought, if any.
+    #[serde(default)]
+    pub target: Option<Entity>,
+}
+
+/// An enemy of the colony, which seeks out colonists and fights them.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Invader;
+
+/// A wild animal, which wanders about and runs away from colonists.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Animal {
+    pub kind: AnimalKind,
+    /// The number of ticks the animal has spent running away lately. Animals
+    /// which have run for too long are too tired to keep running.
+    #[serde(default)]
+    pub fatigue: u32,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum AnimalKind {
+    Deer,
+    Goat,
+    Lizard,
+    Rabbit,
+}
+
+/// A loose object which colonists can carry. Items lying on the ground have a
+/// position, while carried items do not.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Item {
+    pub kind: ItemKind,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ItemKind {
+    Stone,
+    Wood,
+    Food,
+}
+
+/// Something which colonists can build.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Structure {
+    /// Fills the tile with a wall.
+    Wall,
+    /// Fills the tile beneath with a floor, so that the tile can be walked on.
+    Floor,
+    /// Places stairs in the tile.
+    Stairs,
+    /// Places a ramp in the tile, leading up to the tile above the next one.
+    Ramp,
+    /// Places a workshop in the tile.
+    Workshop,
+}
+
+/// A structure waiting to be built at the position of the entity.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Blueprint {
+    pub structure: Structure,
+    /// The item set aside to build the structure from, if one has been
+    /// chosen.
+    pub material: Option<Entity>,
+}
+
+/// A piece of furniture standing at the position of the entity.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Furniture {
+    pub kind: FurnitureKind,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum FurnitureKind {
+    Workshop,
+}
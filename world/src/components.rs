@@ -0,0 +1,190 @@
+use cgmath::Point3;
+
+use entity::Entity;
+
+#[cfg(feature = "nightly")]
+include!("components.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/components.rs"));
+
+impl Position {
+    pub fn new(x: i32, y: i32, z: i32) -> Self {
+        Position {
+            x: x,
+            y: y,
+            z: z,
+        }
+    }
+
+    pub fn to_point(&self) -> Point3<i32> {
+        Point3::new(self.x, self.y, self.z)
+    }
+}
+
+impl From<Point3<i32>> for Position {
+    fn from(p: Point3<i32>) -> Self {
+        Position::new(p.x, p.y, p.z)
+    }
+}
+
+impl Pathing {
+    /// Sets the tile to walk to, discarding the current route.
+    pub fn walk_to(&mut self, target: Position, adjacent: bool) {
+        self.target = Some(target);
+        self.adjacent = adjacent;
+        self.path.clear();
+        self.unreachable = false;
+    }
+
+    /// Stops walking, discarding the current route.
+    pub fn stop(&mut self) {
+        self.target = None;
+        self.path.clear();
+        self.unreachable = false;
+    }
+}
+
+impl Needs {
+    pub fn new() -> Self {
+        Needs {
+            food: 1.0,
+            rest: 1.0,
+            mood: 1.0,
+        }
+    }
+
+    /// Returns the factor by which the work of the colonist is sped up or
+    /// slowed down by its mood.
+    pub fn work_speed(&self) -> f32 {
+        0.5 + self.mood * 0.75
+    }
+}
+
+impl Default for Needs {
+    fn default() -> Self {
+        Needs::new()
+    }
+}
+
+impl ItemKind {
+    /// Returns the name by which this kind of item is referred to.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ItemKind::Stone => "stone",
+            ItemKind::Wood => "wood",
+            ItemKind::Food => "food",
+        }
+    }
+
+    /// Returns the kind of item with the specified name, if any.
+    pub fn from_name(name: &str) -> Option<ItemKind> {
+        [ItemKind::Stone, ItemKind::Wood, ItemKind::Food].iter()
+            .find(|kind| kind.name() == name)
+            .cloned()
+    }
+
+    /// Returns the color in which items of this kind are drawn.
+    pub fn color(&self) -> [f32; 4] {
+        match *self {
+            ItemKind::Stone => [0.6, 0.6, 0.6, 1.0],
+            ItemKind::Wood => [0.55, 0.35, 0.15, 1.0],
+            ItemKind::Food => [0.85, 0.2, 0.2, 1.0],
+        }
+    }
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Health {
+            current: max,
+            max: max,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+}
+
+impl Fighter {
+    pub fn new(damage: f32) -> Self {
+        Fighter {
+            damage: damage,
+            cooldown: 0,
+            target: None,
+        }
+    }
+}
+
+impl AnimalKind {
+    /// Returns the name by which this kind of animal is referred to.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            AnimalKind::Deer => "deer",
+            AnimalKind::Goat => "goat",
+            AnimalKind::Lizard => "lizard",
+            AnimalKind::Rabbit => "rabbit",
+        }
+    }
+
+    /// Returns the color in which animals of this kind are drawn.
+    pub fn color(&self) -> [f32; 4] {
+        match *self {
+            AnimalKind::Deer => [0.6, 0.4, 0.2, 1.0],
+            AnimalKind::Goat => [0.85, 0.85, 0.8, 1.0],
+            AnimalKind::Lizard => [0.4, 0.6, 0.2, 1.0],
+            AnimalKind::Rabbit => [0.7, 0.65, 0.55, 1.0],
+        }
+    }
+
+    /// Returns the number of food items left by killing an animal of this
+    /// kind.
+    pub fn food_yield(&self) -> u32 {
+        match *self {
+            AnimalKind::Deer => 4,
+            AnimalKind::Goat => 3,
+            AnimalKind::Lizard | AnimalKind::Rabbit => 1,
+        }
+    }
+}
+
+impl Structure {
+    /// Returns the kind of item consumed by building the structure.
+    pub fn material_needed(&self) -> ItemKind {
+        match *self {
+            Structure::Wall | Structure::Stairs | Structure::Ramp => ItemKind::Stone,
+            Structure::Floor | Structure::Workshop => ItemKind::Wood,
+        }
+    }
+
+    /// Returns the number of ticks needed to build the structure once its
+    /// material has been brought to it.
+    pub fn build_ticks(&self) -> f32 {
+        match *self {
+            Structure::Wall => 20.0,
+            Structure::Floor => 10.0,
+            Structure::Stairs | Structure::Ramp => 15.0,
+            Structure::Workshop => 40.0,
+        }
+    }
+}
+
+impl FurnitureKind {
+    /// Returns the color in which furniture of this kind is drawn.
+    pub fn color(&self) -> [f32; 4] {
+        match *self {
+            FurnitureKind::Workshop => [0.5, 0.3, 0.1, 1.0],
+        }
+    }
+}
+
+impl Job {
+    pub fn new(kind: JobKind, target: Position) -> Self {
+        Job {
+            kind: kind,
+            target: target,
+            progress: 0.0,
+        }
+    }
+}
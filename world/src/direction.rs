@@ -19,4 +19,13 @@ impl Direction {
             Down => -Vector3::unit_y(),
         }
     }
+
+    /// Returns true if this direction moves between z-levels rather than
+    /// along the horizontal plane.
+    pub fn is_vertical(&self) -> bool {
+        match *self {
+            Direction::Up | Direction::Down => true,
+            _ => false,
+        }
+    }
 }
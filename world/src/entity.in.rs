@@ -0,0 +1,50 @@
+/// Identifies an entity. The generation distinguishes an entity from any
+/// earlier entity which was despawned and whose index was reused.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+/// The components of a single type, indexed by entity.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Storage<T> {
+    /// The generation of the entity owning each component, and the component
+    /// itself, by entity index.
+    components: Vec<Option<(u32, T)>>,
+}
+
+/// Every entity of the world, and their components.
+///
+/// Each type of component is kept in its own storage, so that systems can
+/// borrow the storages they read and write independently of each other.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Entities {
+    /// The current generation of every entity index.
+    generations: Vec<u32>,
+    /// Whether the entity of each index is currently alive.
+    alive: Vec<bool>,
+    /// Indices of despawned entities, which are reused by new entities.
+    free: Vec<u32>,
+    pub positions: Storage<Position>,
+    pub renderables: Storage<Renderable>,
+    pub pathing: Storage<Pathing>,
+    pub jobs: Storage<Job>,
+    pub colonists: Storage<Colonist>,
+    #[serde(default)]
+    pub items: Storage<Item>,
+    #[serde(default)]
+    pub needs: Storage<Needs>,
+    #[serde(default)]
+    pub blueprints: Storage<Blueprint>,
+    #[serde(default)]
+    pub furniture: Storage<Furniture>,
+    #[serde(default)]
+    pub animals: Storage<Animal>,
+    #[serde(default)]
+    pub health: Storage<Health>,
+    #[serde(default)]
+    pub fighters: Storage<Fighter>,
+    #[serde(default)]
+    pub invaders: Storage<Invader>,
+}
@@ -0,0 +1,167 @@
+use components::{Animal, Blueprint, Colonist, Fighter, Furniture, Health, Invader, Item, Job, Needs, Pathing, Position, Renderable};
+
+#[cfg(feature = "nightly")]
+include!("entity.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/entity.rs"));
+
+impl<T> Storage<T> {
+    pub fn new() -> Self {
+        Storage {
+            components: Vec::new(),
+        }
+    }
+
+    /// Attaches `component` to `entity`, replacing any component of the same
+    /// type which it already had.
+    pub fn insert(&mut self, entity: Entity, component: T) {
+        let index = entity.index as usize;
+        while self.components.len() <= index {
+            self.components.push(None);
+        }
+        self.components[index] = Some((entity.generation, component));
+    }
+
+    /// Detaches the component of this type from `entity`, returning it.
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        if self.get(entity).is_none() {
+            return None;
+        }
+        self.components[entity.index as usize].take().map(|(_, component)| component)
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        match self.components.get(entity.index as usize) {
+            Some(&Some((generation, ref component))) if generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        match self.components.get_mut(entity.index as usize) {
+            Some(&mut Some((generation, ref mut component))) if generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    /// Returns an iterator over every entity with a component of this type,
+    /// and the component.
+    pub fn iter<'a>(&'a self) -> Box<Iterator<Item=(Entity, &'a T)> + 'a> {
+        Box::new(self.components.iter().enumerate().filter_map(|(index, component)| {
+            component.as_ref().map(|&(generation, ref component)| {
+                (Entity { index: index as u32, generation: generation }, component)
+            })
+        }))
+    }
+
+    /// Returns an iterator over every entity with a component of this type,
+    /// and a mutable reference to the component.
+    pub fn iter_mut<'a>(&'a mut self) -> Box<Iterator<Item=(Entity, &'a mut T)> + 'a> {
+        Box::new(self.components.iter_mut().enumerate().filter_map(|(index, component)| {
+            component.as_mut().map(|&mut (generation, ref mut component)| {
+                (Entity { index: index as u32, generation: generation }, component)
+            })
+        }))
+    }
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Storage::new()
+    }
+}
+
+impl Entities {
+    pub fn new() -> Self {
+        Entities {
+            generations: Vec::new(),
+            alive: Vec::new(),
+            free: Vec::new(),
+            positions: Storage::new(),
+            renderables: Storage::new(),
+            pathing: Storage::new(),
+            jobs: Storage::new(),
+            colonists: Storage::new(),
+            items: Storage::new(),
+            needs: Storage::new(),
+            blueprints: Storage::new(),
+            furniture: Storage::new(),
+            animals: Storage::new(),
+            health: Storage::new(),
+            fighters: Storage::new(),
+            invaders: Storage::new(),
+        }
+    }
+
+    /// Creates a new entity without any components.
+    pub fn spawn(&mut self) -> Entity {
+        match self.free.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+                Entity {
+                    index: index,
+                    generation: self.generations[index as usize],
+                }
+            },
+            None => {
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity {
+                    index: self.generations.len() as u32 - 1,
+                    generation: 0,
+                }
+            },
+        }
+    }
+
+    /// Removes an entity along with all of its components.
+    pub fn despawn(&mut self, entity: Entity) {
+        if !self.is_alive(entity) {
+            return;
+        }
+
+        self.positions.remove(entity);
+        self.renderables.remove(entity);
+        self.pathing.remove(entity);
+        self.jobs.remove(entity);
+        self.colonists.remove(entity);
+        self.items.remove(entity);
+        self.needs.remove(entity);
+        self.blueprints.remove(entity);
+        self.furniture.remove(entity);
+        self.animals.remove(entity);
+        self.health.remove(entity);
+        self.fighters.remove(entity);
+        self.invaders.remove(entity);
+
+        let index = entity.index as usize;
+        self.alive[index] = false;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free.push(entity.index);
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        index < self.alive.len() && self.alive[index] && self.generations[index] == entity.generation
+    }
+
+    /// Returns the number of living entities.
+    pub fn len(&self) -> usize {
+        self.generations.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for Entities {
+    fn default() -> Self {
+        Entities::new()
+    }
+}
@@ -0,0 +1,118 @@
+use calendar::Season;
+use components::{JobKind, Position};
+use entity::Entity;
+use material::MaterialId;
+
+/// Something which happened in the world during a step of the simulation, for
+/// the game to respond to (such as by playing a sound).
+///
+/// The systems of the world only report what happened, so that whatever
+/// responds to it, such as the sounds, the particles and the hooks of mods,
+/// never needs to reach into them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WorldEvent {
+    /// The material of a tile changed from the first to the second, however
+    /// it was changed: dug out, built on, flooded or set by a mod.
+    VoxelChanged(Position, MaterialId, MaterialId),
+    /// A tile was dug out.
+    Dug(Position),
+    /// A colonist finished a job of the specified kind at the target of the
+    /// job. Jobs which are abandoned are not reported.
+    JobFinished(Entity, JobKind, Position),
+    /// An entity was spawned, such as an animal wandering in, an invader or
+    /// an item dropped by a job.
+    Spawned(Entity),
+    /// A season began.
+    SeasonChanged(Season),
+    /// A colonist died, and was despawned.
+    ColonistDied(Entity),
+}
+
+impl WorldEvent {
+    pub fn kind(&self) -> EventKind {
+        match *self {
+            WorldEvent::VoxelChanged(..) => EventKind::VoxelChanged,
+            WorldEvent::Dug(..) => EventKind::Dug,
+            WorldEvent::JobFinished(..) => EventKind::JobFinished,
+            WorldEvent::Spawned(..) => EventKind::Spawned,
+            WorldEvent::SeasonChanged(..) => EventKind::SeasonChanged,
+            WorldEvent::ColonistDied(..) => EventKind::ColonistDied,
+        }
+    }
+}
+
+/// The kinds of `WorldEvent`, by which a subscriber chooses the events it
+/// receives.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EventKind {
+    VoxelChanged,
+    Dug,
+    JobFinished,
+    Spawned,
+    SeasonChanged,
+    ColonistDied,
+}
+
+/// Identifies a subscriber of an `EventBus`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Subscription(usize);
+
+struct Subscriber {
+    kinds: Vec<EventKind>,
+    /// The events published since the subscriber last took them.
+    pending: Vec<WorldEvent>,
+}
+
+/// Delivers the events of the world to every subscriber of their kind.
+///
+/// Each subscriber takes its own events when it is ready to respond to them,
+/// so that one subscriber taking them never hides them from another, and
+/// events of kinds nobody subscribed to are not kept at all.
+pub struct EventBus {
+    subscribers: Vec<Subscriber>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        EventBus {
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Subscribes to the events of the specified kinds published from now on.
+    pub fn subscribe(&mut self, kinds: &[EventKind]) -> Subscription {
+        self.subscribers.push(Subscriber {
+            kinds: kinds.to_vec(),
+            pending: Vec::new(),
+        });
+        Subscription(self.subscribers.len() - 1)
+    }
+
+    /// Queues `event` for every subscriber of its kind.
+    pub fn publish(&mut self, event: WorldEvent) {
+        let kind = event.kind();
+        for subscriber in &mut self.subscribers {
+            if subscriber.kinds.contains(&kind) {
+                subscriber.pending.push(event);
+            }
+        }
+    }
+
+    /// Returns the events published for `subscription` since it last took
+    /// them, in the order in which they were published.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `subscription` was returned by another bus.
+    pub fn take(&mut self, subscription: Subscription) -> Vec<WorldEvent> {
+        ::std::mem::replace(&mut self.subscribers[subscription.0].pending, Vec::new())
+    }
+
+    /// Forgets the events which have not been taken yet, keeping the
+    /// subscribers.
+    pub fn clear(&mut self) {
+        for subscriber in &mut self.subscribers {
+            subscriber.pending.clear();
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use cgmath::Point3;
+
+use area::Area;
+use direction::Direction;
+use material::{MaterialId, MaterialRegistry};
+use terrain::Tile;
+
+/// The amount of fluid held by a completely full tile.
+pub const MAX_FLUID_LEVEL: u8 = 8;
+
+const HORIZONTAL_DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+
+/// Advances the fluid simulation by one step.
+///
+/// Only the tiles which changed since the previous step (and their
+/// neighbours) are considered. Each fluid tile first flows as much of its
+/// volume as possible into the tile below it, then passes single units of its
+/// remaining volume to any horizontal neighbours holding less than it, so that
+/// the total volume of fluid is conserved.
+pub fn tick(area: &mut Area, materials: &MaterialRegistry) {
+    let air = materials.find("air").expect("required material is defined");
+
+    for pos in area.take_active_fluids() {
+        let tile = area.get_tile(&pos);
+        if !materials.is_liquid(tile.material) || tile.fluid_level == 0 {
+            continue;
+        }
+
+        let mut level = tile.fluid_level;
+
+        let below = pos + Direction::Down.to_vector();
+        let flow = capacity(area, air, tile.material, &below);
+        if flow > 0 {
+            let flow = if flow < level { flow } else { level };
+            add_fluid(area, tile.material, &below, flow);
+            level -= flow;
+        }
+
+        for direction in &HORIZONTAL_DIRECTIONS {
+            if level <= 1 {
+                break;
+            }
+
+            let neighbour = pos + direction.to_vector();
+            let neighbour_level = match capacity(area, air, tile.material, &neighbour) {
+                0 => continue,
+                capacity => MAX_FLUID_LEVEL - capacity,
+            };
+            if neighbour_level + 1 < level {
+                add_fluid(area, tile.material, &neighbour, 1);
+                level -= 1;
+            }
+        }
+
+        if level != tile.fluid_level {
+            let tile = match level {
+                0 => Tile::new(air),
+                _ => Tile::with_fluid_level(tile.material, level),
+            };
+            area.set_tile(&pos, tile);
+        }
+    }
+}
+
+/// Returns the amount of `fluid` that the tile at `pos` can accept. Only empty
+/// tiles and tiles holding the same fluid can accept any.
+fn capacity(area: &Area, air: MaterialId, fluid: MaterialId, pos: &Point3<i32>) -> u8 {
+    let tile = area.get_tile(pos);
+    match tile.material {
+        material if material == air => MAX_FLUID_LEVEL,
+        material if material == fluid => MAX_FLUID_LEVEL - tile.fluid_level,
+        _ => 0,
+    }
+}
+
+fn add_fluid(area: &mut Area, fluid: MaterialId, pos: &Point3<i32>, amount: u8) {
+    let tile = area.get_tile(pos);
+    let level = if tile.material == fluid { tile.fluid_level } else { 0 };
+    area.set_tile(pos, Tile::with_fluid_level(fluid, level + amount));
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Point2, Point3};
+
+    use area::Area;
+    use material::MaterialRegistry;
+    use terrain::Tile;
+    use world_gen_config::WorldGenConfig;
+    use super::{tick, MAX_FLUID_LEVEL};
+
+    const SEED: u32 = 7;
+    const STEPS: usize = 32;
+    /// The distance from the poured water within which the tiles of the two
+    /// areas are compared.
+    const RADIUS: i32 = 8;
+
+    /// Pours a row of water over the surface at the origin of a freshly
+    /// generated area, and lets it flow for `STEPS` steps.
+    fn pour(materials: &MaterialRegistry) -> (Area, Point3<i32>) {
+        let mut area = Area::new(SEED, 1, Point2::new(0, 0), WorldGenConfig::default(), materials, None);
+        let water = materials.find("water").unwrap();
+        let origin = Point3::new(0, area.surface_height(0, 0) + 2, 0);
+        for x in -2..3 {
+            area.set_tile(&Point3::new(origin.x + x, origin.y, origin.z), Tile::with_fluid_level(water, MAX_FLUID_LEVEL));
+        }
+        for _ in 0..STEPS {
+            tick(&mut area, materials);
+        }
+        (area, origin)
+    }
+
+    #[test]
+    fn flows_alike_in_areas_set_up_alike() {
+        let materials = MaterialRegistry::default();
+        let (first, origin) = pour(&materials);
+        let (second, _) = pour(&materials);
+
+        for y in -RADIUS..RADIUS + 1 {
+            for z in -RADIUS..RADIUS + 1 {
+                for x in -RADIUS..RADIUS + 1 {
+                    let pos = Point3::new(origin.x + x, origin.y + y, origin.z + z);
+                    let (a, b) = (first.get_tile(&pos), second.get_tile(&pos));
+                    assert_eq!((a.material, a.fluid_level), (b.material, b.fluid_level), "at {:?}", pos);
+                }
+            }
+        }
+    }
+}
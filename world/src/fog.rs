@@ -0,0 +1,81 @@
+use cgmath::Point3;
+
+use area::Area;
+use components::Position;
+use direction::Direction;
+use material::MaterialRegistry;
+use world::WorldState;
+
+// Every tile within the sight radius of a colonist is checked whenever the
+// tiles it sees are revealed, so the cost of revealing grows with its square.
+/// The distance within which colonists see the tiles of their z-level.
+const SIGHT_RADIUS: i32 = 10;
+
+const DIRECTIONS: [Direction; 6] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+    Direction::Up,
+    Direction::Down,
+];
+
+/// Reveals the tiles which every colonist can see: those of its z-level
+/// within `SIGHT_RADIUS` of it, unless something solid stands in the way.
+/// Solid tiles are revealed too, but hide whatever lies beyond them.
+pub fn tick(state: &WorldState, area: &mut Area, materials: &MaterialRegistry) {
+    let colonists: Vec<Position> = state.entities.colonists.iter()
+        .filter_map(|(entity, _)| state.entities.positions.get(entity))
+        .cloned()
+        .collect();
+
+    for colonist in colonists {
+        let eye = colonist.to_point();
+        for dz in -SIGHT_RADIUS..SIGHT_RADIUS + 1 {
+            for dx in -SIGHT_RADIUS..SIGHT_RADIUS + 1 {
+                let pos = Point3::new(eye.x + dx, eye.y, eye.z + dz);
+                let is_within_radius = dx * dx + dz * dz <= SIGHT_RADIUS * SIGHT_RADIUS;
+                if is_within_radius && !area.is_revealed(&pos) && is_in_sight(area, materials, &eye, &pos) {
+                    area.reveal(&pos);
+                }
+            }
+        }
+    }
+}
+
+/// Reveals the tile at `pos` and the tiles beside, above and below it, such
+/// as when it is dug out.
+pub fn reveal_around(area: &mut Area, pos: &Point3<i32>) {
+    area.reveal(pos);
+    for direction in &DIRECTIONS {
+        area.reveal(&(*pos + direction.to_vector()));
+    }
+}
+
+/// Returns true if nothing solid lies between `eye` and `target` along the
+/// line joining them, which is traced across the z-level of `eye`.
+fn is_in_sight(area: &Area, materials: &MaterialRegistry, eye: &Point3<i32>, target: &Point3<i32>) -> bool {
+    let (dx, dz) = ((target.x - eye.x).abs(), (target.z - eye.z).abs());
+    let (step_x, step_z) = ((target.x - eye.x).signum(), (target.z - eye.z).signum());
+    let mut error = dx - dz;
+    let (mut x, mut z) = (eye.x, eye.z);
+
+    loop {
+        let error_twice = error * 2;
+        if error_twice > -dz {
+            error -= dz;
+            x += step_x;
+        }
+        if error_twice < dx {
+            error += dx;
+            z += step_z;
+        }
+
+        if x == target.x && z == target.z {
+            return true;
+        }
+        if materials.is_solid(area.get_tile(&Point3::new(x, eye.y, z)).material) {
+            return false;
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::{HashMap, VecDeque};
+
+use area::Area;
+use chunk::ChunkPos;
+use region;
+use world::WorldState;
+
+/// The state of the world at some point of the simulation.
+struct Snapshot {
+    state: WorldState,
+    /// The chunks modified between this snapshot and the next, encoded as
+    /// they were when this one was taken. Always empty for the latest
+    /// snapshot, whose modified chunks are journaled by the area instead.
+    chunks: HashMap<ChunkPos, Vec<u8>>,
+}
+
+/// A record of the most recent states of a world, to which it can be rewound.
+///
+/// Rather than a copy of every chunk, each snapshot only keeps the chunks
+/// modified after it was taken, as they were when it was. These are kept in
+/// the encoding in which chunks are saved, where tiles are stored as runs,
+/// so that a snapshot of a world where little has changed takes little
+/// memory.
+pub struct History {
+    snapshots: VecDeque<Snapshot>,
+    /// The number of snapshots kept, beyond which the oldest are discarded.
+    capacity: usize,
+}
+
+impl History {
+    /// Creates a history which keeps no snapshots.
+    pub fn new() -> Self {
+        History {
+            snapshots: VecDeque::new(),
+            capacity: 0,
+        }
+    }
+
+    /// Returns the number of snapshots to which the world can be rewound.
+    pub fn snapshot_count(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Keeps up to `capacity` snapshots from now on, journaling the chunks
+    /// of `area` whose tiles are set unless it is zero.
+    pub fn set_capacity(&mut self, capacity: usize, area: &mut Area) {
+        self.capacity = capacity;
+        while self.snapshots.len() > capacity {
+            self.snapshots.pop_front();
+        }
+        area.set_journaling(capacity > 0);
+    }
+
+    /// Takes a snapshot of `state` and of the chunks of `area`, discarding
+    /// the oldest snapshot if there are too many.
+    pub fn record(&mut self, state: &WorldState, area: &mut Area) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let journal = area.take_journal();
+        if let Some(latest) = self.snapshots.back_mut() {
+            latest.chunks = journal;
+        }
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(Snapshot {
+            state: state.clone(),
+            chunks: HashMap::new(),
+        });
+    }
+
+    /// Restores the chunks of `area` as they were when the latest snapshot
+    /// was taken, returning the state of the world then, or `None` if there
+    /// are no snapshots.
+    ///
+    /// The snapshot is removed, so that rewinding again goes back to the
+    /// snapshot before it.
+    pub fn rewind(&mut self, area: &mut Area) -> Option<WorldState> {
+        let snapshot = match self.snapshots.pop_back() {
+            Some(snapshot) => snapshot,
+            None => return None,
+        };
+
+        for (pos, data) in area.take_journal() {
+            if let Ok(chunk) = region::decode_chunk(&data) {
+                area.restore_chunk(pos, chunk);
+            }
+        }
+        // The chunks modified since the previous snapshot are journaled
+        // again, as they are now the ones to restore to rewind to it.
+        if let Some(previous) = self.snapshots.back_mut() {
+            area.extend_journal(::std::mem::replace(&mut previous.chunks, HashMap::new()));
+        }
+
+        Some(snapshot.state)
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new()
+    }
+}
@@ -2,11 +2,13 @@
 #![cfg_attr(feature = "nightly", plugin(serde_macros))]
 #![cfg_attr(feature = "clippy", plugin(clippy))]
 
-extern crate array;
 extern crate cgmath;
+#[macro_use]
+extern crate log;
 extern crate noise;
-extern crate num;
+extern crate num_cpus;
 extern crate rand;
+extern crate rayon;
 extern crate colonize_utility as utility;
 
 // TODO: refactor these values to be configurable.
@@ -17,18 +19,56 @@ pub const CHUNK_SIZE: usize = 16;
 // This should always be the log base 2 of `CHUNK_SIZE`.
 // TODO: find a way to generate this at runtime.
 pub const LOG2_OF_CHUNK_SIZE: u32 = 4;
-// The multiplier by which the generated height maps are multiplied.
-pub const HEIGHT_MAP_MULTIPLIER: f64 = 32.0;
 
-pub use self::area::abs_pos_to_chunk_pos;
-pub use self::chunk::Chunk;
+pub use self::area::{Area, abs_pos_to_chunk_pos, abs_pos_to_rel_chunk_pos};
+pub use self::biome::Biome;
+pub use self::calendar::{Calendar, Season, TICKS_PER_HOUR};
+pub use self::chunk_loader::ChunkLoadError;
+pub use self::chunk::{Chunk, ChunkArray2d, ChunkPos, RowIter, SliceIter, TileMetadata, Tiles, Visibility};
+pub use self::components::{Animal, AnimalKind, Blueprint, Colonist, Fighter, Furniture, FurnitureKind, Health, Invader, Item, ItemKind, Job, JobKind, Needs, Pathing, Position, Renderable, Structure};
 pub use self::direction::Direction;
-pub use self::terrain::TileType;
-pub use self::world::World;
+pub use self::entity::{Entities, Entity, Storage};
+pub use self::event::{EventKind, Subscription, WorldEvent};
+pub use self::fluid::MAX_FLUID_LEVEL;
+pub use self::light::MAX_LIGHT_LEVEL;
+pub use self::mapgen::WorldGenerator;
+pub use self::material::{Climb, Material, MaterialError, MaterialId, MaterialRegistry, OreDistribution, OUT_OF_BOUNDS};
+pub use self::navigation::NavGraph;
+pub use self::overworld::{Overworld, OverworldCell, CELL_CHUNKS, OVERWORLD_SIZE};
+pub use self::pathfinding::{SearchTrace, find_path, is_standable};
+pub use self::region::{RegionStore, REGION_SIZE, decode_chunk, encode_chunk};
+pub use self::rng::GameRng;
+pub use self::stats::{DayStats, Stats};
+pub use self::terrain::{TerrainMaterials, Tile};
+pub use self::weather::{Precipitation, Weather};
+pub use self::world::{World, WorldState};
+pub use self::world_gen_config::WorldGenConfig;
 
 mod area;
+mod biome;
+mod calendar;
 mod chunk;
+mod chunk_loader;
+mod combat;
+mod components;
 mod direction;
+mod entity;
+mod event;
+mod fluid;
+mod fog;
+mod history;
+mod light;
 mod mapgen;
+mod material;
+mod navigation;
+mod overworld;
+mod pathfinding;
+mod region;
+mod rng;
+mod stats;
+mod systems;
 mod terrain;
+mod weather;
+mod wildlife;
 mod world;
+mod world_gen_config;
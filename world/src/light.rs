@@ -0,0 +1,183 @@
+use std::collections::VecDeque;
+
+use cgmath::Point3;
+
+use CHUNK_SIZE;
+use area::Area;
+use chunk::{tile_index, ChunkPos, CHUNK_VOLUME};
+use direction::Direction;
+use material::MaterialRegistry;
+
+/// The light level of tiles lit directly by the sun. Light sources emit at
+/// most one less, so that sunlight can always be told apart.
+pub const MAX_LIGHT_LEVEL: u8 = 15;
+
+const DIRECTIONS: [Direction; 6] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+    Direction::Up,
+    Direction::Down,
+];
+
+/// Recomputes the light of up to `budget` of the chunks queued for
+/// relighting, leaving the rest queued for the next update.
+///
+/// Whenever the light of a chunk changes, its neighbours are queued in turn,
+/// since the light entering them may have changed too. Light spreads no
+/// further than `MAX_LIGHT_LEVEL` tiles, so this settles after a few updates.
+pub fn update(area: &mut Area, materials: &MaterialRegistry, budget: usize) {
+    let mut queued: Vec<ChunkPos> = area.take_unlit_chunks().into_iter().collect();
+    // Sunlight enters each chunk from the one above it, so chunks are lit
+    // from the top down.
+    queued.sort_by(|a, b| b.y.cmp(&a.y));
+    if queued.len() > budget {
+        for pos in queued.split_off(budget) {
+            area.queue_relight(pos);
+        }
+    }
+
+    for pos in queued {
+        let light = match compute(area, materials, pos) {
+            Some(light) => light,
+            None => continue,
+        };
+        if area.set_light(pos, light) {
+            for direction in &DIRECTIONS {
+                area.queue_relight(pos + direction.to_vector());
+            }
+        }
+    }
+}
+
+/// Computes the light level of every tile of the chunk at `pos`, from the
+/// sunlight and light entering it from its neighbours and from the light
+/// sources within it. Returns `None` if the chunk is not loaded.
+fn compute(area: &Area, materials: &MaterialRegistry, pos: ChunkPos) -> Option<Box<[u8]>> {
+    let chunk = match area.get_chunk(pos) {
+        Some(chunk) => chunk,
+        None => return None,
+    };
+
+    // Light passes through every tile which does not fill its space.
+    let transparent: Vec<bool> = chunk.palette().iter()
+        .map(|tile| !materials.is_solid(tile.material))
+        .collect();
+    let emitted: Vec<u8> = chunk.palette().iter()
+        .map(|tile| materials.light_emitted(tile.material).min(MAX_LIGHT_LEVEL - 1))
+        .collect();
+    let is_transparent = |p: &Point3<usize>| transparent[chunk.palette_index(p)];
+
+    let mut light = vec![0; CHUNK_VOLUME];
+    let mut queue = VecDeque::new();
+
+    // Sunlight shines straight down without dimming until it reaches an
+    // opaque tile. It enters through the top of the chunk wherever the bottom
+    // of the chunk above is sunlit, or everywhere if the chunk above is not
+    // loaded.
+    let above = area.get_chunk(pos + Direction::Up.to_vector());
+    for z in 0..CHUNK_SIZE {
+        for x in 0..CHUNK_SIZE {
+            let sunlit = above.map_or(true, |above| above.light_level(&Point3::new(x, 0, z)) == MAX_LIGHT_LEVEL);
+            if !sunlit {
+                continue;
+            }
+
+            for y in (0..CHUNK_SIZE).rev() {
+                let p = Point3::new(x, y, z);
+                if !is_transparent(&p) {
+                    break;
+                }
+                light[tile_index(x, y, z)] = MAX_LIGHT_LEVEL;
+                queue.push_back(p);
+            }
+        }
+    }
+
+    if emitted.iter().any(|&level| level > 0) {
+        for y in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let p = Point3::new(x, y, z);
+                    let level = emitted[chunk.palette_index(&p)];
+                    if level > light[tile_index(x, y, z)] {
+                        light[tile_index(x, y, z)] = level;
+                        queue.push_back(p);
+                    }
+                }
+            }
+        }
+    }
+
+    for direction in &DIRECTIONS {
+        let neighbour = match area.get_chunk(pos + direction.to_vector()) {
+            Some(neighbour) if neighbour.is_lit() => neighbour,
+            _ => continue,
+        };
+
+        for a in 0..CHUNK_SIZE {
+            for b in 0..CHUNK_SIZE {
+                let (inside, outside) = face_tiles(direction, a, b);
+                let level = neighbour.light_level(&outside).saturating_sub(1);
+                let i = tile_index(inside.x, inside.y, inside.z);
+                if level > light[i] && is_transparent(&inside) {
+                    light[i] = level;
+                    queue.push_back(inside);
+                }
+            }
+        }
+    }
+
+    // Light dims by one level for every tile it spreads to.
+    while let Some(p) = queue.pop_front() {
+        let level = light[tile_index(p.x, p.y, p.z)];
+        if level <= 1 {
+            continue;
+        }
+
+        for direction in &DIRECTIONS {
+            let neighbour = match neighbour_within_chunk(&p, direction) {
+                Some(neighbour) => neighbour,
+                None => continue,
+            };
+            let i = tile_index(neighbour.x, neighbour.y, neighbour.z);
+            if light[i] < level - 1 && is_transparent(&neighbour) {
+                light[i] = level - 1;
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    Some(light.into_boxed_slice())
+}
+
+/// Returns the position of a tile on the face of a chunk towards `direction`,
+/// and that of the adjacent tile in the neighbouring chunk, where `a` and `b`
+/// are the coordinates of the tile along the face.
+fn face_tiles(direction: &Direction, a: usize, b: usize) -> (Point3<usize>, Point3<usize>) {
+    let last = CHUNK_SIZE - 1;
+
+    match *direction {
+        Direction::North => (Point3::new(a, b, 0), Point3::new(a, b, last)),
+        Direction::South => (Point3::new(a, b, last), Point3::new(a, b, 0)),
+        Direction::West => (Point3::new(0, a, b), Point3::new(last, a, b)),
+        Direction::East => (Point3::new(last, a, b), Point3::new(0, a, b)),
+        Direction::Up => (Point3::new(a, last, b), Point3::new(a, 0, b)),
+        Direction::Down => (Point3::new(a, 0, b), Point3::new(a, last, b)),
+    }
+}
+
+/// Returns the position of the tile next to `p` in the specified direction,
+/// if it lies within the same chunk.
+fn neighbour_within_chunk(p: &Point3<usize>, direction: &Direction) -> Option<Point3<usize>> {
+    let v = direction.to_vector();
+    let (x, y, z) = (p.x as i32 + v.x, p.y as i32 + v.y, p.z as i32 + v.z);
+    let size = CHUNK_SIZE as i32;
+
+    if x < 0 || y < 0 || z < 0 || x >= size || y >= size || z >= size {
+        None
+    } else {
+        Some(Point3::new(x as usize, y as usize, z as usize))
+    }
+}
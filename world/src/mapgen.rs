@@ -1,51 +1,462 @@
-use array::Array;
-use cgmath::Point3;
-use noise::{ GenFn2, Seed };
-use num;
-use num::{ Float, NumCast };
+use cgmath::{Point2, Point3};
+use noise::{Seed, open_simplex2, open_simplex3};
+use rand::Rng;
+use rayon::prelude::*;
+use utility::profiler;
 
 use CHUNK_SIZE;
-use chunk::Chunk;
+use biome::Biome;
+use chunk::{Chunk, ChunkArray2d, ChunkArray3d, ChunkPos, Tiles, Visibility};
+use fluid::MAX_FLUID_LEVEL;
+use material::{MaterialId, MaterialRegistry, OreDistribution};
+use rng::GameRng;
+use terrain::{TerrainMaterials, Tile};
+use world_gen_config::WorldGenConfig;
 
-pub fn generate_chunk<F>(pos: Point3<i32>, height_map: [[f64; CHUNK_SIZE]; CHUNK_SIZE], mut set_chunk: F)
-    where F: FnMut(Point3<i32>, Chunk),
-{
-    set_chunk(pos, Chunk::generate(pos, height_map));
+// TODO: refactor these values to be configurable.
+const CLIMATE_SCALING_FACTOR: f64 = 1.0 / 256.0;
+const CAVE_SCALING_FACTOR: f64 = 1.0 / 32.0;
+// The distance, in tiles, between the climate samples blended together to
+// smooth the terrain along the borders between biomes.
+const BIOME_BLEND_DISTANCE: i32 = 8;
+
+// Offsets applied to the world seed so that each generation pass is
+// independent of the others.
+const TEMPERATURE_SEED_OFFSET: u32 = 0x9e37_79b9;
+const MOISTURE_SEED_OFFSET: u32 = 0x7f4a_7c15;
+const CAVE_SEED_OFFSETS: (u32, u32) = (0x85eb_ca6b, 0xc2b2_ae35);
+const ORE_SEED_OFFSET: u32 = 0x27d4_eb2f;
+const VEGETATION_SEED_OFFSET: u32 = 0x1656_67b1;
+// The dimensions of the generated trees, in tiles. The canopy radius is also
+// how far beyond its borders each chunk looks for trees whose canopy
+// overhangs it.
+const TREE_MIN_TRUNK_HEIGHT: i32 = 3;
+const TREE_MAX_TRUNK_HEIGHT: i32 = 6;
+const TREE_CANOPY_RADIUS: i32 = 2;
+// The offset, in noise space, between successive octaves of the height map,
+// so that their features do not all line up at the origin.
+const OCTAVE_OFFSET: f64 = 71.3;
+
+/// The height map and biomes of a column of chunks, indexed by `[z][x]`.
+pub struct Column {
+    pub heights: ChunkArray2d<i32>,
+    pub biomes: ChunkArray2d<Biome>,
+}
+
+/// A plant growing on top of a column of the world.
+enum Vegetation {
+    /// A tree with a trunk of the specified height.
+    Tree(i32),
+    Shrub,
+}
+
+/// Generates the terrain of the world from its seed.
+pub struct WorldGenerator {
+    config: WorldGenConfig,
+    seed: u32,
+    height_seed: Seed,
+    temperature_seed: Seed,
+    moisture_seed: Seed,
+    cave_seeds: (Seed, Seed),
+    materials: TerrainMaterials,
+    ores: Vec<(MaterialId, OreDistribution)>,
+}
+
+impl WorldGenerator {
+    pub fn new(seed: u32, config: WorldGenConfig, materials: &MaterialRegistry) -> Self {
+        WorldGenerator {
+            config: config,
+            seed: seed,
+            height_seed: Seed::new(seed),
+            temperature_seed: Seed::new(seed.wrapping_add(TEMPERATURE_SEED_OFFSET)),
+            moisture_seed: Seed::new(seed.wrapping_add(MOISTURE_SEED_OFFSET)),
+            cave_seeds: (
+                Seed::new(seed.wrapping_add(CAVE_SEED_OFFSETS.0)),
+                Seed::new(seed.wrapping_add(CAVE_SEED_OFFSETS.1)),
+            ),
+            materials: TerrainMaterials::new(materials),
+            ores: materials.ores(),
+        }
+    }
+
+    /// Returns the biome at the specified absolute position along the X and Z
+    /// axes.
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        let point = [x as f64 * CLIMATE_SCALING_FACTOR, z as f64 * CLIMATE_SCALING_FACTOR];
+        Biome::from_climate(
+            open_simplex2(&self.temperature_seed, &point),
+            open_simplex2(&self.moisture_seed, &point))
+    }
+
+    /// Generates the height map and biomes of the column of chunks at the
+    /// specified position, in chunk coordinates along the X and Z axes.
+    pub fn generate_column(&self, pos: Point2<i32>) -> Column {
+        let origin_x = pos.x * CHUNK_SIZE as i32;
+        let origin_z = pos.y * CHUNK_SIZE as i32;
+
+        Column {
+            heights: par_array_16x16(|x, z| self.height_at(origin_x + x as i32, origin_z + z as i32)),
+            biomes: par_array_16x16(|x, z| self.biome_at(origin_x + x as i32, origin_z + z as i32)),
+        }
+    }
+
+    /// Generates the chunk at the specified position.
+    ///
+    /// Every generation pass samples its noise and random numbers by absolute
+    /// position, so chunks can be generated in any order and still line up
+    /// seamlessly with their neighbours. The passes which only sample noise
+    /// work on the slices of the chunk in parallel, while those drawing random
+    /// numbers run serially, so that a seed always generates the same chunk.
+    pub fn generate_chunk(&self, pos: ChunkPos) -> Chunk {
+        let column = self.generate_column(Point2::new(pos.x, pos.z));
+        self.generate_chunk_in_column(pos, &column)
+    }
+
+    /// Generates the chunk at the specified position from the column which
+    /// contains it, which is faster than `generate_chunk` when generating
+    /// several chunks of the same column.
+    pub fn generate_chunk_in_column(&self, pos: ChunkPos, column: &Column) -> Chunk {
+        let _scope = profiler::scope("worldgen");
+        // The generation passes work on a plain array of tiles, which is only
+        // packed into a chunk once they are done.
+        let mut tiles = self.generate_terrain(pos, column);
+        self.place_ores(pos, column, &mut tiles);
+        if self.config.caves {
+            self.carve_caves(pos, column, &mut tiles);
+        }
+        self.plant_vegetation(pos, column, &mut tiles);
+        Chunk::from_tiles(&tiles).with_visibility(self.surface_visibility(pos, column))
+    }
+
+    /// Returns which tiles of the chunk can be seen from the sky before
+    /// anything has been explored: the tiles at and above the surface, and
+    /// those exposed along the sides of cliffs.
+    fn surface_visibility(&self, pos: ChunkPos, column: &Column) -> Visibility {
+        let size = CHUNK_SIZE as i32;
+        let (origin_x, chunk_y, origin_z) = (pos.x * size, pos.y * size, pos.z * size);
+        let height = |x: i32, z: i32| {
+            if x >= 0 && x < size && z >= 0 && z < size {
+                column.heights[z as usize][x as usize]
+            } else {
+                self.height_at(origin_x + x, origin_z + z)
+            }
+        };
+
+        // The lowest surface of each tile of the column and the tiles beside
+        // it, below which nothing of the column can be seen.
+        let exposed_from = array_16x16(|x, z| {
+            let (x, z) = (x as i32, z as i32);
+            [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)].iter()
+                .map(|&(dx, dz)| height(x + dx, z + dz))
+                .min()
+                .expect("offsets are not empty")
+        });
+
+        Visibility::from_fn(|x, y, z| chunk_y + y as i32 >= exposed_from[z][x])
+    }
+
+    /// Fills the chunk with the layers of terrain of its column, and with
+    /// water up to sea level.
+    fn generate_terrain(&self, pos: ChunkPos, column: &Column) -> Tiles {
+        let chunk_y = pos.y * CHUNK_SIZE as i32;
+
+        par_array_16x16x16(|x, y, z| {
+            let tile_y = chunk_y + y as i32;
+            let material = self.materials.get_from_elevation(tile_y, column.heights[z][x], column.biomes[z][x], self.config.sea_level);
+            if material == self.materials.water {
+                Tile::with_fluid_level(material, MAX_FLUID_LEVEL)
+            } else {
+                Tile::new(material)
+            }
+        })
+    }
+
+    /// Scatters veins of every ore material through the stone of the chunk.
+    ///
+    /// Each vein is placed by a random walk starting at a random position
+    /// within the chunk which seeds it, replacing stone at the depths at which
+    /// the ore is found. Since veins may wander across chunk borders, the
+    /// veins seeded by every chunk within reach are walked, and only the parts
+    /// of them which lie within this chunk are placed.
+    fn place_ores(&self, pos: ChunkPos, column: &Column, tiles: &mut Tiles) {
+        let size = CHUNK_SIZE as i32;
+        let origin = Point3::new(pos.x * size, pos.y * size, pos.z * size);
+        let max_vein_size = self.ores.iter().map(|&(_, ref ore)| ore.vein_size).max().unwrap_or(0) as i32;
+        let reach = (max_vein_size + size - 1) / size;
+
+        for dy in -reach..reach + 1 {
+            for dz in -reach..reach + 1 {
+                for dx in -reach..reach + 1 {
+                    let source = Point3::new(pos.x + dx, pos.y + dy, pos.z + dz);
+                    let mut rng = GameRng::from_position(self.seed.wrapping_add(ORE_SEED_OFFSET), source);
+
+                    for &(material, ref ore) in &self.ores {
+                        let mut veins = ore.veins_per_chunk.floor() as u32;
+                        if rng.gen::<f64>() < ore.veins_per_chunk.fract() {
+                            veins += 1;
+                        }
+
+                        for _ in 0..veins {
+                            // The position is relative to the origin of this
+                            // chunk, rather than that of the source chunk.
+                            let mut p = [
+                                dx * size + rng.gen_range(0, size),
+                                dy * size + rng.gen_range(0, size),
+                                dz * size + rng.gen_range(0, size),
+                            ];
+
+                            for _ in 0..ore.vein_size {
+                                let inside = p.iter().all(|&c| c >= 0 && c < size);
+                                if inside {
+                                    let (x, y, z) = (p[0] as usize, p[1] as usize, p[2] as usize);
+                                    let depth = column.heights[z][x] - (origin.y + y as i32);
+                                    if depth >= ore.min_depth && depth <= ore.max_depth &&
+                                        tiles[y][z][x].material == self.materials.wall {
+                                        tiles[y][z][x].material = material;
+                                    }
+                                }
+
+                                let axis = rng.gen_range(0, 3);
+                                p[axis] += if rng.gen() { 1 } else { -1 };
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Plants trees and shrubs on the surface of the columns of the chunk.
+    ///
+    /// Whether a column has any vegetation depends only on its position, so
+    /// the columns surrounding the chunk are considered as well, in order to
+    /// draw the parts of the canopies of neighbouring trees which overhang
+    /// the chunk.
+    fn plant_vegetation(&self, pos: ChunkPos, column: &Column, tiles: &mut Tiles) {
+        let origin = Point3::new(
+            pos.x * CHUNK_SIZE as i32,
+            pos.y * CHUNK_SIZE as i32,
+            pos.z * CHUNK_SIZE as i32);
+        let size = CHUNK_SIZE as i32;
+
+        for z in -TREE_CANOPY_RADIUS..size + TREE_CANOPY_RADIUS {
+            for x in -TREE_CANOPY_RADIUS..size + TREE_CANOPY_RADIUS {
+                let inside = x >= 0 && x < size && z >= 0 && z < size;
+                let (abs_x, abs_z) = (origin.x + x, origin.z + z);
+
+                let biome = if inside {
+                    column.biomes[z as usize][x as usize]
+                } else {
+                    self.biome_at(abs_x, abs_z)
+                };
+                let vegetation = match self.vegetation_at(abs_x, abs_z, biome) {
+                    Some(vegetation) => vegetation,
+                    None => continue,
+                };
+
+                let height = if inside {
+                    column.heights[z as usize][x as usize]
+                } else {
+                    self.height_at(abs_x, abs_z)
+                };
+                // Nothing grows underwater.
+                if height <= self.config.sea_level {
+                    continue;
+                }
+
+                let base = Point3::new(abs_x, height + 1, abs_z);
+                match vegetation {
+                    Vegetation::Tree(trunk_height) => self.plant_tree(origin, tiles, base, trunk_height),
+                    Vegetation::Shrub => self.plant(origin, tiles, base, self.materials.shrub),
+                }
+            }
+        }
+    }
+
+    /// Returns the vegetation growing on the column at the specified absolute
+    /// position along the X and Z axes, within `biome`.
+    fn vegetation_at(&self, x: i32, z: i32, biome: Biome) -> Option<Vegetation> {
+        let mut rng = GameRng::from_position(self.seed.wrapping_add(VEGETATION_SEED_OFFSET), Point3::new(x, 0, z));
+        let roll = rng.gen::<f64>();
+
+        match () {
+            _ if roll < biome.tree_density() => {
+                Some(Vegetation::Tree(rng.gen_range(TREE_MIN_TRUNK_HEIGHT, TREE_MAX_TRUNK_HEIGHT + 1)))
+            },
+            _ if roll < biome.tree_density() + biome.shrub_density() => Some(Vegetation::Shrub),
+            _ => None,
+        }
+    }
+
+    /// Plants a tree whose trunk starts at the absolute position `base`,
+    /// placing only the parts of it which lie within the chunk.
+    fn plant_tree(&self, origin: Point3<i32>, tiles: &mut Tiles, base: Point3<i32>, trunk_height: i32) {
+        let top = base.y + trunk_height - 1;
+
+        // The canopy is a rounded blob of leaves around the top of the trunk,
+        // narrowing above it.
+        for y in top - 1..top + 2 {
+            let radius = if y > top { TREE_CANOPY_RADIUS - 1 } else { TREE_CANOPY_RADIUS };
+            for dz in -radius..radius + 1 {
+                for dx in -radius..radius + 1 {
+                    if dx * dx + dz * dz <= radius * radius + 1 {
+                        self.plant(origin, tiles, Point3::new(base.x + dx, y, base.z + dz), self.materials.leaves);
+                    }
+                }
+            }
+        }
+
+        for y in base.y..top + 1 {
+            self.plant(origin, tiles, Point3::new(base.x, y, base.z), self.materials.wood);
+        }
+    }
+
+    /// Places `material` at the absolute position `pos`, if it lies within
+    /// the chunk and is empty. Trunks may replace leaves, so that they are
+    /// visible through the canopy.
+    fn plant(&self, origin: Point3<i32>, tiles: &mut Tiles, pos: Point3<i32>, material: MaterialId) {
+        let size = CHUNK_SIZE as i32;
+        let (x, y, z) = (pos.x - origin.x, pos.y - origin.y, pos.z - origin.z);
+        if x < 0 || x >= size || y < 0 || y >= size || z < 0 || z >= size {
+            return;
+        }
+
+        let tile = &mut tiles[y as usize][z as usize][x as usize];
+        let replaceable = tile.material == self.materials.air ||
+            (material == self.materials.wood && tile.material == self.materials.leaves);
+        if replaceable {
+            tile.material = material;
+        }
+    }
+
+    /// Replaces the stone beneath the surface of the chunk with air wherever
+    /// two 3D noise fields are both close to zero. The intersection of the two
+    /// near-zero surfaces forms winding tunnels, rather than isolated pockets.
+    fn carve_caves(&self, pos: ChunkPos, column: &Column, tiles: &mut Tiles) {
+        let origin = Point3::new(
+            pos.x * CHUNK_SIZE as i32,
+            pos.y * CHUNK_SIZE as i32,
+            pos.z * CHUNK_SIZE as i32);
+        let radius_squared = self.config.cave_density * self.config.cave_density;
+
+        tiles.par_iter_mut().enumerate().for_each(|(y, plane)| {
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    // The surface itself is left intact, so that caves only
+                    // open up where the terrain is dug into.
+                    let tile_y = origin.y + y as i32;
+                    if tile_y >= column.heights[z][x] || plane[z][x].material != self.materials.wall {
+                        continue;
+                    }
+
+                    let point = [
+                        (origin.x + x as i32) as f64 * CAVE_SCALING_FACTOR,
+                        tile_y as f64 * CAVE_SCALING_FACTOR,
+                        (origin.z + z as i32) as f64 * CAVE_SCALING_FACTOR,
+                    ];
+                    let a = open_simplex3(&self.cave_seeds.0, &point);
+                    let b = open_simplex3(&self.cave_seeds.1, &point);
+                    if a * a + b * b < radius_squared {
+                        plane[z][x].material = self.materials.air;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Returns the elevation of the surface at the specified absolute
+    /// position along the X and Z axes.
+    ///
+    /// The height curves of the biomes surrounding the position are averaged,
+    /// so that the terrain does not form cliffs along biome borders.
+    pub fn height_at(&self, x: i32, z: i32) -> i32 {
+        let value = clamp(self.fractal_noise(x as f64, z as f64) * 0.5 + 0.5, 0.0, 1.0);
+
+        let mut total = 0.0;
+        let mut samples = 0;
+        for dz in -1..2 {
+            for dx in -1..2 {
+                let biome = self.biome_at(x + dx * BIOME_BLEND_DISTANCE, z + dz * BIOME_BLEND_DISTANCE);
+                total += biome.height(value, self.config.amplitude);
+                samples += 1;
+            }
+        }
+
+        self.config.sea_level + (total / samples as f64) as i32
+    }
+
+    /// Sums several octaves of noise at the specified position (fractal
+    /// Brownian motion), returning a value in the range -1.0 to 1.0.
+    fn fractal_noise(&self, x: f64, z: f64) -> f64 {
+        let mut frequency = self.config.frequency;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+        let mut max_total = 0.0;
+
+        for octave in 0..self.config.octaves {
+            let offset = octave as f64 * OCTAVE_OFFSET;
+            let point = [x * frequency + offset, z * frequency + offset];
+            total += open_simplex2(&self.height_seed, &point) * amplitude;
+            max_total += amplitude;
+            amplitude *= self.config.persistence;
+            frequency *= self.config.lacunarity;
+        }
+
+        if max_total > 0.0 {
+            total / max_total
+        } else {
+            0.0
+        }
+    }
 }
 
-/// Generates a 2D height map at the specified location.
-pub fn generate_height_map<T, F>(seed: &Seed, pos: &Point3<i32>, rng: F) -> [[T; CHUNK_SIZE]; CHUNK_SIZE]
-    where T: Float + NumCast,
-          F: GenFn2<T>,
+// The arrays are filled with the first element before the rest are computed,
+// so that no element is ever left uninitialized.
+fn array_16x16<T, F>(mut f: F) -> ChunkArray2d<T>
+    where T: Copy,
+          F: FnMut(usize, usize) -> T
 {
-    let get_random_height = |x, z| {
-        let loc = [
-            cast(x as i32 + pos.x * CHUNK_SIZE as i32),
-            cast(z as i32 + pos.z * CHUNK_SIZE as i32),
-        ];
-        let value: f64 = cast(rng(seed, &loc));
-        // TODO: refactor these values to be configurable.
-        cast(clamp(value * 0.5 + 0.5, 0.0, 1.0))
-    };
-    array_16x16(get_random_height)
-}
-
-fn array_16x16<T, F>(mut f: F) -> [[T; CHUNK_SIZE]; CHUNK_SIZE]
-    where F: FnMut(usize, usize) -> T
+    let mut array = [[f(0, 0); CHUNK_SIZE]; CHUNK_SIZE];
+    for (z, row) in array.iter_mut().enumerate() {
+        for (x, element) in row.iter_mut().enumerate() {
+            *element = f(x, z);
+        }
+    }
+    array
+}
+
+/// Like `array_16x16`, but computes the rows of the array in parallel.
+fn par_array_16x16<T, F>(f: F) -> ChunkArray2d<T>
+    where T: Copy + Send,
+          F: Fn(usize, usize) -> T + Sync
 {
-    Array::from_fn(|z| -> [T; CHUNK_SIZE] {
-        Array::from_fn(|x| f(x, z))
-    })
+    let mut array = [[f(0, 0); CHUNK_SIZE]; CHUNK_SIZE];
+    array.par_iter_mut().enumerate().for_each(|(z, row)| {
+        for (x, element) in row.iter_mut().enumerate() {
+            *element = f(x, z);
+        }
+    });
+    array
 }
 
-fn cast<T, R>(val: T) -> R
-    where R: NumCast,
-          T: NumCast,
+/// Like `par_array_16x16`, for arrays indexed by `[y][z][x]`, whose planes
+/// are computed in parallel.
+fn par_array_16x16x16<T, F>(f: F) -> ChunkArray3d<T>
+    where T: Copy + Send,
+          F: Fn(usize, usize, usize) -> T + Sync
 {
-    num::cast(val).unwrap()
+    let mut array = [[[f(0, 0, 0); CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+    array.par_iter_mut().enumerate().for_each(|(y, plane)| {
+        for (z, row) in plane.iter_mut().enumerate() {
+            for (x, element) in row.iter_mut().enumerate() {
+                *element = f(x, y, z);
+            }
+        }
+    });
+    array
 }
 
-fn clamp<F: Float>(val: F, min: F, max: F) -> F {
+fn clamp(val: f64, min: f64, max: f64) -> f64 {
     match () {
         _ if val < min => min,
         _ if val > max => max,
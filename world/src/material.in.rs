@@ -0,0 +1,70 @@
+/// Identifies a material within a `MaterialRegistry`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct MaterialId(pub u16);
+
+/// Describes a material from which tiles can be made.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Material {
+    /// Unique identifier of the material. Identifiers are stored in saved
+    /// worlds, so they must not change once assigned.
+    pub id: u16,
+    /// Unique name by which the material is referred to.
+    pub name: String,
+    /// Color used to draw the material when it has no texture.
+    pub color: [f32; 4],
+    /// Name of the tile atlas region depicting the material, if any.
+    pub texture: Option<String>,
+    /// Character drawing the material in the terminal, in its color. Solid
+    /// materials without one are drawn as `#`, liquids as `~` and the others
+    /// as `.`.
+    #[serde(default)]
+    pub glyph: Option<char>,
+    /// How hard the material is to dig through, relative to soil.
+    pub hardness: f32,
+    /// Whether creatures can walk on top of the material.
+    pub walkable: bool,
+    /// Whether the material fills its tile, hiding the tiles beneath it.
+    pub solid: bool,
+    /// Whether the material flows between tiles, and is drawn translucently
+    /// over the tiles beneath it.
+    #[serde(default)]
+    pub liquid: bool,
+    /// The light level emitted by the material, from 0 (none) up to one less
+    /// than `MAX_LIGHT_LEVEL`.
+    #[serde(default)]
+    pub light: u8,
+    /// How creatures climb between z-levels through a tile of the material, if
+    /// they can.
+    #[serde(default)]
+    pub climb: Option<Climb>,
+    /// The item left behind when a tile of the material is dug out, if any.
+    #[serde(default)]
+    pub drops: Option<ItemKind>,
+    /// How the material is scattered through stone as ore veins, if at all.
+    #[serde(default)]
+    pub ore: Option<OreDistribution>,
+}
+
+/// How creatures climb between z-levels through a tile.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Climb {
+    /// Creatures climb straight up out of the tile, and straight down into it
+    /// from the tile above.
+    Stairs,
+    /// Creatures walk up out of the tile onto the tiles beside the tile
+    /// above, and down into it from them.
+    Ramp,
+}
+
+/// Describes where and how often veins of an ore are generated.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct OreDistribution {
+    /// Average number of veins generated in each chunk.
+    pub veins_per_chunk: f64,
+    /// Number of steps taken by the random walk which places each vein.
+    pub vein_size: u32,
+    /// Minimum depth below the surface at which the ore is found.
+    pub min_depth: i32,
+    /// Maximum depth below the surface at which the ore is found.
+    pub max_depth: i32,
+}
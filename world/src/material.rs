@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::u16;
+
+use components::ItemKind;
+
+#[cfg(feature = "nightly")]
+include!("material.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/material.rs"));
+
+/// Names of the materials placed by the world generator and by colonists,
+/// which every registry must define.
+pub const REQUIRED_MATERIALS: &'static [&'static str] = &[
+    "air", "grass", "leaves", "planks", "ramp", "sand", "shrub", "snow", "soil", "stairs", "wall", "water", "wood",
+];
+
+/// The material of every tile outside of the generated chunks. It is never
+/// defined by a registry.
+pub const OUT_OF_BOUNDS: MaterialId = MaterialId(u16::MAX);
+
+/// An error encountered while building a `MaterialRegistry`.
+#[derive(Debug)]
+pub enum MaterialError {
+    /// Two materials were defined with the same identifier.
+    DuplicateId(u16),
+    /// Two materials were defined with the same name.
+    DuplicateName(String),
+    /// A material required by the game was not defined.
+    MissingMaterial(String),
+    /// A material was defined with an identifier reserved by the game.
+    ReservedId(u16),
+}
+
+impl fmt::Display for MaterialError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MaterialError::DuplicateId(id) => write!(f, "duplicate material id: {}", id),
+            MaterialError::DuplicateName(ref name) => write!(f, "duplicate material name: {}", name),
+            MaterialError::MissingMaterial(ref name) => write!(f, "missing required material: {}", name),
+            MaterialError::ReservedId(id) => write!(f, "reserved material id: {}", id),
+        }
+    }
+}
+
+impl Error for MaterialError {
+    fn description(&self) -> &str {
+        match *self {
+            MaterialError::DuplicateId(_) => "duplicate material id",
+            MaterialError::DuplicateName(_) => "duplicate material name",
+            MaterialError::MissingMaterial(_) => "missing required material",
+            MaterialError::ReservedId(_) => "reserved material id",
+        }
+    }
+}
+
+/// The set of all materials known to the game, indexed by identifier.
+pub struct MaterialRegistry {
+    materials: Vec<Option<Material>>,
+    ids_by_name: HashMap<String, MaterialId>,
+}
+
+impl MaterialRegistry {
+    pub fn new(materials: Vec<Material>) -> Result<Self, MaterialError> {
+        let mut registry = MaterialRegistry {
+            materials: Vec::new(),
+            ids_by_name: HashMap::new(),
+        };
+
+        for material in materials {
+            let id = material.id;
+            if MaterialId(id) == OUT_OF_BOUNDS {
+                return Err(MaterialError::ReservedId(id));
+            }
+            if registry.get(MaterialId(id)).is_some() {
+                return Err(MaterialError::DuplicateId(id));
+            }
+            if registry.ids_by_name.contains_key(&material.name) {
+                return Err(MaterialError::DuplicateName(material.name));
+            }
+
+            while registry.materials.len() <= id as usize {
+                registry.materials.push(None);
+            }
+            registry.ids_by_name.insert(material.name.clone(), MaterialId(id));
+            registry.materials[id as usize] = Some(material);
+        }
+
+        for name in REQUIRED_MATERIALS {
+            try!(registry.require(name));
+        }
+
+        Ok(registry)
+    }
+
+    /// Returns the material with the specified identifier, if it is defined.
+    pub fn get(&self, id: MaterialId) -> Option<&Material> {
+        match self.materials.get(id.0 as usize) {
+            Some(&Some(ref material)) => Some(material),
+            _ => None,
+        }
+    }
+
+    /// Returns the identifier of the material with the specified name.
+    pub fn find(&self, name: &str) -> Option<MaterialId> {
+        self.ids_by_name.get(name).cloned()
+    }
+
+    /// Returns the identifier of the material with the specified name, or an
+    /// error if the game requires it but it is not defined.
+    pub fn require(&self, name: &str) -> Result<MaterialId, MaterialError> {
+        self.find(name).ok_or_else(|| MaterialError::MissingMaterial(name.to_owned()))
+    }
+
+    /// Returns true if the material fills its tile. Undefined materials are
+    /// never solid.
+    pub fn is_solid(&self, id: MaterialId) -> bool {
+        self.get(id).map_or(false, |material| material.solid)
+    }
+
+    /// Returns true if the material flows between tiles.
+    pub fn is_liquid(&self, id: MaterialId) -> bool {
+        self.get(id).map_or(false, |material| material.liquid)
+    }
+
+    /// Returns the light level emitted by the material.
+    pub fn light_emitted(&self, id: MaterialId) -> u8 {
+        self.get(id).map_or(0, |material| material.light)
+    }
+
+    /// Returns how creatures climb between z-levels through the material, if
+    /// they can.
+    pub fn climb(&self, id: MaterialId) -> Option<Climb> {
+        self.get(id).and_then(|material| material.climb)
+    }
+
+    /// Returns true if creatures can walk on top of the material.
+    pub fn is_walkable(&self, id: MaterialId) -> bool {
+        self.get(id).map_or(false, |material| material.walkable)
+    }
+
+    /// Returns the identifier and distribution of every ore material.
+    pub fn ores(&self) -> Vec<(MaterialId, OreDistribution)> {
+        self.iter()
+            .filter_map(|material| material.ore.map(|ore| (MaterialId(material.id), ore)))
+            .collect()
+    }
+
+    /// Returns an iterator over every defined material.
+    pub fn iter<'a>(&'a self) -> Box<Iterator<Item=&'a Material> + 'a> {
+        Box::new(self.materials.iter().filter_map(|material| material.as_ref()))
+    }
+}
+
+impl Default for MaterialRegistry {
+    /// Returns a registry containing the materials the game was built with,
+    /// for use when the material definitions cannot be loaded.
+    fn default() -> Self {
+        let material = |id: u16, name: &str, color: [f32; 4], hardness: f32, walkable: bool, solid: bool| Material {
+            id: id,
+            name: name.to_owned(),
+            color: color,
+            texture: None,
+            glyph: None,
+            hardness: hardness,
+            walkable: walkable,
+            solid: solid,
+            liquid: false,
+            light: 0,
+            climb: None,
+            drops: None,
+            ore: None,
+        };
+        let textured = |material: Material| Material {
+            texture: Some(material.name.clone()),
+            ..material
+        };
+        let glyph = |material: Material, glyph: char| Material {
+            glyph: Some(glyph),
+            ..material
+        };
+        let climb = |material: Material, climb: Climb| Material {
+            climb: Some(climb),
+            ..material
+        };
+        let drops = |material: Material, kind: ItemKind| Material {
+            drops: Some(kind),
+            ..material
+        };
+        let ore = |material: Material, veins_per_chunk: f64, vein_size: u32, min_depth: i32, max_depth: i32| Material {
+            ore: Some(OreDistribution {
+                veins_per_chunk: veins_per_chunk,
+                vein_size: vein_size,
+                min_depth: min_depth,
+                max_depth: max_depth,
+            }),
+            ..material
+        };
+
+        MaterialRegistry::new(vec![
+            material(0, "air", [0.0, 0.0, 0.0, 0.0], 0.0, false, false),
+            glyph(textured(material(1, "grass", [0.2, 0.6, 0.2, 1.0], 1.0, true, true)), ','),
+            glyph(textured(material(2, "sand", [0.86, 0.8, 0.55, 1.0], 0.5, true, true)), ':'),
+            glyph(textured(material(3, "soil", [0.45, 0.3, 0.15, 1.0], 1.0, true, true)), '.'),
+            glyph(drops(textured(material(4, "wall", [0.5, 0.5, 0.5, 1.0], 4.0, true, true)), ItemKind::Stone), '#'),
+            glyph(textured(Material {
+                liquid: true,
+                ..material(5, "water", [0.1, 0.3, 0.8, 0.6], 0.0, false, false)
+            }), '~'),
+            glyph(material(6, "snow", [0.93, 0.95, 0.98, 1.0], 0.5, true, true), '.'),
+            glyph(ore(drops(material(7, "coal", [0.15, 0.15, 0.15, 1.0], 4.0, true, true), ItemKind::Stone), 2.0, 12, 4, 48), '%'),
+            glyph(ore(drops(material(8, "iron", [0.65, 0.45, 0.35, 1.0], 5.0, true, true), ItemKind::Stone), 1.0, 8, 12, 64), '%'),
+            glyph(ore(drops(material(9, "gold", [0.9, 0.75, 0.2, 1.0], 5.0, true, true), ItemKind::Stone), 0.25, 6, 24, 96), '$'),
+            glyph(drops(material(10, "wood", [0.4, 0.26, 0.13, 1.0], 2.0, true, true), ItemKind::Wood), 'O'),
+            glyph(material(11, "leaves", [0.13, 0.4, 0.13, 1.0], 0.2, false, true), '"'),
+            glyph(drops(material(12, "shrub", [0.3, 0.5, 0.2, 1.0], 0.1, false, true), ItemKind::Food), ';'),
+            glyph(Material {
+                light: 14,
+                ..material(13, "torch", [1.0, 0.8, 0.3, 1.0], 0.1, false, false)
+            }, '!'),
+            glyph(material(14, "snow_cover", [0.95, 0.97, 1.0, 0.9], 0.0, false, false), '.'),
+            glyph(drops(material(15, "planks", [0.7, 0.52, 0.3, 1.0], 1.5, true, true), ItemKind::Wood), '='),
+            glyph(climb(material(16, "stairs", [0.55, 0.55, 0.6, 1.0], 2.0, false, false), Climb::Stairs), '>'),
+            glyph(climb(material(17, "ramp", [0.6, 0.6, 0.55, 1.0], 2.0, false, false), Climb::Ramp), '^'),
+            glyph(ore(drops(material(18, "copper", [0.35, 0.62, 0.52, 1.0], 4.5, true, true), ItemKind::Stone), 1.5, 10, 8, 56), '%'),
+        ]).expect("built-in materials are valid")
+    }
+}
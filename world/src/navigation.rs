@@ -0,0 +1,324 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::u16;
+use std::usize;
+
+use cgmath::{Point3, Vector3};
+
+use CHUNK_SIZE;
+use area::{Area, abs_pos_to_chunk_pos, abs_pos_to_rel_chunk_pos};
+use chunk::{ChunkPos, CHUNK_VOLUME, tile_index};
+use direction::Direction;
+use material::MaterialRegistry;
+use pathfinding;
+use pathfinding::{HORIZONTAL_DIRECTIONS, SearchTrace};
+
+/// The zone of the tiles which cannot be stood in.
+const NO_ZONE: u16 = u16::MAX;
+/// The most zones explored while searching for a route, so that searching for
+/// an unreachable tile does not stall the game.
+const MAX_EXPLORED_ZONES: usize = 2048;
+
+/// A zone of a single chunk, identified by the position of the chunk and the
+/// index of the zone within it.
+type ZoneId = (ChunkPos, u16);
+
+/// A set of tiles of a single chunk which can all be walked between without
+/// leaving the chunk.
+struct Zone {
+    /// The tiles of the zone which lie on the faces of the chunk, which are
+    /// the only tiles from which a creature can step into another chunk.
+    border: Vec<Point3<i32>>,
+}
+
+/// The zones of a single chunk.
+struct ChunkZones {
+    /// Revisions of the chunk and of the chunk below it at the time its zones
+    /// were found, since whether a tile can be stood in depends on the tile
+    /// beneath it.
+    revisions: (Option<u64>, Option<u64>),
+    /// The zone of every tile of the chunk, indexed like the tiles of the
+    /// chunk.
+    tiles: Box<[u16]>,
+    zones: Vec<Zone>,
+}
+
+/// A zone waiting to be explored, ordered so that the zone with the lowest
+/// estimated route cost is popped from a `BinaryHeap` first.
+#[derive(Eq, PartialEq)]
+struct Candidate {
+    estimated_cost: u32,
+    zone: ZoneId,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        other.estimated_cost.cmp(&self.estimated_cost)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Finds routes across large areas by searching in two steps: first for the
+/// zones a route passes through, then for the tiles of the route within those
+/// zones.
+///
+/// The zones of each chunk are found the first time a route passes near it,
+/// and are found again whenever the chunk (or the chunk below it) is
+/// modified. The links between the zones of neighbouring chunks are found
+/// from the borders of the zones while searching, so a modified chunk never
+/// requires its neighbours to be revisited.
+pub struct NavGraph {
+    chunks: HashMap<ChunkPos, ChunkZones>,
+    /// The tiles explored by searches, while they are being traced.
+    trace: Option<SearchTrace>,
+}
+
+impl NavGraph {
+    pub fn new() -> Self {
+        NavGraph {
+            chunks: HashMap::new(),
+            trace: None,
+        }
+    }
+
+    /// Starts or stops recording the tiles explored by searches. Any tiles
+    /// recorded so far are forgotten.
+    pub fn set_tracing(&mut self, enabled: bool) {
+        self.trace = if enabled { Some(SearchTrace::default()) } else { None };
+    }
+
+    /// Returns the tiles explored by searches since tracing was started, if
+    /// it has been.
+    pub fn trace(&self) -> Option<&SearchTrace> {
+        self.trace.as_ref()
+    }
+
+    /// Finds a route from `start` to `target`, like `pathfinding::find_path`.
+    ///
+    /// Falls back to searching tile by tile if `start` cannot be stood in,
+    /// since it then belongs to no zone.
+    pub fn find_path(&mut self, area: &Area, materials: &MaterialRegistry, start: Point3<i32>, target: Point3<i32>, adjacent: bool) -> Option<Vec<Point3<i32>>> {
+        let start_zone = match self.zone_at(area, materials, start) {
+            Some(zone) => zone,
+            None => return pathfinding::find_path(area, materials, start, target, adjacent),
+        };
+
+        let goals: HashSet<ZoneId> = if adjacent {
+            HORIZONTAL_DIRECTIONS.iter()
+                .filter_map(|direction| self.zone_at(area, materials, target + direction.to_vector()))
+                .collect()
+        } else {
+            self.zone_at(area, materials, target).into_iter().collect()
+        };
+        if goals.is_empty() {
+            return None;
+        }
+
+        let corridor = match self.find_corridor(area, materials, start_zone, &goals, abs_pos_to_chunk_pos(&target)) {
+            Some(corridor) => corridor,
+            None => return None,
+        };
+
+        // The corridor is connected, so the search can only explore its tiles
+        // and needs no further limit.
+        let chunks = &self.chunks;
+        pathfinding::find_path_within(area, materials, start, target, adjacent, usize::MAX, self.trace.as_mut(), |pos| {
+            cached_zone_at(chunks, pos).map_or(false, |zone| corridor.contains(&zone))
+        })
+    }
+
+    /// Forgets the zones of the chunks which are no longer loaded.
+    pub fn forget_unloaded(&mut self, area: &Area) {
+        let unloaded: Vec<ChunkPos> = self.chunks.keys()
+            .filter(|pos| area.get_chunk(**pos).is_none())
+            .cloned()
+            .collect();
+        for pos in unloaded {
+            self.chunks.remove(&pos);
+        }
+    }
+
+    /// Finds the zones along the route from the zone `start` to any of the
+    /// zones `goals`, using the A* algorithm.
+    fn find_corridor(&mut self, area: &Area, materials: &MaterialRegistry, start: ZoneId, goals: &HashSet<ZoneId>, target: ChunkPos) -> Option<HashSet<ZoneId>> {
+        // Every step between zones leads into a neighbouring chunk, and a step
+        // up or down a ramp also moves along a horizontal axis.
+        let heuristic = |zone: ZoneId| {
+            let pos = zone.0;
+            let distance = ((pos.x - target.x).abs() + (pos.z - target.z).abs()) as u32;
+            distance.max((pos.y - target.y).abs() as u32)
+        };
+
+        let mut open = BinaryHeap::new();
+        let mut costs = HashMap::new();
+        let mut came_from = HashMap::new();
+        open.push(Candidate {
+            estimated_cost: heuristic(start),
+            zone: start,
+        });
+        costs.insert(start, 0);
+
+        while let Some(Candidate { zone, .. }) = open.pop() {
+            if goals.contains(&zone) {
+                let mut corridor = HashSet::new();
+                let mut current = zone;
+                while current != start {
+                    corridor.insert(current);
+                    current = came_from[&current];
+                }
+                corridor.insert(start);
+                return Some(corridor);
+            }
+            if costs.len() > MAX_EXPLORED_ZONES {
+                return None;
+            }
+
+            let cost = costs[&zone] + 1;
+            for next in self.links(area, materials, zone) {
+                let is_shorter = costs.get(&next).map_or(true, |&previous| cost < previous);
+                if is_shorter {
+                    costs.insert(next, cost);
+                    came_from.insert(next, zone);
+                    open.push(Candidate {
+                        estimated_cost: cost + heuristic(next),
+                        zone: next,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the zones of neighbouring chunks into which a creature can step
+    /// from `zone`.
+    fn links(&mut self, area: &Area, materials: &MaterialRegistry, zone: ZoneId) -> Vec<ZoneId> {
+        let border = match self.chunk_zones(area, materials, zone.0).zones.get(zone.1 as usize) {
+            Some(zone) => zone.border.clone(),
+            None => return Vec::new(),
+        };
+
+        let mut links = Vec::new();
+        for pos in border {
+            for next in pathfinding::neighbours(area, materials, pos) {
+                if abs_pos_to_chunk_pos(&next) == zone.0 {
+                    continue;
+                }
+                if let Some(next_zone) = self.zone_at(area, materials, next) {
+                    if !links.contains(&next_zone) {
+                        links.push(next_zone);
+                    }
+                }
+            }
+        }
+        links
+    }
+
+    /// Returns the zone containing the tile at `pos`, if it can be stood in.
+    fn zone_at(&mut self, area: &Area, materials: &MaterialRegistry, pos: Point3<i32>) -> Option<ZoneId> {
+        let chunk_pos = abs_pos_to_chunk_pos(&pos);
+        self.chunk_zones(area, materials, chunk_pos);
+        cached_zone_at(&self.chunks, pos)
+    }
+
+    /// Returns the zones of the chunk at `chunk_pos`, finding them again if
+    /// the chunk has been modified since they were last found.
+    fn chunk_zones(&mut self, area: &Area, materials: &MaterialRegistry, chunk_pos: ChunkPos) -> &ChunkZones {
+        let revisions = (
+            area.get_chunk(chunk_pos).map(|chunk| chunk.revision()),
+            area.get_chunk(chunk_pos + Direction::Down.to_vector()).map(|chunk| chunk.revision()),
+        );
+        let is_stale = match self.chunks.get(&chunk_pos) {
+            Some(zones) => zones.revisions != revisions,
+            None => true,
+        };
+
+        if is_stale {
+            let zones = find_zones(area, materials, chunk_pos, revisions);
+            self.chunks.insert(chunk_pos, zones);
+        }
+
+        &self.chunks[&chunk_pos]
+    }
+}
+
+/// Returns the zone containing the tile at `pos`, if it can be stood in and
+/// the zones of its chunk have been found.
+fn cached_zone_at(chunks: &HashMap<ChunkPos, ChunkZones>, pos: Point3<i32>) -> Option<ZoneId> {
+    let chunk_pos = abs_pos_to_chunk_pos(&pos);
+    let rel_pos = abs_pos_to_rel_chunk_pos(&pos);
+    let zone = match chunks.get(&chunk_pos) {
+        Some(zones) => zones.tiles[tile_index(rel_pos.x, rel_pos.y, rel_pos.z)],
+        None => return None,
+    };
+
+    if zone == NO_ZONE { None } else { Some((chunk_pos, zone)) }
+}
+
+/// Splits the tiles of the chunk at `chunk_pos` which can be stood in into
+/// zones, by flooding outwards from each tile not yet part of a zone.
+fn find_zones(area: &Area, materials: &MaterialRegistry, chunk_pos: ChunkPos, revisions: (Option<u64>, Option<u64>)) -> ChunkZones {
+    let size = CHUNK_SIZE as i32;
+    let origin = Point3::new(chunk_pos.x * size, chunk_pos.y * size, chunk_pos.z * size);
+    let mut tiles = vec![NO_ZONE; CHUNK_VOLUME].into_boxed_slice();
+    let mut zones = Vec::new();
+    if revisions.0.is_none() {
+        return ChunkZones {
+            revisions: revisions,
+            tiles: tiles,
+            zones: zones,
+        };
+    }
+
+    let index_of = |pos: Point3<i32>| {
+        let rel = pos - origin;
+        let is_inside = rel.x >= 0 && rel.x < size && rel.y >= 0 && rel.y < size && rel.z >= 0 && rel.z < size;
+        if is_inside { Some(tile_index(rel.x as usize, rel.y as usize, rel.z as usize)) } else { None }
+    };
+    let is_on_face = |pos: Point3<i32>| {
+        let rel = pos - origin;
+        rel.x == 0 || rel.x == size - 1 || rel.y == 0 || rel.y == size - 1 || rel.z == 0 || rel.z == size - 1
+    };
+
+    for y in 0..size {
+        for z in 0..size {
+            for x in 0..size {
+                let pos = origin + Vector3::new(x, y, z);
+                let index = tile_index(x as usize, y as usize, z as usize);
+                if tiles[index] != NO_ZONE || !pathfinding::is_standable(area, materials, &pos) {
+                    continue;
+                }
+
+                let zone = zones.len() as u16;
+                let mut border = Vec::new();
+                let mut open = vec![pos];
+                tiles[index] = zone;
+                while let Some(pos) = open.pop() {
+                    if is_on_face(pos) {
+                        border.push(pos);
+                    }
+                    for next in pathfinding::neighbours(area, materials, pos) {
+                        if let Some(index) = index_of(next) {
+                            if tiles[index] == NO_ZONE {
+                                tiles[index] = zone;
+                                open.push(next);
+                            }
+                        }
+                    }
+                }
+                zones.push(Zone { border: border });
+            }
+        }
+    }
+
+    ChunkZones {
+        revisions: revisions,
+        tiles: tiles,
+        zones: zones,
+    }
+}
@@ -0,0 +1,84 @@
+use cgmath::Point2;
+use rayon::prelude::*;
+
+use CHUNK_SIZE;
+use biome::Biome;
+use mapgen::WorldGenerator;
+
+/// The number of cells along each side of the overworld.
+pub const OVERWORLD_SIZE: i32 = 256;
+/// The number of chunk columns along each side of a cell of the overworld.
+pub const CELL_CHUNKS: i32 = 4;
+
+/// What the terrain of a cell of the overworld is like, as sampled at its
+/// center.
+#[derive(Clone, Copy, Debug)]
+pub struct OverworldCell {
+    pub biome: Biome,
+    /// The elevation of the surface.
+    pub elevation: i32,
+}
+
+/// A coarse map of the world, of `OVERWORLD_SIZE` cells along each side,
+/// from which the site of a colony is picked before the chunks around it are
+/// generated.
+///
+/// Each cell covers `CELL_CHUNKS` chunk columns along each side, and is
+/// centered on the chunk column returned by `Overworld::cell_center`, so
+/// that the origin of the world lies at the center of the middle cell. Cells
+/// are indexed along the X and Z axes, from the corner with the lowest
+/// coordinates.
+pub struct Overworld {
+    /// The cells, indexed by `[z][x]`.
+    cells: Vec<Vec<OverworldCell>>,
+}
+
+impl Overworld {
+    /// Samples the biome and elevation of every cell from `generator`, a row
+    /// of cells at a time in parallel.
+    pub fn generate(generator: &WorldGenerator) -> Self {
+        let sample = |cell: Point2<i32>| {
+            let column = Overworld::cell_center(cell);
+            let x = column.x * CHUNK_SIZE as i32;
+            let z = column.y * CHUNK_SIZE as i32;
+            OverworldCell {
+                biome: generator.biome_at(x, z),
+                elevation: generator.height_at(x, z),
+            }
+        };
+
+        let first = sample(Point2::new(0, 0));
+        let mut cells = vec![vec![first; OVERWORLD_SIZE as usize]; OVERWORLD_SIZE as usize];
+        cells.par_iter_mut().enumerate().for_each(|(z, row)| {
+            for (x, cell) in row.iter_mut().enumerate() {
+                *cell = sample(Point2::new(x as i32, z as i32));
+            }
+        });
+
+        Overworld {
+            cells: cells,
+        }
+    }
+
+    /// Returns the cell at `cell`, or `None` if it lies outside of the
+    /// overworld.
+    pub fn get(&self, cell: Point2<i32>) -> Option<OverworldCell> {
+        if !Overworld::contains(cell) {
+            return None;
+        }
+        Some(self.cells[cell.y as usize][cell.x as usize])
+    }
+
+    /// Returns whether `cell` lies within the overworld.
+    pub fn contains(cell: Point2<i32>) -> bool {
+        cell.x >= 0 && cell.x < OVERWORLD_SIZE && cell.y >= 0 && cell.y < OVERWORLD_SIZE
+    }
+
+    /// Returns the chunk column at the center of `cell`.
+    pub fn cell_center(cell: Point2<i32>) -> Point2<i32> {
+        Point2::new(
+            (cell.x - OVERWORLD_SIZE / 2) * CELL_CHUNKS,
+            (cell.y - OVERWORLD_SIZE / 2) * CELL_CHUNKS,
+        )
+    }
+}
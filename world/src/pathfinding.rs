@@ -0,0 +1,179 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use cgmath::Point3;
+use utility::profiler;
+
+use area::Area;
+use direction::Direction;
+use material::{Climb, MaterialRegistry};
+
+/// The most tiles explored while searching for a route, so that searching for
+/// an unreachable tile does not stall the game.
+const MAX_EXPLORED_TILES: usize = 4096;
+
+pub const HORIZONTAL_DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+
+/// A record of the tiles explored by searches for routes, for debugging.
+#[derive(Default)]
+pub struct SearchTrace {
+    /// The number of times each tile has been explored.
+    pub explored: HashMap<Point3<i32>, u32>,
+    /// The tiles still waiting to be explored when the last search ended.
+    pub frontier: Vec<Point3<i32>>,
+}
+
+/// A tile waiting to be explored, ordered so that the tile with the lowest
+/// estimated route cost is popped from a `BinaryHeap` first.
+#[derive(Eq, PartialEq)]
+struct Candidate {
+    estimated_cost: u32,
+    pos: Point3<i32>,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        other.estimated_cost.cmp(&self.estimated_cost)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Returns true if a creature can stand in the tile at `pos`: the tile must
+/// be open, and the tile beneath it must be walkable. Stairs hold up whoever
+/// stands in them or on top of them.
+pub fn is_standable(area: &Area, materials: &MaterialRegistry, pos: &Point3<i32>) -> bool {
+    let tile = area.get_tile(pos);
+    let below = area.get_tile(&(*pos + Direction::Down.to_vector()));
+    let is_open = materials.get(tile.material).map_or(false, |material| !material.solid && !material.liquid);
+    let is_stairs = |material| materials.climb(material) == Some(Climb::Stairs);
+
+    is_open && (materials.is_walkable(below.material) || is_stairs(tile.material) || is_stairs(below.material))
+}
+
+/// Returns the tiles which a creature standing at `pos` can move to in a
+/// single step: the standable tiles beside it, and the tiles above and below
+/// it which stairs or ramps lead to.
+pub fn neighbours(area: &Area, materials: &MaterialRegistry, pos: Point3<i32>) -> Vec<Point3<i32>> {
+    let climb = |pos: Point3<i32>| materials.climb(area.get_tile(&pos).material);
+    let up = Direction::Up.to_vector();
+    let down = Direction::Down.to_vector();
+    let mut neighbours = Vec::new();
+
+    for direction in &HORIZONTAL_DIRECTIONS {
+        let next = pos + direction.to_vector();
+        if is_standable(area, materials, &next) {
+            neighbours.push(next);
+        }
+        // Ramps lead up onto the tiles beside the tile above them, and down
+        // from those tiles.
+        if climb(pos) == Some(Climb::Ramp) && is_standable(area, materials, &(next + up)) {
+            neighbours.push(next + up);
+        }
+        if climb(next + down) == Some(Climb::Ramp) && is_standable(area, materials, &(next + down)) {
+            neighbours.push(next + down);
+        }
+    }
+
+    // Stairs lead straight up out of them, and down into them.
+    if climb(pos) == Some(Climb::Stairs) && is_standable(area, materials, &(pos + up)) {
+        neighbours.push(pos + up);
+    }
+    if climb(pos + down) == Some(Climb::Stairs) && is_standable(area, materials, &(pos + down)) {
+        neighbours.push(pos + down);
+    }
+
+    neighbours
+}
+
+/// Finds the shortest route from `start` to `target`, using the A* algorithm.
+/// Routes may lead between z-levels by way of stairs and ramps. If `adjacent`
+/// is true, the route ends beside `target` on the same z-level instead, which
+/// lets creatures reach tiles they cannot stand in (such as a wall being dug
+/// out).
+///
+/// Returns the tiles of the route after `start`, with the next tile last, or
+/// `None` if no route was found.
+pub fn find_path(area: &Area, materials: &MaterialRegistry, start: Point3<i32>, target: Point3<i32>, adjacent: bool) -> Option<Vec<Point3<i32>>> {
+    find_path_within(area, materials, start, target, adjacent, MAX_EXPLORED_TILES, None, |_| true)
+}
+
+/// Finds the shortest route from `start` to `target` like `find_path`, but
+/// only through the tiles for which `is_allowed` returns true, giving up once
+/// more than `max_explored` tiles have been explored. The explored tiles are
+/// recorded in `trace`, if provided.
+pub fn find_path_within<F>(area: &Area, materials: &MaterialRegistry, start: Point3<i32>, target: Point3<i32>, adjacent: bool, max_explored: usize, mut trace: Option<&mut SearchTrace>, is_allowed: F) -> Option<Vec<Point3<i32>>>
+    where F: Fn(Point3<i32>) -> bool,
+{
+    let _scope = profiler::scope("pathfinding");
+    let is_goal = |pos: Point3<i32>| {
+        if adjacent {
+            pos.y == target.y && (pos.x - target.x).abs() + (pos.z - target.z).abs() == 1
+        } else {
+            pos == target
+        }
+    };
+    // A step up or down a ramp also moves along a horizontal axis, so the
+    // vertical distance may be covered alongside the horizontal distance.
+    let heuristic = |pos: Point3<i32>| {
+        let distance = ((pos.x - target.x).abs() + (pos.z - target.z).abs()) as u32;
+        let distance = if adjacent { distance.saturating_sub(1) } else { distance };
+        distance.max((pos.y - target.y).abs() as u32)
+    };
+
+    let mut open = BinaryHeap::new();
+    let mut costs = HashMap::new();
+    let mut came_from = HashMap::new();
+    open.push(Candidate {
+        estimated_cost: heuristic(start),
+        pos: start,
+    });
+    costs.insert(start, 0);
+
+    let mut path = None;
+    while let Some(Candidate { pos, .. }) = open.pop() {
+        if let Some(ref mut trace) = trace {
+            *trace.explored.entry(pos).or_insert(0) += 1;
+        }
+        if is_goal(pos) {
+            let mut route = Vec::new();
+            let mut current = pos;
+            while current != start {
+                route.push(current);
+                current = came_from[&current];
+            }
+            path = Some(route);
+            break;
+        }
+        if costs.len() > max_explored {
+            break;
+        }
+
+        let cost = costs[&pos] + 1;
+        for next in neighbours(area, materials, pos) {
+            let is_shorter = costs.get(&next).map_or(true, |&previous| cost < previous);
+            if is_shorter && is_allowed(next) {
+                costs.insert(next, cost);
+                came_from.insert(next, pos);
+                open.push(Candidate {
+                    estimated_cost: cost + heuristic(next),
+                    pos: next,
+                });
+            }
+        }
+    }
+
+    if let Some(trace) = trace {
+        trace.frontier = open.into_iter().map(|candidate| candidate.pos).collect();
+    }
+    path
+}
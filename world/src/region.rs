@@ -0,0 +1,578 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::u16;
+
+use cgmath::Point3;
+use utility::profiler;
+
+use CHUNK_SIZE;
+use chunk::{Chunk, ChunkPos, TileMetadata, Tiles, Visibility, CHUNK_MASK_LENGTH, CHUNK_VOLUME};
+use material::MaterialId;
+use terrain::Tile;
+
+/// The number of chunks along each axis of a region. Must be a power of two.
+pub const REGION_SIZE: i32 = 8;
+const LOG2_OF_REGION_SIZE: u32 = 3;
+const REGION_VOLUME: usize = (REGION_SIZE * REGION_SIZE * REGION_SIZE) as usize;
+
+const MAGIC: &'static [u8; 4] = b"CLRG";
+const FORMAT_VERSION: u32 = 4;
+// Region files of this version store every tile in full, and are still read.
+const UNCOMPRESSED_FORMAT_VERSION: u32 = 1;
+// Region files of this version do not store which tiles have been revealed,
+// and are still read with every tile revealed.
+const UNREVEALED_FORMAT_VERSION: u32 = 2;
+// Region files of this version do not store the metadata of tiles, and are
+// still read with no tile having any.
+const UNANNOTATED_FORMAT_VERSION: u32 = 3;
+const HEADER_LENGTH: usize = 8 + REGION_VOLUME * 8;
+// Each tile is stored as its material (two bytes) followed by its fluid level
+// (one byte).
+const TILE_LENGTH: usize = 3;
+const UNCOMPRESSED_CHUNK_LENGTH: usize = CHUNK_VOLUME * TILE_LENGTH;
+// Each run of tiles is stored as its length followed by its palette index
+// (two bytes each).
+const RUN_LENGTH: usize = 4;
+// Which tiles have been revealed is stored as a tag, followed by one bit per
+// tile if only some of them have been.
+const VISIBILITY_HIDDEN: u8 = 0;
+const VISIBILITY_REVEALED: u8 = 1;
+const VISIBILITY_PARTIAL: u8 = 2;
+// The metadata of tiles is stored as the number of tiles which have any,
+// followed by the index and the metadata of each (two bytes each).
+const METADATA_ENTRY_LENGTH: usize = 4;
+
+const REGION_FILE_EXTENSION: &'static str = "region";
+
+// Distinguishes the temporary stores created by a single process.
+static TEMPORARY_STORE_COUNT: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Stores chunks on disk, grouped into region files of `REGION_SIZE` chunks
+/// along each axis.
+///
+/// Each region file begins with a header of four magic bytes, the format
+/// version, and an index of the offset and length of every chunk of the
+/// region stored in the file, followed by the chunk data. All integers are
+/// little-endian.
+///
+/// Each chunk is stored as the length of its palette, the tiles of its
+/// palette, which of its tiles have been revealed, the metadata of the tiles
+/// which have any, and the runs of consecutive tiles sharing a palette index,
+/// in the order in which the chunk stores them.
+pub struct RegionStore {
+    directory: PathBuf,
+    /// Whether the directory is removed when the store is dropped.
+    temporary: bool,
+}
+
+impl RegionStore {
+    /// Creates a store keeping its region files in `directory`, creating the
+    /// directory if needed.
+    pub fn new<P>(directory: P) -> io::Result<Self>
+        where P: AsRef<Path>,
+    {
+        try!(fs::create_dir_all(directory.as_ref()));
+        Ok(RegionStore {
+            directory: directory.as_ref().to_path_buf(),
+            temporary: false,
+        })
+    }
+
+    /// Creates a store in a new directory under the system's temporary
+    /// directory, which is removed along with its contents when the store is
+    /// dropped.
+    pub fn temporary() -> io::Result<Self> {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|duration| (duration.as_secs(), duration.subsec_nanos()))
+            .unwrap_or((0, 0));
+        let count = TEMPORARY_STORE_COUNT.fetch_add(1, Ordering::SeqCst);
+        let directory = env::temp_dir().join(format!("colonize-{}-{}-{}", time.0, time.1, count));
+
+        let mut store = try!(RegionStore::new(directory));
+        store.temporary = true;
+        Ok(store)
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Reads the chunk at `pos`, returning `None` if it was never saved.
+    pub fn load_chunk(&self, pos: ChunkPos) -> io::Result<Option<Chunk>> {
+        let (region, index) = region_pos(pos);
+        let chunks = try!(self.read_region(region));
+        match chunks.get(&index) {
+            Some(data) => decode_chunk(data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Writes the specified chunks, rewriting only the region files which
+    /// contain them.
+    pub fn save_chunks(&self, chunks: &[(ChunkPos, &Chunk)]) -> io::Result<()> {
+        let mut regions: HashMap<Point3<i32>, Vec<(usize, &Chunk)>> = HashMap::new();
+        for &(pos, chunk) in chunks {
+            let (region, index) = region_pos(pos);
+            regions.entry(region).or_insert_with(Vec::new).push((index, chunk));
+        }
+
+        for (region, chunks) in regions {
+            let mut stored = try!(self.read_region(region));
+            for (index, chunk) in chunks {
+                stored.insert(index, encode_chunk(chunk));
+            }
+            try!(self.write_region(region, &stored));
+        }
+
+        Ok(())
+    }
+
+    /// Copies every region file into the directory of `other`.
+    pub fn copy_to(&self, other: &RegionStore) -> io::Result<()> {
+        for entry in try!(fs::read_dir(&self.directory)) {
+            let path = try!(entry).path();
+            if path.extension().map_or(false, |extension| extension == REGION_FILE_EXTENSION) {
+                if let Some(name) = path.file_name() {
+                    try!(fs::copy(&path, other.directory.join(name)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes every chunk stored in this store into `other`, replacing the
+    /// chunks of `other` at the same positions and keeping the rest of its
+    /// chunks.
+    pub fn merge_into(&self, other: &RegionStore) -> io::Result<()> {
+        for entry in try!(fs::read_dir(&self.directory)) {
+            let path = try!(entry).path();
+            let region = match region_from_path(&path) {
+                Some(region) => region,
+                None => continue,
+            };
+
+            let mut merged = try!(other.read_region(region));
+            merged.extend(try!(self.read_region(region)));
+            try!(other.write_region(region, &merged));
+        }
+        Ok(())
+    }
+
+    /// Rewrites every region file of an older format in the current one,
+    /// returning the number of files rewritten. Older files are otherwise
+    /// only converted once a chunk of their region is saved.
+    pub fn upgrade(&self) -> io::Result<usize> {
+        let mut upgraded = 0;
+        for entry in try!(fs::read_dir(&self.directory)) {
+            let path = try!(entry).path();
+            let region = match region_from_path(&path) {
+                Some(region) => region,
+                None => continue,
+            };
+
+            let mut header = [0; 8];
+            try!(try!(File::open(&path)).read_exact(&mut header));
+            if &header[0..4] != MAGIC {
+                return Err(invalid_data("not a region file"));
+            }
+            if read_u32(&header[4..]) == FORMAT_VERSION {
+                continue;
+            }
+
+            let chunks = try!(self.read_region(region));
+            try!(self.write_region(region, &chunks));
+            upgraded += 1;
+        }
+        Ok(upgraded)
+    }
+
+    fn region_path(&self, region: Point3<i32>) -> PathBuf {
+        self.directory.join(format!("r.{}.{}.{}.{}", region.x, region.y, region.z, REGION_FILE_EXTENSION))
+    }
+
+    /// Reads the data of every chunk stored in a region file, by index within
+    /// the region.
+    fn read_region(&self, region: Point3<i32>) -> io::Result<HashMap<usize, Vec<u8>>> {
+        let mut chunks = HashMap::new();
+
+        let mut file = match File::open(self.region_path(region)) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(chunks),
+            Err(e) => return Err(e),
+        };
+        let mut data = Vec::new();
+        try!(file.read_to_end(&mut data));
+
+        if data.len() < HEADER_LENGTH || &data[0..4] != MAGIC {
+            return Err(invalid_data("not a region file"));
+        }
+        let version = read_u32(&data[4..]);
+        if version > FORMAT_VERSION || version < UNCOMPRESSED_FORMAT_VERSION {
+            return Err(invalid_data("unsupported region file version"));
+        }
+
+        for index in 0..REGION_VOLUME {
+            let entry = 8 + index * 8;
+            let offset = read_u32(&data[entry..]) as usize;
+            let length = read_u32(&data[entry + 4..]) as usize;
+            if length == 0 {
+                continue;
+            }
+            if offset + length > data.len() {
+                return Err(invalid_data("chunk data out of bounds"));
+            }
+
+            let chunk_data = &data[offset..offset + length];
+            // Chunks from older region files are converted as they are read,
+            // so that the region is written back in the current format.
+            let chunk_data = match version {
+                UNCOMPRESSED_FORMAT_VERSION => encode_chunk(&try!(decode_uncompressed_chunk(chunk_data))),
+                FORMAT_VERSION => chunk_data.to_vec(),
+                _ => encode_chunk(&try!(decode_chunk_of_version(chunk_data, version))),
+            };
+            chunks.insert(index, chunk_data);
+        }
+
+        Ok(chunks)
+    }
+
+    /// Writes a region file, replacing it atomically so that a region is
+    /// never read while partially written.
+    fn write_region(&self, region: Point3<i32>, chunks: &HashMap<usize, Vec<u8>>) -> io::Result<()> {
+        let mut header = Vec::with_capacity(HEADER_LENGTH);
+        header.extend_from_slice(MAGIC);
+        write_u32(&mut header, FORMAT_VERSION);
+
+        let mut body = Vec::new();
+        for index in 0..REGION_VOLUME {
+            match chunks.get(&index) {
+                Some(data) => {
+                    write_u32(&mut header, (HEADER_LENGTH + body.len()) as u32);
+                    write_u32(&mut header, data.len() as u32);
+                    body.extend_from_slice(data);
+                },
+                None => {
+                    write_u32(&mut header, 0);
+                    write_u32(&mut header, 0);
+                },
+            }
+        }
+
+        let path = self.region_path(region);
+        let temporary_path = path.with_extension("tmp");
+        {
+            let mut file = try!(File::create(&temporary_path));
+            try!(file.write_all(&header));
+            try!(file.write_all(&body));
+            try!(file.sync_all());
+        }
+        fs::rename(&temporary_path, &path)
+    }
+}
+
+impl Drop for RegionStore {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = fs::remove_dir_all(&self.directory);
+        }
+    }
+}
+
+/// Returns the position of the region containing the chunk at `pos`, and the
+/// index of the chunk within the region.
+fn region_pos(pos: ChunkPos) -> (Point3<i32>, usize) {
+    let region = Point3::new(
+        pos.x >> LOG2_OF_REGION_SIZE,
+        pos.y >> LOG2_OF_REGION_SIZE,
+        pos.z >> LOG2_OF_REGION_SIZE);
+    let mask = REGION_SIZE - 1;
+    let index = ((pos.y & mask) * REGION_SIZE + (pos.z & mask)) * REGION_SIZE + (pos.x & mask);
+    (region, index as usize)
+}
+
+/// Returns the position of the region stored in the region file at `path`,
+/// or `None` if it is not named as a region file.
+fn region_from_path(path: &Path) -> Option<Point3<i32>> {
+    if path.extension().map_or(true, |extension| extension != REGION_FILE_EXTENSION) {
+        return None;
+    }
+    let stem = match path.file_stem().and_then(|stem| stem.to_str()) {
+        Some(stem) => stem,
+        None => return None,
+    };
+    let parts: Vec<&str> = stem.split('.').collect();
+    if parts.len() != 4 || parts[0] != "r" {
+        return None;
+    }
+    match (parts[1].parse(), parts[2].parse(), parts[3].parse()) {
+        (Ok(x), Ok(y), Ok(z)) => Some(Point3::new(x, y, z)),
+        _ => None,
+    }
+}
+
+/// Encodes a chunk in the current format, in which its tiles are stored as
+/// runs of palette indices. Chunks are also kept in memory in this format by
+/// the history of the world.
+pub fn encode_chunk(chunk: &Chunk) -> Vec<u8> {
+    let _scope = profiler::scope("chunk encoding");
+    let palette = chunk.palette();
+    let mut data = Vec::new();
+
+    write_u16(&mut data, palette.len() as u16);
+    for tile in palette {
+        let MaterialId(material) = tile.material;
+        write_u16(&mut data, material);
+        data.push(tile.fluid_level);
+    }
+
+    match *chunk.visibility() {
+        Visibility::Hidden => data.push(VISIBILITY_HIDDEN),
+        Visibility::Revealed => data.push(VISIBILITY_REVEALED),
+        Visibility::Partial(ref mask) => {
+            data.push(VISIBILITY_PARTIAL);
+            data.extend_from_slice(mask);
+        },
+    }
+
+    write_u16(&mut data, chunk.iter_metadata().len() as u16);
+    for (&i, tile_metadata) in chunk.iter_metadata() {
+        write_u16(&mut data, i as u16);
+        write_u16(&mut data, tile_metadata.to_raw());
+    }
+
+    let mut run: Option<(usize, usize)> = None;
+    for y in 0..CHUNK_SIZE {
+        for z in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                let index = chunk.palette_index(&Point3::new(x, y, z));
+                run = match run {
+                    // Runs are limited to the largest length which fits in
+                    // two bytes.
+                    Some((length, run_index)) if run_index == index && length < u16::MAX as usize => Some((length + 1, index)),
+                    Some((length, run_index)) => {
+                        write_run(&mut data, length, run_index);
+                        Some((1, index))
+                    },
+                    None => Some((1, index)),
+                };
+            }
+        }
+    }
+    if let Some((length, index)) = run {
+        write_run(&mut data, length, index);
+    }
+
+    data
+}
+
+fn write_run(data: &mut Vec<u8>, length: usize, index: usize) {
+    write_u16(data, length as u16);
+    write_u16(data, index as u16);
+}
+
+/// Decodes a chunk encoded in the current format.
+pub fn decode_chunk(data: &[u8]) -> io::Result<Chunk> {
+    decode_chunk_of_version(data, FORMAT_VERSION)
+}
+
+/// Decodes a chunk encoded in the format of `version`, which has every tile
+/// revealed if the format does not store which tiles have been, and no
+/// metadata if it does not store metadata.
+fn decode_chunk_of_version(data: &[u8], version: u32) -> io::Result<Chunk> {
+    let _scope = profiler::scope("chunk decoding");
+    if data.len() < 2 {
+        return Err(invalid_data("chunk data is truncated"));
+    }
+    let palette_len = read_u16(data) as usize;
+    let palette_end = 2 + palette_len * TILE_LENGTH;
+    if data.len() < palette_end {
+        return Err(invalid_data("chunk data is truncated"));
+    }
+
+    let (visibility, visibility_end) = if version > UNREVEALED_FORMAT_VERSION {
+        match data.get(palette_end).cloned() {
+            Some(VISIBILITY_HIDDEN) => (Visibility::Hidden, palette_end + 1),
+            Some(VISIBILITY_REVEALED) => (Visibility::Revealed, palette_end + 1),
+            Some(VISIBILITY_PARTIAL) if data.len() >= palette_end + 1 + CHUNK_MASK_LENGTH => {
+                let mask = data[palette_end + 1..palette_end + 1 + CHUNK_MASK_LENGTH].to_vec().into_boxed_slice();
+                (Visibility::from_mask(mask), palette_end + 1 + CHUNK_MASK_LENGTH)
+            },
+            _ => return Err(invalid_data("chunk visibility is invalid")),
+        }
+    } else {
+        (Visibility::Revealed, palette_end)
+    };
+
+    let mut metadata = BTreeMap::new();
+    let runs_offset = if version > UNANNOTATED_FORMAT_VERSION {
+        if data.len() < visibility_end + 2 {
+            return Err(invalid_data("chunk data is truncated"));
+        }
+        let metadata_len = read_u16(&data[visibility_end..]) as usize;
+        let metadata_end = visibility_end + 2 + metadata_len * METADATA_ENTRY_LENGTH;
+        if data.len() < metadata_end {
+            return Err(invalid_data("chunk data is truncated"));
+        }
+        for entry in data[visibility_end + 2..metadata_end].chunks(METADATA_ENTRY_LENGTH) {
+            metadata.insert(read_u16(entry) as usize, TileMetadata::from_raw(read_u16(&entry[2..])));
+        }
+        metadata_end
+    } else {
+        visibility_end
+    };
+    if (data.len() - runs_offset) % RUN_LENGTH != 0 {
+        return Err(invalid_data("chunk data has the wrong length"));
+    }
+
+    let palette = data[2..palette_end].chunks(TILE_LENGTH)
+        .map(|tile| Tile::with_fluid_level(MaterialId(read_u16(tile)), tile[2]))
+        .collect();
+
+    let mut indices = Vec::with_capacity(CHUNK_VOLUME);
+    for run in data[runs_offset..].chunks(RUN_LENGTH) {
+        let length = read_u16(run) as usize;
+        if indices.len() + length > CHUNK_VOLUME {
+            return Err(invalid_data("chunk data has too many tiles"));
+        }
+        let index = read_u16(&run[2..]);
+        indices.extend((0..length).map(|_| index));
+    }
+
+    Chunk::from_palette(palette, &indices)
+        .and_then(|chunk| chunk.with_visibility(visibility).with_metadata(metadata))
+        .ok_or_else(|| invalid_data("chunk data is invalid"))
+}
+
+fn decode_uncompressed_chunk(data: &[u8]) -> io::Result<Chunk> {
+    if data.len() != UNCOMPRESSED_CHUNK_LENGTH {
+        return Err(invalid_data("chunk data has the wrong length"));
+    }
+
+    let mut tiles: Tiles = [[[Tile::new(MaterialId(0)); CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+    let mut offset = 0;
+    for plane in tiles.iter_mut() {
+        for row in plane.iter_mut() {
+            for tile in row.iter_mut() {
+                let material = data[offset] as u16 | (data[offset + 1] as u16) << 8;
+                *tile = Tile::with_fluid_level(MaterialId(material), data[offset + 2]);
+                offset += TILE_LENGTH;
+            }
+        }
+    }
+
+    Ok(Chunk::from_tiles(&tiles))
+}
+
+fn read_u16(data: &[u8]) -> u16 {
+    data[0] as u16 | (data[1] as u16) << 8
+}
+
+fn write_u16(data: &mut Vec<u8>, value: u16) {
+    data.push(value as u8);
+    data.push((value >> 8) as u8);
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    data[0] as u32 | (data[1] as u32) << 8 | (data[2] as u32) << 16 | (data[3] as u32) << 24
+}
+
+fn write_u32(data: &mut Vec<u8>, value: u32) {
+    data.push(value as u8);
+    data.push((value >> 8) as u8);
+    data.push((value >> 16) as u8);
+    data.push((value >> 24) as u8);
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use cgmath::Point3;
+
+    use CHUNK_SIZE;
+    use chunk::{Chunk, TileMetadata, Visibility, CHUNK_VOLUME};
+    use material::MaterialId;
+    use terrain::Tile;
+    use super::{decode_chunk, encode_chunk};
+
+    /// Returns a chunk of `palette_len` distinct tiles, one of them holding
+    /// fluid, used in turn.
+    fn chunk_with_palette_len(palette_len: usize) -> Chunk {
+        let palette = (0..palette_len)
+            .map(|i| Tile::with_fluid_level(MaterialId(i as u16), (i % 2) as u8))
+            .collect();
+        let indices: Vec<u16> = (0..CHUNK_VOLUME).map(|i| (i % palette_len) as u16).collect();
+        Chunk::from_palette(palette, &indices).unwrap()
+    }
+
+    fn assert_round_trips(chunk: &Chunk) {
+        let decoded = decode_chunk(&encode_chunk(chunk)).unwrap();
+        assert_eq!(decoded.palette(), chunk.palette());
+        for y in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let p = Point3::new(x, y, z);
+                    assert_eq!(decoded.get_tile(&p), chunk.get_tile(&p));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn uniform_chunk_round_trips() {
+        assert_round_trips(&chunk_with_palette_len(1));
+    }
+
+    #[test]
+    fn byte_indexed_chunk_round_trips() {
+        assert_round_trips(&chunk_with_palette_len(5));
+    }
+
+    #[test]
+    fn short_indexed_chunk_round_trips() {
+        assert_round_trips(&chunk_with_palette_len(300));
+    }
+
+    #[test]
+    fn visibility_and_metadata_round_trip() {
+        let mut metadata = BTreeMap::new();
+        metadata.insert(5, TileMetadata::from_raw(150));
+        metadata.insert(CHUNK_VOLUME - 1, TileMetadata::from_raw(3));
+        let mut chunk = chunk_with_palette_len(5)
+            .with_visibility(Visibility::from_fn(|x, y, z| x < y && z % 2 == 0))
+            .with_metadata(metadata)
+            .unwrap();
+        chunk.set_revision(42);
+
+        let decoded = decode_chunk(&encode_chunk(&chunk)).unwrap();
+        assert_round_trips(&chunk);
+        assert_eq!(decoded.iter_metadata().collect::<Vec<_>>(), chunk.iter_metadata().collect::<Vec<_>>());
+        for y in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let p = Point3::new(x, y, z);
+                    assert_eq!(decoded.is_revealed(&p), chunk.is_revealed(&p));
+                }
+            }
+        }
+        // The revision is not saved, since the area a chunk is loaded into
+        // gives it a revision of its own, and a loaded chunk is unmodified.
+        assert_eq!(decoded.revision(), 0);
+        assert!(!decoded.is_dirty());
+    }
+
+    #[test]
+    fn truncated_chunk_is_rejected() {
+        let data = encode_chunk(&chunk_with_palette_len(5));
+        assert!(decode_chunk(&data[..data.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,9 @@
+/// A deterministic random number generator whose state can be saved along
+/// with the game.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GameRng {
+    x: u32,
+    y: u32,
+    z: u32,
+    w: u32,
+}
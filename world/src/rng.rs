@@ -0,0 +1,53 @@
+use cgmath::Point3;
+use rand::Rng;
+
+#[cfg(feature = "nightly")]
+include!("rng.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/rng.rs"));
+
+impl GameRng {
+    /// Creates a generator whose sequence of random numbers is determined
+    /// entirely by `seed`.
+    pub fn new(seed: u32) -> Self {
+        GameRng::from_position(seed, Point3::new(0, 0, 0))
+    }
+
+    /// Creates a generator seeded from `seed` and a position (of a chunk or a
+    /// tile), so that every position has its own sequence of random numbers,
+    /// regardless of the order in which the positions are visited.
+    pub fn from_position(seed: u32, pos: Point3<i32>) -> Self {
+        // The coordinates are mixed so that neighbouring positions start from
+        // very different states, and so that the state is never entirely zero
+        // (from which xorshift never escapes).
+        GameRng {
+            x: mix(seed),
+            y: mix(seed ^ pos.x as u32),
+            z: mix(seed ^ pos.y as u32).rotate_left(11),
+            w: mix(seed ^ pos.z as u32).rotate_left(22) | 1,
+        }
+    }
+}
+
+impl Rng for GameRng {
+    /// Advances the xorshift128 state, as described by Marsaglia in "Xorshift
+    /// RNGs".
+    fn next_u32(&mut self) -> u32 {
+        let t = self.x ^ (self.x << 11);
+        self.x = self.y;
+        self.y = self.z;
+        self.z = self.w;
+        self.w = self.w ^ (self.w >> 19) ^ (t ^ (t >> 8));
+        self.w
+    }
+}
+
+/// Scrambles the bits of a 32-bit integer.
+fn mix(mut value: u32) -> u32 {
+    value ^= value >> 16;
+    value = value.wrapping_mul(0x85eb_ca6b);
+    value ^= value >> 13;
+    value = value.wrapping_mul(0xc2b2_ae35);
+    value ^ (value >> 16)
+}
@@ -0,0 +1,24 @@
+/// The metrics of the colony over a single day.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct DayStats {
+    /// The day, counted in whole days since the world was created.
+    pub day: u64,
+    /// The number of colonists alive at the end of the day.
+    pub population: u32,
+    /// The number of tiles dug out during the day.
+    pub items_mined: u32,
+    /// The number of food items in the world at the end of the day.
+    pub food_stock: u32,
+    /// The number of colonists who died during the day.
+    pub deaths: u32,
+}
+
+/// The metrics of the colony for every day since it was founded, which are
+/// saved along with the game.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Stats {
+    /// The metrics of the days which have ended, the earliest first.
+    days: Vec<DayStats>,
+    /// The metrics of the current day so far.
+    today: DayStats,
+}
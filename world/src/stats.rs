@@ -0,0 +1,64 @@
+use components::ItemKind;
+use event::WorldEvent;
+use world::WorldState;
+
+#[cfg(feature = "nightly")]
+include!("stats.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/stats.rs"));
+
+impl Stats {
+    /// Creates the statistics of a colony whose first day is `day`.
+    pub fn new(day: u64) -> Self {
+        Stats {
+            days: Vec::new(),
+            today: DayStats {
+                day: day,
+                ..DayStats::default()
+            },
+        }
+    }
+
+    /// Returns the metrics of the days which have ended, the earliest first.
+    pub fn days(&self) -> &[DayStats] {
+        &self.days
+    }
+
+    /// Returns the metrics of the current day so far. The population and the
+    /// food stock are only counted once the day ends.
+    pub fn today(&self) -> &DayStats {
+        &self.today
+    }
+}
+
+/// Counts what was reported in `events` during the last step of the
+/// simulation towards the current day, and records the day once it has
+/// ended. Days skipped over at once are not recorded.
+pub fn tick(state: &mut WorldState, events: &[WorldEvent]) {
+    let stats = &mut state.stats;
+    for event in events {
+        match *event {
+            WorldEvent::Dug(_) => stats.today.items_mined += 1,
+            WorldEvent::ColonistDied(_) => stats.today.deaths += 1,
+            _ => {},
+        }
+    }
+
+    let day = state.calendar.days();
+    if day == stats.today.day {
+        return;
+    }
+
+    let entities = &state.entities;
+    stats.today.population = entities.colonists.iter().count() as u32;
+    stats.today.food_stock = entities.items.iter()
+        .filter(|&(_, item)| item.kind == ItemKind::Food)
+        .count() as u32;
+    let ended = stats.today;
+    stats.days.push(ended);
+    stats.today = DayStats {
+        day: day,
+        ..DayStats::default()
+    };
+}
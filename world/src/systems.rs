@@ -0,0 +1,636 @@
+use std::collections::BTreeSet;
+
+use area::Area;
+use calendar::TICKS_PER_HOUR;
+use cgmath::Point3;
+
+use components::{Furniture, FurnitureKind, Item, ItemKind, Job, JobKind, Position, Renderable, Structure};
+use direction::Direction;
+use entity::{Entities, Entity, Storage};
+use event::WorldEvent;
+use fog;
+use material::MaterialRegistry;
+use navigation::NavGraph;
+use pathfinding;
+use terrain::Tile;
+use world::WorldState;
+
+/// The number of ticks needed to dig out a tile, per unit of hardness of its
+/// material.
+const DIG_TICKS_PER_HARDNESS: f32 = 5.0;
+/// The number of ticks needed to finish a job defined by a mod.
+const CUSTOM_JOB_TICKS: f32 = 20.0;
+/// How much of each need is used up every tick.
+const FOOD_PER_TICK: f32 = 1.0 / (TICKS_PER_HOUR * 36) as f32;
+const REST_PER_TICK: f32 = 1.0 / (TICKS_PER_HOUR * 24) as f32;
+/// How much rest a sleeping colonist regains every tick.
+const SLEEP_RECOVERY_PER_TICK: f32 = 1.0 / (TICKS_PER_HOUR * 6) as f32;
+/// The level below which a need interrupts the job of a colonist.
+const CRITICAL_NEED: f32 = 0.2;
+/// The most the mood of a colonist changes every tick.
+const MOOD_CHANGE_PER_TICK: f32 = 1.0 / (TICKS_PER_HOUR * 4) as f32;
+
+/// Moves every entity which is walking somewhere one tile along its route,
+/// finding a route first if it has none.
+pub fn walk(entities: &mut Entities, area: &Area, materials: &MaterialRegistry, navigation: &mut NavGraph) {
+    let positions = &mut entities.positions;
+
+    for (entity, pathing) in entities.pathing.iter_mut() {
+        let position = match positions.get_mut(entity) {
+            Some(position) => position,
+            None => continue,
+        };
+        let target = match pathing.target {
+            Some(target) => target,
+            None => continue,
+        };
+
+        if pathing.path.is_empty() {
+            match navigation.find_path(area, materials, position.to_point(), target.to_point(), pathing.adjacent) {
+                Some(path) => pathing.path = path.into_iter().map(Position::from).collect(),
+                None => {
+                    pathing.stop();
+                    pathing.unreachable = true;
+                    continue;
+                },
+            }
+        }
+
+        match pathing.path.pop() {
+            // The world may have changed since the route was found, in which
+            // case a new route is found during the next tick.
+            Some(next) => if pathfinding::is_standable(area, materials, &next.to_point()) {
+                *position = next;
+            } else {
+                pathing.path.clear();
+            },
+            None => pathing.stop(),
+        }
+    }
+}
+
+/// Lets the needs of every colonist decay, and interrupts the job of any
+/// colonist whose needs have become critical so that it eats or sleeps
+/// instead. Interrupted jobs are queued again.
+pub fn satisfy_needs(state: &mut WorldState) {
+    let entities = &mut state.entities;
+
+    // Food which a colonist is already on the way to eat.
+    let mut claimed_food: Vec<Entity> = entities.jobs.iter()
+        .filter_map(|(_, job)| match job.kind {
+            JobKind::Eat(food) => Some(food),
+            _ => None,
+        })
+        .collect();
+
+    let colonists: Vec<Entity> = entities.needs.iter().map(|(entity, _)| entity).collect();
+    for entity in colonists {
+        let position = match entities.positions.get(entity) {
+            Some(position) => *position,
+            None => continue,
+        };
+        let current = entities.jobs.get(entity).map(|job| job.kind);
+        let is_sleeping = current == Some(JobKind::Sleep);
+        let is_eating = match current {
+            Some(JobKind::Eat(_)) => true,
+            _ => false,
+        };
+
+        let (food, rest) = match entities.needs.get_mut(entity) {
+            Some(needs) => {
+                needs.food = (needs.food - FOOD_PER_TICK).max(0.0);
+                if !is_sleeping {
+                    needs.rest = (needs.rest - REST_PER_TICK).max(0.0);
+                }
+                let change = (needs.food + needs.rest) / 2.0 - needs.mood;
+                needs.mood += change.max(-MOOD_CHANGE_PER_TICK).min(MOOD_CHANGE_PER_TICK);
+                (needs.food, needs.rest)
+            },
+            None => continue,
+        };
+        // Drafted colonists stay at their posts however they feel.
+        let is_drafted = entities.colonists.get(entity).map_or(false, |colonist| colonist.drafted);
+        if is_sleeping || is_eating || is_drafted {
+            continue;
+        }
+
+        let need_job = if rest < CRITICAL_NEED {
+            Some(Job::new(JobKind::Sleep, position))
+        } else if food < CRITICAL_NEED {
+            nearest_item(&entities.items, &entities.positions, ItemKind::Food, &position, &claimed_food).map(|(food, food_position)| {
+                claimed_food.push(food);
+                Job::new(JobKind::Eat(food), food_position)
+            })
+        } else {
+            None
+        };
+        let need_job = match need_job {
+            Some(need_job) => need_job,
+            None => continue,
+        };
+
+        interrupt_job(entities, &mut state.job_queue, entity);
+        entities.jobs.insert(entity, need_job);
+    }
+}
+
+/// Takes the current job away from a colonist, queueing it again at the front
+/// of the queue, and stops the colonist in its tracks.
+///
+/// Anything being carried is put down where the colonist stands. Hauling jobs
+/// are discarded rather than queued again, since the item is hauled again
+/// later anyway, as are jobs which only the colonist itself could carry out.
+pub fn interrupt_job(entities: &mut Entities, job_queue: &mut Vec<Job>, entity: Entity) {
+    if let Some(job) = entities.jobs.remove(entity) {
+        match job.kind {
+            JobKind::Haul(_) | JobKind::Eat(_) | JobKind::Sleep => {},
+            _ => job_queue.insert(0, job),
+        }
+    }
+
+    let carried = entities.colonists.get_mut(entity).and_then(|colonist| colonist.carrying.take());
+    let position = entities.positions.get(entity).cloned();
+    if let (Some(item), Some(position)) = (carried, position) {
+        entities.positions.insert(item, position);
+    }
+    if let Some(pathing) = entities.pathing.get_mut(entity) {
+        pathing.stop();
+    }
+}
+
+/// Returns the item of the specified kind lying closest to `position` on the
+/// same z-level, and its position, ignoring the items in `claimed`.
+fn nearest_item(items: &Storage<Item>, positions: &Storage<Position>, kind: ItemKind, position: &Position, claimed: &[Entity]) -> Option<(Entity, Position)> {
+    items.iter()
+        .filter(|&(item, other)| other.kind == kind && !claimed.contains(&item))
+        .filter_map(|(item, _)| positions.get(item).map(|item_position| (item, *item_position)))
+        .filter(|&(_, item_position)| item_position.y == position.y)
+        .min_by_key(|&(_, item_position)| (item_position.x - position.x).abs() + (item_position.z - position.z).abs())
+}
+
+/// Assigns queued jobs to idle colonists, and carries out the jobs of the
+/// colonists which have reached them. Tiles dug out, jobs finished and the
+/// entities spawned by them are reported to `events`.
+pub fn work(state: &mut WorldState, area: &mut Area, materials: &MaterialRegistry, events: &mut Vec<WorldEvent>) {
+    let air = materials.find("air").expect("required material is defined");
+    let stairs = materials.find("stairs").expect("required material is defined");
+    let entities = &mut state.entities;
+    let stockpiles = &state.stockpiles;
+
+    let idle: Vec<_> = entities.colonists.iter()
+        .filter(|&(_, colonist)| !colonist.drafted)
+        .map(|(entity, _)| entity)
+        .filter(|entity| !entities.jobs.contains(*entity))
+        .collect();
+    for entity in idle {
+        if state.job_queue.is_empty() {
+            break;
+        }
+        let job = state.job_queue.remove(0);
+        entities.jobs.insert(entity, job);
+    }
+
+    // Items which are already being hauled, eaten or built with.
+    let mut claimed: Vec<Entity> = entities.blueprints.iter()
+        .filter_map(|(_, blueprint)| blueprint.material)
+        .collect();
+    for job in state.job_queue.iter().chain(entities.jobs.iter().map(|(_, job)| job)) {
+        match job.kind {
+            JobKind::Haul(item) | JobKind::Eat(item) => claimed.push(item),
+            _ => {},
+        }
+    }
+
+    let mut finished = Vec::new();
+    let mut requeued = Vec::new();
+    let mut dropped_items = Vec::new();
+    let mut eaten = Vec::new();
+    let mut built = Vec::new();
+    let mut hunted = Vec::new();
+    for (entity, job) in entities.jobs.iter_mut() {
+        let (position, pathing) = match (entities.positions.get(entity), entities.pathing.get_mut(entity)) {
+            (Some(position), Some(pathing)) => (*position, pathing),
+            _ => continue,
+        };
+        let target = job.target.to_point();
+
+        match job.kind {
+            JobKind::Dig | JobKind::DigStairs => {
+                // The job is done once there is nothing left to dig, and is
+                // abandoned if the tile cannot be reached.
+                let (hardness, drops) = match materials.get(area.get_tile(&target).material) {
+                    Some(material) if material.solid => (material.hardness, material.drops),
+                    _ => {
+                        finished.push(entity);
+                        continue;
+                    },
+                };
+                if pathing.unreachable {
+                    finished.push(entity);
+                    continue;
+                }
+
+                // Stairs are dug out from the tile above whenever it can be
+                // stood in, which is how colonists dig their way down.
+                let above = Position::new(job.target.x, job.target.y + 1, job.target.z);
+                let from_above = job.kind == JobKind::DigStairs &&
+                    pathfinding::is_standable(area, materials, &above.to_point());
+                let is_beside = position.y == job.target.y &&
+                    (position.x - job.target.x).abs() + (position.z - job.target.z).abs() == 1;
+                let is_in_reach = if from_above { position == above } else { is_beside };
+                if !is_in_reach {
+                    if pathing.target.is_none() {
+                        if from_above {
+                            pathing.walk_to(above, false);
+                        } else {
+                            pathing.walk_to(job.target, true);
+                        }
+                    }
+                    continue;
+                }
+
+                // Colonists in a good mood work faster. The progress is kept
+                // with the tile, so that another colonist picks up where a
+                // dig was left.
+                let mut metadata = area.get_metadata(&target);
+                let progress = metadata.dig_progress() + entities.needs.get(entity).map_or(1.0, |needs| needs.work_speed());
+                if progress < hardness * DIG_TICKS_PER_HARDNESS {
+                    metadata.set_dig_progress(progress);
+                    area.set_metadata(&target, metadata);
+                } else {
+                    let dug_out = if job.kind == JobKind::DigStairs { stairs } else { air };
+                    area.set_tile(&target, Tile::new(dug_out));
+                    fog::reveal_around(area, &target);
+                    events.push(WorldEvent::Dug(job.target));
+                    events.push(WorldEvent::JobFinished(entity, job.kind, job.target));
+                    if let Some(kind) = drops {
+                        dropped_items.push((kind, job.target));
+                    }
+                    finished.push(entity);
+                }
+            },
+            JobKind::Haul(item) => {
+                let colonist = match entities.colonists.get_mut(entity) {
+                    Some(colonist) => colonist,
+                    None => continue,
+                };
+
+                // The job is abandoned if the item or the stockpile cannot be
+                // reached, or if the stockpile tile is no longer free.
+                let is_occupied = {
+                    let positions = &entities.positions;
+                    entities.items.iter().any(|(other, _)| other != item && positions.get(other) == Some(&job.target))
+                };
+                let item_position = entities.positions.get(item).cloned();
+                let is_lost = colonist.carrying != Some(item) && item_position.is_none();
+                if pathing.unreachable || is_occupied || is_lost || !stockpiles.contains(&job.target) {
+                    // Whatever is being carried is put down where the
+                    // colonist stands.
+                    if colonist.carrying.take().is_some() {
+                        entities.positions.insert(item, position);
+                    }
+                    finished.push(entity);
+                    continue;
+                }
+
+                let destination = match item_position {
+                    Some(item_position) if item_position == position => {
+                        entities.positions.remove(item);
+                        colonist.carrying = Some(item);
+                        job.target
+                    },
+                    Some(item_position) => item_position,
+                    None if position == job.target => {
+                        entities.positions.insert(item, job.target);
+                        colonist.carrying = None;
+                        events.push(WorldEvent::JobFinished(entity, job.kind, job.target));
+                        finished.push(entity);
+                        continue;
+                    },
+                    None => job.target,
+                };
+                if pathing.target != Some(destination) {
+                    pathing.walk_to(destination, false);
+                }
+            },
+            JobKind::Eat(food) => {
+                let food_position = match entities.positions.get(food) {
+                    Some(food_position) => *food_position,
+                    None => {
+                        finished.push(entity);
+                        continue;
+                    },
+                };
+                if pathing.unreachable {
+                    finished.push(entity);
+                    continue;
+                }
+
+                if position != food_position {
+                    if pathing.target != Some(food_position) {
+                        pathing.walk_to(food_position, false);
+                    }
+                    continue;
+                }
+
+                if let Some(needs) = entities.needs.get_mut(entity) {
+                    needs.food = 1.0;
+                }
+                eaten.push(food);
+                events.push(WorldEvent::JobFinished(entity, job.kind, job.target));
+                finished.push(entity);
+            },
+            JobKind::Sleep => {
+                match entities.needs.get_mut(entity) {
+                    Some(needs) => {
+                        needs.rest = (needs.rest + SLEEP_RECOVERY_PER_TICK).min(1.0);
+                        if needs.rest >= 1.0 {
+                            events.push(WorldEvent::JobFinished(entity, job.kind, job.target));
+                            finished.push(entity);
+                        }
+                    },
+                    None => finished.push(entity),
+                }
+            },
+            JobKind::Build(blueprint_entity) => {
+                // Nothing is built over anyone standing in the way.
+                let is_blocked = {
+                    let positions = &entities.positions;
+                    entities.colonists.iter().any(|(other, _)| positions.get(other) == Some(&job.target))
+                };
+                let colonist = match entities.colonists.get_mut(entity) {
+                    Some(colonist) => colonist,
+                    None => continue,
+                };
+                let blueprint = match entities.blueprints.get_mut(blueprint_entity) {
+                    Some(blueprint) => blueprint,
+                    None => {
+                        if let Some(item) = colonist.carrying.take() {
+                            entities.positions.insert(item, position);
+                        }
+                        finished.push(entity);
+                        continue;
+                    },
+                };
+
+                // If the material or the blueprint cannot be reached, the
+                // material is put down and left for someone else to try.
+                if pathing.unreachable {
+                    if let Some(item) = colonist.carrying.take() {
+                        entities.positions.insert(item, position);
+                    }
+                    blueprint.material = None;
+                    finished.push(entity);
+                    requeued.push(job.clone());
+                    continue;
+                }
+
+                let material = match blueprint.material {
+                    Some(item) if entities.items.contains(item) => item,
+                    _ => {
+                        let kind = blueprint.structure.material_needed();
+                        match nearest_item(&entities.items, &entities.positions, kind, &position, &claimed) {
+                            Some((item, _)) => {
+                                claimed.push(item);
+                                blueprint.material = Some(item);
+                            },
+                            // The job waits at the back of the queue until
+                            // the material turns up.
+                            None => {
+                                finished.push(entity);
+                                requeued.push(job.clone());
+                            },
+                        }
+                        continue;
+                    },
+                };
+
+                if colonist.carrying != Some(material) {
+                    let item_position = entities.positions.get(material).cloned();
+                    match item_position {
+                        Some(item_position) if item_position == position => {
+                            entities.positions.remove(material);
+                            colonist.carrying = Some(material);
+                        },
+                        Some(item_position) => if pathing.target != Some(item_position) {
+                            pathing.walk_to(item_position, false);
+                        },
+                        // Someone else has picked up the material, so
+                        // another is chosen.
+                        None => blueprint.material = None,
+                    }
+                    continue;
+                }
+
+                let is_beside = position.y == job.target.y &&
+                    (position.x - job.target.x).abs() + (position.z - job.target.z).abs() == 1;
+                if !is_beside {
+                    if pathing.target != Some(job.target) {
+                        pathing.walk_to(job.target, true);
+                    }
+                    continue;
+                }
+
+                if is_blocked {
+                    continue;
+                }
+
+                job.progress += entities.needs.get(entity).map_or(1.0, |needs| needs.work_speed());
+                if job.progress >= blueprint.structure.build_ticks() {
+                    colonist.carrying = None;
+                    built.push((blueprint_entity, blueprint.structure, job.target, material));
+                    events.push(WorldEvent::JobFinished(entity, job.kind, job.target));
+                    finished.push(entity);
+                }
+            },
+            JobKind::Hunt(animal) => {
+                // The hunt is over once the animal is gone, and is given up
+                // if the animal cannot be reached.
+                let animal_position = match entities.positions.get(animal) {
+                    Some(animal_position) if entities.animals.contains(animal) => *animal_position,
+                    _ => {
+                        finished.push(entity);
+                        continue;
+                    },
+                };
+                if pathing.unreachable {
+                    finished.push(entity);
+                    continue;
+                }
+
+                let is_beside = position.y == animal_position.y &&
+                    (position.x - animal_position.x).abs() + (position.z - animal_position.z).abs() <= 1;
+                if !is_beside {
+                    // The route is found again whenever the animal moves.
+                    if pathing.target != Some(animal_position) {
+                        pathing.walk_to(animal_position, true);
+                    }
+                    continue;
+                }
+
+                hunted.push((animal, animal_position));
+                events.push(WorldEvent::JobFinished(entity, job.kind, job.target));
+                finished.push(entity);
+            },
+            JobKind::Custom(_) => {
+                if pathing.unreachable {
+                    finished.push(entity);
+                    continue;
+                }
+
+                let is_beside = position.y == job.target.y &&
+                    (position.x - job.target.x).abs() + (position.z - job.target.z).abs() == 1;
+                if !is_beside {
+                    if pathing.target != Some(job.target) {
+                        pathing.walk_to(job.target, true);
+                    }
+                    continue;
+                }
+
+                job.progress += entities.needs.get(entity).map_or(1.0, |needs| needs.work_speed());
+                if job.progress >= CUSTOM_JOB_TICKS {
+                    events.push(WorldEvent::JobFinished(entity, job.kind, job.target));
+                    finished.push(entity);
+                }
+            },
+        }
+    }
+
+    for entity in finished {
+        entities.jobs.remove(entity);
+        if let Some(pathing) = entities.pathing.get_mut(entity) {
+            pathing.stop();
+        }
+    }
+    for (kind, position) in dropped_items {
+        events.push(WorldEvent::Spawned(spawn_item(entities, kind, position)));
+    }
+    for food in eaten {
+        entities.despawn(food);
+    }
+    state.job_queue.extend(requeued);
+
+    for (animal, position) in hunted {
+        let kind = match entities.animals.get(animal) {
+            Some(animal) => animal.kind,
+            None => continue,
+        };
+        entities.despawn(animal);
+        for _ in 0..kind.food_yield() {
+            events.push(WorldEvent::Spawned(spawn_item(entities, ItemKind::Food, position)));
+        }
+    }
+
+    for (blueprint, structure, target, material) in built {
+        entities.despawn(blueprint);
+        entities.despawn(material);
+        // The tile may have changed while the structure was being built, in
+        // which case the material is wasted.
+        let pos = target.to_point();
+        if !can_build(area, materials, structure, &pos) {
+            continue;
+        }
+
+        match structure {
+            Structure::Wall => {
+                let wall = materials.find("wall").expect("required material is defined");
+                area.set_tile(&pos, Tile::new(wall));
+            },
+            Structure::Floor => {
+                let planks = materials.find("planks").expect("required material is defined");
+                area.set_tile(&(pos + Direction::Down.to_vector()), Tile::new(planks));
+            },
+            Structure::Stairs => {
+                area.set_tile(&pos, Tile::new(stairs));
+            },
+            Structure::Ramp => {
+                let ramp = materials.find("ramp").expect("required material is defined");
+                area.set_tile(&pos, Tile::new(ramp));
+            },
+            Structure::Workshop => {
+                events.push(WorldEvent::Spawned(spawn_furniture(entities, FurnitureKind::Workshop, target)));
+            },
+        }
+    }
+}
+
+/// Returns true if `structure` can be built at `pos`. Walls, stairs and ramps
+/// need an open tile, floors need an open tile above an open tile, and furniture
+/// needs a tile which can be stood in.
+pub fn can_build(area: &Area, materials: &MaterialRegistry, structure: Structure, pos: &Point3<i32>) -> bool {
+    let is_open = |pos: &Point3<i32>| {
+        materials.get(area.get_tile(pos).material).map_or(false, |material| !material.solid && !material.liquid)
+    };
+
+    match structure {
+        Structure::Wall | Structure::Stairs | Structure::Ramp => is_open(pos),
+        Structure::Floor => is_open(pos) && is_open(&(*pos + Direction::Down.to_vector())),
+        Structure::Workshop => pathfinding::is_standable(area, materials, pos),
+    }
+}
+
+/// Queues a job to haul each loose item lying outside of the stockpiles to a
+/// free stockpile tile, for as long as there are free tiles.
+pub fn queue_hauling(state: &mut WorldState) {
+    if state.stockpiles.is_empty() {
+        return;
+    }
+    let entities = &state.entities;
+
+    // Stockpile tiles already holding an item, or which an item is being
+    // hauled to, are not free.
+    let mut hauled = Vec::new();
+    let mut claimed = BTreeSet::new();
+    for job in state.job_queue.iter().chain(entities.jobs.iter().map(|(_, job)| job)) {
+        if let JobKind::Haul(item) = job.kind {
+            hauled.push(item);
+            claimed.insert(job.target);
+        }
+    }
+    // Items set aside for building are left where they lie.
+    hauled.extend(entities.blueprints.iter().filter_map(|(_, blueprint)| blueprint.material));
+    for (item, _) in entities.items.iter() {
+        if let Some(position) = entities.positions.get(item) {
+            claimed.insert(*position);
+        }
+    }
+
+    let mut free = state.stockpiles.iter().filter(|tile| !claimed.contains(*tile));
+    let mut jobs = Vec::new();
+    for (item, _) in entities.items.iter() {
+        let is_loose = match entities.positions.get(item) {
+            Some(position) => !state.stockpiles.contains(position) && !hauled.contains(&item),
+            None => false,
+        };
+        if !is_loose {
+            continue;
+        }
+
+        match free.next() {
+            Some(tile) => jobs.push(Job::new(JobKind::Haul(item), *tile)),
+            None => break,
+        }
+    }
+
+    state.job_queue.extend(jobs);
+}
+
+/// Spawns a piece of furniture of the specified kind standing at `position`.
+pub fn spawn_furniture(entities: &mut Entities, kind: FurnitureKind, position: Position) -> Entity {
+    let entity = entities.spawn();
+
+    entities.positions.insert(entity, position);
+    entities.renderables.insert(entity, Renderable { color: kind.color() });
+    entities.furniture.insert(entity, Furniture { kind: kind });
+    entity
+}
+
+/// Spawns an item of the specified kind lying at `position`.
+pub fn spawn_item(entities: &mut Entities, kind: ItemKind, position: Position) -> Entity {
+    let entity = entities.spawn();
+
+    entities.positions.insert(entity, position);
+    entities.renderables.insert(entity, Renderable { color: kind.color() });
+    entities.items.insert(entity, Item { kind: kind });
+    entity
+}
@@ -1,50 +1,86 @@
-use self::TileType::*;
+use biome::Biome;
+use material::{MaterialId, MaterialRegistry, OUT_OF_BOUNDS};
 
 // TODO: refactor these values to be configurable.
-const WATER_LINE: i32 = 14;
 const SOIL_DEPTH: i32 = 3;
 
-#[derive(Clone, Copy, Eq, Hash, PartialEq)]
-pub enum TileType {
-    Air,
-    Grass,
-    OutOfBounds,
-    Sand,
-    Soil,
-    Wall,
-    Water,
+/// The materials placed by the world generator, resolved from a
+/// `MaterialRegistry` by name.
+#[derive(Clone, Copy)]
+pub struct TerrainMaterials {
+    pub air: MaterialId,
+    pub grass: MaterialId,
+    pub leaves: MaterialId,
+    pub sand: MaterialId,
+    pub shrub: MaterialId,
+    pub snow: MaterialId,
+    pub soil: MaterialId,
+    pub wall: MaterialId,
+    pub water: MaterialId,
+    pub wood: MaterialId,
 }
 
-impl TileType {
-    pub fn is_solid(&self) -> bool {
-        match *self {
-            Grass | Sand | Soil | Wall | Water => true,
-            Air | OutOfBounds => false,
+impl TerrainMaterials {
+    pub fn new(registry: &MaterialRegistry) -> Self {
+        // Every registry is guaranteed to define the required materials.
+        let require = |name: &str| registry.find(name).expect("required material is defined");
+
+        TerrainMaterials {
+            air: require("air"),
+            grass: require("grass"),
+            leaves: require("leaves"),
+            sand: require("sand"),
+            shrub: require("shrub"),
+            snow: require("snow"),
+            soil: require("soil"),
+            wall: require("wall"),
+            water: require("water"),
+            wood: require("wood"),
+        }
+    }
+
+    /// Returns the material covering the surface of a biome.
+    pub fn surface(&self, biome: Biome) -> MaterialId {
+        match biome {
+            Biome::Desert => self.sand,
+            Biome::Mountains => self.wall,
+            Biome::Plains => self.grass,
+            Biome::Tundra => self.snow,
+        }
+    }
+
+    /// Returns the material found just beneath the surface of a biome.
+    pub fn subsoil(&self, biome: Biome) -> MaterialId {
+        match biome {
+            Biome::Desert => self.sand,
+            Biome::Mountains => self.wall,
+            Biome::Plains | Biome::Tundra => self.soil,
         }
     }
 
-    /// Returns the TileType for a tile at a specific elevation, provided the
-    /// height_map specifies a `height` at this location.
-    pub fn get_from_elevation(elevation: i32, height: i32) -> Self {
+    /// Returns the material for a tile at a specific elevation, provided the
+    /// height map specifies a `height` at this location, within `biome`.
+    /// Empty tiles at or below `sea_level` are filled with water.
+    pub fn get_from_elevation(&self, elevation: i32, height: i32, biome: Biome, sea_level: i32) -> MaterialId {
         match elevation {
             _ if elevation > height => {
                 match elevation {
-                    _ if elevation > WATER_LINE => TileType::Air,
-                    _ => TileType::Water,
+                    _ if elevation > sea_level => self.air,
+                    _ => self.water,
                 }
             }
             _ => match elevation {
-                _ if elevation > WATER_LINE => {
+                _ if elevation > sea_level => {
                     match elevation {
-                        _ if elevation > height - 1 => TileType::Grass,
-                        _ if elevation > height - SOIL_DEPTH => TileType::Soil,
-                        _ => TileType::Wall,
+                        _ if elevation > height - 1 => self.surface(biome),
+                        _ if elevation > height - SOIL_DEPTH => self.subsoil(biome),
+                        _ => self.wall,
                     }
                 },
                 _ => {
                     match elevation {
-                        _ if elevation > height - SOIL_DEPTH => TileType::Sand,
-                        _ => TileType::Wall,
+                        _ if elevation > height - SOIL_DEPTH => self.sand,
+                        _ => self.wall,
                     }
                 }
             }
@@ -52,15 +88,33 @@ impl TileType {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Tile {
-    pub tile_type: TileType
+    pub material: MaterialId,
+    /// The amount of fluid in the tile, up to `MAX_FLUID_LEVEL`. Always zero
+    /// for tiles which are not made of a liquid.
+    pub fluid_level: u8,
 }
 
 impl Tile {
-    pub fn new(tile_type: TileType) -> Tile {
+    pub fn new(material: MaterialId) -> Tile {
         Tile {
-            tile_type: tile_type
+            material: material,
+            fluid_level: 0,
         }
     }
+
+    /// Creates a tile of a liquid material holding the specified amount of
+    /// fluid.
+    pub fn with_fluid_level(material: MaterialId, fluid_level: u8) -> Tile {
+        Tile {
+            material: material,
+            fluid_level: fluid_level,
+        }
+    }
+
+    /// Returns the tile found outside of the generated chunks.
+    pub fn out_of_bounds() -> Tile {
+        Tile::new(OUT_OF_BOUNDS)
+    }
 }
@@ -0,0 +1,15 @@
+/// The weather over the whole world, which changes every few hours.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Weather {
+    /// Whether rain or snow is falling, depending on the temperature.
+    precipitating: bool,
+    /// The number of ticks until the weather changes.
+    remaining_ticks: u64,
+}
+
+/// What falls from the sky while it is precipitating.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Precipitation {
+    Rain,
+    Snow,
+}
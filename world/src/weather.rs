@@ -0,0 +1,179 @@
+use cgmath::Point3;
+use rand::Rng;
+
+use area::Area;
+use biome::Biome;
+use calendar::{Calendar, Season, TICKS_PER_HOUR};
+use material::{MaterialId, MaterialRegistry, OUT_OF_BOUNDS};
+use terrain::Tile;
+use world::WorldState;
+
+#[cfg(feature = "nightly")]
+include!("weather.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/weather.rs"));
+
+// The weather is drawn with the rng of the world, so changing how it is
+// drawn changes the weather of every seed, and of every recorded replay.
+/// The chance that it starts precipitating whenever the weather changes.
+const PRECIPITATION_CHANCE: f64 = 0.35;
+/// The range of the number of hours the weather lasts before changing.
+const MIN_WEATHER_HOURS: u64 = 2;
+const MAX_WEATHER_HOURS: u64 = 12;
+/// The difference between the temperature at noon and the daily average, in
+/// degrees Celsius.
+const DAILY_TEMPERATURE_VARIATION: f64 = 5.0;
+/// The temperature at or below which snow falls instead of rain, in degrees
+/// Celsius.
+const FREEZING_POINT: f64 = 0.0;
+/// The number of surface tiles affected by the weather every tick.
+const SURFACE_SAMPLES_PER_TICK: usize = 4;
+/// The distance from a colonist within which the surface is affected by the
+/// weather, along the horizontal axes.
+const SURFACE_SAMPLE_RADIUS: i32 = 32;
+/// The distance above and below a colonist within which the surface is
+/// searched for.
+const SURFACE_SEARCH_HEIGHT: i32 = 16;
+/// The deepest a puddle left by rain can get.
+const MAX_PUDDLE_LEVEL: u8 = 2;
+
+impl Weather {
+    pub fn is_precipitating(&self) -> bool {
+        self.precipitating
+    }
+
+    /// Returns what is falling from the sky where the temperature is
+    /// `temperature`, if anything.
+    pub fn precipitation(&self, temperature: f64) -> Option<Precipitation> {
+        match () {
+            _ if !self.precipitating => None,
+            _ if temperature <= FREEZING_POINT => Some(Precipitation::Snow),
+            _ => Some(Precipitation::Rain),
+        }
+    }
+
+    /// Advances the weather by one tick, changing it at random once it has
+    /// lasted long enough.
+    fn tick<R: Rng>(&mut self, rng: &mut R) {
+        if self.remaining_ticks > 0 {
+            self.remaining_ticks -= 1;
+            return;
+        }
+
+        self.precipitating = rng.gen::<f64>() < PRECIPITATION_CHANCE;
+        self.remaining_ticks = rng.gen_range(MIN_WEATHER_HOURS, MAX_WEATHER_HOURS + 1) * TICKS_PER_HOUR;
+    }
+}
+
+impl Biome {
+    /// Returns the average temperature of this biome over the year, in
+    /// degrees Celsius.
+    pub fn base_temperature(&self) -> f64 {
+        match *self {
+            Biome::Desert => 30.0,
+            Biome::Mountains => 4.0,
+            Biome::Plains => 14.0,
+            Biome::Tundra => -8.0,
+        }
+    }
+}
+
+impl Season {
+    /// Returns the difference between the average temperature during this
+    /// season and that over the year, in degrees Celsius.
+    pub fn temperature_offset(&self) -> f64 {
+        match *self {
+            Season::Spring => 0.0,
+            Season::Summer => 10.0,
+            Season::Autumn => -2.0,
+            Season::Winter => -12.0,
+        }
+    }
+}
+
+/// Returns the temperature in `biome` at the date and time of `calendar`, in
+/// degrees Celsius.
+pub fn temperature(biome: Biome, calendar: &Calendar) -> f64 {
+    let daily = (calendar.daylight() * 2.0 - 1.0) * DAILY_TEMPERATURE_VARIATION;
+    biome.base_temperature() + calendar.season().temperature_offset() + daily
+}
+
+/// Advances the weather by one tick, and lets it change a few of the surface
+/// tiles around the colonists: rain leaves puddles and melts snow, snow
+/// covers the ground, and both melt and dry up once it stops.
+pub fn tick(state: &mut WorldState, area: &mut Area, materials: &MaterialRegistry) {
+    state.weather.tick(&mut state.rng);
+
+    let air = materials.find("air").expect("required material is defined");
+    let water = materials.find("water").expect("required material is defined");
+    let snow_cover = materials.find("snow_cover");
+
+    let colonists: Vec<Point3<i32>> = state.entities.colonists.iter()
+        .filter_map(|(entity, _)| state.entities.positions.get(entity))
+        .map(|position| position.to_point())
+        .collect();
+    if colonists.is_empty() {
+        return;
+    }
+
+    for _ in 0..SURFACE_SAMPLES_PER_TICK {
+        let center = *state.rng.choose(&colonists).expect("colonists are present");
+        let x = center.x + state.rng.gen_range(-SURFACE_SAMPLE_RADIUS, SURFACE_SAMPLE_RADIUS + 1);
+        let z = center.z + state.rng.gen_range(-SURFACE_SAMPLE_RADIUS, SURFACE_SAMPLE_RADIUS + 1);
+        let pos = match find_surface(area, materials, x, center.y, z) {
+            Some(pos) => pos,
+            None => continue,
+        };
+
+        let tile = area.get_tile(&pos);
+        let temperature = temperature(area.biome_at(x, z), &state.calendar);
+        let is_snow = |material: MaterialId| Some(material) == snow_cover;
+        let is_puddle = tile.material == water && tile.fluid_level <= MAX_PUDDLE_LEVEL;
+
+        let replacement = match state.weather.precipitation(temperature) {
+            Some(Precipitation::Snow) if tile.material == air => snow_cover.map(Tile::new),
+            Some(Precipitation::Rain) if tile.material == air || is_snow(tile.material) => {
+                Some(Tile::with_fluid_level(water, 1))
+            },
+            Some(Precipitation::Rain) if is_puddle && tile.fluid_level < MAX_PUDDLE_LEVEL => {
+                Some(Tile::with_fluid_level(water, tile.fluid_level + 1))
+            },
+            None if temperature > FREEZING_POINT && is_snow(tile.material) => {
+                Some(Tile::with_fluid_level(water, 1))
+            },
+            None if temperature > FREEZING_POINT && is_puddle => match tile.fluid_level {
+                0 | 1 => Some(Tile::new(air)),
+                level => Some(Tile::with_fluid_level(water, level - 1)),
+            },
+            _ => None,
+        };
+        if let Some(replacement) = replacement {
+            area.set_tile(&pos, replacement);
+        }
+    }
+}
+
+/// Returns the position of the uppermost tile of the column at `x` and `z`
+/// which is open to the sky, searching within `SURFACE_SEARCH_HEIGHT` tiles of
+/// `y`: either the empty tile above the ground, or the liquid or other
+/// see-through tile lying on it.
+pub fn find_surface(area: &Area, materials: &MaterialRegistry, x: i32, y: i32, z: i32) -> Option<Point3<i32>> {
+    let air = materials.find("air").expect("required material is defined");
+
+    for y in (y - SURFACE_SEARCH_HEIGHT..y + SURFACE_SEARCH_HEIGHT + 1).rev() {
+        let pos = Point3::new(x, y, z);
+        let tile = area.get_tile(&pos);
+        if tile.material == air || tile.material == OUT_OF_BOUNDS {
+            continue;
+        }
+
+        return if materials.is_solid(tile.material) {
+            Some(Point3::new(x, y + 1, z))
+        } else {
+            Some(pos)
+        };
+    }
+
+    None
+}
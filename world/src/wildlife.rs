@@ -0,0 +1,186 @@
+use rand::Rng;
+
+use {CHUNK_SIZE, LOG2_OF_CHUNK_SIZE};
+use area::Area;
+use calendar::TICKS_PER_HOUR;
+use components::{Animal, AnimalKind, Pathing, Position, Renderable};
+use entity::{Entities, Entity};
+use event::WorldEvent;
+use material::MaterialRegistry;
+use pathfinding;
+use weather;
+use world::WorldState;
+
+// Newcomers only arrive on a surface found in loaded chunks, so
+// `SPAWN_CHUNK_DISTANCE` must stay within the chunks loaded around the
+// colonists for any animal or invader to arrive at all.
+/// The most animals which roam the world at once.
+const MAX_ANIMALS: usize = 12;
+/// The chance that an animal arrives every tick, while there are fewer than
+/// `MAX_ANIMALS`.
+const SPAWN_CHANCE: f64 = 1.0 / TICKS_PER_HOUR as f64;
+/// The distance, in chunks, from the chunk of a colonist to the chunks on
+/// whose outer edges animals and invaders arrive.
+const SPAWN_CHUNK_DISTANCE: i32 = 2;
+/// The distance from a colonist within which animals run away.
+const FLEE_DISTANCE: i32 = 6;
+/// The furthest an animal runs at a time, along each horizontal axis.
+const FLEE_STEP: i32 = 8;
+/// The number of ticks an animal can keep running before it is too tired to
+/// run any further.
+const MAX_FATIGUE: u32 = 40;
+/// The chance that an animal with nowhere to go wanders off every tick.
+const WANDER_CHANCE: f64 = 0.02;
+/// The furthest an animal wanders at a time, along each horizontal axis.
+const WANDER_RADIUS: i32 = 6;
+/// The number of random destinations tried whenever an animal decides where
+/// to go.
+const DESTINATION_ATTEMPTS: usize = 4;
+
+/// Lets new animals arrive around the colonists, and decides where every
+/// animal goes: away from any colonist coming near, for as long as it has the
+/// energy to run, and otherwise wherever it pleases. The animals which arrive
+/// are reported to `events`.
+pub fn tick(state: &mut WorldState, area: &Area, materials: &MaterialRegistry, events: &mut Vec<WorldEvent>) {
+    let colonists: Vec<Position> = state.entities.colonists.iter()
+        .filter_map(|(entity, _)| state.entities.positions.get(entity))
+        .cloned()
+        .collect();
+    if colonists.is_empty() {
+        return;
+    }
+
+    if state.entities.animals.iter().count() < MAX_ANIMALS && state.rng.gen::<f64>() < SPAWN_CHANCE {
+        let center = *state.rng.choose(&colonists).expect("colonists are present");
+        if let Some(position) = find_spawn_position(&mut state.rng, area, materials, &center) {
+            let biome = area.biome_at(position.x, position.z);
+            if let Some(&kind) = state.rng.choose(biome.animals()) {
+                events.push(WorldEvent::Spawned(spawn_animal(&mut state.entities, kind, position)));
+            }
+        }
+    }
+
+    let rng = &mut state.rng;
+    let entities = &mut state.entities;
+    for (entity, animal) in entities.animals.iter_mut() {
+        let (position, pathing) = match (entities.positions.get(entity), entities.pathing.get_mut(entity)) {
+            (Some(position), Some(pathing)) => (*position, pathing),
+            _ => continue,
+        };
+
+        let nearest = colonists.iter()
+            .filter(|colonist| colonist.y == position.y)
+            .min_by_key(|colonist| distance(colonist, &position));
+        let threat = match nearest {
+            Some(colonist) if distance(colonist, &position) <= FLEE_DISTANCE => Some(*colonist),
+            _ => None,
+        };
+
+        match threat {
+            Some(threat) if animal.fatigue < MAX_FATIGUE => {
+                animal.fatigue += 1;
+                if pathing.target.is_none() {
+                    if let Some(destination) = flee_destination(rng, area, materials, &position, &threat) {
+                        pathing.walk_to(destination, false);
+                    }
+                }
+            },
+            // Too tired to run any further, the animal stands its ground.
+            Some(_) => pathing.stop(),
+            None => {
+                animal.fatigue = animal.fatigue.saturating_sub(1);
+                if pathing.target.is_none() && rng.gen::<f64>() < WANDER_CHANCE {
+                    if let Some(destination) = wander_destination(rng, area, materials, &position) {
+                        pathing.walk_to(destination, false);
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Spawns an animal of the specified kind standing at `position`.
+pub fn spawn_animal(entities: &mut Entities, kind: AnimalKind, position: Position) -> Entity {
+    let entity = entities.spawn();
+
+    entities.positions.insert(entity, position);
+    entities.renderables.insert(entity, Renderable { color: kind.color() });
+    entities.pathing.insert(entity, Pathing::default());
+    entities.animals.insert(entity, Animal {
+        kind: kind,
+        fatigue: 0,
+    });
+    entity
+}
+
+/// Returns the distance between two positions along the horizontal axes.
+fn distance(a: &Position, b: &Position) -> i32 {
+    (a.x - b.x).abs() + (a.z - b.z).abs()
+}
+
+/// Picks a place on the surface for a newcomer to the world to arrive at,
+/// somewhere along the outer edge of the chunks within `SPAWN_CHUNK_DISTANCE`
+/// of the chunk containing `center`, so that it comes in from out of sight.
+pub fn find_spawn_position<R: Rng>(rng: &mut R, area: &Area, materials: &MaterialRegistry, center: &Position) -> Option<Position> {
+    let size = CHUNK_SIZE as i32;
+    let chunk_x = center.x >> LOG2_OF_CHUNK_SIZE;
+    let chunk_z = center.z >> LOG2_OF_CHUNK_SIZE;
+    let (min_x, max_x) = ((chunk_x - SPAWN_CHUNK_DISTANCE) * size, (chunk_x + SPAWN_CHUNK_DISTANCE + 1) * size - 1);
+    let (min_z, max_z) = ((chunk_z - SPAWN_CHUNK_DISTANCE) * size, (chunk_z + SPAWN_CHUNK_DISTANCE + 1) * size - 1);
+
+    let along_x = rng.gen_range(min_x, max_x + 1);
+    let along_z = rng.gen_range(min_z, max_z + 1);
+    let (x, z) = match rng.gen_range(0, 4) {
+        0 => (min_x, along_z),
+        1 => (max_x, along_z),
+        2 => (along_x, min_z),
+        _ => (along_x, max_z),
+    };
+
+    match weather::find_surface(area, materials, x, center.y, z) {
+        Some(pos) if pathfinding::is_standable(area, materials, &pos) => Some(Position::from(pos)),
+        _ => None,
+    }
+}
+
+/// Picks a tile for an animal at `position` to run to, away from `threat`.
+fn flee_destination<R: Rng>(rng: &mut R, area: &Area, materials: &MaterialRegistry, position: &Position, threat: &Position) -> Option<Position> {
+    let away_x = (position.x - threat.x).signum();
+    let away_z = (position.z - threat.z).signum();
+
+    for _ in 0..DESTINATION_ATTEMPTS {
+        let x = position.x + flee_offset(rng, away_x);
+        let z = position.z + flee_offset(rng, away_z);
+        let destination = Position::new(x, position.y, z);
+        if pathfinding::is_standable(area, materials, &destination.to_point()) {
+            return Some(destination);
+        }
+    }
+
+    None
+}
+
+/// Returns how far to run along an axis: away from the threat if it lies in
+/// the direction `away` along that axis, or either way if it lies level with
+/// the animal.
+fn flee_offset<R: Rng>(rng: &mut R, away: i32) -> i32 {
+    if away == 0 {
+        rng.gen_range(-FLEE_STEP, FLEE_STEP + 1)
+    } else {
+        away * rng.gen_range(FLEE_STEP / 2, FLEE_STEP + 1)
+    }
+}
+
+/// Picks a tile near `position` for an animal to wander to.
+fn wander_destination<R: Rng>(rng: &mut R, area: &Area, materials: &MaterialRegistry, position: &Position) -> Option<Position> {
+    for _ in 0..DESTINATION_ATTEMPTS {
+        let x = position.x + rng.gen_range(-WANDER_RADIUS, WANDER_RADIUS + 1);
+        let z = position.z + rng.gen_range(-WANDER_RADIUS, WANDER_RADIUS + 1);
+        let destination = Position::new(x, position.y, z);
+        if pathfinding::is_standable(area, materials, &destination.to_point()) {
+            return Some(destination);
+        }
+    }
+
+    None
+}
@@ -0,0 +1,35 @@
+/// The parts of the world which are not stored in its chunks, which are saved
+/// along with the game.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct WorldState {
+    /// The random number generator to be used by anything in the game which
+    /// needs randomness.
+    pub rng: GameRng,
+    /// The in-game date and time.
+    #[serde(default)]
+    pub calendar: Calendar,
+    #[serde(default)]
+    pub weather: Weather,
+    pub entities: Entities,
+    /// Jobs waiting to be assigned to a colonist, in the order in which they
+    /// were queued.
+    pub job_queue: Vec<Job>,
+    /// The tiles designated as stockpiles, to which loose items are hauled.
+    #[serde(default)]
+    pub stockpiles: BTreeSet<Position>,
+    /// The tick of the calendar at which the next raid arrives, or 0 if none
+    /// has been scheduled yet.
+    #[serde(default)]
+    pub next_raid: u64,
+    /// The names of the jobs defined by mods which have been queued, indexed
+    /// by `JobKind::Custom`.
+    #[serde(default)]
+    pub custom_jobs: Vec<String>,
+    /// The chunk column around which the world was first generated, along
+    /// the X and Z axes.
+    #[serde(default)]
+    pub embark: [i32; 2],
+    /// The metrics of the colony for every day since it was founded.
+    #[serde(default)]
+    pub stats: Stats,
+}
@@ -1,19 +1,639 @@
+use std::collections::{BTreeSet, HashSet};
+use std::io;
+use std::rc::Rc;
+
+use cgmath::{Point2, Point3};
 use rand;
 use rand::Rng;
+use utility::profiler;
 
+use CHUNK_SIZE;
 use area::Area;
+use calendar::Calendar;
+use combat;
+use components::{Blueprint, Colonist, Fighter, FurnitureKind, Health, ItemKind, Job, JobKind, Needs, Pathing, Position, Renderable, Structure};
+use direction::Direction;
+use entity::{Entities, Entity};
+use event::{EventBus, EventKind, Subscription, WorldEvent};
+use fluid;
+use fog;
+use history::History;
+use light;
+use material::MaterialRegistry;
+use navigation::NavGraph;
+use pathfinding;
+use pathfinding::SearchTrace;
+use region::RegionStore;
+use rng::GameRng;
+use stats;
+use stats::Stats;
+use systems;
+use weather;
+use weather::Weather;
+use wildlife;
+use world_gen_config::WorldGenConfig;
+
+#[cfg(feature = "nightly")]
+include!("world.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/world.rs"));
+
+// The names of the colonists are drawn with the rng of the world, so
+// changing them, or how many colonists there are, changes the colony every
+// seed starts with.
+const INITIAL_COLONISTS: usize = 3;
+const COLONIST_NAMES: &'static [&'static str] = &[
+    "Ada", "Bram", "Cora", "Dario", "Edda", "Fenn", "Greta", "Hugo",
+];
+const COLONIST_COLOR: [f32; 4] = [0.85, 0.15, 0.55, 1.0];
+const COLONIST_HEALTH: f32 = 100.0;
+const COLONIST_DAMAGE: f32 = 10.0;
 
 pub struct World {
     pub area: Area,
+    materials: Rc<MaterialRegistry>,
+    state: WorldState,
+    /// The zones through which routes are found, which are found again from
+    /// the chunks rather than saved.
+    navigation: NavGraph,
+    /// Delivers the events of the steps run to the subscribers of the world.
+    events: EventBus,
+    /// The recent snapshots of the world, to which it can be rewound.
+    history: History,
+    seed: u32,
+    config: WorldGenConfig,
 }
 
 impl World {
-    pub fn new(seed: Option<u32>, initial_size: u32) -> Self {
+    /// Generates a world from `seed`, first around the chunk column
+    /// `embark`, where the colonists are spawned.
+    pub fn new(seed: Option<u32>, initial_size: u32, embark: Point2<i32>, config: WorldGenConfig, materials: Rc<MaterialRegistry>) -> Self {
         // Use system RNG for seed if the user didn't provide one.
         let seed = seed.unwrap_or(rand::thread_rng().gen());
+        let area = Area::new(seed, initial_size, embark, config, &materials, None);
+
+        let mut world = World {
+            area: area,
+            materials: materials,
+            state: WorldState {
+                rng: GameRng::new(seed),
+                calendar: Calendar::new(),
+                weather: Weather::default(),
+                entities: Entities::new(),
+                job_queue: Vec::new(),
+                stockpiles: BTreeSet::new(),
+                next_raid: 0,
+                custom_jobs: Vec::new(),
+                embark: [embark.x, embark.y],
+                stats: Stats::new(0),
+            },
+            navigation: NavGraph::new(),
+            events: EventBus::new(),
+            history: History::new(),
+            seed: seed,
+            config: config,
+        };
+        world.spawn_colonists(initial_size);
+        world
+    }
+
+    /// Recreates a saved world from its seed and state, loading the chunks
+    /// which were modified before it was saved from `store`.
+    pub fn load(seed: u32, state: WorldState, initial_size: u32, config: WorldGenConfig, materials: Rc<MaterialRegistry>, store: RegionStore) -> Self {
+        let embark = Point2::new(state.embark[0], state.embark[1]);
+        let area = Area::new(seed, initial_size, embark, config, &materials, Some(store));
 
         World {
-            area: Area::new(seed, initial_size),
+            area: area,
+            materials: materials,
+            state: state,
+            navigation: NavGraph::new(),
+            events: EventBus::new(),
+            history: History::new(),
+            seed: seed,
+            config: config,
+        }
+    }
+
+    /// Spawns the initial colonists on the surface of the embark chunk
+    /// column.
+    fn spawn_colonists(&mut self, initial_size: u32) {
+        // Only the initially generated chunks are searched, from the top
+        // down.
+        let embark = self.embark();
+        let center = self.area.surface_chunk(embark);
+        let bottom = (center.y - initial_size as i32) * CHUNK_SIZE as i32;
+        let top = (center.y + initial_size as i32) * CHUNK_SIZE as i32 - 1;
+        let origin_x = embark.x * CHUNK_SIZE as i32;
+        let origin_z = embark.y * CHUNK_SIZE as i32;
+        let mut spawned = 0;
+
+        'search: for z in origin_z..origin_z + CHUNK_SIZE as i32 {
+            for x in origin_x..origin_x + CHUNK_SIZE as i32 {
+                let ground = (bottom..top).rev()
+                    .map(|y| Point3::new(x, y, z))
+                    .find(|pos| pathfinding::is_standable(&self.area, &self.materials, pos));
+                if let Some(pos) = ground {
+                    self.spawn_colonist(Position::from(pos));
+                    spawned += 1;
+                    if spawned == INITIAL_COLONISTS {
+                        break 'search;
+                    }
+                }
+            }
+        }
+    }
+
+    fn spawn_colonist(&mut self, position: Position) {
+        let name = *self.state.rng.choose(COLONIST_NAMES).expect("colonist names are defined");
+        let entities = &mut self.state.entities;
+        let entity = entities.spawn();
+
+        entities.positions.insert(entity, position);
+        entities.renderables.insert(entity, Renderable { color: COLONIST_COLOR });
+        entities.pathing.insert(entity, Pathing::default());
+        entities.needs.insert(entity, Needs::new());
+        entities.health.insert(entity, Health::new(COLONIST_HEALTH));
+        entities.fighters.insert(entity, Fighter::new(COLONIST_DAMAGE));
+        entities.colonists.insert(entity, Colonist {
+            name: name.to_owned(),
+            carrying: None,
+            drafted: false,
+        });
+    }
+
+    /// Saves the chunks modified since the last save to `store`. The seed,
+    /// state and generation parameters must be saved separately.
+    pub fn save(&mut self, store: RegionStore) -> io::Result<()> {
+        self.area.save(store)
+    }
+
+    /// Returns the registry of materials from which the world is made.
+    pub fn materials(&self) -> &MaterialRegistry {
+        &self.materials
+    }
+
+    /// Advances the simulation by one step.
+    pub fn tick(&mut self) {
+        let _scope = profiler::scope("world tick");
+        let mut events = Vec::new();
+        let season = self.state.calendar.season();
+        self.state.calendar.tick();
+        if self.state.calendar.season() != season {
+            events.push(WorldEvent::SeasonChanged(self.state.calendar.season()));
+        }
+        weather::tick(&mut self.state, &mut self.area, &self.materials);
+        fluid::tick(&mut self.area, &self.materials);
+        wildlife::tick(&mut self.state, &self.area, &self.materials, &mut events);
+        combat::tick(&mut self.state, &self.area, &self.materials, &mut events);
+        systems::satisfy_needs(&mut self.state);
+        systems::queue_hauling(&mut self.state);
+        systems::work(&mut self.state, &mut self.area, &self.materials, &mut events);
+        systems::walk(&mut self.state.entities, &self.area, &self.materials, &mut self.navigation);
+        self.navigation.forget_unloaded(&self.area);
+        fog::tick(&self.state, &mut self.area, &self.materials);
+        stats::tick(&mut self.state, &events);
+        self.publish_changed_tiles();
+        for event in events {
+            self.events.publish(event);
+        }
+    }
+
+    /// Publishes the tiles whose material has changed since this was last
+    /// called, including those set between steps, such as by commands.
+    fn publish_changed_tiles(&mut self) {
+        for (pos, before, after) in self.area.take_changed_tiles() {
+            self.events.publish(WorldEvent::VoxelChanged(Position::from(pos), before, after));
+        }
+    }
+
+    /// Recomputes the light of up to `budget` of the chunks whose light is
+    /// out of date.
+    pub fn update_light(&mut self, budget: usize) {
+        light::update(&mut self.area, &self.materials, budget);
+    }
+
+    /// Returns the random number generator to be used by anything in the game
+    /// which needs randomness, so that a game played from the same seed with
+    /// the same inputs always unfolds the same way.
+    pub fn rng(&mut self) -> &mut GameRng {
+        &mut self.state.rng
+    }
+
+    /// Returns the state of the world which must be saved along with its
+    /// chunks.
+    pub fn state(&self) -> &WorldState {
+        &self.state
+    }
+
+    /// Replaces the state of the world with `state`, as when it is kept in
+    /// step with a world simulated elsewhere.
+    pub fn set_state(&mut self, state: WorldState) {
+        self.state = state;
+        self.area.take_changed_tiles();
+        self.events.clear();
+    }
+
+    pub fn calendar(&self) -> &Calendar {
+        &self.state.calendar
+    }
+
+    pub fn weather(&self) -> &Weather {
+        &self.state.weather
+    }
+
+    /// Returns the metrics of the colony for every day since it was founded.
+    pub fn stats(&self) -> &Stats {
+        &self.state.stats
+    }
+
+    /// Returns the current temperature at the specified absolute position
+    /// along the X and Z axes, in degrees Celsius.
+    pub fn temperature_at(&self, x: i32, z: i32) -> f64 {
+        weather::temperature(self.area.biome_at(x, z), &self.state.calendar)
+    }
+
+    /// Moves the calendar forward by the specified number of steps of the
+    /// simulation, without simulating anything else.
+    pub fn skip_time(&mut self, ticks: u64) {
+        self.state.calendar.advance(ticks);
+    }
+
+    pub fn entities(&self) -> &Entities {
+        &self.state.entities
+    }
+
+    /// Returns the jobs waiting for an idle colonist to carry them out.
+    pub fn job_queue(&self) -> &[Job] {
+        &self.state.job_queue
+    }
+
+    /// Starts or stops recording the tiles explored while finding routes.
+    pub fn set_search_tracing(&mut self, enabled: bool) {
+        self.navigation.set_tracing(enabled);
+    }
+
+    /// Returns the tiles explored while finding routes since recording was
+    /// started, if it has been.
+    pub fn search_trace(&self) -> Option<&SearchTrace> {
+        self.navigation.trace()
+    }
+
+    /// Subscribes to the events of the specified kinds, which are then kept
+    /// until taken with `take_events`. A world which replaces this one must
+    /// be subscribed to again.
+    pub fn subscribe(&mut self, kinds: &[EventKind]) -> Subscription {
+        self.events.subscribe(kinds)
+    }
+
+    /// Returns the events for `subscription` since it last took them, so
+    /// that each subscriber sees every event of its kinds once, whichever
+    /// subscribers take theirs first.
+    pub fn take_events(&mut self, subscription: Subscription) -> Vec<WorldEvent> {
+        self.publish_changed_tiles();
+        self.events.take(subscription)
+    }
+
+    /// Queues a job to be carried out by the next idle colonist.
+    pub fn queue_job(&mut self, job: Job) {
+        self.state.job_queue.push(job);
+    }
+
+    /// Queues a job to dig out the tile at `pos`, returning false if the tile
+    /// is not solid or is already to be dug out.
+    pub fn designate_dig(&mut self, pos: Point3<i32>) -> bool {
+        self.queue_dig(JobKind::Dig, pos)
+    }
+
+    /// Queues a job to dig stairs out of the tile at `pos`, or out of the
+    /// ground beneath it if the tile is open, returning false if that tile is
+    /// not solid or is already to be dug out.
+    pub fn designate_dig_stairs(&mut self, pos: Point3<i32>) -> bool {
+        let pos = if self.materials.is_solid(self.area.get_tile(&pos).material) {
+            pos
+        } else {
+            pos + Direction::Down.to_vector()
+        };
+        self.queue_dig(JobKind::DigStairs, pos)
+    }
+
+    fn queue_dig(&mut self, kind: JobKind, pos: Point3<i32>) -> bool {
+        let target = Position::from(pos);
+        let is_designated = self.state.job_queue.iter()
+            .chain(self.state.entities.jobs.iter().map(|(_, job)| job))
+            .any(|job| (job.kind == JobKind::Dig || job.kind == JobKind::DigStairs) && job.target == target);
+        if is_designated || !self.materials.is_solid(self.area.get_tile(&pos).material) {
+            return false;
         }
+
+        self.queue_job(Job::new(kind, target));
+        true
+    }
+
+    /// Queues a job to hunt the animal standing at `pos`, returning false if
+    /// there is none or it is already being hunted.
+    pub fn designate_hunt(&mut self, pos: Point3<i32>) -> bool {
+        let target = Position::from(pos);
+        let animal = {
+            let entities = &self.state.entities;
+            let animal = entities.animals.iter()
+                .map(|(entity, _)| entity)
+                .find(|&entity| entities.positions.get(entity) == Some(&target));
+            let animal = match animal {
+                Some(animal) => animal,
+                None => return false,
+            };
+            let is_hunted = self.state.job_queue.iter()
+                .chain(entities.jobs.iter().map(|(_, job)| job))
+                .any(|job| job.kind == JobKind::Hunt(animal));
+            if is_hunted {
+                return false;
+            }
+            animal
+        };
+
+        self.queue_job(Job::new(JobKind::Hunt(animal), target));
+        true
+    }
+
+    /// Drafts the specified colonist, taking away its job, or releases it if
+    /// it is already drafted.
+    pub fn toggle_draft(&mut self, colonist: Entity) {
+        let entities = &mut self.state.entities;
+        let drafted = match entities.colonists.get_mut(colonist) {
+            Some(colonist) => {
+                colonist.drafted = !colonist.drafted;
+                colonist.drafted
+            },
+            None => return,
+        };
+
+        if drafted {
+            systems::interrupt_job(entities, &mut self.state.job_queue, colonist);
+        }
+    }
+
+    /// Orders a drafted colonist to move to `pos`, returning false if the
+    /// colonist is not drafted or cannot stand there.
+    pub fn order_move(&mut self, colonist: Entity, pos: Point3<i32>) -> bool {
+        let entities = &mut self.state.entities;
+        let is_drafted = entities.colonists.get(colonist).map_or(false, |colonist| colonist.drafted);
+        if !is_drafted || !pathfinding::is_standable(&self.area, &self.materials, &pos) {
+            return false;
+        }
+
+        // Fighting is put off until the colonist gets there.
+        if let Some(fighter) = entities.fighters.get_mut(colonist) {
+            fighter.target = None;
+        }
+        match entities.pathing.get_mut(colonist) {
+            Some(pathing) => {
+                pathing.walk_to(Position::from(pos), false);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Designates the tile at `pos` as part of a stockpile, returning false if
+    /// items cannot be put down on it.
+    pub fn add_stockpile(&mut self, pos: Point3<i32>) -> bool {
+        if !pathfinding::is_standable(&self.area, &self.materials, &pos) {
+            return false;
+        }
+
+        self.state.stockpiles.insert(Position::from(pos));
+        true
+    }
+
+    /// Queues a job defined by a mod, named `name`, to be worked on beside
+    /// `pos`.
+    pub fn queue_custom_job(&mut self, name: &str, pos: Point3<i32>) {
+        let index = match self.state.custom_jobs.iter().position(|job| job == name) {
+            Some(index) => index,
+            None => {
+                self.state.custom_jobs.push(name.to_owned());
+                self.state.custom_jobs.len() - 1
+            },
+        };
+        self.queue_job(Job::new(JobKind::Custom(index as u32), Position::from(pos)));
+    }
+
+    /// Returns the name of the job defined by a mod which `JobKind::Custom`
+    /// refers to by `index`.
+    pub fn custom_job_name(&self, index: u32) -> Option<&String> {
+        self.state.custom_jobs.get(index as usize)
+    }
+
+    /// Places a blueprint for `structure` at `pos` and queues a job to build
+    /// it, returning false if the structure cannot be built there or the tile
+    /// already holds a blueprint or furniture.
+    pub fn place_blueprint(&mut self, structure: Structure, pos: Point3<i32>) -> bool {
+        let target = Position::from(pos);
+        {
+            let entities = &self.state.entities;
+            let is_taken = entities.blueprints.iter().map(|(entity, _)| entity)
+                .chain(entities.furniture.iter().map(|(entity, _)| entity))
+                .any(|entity| entities.positions.get(entity) == Some(&target));
+            if is_taken || !systems::can_build(&self.area, &self.materials, structure, &pos) {
+                return false;
+            }
+        }
+
+        let entity = self.state.entities.spawn();
+        self.state.entities.positions.insert(entity, target);
+        self.state.entities.blueprints.insert(entity, Blueprint {
+            structure: structure,
+            material: None,
+        });
+        self.queue_job(Job::new(JobKind::Build(entity), target));
+        true
+    }
+
+    /// Returns the tiles designated as stockpiles.
+    pub fn stockpiles(&self) -> &BTreeSet<Position> {
+        &self.state.stockpiles
+    }
+
+    /// Returns the workshops at which a job is being worked on, along with
+    /// where they stand.
+    pub fn workshops_in_use(&self) -> Vec<(Entity, Position)> {
+        let entities = &self.state.entities;
+        let worked_at: HashSet<Position> = entities.jobs.iter()
+            .filter(|&(_, job)| job.progress > 0.0)
+            .map(|(_, job)| job.target)
+            .collect();
+
+        entities.furniture.iter()
+            .filter(|&(_, furniture)| furniture.kind == FurnitureKind::Workshop)
+            .filter_map(|(entity, _)| entities.positions.get(entity).map(|&position| (entity, position)))
+            .filter(|&(_, position)| worked_at.contains(&position))
+            .collect()
+    }
+
+    /// Spawns an item of the specified kind lying at `pos`.
+    pub fn spawn_item(&mut self, kind: ItemKind, pos: Point3<i32>) {
+        let entity = systems::spawn_item(&mut self.state.entities, kind, Position::from(pos));
+        self.events.publish(WorldEvent::Spawned(entity));
+    }
+
+    /// Keeps up to `capacity` snapshots of the world to rewind it to, or
+    /// none at all if it is zero.
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.history.set_capacity(capacity, &mut self.area);
+    }
+
+    /// Takes a snapshot of the world, to which it can later be rewound.
+    pub fn take_snapshot(&mut self) {
+        self.history.record(&self.state, &mut self.area);
+    }
+
+    /// Returns the number of snapshots to which the world can be rewound.
+    pub fn snapshot_count(&self) -> usize {
+        self.history.snapshot_count()
+    }
+
+    /// Rewinds the world to its latest snapshot, returning false if there is
+    /// none. Rewinding again goes back to the snapshot before it.
+    pub fn rewind(&mut self) -> bool {
+        match self.history.rewind(&mut self.area) {
+            Some(state) => {
+                self.state = state;
+                self.area.take_changed_tiles();
+                self.events.clear();
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Returns the seed from which the world was generated.
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Returns the chunk column around which the world was first generated.
+    pub fn embark(&self) -> Point2<i32> {
+        Point2::new(self.state.embark[0], self.state.embark[1])
+    }
+
+    /// Returns the parameters with which the world was generated.
+    pub fn config(&self) -> &WorldGenConfig {
+        &self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use cgmath::{Point2, Point3};
+
+    use components::{JobKind, Position};
+    use event::{EventKind, WorldEvent};
+    use material::MaterialRegistry;
+    use world_gen_config::WorldGenConfig;
+    use super::World;
+
+    const SEED: u32 = 1;
+    /// How many ticks a colonist gets to walk over and finish a dig.
+    const MAX_TICKS: usize = 5000;
+
+    /// Generates a small world around the origin, with the default
+    /// materials.
+    fn new_world() -> World {
+        World::new(Some(SEED), 2, Point2::new(0, 0), WorldGenConfig::default(), Rc::new(MaterialRegistry::default()))
+    }
+
+    fn is_solid(world: &World, pos: &Point3<i32>) -> bool {
+        world.materials().is_solid(world.area.get_tile(pos).material)
+    }
+
+    /// Returns the ground of a tile next to the first colonist, falling back
+    /// to the ground the colonist stands on.
+    fn ground_near_colonist(world: &World) -> Point3<i32> {
+        let entities = world.entities();
+        let (colonist, _) = entities.colonists.iter().next().expect("colonists are spawned");
+        let position = entities.positions.get(colonist).expect("colonists have a position").to_point();
+        [(1, 0), (-1, 0), (0, 1), (0, -1)].iter()
+            .map(|&(dx, dz)| Point3::new(position.x + dx, position.y - 1, position.z + dz))
+            .find(|pos| is_solid(world, pos) && !is_solid(world, &Point3::new(pos.x, pos.y + 1, pos.z)))
+            .unwrap_or(Point3::new(position.x, position.y - 1, position.z))
+    }
+
+    fn is_dig_pending(world: &World, target: Position) -> bool {
+        world.job_queue().iter()
+            .chain(world.entities().jobs.iter().map(|(_, job)| job))
+            .any(|job| job.kind == JobKind::Dig && job.target == target)
+    }
+
+    #[test]
+    fn colonists_finish_a_designated_dig() {
+        let mut world = new_world();
+        let pos = ground_near_colonist(&world);
+        let target = Position::from(pos);
+        assert!(is_solid(&world, &pos));
+
+        assert!(world.designate_dig(pos));
+        assert!(is_dig_pending(&world, target));
+        assert!(!world.designate_dig(pos), "a tile is only designated once");
+
+        let digs = world.subscribe(&[EventKind::Dug]);
+        let mut finished = false;
+        for _ in 0..MAX_TICKS {
+            world.tick();
+            finished = world.take_events(digs).iter().any(|event| *event == WorldEvent::Dug(target));
+            if finished {
+                break;
+            }
+        }
+
+        assert!(finished, "the dig was not finished within {} ticks", MAX_TICKS);
+        assert!(!is_solid(&world, &pos));
+        assert!(!is_dig_pending(&world, target));
+    }
+
+    #[test]
+    fn every_subscriber_takes_the_events_of_its_kinds() {
+        let mut world = new_world();
+        let pos = ground_near_colonist(&world);
+        let material = world.area.get_tile(&pos).material;
+        let digs = world.subscribe(&[EventKind::Dug]);
+        let voxels = world.subscribe(&[EventKind::VoxelChanged]);
+        let both = world.subscribe(&[EventKind::Dug, EventKind::VoxelChanged]);
+
+        assert!(world.designate_dig(pos));
+        let mut dug = Vec::new();
+        for _ in 0..MAX_TICKS {
+            world.tick();
+            dug = world.take_events(digs);
+            if !dug.is_empty() {
+                break;
+            }
+        }
+
+        let target = Position::from(pos);
+        assert_eq!(dug, vec![WorldEvent::Dug(target)]);
+        let changes = world.take_events(voxels);
+        assert!(changes.iter().all(|event| event.kind() == EventKind::VoxelChanged));
+        assert!(changes.iter().any(|event| match *event {
+            WorldEvent::VoxelChanged(pos, before, _) => pos == target && before == material,
+            _ => false,
+        }));
+        let taken = world.take_events(both);
+        assert_eq!(taken.len(), dug.len() + changes.len(), "taking events never hides them from another subscriber");
+        assert!(world.take_events(both).is_empty(), "events are only taken once");
+    }
+
+    #[test]
+    fn open_tiles_cannot_be_designated() {
+        let mut world = new_world();
+        let ground = ground_near_colonist(&world);
+        let above = Point3::new(ground.x, ground.y + 1, ground.z);
+        assert!(!is_solid(&world, &above));
+
+        assert!(!world.designate_dig(above));
+        assert!(world.job_queue().is_empty());
     }
 }
@@ -0,0 +1,21 @@
+/// Parameters controlling the shape of the generated terrain.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct WorldGenConfig {
+    /// Number of layers of noise summed to produce the height map
+    pub octaves: u32,
+    /// Frequency of the first octave, in cycles per tile
+    pub frequency: f64,
+    /// Multiplier applied to the frequency of each successive octave
+    pub lacunarity: f64,
+    /// Multiplier applied to the amplitude of each successive octave
+    pub persistence: f64,
+    /// Multiplier applied to the height variation of every biome
+    pub amplitude: f64,
+    /// Elevation below which empty tiles are filled with water
+    pub sea_level: i32,
+    /// Carve caves out of the stone beneath the surface
+    pub caves: bool,
+    /// Radius of the cave tunnels, in noise units. Larger values produce wider
+    /// and more frequent caves
+    pub cave_density: f64,
+}
@@ -0,0 +1,20 @@
+#[cfg(feature = "nightly")]
+include!("world_gen_config.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/world_gen_config.rs"));
+
+impl Default for WorldGenConfig {
+    fn default() -> Self {
+        WorldGenConfig {
+            octaves: 4,
+            frequency: 1.0 / 64.0,
+            lacunarity: 2.0,
+            persistence: 0.5,
+            amplitude: 1.0,
+            sea_level: 14,
+            caves: true,
+            cave_density: 0.1,
+        }
+    }
+}